@@ -1,5 +1,5 @@
 use criterion::{Criterion, black_box, criterion_group, criterion_main};
-use mquickjs::Context;
+use mquickjs::embed::{Context, Value};
 
 fn bench_fib(c: &mut Criterion) {
     // Use iterative fibonacci to avoid stack overflow
@@ -64,6 +64,34 @@ fn bench_array_push(c: &mut Criterion) {
     });
 }
 
+fn bench_array_unshift(c: &mut Criterion) {
+    // 10k elements in 40 calls of 250 arguments onto a 100k array
+    let values = (0..250)
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let code = format!(
+        r#"
+        var arr = [];
+        for (var i = 0; i < 100000; i = i + 1) {{
+            arr.push(i);
+        }}
+        for (var j = 0; j < 40; j = j + 1) {{
+            arr.unshift({});
+        }}
+        return arr.length;
+    "#,
+        values
+    );
+
+    c.bench_function("array unshift 10k onto 100k", |b| {
+        b.iter(|| {
+            let mut ctx = Context::new(4 * 1024 * 1024);
+            black_box(ctx.eval(&code).unwrap())
+        })
+    });
+}
+
 fn bench_object_create(c: &mut Criterion) {
     let code = r#"
         function Point(x, y) {
@@ -85,6 +113,28 @@ fn bench_object_create(c: &mut Criterion) {
     });
 }
 
+fn bench_small_objects(c: &mut Criterion) {
+    let code = r#"
+        var objects = [];
+        for (var i = 0; i < 10000; i = i + 1) {
+            objects.push({ id: i, x: i * 2, y: i * 3, label: "p" });
+        }
+        var sum = 0;
+        for (var j = 0; j < objects.length; j = j + 1) {
+            var o = objects[j];
+            sum = sum + o.id + o.x + o.y;
+        }
+        return sum;
+    "#;
+
+    c.bench_function("small objects 10k", |b| {
+        b.iter(|| {
+            let mut ctx = Context::new(4 * 1024 * 1024);
+            black_box(ctx.eval(code).unwrap())
+        })
+    });
+}
+
 fn bench_closure(c: &mut Criterion) {
     let code = r#"
         function makeAdder(x) {
@@ -126,6 +176,43 @@ fn bench_string_concat(c: &mut Criterion) {
     });
 }
 
+fn bench_number_concat(c: &mut Criterion) {
+    // Each step appends an integer and a separator, so the number must be
+    // formatted and the result built without temporary strings
+    let code = r#"
+        var s = "";
+        for (var i = 0; i < 1000; i = i + 1) {
+            s = s + i + ",";
+        }
+        return s.length;
+    "#;
+
+    c.bench_function("number concat 1k", |b| {
+        b.iter(|| {
+            let mut ctx = Context::new(4 * 1024 * 1024);
+            black_box(ctx.eval(code).unwrap())
+        })
+    });
+}
+
+fn bench_split_chars(c: &mut Criterion) {
+    let code = r#"
+        var count = 0;
+        for (var i = 0; i < 1000; i = i + 1) {
+            var chars = "benchmark".split("");
+            count = count + chars.length;
+        }
+        return count;
+    "#;
+
+    c.bench_function("split chars 1k", |b| {
+        b.iter(|| {
+            let mut ctx = Context::new(64 * 1024);
+            black_box(ctx.eval(code).unwrap())
+        })
+    });
+}
+
 fn bench_json_parse(c: &mut Criterion) {
     let code = r#"
         var data = '{"name": "test", "value": 42, "items": [1, 2, 3]}';
@@ -200,17 +287,100 @@ fn bench_recursion(c: &mut Criterion) {
     });
 }
 
+/// Formula evaluated once per data point, as a spreadsheet-style host does
+const FORMULA: &str = r#"
+    var base = x * 3 + 7;
+    var scaled = base * 2;
+    var offset = scaled - x;
+    var clipped = offset;
+    if (clipped > 1000) {
+        clipped = 1000;
+    }
+    if (clipped < 0) {
+        clipped = 0;
+    }
+    var bucket = clipped % 10;
+    var parity = bucket % 2;
+    var weight = parity == 0 ? 2 : 3;
+    var total = clipped * weight;
+    var bonus = x > 50 ? 5 : 1;
+    total = total + bonus;
+    var label = "point";
+    var adjusted = total - bucket;
+    adjusted;
+"#;
+
+fn bench_else_if_dispatch(c: &mut Criterion) {
+    // A 40-way else-if chain on one variable, as generated protocol
+    // handlers write it, hit on every branch in turn
+    let branches = (0..40)
+        .map(|op| format!("if (op === {}) {{ total = total + {}; }}", op, op + 1))
+        .collect::<Vec<_>>()
+        .join(" else ");
+    let code = format!(
+        r#"
+        function handle(op, total) {{
+            {} else {{ total = total - 1; }}
+            return total;
+        }}
+        var total = 0;
+        for (var i = 0; i < 10000; i = i + 1) {{
+            total = handle(i % 40, total);
+        }}
+        return total;
+    "#,
+        branches
+    );
+
+    c.bench_function("else-if dispatch 40 branches 10k", |b| {
+        b.iter(|| {
+            let mut ctx = Context::new(64 * 1024);
+            black_box(ctx.eval(&code).unwrap())
+        })
+    });
+}
+
+fn bench_formula_evals(c: &mut Criterion) {
+    let mut group = c.benchmark_group("formula 10k evals");
+    for cache_size in [0, 16] {
+        let name = if cache_size == 0 {
+            "uncached"
+        } else {
+            "cached"
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut ctx = Context::new(256 * 1024);
+                ctx.set_compile_cache_size(cache_size);
+                let mut sum = 0;
+                for x in 0..10_000 {
+                    ctx.set_global("x", Value::int(x % 100));
+                    sum += ctx.eval(FORMULA).unwrap().to_i32().unwrap();
+                }
+                black_box(sum)
+            })
+        });
+    }
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_fib,
     bench_loop,
     bench_array_push,
+    bench_array_unshift,
     bench_object_create,
+    bench_small_objects,
     bench_closure,
     bench_string_concat,
+    bench_number_concat,
+    bench_split_chars,
     bench_json_parse,
     bench_sieve,
     bench_recursion,
+    bench_else_if_dispatch,
+    bench_formula_evals,
 );
 
 criterion_main!(benches);
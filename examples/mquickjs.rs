@@ -0,0 +1,160 @@
+//! Minimal script runner and REPL built on the public mquickjs API
+//!
+//! Usage:
+//!   cargo run --example mquickjs -- script.js   Run a script file
+//!   cargo run --example mquickjs                Start a REPL reading stdin
+//!
+//! Every REPL input is evaluated in the same Context, so top-level
+//! declarations persist between lines. Special commands:
+//!   .stats   Print interpreter object-table statistics
+//!   .exit    Leave the REPL
+
+use std::io::{self, BufRead, Write};
+
+use mquickjs::embed::{Context, EvalError, Value};
+
+/// Memory given to the engine
+const MEMORY_SIZE: usize = 1024 * 1024;
+
+fn main() {
+    let mut ctx = Context::new(MEMORY_SIZE);
+
+    match std::env::args().nth(1) {
+        Some(path) => {
+            let source = match std::fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(e) => {
+                    eprintln!("Error reading {}: {}", path, e);
+                    std::process::exit(1);
+                }
+            };
+            if let Err(e) = ctx.eval(&source) {
+                eprintln!("{}", format_error(&e));
+                std::process::exit(1);
+            }
+        }
+        None => {
+            let stdin = io::stdin();
+            let stdout = io::stdout();
+            if let Err(e) = repl(&mut ctx, stdin.lock(), stdout.lock()) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Run the read-eval-print loop until end of input or `.exit`
+fn repl<R: BufRead, W: Write>(ctx: &mut Context, input: R, mut output: W) -> io::Result<()> {
+    write!(output, "> ")?;
+    output.flush()?;
+
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        match line {
+            "" => {}
+            ".exit" => break,
+            ".stats" => print_stats(ctx, &mut output)?,
+            _ => match eval_line(ctx, line) {
+                Ok(value) => writeln!(output, "{}", ctx.inspect(value))?,
+                Err(e) => writeln!(output, "{}", format_error(&e))?,
+            },
+        }
+
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+
+    writeln!(output)?;
+    Ok(())
+}
+
+/// Evaluate one line of input
+///
+/// The line is first tried as an expression so its value can be printed;
+/// if that does not compile it is evaluated as a statement list instead.
+/// Only compile errors trigger the fallback, so nothing runs twice.
+fn eval_line(ctx: &mut Context, line: &str) -> Result<Value, EvalError> {
+    match ctx.eval_expression(line) {
+        Err(EvalError::CompileError(_)) => {
+            if line.ends_with(';') || line.ends_with('}') {
+                ctx.eval(line)
+            } else {
+                ctx.eval(&format!("{};", line))
+            }
+        }
+        result => result,
+    }
+}
+
+/// Format an evaluation error for display
+fn format_error(e: &EvalError) -> String {
+    match e {
+        EvalError::CompileError(e) => format!("SyntaxError: {}", e),
+        EvalError::RuntimeError(msg) => format!("Uncaught {}", msg),
+    }
+}
+
+/// Print interpreter statistics
+fn print_stats<W: Write>(ctx: &Context, output: &mut W) -> io::Result<()> {
+    let stats = ctx.interpreter_stats();
+    writeln!(output, "Runtime strings: {}", stats.runtime_strings)?;
+    writeln!(output, "Arrays:          {}", stats.arrays)?;
+    writeln!(output, "Objects:         {}", stats.objects)?;
+    writeln!(output, "Closures:        {}", stats.closures)?;
+    writeln!(output, "Error objects:   {}", stats.error_objects)?;
+    writeln!(output, "RegExp objects:  {}", stats.regex_objects)?;
+    writeln!(output, "TypedArrays:     {}", stats.typed_arrays)?;
+    writeln!(output, "ArrayBuffers:    {}", stats.array_buffers)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drive the REPL with a scripted session and return everything it printed
+    fn run_session(input: &str) -> String {
+        let mut ctx = Context::new(MEMORY_SIZE);
+        let mut output = Vec::new();
+        repl(&mut ctx, input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_repl_prints_expression_results() {
+        let out = run_session("1 + 2\n\"a\" + \"b\"\n[1, 2, 3]\n");
+        assert!(out.contains("> 3\n"));
+        assert!(out.contains("> ab\n"));
+        assert!(out.contains("> [1, 2, 3]\n"));
+    }
+
+    #[test]
+    fn test_repl_variables_persist() {
+        let out = run_session(
+            "var x = 40;\nfunction add(a, b) { return a + b; }\nadd(x, 2)\nvar s = \"hi\";\ns + \"!\"\n",
+        );
+        assert!(out.contains("> 42\n"));
+        assert!(out.contains("> hi!\n"));
+    }
+
+    #[test]
+    fn test_repl_reports_errors_and_continues() {
+        let out = run_session("1 +\nundefinedName\nthrow 7;\n5\n");
+        assert!(out.contains("SyntaxError"));
+        assert!(out.contains("undefinedName is not defined"));
+        assert!(out.contains("Uncaught"));
+        assert!(out.contains("> 5\n"));
+    }
+
+    #[test]
+    fn test_repl_stats_command() {
+        let out = run_session("var a = [1, 2];\n.stats\n.exit\n42\n");
+        assert!(out.contains("Arrays:"));
+        assert!(out.contains("Closures:"));
+        // Input after .exit is not evaluated
+        assert!(!out.contains("42"));
+    }
+}
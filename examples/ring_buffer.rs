@@ -0,0 +1,196 @@
+//! A host class scripts construct with `new`: a fixed-size ring buffer
+//!
+//! Usage:
+//!   cargo run --example ring_buffer
+//!
+//! `new RingBuffer(capacity)` creates an empty buffer of numbers. `push`
+//! overwrites the oldest entry once the buffer is full, `pop` removes the
+//! oldest entry, `size` counts entries and `forEach(callback)` calls back
+//! into the script for each entry, oldest first.
+
+use std::collections::VecDeque;
+
+use mquickjs::embed::{ClassDef, Context, EvalError, HostError, NativeScope, Value};
+
+/// Memory given to the engine
+const MEMORY_SIZE: usize = 64 * 1024;
+
+/// Largest capacity scripts may request
+const MAX_CAPACITY: i32 = 1 << 16;
+
+/// Numbers kept in insertion order, dropping the oldest when full
+struct RingBuffer {
+    items: VecDeque<i32>,
+    capacity: usize,
+}
+
+impl RingBuffer {
+    fn new(capacity: usize) -> Self {
+        RingBuffer {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Append a value, returning the one it evicted
+    fn push(&mut self, value: i32) -> Option<i32> {
+        let evicted = if self.items.len() == self.capacity {
+            self.items.pop_front()
+        } else {
+            None
+        };
+        self.items.push_back(value);
+        evicted
+    }
+}
+
+/// Describe `RingBuffer` to the engine
+fn ring_buffer_class() -> ClassDef<RingBuffer> {
+    ClassDef::new(|_, args| match args.first().and_then(|v| v.to_i32()) {
+        Some(n) if (1..=MAX_CAPACITY).contains(&n) => Ok(RingBuffer::new(n as usize)),
+        _ => Err(HostError::Range(format!(
+            "RingBuffer capacity must be between 1 and {}",
+            MAX_CAPACITY
+        ))),
+    })
+    .method("push", |_, ring, args| {
+        let value = args
+            .first()
+            .and_then(|v| v.to_i32())
+            .ok_or("RingBuffer.push expects a number")?;
+        Ok(ring.push(value).map_or_else(Value::undefined, Value::int))
+    })
+    .method("pop", |_, ring, _| {
+        Ok(ring
+            .items
+            .pop_front()
+            .map_or_else(Value::undefined, Value::int))
+    })
+    .method("size", |_, ring, _| Ok(Value::int(ring.items.len() as i32)))
+    .method("forEach", |scope: &mut NativeScope, ring, args| {
+        let callback = args.first().copied().unwrap_or_default();
+        for &item in &ring.items {
+            scope.call(callback, Value::undefined(), &[Value::int(item)])?;
+        }
+        Ok(Value::undefined())
+    })
+    .getter("capacity", |_, ring| Value::int(ring.capacity as i32))
+}
+
+fn main() -> Result<(), EvalError> {
+    let mut ctx = Context::new(MEMORY_SIZE);
+    ctx.register_class("RingBuffer", ring_buffer_class());
+    ctx.eval(
+        "var ring = new RingBuffer(3);
+         ring.push(1); ring.push(2); ring.push(3); ring.push(4);
+         console.log(\"size \" + ring.size() + \" of \" + ring.capacity);
+         function show(n) { console.log(n); }
+         ring.forEach(show);",
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn context() -> Context {
+        let mut ctx = Context::new(MEMORY_SIZE);
+        ctx.register_class("RingBuffer", ring_buffer_class());
+        ctx
+    }
+
+    #[test]
+    fn test_ring_buffer_push_pop_size() {
+        let mut ctx = context();
+        let result = ctx
+            .eval(
+                "var ring = new RingBuffer(2);
+                 var evicted = [ring.push(1), ring.push(2), ring.push(3)];
+                 [evicted[2], ring.size(), ring.pop(), ring.pop(), ring.pop(), ring.size()];",
+            )
+            .unwrap();
+        let items = ctx.get_array(result).unwrap();
+        assert_eq!(items[0].to_i32(), Some(1));
+        assert_eq!(items[1].to_i32(), Some(2));
+        assert_eq!(items[2].to_i32(), Some(2));
+        assert_eq!(items[3].to_i32(), Some(3));
+        assert!(items[4].is_undefined());
+        assert_eq!(items[5].to_i32(), Some(0));
+    }
+
+    #[test]
+    fn test_ring_buffer_is_a_class() {
+        let mut ctx = context();
+        let result = ctx
+            .eval(
+                "var ring = new RingBuffer(4);
+                 [ring instanceof RingBuffer, typeof RingBuffer, ring.capacity, typeof ring.push];",
+            )
+            .unwrap();
+        let items = ctx.get_array(result).unwrap();
+        assert_eq!(items[0].to_bool(), Some(true));
+        assert_eq!(ctx.get_string(items[1]).as_deref(), Some("function"));
+        assert_eq!(items[2].to_i32(), Some(4));
+        assert_eq!(ctx.get_string(items[3]).as_deref(), Some("function"));
+    }
+
+    #[test]
+    fn test_ring_buffer_methods_reenter_the_interpreter() {
+        let mut ctx = context();
+        let result = ctx
+            .eval(
+                "var ring = new RingBuffer(3);
+                 ring.push(5); ring.push(6); ring.push(7);
+                 var total = [0];
+                 function add(n) { total[0] = total[0] + n; }
+                 ring.forEach(add);
+                 total[0];",
+            )
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(18));
+
+        // The instance is lent to forEach, so touching it from the callback throws
+        let err = ctx
+            .eval("function peek(n) { ring.size(); } ring.forEach(peek);")
+            .unwrap_err();
+        assert!(format!("{}", err).contains("already in use"));
+        assert_eq!(ctx.eval("ring.size();").unwrap().to_i32(), Some(3));
+    }
+
+    #[test]
+    fn test_ring_buffer_errors() {
+        let mut ctx = context();
+        let err = ctx.eval("new RingBuffer(0);").unwrap_err();
+        assert!(format!("{}", err).contains("RangeError"));
+
+        let err = ctx.eval("RingBuffer(2);").unwrap_err();
+        assert!(format!("{}", err).contains("without 'new'"));
+
+        let result = ctx
+            .eval(
+                "var ring = new RingBuffer(2);
+                 var caught = \"\";
+                 try { ring.push(\"x\"); } catch (e) { caught = e.name; }
+                 caught;",
+            )
+            .unwrap();
+        assert_eq!(ctx.get_string(result).as_deref(), Some("TypeError"));
+    }
+
+    #[test]
+    fn test_ring_buffer_finalizer() {
+        let finalized = Rc::new(Cell::new(0));
+        let seen = finalized.clone();
+        let mut ctx = Context::new(MEMORY_SIZE);
+        ctx.register_class(
+            "RingBuffer",
+            ring_buffer_class().finalizer(move |ring| seen.set(ring.capacity)),
+        );
+        ctx.eval("var ring = new RingBuffer(9); ring = 0;").unwrap();
+        ctx.gc();
+        assert_eq!(finalized.get(), 9);
+    }
+}
@@ -11,7 +11,7 @@
 //!   -c, --compile      Compile to bytecode (output to .qbc file)
 //!   --memory-limit N   Limit memory usage to N bytes (supports k/K, m/M suffixes)
 
-use mquickjs::Context;
+use mquickjs::embed::Context;
 use rustyline::DefaultEditor;
 use rustyline::error::ReadlineError;
 
@@ -322,7 +322,7 @@ fn dump_memory_stats(ctx: &Context) {
 /// Bytecode file magic bytes
 const BYTECODE_MAGIC: &[u8] = b"MQJS";
 /// Bytecode file version
-const BYTECODE_VERSION: u8 = 1;
+const BYTECODE_VERSION: u8 = 2;
 
 /// Compile a JavaScript file to bytecode and save to .qbc file
 fn compile_to_bytecode(script_path: &str) -> Result<(), String> {
@@ -332,15 +332,18 @@ fn compile_to_bytecode(script_path: &str) -> Result<(), String> {
 
     // Compile
     let ctx = Context::new(1024 * 1024);
-    let bytecode = ctx
+    let (script, diagnostics) = ctx
         .compile(&source)
         .map_err(|e| format!("Compile error: {}", e))?;
+    for diagnostic in &diagnostics {
+        eprintln!("{}:{}", script_path, diagnostic);
+    }
 
     // Serialize
     let mut output = Vec::new();
     output.extend_from_slice(BYTECODE_MAGIC);
     output.push(BYTECODE_VERSION);
-    let serialized = bytecode.serialize();
+    let serialized = script.serialize();
     output.extend_from_slice(&serialized);
 
     // Write to .qbc file
@@ -364,7 +367,7 @@ fn compile_to_bytecode(script_path: &str) -> Result<(), String> {
 
 /// Load and execute a bytecode file
 fn run_bytecode_file(ctx: &mut Context, filename: &str) -> Result<(), String> {
-    use mquickjs::FunctionBytecode;
+    use mquickjs::embed::Script;
 
     // Read bytecode file
     let data = std::fs::read(filename).map_err(|e| format!("Error reading {}: {}", filename, e))?;
@@ -384,11 +387,11 @@ fn run_bytecode_file(ctx: &mut Context, filename: &str) -> Result<(), String> {
     }
 
     // Deserialize
-    let (bytecode, _) = FunctionBytecode::deserialize(&data[5..])
-        .map_err(|e| format!("Error loading bytecode: {}", e))?;
+    let script =
+        Script::deserialize(&data[5..]).map_err(|e| format!("Error loading bytecode: {}", e))?;
 
     // Execute
-    match ctx.execute(&bytecode) {
+    match ctx.execute(script) {
         Ok(result) => {
             if !result.is_undefined() {
                 println!("{}", result);
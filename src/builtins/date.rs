@@ -1 +1,402 @@
 //! JavaScript date built-in
+//!
+//! Time values are milliseconds since the Unix epoch (UTC) held in an i64.
+//! There is no time zone database, so local time is always UTC and every
+//! formatter produces the same output on every host.
+//!
+//! Scripts see time values as whole seconds since the epoch, rounded down,
+//! so that current dates fit an integer value until numbers have floats:
+//! `Date.now`, `Date.parse`, `getTime`, `valueOf`, Date subtraction and the
+//! numeric `new Date(seconds)` all use the same unit. The range runs to
+//! 2038-01-19 on 64-bit targets (2004-01-10 on 32-bit ones, where integers
+//! have 31 bits); a time beyond it raises a RangeError when read as a
+//! number. Dates themselves keep millisecond precision and the full range,
+//! which `getMilliseconds` and the string forms show.
+
+use crate::prelude::*;
+
+/// Milliseconds per day
+pub const MS_PER_DAY: i64 = 86_400_000;
+
+/// Milliseconds per script-visible time unit (one second)
+pub const MS_PER_SECOND: i64 = 1000;
+
+/// Largest magnitude of a valid time value (100,000,000 days around the epoch)
+pub const MAX_TIME: i64 = 8_640_000_000_000_000;
+
+const DAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Broken-down UTC date and time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateFields {
+    /// Full year (may be negative or above 9999)
+    pub year: i64,
+    /// Month, 0-based as in JS
+    pub month: u32,
+    /// Day of the month, 1-based
+    pub day: u32,
+    /// Day of the week, 0 = Sunday
+    pub weekday: u32,
+    /// Hours (0-23)
+    pub hours: u32,
+    /// Minutes (0-59)
+    pub minutes: u32,
+    /// Seconds (0-59)
+    pub seconds: u32,
+    /// Milliseconds (0-999)
+    pub millis: u32,
+}
+
+/// Days since the epoch for a proleptic Gregorian date (month 1-12)
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Gregorian (year, month 1-12, day) for a day count since the epoch
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Number of days in a month (month 1-12)
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Script-visible seconds for a time value, rounded down
+pub fn to_epoch_seconds(time: i64) -> i64 {
+    time.div_euclid(MS_PER_SECOND)
+}
+
+/// Time value for script-visible seconds, or None outside the valid range
+pub fn from_epoch_seconds(seconds: i64) -> Option<i64> {
+    seconds
+        .checked_mul(MS_PER_SECOND)
+        .filter(|time| time.abs() <= MAX_TIME)
+}
+
+/// Split a time value into UTC date and time fields
+pub fn fields(time: i64) -> DateFields {
+    let days = time.div_euclid(MS_PER_DAY);
+    let ms_in_day = time.rem_euclid(MS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    DateFields {
+        year,
+        month: month - 1,
+        day,
+        weekday: (days + 4).rem_euclid(7) as u32,
+        hours: (ms_in_day / 3_600_000) as u32,
+        minutes: (ms_in_day / 60_000 % 60) as u32,
+        seconds: (ms_in_day / 1000 % 60) as u32,
+        millis: (ms_in_day % 1000) as u32,
+    }
+}
+
+/// Format a time value as an ISO-8601 string (Date.prototype.toISOString)
+///
+/// Years outside 0..=9999 use the six-digit signed extended form.
+pub fn to_iso_string(time: i64) -> String {
+    let f = fields(time);
+    let year = if (0..=9999).contains(&f.year) {
+        format!("{:04}", f.year)
+    } else if f.year < 0 {
+        format!("-{:06}", -f.year)
+    } else {
+        format!("+{:06}", f.year)
+    };
+    format!(
+        "{}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        f.month + 1,
+        f.day,
+        f.hours,
+        f.minutes,
+        f.seconds,
+        f.millis
+    )
+}
+
+/// Format a time value for Date.prototype.toString
+///
+/// The format is "Sat Jun 01 2024 12:30:00 GMT+0000", always in UTC.
+pub fn to_date_string(time: i64) -> String {
+    let f = fields(time);
+    let year = if f.year < 0 {
+        format!("-{:06}", -f.year)
+    } else {
+        format!("{:04}", f.year)
+    };
+    format!(
+        "{} {} {:02} {} {:02}:{:02}:{:02} GMT+0000",
+        DAY_NAMES[f.weekday as usize],
+        MONTH_NAMES[f.month as usize],
+        f.day,
+        year,
+        f.hours,
+        f.minutes,
+        f.seconds
+    )
+}
+
+/// Cursor over the bytes of a date string
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Read exactly `n` ASCII digits
+    fn digits(&mut self, n: usize) -> Option<u32> {
+        let end = self.pos + n;
+        let chunk = self.bytes.get(self.pos..end)?;
+        if !chunk.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.pos = end;
+        Some(chunk.iter().fold(0, |acc, b| acc * 10 + (b - b'0') as u32))
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+}
+
+/// Parse the ISO-8601 subset accepted by Date.parse
+///
+/// Supported forms:
+/// - `YYYY`, `YYYY-MM`, `YYYY-MM-DD` (and `±YYYYYY` extended years)
+/// - any date form followed by `THH:MM`, `THH:MM:SS` or `THH:MM:SS.sss`
+/// - an optional `Z` or `±HH:MM` offset after the time
+///
+/// Forms without an offset are UTC. Returns None for anything else.
+pub fn parse_iso8601(s: &str) -> Option<i64> {
+    let mut sc = Scanner {
+        bytes: s.trim().as_bytes(),
+        pos: 0,
+    };
+
+    let year = match sc.peek()? {
+        b'+' | b'-' => {
+            let negative = sc.peek() == Some(b'-');
+            sc.pos += 1;
+            let y = sc.digits(6)? as i64;
+            // -000000 is not a valid year
+            if negative && y == 0 {
+                return None;
+            }
+            if negative { -y } else { y }
+        }
+        _ => sc.digits(4)? as i64,
+    };
+
+    let mut month = 1;
+    let mut day = 1;
+    if sc.eat(b'-') {
+        month = sc.digits(2)?;
+        if sc.eat(b'-') {
+            day = sc.digits(2)?;
+        }
+    }
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let (mut hours, mut minutes, mut seconds, mut millis) = (0, 0, 0, 0);
+    let mut offset_minutes: i64 = 0;
+    if sc.eat(b'T') {
+        hours = sc.digits(2)?;
+        if !sc.eat(b':') {
+            return None;
+        }
+        minutes = sc.digits(2)?;
+        if sc.eat(b':') {
+            seconds = sc.digits(2)?;
+            if sc.eat(b'.') {
+                // Keep millisecond precision, ignore extra fraction digits
+                let start = sc.pos;
+                while sc.peek().is_some_and(|b| b.is_ascii_digit()) {
+                    sc.pos += 1;
+                }
+                let frac = &sc.bytes[start..sc.pos];
+                if frac.is_empty() {
+                    return None;
+                }
+                millis = frac
+                    .iter()
+                    .chain(b"00".iter())
+                    .take(3)
+                    .fold(0, |acc, b| acc * 10 + (b - b'0') as u32);
+            }
+        }
+        if hours > 24 || minutes > 59 || seconds > 59 {
+            return None;
+        }
+        // 24:00 is allowed only as the end of a day
+        if hours == 24 && (minutes, seconds, millis) != (0, 0, 0) {
+            return None;
+        }
+
+        if !sc.eat(b'Z') {
+            let sign = match sc.peek() {
+                Some(b'+') => 1,
+                Some(b'-') => -1,
+                _ => 0,
+            };
+            if sign != 0 {
+                sc.pos += 1;
+                let off_h = sc.digits(2)?;
+                if !sc.eat(b':') {
+                    return None;
+                }
+                let off_m = sc.digits(2)?;
+                if off_h > 23 || off_m > 59 {
+                    return None;
+                }
+                offset_minutes = sign * (off_h as i64 * 60 + off_m as i64);
+            }
+        }
+    }
+
+    if !sc.at_end() {
+        return None;
+    }
+
+    let time = days_from_civil(year, month, day) * MS_PER_DAY
+        + hours as i64 * 3_600_000
+        + minutes as i64 * 60_000
+        + seconds as i64 * 1000
+        + millis as i64
+        - offset_minutes * 60_000;
+    (time.abs() <= MAX_TIME).then_some(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        for days in [-719_468, -1, 0, 59, 11_016, 19_875, 2_932_896] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+
+    #[test]
+    fn test_parse_forms() {
+        assert_eq!(parse_iso8601("1970-01-01"), Some(0));
+        assert_eq!(parse_iso8601("1970"), Some(0));
+        assert_eq!(parse_iso8601("1970-02"), Some(31 * MS_PER_DAY));
+        assert_eq!(
+            parse_iso8601("2024-06-01T12:30:00Z"),
+            Some(1_717_245_000_000)
+        );
+        assert_eq!(parse_iso8601("2024-06-01T12:30Z"), Some(1_717_245_000_000));
+        assert_eq!(
+            parse_iso8601("2024-06-01T12:30:00.123Z"),
+            Some(1_717_245_000_123)
+        );
+        assert_eq!(
+            parse_iso8601("2024-06-01T14:30:00+02:00"),
+            Some(1_717_245_000_000)
+        );
+        assert_eq!(
+            parse_iso8601("2024-06-01T07:00:00-05:30"),
+            Some(1_717_245_000_000)
+        );
+        assert_eq!(parse_iso8601("-000001-01-01"), Some(-62_198_755_200_000));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        for s in [
+            "",
+            "2024-13-01",
+            "2023-02-29",
+            "2024-06-01T25:00Z",
+            "2024-06-01T12:30:00+0200",
+            "2024-06-01 12:30",
+            "2024-06-01T12:30:00Zjunk",
+            "-000000-01-01",
+            "June 1, 2024",
+        ] {
+            assert_eq!(parse_iso8601(s), None, "{:?}", s);
+        }
+        assert_eq!(parse_iso8601("2024-02-29"), Some(1_709_164_800_000));
+    }
+
+    #[test]
+    fn test_iso_round_trip_across_offsets() {
+        let base = parse_iso8601("2024-06-01T12:30:45.678Z").unwrap();
+        for input in [
+            "2024-06-01T12:30:45.678Z",
+            "2024-06-01T13:30:45.678+01:00",
+            "2024-06-01T00:00:45.678-12:30",
+            "2024-06-02T02:30:45.678+14:00",
+        ] {
+            let t = parse_iso8601(input).unwrap();
+            assert_eq!(t, base);
+            assert_eq!(parse_iso8601(&to_iso_string(t)), Some(t));
+        }
+        assert_eq!(to_iso_string(base), "2024-06-01T12:30:45.678Z");
+        assert_eq!(to_iso_string(-1), "1969-12-31T23:59:59.999Z");
+        assert_eq!(to_iso_string(MAX_TIME), "+275760-09-13T00:00:00.000Z");
+        assert_eq!(parse_iso8601("+275760-09-13T00:00:00.000Z"), Some(MAX_TIME));
+    }
+
+    #[test]
+    fn test_epoch_seconds_round_down() {
+        assert_eq!(to_epoch_seconds(1_717_245_000_999), 1_717_245_000);
+        assert_eq!(to_epoch_seconds(-1), -1);
+        assert_eq!(from_epoch_seconds(1_717_245_000), Some(1_717_245_000_000));
+        assert_eq!(from_epoch_seconds(MAX_TIME / MS_PER_SECOND + 1), None);
+    }
+
+    #[test]
+    fn test_date_string_format() {
+        assert_eq!(to_date_string(0), "Thu Jan 01 1970 00:00:00 GMT+0000");
+        let t = parse_iso8601("2024-06-01T12:30:00Z").unwrap();
+        assert_eq!(to_date_string(t), "Sat Jun 01 2024 12:30:00 GMT+0000");
+        let f = fields(t);
+        assert_eq!((f.year, f.month, f.day, f.weekday), (2024, 5, 1, 6));
+    }
+}
@@ -1 +0,0 @@
-//! JavaScript regexp built-in
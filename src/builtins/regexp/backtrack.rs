@@ -0,0 +1,798 @@
+//! Minimal backtracking regex engine
+//!
+//! Patterns are parsed into a tree, compiled to a short instruction list
+//! and run by a matcher that keeps its backtracking state on a heap stack,
+//! so long inputs never deepen the native stack. It covers character
+//! classes and escapes, greedy and lazy quantifiers, capturing and
+//! non-capturing groups, alternation, anchors, word boundaries and
+//! backreferences. Lookaround, named groups and Unicode property escapes
+//! are rejected.
+//!
+//! Like other backtracking engines it can take exponential time on
+//! patterns such as `(a|a)*b`.
+
+use crate::prelude::*;
+use alloc::rc::Rc;
+use core::ops::Range;
+
+use super::{Captures, CompiledRegex, Regex, RegexEngine, RegexFlags};
+
+/// Most instructions a pattern may compile to; counted repetition copies
+/// its body once per count
+const MAX_PROGRAM_LEN: usize = 65_536;
+
+/// The dependency-free engine
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BacktrackEngine;
+
+impl RegexEngine for BacktrackEngine {
+    fn compile(&self, pattern: &str, flags: RegexFlags) -> Result<Regex, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            groups: 0,
+            total_groups: count_groups(pattern),
+        };
+        let node = parser.alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err("unmatched ')'".to_string());
+        }
+
+        let mut compiler = Compiler {
+            program: Vec::new(),
+            classes: Vec::new(),
+            registers: 0,
+            ignore_case: flags.ignore_case,
+        };
+        compiler.push(Inst::Save(0))?;
+        compiler.node(&node)?;
+        compiler.push(Inst::Save(1))?;
+        compiler.push(Inst::Match)?;
+        Ok(Rc::new(Program {
+            insts: compiler.program,
+            classes: compiler.classes,
+            slots: 2 * (parser.groups + 1),
+            registers: compiler.registers,
+            flags,
+        }))
+    }
+}
+
+/// Pattern syntax tree
+#[derive(Debug)]
+enum Node {
+    Empty,
+    Char(char),
+    /// `.`: anything but a line terminator
+    Any,
+    Class(CharClass),
+    LineStart,
+    LineEnd,
+    WordBoundary {
+        negated: bool,
+    },
+    Group {
+        node: Box<Node>,
+        /// Capture group number; None for `(?:...)`
+        index: Option<usize>,
+    },
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    },
+    Backref(usize),
+}
+
+/// A bracketed class or a class escape such as `\d`
+#[derive(Debug, Clone)]
+struct CharClass {
+    items: Vec<ClassItem>,
+    negated: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ClassItem {
+    Range(char, char),
+    /// `\d`, or `\D` when negated
+    Digit {
+        negated: bool,
+    },
+    /// `\w` / `\W`
+    Word {
+        negated: bool,
+    },
+    /// `\s` / `\S`
+    Space {
+        negated: bool,
+    },
+}
+
+impl ClassItem {
+    fn matches(self, c: char) -> bool {
+        match self {
+            ClassItem::Range(lo, hi) => (lo..=hi).contains(&c),
+            ClassItem::Digit { negated } => c.is_ascii_digit() != negated,
+            ClassItem::Word { negated } => is_word_char(c) != negated,
+            ClassItem::Space { negated } => is_space(c) != negated,
+        }
+    }
+}
+
+impl CharClass {
+    fn matches(&self, c: char, ignore_case: bool) -> bool {
+        let hit = self.items.iter().any(|&item| {
+            item.matches(c) || (ignore_case && (item.matches(fold(c)) || item.matches(upper(c))))
+        });
+        hit != self.negated
+    }
+}
+
+/// Number of capturing groups in `pattern`, so `\N` can be told apart
+/// from a reference to a group that does not exist
+fn count_groups(pattern: &str) -> usize {
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    let mut count = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '(' if !in_class && chars.peek() != Some(&'?') => count += 1,
+            _ => {}
+        }
+    }
+    count
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Capturing groups opened so far
+    groups: usize,
+    total_groups: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Result<char, String> {
+        let c = self.peek().ok_or("unexpected end of pattern")?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut alternatives = vec![self.concat()?];
+        while self.eat('|') {
+            alternatives.push(self.concat()?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap_or(Node::Empty)
+        } else {
+            Node::Alternate(alternatives)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut items = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            items.push(self.quantified(atom)?);
+        }
+        Ok(match items.len() {
+            0 => Node::Empty,
+            1 => items.pop().unwrap_or(Node::Empty),
+            _ => Node::Concat(items),
+        })
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.next()?;
+        Ok(match c {
+            '^' => Node::LineStart,
+            '$' => Node::LineEnd,
+            '.' => Node::Any,
+            '(' => self.group()?,
+            '[' => Node::Class(self.class()?),
+            '\\' => self.escape()?,
+            '*' | '+' | '?' => return Err("nothing to repeat".to_string()),
+            '{' => {
+                self.pos -= 1;
+                if self.braced_quantifier()?.is_some() {
+                    return Err("nothing to repeat".to_string());
+                }
+                self.pos += 1;
+                Node::Char('{')
+            }
+            c => Node::Char(c),
+        })
+    }
+
+    fn group(&mut self) -> Result<Node, String> {
+        let index = if self.eat('?') {
+            if self.eat(':') {
+                None
+            } else if self.starts_with("=") || self.starts_with("!") {
+                return Err("lookahead is not supported".to_string());
+            } else if self.starts_with("<=") || self.starts_with("<!") {
+                return Err("lookbehind is not supported".to_string());
+            } else if self.starts_with("<") {
+                return Err("named groups are not supported".to_string());
+            } else {
+                return Err("invalid group".to_string());
+            }
+        } else {
+            self.groups += 1;
+            Some(self.groups)
+        };
+        let node = self.alternation()?;
+        if !self.eat(')') {
+            return Err("unterminated group".to_string());
+        }
+        Ok(Node::Group {
+            node: Box::new(node),
+            index,
+        })
+    }
+
+    /// Apply a quantifier following `atom`, if there is one
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('{') => match self.braced_quantifier()? {
+                Some(bounds) => bounds,
+                None => return Ok(atom),
+            },
+            Some(c @ ('*' | '+' | '?')) => {
+                self.pos += 1;
+                match c {
+                    '*' => (0, None),
+                    '+' => (1, None),
+                    _ => (0, Some(1)),
+                }
+            }
+            _ => return Ok(atom),
+        };
+        if matches!(
+            atom,
+            Node::LineStart | Node::LineEnd | Node::WordBoundary { .. }
+        ) {
+            return Err("nothing to repeat".to_string());
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// Parse `{n}`, `{n,}` or `{n,m}` at the cursor, stepping past it
+    ///
+    /// Anything else is a literal `{` and leaves the cursor where it was.
+    fn braced_quantifier(&mut self) -> Result<Option<(u32, Option<u32>)>, String> {
+        let start = self.pos;
+        self.pos += 1; // '{'
+        let Some(min) = self.decimal() else {
+            self.pos = start;
+            return Ok(None);
+        };
+        let max = if self.eat(',') {
+            self.decimal()
+        } else {
+            Some(min)
+        };
+        if !self.eat('}') {
+            self.pos = start;
+            return Ok(None);
+        }
+        if let Some(max) = max
+            && min > max
+        {
+            return Err("numbers out of order in {} quantifier".to_string());
+        }
+        Ok(Some((min, max)))
+    }
+
+    fn decimal(&mut self) -> Option<u32> {
+        let start = self.pos;
+        let mut value: u32 = 0;
+        while let Some(d) = self.peek().and_then(|c| c.to_digit(10)) {
+            value = value.saturating_mul(10).saturating_add(d);
+            self.pos += 1;
+        }
+        (self.pos > start).then_some(value)
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let c = self
+            .next()
+            .map_err(|_| "\\ at end of pattern".to_string())?;
+        let class = |item| {
+            Node::Class(CharClass {
+                items: vec![item],
+                negated: false,
+            })
+        };
+        Ok(match c {
+            'b' => Node::WordBoundary { negated: false },
+            'B' => Node::WordBoundary { negated: true },
+            '1'..='9' => {
+                self.pos -= 1;
+                let n = self.decimal().unwrap_or(0) as usize;
+                if n > self.total_groups {
+                    return Err(format!("invalid backreference \\{}", n));
+                }
+                Node::Backref(n)
+            }
+            'k' => return Err("named backreferences are not supported".to_string()),
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' => class(self.class_escape(c)),
+            c => Node::Char(self.char_escape(c)?),
+        })
+    }
+
+    fn class_escape(&self, c: char) -> ClassItem {
+        let negated = c.is_ascii_uppercase();
+        match c.to_ascii_lowercase() {
+            'd' => ClassItem::Digit { negated },
+            'w' => ClassItem::Word { negated },
+            _ => ClassItem::Space { negated },
+        }
+    }
+
+    /// The character an escape other than a class or assertion stands for
+    fn char_escape(&mut self, c: char) -> Result<char, String> {
+        Ok(match c {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'v' => '\u{b}',
+            'f' => '\u{c}',
+            '0' => '\0',
+            'x' => self.hex_escape(2)?,
+            'u' if self.eat('{') => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    self.pos += 1;
+                }
+                let digits: String = self.chars[start..self.pos].iter().collect();
+                if !self.eat('}') {
+                    return Err("invalid Unicode escape".to_string());
+                }
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or("invalid Unicode escape")?
+            }
+            'u' => self.hex_escape(4)?,
+            'c' => match self.peek() {
+                Some(letter) if letter.is_ascii_alphabetic() => {
+                    self.pos += 1;
+                    char::from(letter as u8 % 32)
+                }
+                _ => '\\',
+            },
+            'p' | 'P' => return Err("Unicode property escapes are not supported".to_string()),
+            c => c,
+        })
+    }
+
+    fn hex_escape(&mut self, len: usize) -> Result<char, String> {
+        let digits: String = self
+            .chars
+            .get(self.pos..self.pos + len)
+            .ok_or("invalid escape")?
+            .iter()
+            .collect();
+        let c = u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or("invalid escape")?;
+        self.pos += len;
+        Ok(c)
+    }
+
+    /// Parse a bracketed class after its `[`
+    fn class(&mut self) -> Result<CharClass, String> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        loop {
+            let c = self
+                .next()
+                .map_err(|_| "unterminated character class".to_string())?;
+            if c == ']' {
+                break;
+            }
+            let lo = match self.class_atom(c)? {
+                Ok(c) => c,
+                Err(item) => {
+                    items.push(item);
+                    continue;
+                }
+            };
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                self.pos += 1;
+                let c = self
+                    .next()
+                    .map_err(|_| "unterminated character class".to_string())?;
+                let hi = match self.class_atom(c)? {
+                    Ok(hi) => hi,
+                    Err(_) => return Err("invalid character class range".to_string()),
+                };
+                if lo > hi {
+                    return Err("range out of order in character class".to_string());
+                }
+                items.push(ClassItem::Range(lo, hi));
+            } else {
+                items.push(ClassItem::Range(lo, lo));
+            }
+        }
+        Ok(CharClass { items, negated })
+    }
+
+    /// A single character in a class, or the class an escape such as `\d`
+    /// stands for
+    fn class_atom(&mut self, c: char) -> Result<Result<char, ClassItem>, String> {
+        if c != '\\' {
+            return Ok(Ok(c));
+        }
+        let c = self
+            .next()
+            .map_err(|_| "unterminated character class".to_string())?;
+        Ok(match c {
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' => Err(self.class_escape(c)),
+            'b' => Ok('\u{8}'),
+            c => Ok(self.char_escape(c)?),
+        })
+    }
+}
+
+/// Matcher instruction
+#[derive(Debug, Clone, Copy)]
+enum Inst {
+    Char(char),
+    Any,
+    /// Index into the program's classes
+    Class(usize),
+    LineStart,
+    LineEnd,
+    WordBoundary {
+        negated: bool,
+    },
+    /// Record the position in a capture slot
+    Save(usize),
+    /// Try the first target, backtracking to the second
+    Split(usize, usize),
+    Jmp(usize),
+    /// Record where a loop iteration starts in a register
+    Mark(usize),
+    /// Fail an iteration that consumed nothing since its `Mark`, which
+    /// would otherwise loop forever
+    Progress(usize),
+    Backref(usize),
+    Match,
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+    classes: Vec<CharClass>,
+    registers: usize,
+    ignore_case: bool,
+}
+
+impl Compiler {
+    fn push(&mut self, inst: Inst) -> Result<usize, String> {
+        if self.program.len() >= MAX_PROGRAM_LEN {
+            return Err("regular expression too large".to_string());
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    fn patch_split(&mut self, at: usize, first: usize, second: usize) {
+        self.program[at] = Inst::Split(first, second);
+    }
+
+    fn node(&mut self, node: &Node) -> Result<(), String> {
+        match node {
+            Node::Empty => {}
+            Node::Char(c) => {
+                let c = if self.ignore_case { fold(*c) } else { *c };
+                self.push(Inst::Char(c))?;
+            }
+            Node::Any => {
+                self.push(Inst::Any)?;
+            }
+            Node::Class(class) => {
+                self.classes.push(class.clone());
+                self.push(Inst::Class(self.classes.len() - 1))?;
+            }
+            Node::LineStart => {
+                self.push(Inst::LineStart)?;
+            }
+            Node::LineEnd => {
+                self.push(Inst::LineEnd)?;
+            }
+            Node::WordBoundary { negated } => {
+                self.push(Inst::WordBoundary { negated: *negated })?;
+            }
+            Node::Group { node, index } => match index {
+                Some(i) => {
+                    self.push(Inst::Save(2 * i))?;
+                    self.node(node)?;
+                    self.push(Inst::Save(2 * i + 1))?;
+                }
+                None => self.node(node)?,
+            },
+            Node::Concat(items) => {
+                for item in items {
+                    self.node(item)?;
+                }
+            }
+            Node::Alternate(alternatives) => {
+                let mut exits = Vec::new();
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i + 1 == alternatives.len() {
+                        self.node(alternative)?;
+                        break;
+                    }
+                    let split = self.push(Inst::Split(0, 0))?;
+                    self.node(alternative)?;
+                    exits.push(self.push(Inst::Jmp(0))?);
+                    let next = self.program.len();
+                    self.patch_split(split, split + 1, next);
+                }
+                let end = self.program.len();
+                for exit in exits {
+                    self.program[exit] = Inst::Jmp(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => self.repeat(node, *min, *max, *greedy)?,
+            Node::Backref(n) => {
+                self.push(Inst::Backref(*n))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn repeat(
+        &mut self,
+        node: &Node,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    ) -> Result<(), String> {
+        for _ in 0..min {
+            self.node(node)?;
+        }
+        let order = |body: usize, exit: usize| {
+            if greedy { (body, exit) } else { (exit, body) }
+        };
+        match max {
+            None => {
+                let register = self.registers;
+                self.registers += 1;
+                let split = self.push(Inst::Split(0, 0))?;
+                self.push(Inst::Mark(register))?;
+                self.node(node)?;
+                self.push(Inst::Progress(register))?;
+                self.push(Inst::Jmp(split))?;
+                let exit = self.program.len();
+                let (first, second) = order(split + 1, exit);
+                self.patch_split(split, first, second);
+            }
+            Some(max) => {
+                let mut splits = Vec::new();
+                for _ in min..max {
+                    splits.push(self.push(Inst::Split(0, 0))?);
+                    self.node(node)?;
+                }
+                let exit = self.program.len();
+                for split in splits {
+                    let (first, second) = order(split + 1, exit);
+                    self.patch_split(split, first, second);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A compiled pattern
+struct Program {
+    insts: Vec<Inst>,
+    classes: Vec<CharClass>,
+    /// Two capture slots per group, group 0 being the whole match
+    slots: usize,
+    registers: usize,
+    flags: RegexFlags,
+}
+
+/// State to restore when a path fails
+enum Backtrack {
+    /// Resume at `pc` with the cursor at `pos`
+    Branch {
+        pc: usize,
+        pos: usize,
+    },
+    Slot(usize, Option<usize>),
+    Register(usize, usize),
+}
+
+impl Program {
+    /// Run the program with the match anchored at `begin`
+    fn run_at(&self, text: &str, begin: usize) -> Option<Vec<Option<usize>>> {
+        let mut slots = vec![None; self.slots];
+        let mut registers = vec![usize::MAX; self.registers];
+        let mut stack = Vec::new();
+        let (mut pc, mut pos) = (0, begin);
+        loop {
+            let next = text[pos..].chars().next();
+            let advanced = match self.insts[pc] {
+                Inst::Match => return Some(slots),
+                Inst::Char(want) => next
+                    .filter(|&c| c == want || (self.flags.ignore_case && fold(c) == want))
+                    .map(|c| pos + c.len_utf8()),
+                Inst::Any => next
+                    .filter(|&c| !is_line_terminator(c))
+                    .map(|c| pos + c.len_utf8()),
+                Inst::Class(i) => next
+                    .filter(|&c| self.classes[i].matches(c, self.flags.ignore_case))
+                    .map(|c| pos + c.len_utf8()),
+                Inst::LineStart => {
+                    let prev = text[..pos].chars().next_back();
+                    let at_start = match prev {
+                        None => true,
+                        Some(c) => self.flags.multiline && is_line_terminator(c),
+                    };
+                    at_start.then_some(pos)
+                }
+                Inst::LineEnd => {
+                    let at_end = match next {
+                        None => true,
+                        Some(c) => self.flags.multiline && is_line_terminator(c),
+                    };
+                    at_end.then_some(pos)
+                }
+                Inst::WordBoundary { negated } => {
+                    let before = text[..pos].chars().next_back().is_some_and(is_word_char);
+                    let after = next.is_some_and(is_word_char);
+                    ((before != after) != negated).then_some(pos)
+                }
+                Inst::Save(slot) => {
+                    stack.push(Backtrack::Slot(slot, slots[slot]));
+                    slots[slot] = Some(pos);
+                    Some(pos)
+                }
+                Inst::Split(first, second) => {
+                    stack.push(Backtrack::Branch { pc: second, pos });
+                    pc = first;
+                    continue;
+                }
+                Inst::Jmp(target) => {
+                    pc = target;
+                    continue;
+                }
+                Inst::Mark(register) => {
+                    stack.push(Backtrack::Register(register, registers[register]));
+                    registers[register] = pos;
+                    Some(pos)
+                }
+                Inst::Progress(register) => (registers[register] != pos).then_some(pos),
+                Inst::Backref(group) => match (slots[2 * group], slots[2 * group + 1]) {
+                    (Some(start), Some(end)) => self.backref(text, pos, &text[start..end]),
+                    // A group that has not matched matches the empty string
+                    _ => Some(pos),
+                },
+            };
+            match advanced {
+                Some(new_pos) => {
+                    pos = new_pos;
+                    pc += 1;
+                }
+                None => loop {
+                    match stack.pop()? {
+                        Backtrack::Branch { pc: p, pos: q } => {
+                            pc = p;
+                            pos = q;
+                            break;
+                        }
+                        Backtrack::Slot(slot, old) => slots[slot] = old,
+                        Backtrack::Register(register, old) => registers[register] = old,
+                    }
+                },
+            }
+        }
+    }
+
+    /// Match the text a group captured again at `pos`
+    fn backref(&self, text: &str, pos: usize, captured: &str) -> Option<usize> {
+        let mut rest = text[pos..].chars();
+        let mut end = pos;
+        for want in captured.chars() {
+            let c = rest.next()?;
+            let same = c == want || (self.flags.ignore_case && fold(c) == fold(want));
+            if !same {
+                return None;
+            }
+            end += c.len_utf8();
+        }
+        Some(end)
+    }
+}
+
+impl CompiledRegex for Program {
+    fn captures_at(&self, text: &str, start: usize) -> Option<Captures> {
+        let starts = text[start..]
+            .char_indices()
+            .map(|(i, _)| start + i)
+            .chain(core::iter::once(text.len()));
+        for begin in starts {
+            if let Some(slots) = self.run_at(text, begin) {
+                return Some(
+                    slots
+                        .chunks(2)
+                        .map(|pair| match (pair[0], pair[1]) {
+                            (Some(s), Some(e)) => Some(Range { start: s, end: e }),
+                            _ => None,
+                        })
+                        .collect(),
+                );
+            }
+        }
+        None
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_space(c: char) -> bool {
+    c.is_whitespace() || c == '\u{feff}'
+}
+
+fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+/// Simple case folding: the first character of the lowercase mapping
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn upper(c: char) -> char {
+    c.to_uppercase().next().unwrap_or(c)
+}
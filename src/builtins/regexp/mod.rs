@@ -0,0 +1,359 @@
+//! JavaScript regexp built-in
+//!
+//! RegExp objects hold a [`Regex`] compiled by a [`RegexEngine`]. Two
+//! engines exist: the `regex` crate, used when the `regex-backend` feature
+//! is on (the default), and [`BacktrackEngine`], a small backtracking
+//! matcher with no dependencies that also supports backreferences. Each
+//! rejects the constructs it cannot run with a message naming them, which
+//! RegExp reports as a SyntaxError.
+//!
+//! Match positions are byte offsets into the UTF-8 text.
+
+use crate::prelude::*;
+
+mod backtrack;
+
+use alloc::rc::Rc;
+use core::ops::Range;
+
+pub use backtrack::BacktrackEngine;
+
+/// Spans of one match: the whole match first, then each capture group,
+/// None for groups that did not take part
+pub type Captures = Vec<Option<Range<usize>>>;
+
+/// A compiled pattern, shared by the RegExp objects created from it
+pub type Regex = Rc<dyn CompiledRegex>;
+
+/// Flags that change how a pattern matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegexFlags {
+    /// `i`: letters match either case
+    pub ignore_case: bool,
+    /// `m`: `^` and `$` also match at line breaks
+    pub multiline: bool,
+}
+
+/// A pattern ready to match
+pub trait CompiledRegex {
+    /// Spans of the first match starting at or after byte offset `start`
+    fn captures_at(&self, text: &str, start: usize) -> Option<Captures>;
+
+    /// Span of the first match starting at or after byte offset `start`
+    fn find_at(&self, text: &str, start: usize) -> Option<Range<usize>> {
+        self.captures_at(text, start)?.into_iter().next()?
+    }
+
+    /// Whether the pattern matches anywhere in `text`
+    fn test(&self, text: &str) -> bool {
+        self.find_at(text, 0).is_some()
+    }
+}
+
+/// A regular expression implementation
+pub trait RegexEngine {
+    /// Compile `pattern`, or describe why it is invalid or unsupported
+    fn compile(&self, pattern: &str, flags: RegexFlags) -> Result<Regex, String>;
+}
+
+/// Compile `pattern` with the engine this build uses
+pub fn compile(pattern: &str, flags: RegexFlags) -> Result<Regex, String> {
+    #[cfg(feature = "regex-backend")]
+    let engine = RegexCrateEngine;
+    #[cfg(not(feature = "regex-backend"))]
+    let engine = BacktrackEngine;
+    engine.compile(pattern, flags)
+}
+
+/// Engine backed by the `regex` crate
+///
+/// It runs in linear time but has no backreferences or lookaround; those
+/// patterns are rejected before the crate sees them so the error names the
+/// construct.
+#[cfg(feature = "regex-backend")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegexCrateEngine;
+
+#[cfg(feature = "regex-backend")]
+impl RegexEngine for RegexCrateEngine {
+    fn compile(&self, pattern: &str, flags: RegexFlags) -> Result<Regex, String> {
+        if let Some(construct) = unsupported_by_regex_crate(pattern) {
+            return Err(construct.to_string());
+        }
+        let mut translated = String::new();
+        if flags.ignore_case || flags.multiline {
+            translated.push_str("(?");
+            if flags.ignore_case {
+                translated.push('i');
+            }
+            if flags.multiline {
+                translated.push('m');
+            }
+            translated.push(')');
+        }
+        translated.push_str(pattern);
+        match regex::Regex::new(&translated) {
+            Ok(regex) => Ok(Rc::new(regex)),
+            // The crate's syntax errors draw the pattern over several lines;
+            // the last one says what is wrong
+            Err(regex::Error::Syntax(msg)) => Err(msg
+                .lines()
+                .last()
+                .unwrap_or_default()
+                .trim_start_matches("error: ")
+                .to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "regex-backend")]
+impl CompiledRegex for regex::Regex {
+    fn captures_at(&self, text: &str, start: usize) -> Option<Captures> {
+        let caps = regex::Regex::captures_at(self, text, start)?;
+        Some(caps.iter().map(|m| m.map(|m| m.range())).collect())
+    }
+
+    fn find_at(&self, text: &str, start: usize) -> Option<Range<usize>> {
+        regex::Regex::find_at(self, text, start).map(|m| m.range())
+    }
+
+    fn test(&self, text: &str) -> bool {
+        self.is_match(text)
+    }
+}
+
+/// The first JS construct in `pattern` the `regex` crate cannot run
+#[cfg(feature = "regex-backend")]
+fn unsupported_by_regex_crate(pattern: &str) -> Option<&'static str> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut in_class = false;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                match chars.get(i + 1) {
+                    Some('1'..='9') if !in_class => {
+                        return Some("backreferences are not supported");
+                    }
+                    Some('k') if !in_class => {
+                        return Some("named backreferences are not supported");
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '(' if !in_class => {
+                let rest: String = chars[i + 1..chars.len().min(i + 4)].iter().collect();
+                if rest.starts_with("?=") || rest.starts_with("?!") {
+                    return Some("lookahead is not supported");
+                }
+                if rest.starts_with("?<=") || rest.starts_with("?<!") {
+                    return Some("lookbehind is not supported");
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engines() -> Vec<(&'static str, Box<dyn RegexEngine>)> {
+        vec![
+            (
+                "backtrack",
+                Box::new(BacktrackEngine) as Box<dyn RegexEngine>,
+            ),
+            #[cfg(feature = "regex-backend")]
+            ("regex", Box::new(RegexCrateEngine)),
+        ]
+    }
+
+    /// Captured text of the first match, or None without a match
+    fn captures(
+        engine: &dyn RegexEngine,
+        pattern: &str,
+        flags: RegexFlags,
+        text: &str,
+    ) -> Option<Vec<Option<String>>> {
+        let regex = engine.compile(pattern, flags).unwrap();
+        let caps = regex.captures_at(text, 0)?;
+        Some(
+            caps.into_iter()
+                .map(|span| span.map(|span| text[span].to_string()))
+                .collect(),
+        )
+    }
+
+    /// Pattern, flags, text, and the captures expected (None for no match)
+    type Case<'a> = (&'a str, RegexFlags, &'a str, Option<&'a [Option<&'a str>]>);
+
+    const PLAIN: RegexFlags = RegexFlags {
+        ignore_case: false,
+        multiline: false,
+    };
+
+    #[test]
+    fn test_shared_suite() {
+        let ignore_case = RegexFlags {
+            ignore_case: true,
+            ..PLAIN
+        };
+        let multiline = RegexFlags {
+            multiline: true,
+            ..PLAIN
+        };
+        let cases: &[Case] = &[
+            ("world", PLAIN, "hello world", Some(&[Some("world")])),
+            ("xyz", PLAIN, "hello world", None),
+            ("[0-9]+", PLAIN, "abc123def", Some(&[Some("123")])),
+            ("[^a-c]+", PLAIN, "abcxyzabc", Some(&[Some("xyz")])),
+            (
+                "\\d+\\s\\w+",
+                PLAIN,
+                "it is 42 apples",
+                Some(&[Some("42 apples")]),
+            ),
+            ("a.c", PLAIN, "a\nc abc", Some(&[Some("abc")])),
+            ("colou?r", PLAIN, "my color", Some(&[Some("color")])),
+            ("a{2,3}", PLAIN, "caaaat", Some(&[Some("aaa")])),
+            ("a{2}", PLAIN, "caaaat", Some(&[Some("aa")])),
+            ("a{2,}", PLAIN, "caaaat", Some(&[Some("aaaa")])),
+            ("<.+?>", PLAIN, "<a><b>", Some(&[Some("<a>")])),
+            ("<.+>", PLAIN, "<a><b>", Some(&[Some("<a><b>")])),
+            (
+                "(\\w)=(\\w)",
+                PLAIN,
+                "k=v",
+                Some(&[Some("k=v"), Some("k"), Some("v")]),
+            ),
+            ("(a)|(b)", PLAIN, "b", Some(&[Some("b"), None, Some("b")])),
+            ("(?:ab)+", PLAIN, "xababy", Some(&[Some("abab")])),
+            ("cat|dog", PLAIN, "hotdog", Some(&[Some("dog")])),
+            ("^abc", PLAIN, "xabc", None),
+            ("abc$", PLAIN, "abc\n", None),
+            ("^b", multiline, "a\nb", Some(&[Some("b")])),
+            ("a$", multiline, "a\nb", Some(&[Some("a")])),
+            ("\\bcat\\b", PLAIN, "concat cat", Some(&[Some("cat")])),
+            ("\\Bcat", PLAIN, "concat", Some(&[Some("cat")])),
+            ("HELLO", PLAIN, "hello", None),
+            ("HELLO", ignore_case, "say hello", Some(&[Some("hello")])),
+            ("[A-Z]+", ignore_case, "abc", Some(&[Some("abc")])),
+            ("(a*)*b", PLAIN, "aaab", Some(&[Some("aaab"), Some("aaa")])),
+            ("\\u0041\\x42\\t", PLAIN, "AB\t", Some(&[Some("AB\t")])),
+            ("é+", PLAIN, "caféé", Some(&[Some("éé")])),
+            ("", PLAIN, "abc", Some(&[Some("")])),
+        ];
+        for (name, engine) in engines() {
+            for &(pattern, flags, text, expected) in cases {
+                let expected =
+                    expected.map(|caps| caps.iter().map(|c| c.map(String::from)).collect());
+                assert_eq!(
+                    captures(engine.as_ref(), pattern, flags, text),
+                    expected,
+                    "{} engine, /{}/ on {:?}",
+                    name,
+                    pattern,
+                    text
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_at_and_test() {
+        for (name, engine) in engines() {
+            let regex = engine.compile("o", PLAIN).unwrap();
+            assert_eq!(regex.find_at("foo boo", 0), Some(1..2), "{}", name);
+            assert_eq!(regex.find_at("foo boo", 3), Some(5..6), "{}", name);
+            assert_eq!(regex.find_at("foo boo", 7), None, "{}", name);
+            assert!(regex.test("o"), "{}", name);
+            assert!(!regex.test("x"), "{}", name);
+            // Anchors see the whole text, not just the part searched
+            let anchored = engine.compile("^a", PLAIN).unwrap();
+            assert_eq!(anchored.find_at("aa", 1), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_invalid_patterns_are_errors_on_every_engine() {
+        for (name, engine) in engines() {
+            for pattern in ["(ab", "[ab", "a{2,1}", "*a", "(?<=a)b", "(?<!a)b"] {
+                assert!(
+                    engine.compile(pattern, PLAIN).is_err(),
+                    "{} engine accepted /{}/",
+                    name,
+                    pattern
+                );
+            }
+            let err = engine.compile("(?<=a)b", PLAIN).err().unwrap();
+            assert!(err.contains("lookbehind"), "{}: {}", name, err);
+        }
+    }
+
+    #[test]
+    fn test_backreferences() {
+        let engine = BacktrackEngine;
+        let cases: &[Case] = &[
+            (
+                "(a+)b\\1",
+                PLAIN,
+                "aabaa",
+                Some(&[Some("aabaa"), Some("aa")]),
+            ),
+            ("(a+)b\\1", PLAIN, "aaba", Some(&[Some("aba"), Some("a")])),
+            ("(\\w)\\1", PLAIN, "abccd", Some(&[Some("cc"), Some("c")])),
+            (
+                "(['\"]).*?\\1",
+                PLAIN,
+                "say \"hi\" 'x'",
+                Some(&[Some("\"hi\""), Some("\"")]),
+            ),
+            (
+                "(x)\\1",
+                RegexFlags {
+                    ignore_case: true,
+                    ..PLAIN
+                },
+                "xX",
+                Some(&[Some("xX"), Some("x")]),
+            ),
+            ("(a)|\\1b", PLAIN, "b", Some(&[Some("b"), None])),
+            (
+                "<(\\w+)>.*</\\1>",
+                PLAIN,
+                "<b>x</i></b>",
+                Some(&[Some("<b>x</i></b>"), Some("b")]),
+            ),
+        ];
+        for &(pattern, flags, text, expected) in cases {
+            let expected = expected.map(|caps| caps.iter().map(|c| c.map(String::from)).collect());
+            assert_eq!(
+                captures(&engine, pattern, flags, text),
+                expected,
+                "/{}/",
+                pattern
+            );
+        }
+        assert!(engine.compile("(a)\\2", PLAIN).is_err());
+    }
+
+    #[cfg(feature = "regex-backend")]
+    #[test]
+    fn test_regex_crate_names_unsupported_constructs() {
+        let message = |pattern| RegexCrateEngine.compile(pattern, PLAIN).err().unwrap();
+        assert_eq!(message("(a)\\1"), "backreferences are not supported");
+        assert_eq!(message("a(?=b)"), "lookahead is not supported");
+        assert_eq!(message("(?<!a)b"), "lookbehind is not supported");
+        assert_eq!(message("(ab"), "unclosed group");
+        // Inside a class the same characters are literals
+        assert!(RegexCrateEngine.compile("[(?=]x", PLAIN).is_ok());
+    }
+}
@@ -3,11 +3,23 @@
 //! The Context is the main entry point for the JavaScript engine.
 //! It owns all memory and provides the API for evaluating JavaScript code.
 
+use crate::prelude::*;
+use alloc::rc::Rc;
+use core::time::Duration;
+use hashbrown::HashMap;
+
 use crate::gc::Heap;
-use crate::parser::compiler::{CompileError, Compiler};
-use crate::runtime::FunctionBytecode;
-use crate::value::Value;
-use crate::vm::Interpreter;
+use crate::parser::compiler::{CompileBudget, CompileError, CompiledFunction, Compiler};
+use crate::parser::diagnostic::{Diagnostic, DiagnosticHandler};
+use crate::runtime::{FunctionBytecode, ScriptMetrics};
+use crate::value::{JsStr, Value};
+#[cfg(feature = "opcode-stats")]
+use crate::vm::OpCode;
+use crate::vm::{
+    ClassDef, Clock, ConsoleSink, ExecutionReport, GcCallback, GcStats, HostFn, Interpreter,
+    InterpreterError, InterpreterResult, InterpreterStats, JsonError, JsonOptions, ModuleBuilder,
+    PersistentValue, Receiver, ReplayLog, ResolverHandle, SlicedRun,
+};
 
 /// JavaScript execution context
 ///
@@ -25,6 +37,95 @@ pub struct Context {
 
     /// Whether we're in the process of handling out-of-memory
     in_out_of_memory: bool,
+
+    /// Receives compiler diagnostics for evaluated scripts
+    diagnostic_handler: Option<DiagnosticHandler>,
+
+    /// Receives uncaught exceptions from timer callbacks
+    unhandled_error_hook: Option<UnhandledErrorHook>,
+
+    /// Scripts compiled by `eval`, reused when the same source runs again
+    compile_cache: CompileCache,
+}
+
+/// Loaded scripts keyed by their full source
+///
+/// Keying by the source itself rather than a hash of it means distinct
+/// sources can never share an entry. Evicted scripts stay loaded in the
+/// interpreter, as every evaluated script does, since closures they
+/// created may still run.
+#[derive(Default)]
+struct CompileCache {
+    /// Most entries kept; 0 disables the cache
+    max_entries: usize,
+    /// Interpreter script index and last-use tick of each cached source
+    entries: HashMap<String, (usize, u64)>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl CompileCache {
+    /// Script index cached for `source`, counting the hit or miss
+    fn lookup(&mut self, source: &str) -> Option<usize> {
+        self.tick += 1;
+        match self.entries.get_mut(source) {
+            Some((script, last_used)) => {
+                *last_used = self.tick;
+                self.hits += 1;
+                Some(*script)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Remember `script` for `source`, evicting the least recently used
+    /// entry when full
+    fn insert(&mut self, source: &str, script: usize) {
+        if self.entries.len() >= self.max_entries {
+            self.evict_oldest();
+        }
+        self.entries.insert(source.to_string(), (script, self.tick));
+    }
+
+    /// Change the capacity, evicting the least recently used entries that
+    /// no longer fit
+    fn resize(&mut self, max_entries: usize) {
+        self.max_entries = max_entries;
+        while self.entries.len() > max_entries {
+            self.evict_oldest();
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, (_, last_used))| *last_used)
+            .map(|(source, _)| source.clone());
+        if let Some(oldest) = oldest {
+            self.entries.remove(&oldest);
+        }
+    }
+
+    /// Drop every entry and keep the counters
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Counters for the compile cache used by [`Context::eval`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompileCacheStats {
+    /// Sources currently cached
+    pub entries: usize,
+    /// Evaluations that reused a cached script
+    pub hits: u64,
+    /// Evaluations that had to compile while the cache was enabled
+    pub misses: u64,
 }
 
 /// Error from JavaScript evaluation
@@ -36,8 +137,8 @@ pub enum EvalError {
     RuntimeError(String),
 }
 
-impl std::fmt::Display for EvalError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             EvalError::CompileError(e) => write!(f, "Compile error: {}", e),
             EvalError::RuntimeError(msg) => write!(f, "Runtime error: {}", msg),
@@ -45,7 +146,7 @@ impl std::fmt::Display for EvalError {
     }
 }
 
-impl std::error::Error for EvalError {}
+impl core::error::Error for EvalError {}
 
 impl From<CompileError> for EvalError {
     fn from(e: CompileError) -> Self {
@@ -53,16 +154,176 @@ impl From<CompileError> for EvalError {
     }
 }
 
+/// Outcome of [`Context::eval_with_timeout`]
+#[derive(Debug, Clone, Copy)]
+pub struct EvalSummary {
+    /// Completion value of the script (undefined if the script itself was
+    /// interrupted)
+    pub result: Value,
+    /// Number of timer callbacks run
+    pub timers_fired: usize,
+    /// Whether the deadline passed before all work was drained
+    pub timed_out: bool,
+}
+
+/// An exception thrown by script code that no handler caught
+#[derive(Debug, Clone)]
+pub struct JsError {
+    /// The thrown value
+    pub value: Value,
+    /// Error name such as "TypeError", or empty when a value other than an
+    /// error object was thrown
+    pub name: String,
+    /// The error message, or the thrown value as `print` shows it
+    pub message: String,
+}
+
+impl core::fmt::Display for JsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.name.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.name, self.message)
+        }
+    }
+}
+
+impl core::error::Error for JsError {}
+
+/// Outcome of one [`Execution::run_for`] slice
+#[derive(Debug, Clone)]
+pub enum StepResult {
+    /// The script and every timer due by now have run; holds the script's
+    /// completion value
+    Completed(Value),
+    /// The instruction budget ran out; call [`Execution::run_for`] again
+    Yielded,
+    /// The script, or a timer callback with no unhandled error hook to
+    /// report to, threw an exception no handler caught
+    Failed(JsError),
+}
+
+/// A script running in bounded slices, started by [`Context::start`]
+///
+/// Between slices the script's frames stay on the context's stacks, where
+/// the collector sees them. [`Execution::context`] lends the context out
+/// meanwhile: evaluations made through it run to completion on top of the
+/// paused script without disturbing it. Dropping an unfinished execution
+/// abandons the script.
+pub struct Execution<'a> {
+    ctx: &'a mut Context,
+    /// The script or timer callback being run, if any
+    run: Option<SlicedRun>,
+    /// Completion value of the script once it has finished
+    result: Option<Value>,
+    /// Outcome to repeat once the execution is over
+    outcome: Option<StepResult>,
+}
+
+/// Receives exceptions thrown by timer callbacks that no handler caught
+pub type UnhandledErrorHook = Box<dyn FnMut(JsError)>;
+
+/// Weak reference to a script value held by the host
+///
+/// Values are not rooted by the host, so a plain [`Value`] may outlive the
+/// object it names; a `WeakValue` checks liveness before handing it back,
+/// and stays empty once a reclaimed slot is reused for something else.
+/// Use [`Context::persist`] to keep the object alive instead.
+#[derive(Debug, Clone, Copy)]
+pub struct WeakValue {
+    value: Value,
+    /// Allocation count of the value's slot when the handle was made
+    generation: u32,
+}
+
+impl WeakValue {
+    /// Get the value back if it has not been reclaimed
+    pub fn upgrade(&self, ctx: &Context) -> Option<Value> {
+        let interp = &ctx.interpreter;
+        (interp.is_live(self.value) && interp.slot_generation(self.value) == self.generation)
+            .then_some(self.value)
+    }
+}
+
+/// A compiled script, from [`Context::compile`] or [`Script::deserialize`]
+///
+/// Run it with [`Context::execute`], or serialize it to load later without
+/// the compiler.
+pub struct Script {
+    function: FunctionBytecode,
+}
+
+impl Script {
+    /// Encode the script for [`Script::deserialize`]
+    pub fn serialize(&self) -> Vec<u8> {
+        self.function.serialize()
+    }
+
+    /// Decode a script written by [`Script::serialize`], rejecting bytecode
+    /// that would not execute safely
+    pub fn deserialize(data: &[u8]) -> Result<Script, String> {
+        let (function, _) = FunctionBytecode::deserialize(data)?;
+        function.validate()?;
+        Ok(Script { function })
+    }
+
+    /// Size figures for the script and its functions
+    pub fn metrics(&self) -> ScriptMetrics {
+        self.function.metrics()
+    }
+
+    /// Human-readable table of [`Script::metrics`], one row per function
+    pub fn report(&self) -> String {
+        self.function.report()
+    }
+
+    /// Render the bytecode of the script and its functions
+    pub fn disassemble(&self) -> String {
+        self.function.disassemble()
+    }
+}
+
+/// Handle to a native function added with [`Context::add_native`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NativeId(u32);
+
+impl NativeId {
+    /// The function as a value scripts can call
+    pub fn value(self) -> Value {
+        Value::native_func(self.0)
+    }
+}
+
+/// Error from [`Context::add_native`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterError {
+    /// A native function with this name is already registered
+    DuplicateName(String),
+}
+
+impl core::fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RegisterError::DuplicateName(name) => {
+                write!(f, "native function {} is already registered", name)
+            }
+        }
+    }
+}
+
+impl core::error::Error for RegisterError {}
+
 /// Memory usage statistics
 #[derive(Debug, Clone, Copy, Default)]
 pub struct MemoryStats {
-    /// Total memory size
+    /// Memory budget given to [`Context::new`]
     pub heap_size: usize,
-    /// Currently used heap memory
+    /// Bytes counted against the budget, as reported by
+    /// [`Interpreter::memory_used`]
     pub used: usize,
-    /// Currently used stack memory
+    /// Bytes of values on the value stack, included in `used`
     pub stack_used: usize,
-    /// Free memory available
+    /// Bytes left in the budget
     pub free: usize,
     /// Number of runtime strings
     pub runtime_strings: usize,
@@ -80,12 +341,188 @@ pub struct MemoryStats {
     pub typed_arrays: usize,
 }
 
+/// Configures a [`Context`] before it is created
+///
+/// Everything is enabled by default, matching [`Context::new`]. Disabled
+/// globals are not defined at all, so scripts naming them get a
+/// ReferenceError, and a disabled `print` statement fails to compile.
+#[derive(Debug, Clone)]
+pub struct ContextBuilder {
+    mem_size: usize,
+    gc_global: bool,
+    load: bool,
+    timers: bool,
+    print: bool,
+    profile: bool,
+    harden: bool,
+    max_array_length: Option<usize>,
+    max_string_length: Option<usize>,
+    compile_budget: CompileBudget,
+    engine_module: bool,
+}
+
+impl ContextBuilder {
+    /// Start from the defaults for a context with the given memory size
+    pub fn new(mem_size: usize) -> Self {
+        ContextBuilder {
+            mem_size,
+            gc_global: true,
+            load: true,
+            timers: true,
+            print: true,
+            profile: false,
+            harden: false,
+            max_array_length: None,
+            max_string_length: None,
+            compile_budget: CompileBudget::default(),
+            engine_module: false,
+        }
+    }
+
+    /// Whether scripts can force a collection with the `gc()` global
+    pub fn enable_gc_global(mut self, enabled: bool) -> Self {
+        self.gc_global = enabled;
+        self
+    }
+
+    /// Whether scripts can run other files with the `load()` global
+    pub fn enable_load(mut self, enabled: bool) -> Self {
+        self.load = enabled;
+        self
+    }
+
+    /// Whether scripts get the `setTimeout` and `clearTimeout` globals
+    pub fn enable_timers(mut self, enabled: bool) -> Self {
+        self.timers = enabled;
+        self
+    }
+
+    /// Whether scripts may use the non-standard `print` statement
+    pub fn enable_print(mut self, enabled: bool) -> Self {
+        self.print = enabled;
+        self
+    }
+
+    /// Whether to collect an [`ExecutionReport`] of the resources scripts
+    /// use, as [`Context::eval_profiled`] returns; off by default
+    pub fn profile(mut self, enabled: bool) -> Self {
+        self.profile = enabled;
+        self
+    }
+
+    /// Whether to protect the builtins from prototype pollution by
+    /// untrusted scripts; off by default
+    ///
+    /// Hardened, writes to builtin objects such as `Array.prototype` throw
+    /// a TypeError, `constructor` reads as undefined on every value, and
+    /// `__proto__` cannot be read, written or defined. Calling builtin
+    /// methods works as before. See [`Interpreter::set_hardened`].
+    pub fn harden(mut self, enabled: bool) -> Self {
+        self.harden = enabled;
+        self
+    }
+
+    /// Longest array scripts may grow, below the memory budget; growth
+    /// past it throws a RangeError (see [`Interpreter::set_max_array_length`])
+    ///
+    /// Defaults to, and cannot exceed, [`Interpreter::MAX_ARRAY_LENGTH`].
+    pub fn max_array_length(mut self, len: usize) -> Self {
+        self.max_array_length = Some(len);
+        self
+    }
+
+    /// Longest string scripts may build, in bytes; building a longer one
+    /// throws a RangeError (see [`Interpreter::set_max_string_length`])
+    pub fn max_string_length(mut self, len: usize) -> Self {
+        self.max_string_length = Some(len);
+        self
+    }
+
+    /// Most tokens one script may contain; longer scripts fail to compile
+    /// with [`CompileError::BudgetExceeded`]
+    ///
+    /// Defaults to [`DEFAULT_MAX_TOKENS`](crate::parser::compiler::DEFAULT_MAX_TOKENS).
+    pub fn max_compile_tokens(mut self, limit: usize) -> Self {
+        self.compile_budget.max_tokens = limit;
+        self
+    }
+
+    /// Most bytes of bytecode one script may compile to, counting every
+    /// function it defines; larger scripts fail to compile with
+    /// [`CompileError::BudgetExceeded`]
+    ///
+    /// Defaults to [`DEFAULT_MAX_BYTECODE`](crate::parser::compiler::DEFAULT_MAX_BYTECODE).
+    pub fn max_compile_bytecode(mut self, limit: usize) -> Self {
+        self.compile_budget.max_bytecode = limit;
+        self
+    }
+
+    /// Longest compiling one script may take on the context's clock before
+    /// it fails with [`CompileError::BudgetExceeded`]; unlimited by default
+    ///
+    /// Execution time is limited separately, by
+    /// [`Context::eval_with_timeout`].
+    pub fn compile_timeout(mut self, timeout: Duration) -> Self {
+        self.compile_budget.timeout = Some(timeout);
+        self
+    }
+
+    /// Whether scripts get the `__engine` module of host utilities, such
+    /// as `__engine.diff`; off by default (see [`engine_module`](crate::vm::engine_module))
+    pub fn enable_engine_module(mut self, enabled: bool) -> Self {
+        self.engine_module = enabled;
+        self
+    }
+
+    /// Create the context
+    ///
+    /// # Panics
+    /// Panics if the memory size is too small, as [`Context::new`] does
+    pub fn build(self) -> Context {
+        let mut ctx = Context::new(self.mem_size);
+        let disabled = [
+            (!self.gc_global, &["gc"][..]),
+            (!self.load, &["load"][..]),
+            (!self.timers, &["setTimeout", "clearTimeout"][..]),
+        ];
+        for name in disabled
+            .iter()
+            .filter(|(off, _)| *off)
+            .flat_map(|(_, names)| names.iter())
+        {
+            ctx.interpreter.remove_builtin_native(name);
+        }
+        ctx.interpreter.set_print_enabled(self.print);
+        ctx.interpreter.set_profiling(self.profile);
+        ctx.interpreter.set_hardened(self.harden);
+        ctx.interpreter.set_compile_budget(self.compile_budget);
+        if self.engine_module {
+            ctx.register_module(crate::vm::engine_module());
+        }
+        if let Some(len) = self.max_array_length {
+            ctx.interpreter.set_max_array_length(len);
+        }
+        if let Some(len) = self.max_string_length {
+            ctx.interpreter.set_max_string_length(len);
+        }
+        ctx
+    }
+}
+
 impl Context {
     /// Create a new JavaScript context with the given memory size
     ///
     /// # Arguments
     /// * `mem_size` - Total memory available for the JS engine in bytes
     ///
+    /// The budget covers what scripts allocate: array elements, object
+    /// properties, closure captures, strings built at runtime, error
+    /// objects, typed array storage and the values on the stack (see
+    /// [`Interpreter::memory_used`]). Compiled bytecode is not counted.
+    /// A script that would go over the budget gets a catchable
+    /// `RangeError: out of memory`; [`Context::memory_stats`] reports
+    /// current usage.
+    ///
     /// # Panics
     /// Panics if mem_size is too small (minimum ~4KB recommended)
     pub fn new(mem_size: usize) -> Self {
@@ -96,100 +533,428 @@ impl Context {
             MIN_MEM_SIZE
         );
 
+        let mut interpreter = Interpreter::new();
+        interpreter.set_memory_limit(mem_size);
+
         Context {
             heap: Heap::new(mem_size),
-            interpreter: Interpreter::new(),
+            interpreter,
             current_exception: Value::undefined(),
             in_out_of_memory: false,
+            diagnostic_handler: None,
+            unhandled_error_hook: None,
+            compile_cache: CompileCache::default(),
         }
     }
 
+    /// Start configuring a context with the given memory size, for
+    /// choosing which non-standard globals and statements scripts get
+    pub fn builder(mem_size: usize) -> ContextBuilder {
+        ContextBuilder::new(mem_size)
+    }
+
     /// Evaluate JavaScript source code
     ///
     /// # Arguments
     /// * `source` - JavaScript source code as a string
     ///
     /// # Returns
-    /// The result of evaluating the code, or an error
+    /// The script's completion value (the value of the last top-level
+    /// expression statement, or undefined if the script ends with a
+    /// declaration or other statement), or an error
     pub fn eval(&mut self, source: &str) -> Result<Value, EvalError> {
-        // Compile the source code
-        let compiled = Compiler::new(source).compile()?;
-
-        // Convert to FunctionBytecode for the interpreter
-        let bytecode = Self::compiled_to_bytecode(compiled);
+        if self.compile_cache.max_entries == 0 {
+            let compiled = self.compile_script(source)?;
+            return self.run_compiled(compiled);
+        }
 
-        // Execute the bytecode
+        let script = match self.compile_cache.lookup(source) {
+            Some(script) => script,
+            None => {
+                let compiled = self.compile_script(source)?;
+                let script = self
+                    .interpreter
+                    .load_script(FunctionBytecode::from_compiled(compiled));
+                self.compile_cache.insert(source, script);
+                script
+            }
+        };
         self.interpreter
-            .execute(&bytecode)
+            .run_script(script)
             .map_err(|e| EvalError::RuntimeError(e.to_string()))
     }
 
-    /// Convert CompiledFunction to FunctionBytecode (recursive for inner functions)
-    fn compiled_to_bytecode(
-        compiled: crate::parser::compiler::CompiledFunction,
-    ) -> FunctionBytecode {
-        use crate::runtime::CaptureInfo;
+    /// Evaluate JavaScript source code as [`Context::eval`] does, also
+    /// returning the resources the evaluation used
+    ///
+    /// The report is all zero unless the context was built with
+    /// [`ContextBuilder::profile`] enabled. Timers fired later are not
+    /// included.
+    pub fn eval_profiled(&mut self, source: &str) -> (Result<Value, EvalError>, ExecutionReport) {
+        self.interpreter.reset_execution_report();
+        let result = self.eval(source);
+        (result, self.interpreter.execution_report())
+    }
 
-        let inner_functions = compiled
-            .functions
-            .into_iter()
-            .map(Self::compiled_to_bytecode)
-            .collect();
+    /// Executions of every opcode since the context was created or the
+    /// counters were last reset, most executed first
+    ///
+    /// See [`Interpreter::opcode_stats`].
+    #[cfg(feature = "opcode-stats")]
+    pub fn opcode_stats(&self) -> Vec<(OpCode, u64)> {
+        self.interpreter.opcode_stats()
+    }
 
-        // Convert compiler's CaptureInfo to runtime's CaptureInfo
-        let captures = compiled
-            .captures
-            .into_iter()
-            .map(|c| CaptureInfo {
-                outer_index: c.outer_index,
-                is_local: c.is_local,
-            })
-            .collect();
+    /// Calls per native function name, most called first
+    #[cfg(feature = "opcode-stats")]
+    pub fn native_call_stats(&self) -> Vec<(String, u64)> {
+        self.interpreter.native_call_stats()
+    }
+
+    /// Zero the opcode and native call counters
+    #[cfg(feature = "opcode-stats")]
+    pub fn reset_opcode_stats(&mut self) {
+        self.interpreter.reset_opcode_stats();
+    }
+
+    /// The opcode and native call counters as a readable table
+    ///
+    /// See [`Interpreter::opcode_stats_report`] for the layout and how to
+    /// read it.
+    #[cfg(feature = "opcode-stats")]
+    pub fn opcode_stats_report(&self) -> String {
+        self.interpreter.opcode_stats_report()
+    }
+
+    /// Keep up to `max_entries` compiled scripts so [`Context::eval`] of a
+    /// source seen before skips compilation; 0 (the default) disables caching
+    ///
+    /// Cached scripts look up globals when they run, so they see the
+    /// current environment rather than the one at first compile. Compiler
+    /// diagnostics are reported only when a source is first compiled.
+    pub fn set_compile_cache_size(&mut self, max_entries: usize) {
+        self.compile_cache.resize(max_entries);
+    }
+
+    /// Forget every cached script
+    pub fn clear_compile_cache(&mut self) {
+        self.compile_cache.clear();
+    }
 
-        FunctionBytecode {
-            name: None,
-            arg_count: compiled.arg_count as u16,
-            local_count: compiled.local_count as u16,
-            stack_size: 64, // Default stack size
-            has_arguments: false,
-            bytecode: compiled.bytecode,
-            constants: compiled.constants,
-            string_constants: compiled.string_constants,
-            source_file: None,
-            line_numbers: Vec::new(),
-            inner_functions,
-            captures,
+    /// Hit and miss counters of the compile cache
+    pub fn compile_cache_stats(&self) -> CompileCacheStats {
+        CompileCacheStats {
+            entries: self.compile_cache.entries.len(),
+            hits: self.compile_cache.hits,
+            misses: self.compile_cache.misses,
         }
     }
 
-    /// Compile JavaScript source code without executing
+    /// Evaluate source that must be a single expression and return its value
+    ///
+    /// Statements such as `var x = 1` are rejected with a compile error.
+    pub fn eval_expression(&mut self, source: &str) -> Result<Value, EvalError> {
+        let compiled = self.compiler(source).compile_expression()?;
+        self.run_compiled(compiled)
+    }
+
+    /// Evaluate a script, then keep firing its timers until none are left
+    /// or `timeout` has elapsed on the context's clock
+    ///
+    /// The deadline also interrupts long-running script or callback code.
+    /// Timers not yet due at the deadline stay pending for a later call.
+    pub fn eval_with_timeout(
+        &mut self,
+        source: &str,
+        timeout: Duration,
+    ) -> Result<EvalSummary, EvalError> {
+        let compiled = self.compile_script(source)?;
+        let bytecode = FunctionBytecode::from_compiled(compiled);
+
+        let clock = self.interpreter.clock();
+        let deadline = clock
+            .now_ms()
+            .saturating_add(timeout.as_millis().min(u64::MAX as u128) as u64);
+        let mut summary = EvalSummary {
+            result: Value::undefined(),
+            timers_fired: 0,
+            timed_out: false,
+        };
+        self.interpreter.set_deadline(Some(deadline));
+        let result = self.drive_timers(bytecode, deadline, &*clock, &mut summary);
+        self.interpreter.set_deadline(None);
+
+        match result {
+            Ok(()) => Ok(summary),
+            Err(InterpreterError::Interrupted) => {
+                summary.timed_out = true;
+                Ok(summary)
+            }
+            Err(e) => Err(EvalError::RuntimeError(e.to_string())),
+        }
+    }
+
+    /// Run a script and then its due timers until drained or `deadline`,
+    /// recording progress in `summary`
+    fn drive_timers(
+        &mut self,
+        bytecode: FunctionBytecode,
+        deadline: u64,
+        clock: &dyn Clock,
+        summary: &mut EvalSummary,
+    ) -> InterpreterResult<()> {
+        summary.result = self.interpreter.execute_script(bytecode)?;
+
+        while let Some(due) = self.interpreter.next_timer_due() {
+            let now = clock.now_ms();
+            if now >= deadline || due >= deadline {
+                // Nothing more can fire in time; use up the window
+                if now < deadline {
+                    clock.sleep_ms(deadline - now);
+                }
+                summary.timed_out = true;
+                return Ok(());
+            }
+            if due > now {
+                clock.sleep_ms(due - now);
+                continue;
+            }
+            if let Some(result) = self.interpreter.run_next_timer(now) {
+                summary.timers_fired += 1;
+                if let Err(err) = result {
+                    self.report_unhandled(err)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compile `source` and prepare it to run in slices
     ///
-    /// Returns the compiled bytecode for inspection or later execution.
-    pub fn compile(&self, source: &str) -> Result<FunctionBytecode, CompileError> {
-        let compiled = Compiler::new(source).compile()?;
-        Ok(Self::compiled_to_bytecode(compiled))
+    /// Nothing runs until [`Execution::run_for`] is called. For a host on
+    /// a cooperative scheduler that must not let one script hold the CPU:
+    ///
+    /// ```
+    /// use mquickjs::embed::{Context, StepResult};
+    ///
+    /// let mut ctx = Context::new(64 * 1024);
+    /// let mut execution = ctx
+    ///     .start("var s = 0; for (var i = 0; i < 1000; i = i + 1) { s = s + i; } s;")
+    ///     .unwrap();
+    /// let result = loop {
+    ///     match execution.run_for(500) {
+    ///         StepResult::Completed(value) => break value,
+    ///         // Let other tasks run before the next slice
+    ///         StepResult::Yielded => continue,
+    ///         StepResult::Failed(err) => panic!("{}", err),
+    ///     }
+    /// };
+    /// assert_eq!(result.to_i32(), Some(499500));
+    /// ```
+    pub fn start(&mut self, source: &str) -> Result<Execution<'_>, EvalError> {
+        let compiled = self.compile_script(source)?;
+        let script = self
+            .interpreter
+            .load_script(FunctionBytecode::from_compiled(compiled));
+        let run = self
+            .interpreter
+            .start_sliced(script)
+            .map_err(|e| EvalError::RuntimeError(e.to_string()))?;
+        Ok(Execution {
+            ctx: self,
+            run: Some(run),
+            result: None,
+            outcome: None,
+        })
+    }
+
+    /// Describe an error that ended a sliced run
+    fn js_error(&mut self, err: InterpreterError) -> JsError {
+        let value = if matches!(err, InterpreterError::InternalError(_))
+            && let Some(exception) = self.interpreter.take_uncaught_exception()
+        {
+            self.current_exception = exception;
+            exception
+        } else if let Some((name, message)) = err.js_error_parts() {
+            self.interpreter.alloc_error(name, message)
+        } else {
+            self.interpreter
+                .alloc_error("InternalError", &err.to_string())
+        };
+        let (name, message) = self.interpreter.describe_exception(value);
+        JsError {
+            value,
+            name,
+            message,
+        }
+    }
+
+    /// Pass an exception no timer callback handler caught to the unhandled
+    /// error hook, or return it when no hook is set or scripts could not
+    /// have caught it
+    fn report_unhandled(&mut self, err: InterpreterError) -> InterpreterResult<()> {
+        let Some(hook) = self.unhandled_error_hook.as_mut() else {
+            return Err(err);
+        };
+        let value = if let Some((name, message)) = err.js_error_parts() {
+            self.interpreter.alloc_error(name, message)
+        } else if matches!(err, InterpreterError::InternalError(_))
+            && let Some(exception) = self.interpreter.take_uncaught_exception()
+        {
+            exception
+        } else {
+            return Err(err);
+        };
+        let (name, message) = self.interpreter.describe_exception(value);
+        hook(JsError {
+            value,
+            name,
+            message,
+        });
+        Ok(())
+    }
+
+    /// Report exceptions thrown by timer callbacks run from
+    /// [`Context::eval_with_timeout`] to `hook` and keep running the
+    /// remaining timers
+    ///
+    /// Without a hook the first such exception ends the call with an error.
+    pub fn set_unhandled_error_hook(&mut self, hook: Option<UnhandledErrorHook>) {
+        self.unhandled_error_hook = hook;
+    }
+
+    /// Replace the clock used by timers and [`Context::eval_with_timeout`]
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.interpreter.set_clock(clock);
     }
 
-    /// Execute pre-compiled bytecode
-    pub fn execute(&mut self, bytecode: &FunctionBytecode) -> Result<Value, EvalError> {
+    /// Number of timers waiting to fire
+    pub fn pending_timers(&self) -> usize {
+        self.interpreter.pending_timers()
+    }
+
+    /// Create a promise for work the host finishes later, and the handle
+    /// that settles it
+    ///
+    /// Natives make one with [`Interpreter::create_pending_promise`] and
+    /// return the promise at once. Settling through the handle only queues
+    /// the outcome; the script's reactions run on the next
+    /// [`Context::run_pending_jobs`].
+    pub fn create_pending(&mut self) -> (Value, ResolverHandle) {
+        self.interpreter.create_pending_promise()
+    }
+
+    /// Settle promises whose handles reported an outcome and run the
+    /// promise reactions that are ready, including ones they queue
+    ///
+    /// Returns how many reactions ran.
+    pub fn run_pending_jobs(&mut self) -> Result<usize, EvalError> {
         self.interpreter
-            .execute(bytecode)
+            .run_pending_jobs()
             .map_err(|e| EvalError::RuntimeError(e.to_string()))
     }
 
+    /// Start recording what scripts observe from outside: host native
+    /// results, clock readings and `Math.random` values
+    ///
+    /// Builtins other than the clock and random sources are deterministic
+    /// and are not logged. Collect the log with [`Context::take_replay_log`].
+    pub fn record(&mut self) {
+        self.interpreter.start_recording();
+    }
+
+    /// Feed a recorded log back to the same scripts instead of calling host
+    /// natives or reading the clock
+    ///
+    /// When a script asks for a different input than the log holds next,
+    /// evaluation fails with an error naming the entry index and both
+    /// inputs; scripts cannot catch it.
+    pub fn replay(&mut self, log: ReplayLog) {
+        self.interpreter.start_replay(log);
+    }
+
+    /// Stop recording or replaying and return the log
+    pub fn take_replay_log(&mut self) -> Option<ReplayLog> {
+        self.interpreter.take_replay_log()
+    }
+
+    /// Execute compiled top-level code
+    fn run_compiled(&mut self, compiled: CompiledFunction) -> Result<Value, EvalError> {
+        // The interpreter keeps the bytecode alive for closures and globals
+        let function = FunctionBytecode::from_compiled(compiled);
+        self.execute(Script { function })
+    }
+
+    /// Compile JavaScript source code without executing
+    ///
+    /// Returns the compiled script for inspection or later execution,
+    /// together with any warnings the compiler found.
+    pub fn compile(&self, source: &str) -> Result<(Script, Vec<Diagnostic>), CompileError> {
+        let (compiled, diagnostics) = self.compiler(source).compile_with_diagnostics()?;
+        let function = FunctionBytecode::from_compiled(compiled);
+        Ok((Script { function }, diagnostics))
+    }
+
+    /// Compiler for `source` with this context's settings
+    fn compiler<'s>(&self, source: &'s str) -> Compiler<'s> {
+        self.interpreter.compiler(source)
+    }
+
+    /// Compile a script to run, passing its diagnostics to the handler
+    fn compile_script(&mut self, source: &str) -> Result<CompiledFunction, CompileError> {
+        let (compiled, diagnostics) = self.compiler(source).compile_with_diagnostics()?;
+        if let Some(handler) = &mut self.diagnostic_handler {
+            diagnostics.iter().for_each(handler);
+        }
+        Ok(compiled)
+    }
+
+    /// Execute a pre-compiled script
+    ///
+    /// The context takes ownership of the script, like one passed to
+    /// [`eval`](Self::eval), so functions and strings it publishes as
+    /// globals stay valid after it returns.
+    pub fn execute(&mut self, script: Script) -> Result<Value, EvalError> {
+        self.interpreter
+            .execute_script(script.function)
+            .map_err(|e| {
+                if let Some(exception) = self.interpreter.take_uncaught_exception() {
+                    self.current_exception = exception;
+                }
+                EvalError::RuntimeError(e.to_string())
+            })
+    }
+
     /// Run the garbage collector
+    ///
+    /// Also reclaims unreachable userdata and array storage and runs
+    /// userdata finalizers. Values held only by the host are not roots;
+    /// keep them alive with [`Context::persist`] or publish them with
+    /// `set_global`.
     pub fn gc(&mut self) {
         self.heap.collect();
+        self.interpreter.collect_garbage();
+    }
+
+    /// Statistics gathered by the interpreter's collector
+    pub fn gc_stats(&self) -> GcStats {
+        self.interpreter.gc_stats()
+    }
+
+    /// Install a callback invoked at the start and end of each collection
+    pub fn set_gc_callback(&mut self, callback: GcCallback) {
+        self.interpreter.set_gc_callback(callback);
     }
 
     /// Get memory usage statistics
     pub fn memory_stats(&self) -> MemoryStats {
         let interp_stats = self.interpreter.get_stats();
+        let used = self.interpreter.memory_used();
         MemoryStats {
             heap_size: self.heap.total_size,
-            used: self.heap.heap_used(),
-            stack_used: self.heap.stack_used(),
-            free: self.heap.free_space(),
+            used,
+            stack_used: self.interpreter.stack_bytes(),
+            free: self.heap.total_size.saturating_sub(used),
             runtime_strings: interp_stats.runtime_strings,
             arrays: interp_stats.arrays,
             objects: interp_stats.objects,
@@ -200,63 +965,417 @@ impl Context {
         }
     }
 
-    /// Get the current exception (if any)
-    pub fn get_exception(&self) -> Value {
-        self.current_exception
+    /// Get interpreter object-table statistics
+    pub fn interpreter_stats(&self) -> InterpreterStats {
+        self.interpreter.get_stats()
     }
 
-    /// Clear the current exception
-    pub fn clear_exception(&mut self) {
-        self.current_exception = Value::undefined();
+    /// Restart the stack high-water marks in [`InterpreterStats`]
+    pub fn reset_watermarks(&mut self) {
+        self.interpreter.reset_watermarks();
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[test]
-    fn test_create_context() {
-        let ctx = Context::new(64 * 1024);
-        let stats = ctx.memory_stats();
-        assert!(stats.heap_size >= 64 * 1024);
+    /// Look up a global binding left behind by an earlier `eval`
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.interpreter.get_global(name)
     }
 
-    #[test]
-    fn test_eval_empty() {
-        let mut ctx = Context::new(64 * 1024);
-        let result = ctx.eval("").unwrap();
-        assert!(result.is_undefined());
+    /// Create or update a global binding visible to later `eval` calls
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.interpreter.set_global(name, value);
     }
 
-    #[test]
-    fn test_eval_literal() {
-        let mut ctx = Context::new(64 * 1024);
+    /// Expose a native function to scripts as a global
+    pub fn register_function(&mut self, name: &'static str, func: HostFn, arity: u8) {
+        let idx = self
+            .interpreter
+            .register_host_native(name, func, arity, Receiver::Optional);
+        self.interpreter.set_global(name, Value::native_func(idx));
+    }
 
-        // Test integer literal
-        let result = ctx.eval("42;").unwrap();
-        assert!(result.is_undefined()); // Expression statement drops result
+    /// Register a native function as a global, checking the `this` it is
+    /// called with against `receiver` before it runs
+    ///
+    /// [`Context::register_function`] passes the function whatever it was
+    /// called on; see [`Receiver`] for what each policy guarantees.
+    pub fn register_function_with_receiver(
+        &mut self,
+        name: &'static str,
+        func: HostFn,
+        arity: u8,
+        receiver: Receiver,
+    ) {
+        let idx = self
+            .interpreter
+            .register_host_native(name, func, arity, receiver);
+        self.interpreter.set_global(name, Value::native_func(idx));
+    }
+
+    /// Add a native function scripts can call by name, e.g. when a plugin loads
+    ///
+    /// Unlike [`Context::register_function`] the name need not be static,
+    /// and it must not collide with any registered native, builtins included.
+    pub fn add_native(
+        &mut self,
+        name: String,
+        func: HostFn,
+        arity: u8,
+    ) -> Result<NativeId, RegisterError> {
+        if self.interpreter.get_native_func(&name).is_some() {
+            return Err(RegisterError::DuplicateName(name));
+        }
+        Ok(NativeId(self.interpreter.register_host_native(
+            name,
+            func,
+            arity,
+            Receiver::Optional,
+        )))
+    }
 
-        // Test return statement
-        let result = ctx.eval("return 42;").unwrap();
-        assert_eq!(result.to_i32(), Some(42));
+    /// Remove a native function added with [`Context::add_native`]
+    ///
+    /// The name stops resolving and values still holding the function throw
+    /// a catchable TypeError when called; its slot is never reused, so the
+    /// same name can be added again under a fresh id. Returns false if the
+    /// function was already removed.
+    pub fn remove_native(&mut self, id: NativeId) -> bool {
+        self.interpreter.unregister_native(id.0)
     }
 
-    #[test]
-    fn test_eval_arithmetic() {
-        let mut ctx = Context::new(64 * 1024);
+    /// Register a host class as a global scripts can instantiate with `new`
+    pub fn register_class<T: 'static>(&mut self, name: &'static str, def: ClassDef<T>) {
+        let class = self.interpreter.register_class(name, def);
+        self.interpreter.set_global(name, class);
+    }
 
-        let result = ctx.eval("return 2 + 3;").unwrap();
-        assert_eq!(result.to_i32(), Some(5));
+    /// Register a module of native functions as a global object
+    ///
+    /// Lazy entries are only registered the first time a script reads
+    /// them (see [`ModuleBuilder`]).
+    pub fn register_module(&mut self, def: ModuleBuilder) {
+        let name = def.name;
+        let module = self.interpreter.register_module(def);
+        self.interpreter.set_global(name, module);
+    }
 
-        let result = ctx.eval("return 10 - 4;").unwrap();
-        assert_eq!(result.to_i32(), Some(6));
+    /// Wrap a host value in an object scripts can pass around
+    pub fn create_userdata<T: 'static>(&mut self, value: T) -> Value {
+        self.interpreter.create_userdata(value, None)
+    }
 
-        let result = ctx.eval("return 3 * 4;").unwrap();
-        assert_eq!(result.to_i32(), Some(12));
+    /// Wrap a host value, handing it to `finalizer` once scripts drop it
+    ///
+    /// The finalizer runs exactly once, during the first [`Context::gc`]
+    /// after the object stops being reachable from the VM.
+    pub fn create_userdata_with_finalizer<T: 'static>(
+        &mut self,
+        value: T,
+        finalizer: Box<dyn FnOnce(T)>,
+    ) -> Value {
+        self.interpreter.create_userdata(value, Some(finalizer))
+    }
 
-        let result = ctx.eval("return 20 / 5;").unwrap();
-        assert_eq!(result.to_i32(), Some(4));
+    /// Borrow the host value wrapped by a userdata object
+    pub fn get_userdata<T: 'static>(&self, value: Value) -> Option<&T> {
+        self.interpreter.get_userdata(value)
+    }
+
+    /// Keep `value`, such as an object returned by [`Context::eval`], alive
+    /// until the returned handle is dropped
+    ///
+    /// ```
+    /// use mquickjs::embed::Context;
+    ///
+    /// let mut ctx = Context::new(64 * 1024);
+    /// let list = ctx.eval("[1, 2, 3];").unwrap();
+    /// let list = ctx.persist(list);
+    /// ctx.eval("var other = [4, 5];").unwrap();
+    /// ctx.gc();
+    /// assert_eq!(ctx.get_array(list.value()).map(|items| items.len()), Some(3));
+    /// ```
+    pub fn persist(&mut self, value: Value) -> PersistentValue {
+        self.interpreter.persist(value)
+    }
+
+    /// Make a weak handle that does not keep `value` alive
+    pub fn downgrade(&self, value: Value) -> WeakValue {
+        WeakValue {
+            value,
+            generation: self.interpreter.slot_generation(value),
+        }
+    }
+
+    /// Contents of a string value, or None if `value` is not a string
+    pub fn get_string(&self, value: Value) -> Option<JsStr<'_>> {
+        self.interpreter.get_string(value)
+    }
+
+    /// Copy of an array value's elements, or None if `value` is not an array
+    ///
+    /// Holes are copied as undefined.
+    pub fn get_array(&self, value: Value) -> Option<Vec<Value>> {
+        let len = self.interpreter.array_len(value)?;
+        Some(
+            (0..len)
+                .filter_map(|i| self.interpreter.array_element_at(value, i))
+                .collect(),
+        )
+    }
+
+    /// Render a value the way `console.log` would print it
+    pub fn inspect(&self, value: Value) -> String {
+        self.interpreter.format_value(value)
+    }
+
+    /// Build the value a JSON document describes, without compiling or
+    /// running any script
+    ///
+    /// `options` can relax strict JSON for hand-written config files. The
+    /// value is not rooted: store it in a global to keep it alive.
+    ///
+    /// ```
+    /// use mquickjs::embed::{Context, JsonOptions};
+    ///
+    /// let mut ctx = Context::new(64 * 1024);
+    /// let options = JsonOptions { allow_comments: true, allow_trailing_commas: true };
+    /// let config = ctx.parse_json("{ \"port\": 8080, // default\n }", options).unwrap();
+    /// ctx.set_global("config", config);
+    /// assert_eq!(ctx.eval("config.port;").unwrap().to_i32(), Some(8080));
+    /// ```
+    pub fn parse_json(&mut self, text: &str, options: JsonOptions) -> Result<Value, JsonError> {
+        self.interpreter.parse_json(text, options)
+    }
+
+    /// Serialize a value with the same rules as `JSON.stringify`
+    ///
+    /// With `pretty` set, nested lines are indented by two spaces.
+    pub fn to_json(&mut self, value: Value, pretty: bool) -> Result<String, JsonError> {
+        self.interpreter.to_json(value, pretty)
+    }
+
+    /// Evaluate a script and deserialize its completion value into `T`
+    ///
+    /// Arrays become sequences and plain objects become maps or structs;
+    /// see [`JsValueRef`](crate::embed::JsValueRef) for what converts.
+    #[cfg(feature = "serde")]
+    pub fn eval_into<T: serde::de::DeserializeOwned>(
+        &mut self,
+        source: &str,
+    ) -> Result<T, crate::serde_interop::SerdeError> {
+        let value = self
+            .eval(source)
+            .map_err(crate::serde_interop::SerdeError::Eval)?;
+        T::deserialize(self.value_ref(value))
+    }
+
+    /// Build the script value `data` serializes to
+    ///
+    /// Structs and maps become plain objects, sequences become arrays.
+    /// The value is not rooted: store it in a global to keep it alive.
+    #[cfg(feature = "serde")]
+    pub fn value_from_serde<T: serde::Serialize + ?Sized>(
+        &mut self,
+        data: &T,
+    ) -> Result<Value, crate::serde_interop::SerdeError> {
+        crate::serde_interop::to_value(&mut self.interpreter, data)
+    }
+
+    /// Borrow a value for serializing or deserializing with serde
+    #[cfg(feature = "serde")]
+    pub fn value_ref(&self, value: Value) -> crate::serde_interop::JsValueRef<'_> {
+        crate::serde_interop::JsValueRef::new(&self.interpreter, value)
+    }
+
+    /// Capture console output with a callback instead of printing it
+    ///
+    /// Pass `None` to send output back to stdout/stderr.
+    pub fn set_console_sink(&mut self, sink: Option<ConsoleSink>) {
+        self.interpreter.set_console_sink(sink);
+    }
+
+    /// Receive compiler diagnostics for every script passed to `eval`
+    ///
+    /// Pass `None` to stop reporting them.
+    pub fn set_diagnostic_handler(&mut self, handler: Option<DiagnosticHandler>) {
+        self.diagnostic_handler = handler;
+    }
+
+    /// Limit how deeply scripts may nest expressions, statements, array
+    /// literals and functions
+    ///
+    /// Deeper input fails to compile with
+    /// [`CompileError::TooDeeplyNested`]
+    /// rather than exhausting the native stack, which matters for untrusted
+    /// scripts. Raise it only as far as the host thread's stack allows.
+    pub fn set_max_nesting_depth(&mut self, limit: usize) {
+        self.interpreter.set_max_nesting_depth(limit);
+        // Cached scripts were compiled under the old limit
+        self.compile_cache.clear();
+    }
+
+    /// Get the current exception (if any)
+    pub fn get_exception(&self) -> Value {
+        self.current_exception
+    }
+
+    /// Clear the current exception
+    pub fn clear_exception(&mut self) {
+        self.current_exception = Value::undefined();
+    }
+}
+
+impl Execution<'_> {
+    /// Run for at most about `max_instructions` bytecode instructions
+    ///
+    /// After the script finishes, timers that are due on the context's
+    /// clock fire within the same budget, one callback at a time; timers
+    /// not yet due stay pending (see [`Context::pending_timers`]).
+    /// Exceptions from timer callbacks go to the unhandled error hook when
+    /// one is set. Once the execution has completed or failed, further
+    /// calls return the same outcome.
+    ///
+    /// The budget is only checked in the script's own code: callbacks run
+    /// by natives such as `Array.prototype.map` finish before the script
+    /// pauses, so a slice can overrun by what they execute.
+    pub fn run_for(&mut self, max_instructions: u64) -> StepResult {
+        if let Some(outcome) = &self.outcome {
+            return outcome.clone();
+        }
+        let mut budget = max_instructions;
+        loop {
+            if let Some(run) = self.run.as_mut() {
+                match self.ctx.interpreter.run_slice(run, &mut budget) {
+                    Ok(None) => return StepResult::Yielded,
+                    Ok(Some(value)) => {
+                        self.run = None;
+                        self.result.get_or_insert(value);
+                    }
+                    Err(err) => {
+                        self.run = None;
+                        let in_timer = self.result.is_some();
+                        if let Err(err) = self.report(err, in_timer) {
+                            return self.finish(StepResult::Failed(err));
+                        }
+                    }
+                }
+            }
+
+            let now = self.ctx.interpreter.clock().now_ms();
+            match self.ctx.interpreter.start_next_timer_sliced(now) {
+                Some(Ok(run)) => self.run = Some(run),
+                Some(Err(err)) => {
+                    if let Err(err) = self.report(err, true) {
+                        return self.finish(StepResult::Failed(err));
+                    }
+                }
+                None => {
+                    let value = self.result.unwrap_or_default();
+                    return self.finish(StepResult::Completed(value));
+                }
+            }
+        }
+    }
+
+    /// Whether the execution has completed or failed
+    pub fn is_finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// The context, for other work between slices
+    ///
+    /// Scripts evaluated through it run to completion on top of the paused
+    /// one and see the globals it has published so far.
+    pub fn context(&mut self) -> &mut Context {
+        self.ctx
+    }
+
+    /// Hand a timer callback's error to the unhandled error hook, or turn
+    /// an error that ends the execution into a [`JsError`]
+    fn report(&mut self, err: InterpreterError, in_timer: bool) -> Result<(), JsError> {
+        let err = if in_timer {
+            match self.ctx.report_unhandled(err) {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            }
+        } else {
+            err
+        };
+        Err(self.ctx.js_error(err))
+    }
+
+    /// Remember the outcome that ends the execution
+    fn finish(&mut self, outcome: StepResult) -> StepResult {
+        if let Some(run) = self.run.take() {
+            self.ctx.interpreter.abandon_slice(run);
+        }
+        self.outcome = Some(outcome.clone());
+        outcome
+    }
+}
+
+impl Drop for Execution<'_> {
+    fn drop(&mut self) {
+        if let Some(run) = self.run.take() {
+            self.ctx.interpreter.abandon_slice(run);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::compiler::DEFAULT_MAX_NESTING_DEPTH;
+    use crate::vm::{HostError, NativeFn, NativeScope};
+
+    /// Register a global native that reaches interpreter internals host
+    /// functions cannot see through their scope
+    fn register_builtin(ctx: &mut Context, name: &'static str, func: NativeFn, arity: u8) {
+        let idx = ctx.interpreter.register_native(name, func, arity);
+        ctx.interpreter.set_global(name, Value::native_func(idx));
+    }
+
+    #[test]
+    fn test_create_context() {
+        let ctx = Context::new(64 * 1024);
+        let stats = ctx.memory_stats();
+        assert!(stats.heap_size >= 64 * 1024);
+    }
+
+    #[test]
+    fn test_eval_empty() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx.eval("").unwrap();
+        assert!(result.is_undefined());
+    }
+
+    #[test]
+    fn test_eval_literal() {
+        let mut ctx = Context::new(64 * 1024);
+
+        // Test integer literal
+        let result = ctx.eval("42;").unwrap();
+        assert_eq!(result.to_i32(), Some(42)); // Completion value of the script
+
+        // Test return statement
+        let result = ctx.eval("return 42;").unwrap();
+        assert_eq!(result.to_i32(), Some(42));
+    }
+
+    #[test]
+    fn test_eval_arithmetic() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx.eval("return 2 + 3;").unwrap();
+        assert_eq!(result.to_i32(), Some(5));
+
+        let result = ctx.eval("return 10 - 4;").unwrap();
+        assert_eq!(result.to_i32(), Some(6));
+
+        let result = ctx.eval("return 3 * 4;").unwrap();
+        assert_eq!(result.to_i32(), Some(12));
+
+        let result = ctx.eval("return 20 / 5;").unwrap();
+        assert_eq!(result.to_i32(), Some(4));
     }
 
     #[test]
@@ -1917,6 +3036,106 @@ mod tests {
         assert_eq!(result.to_i32(), Some(9)); // 1+3+5=9
     }
 
+    #[test]
+    fn test_for_in_predeclared_variable() {
+        let mut ctx = Context::new(64 * 1024);
+
+        // Back-to-back loops over different objects share one `k`, which
+        // keeps the last key after each loop
+        let result = ctx
+            .eval(
+                "
+            var k;
+            var keys = [];
+            for (k in {a: 1, b: 2}) { keys.push(k); }
+            keys.push(k);
+            for (k in {c: 3}) { keys.push(k); }
+            keys.push(k);
+            return keys.join(',');
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.get_string(result).as_deref(), Some("a,b,b,c,c"));
+
+        // An undeclared target is assigned as a global
+        let result = ctx.eval("for (g of [1, 2, 3]) { } return g;").unwrap();
+        assert_eq!(result.to_i32(), Some(3));
+    }
+
+    #[test]
+    fn test_for_var_is_shared_by_consecutive_loops() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            function keys() {
+                var out = [];
+                for (var k in {a: 1}) { out.push(k); }
+                for (var k in {b: 2, c: 3}) { out.push(k); }
+                for (var i = 0; i < 2; i = i + 1) { out.push(i); }
+                for (var i = 5; i < 6; i = i + 1) { out.push(i); }
+                out.push(k);
+                out.push(i);
+                return out.join(',');
+            }
+            return keys();
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.get_string(result).as_deref(), Some("a,b,c,0,1,5,c,6"));
+    }
+
+    #[test]
+    fn test_for_multiple_declarators() {
+        let mut ctx = Context::new(64 * 1024);
+
+        // The cached-length idiom
+        let result = ctx
+            .eval(
+                "
+            var arr = [3, 4, 5];
+            var sum = 0;
+            for (var i = 0, n = arr.length; i < n; i++) {
+                sum = sum + arr[i] * i;
+            }
+            return sum * 100 + i * 10 + n;
+        ",
+            )
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(1433));
+
+        // Each initializer sees the declarators before it
+        let result = ctx
+            .eval(
+                "var x = 2, y = x * 3, z; let p = y + 1, q = p; return [x, y, z, p, q].join(',');",
+            )
+            .unwrap();
+        assert_eq!(ctx.get_string(result).as_deref(), Some("2,6,,7,7"));
+    }
+
+    #[test]
+    fn test_for_in_iterator_is_not_a_visible_local() {
+        let mut ctx = Context::new(64 * 1024);
+
+        // Locals declared after a loop never see the hidden iterator, and
+        // the loop variable itself only ever holds keys
+        let result = ctx
+            .eval(
+                "
+            function after() {
+                for (var k in {a: 1, b: 2}) { }
+                for (let v of [1]) { }
+                let fresh;
+                return typeof fresh + ',' + k;
+            }
+            return after();
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.get_string(result).as_deref(), Some("undefined,b"));
+    }
+
     // =========================================================================
     // Native function tests
     // =========================================================================
@@ -2037,6 +3256,26 @@ mod tests {
         // Math.min with negative values
         let result = ctx.eval("return Math.min(-10, -5);").unwrap();
         assert_eq!(result.to_i32(), Some(-10));
+
+        // Arguments go through ToNumber
+        let result = ctx.eval("return Math.max(\"3\", 5);").unwrap();
+        assert_eq!(result.to_i32(), Some(5));
+        let result = ctx.eval("return Math.min(\"3\", 5, true);").unwrap();
+        assert_eq!(result.to_i32(), Some(1));
+        let result = ctx.eval("return Math.max(\"12\", \"9\");").unwrap();
+        assert_eq!(result.to_i32(), Some(12));
+
+        // No arguments: -Infinity/+Infinity, saturated until infinities exist
+        let result = ctx.eval("return Math.max();").unwrap();
+        assert_eq!(result.to_i32(), Some(i32::MIN));
+        let result = ctx.eval("return Math.min();").unwrap();
+        assert_eq!(result.to_i32(), Some(i32::MAX));
+
+        // The rounding functions coerce too
+        let result = ctx
+            .eval("return Math.floor(\"7\") + Math.sign(\"-4\");")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(6));
     }
 
     #[test]
@@ -2327,6 +3566,12 @@ mod tests {
             )
             .unwrap();
         assert_eq!(result.to_i32(), Some(1));
+
+        // Several values keep their argument order
+        let result = ctx
+            .eval("var arr = [4, 5]; var len = arr.unshift(1, 2, 3); [len, arr];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[5, [1, 2, 3, 4, 5]]");
     }
 
     #[test]
@@ -2356,6 +3601,34 @@ mod tests {
         assert_eq!(result.to_i32(), Some(-1));
     }
 
+    #[test]
+    fn test_array_search_strings_and_from_index() {
+        let mut ctx = Context::new(64 * 1024);
+
+        // A runtime string matches an equal literal element
+        let result = ctx
+            .eval(
+                "
+            var key = \"b\" + \"\";
+            return [\"a\", \"b\"].indexOf(key);
+        ",
+            )
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(1));
+
+        // fromIndex skips the first match; lastIndexOf counts back from a negative start
+        let result = ctx
+            .eval(
+                "
+            var arr = [7, 8, 7, 8];
+            return [arr.indexOf(7, 1), arr.indexOf(7, 10), arr.lastIndexOf(8, -2),
+                    arr.lastIndexOf(7, -10), arr.includes(8, -1)];
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[2, -1, 1, -1, true]");
+    }
+
     #[test]
     fn test_array_join() {
         let mut ctx = Context::new(64 * 1024);
@@ -2373,6 +3646,22 @@ mod tests {
         assert!(result.is_string());
     }
 
+    #[test]
+    fn test_array_join_separator_and_cycles() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            var a = [\"x\", [1, 2], null];
+            a.push(a);
+            return [a.join(\"\"), a.join(\" - \"), a.join()];
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[x1,2, x - 1,2 -  - , x,1,2,,]");
+    }
+
     #[test]
     fn test_array_reverse() {
         let mut ctx = Context::new(64 * 1024);
@@ -2747,6 +4036,9 @@ mod tests {
             )
             .unwrap();
         assert_eq!(result.to_i32(), Some(3));
+
+        let result = ctx.eval("\"5\".padStart(4, \"ab\");").unwrap();
+        assert_eq!(ctx.inspect(result), "aba5");
     }
 
     #[test]
@@ -3290,6 +4582,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_date_now_positive() {
         let mut ctx = Context::new(64 * 1024);
 
@@ -3549,6 +4842,38 @@ mod tests {
         assert_eq!(result.to_i32(), Some(-1));
     }
 
+    #[test]
+    fn test_regexp_unsupported_constructs_throw_syntax_errors() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval(
+                "var caught = [];
+                 try { new RegExp(\"(?<=a)b\"); } catch (e) { caught.push(e.name, e.message); }
+                 try { \"abc\".match(\"(b\"); } catch (e) { caught.push(e.name); }
+                 caught;",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[SyntaxError, Invalid regular expression: /(?<=a)b/: lookbehind is not supported, \
+             SyntaxError]"
+        );
+
+        // Backreferences need the backtracking engine
+        let result = ctx.eval(
+            "var m = new RegExp(\"(\\\\w+) \\\\1\").exec(\"say it it again\"); [m[0], m[1], m.index];",
+        );
+        #[cfg(feature = "regex-backend")]
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("backreferences are not supported")
+        );
+        #[cfg(not(feature = "regex-backend"))]
+        assert_eq!(ctx.inspect(result.unwrap()), "[it it, it, 4]");
+    }
+
     // mquickjs-specific String methods
     #[test]
     fn test_string_code_point_at() {
@@ -3957,6 +5282,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::manual_range_contains)]
     fn test_math_random() {
         let mut ctx = Context::new(64 * 1024);
 
@@ -4087,45 +5413,122 @@ mod tests {
     }
 
     #[test]
-    fn test_array_is_array_true() {
+    fn test_global_this_writes_are_global_bindings() {
         let mut ctx = Context::new(64 * 1024);
 
         let result = ctx
             .eval(
-                "
-            var arr = [1, 2, 3];
-            return Array.isArray(arr) ? 1 : 0;
-        ",
+                "globalThis.x = 1;
+                 var y = 1;
+                 globalThis['y'] = 2;
+                 function bump() { globalThis.x = x + 1; }
+                 bump();
+                 var set = Reflect.set(globalThis, 'z', 3);
+                 [x, y, globalThis.x, globalThis.y, set, z];",
             )
             .unwrap();
-        assert_eq!(result.to_i32(), Some(1));
+        assert_eq!(ctx.inspect(result), "[2, 2, 2, 2, true, 3]");
+
+        // The bindings outlive the script that wrote them
+        let result = ctx.eval("[x, y, z];").unwrap();
+        assert_eq!(ctx.inspect(result), "[2, 2, 3]");
+        assert_eq!(ctx.get_global("x").and_then(|v| v.to_i32()), Some(2));
     }
 
     #[test]
-    fn test_array_is_array_false() {
+    fn test_global_this_probes_the_global_environment() {
+        fn noop(_: &mut NativeScope, _: Value, _: &[Value]) -> Result<Value, HostError> {
+            Ok(Value::undefined())
+        }
         let mut ctx = Context::new(64 * 1024);
+        ctx.register_function("gpio", noop, 0);
+        ctx.add_native("fetch".to_string(), noop, 1).unwrap();
+        ctx.eval("var config = 1; var unset;").unwrap();
 
         let result = ctx
             .eval(
-                "
-            function Obj() { this.x = 1; }
-            var obj = new Obj();
-            return Array.isArray(obj) ? 1 : 0;
-        ",
+                "[\"gpio\" in globalThis, \"fetch\" in globalThis, \"config\" in globalThis,
+                  \"unset\" in globalThis, \"Math\" in globalThis, \"missing\" in globalThis,
+                  \"Math.abs\" in globalThis];",
             )
             .unwrap();
-        assert_eq!(result.to_i32(), Some(0));
-    }
-
-    #[test]
-    fn test_array_is_array_number() {
-        let mut ctx = Context::new(64 * 1024);
+        assert_eq!(
+            ctx.inspect(result),
+            "[true, true, true, true, true, false, false]"
+        );
 
         let result = ctx
             .eval(
-                "
-            return Array.isArray(42) ? 1 : 0;
-        ",
+                "[typeof globalThis.gpio, typeof globalThis.fetch, typeof globalThis.config,
+                  typeof globalThis.JSON, typeof globalThis.missing, globalThis.config];",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[function, function, number, object, undefined, 1]"
+        );
+
+        let result = ctx
+            .eval(
+                "var names = []; for (var name in globalThis) { names.push(name); }
+                 [names.indexOf(\"config\") >= 0, names.indexOf(\"gpio\") >= 0,
+                  names.indexOf(\"Math\"), Object.keys(globalThis).indexOf(\"config\") >= 0];",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[true, true, -1, true]");
+
+        // A script sees its own top-level bindings before it finishes
+        let result = ctx
+            .eval(
+                "var gv = 1; function hello() {}
+                 var seen = []; for (var key in globalThis) { seen.push(key); }
+                 [\"gv\" in globalThis, globalThis.gv, typeof globalThis.hello,
+                  seen.indexOf(\"gv\") >= 0, seen.indexOf(\"hello\") >= 0];",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[true, 1, function, true, true]");
+    }
+
+    #[test]
+    fn test_array_is_array_true() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            var arr = [1, 2, 3];
+            return Array.isArray(arr) ? 1 : 0;
+        ",
+            )
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(1));
+    }
+
+    #[test]
+    fn test_array_is_array_false() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            function Obj() { this.x = 1; }
+            var obj = new Obj();
+            return Array.isArray(obj) ? 1 : 0;
+        ",
+            )
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(0));
+    }
+
+    #[test]
+    fn test_array_is_array_number() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            return Array.isArray(42) ? 1 : 0;
+        ",
             )
             .unwrap();
         assert_eq!(result.to_i32(), Some(0));
@@ -4737,6 +6140,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_uint8_clamped_array() {
         let mut ctx = Context::new(64 * 1024);
 
@@ -4928,6 +6332,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_load_function() {
         let mut ctx = Context::new(64 * 1024);
 
@@ -4971,4 +6376,4067 @@ mod tests {
             .unwrap();
         assert!(result.is_string());
     }
+
+    #[test]
+    fn test_globals_persist_across_evals() {
+        let mut ctx = Context::new(64 * 1024);
+
+        ctx.eval("var x = 40; function f() { return 2; }").unwrap();
+        let result = ctx.eval("return x + f();").unwrap();
+        assert_eq!(result.to_i32(), Some(42));
+
+        // Assigning to an undeclared name creates a global
+        ctx.eval("y = 5;").unwrap();
+        let result = ctx.eval("++y; return y;").unwrap();
+        assert_eq!(result.to_i32(), Some(6));
+        assert_eq!(ctx.get_global("y").and_then(|v| v.to_i32()), Some(6));
+    }
+
+    #[test]
+    fn test_global_strings_persist_across_evals() {
+        let mut ctx = Context::new(64 * 1024);
+
+        ctx.eval("var greeting = \"hello\";").unwrap();
+        ctx.eval("var other = \"world\";").unwrap();
+        let result = ctx.eval("return greeting;").unwrap();
+        assert_eq!(ctx.inspect(result), "hello");
+        let result = ctx.eval("return greeting + \" \" + other;").unwrap();
+        assert_eq!(ctx.inspect(result), "hello world");
+    }
+
+    #[test]
+    fn test_eval_recovers_after_uncaught_error() {
+        let mut ctx = Context::new(64 * 1024);
+
+        assert!(ctx.eval("function g() { throw 1; } g();").is_err());
+        let result = ctx.eval("return 7;").unwrap();
+        assert_eq!(result.to_i32(), Some(7));
+    }
+
+    #[test]
+    fn test_console_sink_captures_output() {
+        use crate::vm::ConsoleLevel;
+        use alloc::rc::Rc;
+        use core::cell::RefCell;
+
+        let mut ctx = Context::new(64 * 1024);
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&lines);
+        ctx.set_console_sink(Some(Box::new(move |level, line: &str| {
+            sink.borrow_mut().push((level, line.to_string()));
+        })));
+
+        ctx.eval("console.log(1, \"a\"); console.error(\"oops\"); print(3);")
+            .unwrap();
+        assert_eq!(
+            *lines.borrow(),
+            vec![
+                (ConsoleLevel::Log, "1 a".to_string()),
+                (ConsoleLevel::Error, "oops".to_string()),
+                (ConsoleLevel::Log, "3".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_array_named_property() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            var a = [1, 2];
+            a.total = 10;
+            a.total = a.total + 5;
+            return a.total + a.length;
+        ",
+            )
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(17));
+
+        // Named properties don't affect JSON serialization
+        let result = ctx
+            .eval("var a = [1, 2]; a.extra = 3; return JSON.stringify(a);")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[1,2]");
+    }
+
+    #[test]
+    fn test_array_named_property_for_in_and_keys() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            var a = [7, 8];
+            a.name = 1;
+            var keys = \"\";
+            for (var k in a) {
+                keys = keys + k + \",\";
+            }
+            return keys;
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "0,1,name,");
+
+        let result = ctx
+            .eval("var a = [7]; a.tag = 1; return Object.keys(a).length;")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(2));
+    }
+
+    #[test]
+    fn test_array_named_property_delete_and_in() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            var a = [1];
+            a.flag = true;
+            var before = \"flag\" in a;
+            var deleted = delete a.flag;
+            var after = \"flag\" in a;
+            return [before, deleted, after, a.flag];
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[true, true, false, undefined]");
+
+        let result = ctx
+            .eval("var a = [1, 2]; return [\"length\" in a, \"1\" in a, \"2\" in a];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[true, true, false]");
+    }
+
+    #[test]
+    fn test_array_length_assignment() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval("var a = [1, 2, 3]; a.length = 1; a.push(9); return a[1] + a.length;")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(11));
+    }
+
+    #[test]
+    fn test_array_push_call_on_array_like() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            function ArrayLike() {
+                this.length = 0;
+                return this;
+            }
+            var o = new ArrayLike();
+            var n = Array.prototype.push.call(o, 5, 6);
+            return [n, o.length, Array.prototype.indexOf.call(o, 6)];
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[2, 2, 1]");
+    }
+
+    #[test]
+    fn test_array_like_huge_length_throws() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                r#"
+            var errors = [];
+            try { Array.prototype.indexOf.call({ length: 2000000000 }, 5); }
+            catch (e) { errors.push(e.name + ": " + e.message); }
+            try { Array.prototype.join.call({ length: 100000000 }); }
+            catch (e) { errors.push(e.name + ": " + e.message); }
+            var o = { length: 1073741823 };
+            try { Array.prototype.push.call(o, 1); }
+            catch (e) { errors.push(e.name + ": " + e.message); }
+            [errors.join("; "), o.length];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[RangeError: Invalid array length; RangeError: out of memory; \
+             RangeError: Invalid array length, 1073741823]"
+        );
+    }
+
+    #[test]
+    fn test_string_slice_call() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval("return String.prototype.slice.call(\"hello\", 1);")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "ello");
+
+        // Non-string receivers are converted to strings
+        let result = ctx
+            .eval("return String.prototype.indexOf.call(12345, \"34\");")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(2));
+    }
+
+    #[test]
+    fn test_array_join_call_on_array_like_with_holes() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            function ArrayLike() {
+                this.length = 0;
+                return this;
+            }
+            var o = new ArrayLike();
+            Array.prototype.push.call(o, 1);
+            o.length = 3;
+            Array.prototype.push.call(o, 4);
+            return Array.prototype.join.call(o);
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "1,,,4");
+
+        let result = ctx
+            .eval(
+                "
+            function ArrayLike() {
+                this.length = 2;
+                return this;
+            }
+            return Array.prototype.slice.call(new ArrayLike()).length;
+        ",
+            )
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(2));
+    }
+
+    #[test]
+    fn test_native_apply_and_bind() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx.eval("return Math.max.apply(null, [3, 9, 4]);").unwrap();
+        assert_eq!(result.to_i32(), Some(9));
+
+        let result = ctx
+            .eval(
+                "
+            var a = [1, 2];
+            var pushToA = Array.prototype.push.bind(a, 7);
+            pushToA(8);
+            return a;
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[1, 2, 7, 8]");
+    }
+
+    #[test]
+    fn test_number_to_string_radix() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx.eval("return (-255).toString(16);").unwrap();
+        assert_eq!(ctx.inspect(result), "-ff");
+        let result = ctx.eval("return (1295).toString(36);").unwrap();
+        assert_eq!(ctx.inspect(result), "zz");
+        let result = ctx.eval("return (-5).toString(2);").unwrap();
+        assert_eq!(ctx.inspect(result), "-101");
+        let result = ctx.eval("return (0).toString(7);").unwrap();
+        assert_eq!(ctx.inspect(result), "0");
+
+        let err = ctx.eval("return (10).toString(1);").unwrap_err();
+        assert!(err.to_string().contains("RangeError"));
+        let err = ctx.eval("return (10).toString(37);").unwrap_err();
+        assert!(err.to_string().contains("RangeError"));
+    }
+
+    #[test]
+    fn test_number_parses_prefixed_strings() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx.eval("return Number(\" 0x10 \");").unwrap();
+        assert_eq!(result.to_i32(), Some(16));
+        let result = ctx
+            .eval("return Number(\"0b101\") + Number(\"0o17\");")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(20));
+        let result = ctx.eval("return Number(\"  -42\\n\");").unwrap();
+        assert_eq!(result.to_i32(), Some(-42));
+
+        // Without NaN, unparsable strings currently convert to 0
+        let result = ctx.eval("return Number(\"12px\");").unwrap();
+        assert_eq!(result.to_i32(), Some(0));
+        let result = ctx.eval("return Number(\"0x-1\");").unwrap();
+        assert_eq!(result.to_i32(), Some(0));
+    }
+
+    #[test]
+    fn test_date_iso_round_trip() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval("return new Date(\"2024-06-01T12:30:00+02:00\").toISOString();")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "2024-06-01T10:30:00.000Z");
+        let result = ctx
+            .eval("var d = new Date(\"2024-06-01T07:00:00-05:30\"); return new Date(d.toISOString()).toString();")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "Sat Jun 01 2024 12:30:00 GMT+0000");
+        // Time numbers are epoch seconds, rounded down
+        let result = ctx
+            .eval("return Date.parse(\"1970-01-01T00:00:01.250+00:00\");")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(1));
+        let result = ctx
+            .eval("return Date.parse(\"2024-06-01T12:30:00Z\");")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(1_717_245_000));
+        let result = ctx
+            .eval("return new Date(1717245000).toISOString();")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "2024-06-01T12:30:00.000Z");
+
+        // Beyond 2038 the seconds no longer fit an integer value
+        let err = ctx
+            .eval("return Date.parse(\"2040-01-01T00:00:00Z\");")
+            .unwrap_err();
+        assert!(err.to_string().contains("RangeError"), "{}", err);
+    }
+
+    #[test]
+    fn test_date_now_round_trips_through_constructor() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "var now = Date.now(); var d = new Date(now);
+                 [d.getTime() === now, +d === now, d.getFullYear() >= 2024, new Date() - d >= 0];",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[true, true, true, true]");
+    }
+
+    #[test]
+    fn test_date_invalid() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval("var d = new Date(\"June 1\"); return [d.toString(), d.getTime(), JSON.stringify(d)];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[Invalid Date, 0, null]");
+        let err = ctx
+            .eval("return new Date(\"x\").toISOString();")
+            .unwrap_err();
+        assert!(err.to_string().contains("RangeError"), "{}", err);
+    }
+
+    #[test]
+    fn test_json_stringify_calls_to_json() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "function P(x) { this.x = x; }
+                 function tj() { return \"P\" + this.x; }
+                 var p = new P(1);
+                 p.toJSON = tj;
+                 return JSON.stringify([p, new P(2), new Date(\"2000-01-01\")]);",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[\"P1\",{\"x\":2},\"2000-01-01T00:00:00.000Z\"]"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_performance_mark_measure() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            var before = performance.now();
+            performance.mark(\"start\");
+            var sum = 0;
+            for (var i = 0; i < 20000; ++i) { sum += i; }
+            performance.mark(\"end\");
+            performance.measure(\"loop\", \"start\", \"end\");
+            var entries = performance.getEntriesByName(\"loop\");
+            var e = entries[0];
+            var marks = performance.getEntriesByName(\"start\", \"mark\");
+            return [entries.length, e.name, e.entryType, e.duration > 0,
+                    e.startTime >= before, performance.now() >= e.startTime + e.duration,
+                    marks.length, marks[0].duration,
+                    performance.getEntriesByName(\"start\", \"measure\").length];
+        ",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[1, loop, measure, true, true, true, 1, 0, 0]"
+        );
+
+        let err = ctx
+            .eval("performance.measure(\"m\", \"missing\");")
+            .unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_performance_entries_are_charged_and_cleared() {
+        let mut ctx = Context::new(64 * 1024);
+
+        // Marks count against the budget like any other allocation
+        let err = ctx
+            .eval("for (var n = 0; n < 100000; n = n + 1) { performance.mark('m' + n); }")
+            .unwrap_err();
+        assert!(err.to_string().contains("out of memory"), "{}", err);
+
+        let result = ctx
+            .eval(
+                "performance.clearMarks();
+                 performance.mark('again');
+                 performance.measure('a');
+                 performance.measure('b');
+                 performance.clearMeasures('a');
+                 [performance.getEntriesByName('m0').length,
+                  performance.getEntriesByName('again').length,
+                  performance.getEntriesByName('a').length,
+                  performance.getEntriesByName('b').length];",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[0, 1, 0, 1]");
+    }
+
+    #[test]
+    fn test_userdata_finalizer_runs_once_when_unreachable() {
+        use alloc::rc::Rc;
+        use core::cell::RefCell;
+
+        let closed = Rc::new(RefCell::new(Vec::new()));
+        let mut ctx = Context::new(64 * 1024);
+
+        let log = Rc::clone(&closed);
+        let handle = ctx.create_userdata_with_finalizer(
+            7u32,
+            Box::new(move |fd: u32| log.borrow_mut().push(fd)),
+        );
+        ctx.set_global("file", handle);
+        let weak = ctx.downgrade(handle);
+        assert_eq!(ctx.get_userdata::<u32>(handle), Some(&7));
+
+        // Still reachable from a global and then from an array
+        ctx.gc();
+        assert!(closed.borrow().is_empty());
+        ctx.eval("var keep = [1, [file]]; file = 0;").unwrap();
+        ctx.gc();
+        assert!(closed.borrow().is_empty());
+        assert_eq!(weak.upgrade(&ctx), Some(handle));
+
+        // Dropping the last reference lets the finalizer run, exactly once
+        ctx.eval("keep = null;").unwrap();
+        ctx.gc();
+        ctx.gc();
+        assert_eq!(*closed.borrow(), vec![7]);
+        assert!(weak.upgrade(&ctx).is_none());
+        assert_eq!(ctx.get_userdata::<u32>(handle), None);
+    }
+
+    #[test]
+    fn test_userdata_reachable_through_closures_and_objects() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        let finalized = Rc::new(Cell::new(0));
+        let mut ctx = Context::new(64 * 1024);
+
+        let count = Rc::clone(&finalized);
+        let res = ctx.create_userdata_with_finalizer(
+            "socket",
+            Box::new(move |_: &str| count.set(count.get() + 1)),
+        );
+        ctx.set_global("res", res);
+        ctx.eval(
+            "function Holder(v) { this.v = v; }
+             function make(v) { function get() { return v; } return get; }
+             var h = new Holder(res);
+             var g = make(res);
+             res = 0;",
+        )
+        .unwrap();
+        ctx.gc();
+        assert_eq!(finalized.get(), 0);
+
+        ctx.eval("h = 0;").unwrap();
+        ctx.gc();
+        assert_eq!(finalized.get(), 0);
+
+        // gc() from script collects too
+        ctx.eval("g = 0; gc();").unwrap();
+        assert_eq!(finalized.get(), 1);
+    }
+
+    #[test]
+    fn test_exception_handlers_do_not_leak() {
+        let mut ctx = Context::new(64 * 1024);
+
+        // Returning from inside try must not leave f's handler installed,
+        // or the caller's throw would jump into f's catch block
+        let result = ctx
+            .eval(
+                "
+            function f(x) { try { return x; } catch (e) { return -1; } }
+            var caught = [];
+            for (var round = 0; round < 20; ++round) {
+                try {
+                    var s = 0;
+                    for (var i = 0; i < 50; ++i) { s += f(1); }
+                    throw s + round;
+                } catch (e) { caught.push(e); }
+            }
+            return [caught.length, caught[0], caught[19]];
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[20, 50, 69]");
+
+        let result = ctx
+            .eval(
+                "
+            var r = [];
+            try {
+                for (var j = 0; j < 3; ++j) {
+                    try { if (j == 1) { break; } r.push(j); } catch (e) { r.push(\"stale\"); }
+                }
+                for (var k = 0; k < 3; ++k) {
+                    try { try { if (k < 2) { continue; } } catch (e) {} r.push(k); } catch (e) {}
+                }
+                throw \"outer\";
+            } catch (e) { r.push(e); }
+            return r;
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[0, 2, outer]");
+    }
+
+    #[test]
+    fn test_string_search_and_match_coerce_strings() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval("return [\"hello\".search(\"l\"), \"hello\".search(\"z\"), \"axb\".search(\"a.b\")];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[2, -1, 0]");
+
+        let result = ctx
+            .eval("var m = \"say hello\".match(\"l+\"); return [m[0], m.length, m.index, m.input];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[ll, 1, 6, say hello]");
+        let result = ctx.eval("return \"abc\".match(\"z\");").unwrap();
+        assert!(result.is_null());
+
+        let result = ctx
+            .eval("var g = \"k=v\".match(new RegExp(\"(\\\\w)=(\\\\w)\")); return [g, g.index];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[[k=v, k, v], 0]");
+        let result = ctx
+            .eval("return \"a1b22c333\".match(new RegExp(\"[0-9]+\", \"g\"));")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[1, 22, 333]");
+        let result = ctx
+            .eval("return \"abc\".match(new RegExp(\"[0-9]\", \"g\"));")
+            .unwrap();
+        assert!(result.is_null());
+
+        // Indices count UTF-16 code units, like indexOf
+        let result = ctx
+            .eval("var s = \"éa😀b\"; return [s.search(\"b\"), s.indexOf(\"b\"), s.match(\"a\").index, new RegExp(\"b\").exec(s).index];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[4, 4, 1, 4]");
+    }
+
+    #[test]
+    fn test_new_target() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            function Foo(x) {
+                if (new.target === undefined) { return new Foo(x); }
+                this.x = x;
+                this.viaNew = new.target === Foo;
+            }
+            function plain() { return new.target; }
+            var a = Foo(1);
+            var b = new Foo(2);
+            return [a.x, a.viaNew, b.x, b.viaNew, a instanceof Foo, plain()];
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[1, true, 2, true, true, undefined]");
+    }
+
+    #[test]
+    fn test_native_builder_api() {
+        fn host_stats(
+            scope: &mut NativeScope,
+            _this: Value,
+            args: &[Value],
+        ) -> Result<Value, HostError> {
+            let tags = vec![scope.alloc_string("fast"), scope.alloc_string("small")];
+            let tags = scope.alloc_array(tags);
+            let inner = scope
+                .alloc_object()
+                .prop("depth", Value::int(2))
+                .prop("tags", tags)
+                .finish();
+            let warning = scope.alloc_error("RangeError", "too many");
+            Ok(scope
+                .alloc_object()
+                .prop("ok", Value::bool(true))
+                .prop("count", Value::int(args.len() as i32))
+                .prop_str("name", "stats")
+                .prop("inner", inner)
+                .prop("warning", warning)
+                .finish())
+        }
+
+        let mut ctx = Context::new(64 * 1024);
+        ctx.register_function("hostStats", host_stats, 0);
+
+        let result = ctx
+            .eval(
+                "
+            var s = hostStats(1, 2, 3);
+            return [s.ok, s.count, s.name, s.inner.depth, s.inner.tags[1],
+                    s.warning.message, JSON.stringify(s.inner)];
+        ",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[true, 3, stats, 2, small, too many, {\"depth\":2,\"tags\":[\"fast\",\"small\"]}]"
+        );
+    }
+
+    #[test]
+    fn test_executed_bytecode_globals_outlive_it() {
+        let mut ctx = Context::new(64 * 1024);
+        {
+            let (script, _) = ctx
+                .compile("var label = 'hello world'; function f() { return label.length; }")
+                .unwrap();
+            ctx.execute(script).unwrap();
+        }
+        // The context owns the script, so its globals survive the scope
+        let result = ctx.eval("return label;").unwrap();
+        assert_eq!(ctx.get_string(result).as_deref(), Some("hello world"));
+        let result = ctx.eval("return f();").unwrap();
+        assert_eq!(result.to_i32(), Some(11));
+    }
+
+    #[test]
+    fn test_compiled_bytecode_validates() {
+        let ctx = Context::new(64 * 1024);
+        let (script, _) = ctx
+            .compile(
+                "
+            function outer(n) {
+                var total = 0;
+                function add(x) { total = total + x; }
+                for (var i = 0; i < n; ++i) {
+                    if (i % 2 == 0) { continue; }
+                    try { add(i); } catch (e) { break; } finally { total = total + 1; }
+                }
+                while (total > 100) { total = total - 100; }
+                return total;
+            }
+            var parts = [1, 2, 3];
+            for (var p of parts) { outer(p); }
+            return outer(10);
+        ",
+            )
+            .unwrap();
+        assert_eq!(script.function.validate(), Ok(()));
+        let listing = script.disassemble();
+        // Top level, outer and its nested add
+        assert_eq!(listing.matches("function ").count(), 3);
+        assert!(listing.contains("IfFalse"));
+    }
+
+    #[test]
+    fn test_string_index_accessors() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval(
+                "
+            var s = \"x\" + String.fromCodePoint(128512);
+            return [s.charAt(-1), s.charAt(3), s.charCodeAt(2), s.codePointAt(1),
+                    s.codePointAt(3), s.at(-3), s.at(-4)];
+        ",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[, , 56832, 128512, undefined, x, undefined]"
+        );
+    }
+
+    #[test]
+    fn test_strings_from_code_units_and_indices() {
+        let mut ctx = Context::new(256 * 1024);
+
+        // Pairs join, lone surrogates become U+FFFD, slices cut pairs
+        let result = ctx
+            .eval(
+                "
+            var s = String.fromCharCode(20013, 55357, 56832, 55357, 120);
+            return [s, s.slice(1, 3), s.substring(2), s.indexOf('x'), s.includes('x', 4),
+                    JSON.parse('\"\\\\uD83D\\\\uDE00\\\\uDC00\"'), String.fromCodePoint(55296)];
+        ",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[中😀\u{FFFD}x, 😀, \u{FFFD}\u{FFFD}x, 4, true, 😀\u{FFFD}, \u{FFFD}]"
+        );
+
+        // Indices from search and match slice the same text back out
+        let result = ctx
+            .eval("var t = \"中😀 key=v\"; return [t.slice(t.search(\"key\")), t.slice(t.match(\"=\").index)];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[key=v, =v]");
+
+        // Random code units and indices, negative and past the end, never
+        // fail and always give strings of the expected length
+        let mut state = 0x9e37_79b9_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        for _ in 0..300 {
+            let units: Vec<u32> = (0..next() % 6)
+                .map(|_| match next() % 3 {
+                    0 => 0xD800 + next() % 0x800,
+                    1 => next() % 0x80,
+                    _ => next() % 0x10000,
+                })
+                .collect();
+            let len = units.len() as i64;
+            let (a, b) = ((next() % 16) as i64 - 8, (next() % 16) as i64 - 8);
+            let list =
+                |f: &dyn Fn(&u32) -> String| units.iter().map(f).collect::<Vec<_>>().join(", ");
+            let script = format!(
+                "var s = String.fromCharCode({}); var j = JSON.parse('\"{}\"');
+                 [s.length, j.length, s.slice({a}, {b}).length, s.substring({a}, {b}).length,
+                  s.charAt({a}).length, s.startsWith(s.substring({a}), {a}), s.endsWith('', {b})];",
+                list(&|u| u.to_string()),
+                list(&|u| format!("\\\\u{:04x}", u)).replace(", ", ""),
+            );
+            let result = ctx
+                .eval(&script)
+                .unwrap_or_else(|e| panic!("{}: {}", script, e));
+
+            let slice_at = |i: i64| if i < 0 { (len + i).max(0) } else { i.min(len) };
+            let slice_len = (slice_at(b) - slice_at(a)).max(0);
+            let substring_len = (a.clamp(0, len) - b.clamp(0, len)).abs();
+            let char_len = i64::from((0..len).contains(&a));
+            let expected =
+                format!("[{len}, {len}, {slice_len}, {substring_len}, {char_len}, true, true]");
+            assert_eq!(ctx.inspect(result), expected, "{}", script);
+        }
+    }
+
+    #[test]
+    fn test_eval_completion_value() {
+        let mut ctx = Context::new(64 * 1024);
+
+        // The last expression statement is the script's value
+        let result = ctx.eval("var a = 1; var b = 2; a + b;").unwrap();
+        assert_eq!(result.to_i32(), Some(3));
+
+        // Assignments update globals before the value is returned
+        let result = ctx.eval("var c = 0; c = 7;").unwrap();
+        assert_eq!(result.to_i32(), Some(7));
+        assert_eq!(ctx.eval("c;").unwrap().to_i32(), Some(7));
+
+        // Declarations and loops complete with undefined
+        assert!(ctx.eval("1; var d = 5;").unwrap().is_undefined());
+        assert!(ctx.eval("2; while (false) { 3; }").unwrap().is_undefined());
+        assert!(
+            ctx.eval("function f() { return 1; }")
+                .unwrap()
+                .is_undefined()
+        );
+    }
+
+    #[test]
+    fn test_eval_expression() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval("var x = 40;").unwrap();
+
+        assert_eq!(ctx.eval_expression("x + 2").unwrap().to_i32(), Some(42));
+        assert_eq!(ctx.eval_expression("x * 2;").unwrap().to_i32(), Some(80));
+
+        assert!(matches!(
+            ctx.eval_expression("var y = 1"),
+            Err(EvalError::CompileError(_))
+        ));
+        assert!(matches!(
+            ctx.eval_expression("1; 2"),
+            Err(EvalError::CompileError(_))
+        ));
+    }
+
+    #[test]
+    fn test_arithmetic_errors_are_catchable() {
+        let mut ctx = Context::new(64 * 1024);
+
+        let result = ctx
+            .eval("var r = 0; try { r = 10 / 0; } catch (e) { r = e.name; } r;")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "RangeError");
+
+        let result = ctx
+            .eval("var t = 0; try { t = \"a\" - 1; } catch (e) { t = e.name; } t;")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "TypeError");
+
+        // The interpreter keeps working after a caught arithmetic error
+        let result = ctx.eval("var n = 20; n / 4 + 1;").unwrap();
+        assert_eq!(result.to_i32(), Some(6));
+
+        // Uncaught arithmetic errors still fail the eval
+        assert!(ctx.eval("1 / 0;").is_err());
+        assert_eq!(ctx.eval("2 + 3;").unwrap().to_i32(), Some(5));
+    }
+
+    #[test]
+    fn test_extracted_method_receiver_errors() {
+        let mut ctx = Context::new(64 * 1024);
+
+        // Uncaught: the message names the method and the receiver type
+        let err = ctx.eval("var push = [].push; push(1);").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Array.prototype.push called on undefined"),
+            "{}",
+            err
+        );
+
+        // String methods reject null and undefined before they run
+        let result = ctx
+            .eval(
+                "var out = []; \
+                 try { \"\".trim.call(null); } catch (e) { out.push(e.message); } \
+                 try { \"\".padStart.call(undefined, 2); } catch (e) { out.push(e.message); } \
+                 out;",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[String.prototype.trim called on null, expected a value other than null or undefined, \
+             String.prototype.padStart called on undefined, expected a value other than null or undefined]"
+        );
+
+        // Caught: the error is a TypeError object
+        let result = ctx
+            .eval("var n = 0; try { n.toFixed.call(\"s\", 1); } catch (e) { n = e.name; } n;")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "TypeError");
+
+        // A real receiver via call works
+        let result = ctx.eval("var a = [1]; a.push.call(a, 2); a;").unwrap();
+        assert_eq!(ctx.inspect(result), "[1, 2]");
+    }
+
+    #[test]
+    fn test_nested_namespace_method_calls() {
+        let mut ctx = Context::new(64 * 1024);
+
+        ctx.eval(
+            "
+            var app = {
+                utils: {
+                    unit: \"px\",
+                    clamp: function (v, lo, hi) {
+                        if (typeof v !== \"number\") { return lo; }
+                        if (v < lo) { return lo; }
+                        if (v > hi) { return hi; }
+                        return v;
+                    },
+                    describe: function (v) { return this.clamp(v, 0, 3) + this.unit + \" (clamped)\"; }
+                }
+            };
+        ",
+        )
+        .unwrap();
+
+        // Called from a later script: `this` is the innermost object and the
+        // string literals inside the function still resolve
+        let result = ctx.eval("app.utils.clamp(5, 0, 3);").unwrap();
+        assert_eq!(result.to_i32(), Some(3));
+        let result = ctx.eval("app.utils.describe(5);").unwrap();
+        assert_eq!(ctx.inspect(result), "3px (clamped)");
+        let result = ctx.eval("app.utils.clamp(\"5\", 1, 3);").unwrap();
+        assert_eq!(result.to_i32(), Some(1));
+    }
+
+    #[test]
+    fn test_function_expressions() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval(
+                "
+            var double = function (x) { return x * 2; };
+            var fact = function fac(n) { return n <= 1 ? 1 : n * fac(n - 1); };
+            function adder(k) { return function (x) { return x + k; }; }
+            var add3 = adder(3);
+            [double(4), fact(5), add3(1), [1, 2].map(function (x) { return x * 10; }).join(),
+             (function () { return \"called\"; })()];
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[8, 120, 4, 10,20, called]");
+
+        // The name of a named function expression is not declared outside it
+        let err = ctx.eval("fac(1);").unwrap_err();
+        assert!(err.to_string().contains("fac is not defined"), "{}", err);
+    }
+
+    #[test]
+    fn test_value_classification_matrix() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval(
+            "
+            function F() { return this; }
+            var brand = Object.prototype.toString;
+            function classify(v) {
+                return typeof v + \" \" + Array.isArray(v) + \" \" + brand.call(v);
+            }
+        ",
+        )
+        .unwrap();
+
+        // (sample value, typeof, Array.isArray, Object.prototype.toString.call)
+        let matrix = [
+            ("undefined", "undefined", false, "[object Undefined]"),
+            ("null", "object", false, "[object Null]"),
+            ("true", "boolean", false, "[object Boolean]"),
+            ("42", "number", false, "[object Number]"),
+            ("\"s\"", "string", false, "[object String]"),
+            ("[1, 2]", "object", true, "[object Array]"),
+            ("new F()", "object", false, "[object Object]"),
+            ("F", "function", false, "[object Function]"),
+            ("Math.max", "function", false, "[object Function]"),
+            ("F.bind(null)", "function", false, "[object Function]"),
+            ("Array", "function", false, "[object Function]"),
+            ("Math", "object", false, "[object Math]"),
+            ("new TypeError(\"x\")", "object", false, "[object Error]"),
+            ("new RegExp(\"a\")", "object", false, "[object RegExp]"),
+            ("new Date(0)", "object", false, "[object Date]"),
+            (
+                "new ArrayBuffer(4)",
+                "object",
+                false,
+                "[object ArrayBuffer]",
+            ),
+            ("new Int8Array(1)", "object", false, "[object Int8Array]"),
+            ("new Uint8Array(1)", "object", false, "[object Uint8Array]"),
+            (
+                "new Uint8ClampedArray(1)",
+                "object",
+                false,
+                "[object Uint8ClampedArray]",
+            ),
+            ("new Int16Array(1)", "object", false, "[object Int16Array]"),
+            (
+                "new Uint16Array(1)",
+                "object",
+                false,
+                "[object Uint16Array]",
+            ),
+            ("new Int32Array(1)", "object", false, "[object Int32Array]"),
+            (
+                "new Uint32Array(1)",
+                "object",
+                false,
+                "[object Uint32Array]",
+            ),
+            (
+                "new Float32Array(1)",
+                "object",
+                false,
+                "[object Float32Array]",
+            ),
+            (
+                "new Float64Array(1)",
+                "object",
+                false,
+                "[object Float64Array]",
+            ),
+        ];
+        for (sample, type_of, is_array, brand) in matrix {
+            let result = ctx.eval(&format!("classify({});", sample)).unwrap();
+            assert_eq!(
+                ctx.inspect(result),
+                format!("{} {} {}", type_of, is_array, brand),
+                "classification of {}",
+                sample
+            );
+        }
+    }
+
+    /// Clock whose time moves when code sleeps on it, plus `step` ms on
+    /// every read to simulate time spent computing
+    struct SimulatedClock {
+        now: core::cell::Cell<u64>,
+        step: u64,
+    }
+
+    impl SimulatedClock {
+        fn new(start: u64, step: u64) -> Rc<Self> {
+            Rc::new(SimulatedClock {
+                now: core::cell::Cell::new(start),
+                step,
+            })
+        }
+    }
+
+    impl Clock for SimulatedClock {
+        fn now_ms(&self) -> u64 {
+            let now = self.now.get();
+            self.now.set(now + self.step);
+            now
+        }
+
+        fn sleep_ms(&self, ms: u64) {
+            self.now.set(self.now.get() + ms);
+        }
+    }
+
+    #[test]
+    fn test_eval_with_timeout_fires_due_timers() {
+        let clock = SimulatedClock::new(1000, 0);
+        let mut ctx = Context::new(64 * 1024);
+        ctx.set_clock(clock.clone());
+
+        // Each tick schedules the next one 100ms later
+        let summary = ctx
+            .eval_with_timeout(
+                "
+                var ticks = [];
+                function tick() {
+                    ticks.push(ticks.length + 1);
+                    setTimeout(tick, 100);
+                }
+                setTimeout(tick, 100);
+                ticks.length;
+            ",
+                Duration::from_millis(350),
+            )
+            .unwrap();
+        assert_eq!(summary.result.to_i32(), Some(0));
+        assert_eq!(summary.timers_fired, 3); // at 1100, 1200 and 1300
+        assert!(summary.timed_out);
+        assert_eq!(clock.now_ms(), 1350);
+        assert_eq!(ctx.pending_timers(), 1);
+        assert_eq!(ctx.eval("ticks.length;").unwrap().to_i32(), Some(3));
+
+        // The pending tick (due at 1400) fires on the next call
+        let summary = ctx
+            .eval_with_timeout("ticks.length;", Duration::from_millis(100))
+            .unwrap();
+        assert_eq!(summary.result.to_i32(), Some(3));
+        assert_eq!(summary.timers_fired, 1);
+        assert!(summary.timed_out);
+        assert_eq!(ctx.eval("ticks.length;").unwrap().to_i32(), Some(4));
+    }
+
+    #[test]
+    fn test_eval_with_timeout_drains_and_interrupts() {
+        let clock = SimulatedClock::new(0, 0);
+        let mut ctx = Context::new(64 * 1024);
+        ctx.set_clock(clock.clone());
+
+        // A single timer drains before the deadline
+        let summary = ctx
+            .eval_with_timeout(
+                "var done = 0; function finish() { done = 1; } setTimeout(finish, 10); 5;",
+                Duration::from_millis(1000),
+            )
+            .unwrap();
+        assert_eq!(summary.result.to_i32(), Some(5));
+        assert_eq!(summary.timers_fired, 1);
+        assert!(!summary.timed_out);
+        assert_eq!(clock.now_ms(), 10);
+
+        // A busy-looping callback is cut off at the deadline; every clock
+        // read now costs a millisecond
+        ctx.set_clock(SimulatedClock::new(0, 1));
+        let summary = ctx
+            .eval_with_timeout(
+                "
+                function spin() {
+                    var n = 0;
+                    while (true) { n = n + 1; }
+                }
+                setTimeout(spin, 0);
+            ",
+                Duration::from_millis(50),
+            )
+            .unwrap();
+        assert_eq!(summary.timers_fired, 1);
+        assert!(summary.timed_out);
+        assert_eq!(ctx.pending_timers(), 0);
+
+        // The context keeps working afterwards
+        assert_eq!(ctx.eval("1 + 1;").unwrap().to_i32(), Some(2));
+    }
+
+    #[test]
+    fn test_typed_array_allocation_limits() {
+        let mut ctx = Context::new(64 * 1024);
+
+        // Absurd lengths throw a catchable RangeError instead of aborting
+        for ctor in ["Float64Array", "Uint8Array", "Int32Array"] {
+            let result = ctx
+                .eval(&format!(
+                    "var r = 0; try {{ new {}(2147483647); }} catch (e) {{ r = e.name; }} r;",
+                    ctor
+                ))
+                .unwrap();
+            assert_eq!(ctx.inspect(result), "RangeError", "{}", ctor);
+        }
+        let result = ctx
+            .eval("var b = 0; try { new ArrayBuffer(2147483647); } catch (e) { b = e.name; } b;")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "RangeError");
+
+        // Negative lengths are rejected too
+        let err = ctx.eval("new Uint8Array(-1);").unwrap_err();
+        assert!(
+            err.to_string().contains("invalid typed array length"),
+            "{}",
+            err
+        );
+
+        // Allocations count against the context's memory budget
+        assert!(ctx.eval("var a = new Uint8Array(40000); a.length;").is_ok());
+        let err = ctx.eval("new Uint8Array(40000);").unwrap_err();
+        assert!(err.to_string().contains("RangeError"), "{}", err);
+
+        // Small allocations still succeed and the context stays usable
+        let result = ctx
+            .eval("var t = new Int16Array(4); t[1] = 7; t[1];")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(7));
+    }
+
+    #[test]
+    fn test_compile_reports_diagnostics() {
+        use crate::parser::diagnostic::DiagnosticKind;
+        use core::cell::RefCell;
+
+        let ctx = Context::new(64 * 1024);
+        let (_, diagnostics) = ctx
+            .compile("function f() {\n  var x;\n  return 1;\n  x = 2;\n}\nf();")
+            .unwrap();
+        let found: Vec<_> = diagnostics.iter().map(|d| (d.kind, d.pos.line)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (DiagnosticKind::UnreachableCode, 4),
+                (DiagnosticKind::UnusedVariable, 2),
+            ]
+        );
+        assert!(ctx.compile("var a = 1; a + 1;").unwrap().1.is_empty());
+
+        // The handler sees diagnostics from eval; the script still runs
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&seen);
+        let mut ctx = Context::new(64 * 1024);
+        ctx.set_diagnostic_handler(Some(Box::new(move |d| {
+            sink.borrow_mut().push(d.to_string());
+        })));
+        let result = ctx
+            .eval("function g(n) { if (n = 3) { return n; } return 0; } g(1);")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(3));
+        assert_eq!(seen.borrow().len(), 1);
+        assert!(seen.borrow()[0].starts_with("1:23: warning: assignment used as a condition"));
+    }
+
+    #[test]
+    fn test_console_time_and_table() {
+        use crate::vm::ConsoleLevel;
+        use core::cell::RefCell;
+
+        let mut ctx = Context::new(64 * 1024);
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&lines);
+        ctx.set_console_sink(Some(Box::new(move |level, line: &str| {
+            sink.borrow_mut().push((level, line.to_string()));
+        })));
+
+        ctx.eval("console.time(\"load\"); console.timeEnd(\"load\"); console.timeEnd(\"nope\");")
+            .unwrap();
+        {
+            let lines = lines.borrow();
+            assert_eq!(lines.len(), 2);
+            let (level, line) = &lines[0];
+            assert_eq!(*level, ConsoleLevel::Log);
+            let ms = line
+                .strip_prefix("load: ")
+                .unwrap()
+                .strip_suffix("ms")
+                .unwrap();
+            assert!(ms.parse::<f64>().unwrap() >= 0.0, "{}", line);
+            assert_eq!(
+                lines[1],
+                (
+                    ConsoleLevel::Warn,
+                    "No such label 'nope' for console.timeEnd()".to_string()
+                )
+            );
+        }
+        lines.borrow_mut().clear();
+
+        ctx.eval(
+            "
+            function Row(name) { this.name = name; }
+            var a = new Row(\"alice\");
+            a.age = 30;
+            var b = new Row(\"bob\");
+            b.city = \"a very long city name that gets cut\";
+            console.table([a, b]);
+        ",
+        )
+        .unwrap();
+        let table = lines.borrow()[0].1.clone();
+        assert_eq!(
+            table,
+            [
+                "+---------+-------+-----+--------------------------+",
+                "| (index) | name  | age | city                     |",
+                "+---------+-------+-----+--------------------------+",
+                "| 0       | alice | 30  |                          |",
+                "| 1       | bob   |     | a very long city name... |",
+                "+---------+-------+-----+--------------------------+",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_gc_stats_and_callback() {
+        use crate::vm::GcPhase;
+        use alloc::rc::Rc;
+        use core::cell::RefCell;
+
+        let mut ctx = Context::new(64 * 1024);
+        let phases = Rc::new(RefCell::new(Vec::new()));
+        let seen = phases.clone();
+        ctx.set_gc_callback(Box::new(move |phase| seen.borrow_mut().push(phase)));
+        assert_eq!(ctx.gc_stats().collections, 0);
+
+        let result = ctx
+            .eval(
+                "var big = [];
+                 for (var i = 0; i < 1000; i = i + 1) { big.push(i); }
+                 big = 0;
+                 var r = gc();
+                 [r.bytes_freed, r.collections];",
+            )
+            .unwrap();
+        let items = ctx.get_array(result).unwrap();
+        assert!(items[0].to_i32().unwrap() > 0);
+        assert_eq!(items[1].to_i32(), Some(1));
+
+        let stats = ctx.gc_stats();
+        assert_eq!(stats.collections, 1);
+        assert!(stats.bytes_before > stats.bytes_after);
+        assert_eq!(stats.total_pause_us, stats.last_pause_us);
+        assert_eq!(*phases.borrow(), vec![GcPhase::Start, GcPhase::End]);
+
+        // Reachable arrays survive collection
+        let kept = ctx.eval("var kept = [1, 2, 3]; gc(); kept;").unwrap();
+        assert!(ctx.downgrade(kept).upgrade(&ctx).is_some());
+        assert_eq!(ctx.get_array(kept).map(|a| a.len()), Some(3));
+        assert_eq!(ctx.gc_stats().collections, 2);
+        assert!(ctx.gc_stats().live_objects > 0);
+        assert_eq!(phases.borrow().len(), 4);
+    }
+
+    #[test]
+    fn test_temporary_arrays_reclaimed_per_statement() {
+        let source = "function Bag() { this.a = 1; this.b = 2; this.c = 3; return this; }
+             var o = new Bag();
+             var kept = [];
+             var n = 0;
+             for (var i = 0; i < 10000; i = i + 1) {
+                 for (var k of Object.keys(o)) { n = n + 1; }
+                 KEEP;
+             }
+             n;";
+
+        // Results consumed by for-of never outlive their statement
+        let mut ctx = Context::new(64 * 1024);
+        let before = ctx.interpreter_stats().arrays;
+        let n = ctx.eval(&source.replace("KEEP;", "")).unwrap();
+        assert_eq!(n.to_i32(), Some(30000));
+        let temp_growth = ctx.interpreter_stats().arrays - before;
+        assert!(temp_growth < 10, "grew by {}", temp_growth);
+
+        // Stored results escape and stay intact; every fiftieth one is
+        // kept so the survivors fit the same budget
+        let mut ctx = Context::new(64 * 1024);
+        let before = ctx.interpreter_stats().arrays;
+        let n = ctx
+            .eval(&source.replace("KEEP;", "if (i % 50 == 0) { kept.push(Object.keys(o)); }"))
+            .unwrap();
+        assert_eq!(n.to_i32(), Some(30000));
+        assert!(ctx.interpreter_stats().arrays - before >= 200);
+        let last = ctx.eval("kept[199].join(\",\");").unwrap();
+        assert_eq!(ctx.get_string(last).as_deref(), Some("a,b,c"));
+    }
+
+    #[test]
+    fn test_closure_slots_reused_after_collection() {
+        let mut ctx = Context::new(256 * 1024);
+        ctx.eval("var data = [1, 2, 3];").unwrap();
+
+        // A callback without captures is one value, however often its
+        // declaration is evaluated
+        let before = ctx.interpreter_stats().closures;
+        let result = ctx
+            .eval(
+                "function doubled() { function double(x) { return x * 2; } return data.map(double); }
+                 function one() { function f() { return 1; } return f; }
+                 function other() { function f() { return 1; } return f; }
+                 for (var i = 0; i < 10000; i = i + 1) doubled();
+                 [one() === one(), one() === other(), doubled().join()];",
+            )
+            .unwrap();
+        assert_eq!(ctx.interpreter_stats().closures, before);
+        assert_eq!(ctx.inspect(result), "[true, false, 2,4,6]");
+
+        // Callbacks capturing a variable allocate, but their slots are
+        // reused once collected
+        let run = "function shifted(k) { function add(x) { return x + k; } return data.map(add); }
+                   for (var i = 0; i < 10000; i = i + 1) {
+                       shifted(i);
+                       if (i % 1000 == 0) gc();
+                   }
+                   gc();";
+        ctx.eval(run).unwrap();
+        let settled = ctx.interpreter_stats().closures;
+        assert!(settled < 1000, "kept {} closures", settled);
+        ctx.eval(run).unwrap();
+        assert_eq!(ctx.interpreter_stats().closures, settled);
+
+        // Reachable closures keep their captures across collections
+        let result = ctx
+            .eval(
+                "function adder(n) { function add(x) { return x + n; } return add; }
+                 var add5 = adder(5);
+                 var add7 = adder(7);
+                 for (var i = 0; i < 100; i = i + 1) adder(i);
+                 gc();
+                 var add9 = adder(9);
+                 [add5(1), add7(1), add9(1), data.map(add7).join()];",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[6, 8, 10, 8,9,10]");
+        let add5 = ctx.get_global("add5").unwrap();
+        let dropped = ctx.eval("adder(1);").unwrap();
+        ctx.gc();
+        assert!(ctx.downgrade(add5).upgrade(&ctx).is_some());
+        assert!(ctx.downgrade(dropped).upgrade(&ctx).is_none());
+    }
+
+    #[test]
+    fn test_weak_value_not_revived_by_reused_slot() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval(
+            "function adder(n) { function add(x) { return x + n; } return add; }
+             var first = \"weak\"; var second = \"other\"; var suffix = \" handle\";
+             var kept;",
+        )
+        .unwrap();
+
+        let closure = ctx.eval("adder(1);").unwrap();
+        let weak = ctx.downgrade(closure);
+        assert_eq!(weak.upgrade(&ctx), Some(closure));
+        ctx.gc();
+        assert!(weak.upgrade(&ctx).is_none());
+
+        // The next closure takes over the freed slot
+        let reused = ctx.eval("var add2 = adder(2); add2;").unwrap();
+        assert_eq!(reused, closure);
+        assert!(weak.upgrade(&ctx).is_none());
+        assert_eq!(ctx.downgrade(reused).upgrade(&ctx), Some(reused));
+
+        // Runtime string slots behave the same way
+        let text = ctx.eval("first + suffix;").unwrap();
+        let weak = ctx.downgrade(text);
+        ctx.gc();
+        let other = ctx.eval("kept = second + suffix; kept;").unwrap();
+        assert_eq!(other, text);
+        assert!(weak.upgrade(&ctx).is_none());
+    }
+
+    #[test]
+    fn test_short_strings_stay_out_of_runtime_table() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval(
+            r#"
+            function splitAll(n) {
+                var count = 0;
+                var chars = [];
+                for (var i = 0; i < n; i = i + 1) {
+                    chars = "benchmark".split("");
+                    count = count + chars.length;
+                }
+                return chars.join("") === "benchmark" ? count : -1;
+            }
+            splitAll(1);
+        "#,
+        )
+        .unwrap();
+        let before = ctx.memory_stats().runtime_strings;
+
+        // Every piece is an inline string, so splitting a thousand words
+        // adds nothing to the runtime string table
+        let result = ctx.eval("splitAll(1000);").unwrap();
+        assert_eq!(result.to_i32(), Some(9000));
+        assert_eq!(ctx.memory_stats().runtime_strings, before);
+        ctx.eval("splitAll(1000);").unwrap();
+        assert_eq!(ctx.memory_stats().runtime_strings, before);
+    }
+
+    #[test]
+    fn test_define_property_enumerable_attribute() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval(
+                r#"
+            function Shown() { this.value = 1; this.enumerable = true; return this; }
+            function Reset() { this.value = 3; return this; }
+            function Hide() { this.enumerable = false; return this; }
+            function Thing() { this.a = 0; return this; }
+            var o = new Thing();
+            Object.defineProperty(o, "b", new Shown());
+            Object.defineProperty(o, "a", new Reset());
+            var before = Object.keys(o).join(",");
+            Object.defineProperty(o, "a", new Hide());
+            before + "|" + Object.keys(o).join(",") + "|" + o.a;
+        "#,
+            )
+            .unwrap();
+        assert_eq!(ctx.get_string(result).as_deref(), Some("a,b|b|3"));
+    }
+
+    #[test]
+    fn test_script_metrics() {
+        let ctx = Context::new(64 * 1024);
+        let source = r#"
+            function add(a, b) {
+                function twice(x) { return x * 2; }
+                return twice(a) + b;
+            }
+            var label = "sum";
+            var total = add(1, 2);
+        "#;
+        let (script, _) = ctx.compile(source).unwrap();
+        let metrics = script.metrics();
+        assert_eq!(metrics.inner_functions, 2);
+        assert!(metrics.bytecode_bytes >= script.function.bytecode.len() + 10);
+        assert!(metrics.bytecode_bytes < 200);
+        assert!((2..=8).contains(&metrics.max_locals));
+        assert!((2..=8).contains(&metrics.max_stack_depth));
+        assert!(metrics.string_bytes >= "sum".len());
+
+        let report = script.report();
+        assert!(report.contains("<script>"));
+        assert!(report.contains("\n  add "));
+        assert!(report.contains("\n    twice "));
+        assert!(report.contains("total (3 functions)"));
+
+        // A new literal adds exactly its UTF-8 length to the string bytes
+        let long = "x".repeat(100);
+        let (longer, _) = ctx
+            .compile(&format!("{}var note = \"{}\";", source, long))
+            .unwrap();
+        let grown = longer.metrics();
+        assert_eq!(
+            grown.string_bytes,
+            metrics.string_bytes + "note".len() + 100
+        );
+        assert_eq!(grown.inner_functions, metrics.inner_functions);
+    }
+
+    #[test]
+    fn test_deep_nesting_fails_gracefully() {
+        let mut ctx = Context::new(64 * 1024);
+        let deep = format!("{}1{};", "(".repeat(20_000), ")".repeat(20_000));
+        assert!(matches!(
+            ctx.eval(&deep),
+            Err(EvalError::CompileError(CompileError::TooDeeplyNested))
+        ));
+
+        let shallow = format!("{}1{};", "[".repeat(50), "]".repeat(50));
+        assert!(ctx.eval(&shallow).is_ok());
+        ctx.set_max_nesting_depth(20);
+        assert!(matches!(
+            ctx.eval(&shallow),
+            Err(EvalError::CompileError(CompileError::TooDeeplyNested))
+        ));
+
+        // JSON.parse gets the same limit and throws a catchable error
+        let json = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let script = format!(
+            r#"
+            var ok = JSON.parse("{}").length;
+            var caught = "none";
+            try {{ JSON.parse("{}"); }} catch (e) {{ caught = e.message; }}
+            caught + ok;
+        "#,
+            json(DEFAULT_MAX_NESTING_DEPTH),
+            json(20_000)
+        );
+        let mut ctx = Context::new(1024 * 1024);
+        let result = ctx.eval(&script).unwrap();
+        assert_eq!(
+            ctx.get_string(result).as_deref(),
+            Some("JSON nested too deeply1")
+        );
+    }
+
+    #[test]
+    fn test_properties_on_errors_survive_gc() {
+        let mut ctx = Context::new(64 * 1024);
+
+        ctx.eval("var err = new Error(\"boom\"); err.detail = [1, [2, 3]];")
+            .unwrap();
+        ctx.gc();
+        let result = ctx.eval("return err.detail[1].length;").unwrap();
+        assert_eq!(result.to_i32(), Some(2));
+
+        let result = ctx
+            .eval("err.message = \"changed\"; return [err.message, delete err.detail, err.detail];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[changed, true, undefined]");
+    }
+
+    #[test]
+    fn test_add_and_remove_native() {
+        fn plugin_version(
+            _scope: &mut NativeScope,
+            _this: Value,
+            _args: &[Value],
+        ) -> Result<Value, HostError> {
+            Ok(Value::int(2))
+        }
+
+        let mut ctx = Context::new(64 * 1024);
+        let id = ctx
+            .add_native("pluginVersion".to_string(), plugin_version, 0)
+            .unwrap();
+        let result = ctx
+            .eval("var saved = pluginVersion; return pluginVersion();")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(2));
+
+        // Names collide with every live native, builtins included
+        assert_eq!(
+            ctx.add_native("pluginVersion".to_string(), plugin_version, 0),
+            Err(RegisterError::DuplicateName("pluginVersion".to_string()))
+        );
+        assert!(
+            ctx.add_native("parseInt".to_string(), plugin_version, 0)
+                .is_err()
+        );
+
+        assert!(ctx.remove_native(id));
+        assert!(!ctx.remove_native(id));
+        let result = ctx
+            .eval("try { saved(); return \"called\"; } catch (e) { return e.name + \": \" + e.message; }")
+            .unwrap();
+        assert_eq!(
+            ctx.get_string(result).as_deref(),
+            Some("TypeError: function was unregistered")
+        );
+        assert!(ctx.eval("pluginVersion();").is_err());
+
+        // The name is free again, under a fresh id
+        let again = ctx
+            .add_native("pluginVersion".to_string(), plugin_version, 0)
+            .unwrap();
+        assert_ne!(again, id);
+        let result = ctx.eval("return pluginVersion() + 1;").unwrap();
+        assert_eq!(result.to_i32(), Some(3));
+        let result = ctx
+            .eval("try { saved(); return 0; } catch (e) { return 1; }")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(1));
+    }
+
+    #[test]
+    fn test_parse_json_config() {
+        let mut ctx = Context::new(64 * 1024);
+        let text = r#"{
+            // Server settings
+            "name": "demo",
+            "ports": [80, 443,],
+            /* nested
+               block */
+            "limits": { "depth": 4, "debug": false, },
+        }"#;
+
+        // Strict JSON rejects comments and points at the first one
+        let err = ctx.parse_json(text, JsonOptions::default()).unwrap_err();
+        let pos = err.pos.unwrap();
+        assert_eq!((pos.line, pos.column), (2, 13));
+
+        let options = JsonOptions {
+            allow_comments: true,
+            allow_trailing_commas: true,
+        };
+        let config = ctx.parse_json(text, options).unwrap();
+        ctx.set_global("config", config);
+        let result = ctx
+            .eval("return config.name + \":\" + config.ports[1] + \":\" + config.limits.depth + \":\" + config.ports.length;")
+            .unwrap();
+        assert_eq!(ctx.get_string(result).as_deref(), Some("demo:443:4:2"));
+
+        // Errors report where parsing stopped
+        let err = ctx.parse_json("[1, 2,\n  3 4]", options).unwrap_err();
+        let pos = err.pos.unwrap();
+        assert_eq!((pos.line, pos.column), (2, 5));
+        assert!(ctx.parse_json("{} /* open", options).is_err());
+        assert!(ctx.parse_json("[1,]", JsonOptions::default()).is_err());
+        assert!(ctx.parse_json("1 2", options).is_err());
+    }
+
+    #[test]
+    fn test_to_json_round_trip() {
+        let mut ctx = Context::new(64 * 1024);
+        let value = ctx
+            .parse_json(
+                r#"{ "name": "a\"b", "list": [1, null, true, []], "empty": {} }"#,
+                JsonOptions::default(),
+            )
+            .unwrap();
+
+        let compact = ctx.to_json(value, false).unwrap();
+        assert_eq!(
+            compact,
+            r#"{"name":"a\"b","list":[1,null,true,[]],"empty":{}}"#
+        );
+        let reparsed = ctx.parse_json(&compact, JsonOptions::default()).unwrap();
+        assert_eq!(ctx.to_json(reparsed, false).unwrap(), compact);
+
+        let pretty = ctx.to_json(value, true).unwrap();
+        assert_eq!(
+            pretty,
+            "{\n  \"name\": \"a\\\"b\",\n  \"list\": [\n    1,\n    null,\n    true,\n    []\n  ],\n  \"empty\": {}\n}"
+        );
+        let reparsed = ctx.parse_json(&pretty, JsonOptions::default()).unwrap();
+        assert_eq!(ctx.to_json(reparsed, true).unwrap(), pretty);
+
+        // JSON.stringify goes through the same serializer
+        ctx.set_global("value", value);
+        let result = ctx.eval("return JSON.stringify(value, null, 2);").unwrap();
+        assert_eq!(ctx.get_string(result).as_deref(), Some(pretty.as_str()));
+
+        let cyclic = ctx.eval("var a = []; a.push(a); return a;").unwrap();
+        assert!(ctx.to_json(cyclic, false).is_err());
+        assert!(ctx.to_json(Value::undefined(), false).is_err());
+    }
+
+    #[test]
+    fn test_manual_clock_drives_time_builtins() {
+        use crate::vm::ManualClock;
+
+        let clock = Rc::new(ManualClock::new(1_717_245_000_000));
+        let mut ctx = Context::new(64 * 1024);
+        ctx.set_clock(clock.clone());
+
+        let result = ctx.eval("Date.now();").unwrap();
+        assert_eq!(result.to_i32(), Some(1_717_245_000));
+        let result = ctx.eval("new Date().toISOString();").unwrap();
+        assert_eq!(ctx.inspect(result), "2024-06-01T12:30:00.000Z");
+
+        // performance.now counts microseconds from when the clock was set
+        clock.advance_us(1_500);
+        let result = ctx.eval("performance.now();").unwrap();
+        assert_eq!(result.to_i32(), Some(1_500));
+
+        // Timers wait by advancing the clock instead of blocking
+        let summary = ctx
+            .eval_with_timeout(
+                "var fired = []; function fire() { fired.push(Date.now()); } setTimeout(fire, 2500);",
+                Duration::from_secs(10),
+            )
+            .unwrap();
+        assert_eq!(summary.timers_fired, 1);
+        assert_eq!(ctx.eval("fired[0];").unwrap().to_i32(), Some(1_717_245_002));
+    }
+
+    #[test]
+    fn test_record_and_replay_eval() {
+        use core::sync::atomic::{AtomicI32, Ordering};
+
+        static READINGS: AtomicI32 = AtomicI32::new(40);
+        fn read_sensor(
+            scope: &mut NativeScope,
+            _this: Value,
+            args: &[Value],
+        ) -> Result<Value, HostError> {
+            let channel = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+            let reading = READINGS.fetch_add(1, Ordering::SeqCst) * channel;
+            let label = scope.alloc_string("celsius");
+            Ok(scope.alloc_array(vec![Value::int(reading), label]))
+        }
+        fn broken_sensor(_: &mut NativeScope, _: Value, _: &[Value]) -> Result<Value, HostError> {
+            panic!("the replayed run must not call the host");
+        }
+
+        let script = "
+            var picks = [];
+            for (var i = 0; i < 3; i = i + 1) { picks.push(Math.random()); }
+            var first = readSensor(2);
+            var second = readSensor(3);
+            var failed = \"no\";
+            try { readSensor(); } catch (e) { failed = e.message; }
+            picks.join(\",\") + \"|\" + first.join(\" \") + \"|\" + second[0] + \"|\" + failed + \"|\" + (Date.now() > 0);
+        ";
+        fn read_sensor_or_fail(
+            scope: &mut NativeScope,
+            this: Value,
+            args: &[Value],
+        ) -> Result<Value, HostError> {
+            if args.is_empty() {
+                return Err(HostError::Range("no channel".to_string()));
+            }
+            read_sensor(scope, this, args)
+        }
+
+        let mut ctx = Context::new(64 * 1024);
+        ctx.set_clock(Rc::new(crate::vm::ManualClock::new(1_700_000_000_000)));
+        ctx.register_function("readSensor", read_sensor_or_fail, 1);
+        ctx.record();
+        let recorded = ctx.eval(script).unwrap();
+        let recorded = ctx.get_string(recorded).unwrap().to_string();
+        let log = ctx.take_replay_log().unwrap();
+        assert_eq!(log.len(), 3 + 3 + 1);
+        assert!(recorded.ends_with("|no channel|true"), "{}", recorded);
+
+        let mut replayed = Context::new(64 * 1024);
+        replayed.register_function("readSensor", broken_sensor, 1);
+        replayed.replay(ReplayLog::from_bytes(&log.to_bytes()).unwrap());
+        let result = replayed.eval(script).unwrap();
+        assert_eq!(
+            replayed.get_string(result).as_deref(),
+            Some(recorded.as_str())
+        );
+
+        // A script that asks for different inputs stops at the first mismatch
+        let mut diverged = Context::new(64 * 1024);
+        diverged.register_function("readSensor", broken_sensor, 1);
+        diverged.replay(log.clone());
+        let err = diverged.eval("Math.random(); readSensor(1);").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Runtime error: InternalError: replay diverged at entry 1: log has Math.random, script made call to 'readSensor'"
+        );
+        let mut diverged = Context::new(64 * 1024);
+        diverged.register_function("readSensor", broken_sensor, 1);
+        diverged.replay(log);
+        let err = diverged
+            .eval("Math.random(); Math.random(); Math.random(); readSensor(5);")
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("call to 'readSensor' with different arguments")
+        );
+    }
+
+    #[test]
+    fn test_compile_cache_reuses_scripts() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval("var s = \"alpha\"; s;").unwrap();
+        assert_eq!(ctx.compile_cache_stats(), CompileCacheStats::default());
+
+        ctx.set_compile_cache_size(2);
+        // Sources differing only in a string literal get separate entries
+        let alpha = ctx.eval("var s = \"alpha\"; s;").unwrap();
+        let beta = ctx.eval("var s = \"alphb\"; s;").unwrap();
+        assert_eq!(ctx.get_string(alpha).as_deref(), Some("alpha"));
+        assert_eq!(ctx.get_string(beta).as_deref(), Some("alphb"));
+        let again = ctx.eval("var s = \"alpha\"; s;").unwrap();
+        assert_eq!(ctx.get_string(again).as_deref(), Some("alpha"));
+        assert_eq!(
+            ctx.compile_cache_stats(),
+            CompileCacheStats {
+                entries: 2,
+                hits: 1,
+                misses: 2
+            }
+        );
+
+        // A cached script reads globals as they are when it runs
+        ctx.set_global("rate", Value::int(2));
+        assert_eq!(ctx.eval("rate * 10;").unwrap().to_i32(), Some(20));
+        ctx.set_global("rate", Value::int(3));
+        assert_eq!(ctx.eval("rate * 10;").unwrap().to_i32(), Some(30));
+        let stats = ctx.compile_cache_stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (2, 2, 3));
+
+        // The least recently used entry ("alphb") was evicted for "rate"
+        ctx.eval("var s = \"alphb\"; s;").unwrap();
+        assert_eq!(ctx.compile_cache_stats().misses, 4);
+
+        // Changing compiler options drops scripts compiled under the old ones
+        ctx.eval("[[[1]]];").unwrap();
+        ctx.set_max_nesting_depth(2);
+        assert_eq!(ctx.compile_cache_stats().entries, 0);
+        assert!(matches!(
+            ctx.eval("[[[1]]];"),
+            Err(EvalError::CompileError(CompileError::TooDeeplyNested))
+        ));
+
+        ctx.set_max_nesting_depth(DEFAULT_MAX_NESTING_DEPTH);
+        ctx.eval("rate * 10;").unwrap();
+        ctx.clear_compile_cache();
+        assert_eq!(ctx.compile_cache_stats().entries, 0);
+        ctx.set_compile_cache_size(0);
+        ctx.eval("rate * 10;").unwrap();
+        assert_eq!(ctx.compile_cache_stats().entries, 0);
+    }
+
+    #[test]
+    fn test_memory_budget_is_enforced() {
+        let mut ctx = Context::new(16 * 1024);
+        let within_budget = |ctx: &Context| {
+            let stats = ctx.memory_stats();
+            assert_eq!(stats.heap_size, 16 * 1024);
+            assert!(stats.used <= stats.heap_size, "used {}", stats.used);
+            assert_eq!(stats.free, stats.heap_size - stats.used);
+        };
+        within_budget(&ctx);
+
+        // A modest script fits
+        let result = ctx
+            .eval(
+                "var xs = [];
+                 for (var i = 0; i < 100; i = i + 1) { xs.push(i * 2); }
+                 xs.join(',').length;",
+            )
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(344));
+        within_budget(&ctx);
+
+        // Allocations past the budget throw a catchable RangeError
+        for grow in [
+            "var s = 'abcdefgh'; while (true) { s = s + s; }",
+            "var a = []; while (true) { a.push(1); }",
+            "var a = [0]; while (true) { a.unshift(1, 2, 3); }",
+            "var b = [1, 2, 3, 4]; while (true) { b = b.concat(b, 5); }",
+            "var b = []; for (var i = 0; true; i = i + 1) { b[i] = i; }",
+            "'x'.repeat(100000);",
+        ] {
+            let source = format!(
+                "var caught = ''; try {{ {} }} catch (e) {{ caught = e.name + ': ' + e.message; }} caught;",
+                grow
+            );
+            let result = ctx.eval(&source).unwrap();
+            assert_eq!(
+                ctx.get_string(result).as_deref(),
+                Some("RangeError: out of memory"),
+                "{}",
+                grow
+            );
+            within_budget(&ctx);
+            ctx.eval("s = undefined; a = undefined; b = undefined;")
+                .unwrap();
+            ctx.gc();
+        }
+
+        // Uncaught, it fails the eval and leaves the context usable
+        assert!(ctx.eval("'y'.repeat(100000);").is_err());
+        assert_eq!(ctx.eval("1 + 2;").unwrap().to_i32(), Some(3));
+    }
+
+    #[test]
+    fn test_unreachable_runtime_strings_are_reclaimed() {
+        // Each step leaves the previous string behind; the budget is only
+        // met by collecting them
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval("var s = ''; for (var i = 0; i < 1000; i = i + 1) { s = s + 'x'; } s.length;")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(1000));
+        assert!(ctx.gc_stats().collections > 0);
+        assert!(ctx.memory_stats().used <= 64 * 1024);
+
+        // Reachable strings survive and freed slots are reused for new ones
+        ctx.eval(
+            "var keep = [];
+             for (var i = 0; i < 50; i = i + 1) {
+                 var junk = 'junk-' + i;
+                 keep.push('item-' + i);
+             }",
+        )
+        .unwrap();
+        ctx.gc();
+        let live = ctx.memory_stats().runtime_strings;
+        ctx.eval("for (var i = 0; i < 500; i = i + 1) { var t = 'temp-' + i; } t = undefined;")
+            .unwrap();
+        ctx.gc();
+        // Only the second script's literals are added
+        assert!(ctx.memory_stats().runtime_strings <= live + 2);
+        let keep = ctx.get_array(ctx.get_global("keep").unwrap()).unwrap();
+        for (i, item) in keep.iter().enumerate() {
+            assert_eq!(
+                ctx.get_string(*item).unwrap(),
+                format!("item-{}", i).as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn test_unreachable_objects_are_reclaimed() {
+        // Object literals left behind by each round only fit the budget
+        // once they are collected
+        let mut ctx = Context::new(64 * 1024);
+        for _ in 0..2000 {
+            let result = ctx
+                .eval("var s = 0; for (var i = 0; i < 10; i = i + 1) { var o = { x: i }; s = s + o.x; } s;")
+                .unwrap();
+            assert_eq!(result.to_i32(), Some(45));
+        }
+        assert!(ctx.gc_stats().collections > 0);
+
+        // Reachable objects survive and freed slots are reused
+        ctx.eval("var keep = []; for (var i = 0; i < 20; i = i + 1) { keep.push({ n: i, d: new Date(i) }); }")
+            .unwrap();
+        ctx.gc();
+        let live = ctx.memory_stats().objects;
+        ctx.eval("for (var i = 0; i < 500; i = i + 1) { var t = { n: i }; } t = undefined;")
+            .unwrap();
+        ctx.gc();
+        assert!(ctx.memory_stats().objects <= live);
+        let result = ctx
+            .eval("var sum = 0; for (var i = 0; i < 20; i = i + 1) { sum = sum + keep[i].n + keep[i].d.getTime(); } sum;")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(380));
+    }
+
+    #[test]
+    fn test_script_bytecode_is_released_or_charged() {
+        // Scripts that define no functions are dropped once they have run
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval("var total = 0;").unwrap();
+        let used = ctx.memory_stats().used;
+        for i in 0..2000 {
+            ctx.eval(&format!("total = total + {};", i % 10)).unwrap();
+        }
+        assert_eq!(ctx.eval("total;").unwrap().to_i32(), Some(9000));
+        assert_eq!(ctx.memory_stats().used, used);
+
+        // Scripts that define functions stay loaded and count against the
+        // limit, so an endless stream of them runs out of memory
+        let mut failed = None;
+        for i in 0..2000 {
+            if let Err(err) = ctx.eval(&format!("function f{}() {{ return {}; }}", i, i)) {
+                failed = Some(err.to_string());
+                break;
+            }
+        }
+        let failed = failed.expect("function scripts should be charged");
+        assert!(failed.contains("out of memory"), "{}", failed);
+    }
+
+    #[test]
+    fn test_runtime_string_table_exhaustion_throws() {
+        // Filling the runtime string table must not wrap indices onto
+        // existing strings
+        let mut ctx = Context::new(16 * 1024 * 1024);
+        let result = ctx
+            .eval(
+                "var keep = [];
+                 var failed;
+                 try {
+                     for (var i = 0; i < 40000; i = i + 1) { keep.push('item-' + i); }
+                 } catch (e) {
+                     // Reading the name and message needs no new string slot
+                     failed = e.name == 'RangeError' && e.message == 'string table full';
+                 }
+                 failed;",
+            )
+            .unwrap();
+        assert_eq!(result.to_bool(), Some(true));
+        let keep = ctx.get_array(ctx.get_global("keep").unwrap()).unwrap();
+        assert!(keep.len() < 40000);
+        for (i, item) in keep.iter().enumerate() {
+            assert_eq!(
+                ctx.get_string(*item).unwrap(),
+                format!("item-{}", i).as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn test_callback_exceptions_reach_script_handlers() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval(
+                "var thrown = new TypeError('bad item');
+                 var seen = [];
+                 function check(x) {
+                     seen.push(x);
+                     if (x == 2) { throw thrown; }
+                 }
+                 var caught;
+                 try { [1, 2, 3].forEach(check); } catch (e) { caught = e; }
+                 function twice(x) { throw x * 2; }
+                 var plain;
+                 try { [21].map(twice); } catch (e) { plain = e; }
+                 [caught === thrown, caught.message, seen.length, plain];",
+            )
+            .unwrap();
+        let items = ctx.get_array(result).unwrap();
+        assert_eq!(items[0].to_bool(), Some(true));
+        assert_eq!(ctx.get_string(items[1]).as_deref(), Some("bad item"));
+        assert_eq!(items[2].to_i32(), Some(2));
+        assert_eq!(items[3].to_i32(), Some(42));
+
+        // Uncaught, the thrown value is kept as the context's exception
+        assert!(ctx.eval("[1].forEach(check); [2].forEach(check);").is_err());
+        let exception = ctx.get_exception();
+        assert_eq!(ctx.inspect(exception), "TypeError: bad item");
+    }
+
+    #[test]
+    fn test_unhandled_timer_errors_reach_hook() {
+        use core::cell::RefCell;
+
+        let clock = SimulatedClock::new(0, 0);
+        let mut ctx = Context::new(64 * 1024);
+        ctx.set_clock(clock.clone());
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&reported);
+        ctx.set_unhandled_error_hook(Some(Box::new(move |err: JsError| {
+            sink.borrow_mut().push(err.to_string());
+        })));
+
+        let summary = ctx
+            .eval_with_timeout(
+                "var after = [];
+                 function fail() { throw new RangeError('sensor offline'); }
+                 function missing() { return undefinedName; }
+                 function later() { after.push(1); }
+                 setTimeout(fail, 10);
+                 setTimeout(missing, 20);
+                 setTimeout(later, 30);",
+                Duration::from_millis(1000),
+            )
+            .unwrap();
+        assert_eq!(summary.timers_fired, 3);
+        assert_eq!(
+            *reported.borrow(),
+            [
+                "RangeError: sensor offline",
+                "ReferenceError: undefinedName is not defined"
+            ]
+        );
+        assert_eq!(ctx.eval("after.length;").unwrap().to_i32(), Some(1));
+
+        // Without a hook the exception fails the call
+        ctx.set_unhandled_error_hook(None);
+        let err = ctx
+            .eval_with_timeout("setTimeout(fail, 0);", Duration::from_millis(100))
+            .unwrap_err();
+        assert!(err.to_string().contains("sensor offline"), "{}", err);
+    }
+
+    #[test]
+    fn test_use_strict_directive_is_a_no_op() {
+        let mut ctx = Context::new(64 * 1024);
+        assert_eq!(ctx.eval("'use strict'; 1 + 2;").unwrap().to_i32(), Some(3));
+        assert!(ctx.eval("\"use strict\";").unwrap().is_undefined());
+        let result = ctx
+            .eval("function f() { 'use strict'; return 7; } f();")
+            .unwrap();
+        assert_eq!(result.to_i32(), Some(7));
+    }
+
+    fn frame_args(
+        interp: &mut Interpreter,
+        _this: Value,
+        _args: &[Value],
+    ) -> Result<Value, HostError> {
+        let args = interp.current_frame_args();
+        Ok(interp.alloc_array(args))
+    }
+
+    fn stack_depth(
+        interp: &mut Interpreter,
+        _this: Value,
+        _args: &[Value],
+    ) -> Result<Value, HostError> {
+        Ok(Value::int(interp.stack_len() as i32))
+    }
+
+    #[test]
+    fn test_extra_call_arguments_stay_on_the_frame() {
+        let mut ctx = Context::new(64 * 1024);
+        register_builtin(&mut ctx, "frameArgs", frame_args, 0);
+
+        let result = ctx
+            .eval(
+                "
+            function two(a, b) { var seen = frameArgs(); return [seen, a, b, seen.length]; }
+            return [two(1, 2, 3, 4), two(5), two()];
+        ",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[[[1, 2, 3, 4], 1, 2, 4], [[5], 5, undefined, 1], [[], undefined, undefined, 0]]"
+        );
+
+        // Method, constructor and native-initiated calls share the layout
+        let result = ctx
+            .eval(
+                "
+            var o = { m(a) { var x = a * 2; return [frameArgs(), x]; } };
+            function Box(v) { this.args = frameArgs(); this.v = v; }
+            function each(el) { return frameArgs().length + el; }
+            return [o.m(1, 2, 3), new Box(7, 8).args, [10, 20].map(each)];
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[[[1, 2, 3], 2], [7, 8], [13, 23]]");
+    }
+
+    #[test]
+    fn test_extra_call_arguments_are_cleaned_up() {
+        let mut ctx = Context::new(256 * 1024);
+        register_builtin(&mut ctx, "stackDepth", stack_depth, 0);
+
+        let result = ctx
+            .eval(
+                "
+            function leaf(a) { var t = a + 1; return t; }
+            function deep(n) { if (n == 0) return 0; return deep(n - 1, n, n, n) + 1; }
+            function fail(a) { throw a; }
+            var before = stackDepth();
+            var total = 0;
+            for (var i = 0; i < 10000; i = i + 1) {
+                total = total + leaf(i, 1, 2, 3);
+                try { fail(i, i, i); } catch (e) { total = total - e; }
+            }
+            var depth = deep(200, 1, 2);
+            var after = stackDepth();
+            return [total, depth, after - before];
+        ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[10000, 200, 0]");
+    }
+
+    #[test]
+    fn test_uncaught_error_reports_its_causes() {
+        let mut ctx = Context::new(64 * 1024);
+        let err = ctx
+            .eval(
+                "
+            var inner = new SyntaxError('bad config');
+            throw new Error('init failed', { cause: inner });
+        ",
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Error: init failed"), "{}", err);
+        assert!(
+            err.contains("caused by: SyntaxError: bad config"),
+            "{}",
+            err
+        );
+
+        // Long chains are cut short
+        let err = ctx
+            .eval(
+                "
+            var e = new Error('root');
+            for (var i = 0; i < 20; i = i + 1) {
+                e = new Error('level ' + i, { cause: e });
+            }
+            throw e;
+        ",
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("caused by: Error: level 11"), "{}", err);
+        assert!(!err.contains("level 10"), "{}", err);
+        assert!(err.ends_with("caused by: ..."), "{}", err);
+    }
+
+    #[test]
+    fn test_uncaught_error_like_objects_format_as_errors() {
+        let mut ctx = Context::new(64 * 1024);
+        let err = ctx
+            .eval("throw { name: 'ConfigError', message: 'missing key' };")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.ends_with("Uncaught exception: ConfigError: missing key"),
+            "{}",
+            err
+        );
+
+        // Inheriting from Error.prototype supplies the name
+        let err = ctx
+            .eval("var e = Object.create(Error.prototype); e.message = 'bare'; throw e;")
+            .unwrap_err()
+            .to_string();
+        assert!(err.ends_with("Uncaught exception: Error: bare"), "{}", err);
+
+        // A message that is not a string is not error-like
+        let err = ctx
+            .eval("throw { name: 'Odd', message: 1 };")
+            .unwrap_err()
+            .to_string();
+        assert!(!err.contains("Odd: 1"), "{}", err);
+    }
+
+    /// Serialize like JSON.stringify using only the native-facing read API
+    fn encode(scope: &NativeScope, val: Value, out: &mut String) {
+        if let Some(s) = scope.get_string(val) {
+            out.push('"');
+            for ch in s.chars() {
+                match ch {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    _ => out.push(ch),
+                }
+            }
+            out.push('"');
+        } else if let Some(len) = scope.array_len(val) {
+            out.push('[');
+            for i in 0..len {
+                if i > 0 {
+                    out.push(',');
+                }
+                encode(scope, scope.array_element_at(val, i).unwrap(), out);
+            }
+            out.push(']');
+        } else if let Some(count) = scope.object_property_count(val) {
+            out.push('{');
+            let mut first = true;
+            for i in 0..count {
+                let (key, v) = scope.object_property_at(val, i).unwrap();
+                if !scope.has_property(val, key, crate::vm::PropertyLookup::OwnEnumerable) {
+                    continue;
+                }
+                if !first {
+                    out.push(',');
+                }
+                first = false;
+                out.push_str(&format!("\"{}\":", key));
+                encode(scope, v, out);
+            }
+            out.push('}');
+        } else if let Some(n) = val.to_i32() {
+            out.push_str(&n.to_string());
+        } else if let Some(b) = val.to_bool() {
+            out.push_str(if b { "true" } else { "false" });
+        } else {
+            out.push_str("null");
+        }
+    }
+
+    fn host_encode(
+        scope: &mut NativeScope,
+        _this: Value,
+        args: &[Value],
+    ) -> Result<Value, HostError> {
+        let mut out = String::new();
+        encode(scope, args.first().copied().unwrap_or_default(), &mut out);
+        Ok(scope.alloc_string(&out))
+    }
+
+    fn host_build(
+        scope: &mut NativeScope,
+        _this: Value,
+        args: &[Value],
+    ) -> Result<Value, HostError> {
+        let list = scope.alloc_array(Vec::new());
+        for &arg in args {
+            scope.array_push(list, arg);
+        }
+        let obj = scope.alloc_object().finish();
+        assert!(scope.object_set(obj, "items", list));
+        assert!(scope.object_set(obj, "count", Value::int(args.len() as i32)));
+        assert!(!scope.object_set(list, "nope", Value::null()));
+        assert_eq!(scope.array_push(obj, Value::null()), None);
+        Ok(obj)
+    }
+
+    #[test]
+    fn test_natives_read_values_through_the_public_api() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.register_function("encode", host_encode, 1);
+        ctx.register_function("build", host_build, 0);
+
+        let result = ctx
+            .eval(
+                r#"
+            var hidden = { shown: 1 };
+            Object.defineProperty(hidden, "secret", { value: 2, enumerable: false });
+            var samples = [
+                1, "two", true, null, [], {},
+                [1, [2, [3, "deep"]], { a: [] }],
+                { name: "sensor \"A\"", tags: ["x", "y"], nested: { ok: false, n: -5 } },
+                hidden,
+                build(1, "b", [2]),
+            ];
+            var mismatches = [];
+            for (var i = 0; i < samples.length; i = i + 1) {
+                if (encode(samples[i]) !== JSON.stringify(samples[i])) {
+                    mismatches.push(encode(samples[i]) + " vs " + JSON.stringify(samples[i]));
+                }
+            }
+            return [mismatches.length, encode(build(1, "b")), mismatches.join("; ")];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), r#"[0, {"items":[1,"b"],"count":2}, ]"#);
+    }
+
+    #[test]
+    fn test_builder_disables_nonstandard_globals_and_print() {
+        let mut ctx = Context::builder(64 * 1024)
+            .enable_gc_global(false)
+            .enable_load(false)
+            .enable_timers(false)
+            .enable_print(false)
+            .build();
+        for script in [
+            "gc();",
+            "load('x.js');",
+            "setTimeout(Math.max, 0);",
+            "clearTimeout(1);",
+        ] {
+            let err = ctx.eval(script).unwrap_err().to_string();
+            assert!(err.contains("ReferenceError"), "{}: {}", script, err);
+        }
+        let result = ctx
+            .eval("[globalThis.gc, globalThis.setTimeout, globalThis.load];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[undefined, undefined, undefined]");
+
+        let err = ctx.eval("var x = 1;\nprint x;").unwrap_err().to_string();
+        assert!(
+            err.contains("print statements are disabled at line 2"),
+            "{}",
+            err
+        );
+        let err = ctx
+            .eval("function f() { print 1; }")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("print statements are disabled"), "{}", err);
+
+        // Everything else still works
+        assert_eq!(ctx.eval("Math.max(1, 2);").unwrap().to_i32(), Some(2));
+    }
+
+    #[test]
+    fn test_default_builder_keeps_nonstandard_globals() {
+        let mut ctx = Context::builder(64 * 1024).build();
+        let result = ctx
+            .eval("[typeof gc, typeof setTimeout, typeof clearTimeout];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[function, function, function]");
+        #[cfg(feature = "std")]
+        assert!(ctx.eval("typeof load;").is_ok());
+        assert!(
+            ctx.eval("print 1; gc(); clearTimeout(setTimeout(gc, 0));")
+                .is_ok()
+        );
+    }
+
+    /// A loop of 1000 iterations with a Math.floor call in each
+    const PROFILED_LOOP: &str = "
+        var sum = 0;
+        for (var i = 0; i < 1000; i = i + 1) {
+            sum = sum + Math.floor(i / 2);
+        }
+        var points = [];
+        for (var j = 0; j < 3; j = j + 1) {
+            points.push({ x: j });
+        }
+        sum;
+    ";
+
+    #[test]
+    fn test_eval_profiled_reports_instructions_and_natives() {
+        let mut ctx = Context::builder(64 * 1024).profile(true).build();
+        let (result, report) = ctx.eval_profiled(PROFILED_LOOP);
+        assert_eq!(result.unwrap().to_i32(), Some(249500));
+
+        // Each iteration runs about 25 instructions: the compare, the
+        // increment, the call and the loads and stores around them
+        assert!(
+            (20_000..=30_000).contains(&report.instructions),
+            "{}",
+            report.instructions
+        );
+        assert_eq!(report.natives[0], ("Math.floor".to_string(), 1000));
+        assert!(
+            report
+                .natives
+                .contains(&("Array.prototype.push".to_string(), 3))
+        );
+        assert_eq!(report.native_calls, 1003);
+        assert!(report.peak_stack_depth >= 3);
+        assert_eq!(report.peak_call_depth, 1);
+        assert_eq!(report.allocations.objects, 3);
+        assert_eq!(report.allocations.arrays, 1);
+
+        // Each call reports only its own work
+        let (_, report) = ctx.eval_profiled("Math.floor(1.5);");
+        assert_eq!(report.native_calls, 1);
+        assert!(report.instructions < 10, "{}", report.instructions);
+    }
+
+    #[test]
+    fn test_eval_profiled_tracks_call_depth() {
+        let mut ctx = Context::builder(64 * 1024).profile(true).build();
+        let (result, report) = ctx.eval_profiled(
+            "function down(n) { if (n == 0) return 0; return down(n - 1) + 1; } down(20);",
+        );
+        assert_eq!(result.unwrap().to_i32(), Some(20));
+        assert_eq!(report.peak_call_depth, 22);
+    }
+
+    #[test]
+    fn test_stack_watermarks() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval("function down(n) { if (n == 0) return 0; return down(n - 1) + 1; }")
+            .unwrap();
+        assert_eq!(ctx.eval("down(20);").unwrap().to_i32(), Some(20));
+        assert_eq!(ctx.interpreter_stats().call_depth_high_water, 22);
+
+        // Each level of nesting keeps one more operand waiting
+        let nested = |depth: usize| format!("{}1{};", "1 + (".repeat(depth), ")".repeat(depth));
+        ctx.reset_watermarks();
+        ctx.eval(&nested(10)).unwrap();
+        let shallow = ctx.interpreter_stats().stack_high_water;
+        ctx.reset_watermarks();
+        ctx.eval(&nested(50)).unwrap();
+        let deep = ctx.interpreter_stats().stack_high_water;
+        assert_eq!(deep - shallow, 40);
+
+        // Later evals never lower the marks until they are reset
+        assert_eq!(ctx.eval("down(3);").unwrap().to_i32(), Some(3));
+        ctx.eval("1;").unwrap();
+        let stats = ctx.interpreter_stats();
+        assert_eq!(stats.stack_high_water, deep);
+        assert_eq!(stats.call_depth_high_water, 5);
+        ctx.reset_watermarks();
+        let stats = ctx.interpreter_stats();
+        assert_eq!(
+            (stats.stack_high_water, stats.call_depth_high_water),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn test_disabled_profiling_changes_nothing() {
+        let mut plain = Context::new(64 * 1024);
+        let mut profiled = Context::builder(64 * 1024).profile(true).build();
+        let (profiled_result, _) = profiled.eval_profiled(PROFILED_LOOP);
+        let (plain_result, report) = plain.eval_profiled(PROFILED_LOOP);
+        assert_eq!(
+            plain_result.unwrap().to_i32(),
+            profiled_result.unwrap().to_i32()
+        );
+        assert_eq!(plain.memory_stats().used, profiled.memory_stats().used);
+        assert_eq!(plain.interpreter_stats(), profiled.interpreter_stats());
+        assert_eq!(report.instructions, 0);
+        assert!(report.natives.is_empty());
+        assert_eq!(report.allocations, InterpreterStats::default());
+        assert!(!plain.interpreter.is_profiling());
+    }
+
+    #[test]
+    fn test_delete_global_bindings_across_scripts() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval("var declared = 1; function helper() { return 2; } assigned = 3;")
+            .unwrap();
+        ctx.set_global("host", Value::int(4));
+
+        let result = ctx
+            .eval("[delete declared, delete helper, delete assigned, delete host];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[false, false, true, true]");
+        let result = ctx.eval("[declared, helper()];").unwrap();
+        assert_eq!(ctx.inspect(result), "[1, 2]");
+        for name in ["assigned", "host"] {
+            let err = ctx.eval(&format!("{};", name)).unwrap_err().to_string();
+            assert!(err.contains("is not defined"), "{}", err);
+        }
+
+        // Reassigning a declared global keeps it undeletable
+        let result = ctx
+            .eval("declared = 5; [delete declared, declared];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[false, 5]");
+    }
+
+    #[test]
+    fn test_deleting_builtins_leaves_the_native_registry_intact() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval("[delete Math.floor, delete Math, delete parseInt, delete Array.prototype.push];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[false, false, false, false]");
+        let result = ctx
+            .eval("var a = [Math.floor(7 / 2)]; a.push(parseInt(8)); a;")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[3, 8]");
+        assert!(ctx.interpreter.get_native_func("Math.floor").is_some());
+    }
+
+    fn eval_string(
+        interp: &mut Interpreter,
+        _this: Value,
+        args: &[Value],
+    ) -> Result<Value, HostError> {
+        let code = args
+            .first()
+            .and_then(|&v| interp.get_string(v).map(|s| s.to_string()))
+            .ok_or("evalString requires a string")?;
+        Ok(interp.eval_nested(&code)?)
+    }
+
+    #[test]
+    fn test_eval_nested_keeps_outer_closures_valid() {
+        // Every level nests a run loop and a compile on the native stack,
+        // more than a debug build fits in the default test thread
+        std::thread::Builder::new()
+            .stack_size(32 * 1024 * 1024)
+            .spawn(eval_nested_fifty_levels_deep)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    fn eval_nested_fifty_levels_deep() {
+        let mut ctx = Context::new(1024 * 1024);
+        register_builtin(&mut ctx, "evalString", eval_string, 1);
+        // Each level loads a new script while the outer ones are running,
+        // then calls closures from every level once the inner evals return
+        ctx.eval(
+            "
+                var makers = [];
+                function level(n) {
+                    var base = n * 10;
+                    function make() {
+                        return base + 1;
+                    }
+                    makers.push(make);
+                    if (n < 50) {
+                        evalString('var inner' + n + ' = level(' + (n + 1) + ');');
+                    }
+                    var total = 0;
+                    for (var i = n - 1; i < makers.length; i = i + 1) { total += makers[i](); }
+                    return total;
+                }
+                ",
+        )
+        .unwrap();
+        let result = ctx
+            .eval(
+                "
+                var top = level(1);
+                var sum = 0;
+                for (var i = 0; i < makers.length; i = i + 1) { sum += makers[i](); }
+                [top, sum, makers.length, inner1, inner49];
+                ",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[12800, 12800, 50, 12789, 501]");
+    }
+
+    #[test]
+    fn test_eval_nested_shares_globals_and_propagates_exceptions() {
+        let mut ctx = Context::new(256 * 1024);
+        register_builtin(&mut ctx, "evalString", eval_string, 1);
+        // A running script's own top-level variables are published when it
+        // finishes, so the globals the inner scripts share come from earlier
+        ctx.eval("var seen = []; var shared = 1;").unwrap();
+        let result = ctx
+            .eval(
+                "
+                evalString('shared = shared + 1; var fromInner = shared * 10;');
+                seen.push(shared, fromInner);
+                try { evalString('throw new RangeError(\\'inner\\');'); }
+                catch (e) { seen.push(e.name, e.message); }
+                try { evalString('var = ;'); }
+                catch (e) { seen.push(e.name); }
+                function nested() { return evalString('seen.length;'); }
+                seen.push(nested());
+                seen;
+                ",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[2, 20, RangeError, inner, SyntaxError, 5]"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_load_inside_a_function_returns_to_its_caller() {
+        let path = std::env::temp_dir().join(format!("mquickjs-load-{}.js", std::process::id()));
+        std::fs::write(&path, "var loaded = 7;").unwrap();
+        let mut ctx = Context::new(64 * 1024);
+        let script = format!(
+            "function setup() {{ load({:?}); return 1; }} var ran = setup(); [ran, loaded];",
+            path.display().to_string()
+        );
+        let result = ctx.eval(&script);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(ctx.inspect(result.unwrap()), "[1, 7]");
+    }
+
+    #[test]
+    fn test_long_else_if_chains_hit_every_branch() {
+        let mut source = String::from("function dispatch(op) { var out = 'none';\n");
+        for op in 1..=40 {
+            if op > 1 {
+                source.push_str(" else ");
+            }
+            source.push_str(&format!("if (op === {}) {{ out = {}; }}", op, op * 100));
+        }
+        // A repeated value can never match, and a differently shaped
+        // condition ends the chain with a naive test
+        source.push_str(
+            " else if (op === 7) { out = 'dup'; } else if (op === 'x') { out = 'x'; } \
+             else { out = 'else'; } return out; }",
+        );
+        let mut ctx = Context::new(256 * 1024);
+        ctx.eval(&source).unwrap();
+
+        let calls: Vec<String> = (0..=41).map(|op| format!("dispatch({})", op)).collect();
+        let result = ctx.eval(&format!("[{}];", calls.join(", "))).unwrap();
+        let expected: Vec<String> = (0..=41)
+            .map(|op| match op {
+                1..=40 => (op * 100).to_string(),
+                _ => "else".to_string(),
+            })
+            .collect();
+        assert_eq!(ctx.inspect(result), format!("[{}]", expected.join(", ")));
+
+        let result = ctx
+            .eval(
+                "var calls = 0; var sneaky = { valueOf() { calls = calls + 1; return 5; } };
+                 [dispatch(-3), dispatch('5'), dispatch(sneaky), dispatch(true), dispatch('x'),
+                  dispatch(null), calls];",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[else, else, else, else, x, else, 0]");
+    }
+
+    #[cfg(feature = "opcode-stats")]
+    #[test]
+    fn test_opcode_stats_count_the_script_that_ran() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval(
+            "function sum(n) { var s = 0; for (var i = 0; i < n; i = i + 1) { s = s + i; } return s; }",
+        )
+        .unwrap();
+        ctx.reset_opcode_stats();
+        let result = ctx.eval("Math.max(sum(10), sum(20));").unwrap();
+        assert_eq!(result.to_i32(), Some(190));
+
+        let stats = ctx.opcode_stats();
+        assert_eq!(stats.len(), OpCode::COUNT);
+        assert!(stats.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        let count = |op: OpCode| stats.iter().find(|&&(o, _)| o == op).unwrap().1;
+        // Both loops run 30 times in total; each pass compares and adds
+        assert!(count(OpCode::Lt) >= 30, "{:?}", stats);
+        assert!(count(OpCode::Add) >= 60, "{:?}", stats);
+        assert!(count(OpCode::Return) >= 2, "{:?}", stats);
+        for unused in [
+            OpCode::Throw,
+            OpCode::Mul,
+            OpCode::Div,
+            OpCode::Object,
+            OpCode::ArrayFrom,
+        ] {
+            assert_eq!(count(unused), 0, "{:?}", unused);
+        }
+        assert_eq!(ctx.native_call_stats(), vec![("Math.max".to_string(), 1)]);
+
+        let report = ctx.opcode_stats_report();
+        assert!(report.starts_with("opcodes: "), "{}", report);
+        assert!(report.contains("\n  Lt "), "{}", report);
+        assert!(
+            report.contains("natives: 1 calls\n  Math.max"),
+            "{}",
+            report
+        );
+        assert!(!report.contains("Throw"), "{}", report);
+
+        ctx.reset_opcode_stats();
+        assert!(ctx.opcode_stats().iter().all(|&(_, count)| count == 0));
+        assert!(ctx.native_call_stats().is_empty());
+    }
+
+    #[test]
+    fn test_host_errors_pick_the_thrown_error_type() {
+        fn fail(scope: &mut NativeScope, _: Value, args: &[Value]) -> Result<Value, HostError> {
+            let kind = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+            Err(match kind {
+                0 => HostError::Type("not a sensor".to_string()),
+                1 => HostError::Range("channel 9 does not exist".to_string()),
+                2 => HostError::Reference("sensor is not defined".to_string()),
+                3 => HostError::Syntax("bad unit".to_string()),
+                // A plain message is a TypeError, even if it looks prefixed
+                4 => "RangeError: looks typed".into(),
+                #[allow(deprecated)]
+                5 => HostError::from_prefixed("RangeError: legacy".to_string()),
+                _ => {
+                    let callback = args.get(1).copied().unwrap_or_default();
+                    return scope.call(callback, Value::undefined(), &[]);
+                }
+            })
+        }
+
+        let mut ctx = Context::new(64 * 1024);
+        ctx.register_function("fail", fail, 2);
+        let result = ctx
+            .eval(
+                "var out = [];
+                 for (var i = 0; i < 6; i = i + 1) {
+                     try { fail(i); } catch (e) { out.push(e.name + ': ' + e.message); }
+                 }
+                 function thrower() { throw { code: 7 }; }
+                 try { fail(6, thrower); } catch (e) { out.push(e.code); }
+                 out;",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[TypeError: not a sensor, RangeError: channel 9 does not exist, \
+             ReferenceError: sensor is not defined, SyntaxError: bad unit, \
+             TypeError: RangeError: looks typed, RangeError: legacy, 7]"
+        );
+    }
+
+    #[test]
+    fn test_builtin_errors_keep_their_type() {
+        fn fail(_: &mut Interpreter, _: Value, args: &[Value]) -> Result<Value, HostError> {
+            match args.first().and_then(|v| v.to_i32()) {
+                Some(0) => Err("RangeError: looks typed".into()),
+                _ => Err(HostError::Range("out of range".to_string())),
+            }
+        }
+
+        let mut ctx = Context::new(64 * 1024);
+        register_builtin(&mut ctx, "fail", fail, 1);
+        let result = ctx
+            .eval(
+                "var out = [];
+                 try { fail(0); } catch (e) { out.push(e.name + ': ' + e.message); }
+                 try { fail(1); } catch (e) { out.push(e.name + ': ' + e.message); }
+                 try { [1].map(function () { throw { code: 7 }; }); } catch (e) { out.push(e.code); }
+                 try { (10).toString(1); } catch (e) { out.push(e.name); }
+                 out;",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[TypeError: RangeError: looks typed, RangeError: out of range, 7, RangeError]"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_native_panics_become_catchable_internal_errors() {
+        fn boom(_: &mut NativeScope, _: Value, args: &[Value]) -> Result<Value, HostError> {
+            match args.first().and_then(|v| v.to_i32()) {
+                Some(1) => panic!("sensor {} unplugged", 1),
+                Some(2) => std::panic::panic_any(42),
+                _ => panic!("boom"),
+            }
+        }
+        fn call_back(
+            scope: &mut NativeScope,
+            _: Value,
+            args: &[Value],
+        ) -> Result<Value, HostError> {
+            let callback = args.first().copied().unwrap_or_default();
+            scope.call(callback, Value::undefined(), &[])
+        }
+
+        let mut ctx = Context::new(64 * 1024);
+        ctx.register_function("boom", boom, 1);
+        ctx.register_function("callBack", call_back, 1);
+        let result = ctx
+            .eval(
+                "function describe(e) { return e.name + ': ' + e.message; }
+                 var out = [];
+                 try { boom(0); } catch (e) { out.push(describe(e)); }
+                 try { boom(1); } catch (e) { out.push(describe(e)); }
+                 try { boom(2); } catch (e) { out.push(describe(e)); }
+                 out;",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[InternalError: native function panicked: boom, \
+             InternalError: native function panicked: sensor 1 unplugged, \
+             InternalError: native function panicked: unknown cause]"
+        );
+
+        // A panic inside script code a native is running unwinds through
+        // both; the outer try still catches it and the frames in between
+        // are gone
+        let result = ctx
+            .eval(
+                "var log = [];
+                 function step(x) { log.push(x); if (x === 2) { boom(0); } return x; }
+                 function run() { return [1, 2, 3].map(step); }
+                 var caught = 'none';
+                 try { callBack(run); } catch (e) { caught = e.message; }
+                 [caught, log.length];",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[native function panicked: boom, 2]");
+
+        // Uncaught, it fails the eval, and the context carries on
+        assert!(ctx.eval("boom(0);").is_err());
+        let result = ctx.eval("function add(a, b) { return a + b; } add(2, 3);");
+        assert_eq!(result.unwrap().to_i32(), Some(5));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_host_class_panics_keep_the_instance() {
+        struct Gauge(i32);
+        let mut ctx = Context::new(64 * 1024);
+        ctx.register_class(
+            "Gauge",
+            ClassDef::new(|_, args| match args.first().and_then(|v| v.to_i32()) {
+                Some(n) if n < 0 => panic!("negative gauge"),
+                n => Ok(Gauge(n.unwrap_or(0))),
+            })
+            .method("bump", |_, gauge, args| {
+                if args.is_empty() {
+                    panic!("bump needs an amount");
+                }
+                gauge.0 += args[0].to_i32().unwrap_or(0);
+                Ok(Value::int(gauge.0))
+            }),
+        );
+        let result = ctx
+            .eval(
+                "var out = [];
+                 try { new Gauge(-1); } catch (e) { out.push(e.message); }
+                 var g = new Gauge(1);
+                 try { g.bump(); } catch (e) { out.push(e.message); }
+                 out.push(g.bump(2));
+                 out;",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[native function panicked: negative gauge, native function panicked: bump needs an amount, 3]"
+        );
+    }
+
+    #[test]
+    fn test_hardened_context_blocks_prototype_pollution() {
+        let attack = "var out = [];
+             function attempt(name, f) {
+                 try { f.run(); out.push(name + ': ok'); } catch (e) { out.push(name + ': ' + e.name); }
+             }
+             attempt('Object.prototype', { run() { Object.prototype.isAdmin = true; } });
+             attempt('Array.prototype', { run() { Array.prototype['map'] = 0; } });
+             attempt('assign', { run() { Object.assign(String.prototype, { trim: 0 }); } });
+             attempt('proto write', { run() { var o = {}; o.__proto__ = { isAdmin: true }; } });
+             attempt('proto read', { run() { var o = {}; return o['__pro' + 'to__']; } });
+             attempt('proto literal', { run() { return { __proto__: { isAdmin: true } }; } });
+             attempt('defineProperty', { run() { Object.defineProperty({}, '__proto__', { value: 1 }); } });
+             out;";
+
+        // Unhardened, every step goes through: builtin writes are silently
+        // dropped and __proto__ is an ordinary key, so once prototype chains
+        // are followed these are the writes that would reach other objects
+        let reach_constructor = "var o = { constructor: Object }; o.constructor === Object;";
+        let mut ctx = Context::new(64 * 1024);
+        assert_eq!(ctx.eval(reach_constructor).unwrap().to_bool(), Some(true));
+        let result = ctx.eval(attack).unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[Object.prototype: ok, Array.prototype: ok, assign: ok, \
+             proto write: ok, proto read: ok, proto literal: ok, defineProperty: ok]"
+        );
+
+        let mut ctx = ContextBuilder::new(64 * 1024).harden(true).build();
+        assert_eq!(ctx.eval(reach_constructor).unwrap().to_bool(), Some(false));
+        let result = ctx.eval(attack).unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[Object.prototype: TypeError, Array.prototype: TypeError, assign: TypeError, proto write: TypeError, proto read: TypeError, \
+             proto literal: TypeError, defineProperty: TypeError]"
+        );
+        let result = ctx
+            .eval("var o = { constructor: 1 }; [typeof o.constructor, typeof o['constructor']];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[undefined, undefined]");
+        // Arrays and functions are no way around it either
+        let result = ctx
+            .eval(
+                "var a = [1]; a.constructor = Object;
+                 function f() {} f.constructor = Object;
+                 var reached = [];
+                 [[], a, f, function () {}, [].map].forEach(function (v) {
+                     var c = v.constructor;
+                     reached.push(c === undefined ? 'blocked' : typeof c.prototype);
+                 });
+                 reached.push(typeof a['constructor']);
+                 reached;",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[blocked, blocked, blocked, blocked, blocked, undefined]"
+        );
+        let err = ctx.eval("Math.PI = 3;").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Cannot assign to property 'PI' of a frozen builtin object"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_hardened_context_runs_ordinary_scripts() {
+        let mut ctx = ContextBuilder::new(64 * 1024).harden(true).build();
+        let result = ctx
+            .eval(
+                "function double(x) { return x * 2; }
+                 var doubled = [1, 2, 3].map(double);
+                 var user = { name: '  ada ', tags: [] };
+                 user.name = user.name.trim();
+                 user.tags.push('admin'.toUpperCase());
+                 user['level'] = Math.max(1, 2);
+                 globalThis.seen = true;
+                 var copy = Object.assign({}, user);
+                 [doubled.join(','), copy.name, copy.tags[0], copy.level,
+                  user.hasOwnProperty('name'), Object.keys(user).length];",
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[2,4,6, ada, ADMIN, 2, true, 3]");
+    }
+
+    #[test]
+    fn test_execution_runs_in_slices() {
+        let source = "function fib(n) {
+                 var a = 0; var b = 1;
+                 for (var i = 0; i < n; i = i + 1) { var t = (a + b) % 1000007; a = b; b = t; }
+                 return a;
+             }
+             var parts = [];
+             var total = 0;
+             for (var k = 0; k < 120; k = k + 1) {
+                 total = (total + fib(k)) % 1000007;
+                 if (k % 30 === 0) { parts.push(total); }
+             }
+             parts.join('/') + ':' + total;";
+
+        let mut ctx = ContextBuilder::new(256 * 1024).profile(true).build();
+        let (expected, report) = ctx.eval_profiled(source);
+        let expected = ctx.get_string(expected.unwrap()).unwrap().to_string();
+        let slice = report.instructions.div_ceil(10);
+
+        let mut ctx = Context::new(256 * 1024);
+        let mut execution = ctx.start(source).unwrap();
+        let mut slices = 0;
+        let result = loop {
+            slices += 1;
+            match execution.run_for(slice) {
+                StepResult::Completed(value) => break value,
+                StepResult::Yielded => {}
+                StepResult::Failed(err) => panic!("{}", err),
+            }
+            assert!(!execution.is_finished());
+            // Unrelated work between slices, including a collection, leaves
+            // the paused script alone
+            let ctx = execution.context();
+            let quick = ctx
+                .eval("function inc(x) { return x + 1; } [1, 2, 3].map(inc).length;")
+                .unwrap();
+            assert_eq!(quick.to_i32(), Some(3));
+            assert!(ctx.eval("throw new Error('unrelated');").is_err());
+            ctx.gc();
+        };
+        assert_eq!(slices, 10);
+        assert_eq!(ctx_string(&mut execution, result), expected);
+        // The outcome repeats once finished
+        assert!(matches!(execution.run_for(1), StepResult::Completed(v) if v == result));
+        drop(execution);
+        // Its globals were published when it finished
+        let total = ctx.eval("total;").unwrap().to_i32().unwrap();
+        assert!(expected.ends_with(&format!(":{}", total)), "{}", expected);
+
+        fn ctx_string(execution: &mut Execution, value: Value) -> String {
+            execution.context().get_string(value).unwrap().to_string()
+        }
+    }
+
+    #[test]
+    fn test_execution_fires_due_timers_and_reports_failures() {
+        let clock = Rc::new(crate::vm::ManualClock::new(1_000));
+        let mut ctx = Context::new(64 * 1024);
+        ctx.set_clock(clock.clone());
+        let mut execution = ctx
+            .start(
+                "var log = [];
+                 function spin(tag) { for (var i = 0; i < 100; i = i + 1) { } log.push(tag); }
+                 function now() { spin('now'); }
+                 function later() { spin('later'); }
+                 setTimeout(now, 0);
+                 setTimeout(later, 50);
+                 spin('script');
+                 log.length;",
+            )
+            .unwrap();
+        let mut slices = 0;
+        let result = loop {
+            slices += 1;
+            match execution.run_for(50) {
+                StepResult::Completed(value) => break value,
+                StepResult::Yielded => {}
+                StepResult::Failed(err) => panic!("{}", err),
+            }
+        };
+        // The timer's callback was sliced as well
+        assert!(slices > 5, "{}", slices);
+        assert_eq!(result.to_i32(), Some(1));
+        drop(execution);
+        assert_eq!(
+            ctx.eval("log.join();").map(|v| ctx.inspect(v)).unwrap(),
+            "script,now"
+        );
+        assert_eq!(ctx.pending_timers(), 1);
+
+        // An uncaught exception ends the execution and leaves the context usable
+        let mut execution = ctx
+            .start("var before = 1; function f() { null.x(); } f();")
+            .unwrap();
+        let err = match execution.run_for(1_000) {
+            StepResult::Failed(err) => err,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(err.name, "TypeError");
+        assert!(matches!(execution.run_for(1_000), StepResult::Failed(_)));
+        drop(execution);
+
+        // Dropping an unfinished execution abandons it
+        let mut execution = ctx
+            .start("var forever = 0; while (true) { forever = forever + 1; }")
+            .unwrap();
+        assert!(matches!(execution.run_for(1_000), StepResult::Yielded));
+        drop(execution);
+        assert_eq!(ctx.eval("1 + 1;").unwrap().to_i32(), Some(2));
+    }
+
+    #[test]
+    fn test_own_property_descriptors() {
+        let mut ctx = Context::new(256 * 1024);
+        let result = ctx
+            .eval(
+                r#"
+            var o = { a: 1 };
+            Object.defineProperty(o, "fixed", { value: 2, writable: false });
+            function getter() { return 3; }
+            Object.defineProperty(o, "acc", { get: getter, enumerable: true });
+            var arr = [7, 8];
+            function d(x, k) { return JSON.stringify(Object.getOwnPropertyDescriptor(x, k)); }
+            return [d(o, "a"), d(o, "fixed"), d(o, "acc"),
+              typeof Object.getOwnPropertyDescriptor(o, "missing"),
+              d(arr, 0), d(arr, "length"), d("ab", "length"),
+              JSON.stringify(Object.getOwnPropertyDescriptors({ x: 1 })),
+              Object.getOwnPropertyDescriptor(o, "a") !== Object.getOwnPropertyDescriptor(o, "a")];
+        "#,
+            )
+            .unwrap();
+        let lines = ctx.inspect(result);
+        let lines: Vec<&str> = lines
+            .trim_matches(|c| c == '[' || c == ']')
+            .split(", ")
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"value":1,"writable":true,"enumerable":true,"configurable":true}"#,
+                // Only enumerability is tracked, so the property stays writable
+                r#"{"value":2,"writable":true,"enumerable":false,"configurable":true}"#,
+                // Accessors are stored as data properties holding undefined
+                r#"{"writable":true,"enumerable":true,"configurable":true}"#,
+                "undefined",
+                r#"{"value":7,"writable":true,"enumerable":true,"configurable":true}"#,
+                r#"{"value":2,"writable":true,"enumerable":false,"configurable":false}"#,
+                r#"{"value":2,"writable":false,"enumerable":false,"configurable":false}"#,
+                r#"{"x":{"value":1,"writable":true,"enumerable":true,"configurable":true}}"#,
+                "true",
+            ]
+        );
+
+        let result = ctx
+            .eval(
+                r#"
+            var p = { a: 1 };
+            Object.defineProperty(p, "hidden", { value: 2, enumerable: false });
+            return [p.propertyIsEnumerable("a"), p.propertyIsEnumerable("hidden"),
+              p.propertyIsEnumerable("missing"), [1].propertyIsEnumerable(0),
+              [1].propertyIsEnumerable("length")];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[true, false, false, true, false]");
+
+        let err = ctx
+            .eval("Object.getOwnPropertyDescriptor(null, 'a');")
+            .unwrap_err();
+        assert!(err.to_string().contains("TypeError"), "{}", err);
+    }
+
+    #[test]
+    fn test_array_element_keys() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval(
+                r#"
+            var arr = [1, 2, 3];
+            var before = arr[-1];
+            arr[-1] = 9;
+            arr["01"] = 5;
+            arr["2"] = 7;
+            return [before, arr[-1], arr.length, arr[2], arr["2"], arr["01"],
+              Object.keys(arr).join()];
+        "#,
+            )
+            .unwrap();
+        // Negative and non-canonical keys are named properties, "2" is an index
+        assert_eq!(
+            ctx.inspect(result),
+            "[undefined, 9, 3, 7, 7, 5, 0,1,2,-1,01]"
+        );
+
+        let result = ctx
+            .eval(
+                r#"
+            var ta = new Uint8Array(10);
+            ta[999] = 1;
+            ta[-1] = 4;
+            ta["1.5"] = 4;
+            ta["01"] = 4;
+            ta["3"] = 4;
+            return [ta.length, ta[999], ta[-1], ta["1.5"], ta["01"], ta[3], Object.keys(ta).length];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[10, undefined, undefined, undefined, 4, 4, 11]"
+        );
+    }
+
+    #[test]
+    fn test_array_growth_past_the_limits_throws() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval(
+                r#"
+            var big = [1];
+            big[1000000000] = 1;
+            var dense = [];
+            var error;
+            try {
+                for (var i = 0; i < 1000000; i = i + 1) { dense[i] = i; }
+            } catch (e) { error = e.message; }
+            [error, big.length];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[out of memory, 1000000001]");
+
+        let mut ctx = Context::builder(64 * 1024).max_array_length(100).build();
+        let result = ctx
+            .eval(
+                r#"
+            var arr = [];
+            var errors = [];
+            arr[99] = 1;
+            try { arr[100] = 1; } catch (e) { errors.push(e.name + ": " + e.message); }
+            try { arr.length = 101; } catch (e) { errors.push(e.message); }
+            try { arr.push(1); } catch (e) { errors.push(e.message); }
+            [arr.length, errors.join("; ")];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[100, RangeError: Invalid array length; Invalid array length; Invalid array length]"
+        );
+    }
+
+    #[test]
+    fn test_builtin_kinds_convert_and_iterate_consistently() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval(
+                r#"
+            var chars = [];
+            for (var c of "hé") { chars.push(c); }
+            var bytes = 0;
+            for (var b of new Uint8Array(3)) { bytes = bytes + 1; }
+            var notIterable;
+            try { for (var x of 5) {} } catch (e) { notIterable = e.name; }
+            return [
+                "" ? "truthy" : "falsy", !"", +"12", +true,
+                String(new RangeError("r")), "" + new RegExp("a", "g"), String(Math),
+                chars.join("|"), bytes, notIterable,
+                [] instanceof Array, [] instanceof Object, new TypeError("t") instanceof Error,
+                new TypeError("t") instanceof RangeError, new Uint8Array(1) instanceof Uint8Array,
+                new Uint8Array(1) instanceof Int8Array, 1 instanceof Object
+            ];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[falsy, true, 12, 1, RangeError: r, /a/g, [object Math], h|é, 3, TypeError, \
+             true, true, true, false, true, false, false]"
+        );
+    }
+
+    #[test]
+    fn test_for_in_order_and_inherited_keys() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval(
+            "function keysOf(o) { var out = []; for (var k in o) { out.push(k); } return out.join(); }",
+        )
+        .unwrap();
+        // Integer keys first, ascending, then insertion order; an own
+        // property shadowing an inherited method is listed once and the
+        // inherited, non-enumerable ones not at all
+        let result = ctx
+            .eval("keysOf({ b: 1, 10: 1, toString: 2, a: 1, 2: 1 });")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "2,10,b,toString,a");
+        let result = ctx.eval("Object.keys({ z: 1, 0: 1 }).join();").unwrap();
+        assert_eq!(ctx.inspect(result), "0,z");
+
+        let mut ctx = Context::builder(64 * 1024).harden(true).build();
+        let result = ctx
+            .eval(
+                r#"
+            var o = { shown: 1 };
+            Object.defineProperty(o, "hidden", { value: 2, enumerable: false });
+            var keys = [];
+            for (var k in o) { keys.push(k); }
+            [keys.join(), o.hidden];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[shown, 2]");
+    }
+
+    #[test]
+    fn test_deleted_array_elements_are_holes() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval(
+            "function keysOf(o) { var out = []; for (var k in o) { out.push(k); } return out.join(); }",
+        )
+        .unwrap();
+        let result = ctx
+            .eval(
+                r#"
+            var arr = [1, 2, 3];
+            delete arr[1];
+            [keysOf(arr), Object.keys(arr).join(), 1 in arr, arr.hasOwnProperty(1),
+              arr[1], arr.length, JSON.stringify(arr)];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[0,2, 0,2, false, false, undefined, 3, [1,null,3]]"
+        );
+
+        // Holes move with the elements and writing one fills it
+        let result = ctx
+            .eval(
+                r#"
+            var moved = [1, 2, 3, 4];
+            delete moved[1];
+            moved.unshift(0);
+            var afterUnshift = keysOf(moved);
+            moved.reverse();
+            var afterReverse = keysOf(moved);
+            moved[2] = 5;
+            [afterUnshift, afterReverse, keysOf(moved)];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[0,1,3,4, 0,1,3,4, 0,1,2,3,4]");
+    }
+
+    #[test]
+    fn test_strings_past_the_length_limit_throw() {
+        let mut ctx = Context::builder(256 * 1024).max_string_length(4096).build();
+        let result = ctx
+            .eval(
+                r#"
+            var errors = [];
+            var ok = "x".repeat(4096).length;
+            function attempt(name, f) {
+                try { f(); errors.push(name + " ok"); } catch (e) { errors.push(name + " " + e.name + ": " + e.message); }
+            }
+            function repeat() { return "x".repeat(4097); }
+            function pad() { return "x".padStart(5000); }
+            function padEnd() { return "x".padEnd(5000, "ab"); }
+            function join() { var a = []; a[499] = 1; return a.join("0123456789"); }
+            function concat() { return "x".concat("y".repeat(4000), "z".repeat(100)); }
+            function add() { var s = "y".repeat(3000); return s + s; }
+            function json() { return JSON.stringify(["y".repeat(3000), "z".repeat(3000)]); }
+            attempt("repeat", repeat);
+            attempt("pad", pad);
+            attempt("padEnd", padEnd);
+            attempt("join", join);
+            attempt("concat", concat);
+            attempt("add", add);
+            attempt("json", json);
+            [ok, errors.join("; "), "still " + "fine".repeat(2)];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[4096, repeat RangeError: Invalid string length; \
+             pad RangeError: Invalid string length; \
+             padEnd RangeError: Invalid string length; \
+             join RangeError: Invalid string length; \
+             concat RangeError: Invalid string length; \
+             add RangeError: Invalid string length; \
+             json RangeError: Invalid string length, still finefine]"
+        );
+        assert_eq!(ctx.interpreter.max_string_length(), 4096);
+    }
+
+    #[test]
+    fn test_builder_limits_compile_work() {
+        use crate::parser::compiler::CompileLimit;
+        use crate::vm::clock::ManualClock;
+
+        let mut ctx = Context::builder(64 * 1024).max_compile_tokens(100).build();
+        let source = "x = 1;\n".repeat(20);
+        assert!(ctx.eval(&source).is_ok());
+        match ctx.eval(&"x = 1;\n".repeat(50)) {
+            Err(EvalError::CompileError(CompileError::BudgetExceeded { limit, pos })) => {
+                assert_eq!(limit, CompileLimit::Tokens(100));
+                assert_eq!(pos.line, 26);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let mut ctx = Context::builder(64 * 1024)
+            .max_compile_bytecode(400)
+            .build();
+        assert!(ctx.eval(&source).is_ok());
+        match ctx.eval(&"x = [1, 2, 3, 4, 5, 6, 7, 8];\n".repeat(40)) {
+            Err(EvalError::CompileError(CompileError::BudgetExceeded { limit, .. })) => {
+                assert_eq!(limit, CompileLimit::Bytecode(400))
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // A clock that never moves never runs out of compile time
+        let mut ctx = Context::builder(64 * 1024)
+            .compile_timeout(Duration::from_millis(1))
+            .build();
+        ctx.set_clock(Rc::new(ManualClock::new(0)));
+        assert!(ctx.eval(&"x = 1;\n".repeat(1000)).is_ok());
+    }
+
+    #[test]
+    fn test_reflect_works_across_receiver_kinds() {
+        struct Point(i32);
+        let mut ctx = ContextBuilder::new(64 * 1024).harden(true).build();
+        ctx.register_class(
+            "Point",
+            ClassDef::new(|_, args| Ok(Point(args.first().and_then(|v| v.to_i32()).unwrap_or(0))))
+                .getter("x", |_, point| Value::int(point.0)),
+        );
+        let result = ctx
+            .eval(
+                r#"
+            var obj = { a: 1 };
+            Object.defineProperty(obj, "hidden", { value: 2, enumerable: false });
+            var arr = [1, 2];
+            var p = new Point(7);
+            var key = "a";
+            var out = [];
+            out.push([Reflect.has(obj, key), Reflect.has(obj, "b"), Reflect.has(obj, "hasOwnProperty")].join());
+            out.push([Reflect.has(arr, 1), Reflect.has(arr, 2), Reflect.has(arr, "length")].join());
+            out.push([Reflect.has(p, "x"), Reflect.has(p, "y")].join());
+            out.push([Reflect.get(obj, key), Reflect.get(arr, 0), Reflect.get(arr, "length"), Reflect.get(p, "x")].join());
+            out.push([Reflect.set(obj, "b", 3), Reflect.set(arr, 2, 3), Reflect.set(p, "tag", "t")].join());
+            out.push([obj.b, arr.length, p.tag].join());
+            out.push([Reflect.ownKeys(obj), Reflect.ownKeys(arr), Reflect.ownKeys(p)].join("|"));
+            function f(a, b) { return a; }
+            out.push([Reflect.ownKeys(f), Object.keys(f).length].join("|"));
+            out.push([Reflect.set(Math, "max", 0), typeof Math.max, Reflect.set(f, "x", 1)].join());
+            try { Reflect.get(1, "a"); } catch (e) { out.push(e.name + ": " + e.message); }
+            out.join("; ");
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.get_string(result).unwrap(),
+            "true,false,true; true,false,true; true,false; 1,1,2,7; true,true,true; \
+             3,3,t; a,hidden,b|0,1,2,length|tag; length,name|0; false,function,false; \
+             TypeError: Reflect.get called on non-object"
+        );
+    }
+
+    #[test]
+    fn test_catch_bindings_are_scoped_to_the_catch_block() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval(
+                r#"
+            var out = [];
+            try { throw 1; } catch { out.push("no binding"); }
+            var e = "outer";
+            try { throw "a"; } catch (e) {
+                try { throw "b"; } catch (e) { out.push(e); }
+                out.push(e);
+            }
+            out.push(e);
+            function local() {
+                var e = "local";
+                try { throw "c"; } catch (e) { out.push(e); }
+                return e;
+            }
+            out.push(local());
+            out;
+        "#,
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[no binding, b, a, outer, c, local]");
+
+        // Once the catch block is done the exception is no longer reachable
+        // from the function's frame
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+        let finalized = Rc::new(Cell::new(0));
+        let count = Rc::clone(&finalized);
+        let res = ctx.create_userdata_with_finalizer(
+            "socket",
+            Box::new(move |_: &str| count.set(count.get() + 1)),
+        );
+        ctx.set_global("res", res);
+        ctx.eval(
+            "function handle() {
+                 var thrown = res;
+                 res = 0;
+                 try { throw thrown; } catch (e) { thrown = 0; }
+                 gc();
+             }
+             handle();",
+        )
+        .unwrap();
+        assert_eq!(finalized.get(), 1);
+    }
+
+    #[test]
+    fn test_sparse_arrays_only_pay_for_present_elements() {
+        let mut ctx = Context::new(64 * 1024);
+        let before = ctx.memory_stats().used;
+        let result = ctx
+            .eval(
+                r#"
+            var a = [];
+            a[1000000] = 1;
+            a[5] = 2;
+            a[500000] = 3;
+            [a.length, a[1000000], a[6], Object.keys(a).join(","), a.indexOf(3)];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[1000001, 1, undefined, 5,500000,1000000, 500000]"
+        );
+        // A dense million-element array would need megabytes
+        assert!(ctx.memory_stats().used - before < 4096);
+
+        // Truncating brings the array back to a plain vector
+        let result = ctx.eval("a.length = 6; a.push(7); a;").unwrap();
+        assert_eq!(ctx.inspect(result), "[<5 empty items>, 2, 7]");
+        assert!(ctx.memory_stats().used - before < 4096);
+    }
+
+    #[test]
+    fn test_sparse_and_dense_arrays_behave_the_same() {
+        let mut ctx = Context::new(256 * 1024);
+        let result = ctx
+            .eval(
+                r#"
+            var seen = [];
+            function note(v, i) { seen.push(i + ":" + v); }
+            function double(v) { return v * 2; }
+            function big(v) { return v > 1; }
+            function sum(acc, v) { return acc + v; }
+            function describe(a) {
+                var out = [a.length, a[2000], a[1], 1 in a, 2000 in a];
+                var keys = [];
+                for (var k in a) { keys.push(k); }
+                out.push(keys.join(","), Object.keys(a).join(","), a.join("-").length);
+                var mapped = a.map(double);
+                out.push(mapped.length, mapped[2000], 1 in mapped);
+                out.push(a.filter(big).join(","), a.reduce(sum), a.reduceRight(sum, 10));
+                out.push(a.indexOf(3), a.lastIndexOf(1), a.indexOf(undefined));
+                out.push(a.some(big), a.every(big), JSON.stringify(a).length);
+                out.push(a.concat([7]).length, a.slice(1999, 2001).join(","));
+                out.push(a.push(4), a.pop(), a.pop(), a.length);
+                a.length = 2001;
+                out.push(Object.keys(a).join(","));
+                delete a[2000];
+                out.push(Object.keys(a).join(","), a.length);
+                return out.join(" ");
+            }
+
+            // Elements at 0, 2000 and 3000: written far apart, or filled in
+            // and deleted
+            var sparse = [];
+            sparse[0] = 1;
+            sparse[2000] = 2;
+            sparse[3000] = 3;
+            var dense = [];
+            for (var i = 0; i <= 3000; i = i + 1) { dense.push(i); }
+            for (var i = 1; i < 3000; i = i + 1) { if (i != 2000) { delete dense[i]; } }
+            dense[0] = 1;
+            dense[2000] = 2;
+            dense[3000] = 3;
+
+            sparse.forEach(note);
+            dense.forEach(note);
+            [seen.join(","), describe(sparse), describe(dense)];
+        "#,
+            )
+            .unwrap();
+        let items = ctx.get_array(result).unwrap();
+        let text = |v| ctx.get_string(v).unwrap().to_string();
+        assert_eq!(text(items[0]), "0:1,2000:2,3000:3,0:1,2000:2,3000:3");
+        assert_eq!(
+            text(items[1]),
+            "3001 2  false true 0,2000,3000 0,2000,3000 3003 3001 4 false 2,3 6 16 \
+             3000 0 -1 true false 14997 3002 ,2 3002 4 3 3000 0,2000 0 2001"
+        );
+        assert_eq!(text(items[2]), text(items[1]));
+    }
+
+    #[test]
+    fn test_every_kind_of_value_has_one_truthiness() {
+        let mut ctx = Context::new(64 * 1024);
+        ctx.eval(
+            r#"
+            function f() {}
+            function check(x) {
+                var viaIf = 0;
+                if (x) { viaIf = 1; } else { viaIf = 0; }
+                var viaWhile = 0;
+                while (x && viaWhile == 0) { viaWhile = 1; }
+                var viaFor = 0;
+                for (; x && viaFor == 0;) { viaFor = 1; }
+                var viaOr = 0;
+                if (x || 0) { viaOr = 1; }
+                return [!!x, x ? 1 : 0, viaIf, viaWhile, viaFor, viaOr, Boolean(x), !x].join(",");
+            }
+        "#,
+        )
+        .unwrap();
+
+        for (expr, truthy) in [
+            ("false", false),
+            ("true", true),
+            ("0", false),
+            ("-0", false),
+            ("1", true),
+            ("-1", true),
+            ("''", false),
+            ("'0'", true),
+            ("' '", true),
+            ("'false'", true),
+            ("null", false),
+            ("undefined", false),
+            ("[]", true),
+            ("[0]", true),
+            ("{}", true),
+            ("f", true),
+            ("parseInt", true),
+            ("Math", true),
+            ("new Error('')", true),
+            ("new RegExp('a')", true),
+            ("new Date(0)", true),
+            ("new Uint8Array(0)", true),
+            ("new ArrayBuffer(0)", true),
+        ] {
+            let result = ctx.eval(&format!("check({expr});")).unwrap();
+            let expected = if truthy {
+                "true,1,1,1,1,1,true,false"
+            } else {
+                "false,0,0,0,0,0,false,true"
+            };
+            assert_eq!(ctx.get_string(result).as_deref(), Some(expected), "{expr}");
+        }
+
+        // && and || give back one of their operands, not a boolean
+        let result = ctx
+            .eval("[0 && f, '' || 'x', 2 && 'y', null || 0, 1 && 0 || 3];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[0, x, y, 0, 3]");
+    }
+
+    #[test]
+    fn test_host_settles_a_promise_returned_by_a_native() {
+        use core::cell::RefCell;
+
+        thread_local! {
+            static HANDLES: RefCell<Vec<ResolverHandle>> = const { RefCell::new(Vec::new()) };
+        }
+        fn fetch(
+            scope: &mut NativeScope,
+            _this: Value,
+            _args: &[Value],
+        ) -> Result<Value, HostError> {
+            let (promise, handle) = scope.create_pending_promise();
+            HANDLES.with(|h| h.borrow_mut().push(handle));
+            Ok(promise)
+        }
+
+        let mut ctx = Context::new(64 * 1024);
+        ctx.register_function("fetch", fetch, 0);
+        ctx.eval(
+            r#"
+            var results = [];
+            var failures = [];
+            function store(v) { results.push(v); return v + 1; }
+            function fail(e) { failures.push(e); }
+            function chained(v) { results.push(v); }
+            fetch().then(store).then(chained);
+            fetch().then(undefined, fail);
+        "#,
+        )
+        .unwrap();
+        let handles: Vec<ResolverHandle> = HANDLES.with(|h| h.borrow_mut().drain(..).collect());
+        assert_eq!(handles.len(), 2);
+        let global = |ctx: &Context, name| ctx.inspect(ctx.get_global(name).unwrap());
+
+        // Nothing happens until the jobs run
+        assert!(handles[0].resolve(Value::int(41)));
+        assert_eq!(global(&ctx, "results"), "[]");
+        assert_eq!(ctx.run_pending_jobs().unwrap(), 2);
+        assert_eq!(global(&ctx, "results"), "[41, 42]");
+
+        // Only the first outcome counts, and handles can cross threads
+        ctx.eval("var reason = 'off' + 'line';").unwrap();
+        let reason = ctx.get_global("reason").unwrap();
+        let rejecter = std::thread::spawn({
+            let handle = handles.into_iter().nth(1).unwrap();
+            move || (handle.reject(reason), handle.resolve(Value::int(1)))
+        });
+        assert_eq!(rejecter.join().unwrap(), (true, false));
+        assert_eq!(ctx.run_pending_jobs().unwrap(), 1);
+        assert_eq!(global(&ctx, "failures"), "[offline]");
+        assert_eq!(ctx.run_pending_jobs().unwrap(), 0);
+
+        // Handlers attached after settling still run, and throwing rejects
+        let result = ctx
+            .eval(
+                r#"
+            var late = [];
+            function record(v) { late.push(v); throw "boom"; }
+            function caught(e) { late.push(e); }
+            var p = fetch();
+            p.then(record).then(undefined, caught);
+            Object.prototype.toString.call(p);
+        "#,
+            )
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[object Promise]");
+        let handle = HANDLES.with(|h| h.borrow_mut().pop()).unwrap();
+        handle.resolve(Value::int(7));
+        ctx.run_pending_jobs().unwrap();
+        ctx.eval("var q = p.then(record); q['catch'].call(q, caught);")
+            .unwrap();
+        ctx.run_pending_jobs().unwrap();
+        assert_eq!(global(&ctx, "late"), "[7, boom, 7, boom]");
+
+        // A handle outliving its context does nothing
+        let (_, orphan) = ctx.create_pending();
+        drop(ctx);
+        assert!(!orphan.resolve(Value::int(1)));
+    }
+
+    #[test]
+    fn test_arrays_stop_at_the_maximum_length() {
+        let mut ctx = Context::new(64 * 1024);
+        assert_eq!(
+            ctx.interpreter.max_array_length(),
+            Interpreter::MAX_ARRAY_LENGTH
+        );
+        let result = ctx
+            .eval(
+                r#"
+            var errors = [];
+            function attempt(f) {
+                try { f(); errors.push('ok'); } catch (e) { errors.push(e.name); }
+            }
+            var last = [];
+            last[1073741822] = 1;
+            var full = Array(1073741823);
+            function indexWrite() { var a = []; a[1073741823] = 1; }
+            function lengthWrite() { var a = []; a.length = 1073741824; }
+            function push() { full.push(1); }
+            function unshift() { full.unshift(1); }
+            function concat() { full.concat([1]); }
+            function construct() { Array(1073741824); }
+            function negative() { new Array(-1); }
+            attempt(indexWrite);
+            attempt(lengthWrite);
+            attempt(push);
+            attempt(unshift);
+            attempt(concat);
+            attempt(construct);
+            attempt(negative);
+            [last.length, full.length, errors.join(' ')];
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[1073741823, 1073741823, RangeError RangeError RangeError RangeError RangeError RangeError RangeError]"
+        );
+
+        let result = ctx
+            .eval("var a = new Array(3); var b = Array(1, 2); [a.length, 0 in a, b, Array('x')];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[3, false, [1, 2], [x]]");
+    }
+
+    #[test]
+    fn test_engine_diff_reports_changes_by_path() {
+        let mut ctx = Context::builder(64 * 1024)
+            .enable_engine_module(true)
+            .build();
+        let result = ctx
+            .eval(
+                r#"
+            function diff(a, b) { return JSON.stringify(__engine.diff(a, b)); }
+            function describe(changes) {
+                var c = changes[0];
+                return changes.length + " " + c.path + " " + c.op + " " + c.value[1];
+            }
+            var shared = { id: 7, tags: ["x", "y"] };
+            var cyclic = { n: 1 };
+            cyclic.self = cyclic;
+            var cyclic2 = { n: 2 };
+            cyclic2.self = cyclic2;
+            [
+                diff({ a: { b: 1 } }, { a: { b: 1, c: 2 } }),
+                diff({ list: [1, 2, 3] }, { list: [1, 2] }),
+                diff({ a: { b: [0, { c: "x" }] } }, { a: { b: [0, { c: "y" }] } }),
+                diff({ a: [1, { b: "s" + "t" }], "k k": null }, { a: [1, { b: "st" }], "k k": null }),
+                diff({ one: shared, two: shared }, { one: shared, two: { id: 7, tags: ["x", "y"] } }),
+                diff({ gone: 1, kept: 2 }, { kept: 2, "new key": 3 }),
+                diff(new Uint8Array([1, 2]), new Uint8Array([1, 2])),
+                describe(__engine.diff({ t: new Uint8Array([1, 2]) }, { t: new Uint8Array([1, 3]) })),
+                diff(cyclic, cyclic2),
+                diff(1, "1"),
+            ].join("\n");
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.get_string(result).as_deref(),
+            Some(
+                "[{\"path\":\"a.c\",\"op\":\"add\",\"value\":2}]\n\
+                 [{\"path\":\"list[2]\",\"op\":\"remove\",\"value\":3}]\n\
+                 [{\"path\":\"a.b[1].c\",\"op\":\"change\",\"value\":\"y\"}]\n\
+                 []\n\
+                 []\n\
+                 [{\"path\":\"gone\",\"op\":\"remove\",\"value\":1},\
+                 {\"path\":\"[\\\"new key\\\"]\",\"op\":\"add\",\"value\":3}]\n\
+                 []\n\
+                 1 t change 3\n\
+                 [{\"path\":\"n\",\"op\":\"change\",\"value\":2}]\n\
+                 [{\"path\":\"\",\"op\":\"change\",\"value\":\"1\"}]"
+            )
+        );
+
+        let err = ctx
+            .eval(
+                "var a = {};
+                 var b = {};
+                 var i = 0;
+                 while (i < 100) { a = { d: a }; b = { d: b }; i = i + 1; }
+                 __engine.diff(a, b);",
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("RangeError"), "{}", err);
+        assert!(err.to_string().contains("nested deeper than 64 levels"));
+
+        assert!(Context::new(64 * 1024).get_global("__engine").is_none());
+    }
+
+    #[test]
+    fn test_native_receiver_policies() {
+        use crate::vm::ReceiverKind;
+
+        fn describe(scope: &mut NativeScope, this: Value, _: &[Value]) -> Result<Value, HostError> {
+            let text = match scope.array_len(this) {
+                Some(len) => format!("array of {}", len),
+                None if this.is_undefined() => "undefined".to_string(),
+                None => crate::vm::interpreter::receiver_description(this).to_string(),
+            };
+            Ok(scope.alloc_string(&text))
+        }
+
+        let mut ctx = Context::new(64 * 1024);
+        ctx.register_function_with_receiver("ignoring", describe, 0, Receiver::None);
+        ctx.register_function_with_receiver(
+            "onArray",
+            describe,
+            0,
+            Receiver::Required(ReceiverKind::Array),
+        );
+        ctx.register_function_with_receiver("optional", describe, 0, Receiver::Optional);
+        let result = ctx
+            .eval(
+                r#"
+            function attempt(f) {
+                try { return f(); } catch (e) { return e.name + ": " + e.message; }
+            }
+            function bareArray() { return onArray(); }
+            function numberArray() { return onArray.call(5); }
+            function bareIndexOf() { var indexOf = "abc".indexOf; return indexOf("b"); }
+            function bareGetTime() { var getTime = new Date(0).getTime; return getTime(); }
+            var holder = { ignoring: ignoring, optional: optional, parse: parseInt };
+            [
+                ignoring(), holder.ignoring(), ignoring.call([1, 2]),
+                onArray.call([1, 2, 3]), attempt(bareArray), attempt(numberArray),
+                optional(), holder.optional(), optional.call([1]),
+                holder.parse(12), "abc".indexOf("c"),
+                attempt(bareIndexOf), attempt(bareGetTime),
+            ].join("; ");
+        "#,
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.get_string(result).as_deref(),
+            Some(
+                "undefined; undefined; undefined; \
+                 array of 3; \
+                 TypeError: onArray called on undefined, expected an array; \
+                 TypeError: onArray called on number, expected an array; \
+                 undefined; object; array of 1; \
+                 12; 2; \
+                 TypeError: String.prototype.indexOf called on undefined, \
+                 expected a value other than null or undefined; \
+                 TypeError: Date.prototype.getTime called on undefined, expected a Date"
+            )
+        );
+        assert_eq!(ReceiverKind::TypedArray.description(), "a typed array");
+    }
+
+    #[test]
+    fn test_module_entries_register_lazily_and_warn_once_when_deprecated() {
+        use crate::vm::ConsoleLevel;
+        use alloc::rc::Rc;
+        use core::cell::{Cell, RefCell};
+
+        fn read_pin(_: &mut NativeScope, _: Value, args: &[Value]) -> Result<Value, HostError> {
+            Ok(Value::int(
+                args.first().and_then(|v| v.to_i32()).unwrap_or(0) * 10,
+            ))
+        }
+        fn version(_: &mut NativeScope, _: Value, _: &[Value]) -> Result<Value, HostError> {
+            Ok(Value::int(2))
+        }
+
+        let mut ctx = Context::new(64 * 1024);
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&lines);
+        ctx.set_console_sink(Some(Box::new(move |level, line: &str| {
+            sink.borrow_mut().push((level, line.to_string()));
+        })));
+        let inits = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&inits);
+        ctx.register_module(
+            ModuleBuilder::new("gpio")
+                .function("version", version, 0)
+                .lazy_fn("readPin", move || {
+                    counter.set(counter.get() + 1);
+                    read_pin
+                })
+                .deprecated("read", "readPin"),
+        );
+
+        let result = ctx
+            .eval("[gpio.version(), 'readPin' in gpio, 'read' in gpio, Object.keys(gpio)];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[2, true, true, [version]]");
+        assert_eq!(inits.get(), 0);
+        assert!(ctx.interpreter.get_native_func("gpio.version").is_some());
+        assert!(ctx.interpreter.get_native_func("gpio.readPin").is_none());
+
+        let result = ctx
+            .eval("[gpio.readPin(1), gpio.readPin(2), gpio.read(3), gpio.read(4), gpio.read(5)];")
+            .unwrap();
+        assert_eq!(ctx.inspect(result), "[10, 20, 30, 40, 50]");
+        assert_eq!(inits.get(), 1);
+        assert!(ctx.interpreter.get_native_func("gpio.readPin").is_some());
+        assert_eq!(
+            *lines.borrow(),
+            vec![(
+                ConsoleLevel::Warn,
+                "DeprecationWarning: gpio.read is deprecated, use gpio.readPin instead".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_natives_reject_arguments_of_the_wrong_type_uniformly() {
+        let mut ctx = Context::new(64 * 1024);
+        let result = ctx
+            .eval(
+                r#"
+            var errors = [];
+            function attempt(f) {
+                try { f(); } catch (e) { errors.push(e.name + ': ' + e.message); }
+            }
+            function pow() { Math.pow('2', 3); }
+            function abs() { Math.abs(); }
+            function imul() { Math.imul(2, null); }
+            function map() { [1].map(3); }
+            function sort() { [1].sort('up'); }
+            function slice() { 'abc'.slice(true); }
+            function startsWith() { 'abc'.startsWith(1); }
+            attempt(pow);
+            attempt(abs);
+            attempt(imul);
+            attempt(map);
+            attempt(sort);
+            attempt(slice);
+            attempt(startsWith);
+            errors;
+        "#,
+            )
+            .unwrap();
+        let errors = ctx.get_array(result).unwrap();
+        let errors: Vec<String> = errors
+            .iter()
+            .map(|&e| ctx.get_string(e).unwrap().to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "TypeError: Math.pow: argument 1 must be a number, got string",
+                "TypeError: Math.abs: argument 1 must be a number, got undefined",
+                "TypeError: Math.imul: argument 2 must be a number, got null",
+                "TypeError: Array.prototype.map: argument 1 must be a function, got number",
+                "TypeError: Array.prototype.sort: argument 1 must be a function, got string",
+                "TypeError: String.prototype.slice: argument 1 must be a number, got boolean",
+                "TypeError: String.prototype.startsWith: argument 1 must be a string, got number",
+            ]
+        );
+
+        // Optional arguments still default when missing or undefined
+        let result = ctx
+            .eval(
+                "[Math.imul(), 'abcd'.slice(1), 'abcd'.slice(undefined, 2), [1, [2, [3]]].flat(), \
+                 'ab'.repeat(), 'abc'.endsWith('c'), [3, 1, 2].sort(), [1, 2].map(Math.abs)];",
+            )
+            .unwrap();
+        assert_eq!(
+            ctx.inspect(result),
+            "[0, bcd, ab, [1, 2, [3]], , true, [1, 2, 3], [1, 2]]"
+        );
+    }
+
+    #[test]
+    fn test_persistent_values_survive_evals_and_collections() {
+        let mut ctx = Context::new(256 * 1024);
+        let result = ctx
+            .eval("[[1, 2, 3], { name: 'sen' + 'sor', readings: [4, 5] }];")
+            .unwrap();
+        let handle = ctx.persist(result);
+        let inner = ctx.downgrade(ctx.get_array(result).unwrap()[0]);
+
+        for i in 0..100 {
+            ctx.eval(&format!(
+                "var scratch = [{i}, [{i}], {{ n: {i} }}]; scratch = 0;"
+            ))
+            .unwrap();
+            if i % 10 == 0 {
+                ctx.gc();
+            }
+        }
+        ctx.gc();
+        let items = ctx.get_array(handle.value()).unwrap();
+        assert_eq!(ctx.inspect(items[0]), "[1, 2, 3]");
+        let (key, name) = ctx.interpreter.object_property_at(items[1], 0).unwrap();
+        assert_eq!(key, "name");
+        assert_eq!(ctx.get_string(name).as_deref(), Some("sensor"));
+        let (_, readings) = ctx.interpreter.object_property_at(items[1], 1).unwrap();
+        assert_eq!(ctx.inspect(readings), "[4, 5]");
+        assert!(inner.upgrade(&ctx).is_some());
+
+        let live = ctx.gc_stats().live_objects;
+        drop(handle);
+        ctx.gc();
+        assert!(ctx.gc_stats().live_objects < live);
+        assert!(inner.upgrade(&ctx).is_none());
+    }
 }
@@ -0,0 +1,58 @@
+//! Stable embedding API
+//!
+//! Everything a host application needs to run scripts, exchange values and
+//! expose native functions. The `vm`, `parser`, `runtime`, `gc`, `builtins`
+//! and `util` modules are implementation details that may change between
+//! releases; code written against this module should not need them.
+//!
+//! ```
+//! use mquickjs::embed::{Context, HostError, NativeScope, Value, ValueTag};
+//!
+//! fn shout(scope: &mut NativeScope, _this: Value, args: &[Value]) -> Result<Value, HostError> {
+//!     let text = args.first().and_then(|&v| scope.get_string(v)).unwrap_or_default();
+//!     let loud = text.to_uppercase();
+//!     Ok(scope.alloc_string(&loud))
+//! }
+//!
+//! let mut ctx = Context::new(64 * 1024);
+//! ctx.register_function("shout", shout, 1);
+//! let result = ctx.eval("[shout(\"hi\"), 2];").unwrap();
+//! let items = ctx.get_array(result).unwrap();
+//! assert_eq!(ctx.get_string(items[0]).as_deref(), Some("HI"));
+//! assert_eq!(items[1].classify(), ValueTag::Int);
+//! assert_eq!(items[1].to_i32(), Some(2));
+//! ```
+
+// Running scripts
+pub use crate::context::{
+    CompileCacheStats, Context, ContextBuilder, EvalError, EvalSummary, Execution, JsError,
+    NativeId, RegisterError, Script, StepResult, UnhandledErrorHook, WeakValue,
+};
+pub use crate::parser::compiler::CompileError;
+pub use crate::parser::diagnostic::{Diagnostic, DiagnosticHandler, DiagnosticKind, Severity};
+pub use crate::parser::lexer::SourcePos;
+pub use crate::runtime::ScriptMetrics;
+
+// Values
+#[cfg(feature = "serde")]
+pub use crate::serde_interop::{JsValueRef, SerdeError};
+pub use crate::value::{JsStr, Value, ValueTag};
+pub use crate::vm::{JsonError, JsonOptions, PersistentValue};
+
+// Host functions and hooks
+#[cfg(feature = "std")]
+pub use crate::vm::SystemClock;
+pub use crate::vm::{
+    Args, ClassDef, Clock, ConsoleLevel, ConsoleSink, GcCallback, GcPhase, HostError, HostFn,
+    ManualClock, ModuleBuilder, NativeScope, ObjectBuilder, PropertyLookup, Receiver, ReceiverKind,
+    ResolverHandle, engine_module,
+};
+
+// Record and replay
+pub use crate::vm::{LoggedValue, ReplayEntry, ReplayFormatError, ReplayLog};
+
+// Statistics
+pub use crate::context::MemoryStats;
+#[cfg(feature = "opcode-stats")]
+pub use crate::vm::OpCode;
+pub use crate::vm::{ExecutionReport, GcStats, InterpreterStats};
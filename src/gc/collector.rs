@@ -8,8 +8,9 @@
 //! compared to reference counting.
 
 use super::allocator::{BlockHeader, Heap, MemoryTag};
+use crate::prelude::*;
 use crate::value::Value;
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 /// A GC-safe reference to a value
 ///
@@ -174,7 +175,7 @@ fn compact(heap: &mut Heap) {
                 let dst = heap.base().add(*new_offset);
                 let header = &*(src as *const BlockHeader);
                 let size = header.size_bytes();
-                std::ptr::copy(src, dst, size);
+                core::ptr::copy(src, dst, size);
             }
         }
     }
@@ -11,39 +11,90 @@
 //! - No external dependencies for core functionality
 //!
 //! # Example
-//! ```ignore
-//! use mquickjs::{Context, Value};
+//! ```
+//! use mquickjs::embed::Context;
 //!
 //! let mut ctx = Context::new(64 * 1024); // 64KB memory
-//! let result = ctx.eval("1 + 2").unwrap();
+//! let result = ctx.eval("1 + 2;").unwrap();
 //! assert_eq!(result.to_i32(), Some(3));
 //! ```
+//!
+//! Embedders should import from [`embed`], which re-exports the stable
+//! surface; the remaining modules are internals.
+//!
+//! # Feature flags
+//! - `std` (default): the system clock, console output on stdout/stderr
+//!   when no sink is set, the `load()` global and turning panics in
+//!   natives into catchable errors. Without it the library is `no_std` and
+//!   needs only `alloc`, so it builds for targets such as
+//!   `thumbv7em-none-eabihf`: time comes only from the host's
+//!   [`Clock`](embed::Clock) (a stopped [`ManualClock`](embed::ManualClock)
+//!   until one is installed), console output reaches only a sink, and a
+//!   panic in a native is left to the target's panic handler.
+//! - `cli` (default): the `mqjs` binary and its REPL. Implies `std`.
+//! - `regex-backend` (default): RegExp runs on the `regex` crate. Without
+//!   it a small built-in backtracking engine is used instead, which adds
+//!   backreferences but no other dependency. Either way, patterns the
+//!   engine cannot run throw a SyntaxError naming the construct. Implies
+//!   `std`.
+//! - `serde`: [`Context::eval_into`](embed::Context::eval_into),
+//!   [`Context::value_from_serde`](embed::Context::value_from_serde) and
+//!   [`JsValueRef`](embed::JsValueRef), which convert arrays and plain
+//!   objects to and from Rust types.
+//! - `opcode-stats`: counts every opcode the interpreter dispatches and
+//!   every native call, read through
+//!   [`Context::opcode_stats`](embed::Context::opcode_stats) and
+//!   [`Context::opcode_stats_report`](embed::Context::opcode_stats_report).
+//!   Without it the dispatch loop has no counting code at all.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![allow(dead_code)] // During development
 
+extern crate alloc;
+
+// The parts of the std prelude that come from `alloc`
+mod prelude;
+
 // Core modules
 pub mod context;
 pub mod value;
 
+// Stable embedding API
+pub mod embed;
+
+// Serde conversions, re-exported from `embed`
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub mod serde_interop;
+
+// Internals below are not part of the stable API; use `embed` instead
+
 // Garbage collector
+#[doc(hidden)]
 pub mod gc;
 
 // Virtual machine
+#[doc(hidden)]
 pub mod vm;
 
 // Parser and compiler
+#[doc(hidden)]
 pub mod parser;
 
 // Built-in objects
+#[doc(hidden)]
 pub mod builtins;
 
 // Runtime support
+#[doc(hidden)]
 pub mod runtime;
 
 // Utilities
+#[doc(hidden)]
 pub mod util;
 
 // Re-export main types
-pub use context::{Context, MemoryStats};
-pub use runtime::FunctionBytecode;
+pub use context::{
+    Context, ContextBuilder, EvalSummary, MemoryStats, NativeId, RegisterError, WeakValue,
+};
 pub use value::Value;
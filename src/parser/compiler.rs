@@ -3,8 +3,15 @@
 //! Generates bytecode from source code in a single pass.
 //! Uses precedence climbing for expression parsing.
 
+use crate::prelude::*;
+use alloc::rc::Rc;
+use core::time::Duration;
+
+use super::diagnostic::{Diagnostic, DiagnosticKind};
 use super::lexer::{Lexer, SourcePos, Token};
+use crate::util::dtoa::IntStr;
 use crate::value::Value;
+use crate::vm::clock::Clock;
 use crate::vm::opcode::OpCode;
 
 /// Maximum number of local variables
@@ -13,6 +20,65 @@ const MAX_LOCALS: usize = 256;
 /// Maximum number of constants
 const MAX_CONSTANTS: usize = 65536;
 
+/// Default limit on how deeply expressions, statements, array literals and
+/// functions may nest before compilation fails instead of exhausting the
+/// native stack
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 200;
+
+/// Default limit on the tokens one script may contain
+pub const DEFAULT_MAX_TOKENS: usize = 1 << 22;
+
+/// Default limit on the bytecode one script compiles to, in bytes, counting
+/// every function it defines
+pub const DEFAULT_MAX_BYTECODE: usize = 1 << 24;
+
+/// Tokens read between checks of the compile deadline
+const DEADLINE_CHECK_TOKENS: usize = 256;
+
+/// A compile budget limit, as reported by [`CompileError::BudgetExceeded`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompileLimit {
+    /// Tokens the script may contain
+    Tokens(usize),
+    /// Bytes of bytecode across all of the script's functions
+    Bytecode(usize),
+    /// Time compilation may take on the context's clock
+    Time(Duration),
+}
+
+/// Limits on the work compiling one script may take, as a context applies
+/// them to every script it compiles
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CompileBudget {
+    pub max_tokens: usize,
+    pub max_bytecode: usize,
+    pub timeout: Option<Duration>,
+}
+
+impl Default for CompileBudget {
+    fn default() -> Self {
+        CompileBudget {
+            max_tokens: DEFAULT_MAX_TOKENS,
+            max_bytecode: DEFAULT_MAX_BYTECODE,
+            timeout: None,
+        }
+    }
+}
+
+/// When compilation must stop, on an injectable clock
+struct CompileDeadline {
+    clock: Rc<dyn Clock>,
+    at_ms: u64,
+    timeout: Duration,
+}
+
+/// Fewest branches an `if (x === 1) {..} else if (x === 2) {..}` chain
+/// needs before it is compiled as a binary search over the values
+const MIN_DISPATCH_BRANCHES: usize = 8;
+
+/// Most values a dispatch compares one by one instead of splitting further
+const DISPATCH_LINEAR_CASES: usize = 4;
+
 /// Local variable info
 #[derive(Debug, Clone)]
 struct Local {
@@ -20,6 +86,13 @@ struct Local {
     depth: u32,
     /// Whether this local is captured by an inner function
     is_captured: bool,
+    /// Where a var/let/const declared it; other locals (parameters,
+    /// functions, loop and catch bindings) are not checked for use
+    declared_at: Option<SourcePos>,
+    /// Whether the value is ever read
+    read: bool,
+    /// Whether a value is ever stored by an initializer or assignment
+    written: bool,
 }
 
 /// Captured variable info (for closures)
@@ -33,6 +106,27 @@ struct Capture {
     is_local: bool,
 }
 
+/// Variables of a function whose inner function is being compiled
+#[derive(Debug)]
+struct EnclosingFunction {
+    /// Its locals in scope where the inner function appears
+    locals: Vec<Local>,
+    /// Its captures so far; resolving a name for a deeper function may add
+    /// more
+    captures: Vec<Capture>,
+}
+
+/// Where an assignment target lives
+#[derive(Debug, Clone, Copy)]
+enum VarTarget {
+    /// Local slot in the current frame
+    Local(usize),
+    /// Captured variable of the current closure
+    Capture(usize),
+    /// Global binding, looked up by name
+    Global,
+}
+
 /// Jump patch location
 #[derive(Debug, Clone, Copy)]
 struct JumpPatch {
@@ -40,6 +134,15 @@ struct JumpPatch {
     offset: usize,
 }
 
+/// An else-if chain testing one local against integer literals
+#[derive(Debug)]
+struct DispatchChain {
+    /// Slot of the local every condition reads
+    slot: usize,
+    /// The literal each branch compares against, in source order
+    values: Vec<i32>,
+}
+
 /// Loop context for break/continue
 #[derive(Debug, Clone)]
 struct LoopContext {
@@ -49,6 +152,8 @@ struct LoopContext {
     break_patches: Vec<JumpPatch>,
     /// Scope depth when loop started (for proper cleanup)
     scope_depth: u32,
+    /// Active try handlers when loop started
+    try_depth: u32,
 }
 
 /// Compiler state
@@ -68,20 +173,50 @@ pub struct Compiler<'a> {
     scope_depth: u32,
     /// Current source position
     current_pos: SourcePos,
+    /// Start of the current token
+    token_pos: SourcePos,
     /// Had error during compilation
     had_error: bool,
+    /// First error the lexer reported, with its position; later syntax
+    /// errors usually just follow from it
+    lexer_error: Option<String>,
+    /// Whether the function being compiled, or one enclosing it, starts
+    /// with a "use strict" directive
+    strict: bool,
     /// Panic mode (suppress cascading errors)
     panic_mode: bool,
     /// Compiled inner functions
     functions: Vec<CompiledFunction>,
     /// Loop context stack for break/continue
     loop_stack: Vec<LoopContext>,
+    /// Number of enclosing try blocks whose handlers are active
+    try_depth: u32,
     /// Captured variables from outer scopes (for closures)
     captures: Vec<Capture>,
-    /// Outer function's locals (for resolving captures during inner function compilation)
-    outer_locals: Option<Vec<Local>>,
-    /// Outer function's captures (for resolving nested captures)
-    outer_captures: Option<Vec<Capture>>,
+    /// Functions enclosing the one being compiled, outermost first; empty
+    /// at the top level
+    enclosing: Vec<EnclosingFunction>,
+    /// Warnings collected so far
+    diagnostics: Vec<Diagnostic>,
+    /// Bytecode offset and position just after the most recent plain
+    /// `name = value` assignment, for spotting `if (x = 5)`
+    last_assignment: Option<(usize, SourcePos)>,
+    /// Current syntactic nesting depth
+    nesting_depth: usize,
+    /// Nesting depth at which compilation fails
+    max_nesting_depth: usize,
+    /// Whether the non-standard `print` statement is accepted
+    print_enabled: bool,
+    /// Tokens the source may contain
+    max_tokens: usize,
+    /// Bytes of bytecode the source may compile to
+    max_bytecode: usize,
+    /// Bytes of bytecode emitted so far, across all functions
+    emitted: usize,
+    /// Time limit, if any
+    deadline: Option<CompileDeadline>,
+    /// The limit compilation ran into, once it has
+    budget_error: Option<CompileError>,
 }
 
 impl<'a> Compiler<'a> {
@@ -89,6 +224,7 @@ impl<'a> Compiler<'a> {
     pub fn new(source: &'a str) -> Self {
         let mut lexer = Lexer::new(source);
         let current_token = lexer.next_token();
+        let token_pos = lexer.token_start();
 
         Compiler {
             lexer,
@@ -101,28 +237,153 @@ impl<'a> Compiler<'a> {
             max_locals: 0,
             scope_depth: 0,
             current_pos: SourcePos::default(),
+            token_pos,
             had_error: false,
+            lexer_error: None,
+            strict: false,
             panic_mode: false,
             functions: Vec::new(),
             loop_stack: Vec::new(),
+            try_depth: 0,
             captures: Vec::new(),
-            outer_locals: None,
-            outer_captures: None,
+            enclosing: Vec::new(),
+            diagnostics: Vec::new(),
+            last_assignment: None,
+            nesting_depth: 0,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            print_enabled: true,
+            max_tokens: DEFAULT_MAX_TOKENS,
+            max_bytecode: DEFAULT_MAX_BYTECODE,
+            emitted: 0,
+            deadline: None,
+            budget_error: None,
         }
     }
 
+    /// Set how deeply the source may nest before compilation fails with
+    /// [`CompileError::TooDeeplyNested`]
+    pub fn with_max_nesting_depth(mut self, limit: usize) -> Self {
+        self.max_nesting_depth = limit;
+        self
+    }
+
+    /// Accept or reject the non-standard `print` statement; when rejected
+    /// it is a syntax error
+    pub fn with_print(mut self, enabled: bool) -> Self {
+        self.print_enabled = enabled;
+        self
+    }
+
+    /// Set how many tokens the source may contain before compilation
+    /// stops with [`CompileError::BudgetExceeded`]
+    pub fn with_max_tokens(mut self, limit: usize) -> Self {
+        self.max_tokens = limit;
+        self
+    }
+
+    /// Set how many bytes of bytecode the source may compile to, across
+    /// all its functions, before compilation stops with
+    /// [`CompileError::BudgetExceeded`]
+    pub fn with_max_bytecode(mut self, limit: usize) -> Self {
+        self.max_bytecode = limit;
+        self
+    }
+
+    /// Stop compilation with [`CompileError::BudgetExceeded`] once it has
+    /// taken `timeout` on `clock`
+    ///
+    /// The clock is read every few hundred tokens, so the limit is
+    /// approximate.
+    pub fn with_timeout(mut self, clock: Rc<dyn Clock>, timeout: Duration) -> Self {
+        let at_ms = clock
+            .now_ms()
+            .saturating_add(timeout.as_millis().try_into().unwrap_or(u64::MAX));
+        self.deadline = Some(CompileDeadline {
+            clock,
+            at_ms,
+            timeout,
+        });
+        self
+    }
+
     /// Compile the source and return bytecode
-    pub fn compile(mut self) -> Result<CompiledFunction, CompileError> {
+    ///
+    /// The script returns its completion value: the value of the last
+    /// top-level statement when that is an expression statement, otherwise
+    /// undefined.
+    pub fn compile(self) -> Result<CompiledFunction, CompileError> {
+        self.compile_with_diagnostics().map(|(func, _)| func)
+    }
+
+    /// Compile the source and also return the warnings found along the way
+    ///
+    /// Diagnostics never affect the generated bytecode.
+    pub fn compile_with_diagnostics(
+        mut self,
+    ) -> Result<(CompiledFunction, Vec<Diagnostic>), CompileError> {
+        self.directive_prologue();
+
         // Parse statements until EOF
+        let mut ends_with_expression = false;
         while !self.check(&Token::Eof) {
-            self.statement()?;
+            ends_with_expression = self.at_expression_statement();
+            if let Err(err) = self.statement() {
+                return Err(self.lexer_error_or(err));
+            }
         }
 
-        // Emit implicit return undefined
-        self.emit_op(OpCode::ReturnUndef);
+        if ends_with_expression {
+            // Keep the last expression's value instead of dropping it; the
+            // global sync below is stack-neutral
+            debug_assert_eq!(self.bytecode.last(), Some(&(OpCode::Drop as u8)));
+            self.bytecode.pop();
+            self.emit_global_sync();
+            self.emit_op(OpCode::Return);
+        } else {
+            self.emit_global_sync();
+            self.emit_op(OpCode::ReturnUndef);
+        }
+
+        // Top-level bindings at depth 0 become globals, so they are not
+        // checked for use here
+        let diagnostics = core::mem::take(&mut self.diagnostics);
+        self.finish().map(|func| (func, diagnostics))
+    }
 
-        if self.had_error {
-            Err(CompileError::SyntaxError("Compilation failed".into()))
+    /// Compile source that must be a single expression and return its value
+    ///
+    /// Source starting with `function` is a declaration, as it would be at
+    /// the start of a statement, and is rejected.
+    pub fn compile_expression(mut self) -> Result<CompiledFunction, CompileError> {
+        if self.check(&Token::Function) {
+            return Err(CompileError::SyntaxError(
+                "expected a single expression, found a function declaration".into(),
+            ));
+        }
+        if let Err(err) = self.expression() {
+            return Err(self.lexer_error_or(err));
+        }
+        self.match_token(&Token::Semicolon);
+        if !self.check(&Token::Eof) {
+            return Err(CompileError::SyntaxError(format!(
+                "expected a single expression, found {:?}",
+                self.current_token
+            )));
+        }
+        self.emit_op(OpCode::Return);
+
+        self.finish()
+    }
+
+    /// Package the compiled top-level code
+    fn finish(mut self) -> Result<CompiledFunction, CompileError> {
+        // Catch bytecode emitted after the last token was read
+        self.check_budget();
+        if let Some(err) = self.budget_error.take() {
+            Err(err)
+        } else if self.had_error {
+            let err = CompileError::SyntaxError("Compilation failed".into());
+            Err(self.lexer_error_or(err))
         } else {
             // Convert captures to CaptureInfo
             let captures: Vec<CaptureInfo> = self
@@ -133,8 +394,14 @@ impl<'a> Compiler<'a> {
                     is_local: c.is_local,
                 })
                 .collect();
+            let global_slots = self
+                .top_level_bindings()
+                .into_iter()
+                .map(|(i, name)| (i as u16, name))
+                .collect();
 
             Ok(CompiledFunction {
+                name: None,
                 bytecode: self.bytecode,
                 constants: self.constants,
                 string_constants: self.string_constants,
@@ -142,6 +409,8 @@ impl<'a> Compiler<'a> {
                 arg_count: 0, // Top-level script has no arguments
                 functions: self.functions,
                 captures,
+                strict: self.strict,
+                global_slots,
             })
         }
     }
@@ -152,7 +421,7 @@ impl<'a> Compiler<'a> {
 
     /// Advance to the next token
     fn advance(&mut self) {
-        self.previous_token = std::mem::replace(&mut self.current_token, Token::Eof);
+        self.previous_token = core::mem::replace(&mut self.current_token, Token::Eof);
         self.current_pos = self.lexer.position();
 
         loop {
@@ -162,14 +431,51 @@ impl<'a> Compiler<'a> {
             }
             // Report lexer error and continue
             if let Token::Error(msg) = &self.current_token {
-                self.error(&msg.clone());
+                let msg = msg.clone();
+                let pos = self.lexer.token_start();
+                if self.lexer_error.is_none() {
+                    self.lexer_error = Some(format!(
+                        "{} at line {} column {}",
+                        msg, pos.line, pos.column
+                    ));
+                }
+                self.error(&msg);
             }
         }
+        self.token_pos = self.lexer.token_start();
+        self.check_budget();
+    }
+
+    /// Note the first compile budget limit exceeded, and from then on see
+    /// the end of input so parsing unwinds instead of going on
+    fn check_budget(&mut self) {
+        if self.budget_error.is_none() {
+            let tokens = self.lexer.tokens_read();
+            let limit = if tokens > self.max_tokens {
+                Some(CompileLimit::Tokens(self.max_tokens))
+            } else if self.emitted > self.max_bytecode {
+                Some(CompileLimit::Bytecode(self.max_bytecode))
+            } else {
+                self.deadline
+                    .as_ref()
+                    .filter(|d| {
+                        tokens.is_multiple_of(DEADLINE_CHECK_TOKENS) && d.clock.now_ms() >= d.at_ms
+                    })
+                    .map(|d| CompileLimit::Time(d.timeout))
+            };
+            self.budget_error = limit.map(|limit| CompileError::BudgetExceeded {
+                limit,
+                pos: self.token_pos,
+            });
+        }
+        if self.budget_error.is_some() {
+            self.current_token = Token::Eof;
+        }
     }
 
     /// Check if current token matches expected
     fn check(&self, expected: &Token) -> bool {
-        std::mem::discriminant(&self.current_token) == std::mem::discriminant(expected)
+        core::mem::discriminant(&self.current_token) == core::mem::discriminant(expected)
     }
 
     /// Consume token if it matches, return true if matched
@@ -202,7 +508,32 @@ impl<'a> Compiler<'a> {
         }
         self.panic_mode = true;
         self.had_error = true;
+        #[cfg(feature = "std")]
         eprintln!("[line {}] Error: {}", self.current_pos.line, message);
+        #[cfg(not(feature = "std"))]
+        let _ = message;
+    }
+
+    /// The exceeded compile budget or the first lexer error in place of
+    /// `err`, if there was one
+    fn lexer_error_or(&mut self, err: CompileError) -> CompileError {
+        if let Some(budget) = self.budget_error.take() {
+            return budget;
+        }
+        match self.lexer_error.take() {
+            Some(msg) => CompileError::SyntaxError(msg),
+            None => err,
+        }
+    }
+
+    /// The token after the current one, without consuming anything
+    fn peek_token(&self) -> Token {
+        self.lexer.clone().next_token()
+    }
+
+    /// Record a diagnostic
+    fn warn(&mut self, kind: DiagnosticKind, message: String, pos: SourcePos) {
+        self.diagnostics.push(Diagnostic::new(kind, message, pos));
     }
 
     /// Check if current token is an assignment operator
@@ -258,32 +589,36 @@ impl<'a> Compiler<'a> {
 
     /// Emit a single opcode
     fn emit_op(&mut self, op: OpCode) {
-        self.bytecode.push(op as u8);
+        self.emit_byte(op as u8);
     }
 
     /// Emit a single byte
+    ///
+    /// Every emitted byte goes through here, to be counted against the
+    /// bytecode budget.
     fn emit_byte(&mut self, byte: u8) {
         self.bytecode.push(byte);
+        self.emitted += 1;
     }
 
     /// Emit two bytes
     fn emit_bytes(&mut self, b1: u8, b2: u8) {
-        self.bytecode.push(b1);
-        self.bytecode.push(b2);
+        self.emit_byte(b1);
+        self.emit_byte(b2);
     }
 
     /// Emit a 16-bit value (little-endian)
     fn emit_u16(&mut self, val: u16) {
-        self.bytecode.push((val & 0xff) as u8);
-        self.bytecode.push((val >> 8) as u8);
+        for byte in val.to_le_bytes() {
+            self.emit_byte(byte);
+        }
     }
 
     /// Emit a 32-bit value (little-endian)
     fn emit_u32(&mut self, val: u32) {
-        self.bytecode.push((val & 0xff) as u8);
-        self.bytecode.push(((val >> 8) & 0xff) as u8);
-        self.bytecode.push(((val >> 16) & 0xff) as u8);
-        self.bytecode.push((val >> 24) as u8);
+        for byte in val.to_le_bytes() {
+            self.emit_byte(byte);
+        }
     }
 
     /// Emit a signed 32-bit value (little-endian)
@@ -312,9 +647,8 @@ impl<'a> Compiler<'a> {
                 self.emit_u16(v as i16 as u16);
             }
             _ => {
-                // Large integer: for now, truncate to short int range
-                // TODO: Store as float constant when float support is added
-                let idx = self.add_constant(Value::int(val.clamp(-(1 << 30), (1 << 30) - 1)));
+                // Large integer: stored in the constant pool
+                let idx = self.add_constant(Value::int(val));
                 self.emit_const(idx);
             }
         }
@@ -381,14 +715,51 @@ impl<'a> Compiler<'a> {
 
     /// Emit get global instruction for builtin functions
     fn emit_get_global(&mut self, name: &str) {
+        self.emit_global_op(OpCode::GetGlobal, name);
+    }
+
+    /// Emit set global instruction (pops the value)
+    fn emit_put_global(&mut self, name: &str) {
+        self.emit_global_op(OpCode::PutGlobal, name);
+    }
+
+    /// Emit an instruction naming a global by a constant
+    fn emit_global_op(&mut self, op: OpCode, name: &str) {
         // Add the name as a string constant
         let str_idx = self.string_constants.len() as u16;
         self.string_constants.push(name.to_string());
         let const_idx = self.add_constant(Value::string(str_idx));
-        self.emit_op(OpCode::GetGlobal);
+        self.emit_op(op);
         self.emit_u16(const_idx);
     }
 
+    /// Publish the top-level bindings as globals before the script returns
+    ///
+    /// Top-level variables live in frame slots like any other local, so they
+    /// would vanish with the frame. Copying them out at every exit point lets
+    /// later scripts evaluated in the same context see them (e.g. a REPL).
+    /// Inner functions are unaffected.
+    fn emit_global_sync(&mut self) {
+        if !self.enclosing.is_empty() {
+            return;
+        }
+
+        for (index, name) in self.top_level_bindings() {
+            self.emit_get_local(index);
+            self.emit_global_op(OpCode::DefineGlobal, &name);
+        }
+    }
+
+    /// Local slot and name of each top-level binding declared so far
+    fn top_level_bindings(&self) -> Vec<(usize, String)> {
+        self.locals
+            .iter()
+            .enumerate()
+            .filter(|(_, local)| local.depth == 0 && !local.name.starts_with('\x00'))
+            .map(|(i, local)| (i, local.name.clone()))
+            .collect()
+    }
+
     /// Emit a jump instruction and return the patch location
     fn emit_jump(&mut self, op: OpCode) -> JumpPatch {
         self.emit_op(op);
@@ -478,6 +849,9 @@ impl<'a> Compiler<'a> {
             name: name.to_string(),
             depth: self.scope_depth,
             is_captured: false,
+            declared_at: None,
+            read: false,
+            written: false,
         });
 
         // Track maximum locals for frame allocation
@@ -488,6 +862,66 @@ impl<'a> Compiler<'a> {
         Ok(index)
     }
 
+    /// Declare a var/let/const binding whose use is checked when its scope ends
+    fn declare_variable(&mut self, name: &str, pos: SourcePos) -> Result<usize, CompileError> {
+        let index = self.declare_local(name)?;
+        self.locals[index].declared_at = Some(pos);
+        Ok(index)
+    }
+
+    /// Declare a `var` binding, returning its slot and whether it is new
+    ///
+    /// `var` may name a variable again, so a local of that name already
+    /// visible in this function is reused rather than shadowed. This is what
+    /// lets consecutive `for (var k in ...)` loops share one `k`.
+    fn declare_var(&mut self, name: &str, pos: SourcePos) -> Result<(usize, bool), CompileError> {
+        match self.resolve_local(name) {
+            Some(index) => Ok((index, false)),
+            None => Ok((self.declare_variable(name, pos)?, true)),
+        }
+    }
+
+    /// Note that a variable is read
+    fn mark_read(&mut self, target: VarTarget) {
+        if let VarTarget::Local(idx) = target {
+            self.locals[idx].read = true;
+        }
+    }
+
+    /// Note that a variable is assigned
+    fn mark_written(&mut self, target: VarTarget) {
+        if let VarTarget::Local(idx) = target {
+            self.locals[idx].written = true;
+        }
+    }
+
+    /// Report declared variables in `locals[from..]` that are never read or
+    /// never assigned
+    fn check_locals_used(&mut self, from: usize) {
+        let mut found = Vec::new();
+        for local in &self.locals[from..] {
+            let Some(pos) = local.declared_at else {
+                continue;
+            };
+            if !local.read {
+                found.push((
+                    DiagnosticKind::UnusedVariable,
+                    format!("'{}' is declared but its value is never read", local.name),
+                    pos,
+                ));
+            } else if !local.written {
+                found.push((
+                    DiagnosticKind::UnassignedVariable,
+                    format!("'{}' is read but never assigned a value", local.name),
+                    pos,
+                ));
+            }
+        }
+        for (kind, message, pos) in found {
+            self.warn(kind, message, pos);
+        }
+    }
+
     /// Resolve a local variable, returning its index
     fn resolve_local(&self, name: &str) -> Option<usize> {
         for (i, local) in self.locals.iter().enumerate().rev() {
@@ -501,51 +935,96 @@ impl<'a> Compiler<'a> {
     /// Resolve a captured variable, returning its capture index
     /// This also adds the capture if it doesn't exist yet
     fn resolve_capture(&mut self, name: &str) -> Option<usize> {
-        // First check if we already have this capture
-        for (i, capture) in self.captures.iter().enumerate() {
-            if capture.name == name {
-                return Some(i);
-            }
-        }
+        self.resolve_capture_at(self.enclosing.len(), name)
+    }
 
-        // Try to resolve from outer function's locals
-        if let Some(ref mut outer_locals) = self.outer_locals.clone() {
-            for (i, local) in outer_locals.iter().enumerate().rev() {
-                if local.name == name {
-                    // Mark the outer local as captured
-                    if let Some(ref mut locals) = self.outer_locals {
-                        locals[i].is_captured = true;
-                    }
+    /// Resolve `name` as a capture of the function at `level` of the
+    /// enclosing chain, where `level == enclosing.len()` is the function
+    /// being compiled
+    ///
+    /// A variable found further out is threaded through every function in
+    /// between, so each of them gets a capture slot even if it never uses
+    /// the variable itself.
+    fn resolve_capture_at(&mut self, level: usize, name: &str) -> Option<usize> {
+        let captures = match self.enclosing.get(level) {
+            Some(function) => &function.captures,
+            None => &self.captures,
+        };
+        if let Some(i) = captures.iter().position(|c| c.name == name) {
+            return Some(i);
+        }
 
-                    // Add a new capture
-                    let capture_idx = self.captures.len();
-                    self.captures.push(Capture {
-                        name: name.to_string(),
-                        outer_index: i,
-                        is_local: true,
-                    });
-                    return Some(capture_idx);
-                }
+        let parent = level.checked_sub(1)?;
+        let parent_locals = &mut self.enclosing[parent].locals;
+        let capture = if let Some(i) = parent_locals.iter().rposition(|l| l.name == name) {
+            parent_locals[i].is_captured = true;
+            Capture {
+                name: name.to_string(),
+                outer_index: i,
+                is_local: true,
+            }
+        } else {
+            Capture {
+                name: name.to_string(),
+                outer_index: self.resolve_capture_at(parent, name)?,
+                is_local: false,
             }
+        };
+
+        let captures = match self.enclosing.get_mut(level) {
+            Some(function) => &mut function.captures,
+            None => &mut self.captures,
+        };
+        captures.push(capture);
+        Some(captures.len() - 1)
+    }
+
+    /// Resolve a variable for assignment: local, then capture, then global
+    fn resolve_var(&mut self, name: &str) -> VarTarget {
+        if let Some(idx) = self.resolve_local(name) {
+            VarTarget::Local(idx)
+        } else if let Some(idx) = self.resolve_capture(name) {
+            VarTarget::Capture(idx)
+        } else {
+            VarTarget::Global
         }
+    }
 
-        // Try to resolve from outer function's captures (nested closure)
-        if let Some(ref outer_captures) = self.outer_captures.clone() {
-            for (i, capture) in outer_captures.iter().enumerate() {
-                if capture.name == name {
-                    // Add a new capture that references outer's capture
-                    let capture_idx = self.captures.len();
-                    self.captures.push(Capture {
-                        name: name.to_string(),
-                        outer_index: i,
-                        is_local: false,
-                    });
-                    return Some(capture_idx);
-                }
-            }
+    /// Emit a read of a resolved variable
+    fn emit_get_var(&mut self, name: &str, target: VarTarget) {
+        match target {
+            VarTarget::Local(idx) => self.emit_get_local(idx),
+            VarTarget::Capture(idx) => self.emit_get_capture(idx),
+            VarTarget::Global => self.emit_get_global(name),
         }
+    }
 
-        None
+    /// Emit a write of a resolved variable (pops the value)
+    fn emit_set_var(&mut self, name: &str, target: VarTarget) {
+        match target {
+            VarTarget::Local(idx) => self.emit_set_local(idx),
+            VarTarget::Capture(idx) => self.emit_set_capture(idx),
+            VarTarget::Global => self.emit_put_global(name),
+        }
+    }
+
+    /// Run a recursive parsing step one nesting level deeper
+    ///
+    /// Every construct that can nest (statements, expressions, array
+    /// literals, function bodies) recurses through here, so pathological
+    /// input fails with [`CompileError::TooDeeplyNested`] long before it
+    /// could overflow the native stack.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, CompileError>,
+    ) -> Result<T, CompileError> {
+        if self.nesting_depth >= self.max_nesting_depth {
+            return Err(CompileError::TooDeeplyNested);
+        }
+        self.nesting_depth += 1;
+        let result = parse(self);
+        self.nesting_depth -= 1;
+        result
     }
 
     /// Begin a new scope
@@ -560,12 +1039,13 @@ impl<'a> Compiler<'a> {
         // Remove locals from ended scope from our tracking
         // Note: We don't emit Drop because locals are stored in fixed frame slots,
         // not on the value stack. The frame cleanup happens on function return.
-        while let Some(local) = self.locals.last() {
-            if local.depth <= self.scope_depth {
-                break;
-            }
-            self.locals.pop();
-        }
+        let first = self
+            .locals
+            .iter()
+            .rposition(|local| local.depth <= self.scope_depth)
+            .map_or(0, |i| i + 1);
+        self.check_locals_used(first);
+        self.locals.truncate(first);
     }
 
     // =========================================================================
@@ -574,14 +1054,18 @@ impl<'a> Compiler<'a> {
 
     /// Parse a statement
     fn statement(&mut self) -> Result<(), CompileError> {
+        self.nested(Self::statement_inner)
+    }
+
+    fn statement_inner(&mut self) -> Result<(), CompileError> {
         match &self.current_token {
-            Token::Var => self.var_declaration(),
-            Token::Let => self.let_declaration(),
-            Token::Const => self.const_declaration(),
+            Token::Var => self.var_declaration().map(|_| self.end_statement()),
+            Token::Let => self.let_declaration().map(|_| self.end_statement()),
+            Token::Const => self.const_declaration().map(|_| self.end_statement()),
             Token::Function => self.function_declaration(),
             Token::If => self.if_statement(),
-            Token::While => self.while_statement(),
-            Token::For => self.for_statement(),
+            Token::While => self.while_statement().map(|_| self.end_statement()),
+            Token::For => self.for_statement().map(|_| self.end_statement()),
             Token::Break => self.break_statement(),
             Token::Continue => self.continue_statement(),
             Token::Return => self.return_statement(),
@@ -589,38 +1073,60 @@ impl<'a> Compiler<'a> {
             Token::Try => self.try_statement(),
             Token::Throw => self.throw_statement(),
             Token::LBrace => self.block_statement(),
+            Token::Ident(name) if name == "with" => Err(CompileError::SyntaxError(format!(
+                "with statements are not supported at line {} column {}",
+                self.token_pos.line, self.token_pos.column
+            ))),
             _ => self.expression_statement(),
         }
     }
 
-    /// Parse var declaration: var x = expr;
-    fn var_declaration(&mut self) -> Result<(), CompileError> {
-        self.advance(); // consume 'var'
-
-        let name = match &self.current_token {
-            Token::Ident(s) => s.clone(),
-            _ => return Err(CompileError::SyntaxError("Expected variable name".into())),
-        };
-        self.advance();
-
-        // Declare the variable
-        let index = self.declare_local(&name)?;
-
-        // Optional initializer
-        if self.match_token(&Token::Eq) {
-            self.expression()?;
-        } else {
-            self.emit_op(OpCode::Undefined);
+    /// Skip the directive prologue at the start of a script or function
+    /// body: string literal statements such as "use strict"
+    ///
+    /// Directives compile to nothing, so they never become the completion
+    /// value. A string literal followed by anything but `;`, `}` or the end
+    /// of input starts an ordinary expression statement instead.
+    fn directive_prologue(&mut self) {
+        while let Token::String(directive) = &self.current_token {
+            if !matches!(
+                self.peek_token(),
+                Token::Semicolon | Token::RBrace | Token::Eof
+            ) {
+                break;
+            }
+            if directive == "use strict" {
+                self.strict = true;
+            }
+            self.advance();
+            self.match_token(&Token::Semicolon);
         }
+    }
 
-        // Store to local (value stays on stack as the local)
-        // Actually we need to emit set_local here
-        self.emit_set_local(index);
-
-        // Expect semicolon
-        self.expect(Token::Semicolon)?;
+    /// Check whether the current token starts an expression statement
+    fn at_expression_statement(&self) -> bool {
+        !matches!(
+            self.current_token,
+            Token::Var
+                | Token::Let
+                | Token::Const
+                | Token::Function
+                | Token::If
+                | Token::While
+                | Token::For
+                | Token::Break
+                | Token::Continue
+                | Token::Return
+                | Token::Print
+                | Token::Try
+                | Token::Throw
+                | Token::LBrace
+        )
+    }
 
-        Ok(())
+    /// Parse var declaration: var x = expr, y;
+    fn var_declaration(&mut self) -> Result<(), CompileError> {
+        self.var_declaration_impl("var")
     }
 
     /// Parse let declaration
@@ -634,29 +1140,168 @@ impl<'a> Compiler<'a> {
     }
 
     /// Common implementation for var/let/const
-    fn var_declaration_impl(&mut self, _keyword: &str) -> Result<(), CompileError> {
+    fn var_declaration_impl(&mut self, keyword: &str) -> Result<(), CompileError> {
         self.advance(); // consume keyword
 
-        let name = match &self.current_token {
-            Token::Ident(s) => s.clone(),
-            _ => return Err(CompileError::SyntaxError("Expected variable name".into())),
-        };
-        self.advance();
-
-        let index = self.declare_local(&name)?;
-
-        if self.match_token(&Token::Eq) {
-            self.expression()?;
-        } else {
-            self.emit_op(OpCode::Undefined);
+        if self.check(&Token::LBrace) {
+            return self.object_pattern_declaration();
         }
 
-        self.emit_set_local(index);
+        self.declarators(keyword == "var")?;
         self.expect(Token::Semicolon)?;
 
         Ok(())
     }
 
+    /// Parse the comma separated `name = expr` list of a declaration,
+    /// storing each initializer before the next one is evaluated
+    ///
+    /// The commas here separate declarators; each initializer is a single
+    /// assignment expression.
+    fn declarators(&mut self, is_var: bool) -> Result<(), CompileError> {
+        loop {
+            let name_pos = self.token_pos;
+            let name = match &self.current_token {
+                Token::Ident(s) => s.clone(),
+                _ => return Err(CompileError::SyntaxError("Expected variable name".into())),
+            };
+            self.advance();
+
+            let (index, fresh) = if is_var {
+                self.declare_var(&name, name_pos)?
+            } else {
+                (self.declare_variable(&name, name_pos)?, true)
+            };
+
+            if self.match_token(&Token::Eq) {
+                self.locals[index].written = true;
+                self.expression()?;
+                self.emit_set_local(index);
+            } else if fresh {
+                // The slot may still hold a value from an earlier scope
+                self.emit_op(OpCode::Undefined);
+                self.emit_set_local(index);
+            }
+
+            if !self.match_token(&Token::Comma) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Parse an object pattern declaration after its keyword:
+    /// `{a, key: name, ...rest} = expr;`
+    ///
+    /// Each name is declared as a variable and read from the initializer
+    /// like `expr.key`. A final `...rest` collects the remaining own
+    /// enumerable properties into a fresh object. Defaults, computed keys
+    /// and nested patterns are not yet supported.
+    fn object_pattern_declaration(&mut self) -> Result<(), CompileError> {
+        self.advance(); // consume '{'
+
+        let mut bindings: Vec<(String, usize)> = Vec::new();
+        let mut rest = None;
+        while !self.check(&Token::RBrace) {
+            if self.match_token(&Token::Ellipsis) {
+                let name_pos = self.token_pos;
+                let Token::Ident(name) = &self.current_token else {
+                    return Err(CompileError::SyntaxError(
+                        "Expected rest binding name".into(),
+                    ));
+                };
+                let name = name.clone();
+                self.advance();
+                rest = Some(self.declare_variable(&name, name_pos)?);
+                // Nothing may follow the rest element
+                break;
+            }
+
+            let name_pos = self.token_pos;
+            let (key, shorthand) = match &self.current_token {
+                Token::Ident(name) => (name.clone(), true),
+                Token::String(name) => (name.clone(), false),
+                Token::Number(n) => (number_key(*n), false),
+                _ => {
+                    return Err(CompileError::SyntaxError(format!(
+                        "Expected property name in pattern, found {:?}",
+                        self.current_token
+                    )));
+                }
+            };
+            self.advance();
+            let (name, name_pos) = if self.match_token(&Token::Colon) {
+                let name_pos = self.token_pos;
+                let Token::Ident(name) = &self.current_token else {
+                    return Err(CompileError::SyntaxError(
+                        "nested patterns are not yet supported".into(),
+                    ));
+                };
+                let name = name.clone();
+                self.advance();
+                (name, name_pos)
+            } else if shorthand {
+                (key.clone(), name_pos)
+            } else {
+                return Err(CompileError::SyntaxError(format!(
+                    "Expected ':' after property '{}' in pattern",
+                    key
+                )));
+            };
+            if self.check(&Token::Eq) {
+                return Err(CompileError::SyntaxError(
+                    "default values in patterns are not yet supported".into(),
+                ));
+            }
+            let index = self.declare_variable(&name, name_pos)?;
+            bindings.push((key, index));
+
+            if !self.match_token(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(Token::RBrace)?;
+
+        if !self.match_token(&Token::Eq) {
+            return Err(CompileError::SyntaxError(
+                "Missing initializer in destructuring declaration".into(),
+            ));
+        }
+        self.expression()?;
+
+        for (key, index) in &bindings {
+            self.locals[*index].written = true;
+            self.emit_op(OpCode::Dup);
+            let str_idx = self.string_constants.len() as u16;
+            self.string_constants.push(key.clone());
+            self.emit_op(OpCode::GetField);
+            self.emit_u16(str_idx);
+            self.emit_set_local(*index);
+        }
+
+        match rest {
+            Some(index) => {
+                // rest = a fresh object with every key not named above
+                self.locals[index].written = true;
+                self.emit_op(OpCode::Object);
+                self.emit_u16(0);
+                self.emit_op(OpCode::Swap);
+                for (key, _) in &bindings {
+                    let str_idx = self.string_constants.len() as u16;
+                    self.string_constants.push(key.clone());
+                    self.emit_op(OpCode::PushConst);
+                    let const_idx = self.add_constant(Value::string(str_idx));
+                    self.emit_u16(const_idx);
+                }
+                self.emit_op(OpCode::CopyDataProperties);
+                self.emit_u16(bindings.len() as u16);
+                self.emit_set_local(index);
+            }
+            None => self.emit_op(OpCode::Drop),
+        }
+
+        self.expect(Token::Semicolon)
+    }
+
     /// Parse function declaration: function name(args) { body }
     fn function_declaration(&mut self) -> Result<(), CompileError> {
         self.advance(); // consume 'function'
@@ -671,6 +1316,18 @@ impl<'a> Compiler<'a> {
         // Declare the function as a local variable
         let func_index = self.declare_local(&name)?;
 
+        self.function_value(Some(&name))?;
+
+        // Store to local
+        self.emit_set_local(func_index);
+
+        Ok(())
+    }
+
+    /// Compile `(params) { body }` and push the resulting closure
+    ///
+    /// A named function can refer to itself by `name` from its body.
+    fn function_value(&mut self, name: Option<&str>) -> Result<(), CompileError> {
         // Parse parameter list
         self.expect(Token::LParen)?;
         let mut params: Vec<String> = Vec::new();
@@ -696,7 +1353,7 @@ impl<'a> Compiler<'a> {
 
         // Compile the function body with a new compiler
         // Pass the function name so it can reference itself for recursion
-        let body_bytecode = self.compile_function_body(Some(&name), &params)?;
+        let body_bytecode = self.compile_function_body(name, &params)?;
 
         // Store the bytecode in functions list
         let bytecode_idx = self.functions.len();
@@ -706,9 +1363,6 @@ impl<'a> Compiler<'a> {
         self.emit_op(OpCode::FClosure);
         self.emit_u16(bytecode_idx as u16);
 
-        // Store to local
-        self.emit_set_local(func_index);
-
         Ok(())
     }
 
@@ -719,24 +1373,32 @@ impl<'a> Compiler<'a> {
         &mut self,
         func_name: Option<&str>,
         params: &[String],
+    ) -> Result<CompiledFunction, CompileError> {
+        self.nested(|c| c.compile_function_body_inner(func_name, params))
+    }
+
+    fn compile_function_body_inner(
+        &mut self,
+        func_name: Option<&str>,
+        params: &[String],
     ) -> Result<CompiledFunction, CompileError> {
         // Save current compiler state
-        let saved_bytecode = std::mem::take(&mut self.bytecode);
-        let saved_constants = std::mem::take(&mut self.constants);
-        let saved_string_constants = std::mem::take(&mut self.string_constants);
-        let saved_locals = std::mem::take(&mut self.locals);
-        let saved_functions = std::mem::take(&mut self.functions);
-        let saved_loop_stack = std::mem::take(&mut self.loop_stack);
-        let saved_captures = std::mem::take(&mut self.captures);
-        let saved_outer_locals = std::mem::take(&mut self.outer_locals);
-        let saved_outer_captures = std::mem::take(&mut self.outer_captures);
+        let saved_bytecode = core::mem::take(&mut self.bytecode);
+        let saved_constants = core::mem::take(&mut self.constants);
+        let saved_string_constants = core::mem::take(&mut self.string_constants);
+        let saved_locals = core::mem::take(&mut self.locals);
+        let saved_functions = core::mem::take(&mut self.functions);
+        let saved_loop_stack = core::mem::take(&mut self.loop_stack);
         let saved_max_locals = self.max_locals;
         let saved_scope_depth = self.scope_depth;
+        let saved_try_depth = self.try_depth;
+        let saved_strict = self.strict;
 
-        // Set outer locals and captures for closure resolution
-        // The inner function can capture from our locals
-        self.outer_locals = Some(saved_locals.clone());
-        self.outer_captures = Some(saved_captures.clone());
+        // The inner function can capture from our locals and captures
+        self.enclosing.push(EnclosingFunction {
+            locals: saved_locals,
+            captures: core::mem::take(&mut self.captures),
+        });
 
         // Reset for function compilation
         self.bytecode = Vec::new();
@@ -748,6 +1410,7 @@ impl<'a> Compiler<'a> {
         self.captures = Vec::new();
         self.max_locals = 0;
         self.scope_depth = 0;
+        self.try_depth = 0;
 
         let arg_count = params.len();
 
@@ -766,10 +1429,10 @@ impl<'a> Compiler<'a> {
         }
 
         // Parse function body statements
-        while !self.check(&Token::RBrace) && !self.check(&Token::Eof) {
-            self.statement()?;
-        }
+        self.directive_prologue();
+        self.statement_list()?;
         self.expect(Token::RBrace)?;
+        self.check_locals_used(0);
 
         // Emit implicit return undefined
         self.emit_op(OpCode::ReturnUndef);
@@ -786,36 +1449,55 @@ impl<'a> Compiler<'a> {
 
         // Create compiled function
         let result = CompiledFunction {
-            bytecode: std::mem::take(&mut self.bytecode),
-            constants: std::mem::take(&mut self.constants),
-            string_constants: std::mem::take(&mut self.string_constants),
+            name: func_name.map(str::to_string),
+            bytecode: core::mem::take(&mut self.bytecode),
+            constants: core::mem::take(&mut self.constants),
+            string_constants: core::mem::take(&mut self.string_constants),
             local_count: self.max_locals,
             arg_count,
-            functions: std::mem::take(&mut self.functions),
+            functions: core::mem::take(&mut self.functions),
             captures,
+            strict: self.strict,
+            global_slots: Vec::new(),
         };
 
         // Restore compiler state
+        let outer = self
+            .enclosing
+            .pop()
+            .expect("enclosing function pushed above");
         self.bytecode = saved_bytecode;
         self.constants = saved_constants;
         self.string_constants = saved_string_constants;
-        self.locals = saved_locals;
+        self.locals = outer.locals;
         self.functions = saved_functions;
         self.loop_stack = saved_loop_stack;
-        self.captures = saved_captures;
-        self.outer_locals = saved_outer_locals;
-        self.outer_captures = saved_outer_captures;
+        self.try_depth = saved_try_depth;
+        self.strict = saved_strict;
+        self.captures = outer.captures;
         self.max_locals = saved_max_locals;
         self.scope_depth = saved_scope_depth;
 
+        // Whatever the inner function does with a captured local counts as
+        // both a read and a write here
+        for capture in result.captures.iter().filter(|c| c.is_local) {
+            let local = &mut self.locals[capture.outer_index];
+            local.read = true;
+            local.written = true;
+        }
+
         Ok(result)
     }
 
     /// Parse if statement
     fn if_statement(&mut self) -> Result<(), CompileError> {
+        if let Some(chain) = self.scan_dispatch_chain() {
+            return self.dispatch_chain(chain);
+        }
+
         self.advance(); // consume 'if'
         self.expect(Token::LParen)?;
-        self.expression()?;
+        self.condition()?;
         self.expect(Token::RParen)?;
 
         // Jump over then branch if condition is false
@@ -838,6 +1520,187 @@ impl<'a> Compiler<'a> {
         Ok(())
     }
 
+    /// Look ahead for a long chain of `if (x === 1) { .. } else if (x === 2)
+    /// { .. }` branches on one local variable
+    ///
+    /// Every condition must be a strict comparison of the same local with
+    /// an integer literal, and every branch a block. The chain ends at the
+    /// first `else` that does not continue it; that `else` compiles as the
+    /// fallback. Comparing a local has no side effects, so testing the
+    /// values in any order behaves the same as testing them one by one.
+    fn scan_dispatch_chain(&self) -> Option<DispatchChain> {
+        let mut lexer = self.lexer.clone();
+        let mut name = None;
+        let mut values = Vec::new();
+        loop {
+            let mut branch = lexer.clone();
+            let Some((ident, value)) = Self::scan_dispatch_branch(&mut branch) else {
+                break;
+            };
+            if name.get_or_insert_with(|| ident.clone()) != &ident {
+                break;
+            }
+            values.push(value);
+            lexer = branch;
+            // Continue only through `else if`
+            let mut next = lexer.clone();
+            if next.next_token() != Token::Else || next.next_token() != Token::If {
+                break;
+            }
+            lexer = next;
+        }
+        if values.len() < MIN_DISPATCH_BRANCHES {
+            return None;
+        }
+        let slot = self.resolve_local(&name?)?;
+        Some(DispatchChain { slot, values })
+    }
+
+    /// Scan `(x === 1) { .. }` after an `if`, returning the variable and value
+    fn scan_dispatch_branch(lexer: &mut Lexer<'_>) -> Option<(String, i32)> {
+        if lexer.next_token() != Token::LParen {
+            return None;
+        }
+        let Token::Ident(name) = lexer.next_token() else {
+            return None;
+        };
+        if lexer.next_token() != Token::EqEqEq {
+            return None;
+        }
+        let value = Self::scan_int_literal(lexer)?;
+        if lexer.next_token() != Token::RParen || lexer.next_token() != Token::LBrace {
+            return None;
+        }
+        let mut depth = 1;
+        while depth > 0 {
+            match lexer.next_token() {
+                Token::LBrace => depth += 1,
+                Token::RBrace => depth -= 1,
+                Token::Eof => return None,
+                _ => {}
+            }
+        }
+        Some((name, value))
+    }
+
+    /// Scan an integer literal, optionally negated, that fits in a value
+    fn scan_int_literal(lexer: &mut Lexer<'_>) -> Option<i32> {
+        let (negate, token) = match lexer.next_token() {
+            Token::Minus => (true, lexer.next_token()),
+            token => (false, token),
+        };
+        let Token::Number(n) = token else {
+            return None;
+        };
+        let n = if negate { -n } else { n };
+        let in_range = n >= Value::INT_MIN as f64 && n <= Value::INT_MAX as f64;
+        (n.fract() == 0.0 && in_range).then_some(n as i32)
+    }
+
+    /// Compile a chain found by [`Self::scan_dispatch_chain`]
+    ///
+    /// The discriminant is checked to be a number once, then a binary
+    /// search over the sorted values jumps straight to the matching branch.
+    /// Values repeated later in the chain can never match, as before.
+    fn dispatch_chain(&mut self, chain: DispatchChain) -> Result<(), CompileError> {
+        let DispatchChain { slot, values } = chain;
+        self.locals[slot].read = true;
+
+        // Ordering a string or an object would convert it, so anything but
+        // a number goes straight to the fallback
+        let mut fallback = Vec::new();
+        self.emit_get_local(slot);
+        self.emit_op(OpCode::TypeOf);
+        self.emit_op(OpCode::PushConst);
+        let number = self.add_constant(Value::string(crate::value::STR_NUMBER));
+        self.emit_u16(number);
+        self.emit_op(OpCode::StrictEq);
+        fallback.push(self.emit_jump(OpCode::IfFalse));
+
+        let mut cases: Vec<(i32, usize)> = values.iter().copied().zip(0..).collect();
+        cases.sort_by_key(|&(value, _)| value);
+        cases.dedup_by_key(|&mut (value, _)| value);
+        let mut branches: Vec<Vec<JumpPatch>> = values.iter().map(|_| Vec::new()).collect();
+        self.emit_dispatch(slot, &cases, &mut branches, &mut fallback);
+
+        let mut ends = Vec::new();
+        for (i, jumps) in branches.into_iter().enumerate() {
+            if i > 0 {
+                self.expect(Token::Else)?;
+            }
+            // The condition was already compiled into the dispatch
+            self.expect(Token::If)?;
+            self.expect(Token::LParen)?;
+            self.advance(); // the local
+            self.expect(Token::EqEqEq)?;
+            self.match_token(&Token::Minus);
+            self.advance(); // the literal
+            self.expect(Token::RParen)?;
+
+            for jump in jumps {
+                self.patch_jump(jump);
+            }
+            self.statement()?;
+            ends.push(self.emit_jump(OpCode::Goto));
+        }
+
+        for jump in fallback {
+            self.patch_jump(jump);
+        }
+        if self.match_token(&Token::Else) {
+            self.statement()?;
+        }
+        for jump in ends {
+            self.patch_jump(jump);
+        }
+        Ok(())
+    }
+
+    /// Emit the search for the local's value among sorted `cases`
+    fn emit_dispatch(
+        &mut self,
+        slot: usize,
+        cases: &[(i32, usize)],
+        branches: &mut [Vec<JumpPatch>],
+        fallback: &mut Vec<JumpPatch>,
+    ) {
+        if cases.len() <= DISPATCH_LINEAR_CASES {
+            for &(value, branch) in cases {
+                self.emit_get_local(slot);
+                self.emit_int(value);
+                self.emit_op(OpCode::StrictEq);
+                branches[branch].push(self.emit_jump(OpCode::IfTrue));
+            }
+            fallback.push(self.emit_jump(OpCode::Goto));
+            return;
+        }
+        let (lower, upper) = cases.split_at(cases.len() / 2);
+        self.emit_get_local(slot);
+        self.emit_int(upper[0].0);
+        self.emit_op(OpCode::Lt);
+        let to_upper = self.emit_jump(OpCode::IfFalse);
+        self.emit_dispatch(slot, lower, branches, fallback);
+        self.patch_jump(to_upper);
+        self.emit_dispatch(slot, upper, branches, fallback);
+    }
+
+    /// Parse an if or loop condition, warning when it is a bare assignment
+    fn condition(&mut self) -> Result<(), CompileError> {
+        self.expression()?;
+        if let Some((end, pos)) = self.last_assignment
+            && end == self.bytecode.len()
+        {
+            self.warn(
+                DiagnosticKind::AssignmentInCondition,
+                "assignment used as a condition; did you mean '=='? \
+                 (wrap it in parentheses if intended)"
+                    .into(),
+                pos,
+            );
+        }
+        Ok(())
+    }
+
     /// Parse while statement
     fn while_statement(&mut self) -> Result<(), CompileError> {
         self.advance(); // consume 'while'
@@ -849,10 +1712,11 @@ impl<'a> Compiler<'a> {
             continue_target: loop_start,
             break_patches: Vec::new(),
             scope_depth: self.scope_depth,
+            try_depth: self.try_depth,
         });
 
         self.expect(Token::LParen)?;
-        self.expression()?;
+        self.condition()?;
         self.expect(Token::RParen)?;
 
         let exit_jump = self.emit_jump(OpCode::IfFalse);
@@ -871,53 +1735,62 @@ impl<'a> Compiler<'a> {
         Ok(())
     }
 
-    /// Parse for statement (C-style or for-in)
+    /// Parse for statement (C-style, for-in or for-of)
+    ///
+    /// A `var` in the head is declared in the enclosing scope, so it stays
+    /// visible after the loop and later loops can declare it again. `let`
+    /// and `const` bindings, and the hidden iterator of for-in/for-of, live
+    /// in the loop's own scope and are dropped when it ends. The target of
+    /// `for (k in obj)` is an existing variable, or a global.
     fn for_statement(&mut self) -> Result<(), CompileError> {
         self.advance(); // consume 'for'
         self.expect(Token::LParen)?;
 
-        self.begin_scope();
-
-        // Check for for-in syntax: for (var/let x in obj)
-        if self.check(&Token::Var) || self.check(&Token::Let) {
-            self.advance(); // consume var/let
-
-            if let Token::Ident(name) = self.current_token.clone() {
-                // Look ahead to check if this is for-in
-                // We need to save state and peek at next token
-                let saved_name = name.clone();
+        if self.match_token(&Token::Var) {
+            let name_pos = self.token_pos;
+            let Token::Ident(name) = self.current_token.clone() else {
+                return Err(CompileError::SyntaxError(
+                    "Expected identifier in for loop".to_string(),
+                ));
+            };
+            if matches!(self.peek_token(), Token::In | Token::Of) {
                 self.advance(); // consume identifier
-
-                if self.match_token(&Token::In) {
-                    // This is a for-in loop
-                    return self.for_in_statement_rest(saved_name);
+                let (index, fresh) = self.declare_var(&name, name_pos)?;
+                if fresh {
+                    self.emit_op(OpCode::Undefined);
+                    self.emit_set_local(index);
                 }
+                self.begin_scope();
+                return self.for_in_of_rest(name, Some(VarTarget::Local(index)));
+            }
+            self.declarators(true)?;
+            self.expect(Token::Semicolon)?;
+            self.begin_scope();
+            return self.for_c_style_rest();
+        }
 
-                if self.match_token(&Token::Of) {
-                    // This is a for-of loop
-                    return self.for_of_statement_rest(saved_name);
-                }
+        self.begin_scope();
 
-                // Not a for-in loop, restore and continue as C-style for
-                // We already consumed var/let and identifier, need to handle initializer
-                // Declare local first to get the index
-                let index = self.declare_local(&saved_name)?;
-                // Check for initializer
-                if self.match_token(&Token::Eq) {
-                    // Has initializer: var x = expr
-                    self.expression()?;
-                } else {
-                    // No initializer, push undefined
-                    self.emit_op(OpCode::Undefined);
-                }
-                // Store to local
-                self.emit_set_local(index);
-                self.expect(Token::Semicolon)?;
-            } else {
+        if self.check(&Token::Let) || self.check(&Token::Const) {
+            self.advance(); // consume let/const
+            let Token::Ident(name) = self.current_token.clone() else {
                 return Err(CompileError::SyntaxError(
                     "Expected identifier in for loop".to_string(),
                 ));
+            };
+            if matches!(self.peek_token(), Token::In | Token::Of) {
+                self.advance(); // consume identifier
+                return self.for_in_of_rest(name, None);
             }
+            self.declarators(false)?;
+            self.expect(Token::Semicolon)?;
+        } else if let Token::Ident(name) = self.current_token.clone() {
+            if matches!(self.peek_token(), Token::In | Token::Of) {
+                self.advance(); // consume identifier
+                let target = self.resolve_var(&name);
+                return self.for_in_of_rest(name, Some(target));
+            }
+            self.expression_statement()?;
         } else if !self.match_token(&Token::Semicolon) {
             // C-style initializer expression
             self.expression_statement()?;
@@ -927,8 +1800,29 @@ impl<'a> Compiler<'a> {
         self.for_c_style_rest()
     }
 
-    /// Parse rest of for-in statement after "for (var/let name in"
-    fn for_in_statement_rest(&mut self, var_name: String) -> Result<(), CompileError> {
+    /// Parse the rest of a for-in or for-of head from its `in`/`of`
+    ///
+    /// `target` is the variable each key or value is stored to; `None`
+    /// declares `name` as a new local of the loop scope.
+    fn for_in_of_rest(
+        &mut self,
+        name: String,
+        target: Option<VarTarget>,
+    ) -> Result<(), CompileError> {
+        if self.match_token(&Token::In) {
+            self.for_in_statement_rest(name, target)
+        } else {
+            self.expect(Token::Of)?;
+            self.for_of_statement_rest(name, target)
+        }
+    }
+
+    /// Parse rest of for-in statement after "for (name in"
+    fn for_in_statement_rest(
+        &mut self,
+        var_name: String,
+        target: Option<VarTarget>,
+    ) -> Result<(), CompileError> {
         // Parse the object to iterate over
         self.expression()?;
         self.expect(Token::RParen)?;
@@ -941,10 +1835,17 @@ impl<'a> Compiler<'a> {
         let iter_slot = self.declare_local("\x00iter")?;
         self.emit_set_local(iter_slot);
 
-        // Declare the loop variable
-        self.emit_op(OpCode::Undefined);
-        let var_slot = self.declare_local(&var_name)?;
-        self.emit_set_local(var_slot);
+        // Declare the loop variable unless the head names an existing one
+        let target = match target {
+            Some(target) => target,
+            None => {
+                self.emit_op(OpCode::Undefined);
+                let var_slot = self.declare_local(&var_name)?;
+                self.emit_set_local(var_slot);
+                VarTarget::Local(var_slot)
+            }
+        };
+        self.mark_written(target);
 
         // Loop start - get next key
         let loop_start = self.current_offset();
@@ -954,6 +1855,7 @@ impl<'a> Compiler<'a> {
             continue_target: loop_start,
             break_patches: Vec::new(),
             scope_depth: self.scope_depth,
+            try_depth: self.try_depth,
         });
 
         // Get iterator from hidden local
@@ -965,8 +1867,8 @@ impl<'a> Compiler<'a> {
         // Check if done (pops done flag)
         let exit_jump = self.emit_jump(OpCode::IfTrue);
 
-        // Store key to loop variable (PutLoc pops the key)
-        self.emit_set_local(var_slot);
+        // Store key to loop variable (the store pops the key)
+        self.emit_set_var(&var_name, target);
 
         // Body
         self.statement()?;
@@ -986,13 +1888,22 @@ impl<'a> Compiler<'a> {
             self.patch_jump(patch);
         }
 
+        // Clear the hidden iterator so the slot never hands it to a later
+        // local, whether the loop finished or was broken out of
+        self.emit_op(OpCode::Undefined);
+        self.emit_set_local(iter_slot);
+
         self.end_scope();
 
         Ok(())
     }
 
-    /// Parse rest of for-of statement after "for (var/let name of"
-    fn for_of_statement_rest(&mut self, var_name: String) -> Result<(), CompileError> {
+    /// Parse rest of for-of statement after "for (name of"
+    fn for_of_statement_rest(
+        &mut self,
+        var_name: String,
+        target: Option<VarTarget>,
+    ) -> Result<(), CompileError> {
         // Parse the iterable to iterate over
         self.expression()?;
         self.expect(Token::RParen)?;
@@ -1004,10 +1915,17 @@ impl<'a> Compiler<'a> {
         let iter_slot = self.declare_local("\x00iter")?;
         self.emit_set_local(iter_slot);
 
-        // Declare the loop variable
-        self.emit_op(OpCode::Undefined);
-        let var_slot = self.declare_local(&var_name)?;
-        self.emit_set_local(var_slot);
+        // Declare the loop variable unless the head names an existing one
+        let target = match target {
+            Some(target) => target,
+            None => {
+                self.emit_op(OpCode::Undefined);
+                let var_slot = self.declare_local(&var_name)?;
+                self.emit_set_local(var_slot);
+                VarTarget::Local(var_slot)
+            }
+        };
+        self.mark_written(target);
 
         // Loop start - get next value
         let loop_start = self.current_offset();
@@ -1017,6 +1935,7 @@ impl<'a> Compiler<'a> {
             continue_target: loop_start,
             break_patches: Vec::new(),
             scope_depth: self.scope_depth,
+            try_depth: self.try_depth,
         });
 
         // Get iterator from hidden local
@@ -1028,8 +1947,8 @@ impl<'a> Compiler<'a> {
         // Check if done (pops done flag)
         let exit_jump = self.emit_jump(OpCode::IfTrue);
 
-        // Store value to loop variable (PutLoc pops the value)
-        self.emit_set_local(var_slot);
+        // Store value to loop variable (the store pops the value)
+        self.emit_set_var(&var_name, target);
 
         // Body
         self.statement()?;
@@ -1049,6 +1968,11 @@ impl<'a> Compiler<'a> {
             self.patch_jump(patch);
         }
 
+        // Clear the hidden iterator so the slot never hands it to a later
+        // local, whether the loop finished or was broken out of
+        self.emit_op(OpCode::Undefined);
+        self.emit_set_local(iter_slot);
+
         self.end_scope();
 
         Ok(())
@@ -1060,7 +1984,7 @@ impl<'a> Compiler<'a> {
 
         // Condition
         let exit_jump = if !self.match_token(&Token::Semicolon) {
-            self.expression()?;
+            self.condition()?;
             self.expect(Token::Semicolon)?;
             let j = self.emit_jump(OpCode::IfFalse);
             Some(j)
@@ -1091,6 +2015,7 @@ impl<'a> Compiler<'a> {
             continue_target,
             break_patches: Vec::new(),
             scope_depth: self.scope_depth,
+            try_depth: self.try_depth,
         });
 
         // Body
@@ -1129,6 +2054,8 @@ impl<'a> Compiler<'a> {
             ));
         }
 
+        self.emit_drop_catches();
+
         // Emit jump (will be patched when loop ends)
         let patch = self.emit_jump(OpCode::Goto);
 
@@ -1151,6 +2078,8 @@ impl<'a> Compiler<'a> {
             ));
         }
 
+        self.emit_drop_catches();
+
         // Get the continue target
         let continue_target = self.loop_stack.last().unwrap().continue_target;
 
@@ -1161,15 +2090,26 @@ impl<'a> Compiler<'a> {
         Ok(())
     }
 
+    /// Remove the handlers of try blocks entered since the innermost loop began,
+    /// so jumping out of the loop body does not leave them installed
+    fn emit_drop_catches(&mut self) {
+        let loop_try_depth = self.loop_stack.last().map_or(0, |ctx| ctx.try_depth);
+        for _ in loop_try_depth..self.try_depth {
+            self.emit_op(OpCode::DropCatch);
+        }
+    }
+
     /// Parse return statement
     fn return_statement(&mut self) -> Result<(), CompileError> {
         self.advance(); // consume 'return'
 
         if self.match_token(&Token::Semicolon) {
+            self.emit_global_sync();
             self.emit_op(OpCode::ReturnUndef);
         } else {
             self.expression()?;
             self.expect(Token::Semicolon)?;
+            self.emit_global_sync();
             self.emit_op(OpCode::Return);
         }
 
@@ -1178,6 +2118,12 @@ impl<'a> Compiler<'a> {
 
     /// Parse print statement: print expr;
     fn print_statement(&mut self) -> Result<(), CompileError> {
+        if !self.print_enabled {
+            return Err(CompileError::SyntaxError(format!(
+                "print statements are disabled at line {} column {}",
+                self.token_pos.line, self.token_pos.column
+            )));
+        }
         self.advance(); // consume 'print'
 
         self.expression()?;
@@ -1213,7 +2159,10 @@ impl<'a> Compiler<'a> {
         self.emit_i32(0); // placeholder
 
         // Parse try block
-        self.block_statement()?;
+        self.try_depth += 1;
+        let result = self.block_statement();
+        self.try_depth -= 1;
+        result?;
 
         // If we get here without exception, remove the handler
         self.emit_op(OpCode::DropCatch);
@@ -1233,7 +2182,7 @@ impl<'a> Compiler<'a> {
         if has_catch {
             self.advance(); // consume 'catch'
 
-            // Optional (e) parameter
+            // Optional (e) parameter; `catch { ... }` binds nothing
             if self.match_token(&Token::LParen) {
                 let name = match &self.current_token {
                     Token::Ident(s) => s.clone(),
@@ -1246,10 +2195,9 @@ impl<'a> Compiler<'a> {
                 self.advance();
                 self.expect(Token::RParen)?;
 
-                // Begin a new scope for the catch variable
+                // The catch variable lives in a scope of its own around the
+                // body, shadowing any outer variable of the same name
                 self.begin_scope();
-
-                // Declare the catch variable
                 let index = self.declare_local(&name)?;
 
                 // The exception value is on the stack from Catch opcode
@@ -1262,6 +2210,11 @@ impl<'a> Compiler<'a> {
                 }
                 self.block_statement()?;
 
+                // The slot outlives the scope and may be reused by a later
+                // declaration; clear it so it does not keep the exception
+                // alive until the function returns
+                self.emit_op(OpCode::Undefined);
+                self.emit_set_local(index);
                 self.end_scope();
             } else {
                 // No parameter - just discard the exception value
@@ -1312,13 +2265,43 @@ impl<'a> Compiler<'a> {
         self.advance(); // consume '{'
         self.begin_scope();
 
+        self.statement_list()?;
+
+        self.expect(Token::RBrace)?;
+        self.end_scope();
+
+        Ok(())
+    }
+
+    /// Parse statements up to a closing brace
+    ///
+    /// Warns once if anything follows a return, throw, break or continue.
+    fn statement_list(&mut self) -> Result<(), CompileError> {
+        let mut exit = None;
+        let mut reported = false;
         while !self.check(&Token::RBrace) && !self.check(&Token::Eof) {
+            match exit {
+                Some(keyword) if !reported => {
+                    self.warn(
+                        DiagnosticKind::UnreachableCode,
+                        format!("unreachable code after '{}'", keyword),
+                        self.token_pos,
+                    );
+                    reported = true;
+                }
+                Some(_) => {}
+                None => {
+                    exit = match self.current_token {
+                        Token::Return => Some("return"),
+                        Token::Throw => Some("throw"),
+                        Token::Break => Some("break"),
+                        Token::Continue => Some("continue"),
+                        _ => None,
+                    };
+                }
+            }
             self.statement()?;
         }
-
-        self.expect(Token::RBrace)?;
-        self.end_scope();
-
         Ok(())
     }
 
@@ -1326,10 +2309,20 @@ impl<'a> Compiler<'a> {
     fn expression_statement(&mut self) -> Result<(), CompileError> {
         self.expression()?;
         self.expect(Token::Semicolon)?;
+        // Before the Drop, so a script's completion value can still reuse it
+        self.end_statement();
         self.emit_op(OpCode::Drop); // Discard expression value
         Ok(())
     }
 
+    /// Mark a statement boundary where temporary arrays may be reclaimed
+    ///
+    /// Emitted after declarations, loops and expression statements: the
+    /// places where natives such as `Object.keys` leave short-lived results.
+    fn end_statement(&mut self) {
+        self.emit_op(OpCode::EndStatement);
+    }
+
     // =========================================================================
     // Expression parsing (precedence climbing)
     // =========================================================================
@@ -1341,6 +2334,10 @@ impl<'a> Compiler<'a> {
 
     /// Parse expression with given minimum precedence
     fn parse_precedence(&mut self, min_prec: Precedence) -> Result<(), CompileError> {
+        self.nested(|c| c.parse_precedence_inner(min_prec))
+    }
+
+    fn parse_precedence_inner(&mut self, min_prec: Precedence) -> Result<(), CompileError> {
         // Parse prefix expression
         self.prefix_expr()?;
 
@@ -1392,15 +2389,11 @@ impl<'a> Compiler<'a> {
             Token::Number(n) => {
                 let n = *n;
                 self.advance();
-                // Check if it's an integer that fits in short int range
-                if n.fract() == 0.0 && n >= -(1i64 << 30) as f64 && n <= ((1i64 << 30) - 1) as f64 {
-                    self.emit_int(n as i32);
-                } else {
-                    // TODO: Handle floats when float support is added to Value
-                    // For now, truncate to integer
-                    let int_val = n as i32;
-                    self.emit_int(int_val.clamp(-(1 << 30), (1 << 30) - 1));
-                }
+                // TODO: Handle floats when float support is added to Value
+                // For now fractions truncate, and integers beyond the value
+                // range saturate like the stand-ins for Infinity do
+                let int_val = (n as i64).clamp(Value::INT_MIN as i64, Value::INT_MAX as i64);
+                self.emit_int(int_val as i32);
             }
             Token::String(s) => {
                 let s = s.clone();
@@ -1465,27 +2458,17 @@ impl<'a> Compiler<'a> {
                 // Check for assignment
                 if self.is_assignment_op() {
                     let op = self.current_token.clone();
+                    let op_pos = self.token_pos;
                     self.advance();
 
-                    // Resolve variable: local, capture, or error
-                    let (is_local, idx) = if let Some(idx) = self.resolve_local(&name) {
-                        (true, idx)
-                    } else if let Some(idx) = self.resolve_capture(&name) {
-                        (false, idx)
-                    } else {
-                        return Err(CompileError::SyntaxError(format!(
-                            "Undefined variable '{}'",
-                            name
-                        )));
-                    };
+                    // Resolve variable: local, capture, or global
+                    let target = self.resolve_var(&name);
+                    self.mark_written(target);
 
                     // For compound assignment (+=, -=, etc.), get the current value first
                     if !matches!(op, Token::Eq) {
-                        if is_local {
-                            self.emit_get_local(idx);
-                        } else {
-                            self.emit_get_capture(idx);
-                        }
+                        self.mark_read(target);
+                        self.emit_get_var(&name, target);
                     }
 
                     // Parse the right-hand side
@@ -1510,12 +2493,27 @@ impl<'a> Compiler<'a> {
 
                     // Duplicate value (for expression result) and store
                     self.emit_op(OpCode::Dup);
-                    if is_local {
-                        self.emit_set_local(idx);
-                    } else {
-                        self.emit_set_capture(idx);
+                    self.emit_set_var(&name, target);
+                    if matches!(op, Token::Eq) {
+                        self.last_assignment = Some((self.bytecode.len(), op_pos));
                     }
+                } else if matches!(self.current_token, Token::PlusPlus | Token::MinusMinus) {
+                    // Post-increment/decrement: the old value is the result
+                    let op = if self.check(&Token::PlusPlus) {
+                        OpCode::Inc
+                    } else {
+                        OpCode::Dec
+                    };
+                    self.advance();
+                    let target = self.resolve_var(&name);
+                    self.mark_read(target);
+                    self.mark_written(target);
+                    self.emit_get_var(&name, target);
+                    self.emit_op(OpCode::Dup);
+                    self.emit_op(op);
+                    self.emit_set_var(&name, target);
                 } else if let Some(idx) = self.resolve_local(&name) {
+                    self.locals[idx].read = true;
                     self.emit_get_local(idx);
                 } else if let Some(idx) = self.resolve_capture(&name) {
                     self.emit_get_capture(idx);
@@ -1530,6 +2528,8 @@ impl<'a> Compiler<'a> {
                 self.advance();
                 self.expression()?;
                 self.expect(Token::RParen)?;
+                // Parentheses mark an assignment in a condition as intended
+                self.last_assignment = None;
             }
 
             // Unary operators
@@ -1574,22 +2574,13 @@ impl<'a> Compiler<'a> {
                 if let Token::Ident(name) = &self.current_token {
                     let name = name.clone();
                     self.advance();
-                    if let Some(idx) = self.resolve_local(&name) {
-                        self.emit_get_local(idx);
-                        self.emit_op(OpCode::Inc);
-                        self.emit_op(OpCode::Dup);
-                        self.emit_set_local(idx);
-                    } else if let Some(idx) = self.resolve_capture(&name) {
-                        self.emit_get_capture(idx);
-                        self.emit_op(OpCode::Inc);
-                        self.emit_op(OpCode::Dup);
-                        self.emit_set_capture(idx);
-                    } else {
-                        return Err(CompileError::SyntaxError(format!(
-                            "Undefined variable '{}'",
-                            name
-                        )));
-                    }
+                    let target = self.resolve_var(&name);
+                    self.mark_read(target);
+                    self.mark_written(target);
+                    self.emit_get_var(&name, target);
+                    self.emit_op(OpCode::Inc);
+                    self.emit_op(OpCode::Dup);
+                    self.emit_set_var(&name, target);
                 } else {
                     return Err(CompileError::SyntaxError(
                         "Invalid increment operand".into(),
@@ -1601,22 +2592,13 @@ impl<'a> Compiler<'a> {
                 if let Token::Ident(name) = &self.current_token {
                     let name = name.clone();
                     self.advance();
-                    if let Some(idx) = self.resolve_local(&name) {
-                        self.emit_get_local(idx);
-                        self.emit_op(OpCode::Dec);
-                        self.emit_op(OpCode::Dup);
-                        self.emit_set_local(idx);
-                    } else if let Some(idx) = self.resolve_capture(&name) {
-                        self.emit_get_capture(idx);
-                        self.emit_op(OpCode::Dec);
-                        self.emit_op(OpCode::Dup);
-                        self.emit_set_capture(idx);
-                    } else {
-                        return Err(CompileError::SyntaxError(format!(
-                            "Undefined variable '{}'",
-                            name
-                        )));
-                    }
+                    let target = self.resolve_var(&name);
+                    self.mark_read(target);
+                    self.mark_written(target);
+                    self.emit_get_var(&name, target);
+                    self.emit_op(OpCode::Dec);
+                    self.emit_op(OpCode::Dup);
+                    self.emit_set_var(&name, target);
                 } else {
                     return Err(CompileError::SyntaxError(
                         "Invalid decrement operand".into(),
@@ -1633,9 +2615,47 @@ impl<'a> Compiler<'a> {
                 self.emit_u16(count);
             }
 
+            // Object literal: { a: 1, "b-c": 2, 3: 4, [key]: 5 }
+            Token::LBrace => {
+                self.advance();
+                self.emit_op(OpCode::Object);
+                self.emit_u16(0);
+                self.object_literal()?;
+            }
+
+            // Function expression: function (a) { ... } or function name(a) { ... }
+            // A name is only in scope inside the function's own body
+            Token::Function => {
+                self.advance();
+                let name = match &self.current_token {
+                    Token::Ident(name) => Some(name.clone()),
+                    _ => None,
+                };
+                if name.is_some() {
+                    self.advance();
+                }
+                self.function_value(name.as_deref())?;
+            }
+
             // New expression: new Constructor() or new Constructor
             Token::New => {
                 self.advance();
+                // new.target: the constructor in a `new` call, undefined otherwise
+                if self.match_token(&Token::Dot) {
+                    if !matches!(&self.current_token, Token::Ident(name) if name == "target") {
+                        return Err(CompileError::SyntaxError(
+                            "Expected 'target' after 'new.'".into(),
+                        ));
+                    }
+                    if self.enclosing.is_empty() {
+                        return Err(CompileError::SyntaxError(
+                            "new.target is only allowed inside functions".into(),
+                        ));
+                    }
+                    self.advance();
+                    self.emit_op(OpCode::NewTarget);
+                    return self.postfix_expr();
+                }
                 // Parse the constructor expression (just the primary, not function call)
                 // We handle the member access chain but not the call
                 self.new_expr_target()?;
@@ -1747,6 +2767,7 @@ impl<'a> Compiler<'a> {
                 self.advance();
                 // Resolve as local, capture, or global
                 if let Some(idx) = self.resolve_local(&name) {
+                    self.locals[idx].read = true;
                     self.emit_get_local(idx);
                 } else if let Some(idx) = self.resolve_capture(&name) {
                     self.emit_get_capture(idx);
@@ -1799,6 +2820,10 @@ impl<'a> Compiler<'a> {
 
     /// Parse the operand of a delete expression
     /// Handles: delete obj.prop, delete arr[idx], delete variable
+    ///
+    /// Deleting a variable is a syntax error in strict mode. Otherwise it
+    /// is false for locals and captures, and for globals asks the
+    /// interpreter, which only removes bindings made by assignment.
     fn delete_expr(&mut self) -> Result<(), CompileError> {
         // Parse the base expression (identifier or grouped expression)
         match &self.current_token {
@@ -1806,8 +2831,25 @@ impl<'a> Compiler<'a> {
                 let name = name.clone();
                 self.advance();
 
+                if !matches!(self.current_token, Token::Dot | Token::LBracket) {
+                    if self.strict {
+                        return Err(CompileError::SyntaxError(format!(
+                            "cannot delete unqualified identifier '{}' in strict mode",
+                            name
+                        )));
+                    }
+                    match self.resolve_var(&name) {
+                        VarTarget::Local(_) | VarTarget::Capture(_) => {
+                            self.emit_op(OpCode::PushFalse)
+                        }
+                        VarTarget::Global => self.emit_global_op(OpCode::DeleteGlobal, &name),
+                    }
+                    return Ok(());
+                }
+
                 // Resolve the variable as local, capture, or global
                 if let Some(idx) = self.resolve_local(&name) {
+                    self.locals[idx].read = true;
                     self.emit_get_local(idx);
                 } else if let Some(idx) = self.resolve_capture(&name) {
                     self.emit_get_capture(idx);
@@ -1829,40 +2871,50 @@ impl<'a> Compiler<'a> {
             }
         }
 
-        // Now handle the property access (must have . or [])
-        match &self.current_token {
-            Token::Dot => {
-                self.advance();
-                if let Token::Ident(name) = &self.current_token {
+        // Now handle the property access (must have . or []); members
+        // before the last one are read, the last one is deleted
+        loop {
+            match &self.current_token {
+                Token::Dot => {
+                    self.advance();
+                    let Token::Ident(name) = &self.current_token else {
+                        return Err(CompileError::SyntaxError(
+                            "Expected property name after .".into(),
+                        ));
+                    };
                     let name = name.clone();
                     self.advance();
-                    // Push property name as string constant
                     let str_idx = self.string_constants.len() as u16;
                     self.string_constants.push(name);
+                    if matches!(self.current_token, Token::Dot | Token::LBracket) {
+                        self.emit_op(OpCode::GetField);
+                        self.emit_u16(str_idx);
+                        continue;
+                    }
+                    // Push property name as string constant
                     self.emit_op(OpCode::PushConst);
                     let const_idx = self.add_constant(Value::string(str_idx));
                     self.emit_u16(const_idx);
                     self.emit_op(OpCode::Delete);
-                } else {
-                    return Err(CompileError::SyntaxError(
-                        "Expected property name after .".into(),
-                    ));
+                }
+                Token::LBracket => {
+                    self.advance();
+                    self.expression()?;
+                    self.expect(Token::RBracket)?;
+                    if matches!(self.current_token, Token::Dot | Token::LBracket) {
+                        self.emit_op(OpCode::GetArrayEl);
+                        continue;
+                    }
+                    self.emit_op(OpCode::Delete);
+                }
+                _ => {
+                    // Anything but a reference deletes nothing and is true
+                    self.emit_op(OpCode::Drop);
+                    self.emit_op(OpCode::PushTrue);
                 }
             }
-            Token::LBracket => {
-                self.advance();
-                self.expression()?;
-                self.expect(Token::RBracket)?;
-                self.emit_op(OpCode::Delete);
-            }
-            _ => {
-                // delete on a simple variable - push undefined as key
-                // This is non-standard but we'll return true
-                self.emit_op(OpCode::Undefined);
-                self.emit_op(OpCode::Delete);
-            }
+            return Ok(());
         }
-        Ok(())
     }
 
     /// Parse function call arguments
@@ -1891,6 +2943,10 @@ impl<'a> Compiler<'a> {
     /// Parse array literal elements: expr, expr, ... ]
     /// Called after the opening '[' has been consumed
     fn array_literal(&mut self) -> Result<u16, CompileError> {
+        self.nested(Self::array_literal_inner)
+    }
+
+    fn array_literal_inner(&mut self) -> Result<u16, CompileError> {
         let mut count: u32 = 0;
 
         if !self.check(&Token::RBracket) {
@@ -1917,6 +2973,107 @@ impl<'a> Compiler<'a> {
         Ok(count as u16)
     }
 
+    fn object_literal(&mut self) -> Result<(), CompileError> {
+        self.nested(Self::object_literal_inner)
+    }
+
+    /// Compile the properties of an object literal after its `{`, leaving
+    /// the object on the stack
+    ///
+    /// Identifier and string keys are stored verbatim and numeric keys as
+    /// their canonical string, so `{42: x}` defines "42" just as `o[42]`
+    /// reads it. Computed keys are converted with ToString when the object
+    /// is built. `{x}` is short for `{x: x}` and `{f() {}}` for
+    /// `{f: function f() {}}`. `...expr` copies the own enumerable
+    /// properties of `expr` as `Object.assign` does, so later properties
+    /// override the keys it brings and it overrides earlier ones. A name
+    /// written twice is reported as a [`DiagnosticKind::DuplicateKey`].
+    fn object_literal_inner(&mut self) -> Result<(), CompileError> {
+        let mut keys: Vec<String> = Vec::new();
+        while !self.check(&Token::RBrace) {
+            if self.match_token(&Token::Ellipsis) {
+                self.expression()?;
+                self.emit_op(OpCode::CopyDataProperties);
+                self.emit_u16(0);
+                if !self.match_token(&Token::Comma) {
+                    break;
+                }
+                continue;
+            }
+
+            let ident = match &self.current_token {
+                Token::Ident(name) => Some(name.clone()),
+                _ => None,
+            };
+            let key = match &self.current_token {
+                Token::Ident(name) | Token::String(name) => Some(name.clone()),
+                Token::Number(n) => Some(number_key(*n)),
+                Token::LBracket => None,
+                _ => {
+                    return Err(CompileError::SyntaxError(format!(
+                        "Expected property name, found {:?}",
+                        self.current_token
+                    )));
+                }
+            };
+            if let Some(name) = &key {
+                if keys.contains(name) {
+                    self.warn(
+                        DiagnosticKind::DuplicateKey,
+                        format!(
+                            "duplicate key '{}' in object literal; the last value wins",
+                            name
+                        ),
+                        self.token_pos,
+                    );
+                } else {
+                    keys.push(name.clone());
+                }
+            }
+            self.advance();
+            if key.is_none() {
+                self.expression()?;
+                self.expect(Token::RBracket)?;
+            }
+
+            if let Some(name) = &ident
+                && matches!(self.current_token, Token::Comma | Token::RBrace)
+            {
+                // Shorthand property: read the variable of the same name
+                let target = self.resolve_var(name);
+                self.mark_read(target);
+                self.emit_get_var(name, target);
+            } else if self.check(&Token::LParen) {
+                self.function_value(key.as_deref())?;
+            } else if let Some(accessor @ ("get" | "set")) = ident.as_deref()
+                && !self.check(&Token::Colon)
+            {
+                return Err(CompileError::SyntaxError(format!(
+                    "{}ters in object literals are not yet supported",
+                    accessor
+                )));
+            } else {
+                self.expect(Token::Colon)?;
+                self.expression()?;
+            }
+
+            match key {
+                Some(name) => {
+                    let str_idx = self.string_constants.len() as u16;
+                    self.string_constants.push(name);
+                    self.emit_op(OpCode::DefineField);
+                    self.emit_u16(str_idx);
+                }
+                None => self.emit_op(OpCode::DefineArrayEl),
+            }
+
+            if !self.match_token(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(Token::RBrace)
+    }
+
     /// Get precedence and associativity of current infix operator
     fn infix_precedence(&self) -> Option<(Precedence, Associativity)> {
         use Associativity::*;
@@ -2013,14 +3170,16 @@ impl<'a> Compiler<'a> {
     fn short_circuit_expr(&mut self, op: &Token) -> Result<(), CompileError> {
         match op {
             Token::AmpAmp => {
-                // Left is on stack; if false, skip right
+                // Left is on stack; if false, skip right and keep it as the result
+                self.emit_op(OpCode::Dup);
                 let end_jump = self.emit_jump(OpCode::IfFalse);
                 self.emit_op(OpCode::Drop); // Drop the true value
                 self.parse_precedence(Precedence::LogicalAnd.next())?;
                 self.patch_jump(end_jump);
             }
             Token::PipePipe => {
-                // Left is on stack; if true, skip right
+                // Left is on stack; if true, skip right and keep it as the result
+                self.emit_op(OpCode::Dup);
                 let end_jump = self.emit_jump(OpCode::IfTrue);
                 self.emit_op(OpCode::Drop); // Drop the false value
                 self.parse_precedence(Precedence::LogicalOr.next())?;
@@ -2123,6 +3282,16 @@ enum Associativity {
     Right,
 }
 
+/// Property key for a numeric literal: integers print like the runtime's
+/// number-to-string so literal and computed keys agree
+fn number_key(n: f64) -> String {
+    if n.fract() == 0.0 && n >= i32::MIN as f64 && n <= i32::MAX as f64 {
+        IntStr::new(n as i32).as_str().to_string()
+    } else {
+        n.to_string()
+    }
+}
+
 /// Compiled function
 /// Capture info for closures (public for interpreter use)
 #[derive(Debug, Clone)]
@@ -2134,6 +3303,8 @@ pub struct CaptureInfo {
 }
 
 pub struct CompiledFunction {
+    /// Declared function name, None for the top-level script
+    pub name: Option<String>,
     /// Bytecode bytes
     pub bytecode: Vec<u8>,
     /// Constant pool
@@ -2148,19 +3319,35 @@ pub struct CompiledFunction {
     pub functions: Vec<CompiledFunction>,
     /// Capture information for closures
     pub captures: Vec<CaptureInfo>,
+    /// Whether the function, or one enclosing it, opts into strict mode
+    /// with a "use strict" directive
+    pub strict: bool,
+    /// Local slot and name of each top-level binding of a script; empty
+    /// for functions
+    pub global_slots: Vec<(u16, String)>,
 }
 
 /// Compilation error
 #[derive(Debug)]
 pub enum CompileError {
-    UnexpectedToken { expected: String, found: String },
+    UnexpectedToken {
+        expected: String,
+        found: String,
+    },
     SyntaxError(String),
     TooManyConstants,
     TooManyLocals,
+    TooDeeplyNested,
+    /// The script exceeded a limit on the work compiling it may take;
+    /// `pos` is where compilation stopped
+    BudgetExceeded {
+        limit: CompileLimit,
+        pos: SourcePos,
+    },
 }
 
-impl std::fmt::Display for CompileError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             CompileError::UnexpectedToken { expected, found } => {
                 write!(f, "Expected {}, found {}", expected, found)
@@ -2168,11 +3355,28 @@ impl std::fmt::Display for CompileError {
             CompileError::SyntaxError(msg) => write!(f, "Syntax error: {}", msg),
             CompileError::TooManyConstants => write!(f, "Too many constants"),
             CompileError::TooManyLocals => write!(f, "Too many local variables"),
+            CompileError::TooDeeplyNested => write!(f, "expression too deeply nested"),
+            CompileError::BudgetExceeded { limit, pos } => match limit {
+                CompileLimit::Tokens(n) => {
+                    write!(f, "script too large: exceeded {} tokens", n)?;
+                    write!(f, " at line {}", pos.line)
+                }
+                CompileLimit::Bytecode(n) => {
+                    write!(f, "script too large: exceeded {} bytes of bytecode", n)?;
+                    write!(f, " at line {}", pos.line)
+                }
+                CompileLimit::Time(timeout) => write!(
+                    f,
+                    "compilation took longer than {} ms at line {}",
+                    timeout.as_millis(),
+                    pos.line
+                ),
+            },
         }
     }
 }
 
-impl std::error::Error for CompileError {}
+impl core::error::Error for CompileError {}
 
 #[cfg(test)]
 mod tests {
@@ -2187,7 +3391,7 @@ mod tests {
     #[test]
     fn test_compile_integers() {
         let func = compile_expr("42").unwrap();
-        // Should emit: PushI8 42, Drop, ReturnUndef
+        // Should emit: PushI8 42, Return
         assert!(!func.bytecode.is_empty());
     }
 
@@ -2217,7 +3421,7 @@ mod tests {
     fn test_compile_negative_one() {
         // -1 is parsed as unary minus + 1
         let func = compile_expr("-1").unwrap();
-        // Should emit: Push1, Neg, Drop, ReturnUndef
+        // Should emit: Push1, Neg, Return
         assert_eq!(func.bytecode[0], OpCode::Push1 as u8);
         assert_eq!(func.bytecode[1], OpCode::Neg as u8);
     }
@@ -2240,7 +3444,7 @@ mod tests {
     #[test]
     fn test_compile_addition() {
         let func = compile_expr("1 + 2").unwrap();
-        // Should emit: Push1, Push2, Add, Drop, ReturnUndef
+        // Should emit: Push1, Push2, Add, Return
         assert_eq!(func.bytecode[0], OpCode::Push1 as u8);
         assert_eq!(func.bytecode[1], OpCode::Push2 as u8);
         assert_eq!(func.bytecode[2], OpCode::Add as u8);
@@ -2326,4 +3530,520 @@ mod tests {
         assert!(func.bytecode.contains(&(OpCode::IfFalse as u8)));
         assert!(func.bytecode.contains(&(OpCode::Goto as u8)));
     }
+
+    #[test]
+    fn test_compile_new_target() {
+        let source = "function F() { return new.target; }";
+        let func = Compiler::new(source).compile().unwrap();
+        assert!(
+            func.functions[0]
+                .bytecode
+                .contains(&(OpCode::NewTarget as u8))
+        );
+
+        assert!(Compiler::new("new.target;").compile().is_err());
+        assert!(
+            Compiler::new("function F() { new.foo; }")
+                .compile()
+                .is_err()
+        );
+    }
+
+    fn diagnostics(source: &str) -> Vec<(DiagnosticKind, usize)> {
+        let (_, diagnostics) = Compiler::new(source).compile_with_diagnostics().unwrap();
+        diagnostics.iter().map(|d| (d.kind, d.pos.line)).collect()
+    }
+
+    #[test]
+    fn test_diagnostics_clean_script() {
+        let source = "
+var total = 0;
+function sum(a) {
+    var n = 0;
+    for (var i = 0; i < a.length; ++i) {
+        n = n + a[i];
+    }
+    if ((total = n) > 10) { return total; }
+    return n;
+}
+sum([1, 2]);
+";
+        assert!(diagnostics(source).is_empty());
+    }
+
+    #[test]
+    fn test_diagnostics_kinds_and_lines() {
+        let source = "
+function f(x) {
+    var unused = 1;
+    var never;
+    if (x = 5) {
+        return never;
+        x = 2;
+    }
+    while (x = 0) { }
+    return x;
+}
+";
+        assert_eq!(
+            diagnostics(source),
+            vec![
+                (DiagnosticKind::AssignmentInCondition, 5),
+                (DiagnosticKind::UnreachableCode, 7),
+                (DiagnosticKind::AssignmentInCondition, 9),
+                (DiagnosticKind::UnusedVariable, 3),
+                (DiagnosticKind::UnassignedVariable, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_scoping() {
+        // Top-level bindings are globals and a captured local counts as used
+        let source = "
+var g;
+function outer() {
+    var seen = 0;
+    function inner() { seen = seen + 1; }
+    return inner;
+}
+{
+    let block = 1;
+}
+";
+        assert_eq!(
+            diagnostics(source),
+            vec![(DiagnosticKind::UnusedVariable, 9)]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_duplicate_object_keys() {
+        let source = "var o = {\n    a: 1,\n    \"b\": 2, 1: 3, [\"a\"]: 4,\n    b() { return 5; }, a, 1.0: 6\n};";
+        let (_, diagnostics) = Compiler::new(source).compile_with_diagnostics().unwrap();
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.kind, d.message.as_str(), d.pos.line, d.pos.column))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    DiagnosticKind::DuplicateKey,
+                    "duplicate key 'b' in object literal; the last value wins",
+                    4,
+                    5
+                ),
+                (
+                    DiagnosticKind::DuplicateKey,
+                    "duplicate key 'a' in object literal; the last value wins",
+                    4,
+                    24
+                ),
+                (
+                    DiagnosticKind::DuplicateKey,
+                    "duplicate key '1' in object literal; the last value wins",
+                    4,
+                    27
+                ),
+            ]
+        );
+        assert_eq!(diagnostics[0].severity, crate::parser::Severity::Warning);
+    }
+
+    #[test]
+    fn test_diagnostics_do_not_change_bytecode() {
+        let source = "function f() { var a; if (a = 1) { return a; throw 1; } } f();";
+        let plain = Compiler::new(source).compile().unwrap();
+        let (checked, diagnostics) = Compiler::new(source).compile_with_diagnostics().unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(plain.bytecode, checked.bytecode);
+        assert_eq!(plain.functions[0].bytecode, checked.functions[0].bytecode);
+    }
+
+    /// Source nesting `statements` and then `expressions` around a literal
+    ///
+    /// A `#` in an opener is replaced by its level, so nested function
+    /// declarations get distinct names.
+    fn nest(statements: &[(&str, &str)], expressions: &[(&str, &str)]) -> String {
+        let opens = statements.iter().chain(expressions);
+        let open: String = opens
+            .enumerate()
+            .map(|(i, (o, _))| o.replace('#', &i.to_string()))
+            .collect();
+        let close =
+            |shapes: &[(&str, &str)]| -> String { shapes.iter().rev().map(|(_, c)| *c).collect() };
+        format!("{}1{};{}", open, close(expressions), close(statements))
+    }
+
+    #[test]
+    fn test_nesting_depth_limit() {
+        let statements = [("{ ", " }"), ("if (1) ", ""), ("function f#() { ", " }")];
+        let expressions = [("(", ")"), ("[", "]"), ("- ", ""), ("!", "")];
+        // Each level costs at most two units, plus a few for the statement
+        let below = DEFAULT_MAX_NESTING_DEPTH / 2 - 4;
+        for shape in statements {
+            assert!(
+                Compiler::new(&nest(&vec![shape; below], &[]))
+                    .compile()
+                    .is_ok()
+            );
+            let above = Compiler::new(&nest(&vec![shape; 20_000], &[])).compile();
+            assert!(matches!(above, Err(CompileError::TooDeeplyNested)));
+        }
+        for shape in expressions {
+            assert!(
+                Compiler::new(&nest(&[], &vec![shape; below]))
+                    .compile()
+                    .is_ok()
+            );
+            let above = Compiler::new(&nest(&[], &vec![shape; 20_000])).compile();
+            assert!(matches!(above, Err(CompileError::TooDeeplyNested)));
+        }
+
+        let source = nest(&[], &[("(", ")"); 20]);
+        assert!(Compiler::new(&source).compile().is_ok());
+        let limited = Compiler::new(&source).with_max_nesting_depth(10).compile();
+        assert!(matches!(limited, Err(CompileError::TooDeeplyNested)));
+        assert_eq!(
+            CompileError::TooDeeplyNested.to_string(),
+            "expression too deeply nested"
+        );
+    }
+
+    #[test]
+    fn test_nesting_depth_fuzz() {
+        let statements = [("{ ", " }"), ("if (1) ", ""), ("function f#() { ", " }")];
+        let expressions = [("(", ")"), ("[", "]"), ("- ", ""), ("!", "")];
+        let mut seed: u32 = 7;
+        let mut next = |n: usize| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as usize % n
+        };
+        for _ in 0..200 {
+            // Random depth around the limit, statements outside expressions
+            let levels = next(DEFAULT_MAX_NESTING_DEPTH * 2);
+            let split = next(levels + 1);
+            let outer: Vec<_> = (0..split)
+                .map(|_| statements[next(statements.len())])
+                .collect();
+            let inner: Vec<_> = (split..levels)
+                .map(|_| expressions[next(expressions.len())])
+                .collect();
+            let source = nest(&outer, &inner);
+
+            match Compiler::new(&source).compile() {
+                Ok(_) => assert!(levels < DEFAULT_MAX_NESTING_DEPTH),
+                Err(CompileError::TooDeeplyNested) => {
+                    assert!(levels * 2 + 4 > DEFAULT_MAX_NESTING_DEPTH)
+                }
+                Err(e) => panic!("unexpected error {} for {}", e, source),
+            }
+        }
+    }
+
+    #[test]
+    fn test_compile_budget() {
+        // Two tokens a line, plus the end of input
+        let lines = 100_000;
+        let source = "1;\n".repeat(lines);
+        let tokens = 2 * lines + 1;
+        assert!(
+            Compiler::new(&source)
+                .with_max_tokens(tokens)
+                .compile()
+                .is_ok()
+        );
+        match Compiler::new(&source).with_max_tokens(tokens - 1).compile() {
+            Err(CompileError::BudgetExceeded { limit, .. }) => {
+                assert_eq!(limit, CompileLimit::Tokens(tokens - 1))
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+        // A small limit stops compilation at the token that exceeds it
+        // rather than after reading the whole script
+        match Compiler::new(&source).with_max_tokens(1000).compile() {
+            Err(err @ CompileError::BudgetExceeded { pos, .. }) => {
+                assert_eq!(pos.line, 501);
+                assert_eq!(
+                    err.to_string(),
+                    "script too large: exceeded 1000 tokens at line 501"
+                );
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+
+        // Bytecode counts across every function the script defines
+        let source: String = (0..200)
+            .map(|i| format!("function f{}() {{ return 1; }}\n", i))
+            .collect();
+        assert!(
+            Compiler::new(&source)
+                .with_max_bytecode(20_000)
+                .compile()
+                .is_ok()
+        );
+        match Compiler::new(&source).with_max_bytecode(200).compile() {
+            Err(CompileError::BudgetExceeded { limit, pos }) => {
+                assert_eq!(limit, CompileLimit::Bytecode(200));
+                assert!(pos.line < 200);
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_compile_timeout() {
+        /// Clock that moves a millisecond every time it is read
+        struct TickingClock(core::cell::Cell<u64>);
+        impl Clock for TickingClock {
+            fn now_ms(&self) -> u64 {
+                let now = self.0.get();
+                self.0.set(now + 1);
+                now
+            }
+            fn now_us(&self) -> u64 {
+                self.now_ms() * 1000
+            }
+            fn sleep_ms(&self, _ms: u64) {}
+        }
+
+        let source = "1;\n".repeat(100_000);
+        let clock = Rc::new(TickingClock(core::cell::Cell::new(0)));
+        let timeout = Duration::from_millis(10);
+        match Compiler::new(&source)
+            .with_timeout(clock.clone(), timeout)
+            .compile()
+        {
+            Err(CompileError::BudgetExceeded { limit, pos }) => {
+                assert_eq!(limit, CompileLimit::Time(timeout));
+                assert!(pos.line < 100_000);
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+        // The clock is read every few hundred tokens, not on each one
+        assert!(clock.0.get() < 20);
+
+        let clock = Rc::new(TickingClock(core::cell::Cell::new(0)));
+        assert!(
+            Compiler::new("1;\n")
+                .with_timeout(clock, timeout)
+                .compile()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_deleting_identifiers() {
+        for source in [
+            "'use strict'; var x = 1; delete x;",
+            "'use strict'; delete someGlobal;",
+            "'use strict'; function f(a) { return delete a; }",
+            "function f() { 'use strict'; var v; delete v; }",
+        ] {
+            match Compiler::new(source).compile() {
+                Err(CompileError::SyntaxError(msg)) => {
+                    assert!(
+                        msg.contains("cannot delete unqualified identifier"),
+                        "{}",
+                        msg
+                    )
+                }
+                other => panic!(
+                    "expected a syntax error for {}, got {:?}",
+                    source,
+                    other.err()
+                ),
+            }
+        }
+        // Property deletes and sloppy-mode identifier deletes still compile
+        assert!(
+            Compiler::new("'use strict'; var o = {a: 1}; delete o.a; delete o['a'];")
+                .compile()
+                .is_ok()
+        );
+        assert!(Compiler::new("var x = 1; delete x;").compile().is_ok());
+    }
+
+    #[test]
+    fn test_object_patterns_reject_unsupported_forms() {
+        for (source, expected) in [
+            ("var {a};", "Missing initializer"),
+            ("var {a = 1} = o;", "default values"),
+            ("var {a: {b}} = o;", "nested patterns"),
+            ("var {'a'} = o;", "Expected ':'"),
+            ("var {...r, a} = o;", "Expected RBrace"),
+        ] {
+            match Compiler::new(source).compile() {
+                Err(err) => assert!(err.to_string().contains(expected), "{}: {}", source, err),
+                Ok(_) => panic!("expected {} to fail", source),
+            }
+        }
+        assert!(
+            Compiler::new("let {a, 'b-c': b, 3: c, ...r} = o;")
+                .compile()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_captures_thread_through_intermediate_functions() {
+        let program = Compiler::new(
+            "function a(x) { function b() { function c() { return x; } return c; } return b; }",
+        )
+        .compile()
+        .unwrap();
+        let a = &program.functions[0];
+        let b = &a.functions[0];
+        let c = &b.functions[0];
+        // b never mentions x but still captures it for c
+        assert_eq!(b.captures.len(), 1);
+        assert!(b.captures[0].is_local);
+        assert_eq!(b.captures[0].outer_index, 0);
+        assert_eq!(c.captures.len(), 1);
+        assert!(!c.captures[0].is_local);
+        assert_eq!(c.captures[0].outer_index, 0);
+    }
+
+    #[test]
+    fn test_use_strict_directive_prologue() {
+        let program = Compiler::new("'use strict'; function f() { return 1; }")
+            .compile()
+            .unwrap();
+        assert!(program.strict);
+        // Nested functions inherit strictness
+        assert!(program.functions[0].strict);
+
+        let program = Compiler::new("function f() { \"use strict\"; } function g() {}")
+            .compile()
+            .unwrap();
+        assert!(!program.strict);
+        assert!(program.functions[0].strict);
+        assert!(!program.functions[1].strict);
+
+        // Only leading string statements are directives
+        assert!(!Compiler::new("1; 'use strict';").compile().unwrap().strict);
+        assert!(
+            !Compiler::new("'use strict'.length;")
+                .compile()
+                .unwrap()
+                .strict
+        );
+        assert!(Compiler::new("'a'; 'use strict'").compile().unwrap().strict);
+    }
+
+    #[test]
+    fn test_with_and_legacy_octal_are_rejected() {
+        let message = |source: &str| match Compiler::new(source).compile() {
+            Ok(_) => panic!("{} compiled", source),
+            Err(e) => e.to_string(),
+        };
+
+        assert_eq!(
+            message("var o = 1;\n  with (o) { o; }"),
+            "Syntax error: with statements are not supported at line 2 column 3"
+        );
+        assert_eq!(
+            message("var mode = 0755;"),
+            "Syntax error: legacy octal literal 0755 is not supported at line 1 column 12"
+        );
+        assert_eq!(
+            message("function f() { return 09; }"),
+            "Syntax error: legacy octal literal 09 is not supported at line 1 column 23"
+        );
+        // A property named with is still fine
+        assert!(Compiler::new("var o = [];\no.with;").compile().is_ok());
+    }
+
+    #[test]
+    fn test_unterminated_strings_report_their_position() {
+        let message = |source: &str| match Compiler::new(source).compile() {
+            Ok(_) => panic!("{} compiled", source),
+            Err(e) => e.to_string(),
+        };
+
+        assert_eq!(
+            message("var a = 1;\nvar s = 'no end\n;"),
+            "Syntax error: unterminated string literal at line 2 column 9"
+        );
+        assert_eq!(
+            message("var s = \"eof"),
+            "Syntax error: unterminated string literal at line 1 column 9"
+        );
+        assert_eq!(
+            message("var s = '\\01';"),
+            "Syntax error: octal escape sequences are not allowed at line 1 column 9"
+        );
+    }
+
+    #[test]
+    fn test_compile_object_literal_keys() {
+        let func = Compiler::new("var k = 'b'; var o = { a: 1, 'c-d': 2, 1.5: 3, [k]: 4 };")
+            .compile()
+            .unwrap();
+        assert!(func.bytecode.contains(&(OpCode::Object as u8)));
+        assert!(func.bytecode.contains(&(OpCode::DefineArrayEl as u8)));
+        for key in ["a", "c-d", "1.5"] {
+            assert!(func.string_constants.iter().any(|s| s == key), "{}", key);
+        }
+        assert_eq!(number_key(42.0), "42");
+        assert_eq!(number_key(-7.0), "-7");
+
+        assert!(Compiler::new("var o = { a 1 };").compile().is_err());
+        assert!(Compiler::new("var o = { +: 1 };").compile().is_err());
+    }
+
+    #[test]
+    fn test_compile_object_literal_shorthand() {
+        let func = Compiler::new("var x = 1; var o = { x, getX() { return this.x; } };")
+            .compile()
+            .unwrap();
+        assert_eq!(func.functions.len(), 1);
+        assert_eq!(func.functions[0].name.as_deref(), Some("getX"));
+
+        let message = |source: &str| match Compiler::new(source).compile() {
+            Ok(_) => panic!("{} compiled", source),
+            Err(e) => e.to_string(),
+        };
+        assert_eq!(
+            message("var o = { get x() { return 1; } };"),
+            "Syntax error: getters in object literals are not yet supported"
+        );
+        assert_eq!(
+            message("var o = { set x(v) {} };"),
+            "Syntax error: setters in object literals are not yet supported"
+        );
+        // Shorthand needs an identifier
+        assert!(Compiler::new("var o = { 'a' };").compile().is_err());
+    }
+
+    #[test]
+    fn test_long_else_if_chains_compile_to_a_search() {
+        let chain = |discriminant: &str, branches: i32| {
+            let mut source = format!("var x = 0; var r = 0; {}", discriminant);
+            for i in 0..branches {
+                if i > 0 {
+                    source.push_str(" else ");
+                }
+                source.push_str(&format!("if (x === {}) {{ r = {}; }}", i * 3 - 5, i));
+            }
+            source.push_str(" else { r = -1; }");
+            source
+        };
+        let comparisons = |source: &str| {
+            let func = Compiler::new(source).compile().unwrap();
+            crate::vm::opcode::instructions(&func.bytecode)
+                .filter(|insn| insn.as_ref().unwrap().1.opcode == OpCode::Lt)
+                .count()
+        };
+        assert_eq!(comparisons(&chain("", 7)), 0);
+        assert_eq!(comparisons(&chain("", 8)), 1);
+        assert!(comparisons(&chain("", 40)) > 1);
+        // Reading a global can throw, so only locals qualify
+        let global = chain("", 40).replace("x ===", "y ===");
+        assert_eq!(comparisons(&global), 0);
+        // A branch on another variable ends the chain early
+        let mixed = chain("", 40).replacen("x === 100", "r === 100", 1);
+        assert_eq!(comparisons(&mixed), comparisons(&chain("", 35)));
+    }
 }
@@ -0,0 +1,85 @@
+//! Compile-time diagnostics
+//!
+//! Warnings the compiler collects while parsing. They never change the
+//! generated bytecode; hosts can inspect them through
+//! [`Compiler::compile_with_diagnostics`](super::Compiler::compile_with_diagnostics)
+//! or a handler installed on the `Context`.
+
+use super::lexer::SourcePos;
+use crate::prelude::*;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Harmless but probably unintended
+    Hint,
+    /// Likely a bug
+    Warning,
+}
+
+/// What a diagnostic is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// A declared variable whose value is never read
+    UnusedVariable,
+    /// A variable that is read but never given a value
+    UnassignedVariable,
+    /// `if (x = 5)`: an assignment used directly as a condition
+    AssignmentInCondition,
+    /// A statement following return, throw, break or continue
+    UnreachableCode,
+    /// `{a: 1, a: 2}`: an object literal naming a key more than once
+    DuplicateKey,
+}
+
+impl DiagnosticKind {
+    /// Default severity for this kind of diagnostic
+    pub fn severity(self) -> Severity {
+        match self {
+            DiagnosticKind::UnusedVariable => Severity::Hint,
+            DiagnosticKind::UnassignedVariable
+            | DiagnosticKind::AssignmentInCondition
+            | DiagnosticKind::UnreachableCode
+            | DiagnosticKind::DuplicateKey => Severity::Warning,
+        }
+    }
+}
+
+/// A diagnostic reported during compilation
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    pub message: String,
+    /// Start of the offending token
+    pub pos: SourcePos,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic with the kind's default severity
+    pub fn new(kind: DiagnosticKind, message: String, pos: SourcePos) -> Self {
+        Diagnostic {
+            severity: kind.severity(),
+            kind,
+            message,
+            pos,
+        }
+    }
+}
+
+impl core::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let severity = match self.severity {
+            Severity::Hint => "hint",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.pos.line, self.pos.column, severity, self.message
+        )
+    }
+}
+
+/// Callback receiving diagnostics from scripts compiled by a `Context`
+pub type DiagnosticHandler = Box<dyn FnMut(&Diagnostic)>;
@@ -2,6 +2,8 @@
 //!
 //! Converts source text into a stream of tokens.
 
+use crate::prelude::*;
+
 /// Token types
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
@@ -49,6 +51,7 @@ pub enum Token {
     Semicolon, // ;
     Comma,     // ,
     Dot,       // .
+    Ellipsis,  // ...
 
     LParen,   // (
     RParen,   // )
@@ -123,11 +126,16 @@ pub struct SourcePos {
 }
 
 /// Lexer for JavaScript source code
+#[derive(Clone)]
 pub struct Lexer<'a> {
     source: &'a [u8],
     pos: usize,
     line: usize,
     column: usize,
+    /// Start of the most recently returned token
+    token_start: SourcePos,
+    /// Tokens returned so far
+    tokens_read: usize,
 }
 
 impl<'a> Lexer<'a> {
@@ -138,6 +146,8 @@ impl<'a> Lexer<'a> {
             pos: 0,
             line: 1,
             column: 1,
+            token_start: SourcePos::default(),
+            tokens_read: 0,
         }
     }
 
@@ -150,6 +160,16 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Get the start position of the most recently returned token
+    pub fn token_start(&self) -> SourcePos {
+        self.token_start
+    }
+
+    /// Number of tokens returned so far, the end of input included
+    pub fn tokens_read(&self) -> usize {
+        self.tokens_read
+    }
+
     /// Peek at the current character without consuming it
     fn peek(&self) -> Option<u8> {
         self.source.get(self.pos).copied()
@@ -207,6 +227,8 @@ impl<'a> Lexer<'a> {
     /// Read the next token
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespace();
+        self.token_start = self.position();
+        self.tokens_read += 1;
 
         let Some(c) = self.peek() else {
             return Token::Eof;
@@ -382,7 +404,15 @@ impl<'a> Lexer<'a> {
             b':' => Token::Colon,
             b';' => Token::Semicolon,
             b',' => Token::Comma,
-            b'.' => Token::Dot,
+            b'.' => {
+                if self.peek() == Some(b'.') && self.peek_next() == Some(b'.') {
+                    self.advance();
+                    self.advance();
+                    Token::Ellipsis
+                } else {
+                    Token::Dot
+                }
+            }
             b'(' => Token::LParen,
             b')' => Token::RParen,
             b'[' => Token::LBracket,
@@ -405,7 +435,7 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let ident = std::str::from_utf8(&self.source[start..self.pos])
+        let ident = core::str::from_utf8(&self.source[start..self.pos])
             .unwrap_or("")
             .to_string();
 
@@ -451,6 +481,16 @@ impl<'a> Lexer<'a> {
     fn read_number(&mut self) -> Token {
         let start = self.pos;
 
+        // Legacy octal literals (0755) and their decimal look-alikes (089)
+        // would otherwise silently read as decimal
+        if self.peek() == Some(b'0') && self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.advance();
+            }
+            let literal = core::str::from_utf8(&self.source[start..self.pos]).unwrap_or("0");
+            return Token::Error(format!("legacy octal literal {} is not supported", literal));
+        }
+
         // Integer part
         while let Some(c) = self.peek() {
             if c.is_ascii_digit() {
@@ -487,7 +527,7 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let num_str = std::str::from_utf8(&self.source[start..self.pos]).unwrap_or("0");
+        let num_str = core::str::from_utf8(&self.source[start..self.pos]).unwrap_or("0");
         match num_str.parse::<f64>() {
             Ok(n) => Token::Number(n),
             Err(_) => Token::Error(format!("Invalid number: {}", num_str)),
@@ -495,13 +535,23 @@ impl<'a> Lexer<'a> {
     }
 
     /// Read a string literal
+    ///
+    /// A backslash before a line terminator continues the string on the
+    /// next line and adds nothing; an unescaped line terminator ends the
+    /// literal with an error, as the end of input does.
     fn read_string(&mut self) -> Token {
         let quote = self.advance().unwrap();
-        let mut s = String::new();
+        // Collect raw bytes so multi-byte UTF-8 characters survive intact
+        let mut bytes = Vec::new();
+        // A bad escape is reported once the whole literal is consumed, so
+        // lexing resumes after it
+        let mut error = None;
 
         loop {
             match self.peek() {
-                None => return Token::Error("Unterminated string".to_string()),
+                None | Some(b'\n' | b'\r') => {
+                    return Token::Error("unterminated string literal".to_string());
+                }
                 Some(c) if c == quote => {
                     self.advance();
                     break;
@@ -509,24 +559,40 @@ impl<'a> Lexer<'a> {
                 Some(b'\\') => {
                     self.advance();
                     match self.advance() {
-                        Some(b'n') => s.push('\n'),
-                        Some(b'r') => s.push('\r'),
-                        Some(b't') => s.push('\t'),
-                        Some(b'\\') => s.push('\\'),
-                        Some(b'\'') => s.push('\''),
-                        Some(b'"') => s.push('"'),
-                        Some(c) => s.push(c as char),
-                        None => return Token::Error("Unterminated string".to_string()),
+                        Some(b'n') => bytes.push(b'\n'),
+                        Some(b'r') => bytes.push(b'\r'),
+                        Some(b't') => bytes.push(b'\t'),
+                        Some(b'v') => bytes.push(0x0b),
+                        Some(b'b') => bytes.push(0x08),
+                        Some(b'f') => bytes.push(0x0c),
+                        Some(b'0') if self.peek().is_some_and(|c| c.is_ascii_digit()) => {
+                            error.get_or_insert_with(|| {
+                                "octal escape sequences are not allowed".to_string()
+                            });
+                        }
+                        Some(b'0') => bytes.push(0),
+                        // Line continuation
+                        Some(b'\n') => {}
+                        Some(b'\r') => {
+                            if self.peek() == Some(b'\n') {
+                                self.advance();
+                            }
+                        }
+                        Some(c) => bytes.push(c),
+                        None => return Token::Error("unterminated string literal".to_string()),
                     }
                 }
                 Some(c) => {
                     self.advance();
-                    s.push(c as char);
+                    bytes.push(c);
                 }
             }
         }
 
-        Token::String(s)
+        match error {
+            Some(msg) => Token::Error(msg),
+            None => Token::String(String::from_utf8_lossy(&bytes).into_owned()),
+        }
     }
 }
 
@@ -535,6 +601,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_numbers() {
         let mut lexer = Lexer::new("42 3.14 1e10");
 
@@ -543,6 +610,23 @@ mod tests {
         assert!(matches!(lexer.next_token(), Token::Number(n) if n == 1e10));
     }
 
+    #[test]
+    fn test_legacy_octal_numbers_are_rejected() {
+        let mut lexer = Lexer::new("0 0.5 0755 089");
+
+        assert!(matches!(lexer.next_token(), Token::Number(n) if n == 0.0));
+        assert!(matches!(lexer.next_token(), Token::Number(n) if n == 0.5));
+        assert_eq!(
+            lexer.next_token(),
+            Token::Error("legacy octal literal 0755 is not supported".to_string())
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Token::Error("legacy octal literal 089 is not supported".to_string())
+        );
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
     #[test]
     fn test_strings() {
         let mut lexer = Lexer::new(r#""hello" 'world'"#);
@@ -551,6 +635,40 @@ mod tests {
         assert_eq!(lexer.next_token(), Token::String("world".to_string()));
     }
 
+    #[test]
+    fn test_string_escapes_and_line_continuations() {
+        let mut lexer = Lexer::new("'a\\0b\\v\\b\\f' \"one \\\ntwo\\\r\nthree\" 'x\\07'");
+
+        assert_eq!(
+            lexer.next_token(),
+            Token::String("a\0b\u{b}\u{8}\u{c}".to_string())
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Token::String("one twothree".to_string())
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Token::Error("octal escape sequences are not allowed".to_string())
+        );
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_unterminated_strings() {
+        let mut lexer = Lexer::new("'open\n1 \"eof");
+
+        assert_eq!(
+            lexer.next_token(),
+            Token::Error("unterminated string literal".to_string())
+        );
+        assert!(matches!(lexer.next_token(), Token::Number(n) if n == 1.0));
+        assert_eq!(
+            lexer.next_token(),
+            Token::Error("unterminated string literal".to_string())
+        );
+    }
+
     #[test]
     fn test_identifiers_and_keywords() {
         let mut lexer = Lexer::new("foo var if else");
@@ -572,6 +690,17 @@ mod tests {
         assert_eq!(lexer.next_token(), Token::BangEqEq);
     }
 
+    #[test]
+    fn test_ellipsis_and_dots() {
+        let mut lexer = Lexer::new("...a b.c");
+
+        assert_eq!(lexer.next_token(), Token::Ellipsis);
+        assert_eq!(lexer.next_token(), Token::Ident("a".into()));
+        assert_eq!(lexer.next_token(), Token::Ident("b".into()));
+        assert_eq!(lexer.next_token(), Token::Dot);
+        assert_eq!(lexer.next_token(), Token::Ident("c".into()));
+    }
+
     #[test]
     fn test_comments() {
         let mut lexer = Lexer::new("1 // comment\n2 /* block */ 3");
@@ -3,8 +3,10 @@
 //! Single-pass parser that generates bytecode directly.
 
 pub mod compiler;
+pub mod diagnostic;
 pub mod lexer;
 
 // Re-exports
 pub use compiler::Compiler;
+pub use diagnostic::{Diagnostic, DiagnosticHandler, DiagnosticKind, Severity};
 pub use lexer::{Lexer, Token};
@@ -0,0 +1,47 @@
+//! The parts of the standard prelude that live in `alloc`
+//!
+//! Modules import this with `use crate::prelude::*;` so they build the
+//! same way with and without the `std` feature.
+
+pub(crate) use alloc::boxed::Box;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec::Vec;
+pub(crate) use alloc::{format, vec};
+
+/// The `f64` methods std adds on top of `core`, computed with `libm`
+#[cfg(not(any(feature = "std", test)))]
+pub(crate) trait FloatExt {
+    fn floor(self) -> f64;
+    fn ceil(self) -> f64;
+    fn trunc(self) -> f64;
+    fn fract(self) -> f64;
+    fn sqrt(self) -> f64;
+    fn log10(self) -> f64;
+}
+
+#[cfg(not(any(feature = "std", test)))]
+impl FloatExt for f64 {
+    fn floor(self) -> f64 {
+        libm::floor(self)
+    }
+
+    fn ceil(self) -> f64 {
+        libm::ceil(self)
+    }
+
+    fn trunc(self) -> f64 {
+        libm::trunc(self)
+    }
+
+    fn fract(self) -> f64 {
+        self - libm::trunc(self)
+    }
+
+    fn sqrt(self) -> f64 {
+        libm::sqrt(self)
+    }
+
+    fn log10(self) -> f64 {
+        libm::log10(self)
+    }
+}
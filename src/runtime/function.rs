@@ -5,7 +5,11 @@
 //! - C functions (native Rust functions)
 //! - Function bytecode
 
+use crate::parser::compiler::CompiledFunction;
+use crate::prelude::*;
 use crate::value::Value;
+use crate::vm::atom::Atom;
+use crate::vm::opcode;
 
 /// Maximum number of function arguments
 pub const MAX_ARGS: u16 = 65535;
@@ -118,6 +122,53 @@ pub struct CaptureInfo {
     pub is_local: bool,
 }
 
+/// Static size figures for a compiled script, see [`FunctionBytecode::metrics`]
+///
+/// Totals cover the function and all of its inner functions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScriptMetrics {
+    /// Bytecode bytes
+    pub bytecode_bytes: usize,
+    /// Constant pool entries
+    pub constants: usize,
+    /// String constant pool entries
+    pub string_constants: usize,
+    /// UTF-8 bytes held by string constants
+    pub string_bytes: usize,
+    /// Largest local variable count of any function
+    pub max_locals: usize,
+    /// Deepest value stack any function can reach
+    pub max_stack_depth: usize,
+    /// Number of inner functions at any nesting depth
+    pub inner_functions: usize,
+}
+
+impl ScriptMetrics {
+    /// Figures for one function on its own
+    fn of_function(func: &FunctionBytecode) -> Self {
+        ScriptMetrics {
+            bytecode_bytes: func.bytecode.len(),
+            constants: func.constants.len(),
+            string_constants: func.string_constants.len(),
+            string_bytes: func.string_constants.iter().map(String::len).sum(),
+            max_locals: func.local_count as usize,
+            max_stack_depth: opcode::max_stack_depth(&func.bytecode).unwrap_or(0),
+            inner_functions: func.inner_functions.len(),
+        }
+    }
+
+    /// Fold another function's figures into these totals
+    fn add(&mut self, other: &ScriptMetrics) {
+        self.bytecode_bytes += other.bytecode_bytes;
+        self.constants += other.constants;
+        self.string_constants += other.string_constants;
+        self.string_bytes += other.string_bytes;
+        self.max_locals = self.max_locals.max(other.max_locals);
+        self.max_stack_depth = self.max_stack_depth.max(other.max_stack_depth);
+        self.inner_functions += other.inner_functions;
+    }
+}
+
 pub struct FunctionBytecode {
     /// Function name (for debugging)
     pub name: Option<String>,
@@ -143,6 +194,12 @@ pub struct FunctionBytecode {
     pub inner_functions: Vec<FunctionBytecode>,
     /// Capture information for closures
     pub captures: Vec<CaptureInfo>,
+    /// Property-key atoms of `string_constants`, resolved when an
+    /// interpreter loads the script; empty until then
+    pub atoms: Vec<Atom>,
+    /// Local slot and name of each top-level binding of a script, so
+    /// globalThis sees them while the script runs; empty for functions
+    pub global_slots: Vec<(u16, String)>,
 }
 
 impl FunctionBytecode {
@@ -161,6 +218,45 @@ impl FunctionBytecode {
             line_numbers: Vec::new(),
             inner_functions: Vec::new(),
             captures: Vec::new(),
+            atoms: Vec::new(),
+            global_slots: Vec::new(),
+        }
+    }
+
+    /// Convert the compiler's output, and its inner functions, to
+    /// bytecode the interpreter runs
+    pub fn from_compiled(compiled: CompiledFunction) -> Self {
+        let inner_functions = compiled
+            .functions
+            .into_iter()
+            .map(Self::from_compiled)
+            .collect();
+
+        // Convert compiler's CaptureInfo to runtime's CaptureInfo
+        let captures = compiled
+            .captures
+            .into_iter()
+            .map(|c| CaptureInfo {
+                outer_index: c.outer_index,
+                is_local: c.is_local,
+            })
+            .collect();
+
+        FunctionBytecode {
+            name: compiled.name,
+            arg_count: compiled.arg_count as u16,
+            local_count: compiled.local_count as u16,
+            stack_size: 64, // Default stack size
+            has_arguments: false,
+            bytecode: compiled.bytecode,
+            constants: compiled.constants,
+            string_constants: compiled.string_constants,
+            source_file: None,
+            line_numbers: Vec::new(),
+            inner_functions,
+            captures,
+            atoms: Vec::new(),
+            global_slots: compiled.global_slots,
         }
     }
 
@@ -243,6 +339,88 @@ impl FunctionBytecode {
         self.stack_size = self.local_count.saturating_add(16);
     }
 
+    /// Check this function and its inner functions decode cleanly
+    ///
+    /// Bytecode loaded from outside the compiler should pass this before it
+    /// is executed.
+    pub fn validate(&self) -> Result<(), String> {
+        opcode::validate(&self.bytecode).map_err(|e| match &self.name {
+            Some(name) => format!("{}: {}", name, e),
+            None => e.to_string(),
+        })?;
+        self.inner_functions.iter().try_for_each(|f| f.validate())
+    }
+
+    /// Render the bytecode of this function and its inner functions
+    pub fn disassemble(&self) -> String {
+        let mut out = format!(
+            "function {}:\n",
+            self.name.as_deref().unwrap_or("<anonymous>")
+        );
+        out.push_str(&opcode::disassemble(&self.bytecode));
+        for inner in &self.inner_functions {
+            out.push('\n');
+            out.push_str(&inner.disassemble());
+        }
+        out
+    }
+
+    /// Size figures for this function and its inner functions
+    ///
+    /// Derived from the bytecode alone, without running it, so scripts can
+    /// be checked against a flash or RAM budget before deployment. Bytecode
+    /// that fails [`FunctionBytecode::validate`] reports a stack depth of 0.
+    pub fn metrics(&self) -> ScriptMetrics {
+        let mut metrics = ScriptMetrics::of_function(self);
+        for inner in &self.inner_functions {
+            metrics.add(&inner.metrics());
+        }
+        metrics
+    }
+
+    /// Human-readable table of [`FunctionBytecode::metrics`], one row per
+    /// function with inner functions indented under their parent
+    pub fn report(&self) -> String {
+        let mut out = format!(
+            "{:<24} {:>8} {:>6} {:>8} {:>6} {:>6}\n",
+            "function", "bytecode", "consts", "strings", "locals", "stack"
+        );
+        self.report_rows(0, &mut out);
+        let total = self.metrics();
+        out.push_str(&format!(
+            "{:<24} {:>8} {:>6} {:>8} {:>6} {:>6}\n",
+            format!("total ({} functions)", total.inner_functions + 1),
+            total.bytecode_bytes,
+            total.constants,
+            total.string_bytes,
+            total.max_locals,
+            total.max_stack_depth
+        ));
+        out
+    }
+
+    fn report_rows(&self, depth: usize, out: &mut String) {
+        let name = match (&self.name, depth) {
+            (Some(name), _) => name.as_str(),
+            (None, 0) => "<script>",
+            (None, _) => "<anonymous>",
+        };
+        let m = ScriptMetrics::of_function(self);
+        out.push_str(&format!(
+            "{:<24} {:>8} {:>6} {:>8} {:>6} {:>6}\n",
+            format!("{}{}", "  ".repeat(depth), name),
+            m.bytecode_bytes,
+            m.constants,
+            m.string_bytes,
+            m.max_locals,
+            m.max_stack_depth
+        ));
+        for inner in &self.inner_functions {
+            inner.report_rows(depth + 1, out);
+        }
+    }
+
+    /// Serialize to bytes for bytecode file format
     /// Serialize to bytes for bytecode file format
     pub fn serialize(&self) -> Vec<u8> {
         let mut out = Vec::new();
@@ -310,6 +488,14 @@ impl FunctionBytecode {
             out.push(cap.is_local as u8);
         }
 
+        // Top-level bindings
+        out.extend_from_slice(&(self.global_slots.len() as u32).to_le_bytes());
+        for (slot, name) in &self.global_slots {
+            out.extend_from_slice(&slot.to_le_bytes());
+            out.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            out.extend_from_slice(name.as_bytes());
+        }
+
         out
     }
 
@@ -464,6 +650,14 @@ impl FunctionBytecode {
             });
         }
 
+        // Top-level bindings
+        let slot_count = read_u32(&mut pos)? as usize;
+        let mut global_slots = Vec::with_capacity(slot_count);
+        for _ in 0..slot_count {
+            let slot = read_u16(&mut pos)?;
+            global_slots.push((slot, read_string(&mut pos)?));
+        }
+
         Ok((
             FunctionBytecode {
                 name,
@@ -478,6 +672,8 @@ impl FunctionBytecode {
                 line_numbers,
                 inner_functions,
                 captures,
+                atoms: Vec::new(),
+                global_slots,
             },
             pos,
         ))
@@ -3,6 +3,7 @@
 //! JavaScript objects store properties in a hash table for fast lookup.
 //! This module implements the property storage and access operations.
 
+use crate::prelude::*;
 use crate::runtime::object::{Property, PropertyType};
 use crate::value::Value;
 
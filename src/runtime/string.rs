@@ -4,7 +4,7 @@
 //! This module provides the JSString type and string operations.
 
 use crate::gc::MemoryTag;
-use std::hash::{Hash, Hasher};
+use crate::prelude::*;
 
 /// Memory tag bits for header
 const MTAG_BITS: u32 = 4;
@@ -127,7 +127,7 @@ impl JSString {
     pub unsafe fn as_bytes(&self) -> &[u8] {
         unsafe {
             let ptr = (self as *const Self).add(1) as *const u8;
-            std::slice::from_raw_parts(ptr, self.len())
+            core::slice::from_raw_parts(ptr, self.len())
         }
     }
 
@@ -139,7 +139,7 @@ impl JSString {
     pub unsafe fn as_bytes_mut(&mut self) -> &mut [u8] {
         unsafe {
             let ptr = (self as *mut Self).add(1) as *mut u8;
-            std::slice::from_raw_parts_mut(ptr, self.len())
+            core::slice::from_raw_parts_mut(ptr, self.len())
         }
     }
 
@@ -149,7 +149,7 @@ impl JSString {
     /// Caller must ensure the string buffer contains valid UTF-8.
     #[inline]
     pub unsafe fn as_str(&self) -> &str {
-        unsafe { std::str::from_utf8_unchecked(self.as_bytes()) }
+        unsafe { core::str::from_utf8_unchecked(self.as_bytes()) }
     }
 
     /// Get pointer to string buffer
@@ -167,7 +167,7 @@ impl JSString {
     /// Calculate the total allocation size needed for a string
     #[inline]
     pub fn alloc_size(len: usize) -> usize {
-        std::mem::size_of::<Self>() + len + 1 // +1 for null terminator
+        core::mem::size_of::<Self>() + len + 1 // +1 for null terminator
     }
 
     /// Compare two strings for equality
@@ -181,14 +181,15 @@ impl JSString {
         unsafe { self.as_bytes() == other.as_bytes() }
     }
 
-    /// Hash the string content
+    /// Hash the string content (FNV-1a)
     ///
     /// # Safety
     /// String must be properly initialized.
     pub unsafe fn hash_content(&self) -> u64 {
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        unsafe { self.as_bytes().hash(&mut hasher) };
-        hasher.finish()
+        let bytes = unsafe { self.as_bytes() };
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+            (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        })
     }
 }
 
@@ -352,6 +353,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_jsstring_max_len() {
         // Just verify the constant is reasonable
         assert!(JSString::MAX_LEN > 1_000_000);
@@ -0,0 +1,896 @@
+//! Conversion between script values and serde data (`serde` feature)
+//!
+//! [`JsValueRef`] borrows a value from an interpreter and implements both
+//! `Serialize` and `Deserializer`, so a result can be written out with any
+//! serde format or read straight into a Rust type. [`to_value`] goes the
+//! other way and builds arrays and objects from anything `Serialize`.
+//!
+//! Only data converts: arrays, plain objects (own enumerable properties),
+//! strings, integers, booleans, null and undefined. Functions, dates,
+//! errors and other exotic values fail with [`SerdeError::Convert`], as do
+//! cyclic structures and numbers outside the engine's integer range.
+
+use crate::prelude::*;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::context::EvalError;
+use crate::value::Value;
+use crate::vm::{Interpreter, PropertyLookup};
+
+/// Nesting depth [`JsValueRef`] allows unless told otherwise
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Error from converting between script values and serde data
+#[derive(Debug)]
+pub enum SerdeError {
+    /// The script passed to `eval_into` failed before producing a value
+    Eval(EvalError),
+    /// The value has no serde representation or does not fit the
+    /// requested type
+    Convert(String),
+}
+
+impl core::fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SerdeError::Eval(e) => write!(f, "{}", e),
+            SerdeError::Convert(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl core::error::Error for SerdeError {}
+
+impl ser::Error for SerdeError {
+    fn custom<T: core::fmt::Display>(msg: T) -> Self {
+        SerdeError::Convert(msg.to_string())
+    }
+}
+
+impl de::Error for SerdeError {
+    fn custom<T: core::fmt::Display>(msg: T) -> Self {
+        SerdeError::Convert(msg.to_string())
+    }
+}
+
+/// A script value borrowed from its interpreter for serde
+///
+/// Serializing or deserializing walks arrays and objects recursively,
+/// failing on cycles and past [`JsValueRef::max_depth`] levels of nesting.
+///
+/// ```
+/// use mquickjs::embed::Context;
+///
+/// let mut ctx = Context::new(64 * 1024);
+/// let point: (i32, i32) = ctx.eval_into("[3, 4];").unwrap();
+/// assert_eq!(point, (3, 4));
+/// ```
+#[derive(Clone, Copy)]
+pub struct JsValueRef<'a> {
+    interp: &'a Interpreter,
+    value: Value,
+    max_depth: usize,
+    /// The array or object this value was reached through
+    parent: Option<&'a JsValueRef<'a>>,
+}
+
+/// The shape of a value, as far as serde is concerned
+enum Data<'a> {
+    Undefined,
+    Null,
+    Bool(bool),
+    Int(i32),
+    Str(String),
+    Array(Vec<Value>),
+    Object(Vec<(&'a str, Value)>),
+}
+
+impl<'a> JsValueRef<'a> {
+    /// Borrow `value` from `interp`
+    pub fn new(interp: &'a Interpreter, value: Value) -> Self {
+        Self {
+            interp,
+            value,
+            max_depth: DEFAULT_MAX_DEPTH,
+            parent: None,
+        }
+    }
+
+    /// Limit how deeply arrays and objects may nest
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// The borrowed value
+    pub fn value(&self) -> Value {
+        self.value
+    }
+
+    /// A value reached through this one
+    fn child(&'a self, value: Value) -> JsValueRef<'a> {
+        JsValueRef {
+            interp: self.interp,
+            value,
+            max_depth: self.max_depth,
+            parent: Some(self),
+        }
+    }
+
+    /// Classify the value, checking the path that reached it
+    fn data(&self) -> Result<Data<'a>, SerdeError> {
+        let interp = self.interp;
+        let value = self.value;
+        if value.is_undefined() {
+            return Ok(Data::Undefined);
+        }
+        if value.is_null() {
+            return Ok(Data::Null);
+        }
+        if let Some(b) = value.to_bool() {
+            return Ok(Data::Bool(b));
+        }
+        if let Some(n) = value.to_i32() {
+            return Ok(Data::Int(n));
+        }
+        if let Some(s) = interp.get_string(value) {
+            return Ok(Data::Str(s.to_string()));
+        }
+        if interp.is_callable(value) {
+            return Err(SerdeError::Convert("cannot convert a function".to_string()));
+        }
+
+        let container = if let Some(len) = interp.array_len(value) {
+            Data::Array(
+                (0..len)
+                    .filter_map(|i| interp.array_element_at(value, i))
+                    .collect(),
+            )
+        } else if let Some(count) = interp
+            .object_property_count(value)
+            .filter(|_| interp.date_value(value).is_none())
+        {
+            let entries = (0..count)
+                .filter_map(|i| interp.object_property_at(value, i))
+                .filter(|(key, _)| interp.has_property(value, key, PropertyLookup::OwnEnumerable))
+                .collect();
+            Data::Object(entries)
+        } else {
+            return Err(SerdeError::Convert(format!(
+                "cannot convert {}",
+                interp.format_value(value)
+            )));
+        };
+
+        let mut depth = 0;
+        let mut ancestor = self.parent;
+        while let Some(parent) = ancestor {
+            if parent.value == value {
+                return Err(SerdeError::Convert(
+                    "cannot convert a cyclic structure".to_string(),
+                ));
+            }
+            depth += 1;
+            ancestor = parent.parent;
+        }
+        if depth >= self.max_depth {
+            return Err(SerdeError::Convert(format!(
+                "value nests deeper than {} levels",
+                self.max_depth
+            )));
+        }
+        Ok(container)
+    }
+}
+
+impl Serialize for JsValueRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.data().map_err(ser::Error::custom)? {
+            Data::Undefined => serializer.serialize_none(),
+            Data::Null => serializer.serialize_unit(),
+            Data::Bool(b) => serializer.serialize_bool(b),
+            Data::Int(n) => serializer.serialize_i32(n),
+            Data::Str(s) => serializer.serialize_str(&s),
+            Data::Array(elements) => {
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+                for element in elements {
+                    seq.serialize_element(&self.child(element))?;
+                }
+                seq.end()
+            }
+            Data::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, val) in entries {
+                    map.serialize_entry(key, &self.child(val))?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for JsValueRef<'_> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.data()? {
+            Data::Undefined | Data::Null => visitor.visit_unit(),
+            Data::Bool(b) => visitor.visit_bool(b),
+            Data::Int(n) => visitor.visit_i32(n),
+            Data::Str(s) => visitor.visit_string(s),
+            Data::Array(elements) => visitor.visit_seq(Elements {
+                parent: &self,
+                elements: elements.into_iter(),
+            }),
+            Data::Object(entries) => visitor.visit_map(Entries {
+                parent: &self,
+                entries: entries.into_iter(),
+                pending: None,
+            }),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        if self.value.is_nullish() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.data()? {
+            // "Variant" for unit variants, { Variant: data } for the others
+            Data::Str(s) => visitor.visit_enum(s.into_deserializer()),
+            Data::Object(entries) if entries.len() == 1 => {
+                let (variant, value) = entries[0];
+                visitor.visit_enum(Variant {
+                    name: variant.to_string(),
+                    value: self.child(value),
+                })
+            }
+            _ => Err(SerdeError::Convert(
+                "expected a string or an object with one key for an enum".to_string(),
+            )),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Array elements for a visitor
+struct Elements<'p, 'a> {
+    parent: &'p JsValueRef<'a>,
+    elements: alloc::vec::IntoIter<Value>,
+}
+
+impl<'de> SeqAccess<'de> for Elements<'_, '_> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        match self.elements.next() {
+            Some(element) => seed.deserialize(self.parent.child(element)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+/// Object properties for a visitor
+struct Entries<'p, 'a> {
+    parent: &'p JsValueRef<'a>,
+    entries: alloc::vec::IntoIter<(&'a str, Value)>,
+    pending: Option<Value>,
+}
+
+impl<'de> MapAccess<'de> for Entries<'_, '_> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        match self.entries.next() {
+            Some((key, val)) => {
+                self.pending = Some(val);
+                seed.deserialize(key.to_string().into_deserializer())
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        let val = self.pending.take().unwrap_or_default();
+        seed.deserialize(self.parent.child(val))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// The variant and data of an enum written as `{ Variant: data }`
+struct Variant<'a> {
+    name: String,
+    value: JsValueRef<'a>,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for Variant<'a> {
+    type Error = SerdeError;
+    type Variant = JsValueRef<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, JsValueRef<'a>), SerdeError> {
+        let name = seed.deserialize(self.name.into_deserializer())?;
+        Ok((name, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for JsValueRef<'_> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+/// Build the script value `data` serializes to
+///
+/// Sequences and tuples become arrays; maps and structs become plain
+/// objects built with [`Interpreter::alloc_object`], so map keys must be
+/// strings or integers. `None` and `()` become null, and enums follow
+/// serde's externally tagged layout. Numbers must be integers within the
+/// engine's range. The value is not rooted: store it somewhere before the
+/// next collection.
+pub fn to_value<T: Serialize + ?Sized>(
+    interp: &mut Interpreter,
+    data: &T,
+) -> Result<Value, SerdeError> {
+    data.serialize(ValueSerializer { interp })
+}
+
+/// Serializer producing script values
+struct ValueSerializer<'a> {
+    interp: &'a mut Interpreter,
+}
+
+impl ValueSerializer<'_> {
+    fn int(self, n: i128) -> Result<Value, SerdeError> {
+        if (i128::from(Value::INT_MIN)..=i128::from(Value::INT_MAX)).contains(&n) {
+            Ok(Value::int(n as i32))
+        } else {
+            Err(SerdeError::Convert(format!(
+                "{} is out of range for a script number",
+                n
+            )))
+        }
+    }
+
+    /// `{ variant: value }`, serde's externally tagged enum layout
+    fn tagged(self, variant: &str, value: Value) -> Value {
+        self.interp.alloc_object().prop(variant, value).finish()
+    }
+}
+
+impl<'a> Serializer for ValueSerializer<'a> {
+    type Ok = Value;
+    type Error = SerdeError;
+    type SerializeSeq = ArrayBuilder<'a>;
+    type SerializeTuple = ArrayBuilder<'a>;
+    type SerializeTupleStruct = ArrayBuilder<'a>;
+    type SerializeTupleVariant = ArrayBuilder<'a>;
+    type SerializeMap = ObjectEntries<'a>;
+    type SerializeStruct = ObjectEntries<'a>;
+    type SerializeStructVariant = ObjectEntries<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, SerdeError> {
+        Ok(Value::bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, SerdeError> {
+        self.int(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, SerdeError> {
+        self.int(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, SerdeError> {
+        self.int(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, SerdeError> {
+        self.int(v.into())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, SerdeError> {
+        self.int(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, SerdeError> {
+        self.int(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, SerdeError> {
+        self.int(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, SerdeError> {
+        self.int(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, SerdeError> {
+        self.int(v.into())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, SerdeError> {
+        match i128::try_from(v) {
+            Ok(n) => self.int(n),
+            Err(_) => self.int(i128::MAX),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, SerdeError> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, SerdeError> {
+        if v.fract() == 0.0 && v.abs() < 1e18 {
+            self.int(v as i128)
+        } else {
+            Err(SerdeError::Convert(format!(
+                "{} is not an integer script number",
+                v
+            )))
+        }
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, SerdeError> {
+        Ok(self.interp.alloc_string(v.encode_utf8(&mut [0; 4])))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, SerdeError> {
+        Ok(self.interp.alloc_string(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, SerdeError> {
+        let elements = v.iter().map(|&b| Value::int(b.into())).collect();
+        Ok(self.interp.alloc_array(elements))
+    }
+
+    fn serialize_none(self) -> Result<Value, SerdeError> {
+        Ok(Value::null())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, SerdeError> {
+        Ok(Value::null())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, SerdeError> {
+        Ok(Value::null())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, SerdeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, SerdeError> {
+        let value = value.serialize(ValueSerializer {
+            interp: &mut *self.interp,
+        })?;
+        Ok(self.tagged(variant, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ArrayBuilder<'a>, SerdeError> {
+        Ok(ArrayBuilder {
+            interp: self.interp,
+            elements: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ArrayBuilder<'a>, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<ArrayBuilder<'a>, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<ArrayBuilder<'a>, SerdeError> {
+        let mut builder = self.serialize_seq(Some(len))?;
+        builder.variant = Some(variant);
+        Ok(builder)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<ObjectEntries<'a>, SerdeError> {
+        Ok(ObjectEntries {
+            interp: self.interp,
+            entries: Vec::new(),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<ObjectEntries<'a>, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<ObjectEntries<'a>, SerdeError> {
+        let mut entries = self.serialize_map(Some(len))?;
+        entries.variant = Some(variant);
+        Ok(entries)
+    }
+}
+
+/// Elements of an array being built
+struct ArrayBuilder<'a> {
+    interp: &'a mut Interpreter,
+    elements: Vec<Value>,
+    /// Tuple variant the array is tagged with
+    variant: Option<&'static str>,
+}
+
+impl ArrayBuilder<'_> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let value = value.serialize(ValueSerializer {
+            interp: &mut *self.interp,
+        })?;
+        self.elements.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, SerdeError> {
+        let array = self.interp.alloc_array(self.elements);
+        Ok(match self.variant {
+            Some(variant) => ValueSerializer {
+                interp: self.interp,
+            }
+            .tagged(variant, array),
+            None => array,
+        })
+    }
+}
+
+impl SerializeSeq for ArrayBuilder<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for ArrayBuilder<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for ArrayBuilder<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for ArrayBuilder<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+/// Properties of an object being built
+struct ObjectEntries<'a> {
+    interp: &'a mut Interpreter,
+    entries: Vec<(String, Value)>,
+    /// Key of the map entry whose value comes next
+    key: Option<String>,
+    /// Struct variant the object is tagged with
+    variant: Option<&'static str>,
+}
+
+impl ObjectEntries<'_> {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), SerdeError> {
+        let value = value.serialize(ValueSerializer {
+            interp: &mut *self.interp,
+        })?;
+        self.entries.push((key, value));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, SerdeError> {
+        let mut builder = self.interp.alloc_object();
+        for (key, value) in &self.entries {
+            builder = builder.prop(key, *value);
+        }
+        let object = builder.finish();
+        Ok(match self.variant {
+            Some(variant) => ValueSerializer {
+                interp: self.interp,
+            }
+            .tagged(variant, object),
+            None => object,
+        })
+    }
+}
+
+impl SerializeMap for ObjectEntries<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        let key = key.serialize(ValueSerializer {
+            interp: &mut *self.interp,
+        })?;
+        let key = match (key.to_i32(), self.interp.get_string(key)) {
+            (Some(n), _) => n.to_string(),
+            (None, Some(s)) => s.to_string(),
+            (None, None) => {
+                return Err(SerdeError::Convert(
+                    "object keys must be strings or integers".to_string(),
+                ));
+            }
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self.key.take().unwrap_or_default();
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for ObjectEntries<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for ObjectEntries<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Value, SerdeError> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::context::Context;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Inventory {
+        name: String,
+        counts: Vec<i32>,
+        prices: HashMap<String, u32>,
+        note: Option<String>,
+        kind: Kind,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Kind {
+        Store,
+        Depot { dock: u8 },
+    }
+
+    #[test]
+    fn test_round_trip_through_a_script() {
+        let mut ctx = Context::new(64 * 1024);
+        let inventory = Inventory {
+            name: "north".to_string(),
+            counts: vec![1, 2, 3],
+            prices: HashMap::from([("bolt".to_string(), 5), ("nut".to_string(), 2)]),
+            note: None,
+            kind: Kind::Depot { dock: 4 },
+        };
+        let value = ctx.value_from_serde(&inventory).unwrap();
+        ctx.set_global("inv", value);
+
+        let updated: Inventory = ctx
+            .eval(
+                "inv.counts.push(4); inv.prices.nut = 3; inv.note = \"restocked\"; \
+                 inv.kind.Depot.dock = inv.kind.Depot.dock + 1; inv;",
+            )
+            .map(|v| Inventory::deserialize(ctx.value_ref(v)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.counts, vec![1, 2, 3, 4]);
+        assert_eq!(updated.prices["nut"], 3);
+        assert_eq!(updated.prices["bolt"], 5);
+        assert_eq!(updated.note.as_deref(), Some("restocked"));
+        assert_eq!(updated.kind, Kind::Depot { dock: 5 });
+
+        let store: Kind = ctx.eval_into("var kind = \"Store\"; kind;").unwrap();
+        assert_eq!(store, Kind::Store);
+    }
+
+    #[test]
+    fn test_functions_cycles_and_depth_are_errors() {
+        let mut ctx = Context::new(64 * 1024);
+        let err = ctx
+            .eval_into::<HashMap<String, i32>>("var o = { n: 1, f() { return 1; } }; o;")
+            .unwrap_err();
+        assert!(matches!(&err, SerdeError::Convert(msg) if msg.contains("function")));
+
+        let err = ctx
+            .eval_into::<Vec<i32>>("function add(a, b) { return a + b; } [1, add];")
+            .unwrap_err();
+        assert!(matches!(&err, SerdeError::Convert(msg) if msg.contains("function")));
+
+        let err = ctx
+            .eval_into::<serde::de::IgnoredAny>("var a = [1]; a.push(a); a;")
+            .unwrap_err();
+        assert!(matches!(&err, SerdeError::Convert(msg) if msg.contains("cyclic")));
+
+        let nested = ctx.eval("[[[[1]]]];").unwrap();
+        let shallow = ctx.value_ref(nested).max_depth(2);
+        let err = Vec::<Vec<Vec<Vec<i32>>>>::deserialize(shallow).unwrap_err();
+        assert!(matches!(&err, SerdeError::Convert(msg) if msg.contains("deeper")));
+        let deep = Vec::<Vec<Vec<Vec<i32>>>>::deserialize(ctx.value_ref(nested)).unwrap();
+        assert_eq!(deep, vec![vec![vec![vec![1]]]]);
+
+        let err = ctx.eval_into::<i32>("throw 1;").unwrap_err();
+        assert!(matches!(err, SerdeError::Eval(_)));
+    }
+
+    #[test]
+    fn test_values_outside_the_integer_range_are_errors() {
+        let mut ctx = Context::new(64 * 1024);
+        assert!(ctx.value_from_serde(&(1i64 << 40)).is_err());
+        assert!(ctx.value_from_serde(&1.5f64).is_err());
+        let two = ctx.value_from_serde(&2.0f64).unwrap();
+        assert_eq!(two.to_i32(), Some(2));
+        assert!(ctx.value_from_serde(&HashMap::from([((1, 2), 3)])).is_err());
+    }
+}
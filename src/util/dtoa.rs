@@ -5,44 +5,42 @@
 /// Convert a 32-bit signed integer to decimal string
 ///
 /// Returns the number of characters written.
-pub fn i32_to_str(buf: &mut [u8], mut val: i32) -> usize {
-    if buf.is_empty() {
-        return 0;
+pub fn i32_to_str(buf: &mut [u8], val: i32) -> usize {
+    if val >= 0 {
+        return u32_to_str(buf, val as u32);
+    }
+    match buf.split_first_mut() {
+        Some((sign, digits)) => {
+            *sign = b'-';
+            1 + u32_to_str(digits, val.unsigned_abs())
+        }
+        None => 0,
     }
+}
 
-    let mut i = 0;
-    let negative = val < 0;
-
-    if negative {
-        buf[i] = b'-';
-        i += 1;
-        val = -val;
-    }
+/// Decimal text of an i32 held in a stack buffer
+///
+/// Lets number-to-string conversions append digits to a string being
+/// built without allocating a temporary for them.
+#[derive(Clone, Copy)]
+pub struct IntStr {
+    buf: [u8; 11],
+    len: u8,
+}
 
-    let start = i;
-    loop {
-        if i >= buf.len() {
-            break;
-        }
-        buf[i] = b'0' + (val % 10) as u8;
-        i += 1;
-        val /= 10;
-        if val == 0 {
-            break;
-        }
+impl IntStr {
+    /// Format `val`
+    pub fn new(val: i32) -> Self {
+        let mut buf = [0u8; 11];
+        let len = i32_to_str(&mut buf, val) as u8;
+        IntStr { buf, len }
     }
 
-    // Reverse the digits
-    let end = i;
-    let mut left = start;
-    let mut right = end - 1;
-    while left < right {
-        buf.swap(left, right);
-        left += 1;
-        right -= 1;
+    /// The digits, with a leading `-` for negative values
+    pub fn as_str(&self) -> &str {
+        // Only ASCII digits and '-' are ever written
+        core::str::from_utf8(&self.buf[..self.len as usize]).unwrap_or_default()
     }
-
-    end
 }
 
 /// Convert a 32-bit unsigned integer to decimal string
@@ -203,6 +201,16 @@ mod tests {
 
         let n = i32_to_str(&mut buf, i32::MAX);
         assert_eq!(&buf[..n], b"2147483647");
+
+        let n = i32_to_str(&mut buf, i32::MIN);
+        assert_eq!(&buf[..n], b"-2147483648");
+    }
+
+    #[test]
+    fn test_int_str_matches_display() {
+        for val in [0, 7, -7, 10, -10, 99, 1000, 123_456_789, i32::MAX, i32::MIN] {
+            assert_eq!(IntStr::new(val).as_str(), val.to_string());
+        }
     }
 
     #[test]
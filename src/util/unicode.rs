@@ -1,6 +1,19 @@
 //! Unicode utilities
 //!
 //! String handling with UTF-8 and UTF-16 support.
+//!
+//! Strings are held as UTF-8, which has no encoding for a surrogate code
+//! unit on its own. Every string built from UTF-16 code units, whether
+//! computed by `String.fromCharCode` or cut out of a longer string at a
+//! code unit index, pairs up the surrogates it can and stores each
+//! unpaired one as U+FFFD, so its UTF-16 length is still what the
+//! operation asked for.
+//!
+//! String indices seen by scripts, from `indexOf` and `slice` to the
+//! results of `search`, `match` and `exec`, count UTF-16 code units;
+//! [`utf8_to_utf16_index`] converts byte offsets found on the UTF-8 text.
+
+use crate::prelude::*;
 
 /// Check if a code point is a line terminator
 #[inline]
@@ -76,6 +89,25 @@ pub fn utf16_to_utf8_index(s: &str, utf16_index: usize) -> usize {
     s.len()
 }
 
+/// Build a string from UTF-16 code units, storing unpaired surrogates as
+/// U+FFFD
+pub fn from_code_units(units: &[u16]) -> String {
+    String::from_utf16_lossy(units)
+}
+
+/// The code units of `s` from `start` up to `end`, clamped to its length
+///
+/// Half of a surrogate pair cut off by either end becomes U+FFFD.
+pub fn slice_utf16(s: &str, start: usize, end: usize) -> String {
+    if s.is_ascii() {
+        let end = end.min(s.len());
+        return s[start.min(end)..end].to_string();
+    }
+    let units: Vec<u16> = s.encode_utf16().collect();
+    let end = end.min(units.len());
+    from_code_units(&units[start.min(end)..end])
+}
+
 /// Check if a string needs UTF-16 encoding (contains non-BMP characters)
 pub fn needs_surrogate_pairs(s: &str) -> bool {
     s.chars().any(|c| c.len_utf16() > 1)
@@ -160,4 +192,47 @@ mod tests {
         assert_eq!(code_unit_at_utf16(s, 2), Some(0xDE00)); // Low surrogate for 😀
         assert_eq!(code_unit_at_utf16(s, 3), Some(b'b' as u16));
     }
+
+    #[test]
+    fn test_slice_utf16() {
+        let s = "a😀b";
+
+        assert_eq!(slice_utf16(s, 0, 4), s);
+        assert_eq!(slice_utf16(s, 1, 3), "😀");
+        // Splitting the pair leaves replacement characters
+        assert_eq!(slice_utf16(s, 0, 2), "a\u{FFFD}");
+        assert_eq!(slice_utf16(s, 2, 9), "\u{FFFD}b");
+        assert_eq!(slice_utf16(s, 3, 1), "");
+        assert_eq!(slice_utf16("abc", 1, 9), "bc");
+    }
+
+    #[test]
+    fn test_from_code_units_random() {
+        // xorshift, so failures reproduce
+        let mut state = 0x2545_f491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        for _ in 0..2000 {
+            let len = (next() % 8) as usize;
+            // Mostly surrogates, to pair and orphan them in every order
+            let units: Vec<u16> = (0..len)
+                .map(|_| match next() % 4 {
+                    0 => (next() % 0x80) as u16,
+                    1 => 0xD800 + (next() % 0x400) as u16,
+                    2 => 0xDC00 + (next() % 0x400) as u16,
+                    _ => next() as u16,
+                })
+                .collect();
+            let s = from_code_units(&units);
+            assert_eq!(utf16_len(&s), units.len(), "{:04x?}", units);
+
+            let (a, b) = ((next() % 10) as usize, (next() % 10) as usize);
+            let cut = slice_utf16(&s, a, b);
+            assert_eq!(utf16_len(&cut), b.min(units.len()).saturating_sub(a));
+        }
+    }
 }
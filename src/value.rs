@@ -9,7 +9,7 @@
 //! - Bits 0-2 = 011: Special values (null, undefined, bool, exception, etc.)
 //! - Bits 0-2 = 101: Short float (limited range, no allocation needed)
 
-use std::fmt;
+use core::fmt;
 
 /// Size of a word in bytes (matches pointer size)
 #[cfg(target_pointer_width = "64")]
@@ -87,8 +87,19 @@ pub const TYPED_ARRAY_MARKER: i32 = 1 << 18;
 /// When bit 17 is set, it's an ArrayBuffer object index
 pub const ARRAY_BUFFER_MARKER: i32 = 1 << 17;
 
+/// Marker bit for strings stored inline in the StringChar payload
+/// When bit 26 is set, bits 24-25 hold the byte length and bits 0-23 the
+/// UTF-8 bytes, first byte lowest
+pub const INLINE_STRING_MARKER: i32 = 1 << 26;
+
+/// Longest string, in UTF-8 bytes, that is stored inline
+///
+/// Three bytes keep the payload within the 27 bits a special value has on
+/// 32-bit targets, so both widths share one encoding.
+pub const INLINE_STRING_MAX_LEN: usize = 3;
+
 /// Raw value representation - a single word
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct RawValue(pub usize);
 
@@ -188,6 +199,79 @@ impl fmt::Debug for RawValue {
     }
 }
 
+/// The kind of value a [`Value`] holds, as [`Value::classify`] decides it
+///
+/// Every encoding maps to exactly one tag. Operations that treat kinds
+/// differently (truthiness, ToNumber, ToString, `typeof`, loops, `in`,
+/// `instanceof`, printing) match on the tag exhaustively, so adding a kind
+/// of value fails to compile until each of them decides how to handle it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueTag {
+    Undefined,
+    Null,
+    Bool,
+    Int,
+    String,
+    Object,
+    Array,
+    Closure,
+    /// Function bytecode referenced by pointer
+    Bytecode,
+    /// Inner function index, only seen by the compiler
+    ShortFunc,
+    NativeFunc,
+    /// Builtin namespace, constructor or prototype object
+    Builtin,
+    Error,
+    RegExp,
+    TypedArray,
+    ArrayBuffer,
+    ForInIterator,
+    ForOfIterator,
+    /// Marker for a pending exception, never seen by scripts
+    Exception,
+    /// Marker for a binding in its temporal dead zone, never seen by scripts
+    Uninitialized,
+}
+
+impl ValueTag {
+    /// Every tag, in declaration order
+    pub const ALL: [ValueTag; 20] = [
+        ValueTag::Undefined,
+        ValueTag::Null,
+        ValueTag::Bool,
+        ValueTag::Int,
+        ValueTag::String,
+        ValueTag::Object,
+        ValueTag::Array,
+        ValueTag::Closure,
+        ValueTag::Bytecode,
+        ValueTag::ShortFunc,
+        ValueTag::NativeFunc,
+        ValueTag::Builtin,
+        ValueTag::Error,
+        ValueTag::RegExp,
+        ValueTag::TypedArray,
+        ValueTag::ArrayBuffer,
+        ValueTag::ForInIterator,
+        ValueTag::ForOfIterator,
+        ValueTag::Exception,
+        ValueTag::Uninitialized,
+    ];
+
+    /// Whether values of this kind can be called
+    ///
+    /// Builtin constructors and bound functions are callable too but share
+    /// their tags with non-callable values, so the interpreter checks those
+    /// separately.
+    pub const fn is_function(self) -> bool {
+        matches!(
+            self,
+            ValueTag::Closure | ValueTag::Bytecode | ValueTag::ShortFunc | ValueTag::NativeFunc
+        )
+    }
+}
+
 /// High-level JavaScript value type
 ///
 /// This is the main value type used throughout the engine.
@@ -221,15 +305,64 @@ impl Value {
         }
     }
 
+    /// Smallest integer a value holds exactly
+    ///
+    /// The encoding shifts the integer left by one tag bit. On 64-bit
+    /// targets the whole i32 range fits; on 32-bit targets only 31 bits
+    /// remain, [`SHORT_INT_MIN`]..=[`SHORT_INT_MAX`].
+    pub const INT_MIN: i32 = if usize::BITS >= 64 {
+        i32::MIN
+    } else {
+        SHORT_INT_MIN
+    };
+
+    /// Largest integer a value holds exactly (see [`Value::INT_MIN`])
+    pub const INT_MAX: i32 = if usize::BITS >= 64 {
+        i32::MAX
+    } else {
+        SHORT_INT_MAX
+    };
+
     /// Create an integer value
     ///
-    /// On 64-bit systems, the full i32 range is supported.
-    /// The encoding shifts the value left by 1 bit, which fits in a 64-bit usize.
+    /// `val` must lie in [`Value::INT_MIN`]..=[`Value::INT_MAX`], which debug
+    /// builds assert; outside it the tag bit would be overwritten. Results
+    /// that may not fit go through [`Value::try_int`] or
+    /// [`Value::int_saturating`] instead.
     #[inline]
     pub const fn int(val: i32) -> Self {
+        debug_assert!(
+            usize::BITS >= 64 || fits_in_short_int(val),
+            "integer out of value range"
+        );
         Value(RawValue::from_i32(val))
     }
 
+    /// Create an integer value, or None if `val` does not fit
+    #[inline]
+    pub const fn try_int(val: i64) -> Option<Self> {
+        if val >= Self::INT_MIN as i64 && val <= Self::INT_MAX as i64 {
+            Some(Value(RawValue::from_i32(val as i32)))
+        } else {
+            None
+        }
+    }
+
+    /// Create an integer value, clamping `val` into the representable range
+    ///
+    /// Used where saturation is the documented result, such as lengths and
+    /// the stand-ins for +/-Infinity.
+    #[inline]
+    pub const fn int_saturating(val: i64) -> Self {
+        if val < Self::INT_MIN as i64 {
+            Value::int(Self::INT_MIN)
+        } else if val > Self::INT_MAX as i64 {
+            Value::int(Self::INT_MAX)
+        } else {
+            Value::int(val as i32)
+        }
+    }
+
     /// Create an exception marker
     #[inline]
     pub const fn exception() -> Self {
@@ -270,6 +403,26 @@ impl Value {
         ))
     }
 
+    /// Create a string value holding `s` inline, if it is 1 to
+    /// [`INLINE_STRING_MAX_LEN`] bytes long
+    ///
+    /// The empty string keeps its built-in index so it has one representation.
+    #[inline]
+    pub fn inline_string(s: &str) -> Option<Self> {
+        let bytes = s.as_bytes();
+        if bytes.is_empty() || bytes.len() > INLINE_STRING_MAX_LEN {
+            return None;
+        }
+        let mut payload = INLINE_STRING_MARKER | ((bytes.len() as i32) << 24);
+        for (i, &b) in bytes.iter().enumerate() {
+            payload |= (b as i32) << (8 * i);
+        }
+        Some(Value(RawValue::make_special(
+            SpecialTag::StringChar as u8,
+            payload,
+        )))
+    }
+
     /// Create a closure value (index into interpreter's closures array)
     #[inline]
     pub const fn closure_idx(idx: u32) -> Self {
@@ -411,7 +564,10 @@ impl Value {
                     | FOR_OF_ITERATOR_INDEX_MARKER
                     | NATIVE_FUNC_MARKER
                     | BUILTIN_OBJECT_MARKER
-                    | ERROR_OBJECT_MARKER))
+                    | ERROR_OBJECT_MARKER
+                    | REGEXP_OBJECT_MARKER
+                    | TYPED_ARRAY_MARKER
+                    | ARRAY_BUFFER_MARKER))
                 == 0
     }
 
@@ -462,6 +618,62 @@ impl Value {
             && (self.0.get_special_value() & BUILTIN_OBJECT_MARKER) != 0
     }
 
+    /// The kind of value this is
+    ///
+    /// Marker bits are tested from the highest down, matching the `is_*`
+    /// checks, which exclude higher markers where they overlap.
+    #[inline]
+    pub const fn classify(self) -> ValueTag {
+        if self.is_int() {
+            return ValueTag::Int;
+        }
+        if self.is_ptr() {
+            return ValueTag::Bytecode;
+        }
+        let tag = self.0.get_special_tag();
+        if tag == SpecialTag::Bool as u8 {
+            ValueTag::Bool
+        } else if tag == SpecialTag::Null as u8 {
+            ValueTag::Null
+        } else if tag == SpecialTag::Exception as u8 {
+            ValueTag::Exception
+        } else if tag == SpecialTag::ShortFunc as u8 {
+            ValueTag::ShortFunc
+        } else if tag == SpecialTag::Uninitialized as u8 {
+            ValueTag::Uninitialized
+        } else if tag == SpecialTag::StringChar as u8 {
+            ValueTag::String
+        } else if tag == SpecialTag::CatchOffset as u8 {
+            let payload = self.0.get_special_value();
+            if payload & ARRAY_INDEX_MARKER != 0 {
+                ValueTag::Array
+            } else if payload & OBJECT_INDEX_MARKER != 0 {
+                ValueTag::Object
+            } else if payload & ITERATOR_INDEX_MARKER != 0 {
+                ValueTag::ForInIterator
+            } else if payload & FOR_OF_ITERATOR_INDEX_MARKER != 0 {
+                ValueTag::ForOfIterator
+            } else if payload & NATIVE_FUNC_MARKER != 0 {
+                ValueTag::NativeFunc
+            } else if payload & BUILTIN_OBJECT_MARKER != 0 {
+                ValueTag::Builtin
+            } else if payload & ERROR_OBJECT_MARKER != 0 {
+                ValueTag::Error
+            } else if payload & REGEXP_OBJECT_MARKER != 0 {
+                ValueTag::RegExp
+            } else if payload & TYPED_ARRAY_MARKER != 0 {
+                ValueTag::TypedArray
+            } else if payload & ARRAY_BUFFER_MARKER != 0 {
+                ValueTag::ArrayBuffer
+            } else {
+                ValueTag::Closure
+            }
+        } else {
+            // Undefined, and the short-float encoding, which is never built
+            ValueTag::Undefined
+        }
+    }
+
     // Value extraction
 
     /// Get boolean value, returns None if not a boolean
@@ -514,10 +726,33 @@ impl Value {
         }
     }
 
-    /// Get string constant index, returns None if not a string
+    /// Check if this is a string stored inline rather than in a string table
+    #[inline]
+    pub const fn is_inline_string(self) -> bool {
+        self.is_string() && (self.0.get_special_value() & INLINE_STRING_MARKER) != 0
+    }
+
+    /// Get the contents of an inline string, returns None for any other value
+    #[inline]
+    pub fn to_inline_string(self) -> Option<InlineStr> {
+        if !self.is_inline_string() {
+            return None;
+        }
+        let payload = self.0.get_special_value();
+        let mut bytes = [0u8; INLINE_STRING_MAX_LEN];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = (payload >> (8 * i)) as u8;
+        }
+        Some(InlineStr {
+            len: ((payload >> 24) & 0x3) as u8,
+            bytes,
+        })
+    }
+
+    /// Get string table index, returns None if not a string or stored inline
     #[inline]
     pub const fn to_string_idx(self) -> Option<u16> {
-        if self.is_string() {
+        if self.is_string() && !self.is_inline_string() {
             Some(self.0.get_special_value() as u16)
         } else {
             None
@@ -776,6 +1011,97 @@ impl PartialEq for Value {
 
 impl Eq for Value {}
 
+impl core::hash::Hash for Value {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+/// Contents of a string stored inline in a [`Value`]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct InlineStr {
+    len: u8,
+    bytes: [u8; INLINE_STRING_MAX_LEN],
+}
+
+impl InlineStr {
+    /// The string's text
+    pub fn as_str(&self) -> &str {
+        // Built from a &str by Value::inline_string, so always valid UTF-8
+        core::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for InlineStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// Borrowed contents of a string value, whichever way it is stored
+#[derive(Clone, Copy)]
+pub enum JsStr<'a> {
+    /// Text held in a string table
+    Table(&'a str),
+    /// Text held in the value itself
+    Inline(InlineStr),
+}
+
+impl core::ops::Deref for JsStr<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            JsStr::Table(s) => s,
+            JsStr::Inline(s) => s.as_str(),
+        }
+    }
+}
+
+impl Default for JsStr<'_> {
+    fn default() -> Self {
+        JsStr::Table("")
+    }
+}
+
+impl AsRef<str> for JsStr<'_> {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl fmt::Debug for JsStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for JsStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl PartialEq for JsStr<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for JsStr<'_> {}
+
+impl PartialEq<str> for JsStr<'_> {
+    fn eq(&self, other: &str) -> bool {
+        &**self == other
+    }
+}
+
+impl PartialEq<&str> for JsStr<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        &**self == *other
+    }
+}
+
 /// Short integer range constants
 pub const SHORT_INT_MIN: i32 = -(1 << 30);
 pub const SHORT_INT_MAX: i32 = (1 << 30) - 1;
@@ -873,6 +1199,92 @@ mod tests {
         assert_eq!(min.to_i32(), Some(SHORT_INT_MIN));
     }
 
+    #[test]
+    fn test_int_range_boundary() {
+        for n in [
+            Value::INT_MIN,
+            Value::INT_MIN + 1,
+            -1,
+            0,
+            Value::INT_MAX - 1,
+            Value::INT_MAX,
+        ] {
+            let v = Value::try_int(n as i64).unwrap();
+            assert!(v.is_int());
+            assert_eq!(v.to_i32(), Some(n));
+        }
+        assert_eq!(Value::try_int(Value::INT_MAX as i64 + 1), None);
+        assert_eq!(Value::try_int(Value::INT_MIN as i64 - 1), None);
+        assert_eq!(
+            Value::int_saturating(i64::MAX).to_i32(),
+            Some(Value::INT_MAX)
+        );
+        assert_eq!(
+            Value::int_saturating(i64::MIN).to_i32(),
+            Some(Value::INT_MIN)
+        );
+        assert_eq!(Value::int_saturating(-7).to_i32(), Some(-7));
+    }
+
+    #[test]
+    fn test_classify_agrees_with_the_type_checks() {
+        let samples = [
+            (Value::undefined(), ValueTag::Undefined),
+            (Value::null(), ValueTag::Null),
+            (Value::bool(true), ValueTag::Bool),
+            (Value::int(-3), ValueTag::Int),
+            (Value::inline_string("ab").unwrap(), ValueTag::String),
+            (Value::string(4), ValueTag::String),
+            (Value::object_idx(2), ValueTag::Object),
+            (Value::array_idx(2), ValueTag::Array),
+            (Value::closure_idx(2), ValueTag::Closure),
+            (Value::func_ptr(core::ptr::dangling()), ValueTag::Bytecode),
+            (Value::func(2), ValueTag::ShortFunc),
+            (Value::native_func(2), ValueTag::NativeFunc),
+            (Value::builtin_object(2), ValueTag::Builtin),
+            (Value::error_object(2), ValueTag::Error),
+            (Value::regexp_object(2), ValueTag::RegExp),
+            (Value::typed_array_object(2), ValueTag::TypedArray),
+            (Value::array_buffer_object(2), ValueTag::ArrayBuffer),
+            (Value::iterator_idx(2), ValueTag::ForInIterator),
+            (Value::for_of_iterator_idx(2), ValueTag::ForOfIterator),
+            (Value::exception(), ValueTag::Exception),
+            (Value::uninitialized(), ValueTag::Uninitialized),
+        ];
+        // One sample per tag, at least
+        for tag in ValueTag::ALL {
+            assert!(samples.iter().any(|&(_, t)| t == tag), "{:?}", tag);
+        }
+        for (value, tag) in samples {
+            assert_eq!(value.classify(), tag, "{:?}", value);
+            let checks = [
+                (ValueTag::Undefined, value.is_undefined()),
+                (ValueTag::Null, value.is_null()),
+                (ValueTag::Bool, value.is_bool()),
+                (ValueTag::Int, value.is_int()),
+                (ValueTag::String, value.is_string()),
+                (ValueTag::Object, value.is_object()),
+                (ValueTag::Array, value.is_array()),
+                (ValueTag::Closure, value.is_closure()),
+                (ValueTag::Bytecode, value.is_ptr()),
+                (ValueTag::ShortFunc, value.is_func()),
+                (ValueTag::NativeFunc, value.is_native_func()),
+                (ValueTag::Builtin, value.is_builtin_object()),
+                (ValueTag::Error, value.is_error_object()),
+                (ValueTag::RegExp, value.is_regexp_object()),
+                (ValueTag::TypedArray, value.is_typed_array()),
+                (ValueTag::ArrayBuffer, value.is_array_buffer()),
+                (ValueTag::ForInIterator, value.is_iterator()),
+                (ValueTag::ForOfIterator, value.is_for_of_iterator()),
+                (ValueTag::Exception, value.is_exception()),
+                (ValueTag::Uninitialized, value.is_uninitialized()),
+            ];
+            for (check, result) in checks {
+                assert_eq!(result, check == tag, "{:?} as {:?}", value, check);
+            }
+        }
+    }
+
     #[test]
     fn test_exception() {
         let v = Value::exception();
@@ -888,4 +1300,28 @@ mod tests {
         assert_eq!(format!("{:?}", RawValue::TRUE), "Bool(true)");
         assert_eq!(format!("{:?}", RawValue::from_i32(42)), "Int(42)");
     }
+
+    #[test]
+    fn test_inline_string() {
+        let v = Value::inline_string("ab").unwrap();
+        assert!(v.is_string());
+        assert!(v.is_inline_string());
+        assert_eq!(v.to_string_idx(), None);
+        assert_eq!(v.to_inline_string().unwrap().as_str(), "ab");
+        assert_eq!(Value::inline_string("ab"), Some(v));
+        assert_ne!(Value::inline_string("ba"), Some(v));
+
+        // Multi-byte characters count by their UTF-8 length
+        assert_eq!(
+            Value::inline_string("é!")
+                .unwrap()
+                .to_inline_string()
+                .unwrap()
+                .as_str(),
+            "é!"
+        );
+        assert!(Value::inline_string("abcd").is_none());
+        assert!(Value::inline_string("").is_none());
+        assert!(!Value::string(STR_EMPTY).is_inline_string());
+    }
 }
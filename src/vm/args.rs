@@ -0,0 +1,124 @@
+//! Argument validation for native functions
+//!
+//! [`Args`] wraps the arguments of a native call and converts them to
+//! Rust types, failing with one message shape for every native:
+//! `"Math.pow: argument 1 must be a number, got string"`. Natives return
+//! those messages as their `Err`, which scripts see as a TypeError.
+
+use super::interpreter::receiver_description;
+use super::native::NativeScope;
+use crate::prelude::*;
+use crate::value::Value;
+
+/// Arguments of a native call, read through typed getters
+///
+/// Indices are zero-based; messages count arguments from 1. The `opt_`
+/// getters treat a missing or undefined argument as absent and return the
+/// default, but still reject an argument of the wrong type.
+///
+/// ```
+/// use mquickjs::embed::{Args, Context, HostError, NativeScope, Value};
+///
+/// fn scale(_: &mut NativeScope, _this: Value, args: &[Value]) -> Result<Value, HostError> {
+///     let args = Args::new("scale", args);
+///     let n = args.int(0)?;
+///     let factor = args.opt_int(1, 2)?;
+///     Ok(Value::int(n * factor))
+/// }
+///
+/// let mut ctx = Context::new(64 * 1024);
+/// ctx.register_function("scale", scale, 1);
+/// assert_eq!(ctx.eval("scale(4);").unwrap().to_i32(), Some(8));
+/// let err = ctx.eval("scale('4');").unwrap_err();
+/// assert!(err.to_string().contains("scale: argument 1 must be a number, got string"));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Args<'a> {
+    name: &'a str,
+    values: &'a [Value],
+}
+
+impl<'a> Args<'a> {
+    /// Wrap the arguments of the native `name`, as messages should call it
+    pub fn new(name: &'a str, values: &'a [Value]) -> Self {
+        Args { name, values }
+    }
+
+    /// Number of arguments passed
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether no arguments were passed
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Argument `index`, or undefined if it was not passed
+    pub fn get(&self, index: usize) -> Value {
+        self.values.get(index).copied().unwrap_or_default()
+    }
+
+    /// Whether argument `index` is missing or undefined
+    pub fn is_absent(&self, index: usize) -> bool {
+        self.get(index).is_undefined()
+    }
+
+    /// Argument `index` as a number
+    pub fn int(&self, index: usize) -> Result<i32, String> {
+        self.get(index)
+            .to_i32()
+            .ok_or_else(|| self.type_error(index, "a number"))
+    }
+
+    /// Argument `index` as a number, or `default` if absent
+    pub fn opt_int(&self, index: usize, default: i32) -> Result<i32, String> {
+        if self.is_absent(index) {
+            return Ok(default);
+        }
+        self.int(index)
+    }
+
+    /// Argument `index` as a string
+    pub fn string(&self, index: usize, scope: &NativeScope) -> Result<String, String> {
+        scope
+            .get_string(self.get(index))
+            .map(|s| s.to_string())
+            .ok_or_else(|| self.type_error(index, "a string"))
+    }
+
+    /// Argument `index` as a string, or `default` if absent
+    pub fn opt_string(
+        &self,
+        index: usize,
+        scope: &NativeScope,
+        default: &str,
+    ) -> Result<String, String> {
+        if self.is_absent(index) {
+            return Ok(default.to_string());
+        }
+        self.string(index, scope)
+    }
+
+    /// Argument `index`, which must be a function
+    pub fn callable(&self, index: usize, scope: &NativeScope) -> Result<Value, String> {
+        let val = self.get(index);
+        if scope.is_callable(val) {
+            Ok(val)
+        } else {
+            Err(self.type_error(index, "a function"))
+        }
+    }
+
+    /// Message for argument `index` not being `expected`, e.g.
+    /// "Math.pow: argument 1 must be a number, got string"
+    pub fn type_error(&self, index: usize, expected: &str) -> String {
+        format!(
+            "{}: argument {} must be {}, got {}",
+            self.name,
+            index + 1,
+            expected,
+            receiver_description(self.get(index))
+        )
+    }
+}
@@ -0,0 +1,114 @@
+//! Interned property keys
+//!
+//! Every property name an object stores is interned once into the
+//! interpreter's [`AtomTable`], so objects keep small [`Atom`] ids and
+//! property lookup compares integers instead of strings. Atoms are never
+//! freed: the table only grows, by one entry per distinct key a script uses.
+
+use crate::prelude::*;
+use alloc::rc::Rc;
+use hashbrown::HashMap;
+
+/// An interned property key, valid for the interpreter that created it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Atom(u32);
+
+impl Atom {
+    /// The atom's index in its table
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// Table mapping property keys to atoms and back
+#[derive(Debug, Default)]
+pub struct AtomTable {
+    names: Vec<Rc<str>>,
+    ids: HashMap<Rc<str>, Atom>,
+}
+
+impl AtomTable {
+    /// Create an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The atom for `name`, interning it if it is new
+    ///
+    /// Returns whether the atom was newly created alongside it.
+    pub fn intern(&mut self, name: &str) -> (Atom, bool) {
+        if let Some(&atom) = self.ids.get(name) {
+            return (atom, false);
+        }
+        let atom = Atom(self.names.len() as u32);
+        let name: Rc<str> = Rc::from(name);
+        self.names.push(Rc::clone(&name));
+        self.ids.insert(name, atom);
+        (atom, true)
+    }
+
+    /// The atom for `name` if it was ever interned
+    ///
+    /// No object can own a key that was never interned, so lookups use
+    /// this and skip the insertion.
+    pub fn lookup(&self, name: &str) -> Option<Atom> {
+        self.ids.get(name).copied()
+    }
+
+    /// The key an atom stands for
+    pub fn name(&self, atom: Atom) -> &str {
+        &self.names[atom.0 as usize]
+    }
+
+    /// The key an atom stands for, shared with the table
+    pub fn shared_name(&self, atom: Atom) -> Rc<str> {
+        Rc::clone(&self.names[atom.0 as usize])
+    }
+
+    /// Number of interned keys
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether no key has been interned yet
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Estimated bytes held by the table
+    pub fn bytes(&self) -> usize {
+        self.names.iter().map(|name| atom_bytes(name)).sum()
+    }
+}
+
+/// Bytes interning `name` adds to a table
+pub(crate) fn atom_bytes(name: &str) -> usize {
+    name.len() + 2 * core::mem::size_of::<Rc<str>>() + core::mem::size_of::<Atom>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_returns_the_same_atom_for_equal_keys() {
+        let mut table = AtomTable::new();
+        let (a, new_a) = table.intern("width");
+        let (b, new_b) = table.intern(&format!("wid{}", "th"));
+        assert_eq!(a, b);
+        assert!(new_a);
+        assert!(!new_b);
+        assert_eq!(table.name(a), "width");
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_lookup_does_not_intern() {
+        let mut table = AtomTable::new();
+        assert_eq!(table.lookup("x"), None);
+        assert!(table.is_empty());
+        let (x, _) = table.intern("x");
+        assert_eq!(table.lookup("x"), Some(x));
+        assert_ne!(table.intern("y").0, x);
+    }
+}
@@ -0,0 +1,131 @@
+//! Host classes: Rust types scripts can construct with `new`
+//!
+//! A [`ClassDef`] describes how to build a value from constructor
+//! arguments and which methods and getters scripts see on instances.
+//! Instances are userdata objects, so `instanceof` and finalizers work
+//! exactly as they do for values wrapped with `create_userdata`.
+
+use crate::prelude::*;
+use alloc::rc::Rc;
+use core::any::Any;
+
+use super::interpreter::Interpreter;
+use super::native::{HostError, NativeScope};
+use crate::value::Value;
+
+/// Type-erased constructor producing the boxed host value
+pub(crate) type HostConstructor =
+    Rc<dyn Fn(&mut Interpreter, &[Value]) -> Result<Box<dyn Any>, HostError>>;
+
+/// Type-erased method receiving the boxed host value
+pub(crate) type HostMethod =
+    Rc<dyn Fn(&mut Interpreter, &mut dyn Any, &[Value]) -> Result<Value, HostError>>;
+
+/// Type-erased getter receiving the boxed host value
+pub(crate) type HostGetter = Rc<dyn Fn(&mut Interpreter, &dyn Any) -> Value>;
+
+/// Type-erased finalizer shared by every instance of a class
+pub(crate) type HostFinalizer = Rc<dyn Fn(Box<dyn Any>)>;
+
+/// Constructor of a host class of type `T`
+type Constructor<T> = Box<dyn Fn(&mut NativeScope, &[Value]) -> Result<T, HostError>>;
+
+/// Method of a host class of type `T`
+type Method<T> = Box<dyn Fn(&mut NativeScope, &mut T, &[Value]) -> Result<Value, HostError>>;
+
+/// Getter of a host class of type `T`
+type Getter<T> = Box<dyn Fn(&mut NativeScope, &T) -> Value>;
+
+/// Description of a host class registered with
+/// [`Interpreter::register_class`]
+///
+/// ```
+/// use mquickjs::embed::{ClassDef, Context, Value};
+///
+/// struct Counter(i32);
+///
+/// let mut ctx = Context::new(64 * 1024);
+/// ctx.register_class(
+///     "Counter",
+///     ClassDef::new(|_, args| Ok(Counter(args.first().and_then(|v| v.to_i32()).unwrap_or(0))))
+///         .method("increment", |_, counter, _| {
+///             counter.0 += 1;
+///             Ok(Value::int(counter.0))
+///         })
+///         .getter("value", |_, counter| Value::int(counter.0)),
+/// );
+/// let result = ctx.eval("var c = new Counter(5); c.increment(); c.value;").unwrap();
+/// assert_eq!(result.to_i32(), Some(6));
+/// ```
+pub struct ClassDef<T> {
+    pub(crate) constructor: Constructor<T>,
+    pub(crate) methods: Vec<(&'static str, Method<T>)>,
+    pub(crate) getters: Vec<(&'static str, Getter<T>)>,
+    pub(crate) finalizer: Option<Box<dyn Fn(T)>>,
+}
+
+impl<T: 'static> ClassDef<T> {
+    /// Start a class whose constructor builds the host value from the
+    /// arguments passed to `new`
+    ///
+    /// An `Err` is thrown to the script the same way native function
+    /// errors are, so its [`HostError`] variant selects the error type.
+    pub fn new(
+        constructor: impl Fn(&mut NativeScope, &[Value]) -> Result<T, HostError> + 'static,
+    ) -> Self {
+        ClassDef {
+            constructor: Box::new(constructor),
+            methods: Vec::new(),
+            getters: Vec::new(),
+            finalizer: None,
+        }
+    }
+
+    /// Add a method callable on instances
+    ///
+    /// The method may call back into the interpreter, but calling another
+    /// method of the same instance from such a callback throws a TypeError.
+    pub fn method(
+        mut self,
+        name: &'static str,
+        method: impl Fn(&mut NativeScope, &mut T, &[Value]) -> Result<Value, HostError> + 'static,
+    ) -> Self {
+        self.methods.push((name, Box::new(method)));
+        self
+    }
+
+    /// Add a read-only property computed from the host value
+    pub fn getter(
+        mut self,
+        name: &'static str,
+        getter: impl Fn(&mut NativeScope, &T) -> Value + 'static,
+    ) -> Self {
+        self.getters.push((name, Box::new(getter)));
+        self
+    }
+
+    /// Run `finalizer` with the host value once an instance is collected
+    pub fn finalizer(mut self, finalizer: impl Fn(T) + 'static) -> Self {
+        self.finalizer = Some(Box::new(finalizer));
+        self
+    }
+}
+
+/// A registered host class with its type erased
+pub(crate) struct HostClass {
+    pub(crate) name: &'static str,
+    pub(crate) constructor: HostConstructor,
+    /// Method names and the native function indices that dispatch to them
+    pub(crate) methods: Vec<(&'static str, u32)>,
+    pub(crate) getters: Vec<(&'static str, HostGetter)>,
+    pub(crate) finalizer: Option<HostFinalizer>,
+}
+
+/// What a native function index registered for a host class stands for
+#[derive(Clone)]
+pub(crate) enum HostNative {
+    /// The class value itself
+    Constructor(usize),
+    /// A method of the class
+    Method(usize, HostMethod),
+}
@@ -0,0 +1,102 @@
+//! Time source for timers, deadlines and the time-dependent builtins
+//!
+//! The interpreter reads time only through the [`Clock`] trait so embedders
+//! and tests can substitute a simulated clock for the system one. Targets
+//! without an operating system clock install a [`ManualClock`] and advance
+//! it from their own tick source.
+
+use core::cell::Cell;
+
+/// Source of wall-clock time in milliseconds
+pub trait Clock {
+    /// Current time in milliseconds
+    fn now_ms(&self) -> u64;
+
+    /// Current time in microseconds, used by `performance.now` and GC
+    /// pause timing
+    ///
+    /// Defaults to [`now_ms`](Clock::now_ms) scaled up; override it when a
+    /// finer tick is available.
+    fn now_us(&self) -> u64 {
+        self.now_ms().saturating_mul(1000)
+    }
+
+    /// Block until `ms` milliseconds have passed
+    fn sleep_ms(&self, ms: u64);
+}
+
+/// Clock backed by the system time
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl SystemClock {
+    fn since_epoch() -> core::time::Duration {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        Self::since_epoch().as_millis() as u64
+    }
+
+    fn now_us(&self) -> u64 {
+        Self::since_epoch().as_micros() as u64
+    }
+
+    fn sleep_ms(&self, ms: u64) {
+        std::thread::sleep(core::time::Duration::from_millis(ms));
+    }
+}
+
+/// Clock that only moves when the host advances it
+///
+/// Sleeping advances the clock instead of blocking, so timers waited on by
+/// [`Context::eval_with_timeout`](crate::Context::eval_with_timeout) still
+/// fire in order.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now_us: Cell<u64>,
+}
+
+impl ManualClock {
+    /// Create a clock reading `start_ms`
+    pub fn new(start_ms: u64) -> Self {
+        ManualClock {
+            now_us: Cell::new(start_ms.saturating_mul(1000)),
+        }
+    }
+
+    /// Move the clock forward by `us` microseconds
+    pub fn advance_us(&self, us: u64) {
+        self.now_us.set(self.now_us.get().saturating_add(us));
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&self) -> u64 {
+        self.now_us.get() / 1000
+    }
+
+    fn now_us(&self) -> u64 {
+        self.now_us.get()
+    }
+
+    fn sleep_ms(&self, ms: u64) {
+        self.advance_us(ms.saturating_mul(1000));
+    }
+}
+
+/// The clock a new interpreter starts with: the system clock when the
+/// `std` feature is enabled, otherwise a [`ManualClock`] stopped at zero
+pub(crate) fn default_clock() -> alloc::rc::Rc<dyn Clock> {
+    #[cfg(feature = "std")]
+    return alloc::rc::Rc::new(SystemClock);
+    #[cfg(not(feature = "std"))]
+    return alloc::rc::Rc::new(ManualClock::default());
+}
@@ -0,0 +1,207 @@
+//! Structural diff of script values, for hosts syncing state
+//!
+//! [`diff`] compares two values the way a sync protocol wants: objects by
+//! their enumerable own properties, arrays element by element, typed arrays
+//! by content, and everything else with SameValueZero. It only reads values
+//! through the public accessors and builds its result with the `alloc_*`
+//! builders, so it works as any host native would.
+
+use crate::prelude::*;
+use hashbrown::HashMap;
+
+use super::interpreter::{Interpreter, PropertyLookup};
+use super::module::ModuleBuilder;
+use super::native::HostError;
+use crate::value::Value;
+
+/// Deepest nesting [`diff`] descends into before giving up
+pub const MAX_DIFF_DEPTH: usize = 64;
+
+/// The `__engine` module of host utilities, currently just `diff`
+///
+/// Registered by
+/// [`ContextBuilder::enable_engine_module`](crate::ContextBuilder::enable_engine_module);
+/// hosts building contexts another way can pass it to
+/// [`Context::register_module`](crate::Context::register_module).
+pub fn engine_module() -> ModuleBuilder {
+    ModuleBuilder::new("__engine").builtin("diff", diff, 2)
+}
+
+/// `__engine.diff(a, b)`: the changes that turn `a` into `b`
+///
+/// Returns an array of `{ path, op, value }` records in the order the
+/// properties of `a` and then `b` are enumerated. `path` names the changed
+/// location like `"a.b[2]"` (empty for the values themselves), `op` is
+/// `"add"`, `"remove"` or `"change"`, and `value` is the added or new value,
+/// or the removed one. Identical values and values already being compared
+/// further up, as in cycles, are not descended into. Structures nested
+/// deeper than [`MAX_DIFF_DEPTH`] throw a RangeError.
+pub fn diff(interp: &mut Interpreter, _this: Value, args: &[Value]) -> Result<Value, HostError> {
+    let a = args.first().copied().unwrap_or_default();
+    let b = args.get(1).copied().unwrap_or_default();
+    let mut walk = DiffWalk {
+        interp,
+        path: String::new(),
+        comparing: Vec::new(),
+        changes: Vec::new(),
+    };
+    walk.compare(a, b)?;
+    let DiffWalk {
+        interp, changes, ..
+    } = walk;
+    let records = changes
+        .into_iter()
+        .map(|(path, op, value)| {
+            interp
+                .alloc_object()
+                .prop_str("path", &path)
+                .prop_str("op", op)
+                .prop("value", value)
+                .finish()
+        })
+        .collect();
+    Ok(interp.alloc_array(records))
+}
+
+/// State of one [`diff`] call
+struct DiffWalk<'a> {
+    interp: &'a mut Interpreter,
+    /// Path of the values being compared
+    path: String,
+    /// Pairs of containers being compared, outermost first
+    comparing: Vec<(Value, Value)>,
+    /// Changes found so far, as path, operation and value
+    changes: Vec<(String, &'static str, Value)>,
+}
+
+/// How [`diff`] compares a value
+enum Shape {
+    Object(Vec<(String, Value)>),
+    Array(Vec<Value>),
+    TypedArray(Vec<Value>),
+    Leaf,
+}
+
+impl DiffWalk<'_> {
+    /// Record the changes between `a` and `b` at the current path
+    fn compare(&mut self, a: Value, b: Value) -> Result<(), HostError> {
+        if a == b || self.comparing.contains(&(a, b)) {
+            return Ok(());
+        }
+        match (self.shape(a), self.shape(b)) {
+            (Shape::Object(old), Shape::Object(new)) => self.nested(a, b, |walk| {
+                let new_values: HashMap<&str, Value> =
+                    new.iter().map(|(key, val)| (key.as_str(), *val)).collect();
+                for (key, old_val) in &old {
+                    let len = walk.push_key(key);
+                    match new_values.get(key.as_str()) {
+                        Some(&new_val) => walk.compare(*old_val, new_val)?,
+                        None => walk.record("remove", *old_val),
+                    }
+                    walk.path.truncate(len);
+                }
+                for (key, new_val) in &new {
+                    if !old.iter().any(|(old_key, _)| old_key == key) {
+                        let len = walk.push_key(key);
+                        walk.record("add", *new_val);
+                        walk.path.truncate(len);
+                    }
+                }
+                Ok(())
+            }),
+            (Shape::Array(old), Shape::Array(new)) => self.nested(a, b, |walk| {
+                for index in 0..old.len().max(new.len()) {
+                    let len = walk.path.len();
+                    walk.path.push_str(&format!("[{}]", index));
+                    match (old.get(index), new.get(index)) {
+                        (Some(&old_val), Some(&new_val)) => walk.compare(old_val, new_val)?,
+                        (Some(&old_val), None) => walk.record("remove", old_val),
+                        (None, Some(&new_val)) => walk.record("add", new_val),
+                        (None, None) => {}
+                    }
+                    walk.path.truncate(len);
+                }
+                Ok(())
+            }),
+            (Shape::TypedArray(old), Shape::TypedArray(new)) => {
+                if old != new {
+                    self.record("change", b);
+                }
+                Ok(())
+            }
+            (Shape::Leaf, Shape::Leaf) if self.interp.same_value_zero(a, b) => Ok(()),
+            _ => {
+                self.record("change", b);
+                Ok(())
+            }
+        }
+    }
+
+    /// Compare the contents of containers `a` and `b` with `body`
+    fn nested(
+        &mut self,
+        a: Value,
+        b: Value,
+        body: impl FnOnce(&mut Self) -> Result<(), HostError>,
+    ) -> Result<(), HostError> {
+        if self.comparing.len() >= MAX_DIFF_DEPTH {
+            return Err(HostError::Range(format!(
+                "__engine.diff: values nested deeper than {} levels",
+                MAX_DIFF_DEPTH
+            )));
+        }
+        self.comparing.push((a, b));
+        let result = body(self);
+        self.comparing.pop();
+        result
+    }
+
+    /// What `value` is compared as, with the contents to compare
+    fn shape(&self, value: Value) -> Shape {
+        let interp = &*self.interp;
+        if let Some(len) = interp.array_len(value) {
+            let elements = (0..len)
+                .filter_map(|i| interp.array_element_at(value, i))
+                .collect();
+            return Shape::Array(elements);
+        }
+        if let Some(len) = interp.typed_array_len(value) {
+            let elements = (0..len)
+                .filter_map(|i| interp.typed_array_element_at(value, i))
+                .collect();
+            return Shape::TypedArray(elements);
+        }
+        if let Some(count) = interp.object_property_count(value) {
+            let entries = (0..count)
+                .filter_map(|i| interp.object_property_at(value, i))
+                .filter(|(key, _)| interp.has_property(value, key, PropertyLookup::OwnEnumerable))
+                .map(|(key, val)| (key.to_string(), val))
+                .collect();
+            return Shape::Object(entries);
+        }
+        Shape::Leaf
+    }
+
+    /// Extend the path with property `key`, returning its old length
+    fn push_key(&mut self, key: &str) -> usize {
+        let len = self.path.len();
+        let mut chars = key.chars();
+        let identifier = chars
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+        if !identifier {
+            self.path.push_str(&format!("[{:?}]", key));
+        } else if self.path.is_empty() {
+            self.path.push_str(key);
+        } else {
+            self.path.push('.');
+            self.path.push_str(key);
+        }
+        len
+    }
+
+    fn record(&mut self, op: &'static str, value: Value) {
+        self.changes.push((self.path.clone(), op, value));
+    }
+}
@@ -2,9 +2,33 @@
 //!
 //! Executes JavaScript bytecode using a stack-based virtual machine.
 
+use crate::prelude::*;
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::rc::Rc;
+use core::any::Any;
+use hashbrown::{HashMap, HashSet};
+
+use crate::builtins::date;
+use crate::builtins::regexp::{self, Regex, RegexFlags};
+use crate::parser::compiler::{CompileBudget, CompileError, Compiler, DEFAULT_MAX_NESTING_DEPTH};
+use crate::parser::lexer::SourcePos;
 use crate::runtime::FunctionBytecode;
-use crate::value::Value;
-use crate::vm::opcode::OpCode;
+use crate::util::dtoa::IntStr;
+use crate::util::unicode;
+use crate::value::{JsStr, Value, ValueTag};
+use crate::vm::args::Args;
+use crate::vm::atom::{Atom, AtomTable, atom_bytes};
+use crate::vm::class::{ClassDef, HostClass, HostConstructor, HostNative};
+use crate::vm::clock::{Clock, default_clock};
+use crate::vm::module::{ModuleBuilder, PendingModule};
+use crate::vm::native::{HostError, HostFn, NativeImpl, NativeScope};
+use crate::vm::opcode::{self, OpCode};
+use crate::vm::promise::{
+    JobQueue, PromiseJob, PromiseReaction, PromiseState, ResolverHandle, SettlementQueue,
+};
+use crate::vm::replay::{self, LoggedValue, ReplayEntry, ReplayLog, ReplayMode};
+use crate::vm::roots::{PersistentValue, SharedRoots};
 use crate::vm::stack::Stack;
 
 // Builtin object indices
@@ -68,23 +92,232 @@ pub const BUILTIN_UINT8_CLAMPED_ARRAY: u32 = 24;
 pub const BUILTIN_FLOAT32_ARRAY: u32 = 25;
 /// Float64Array constructor index
 pub const BUILTIN_FLOAT64_ARRAY: u32 = 26;
+/// Array.prototype object index
+pub const BUILTIN_ARRAY_PROTOTYPE: u32 = 30;
+/// String.prototype object index
+pub const BUILTIN_STRING_PROTOTYPE: u32 = 31;
+/// Object.prototype object index
+pub const BUILTIN_OBJECT_PROTOTYPE: u32 = 32;
+/// Reflect object index
+pub const BUILTIN_REFLECT: u32 = 33;
+/// Error.prototype object index
+pub const BUILTIN_ERROR_PROTOTYPE: u32 = 34;
+
+/// Message of the RangeError thrown when the memory budget is exhausted
+const OUT_OF_MEMORY: &str = "out of memory";
+
+/// Message of the RangeError thrown when every runtime string slot is taken
+///
+/// It and the `RangeError` name are interned up front, so a handler can
+/// read the error's name and message without another slot.
+const STRING_TABLE_FULL: &str = "string table full";
+
+/// Message of the RangeError for arrays longer than the array length limit
+const INVALID_ARRAY_LENGTH: &str = "Invalid array length";
+
+/// Message of the RangeError for strings longer than the string length limit
+const INVALID_STRING_LENGTH: &str = "Invalid string length";
+
+/// Most causes an error description follows before giving up
+const MAX_CAUSE_DEPTH: usize = 8;
+
+/// Most prototypes a lookup or `instanceof` follows before giving up
+const MAX_PROTOTYPE_DEPTH: usize = 32;
+
+/// The error type an error constructor builtin creates
+fn error_constructor_name(builtin_idx: u32) -> Option<&'static str> {
+    match builtin_idx {
+        BUILTIN_ERROR => Some("Error"),
+        BUILTIN_TYPE_ERROR => Some("TypeError"),
+        BUILTIN_REFERENCE_ERROR => Some("ReferenceError"),
+        BUILTIN_SYNTAX_ERROR => Some("SyntaxError"),
+        BUILTIN_RANGE_ERROR => Some("RangeError"),
+        BUILTIN_EVAL_ERROR => Some("EvalError"),
+        BUILTIN_URI_ERROR => Some("URIError"),
+        BUILTIN_INTERNAL_ERROR => Some("InternalError"),
+        _ => None,
+    }
+}
+
+/// The element type a typed array constructor builtin creates
+fn typed_array_kind(builtin_idx: u32) -> Option<TypedArrayKind> {
+    match builtin_idx {
+        BUILTIN_INT8_ARRAY => Some(TypedArrayKind::Int8),
+        BUILTIN_UINT8_ARRAY => Some(TypedArrayKind::Uint8),
+        BUILTIN_UINT8_CLAMPED_ARRAY => Some(TypedArrayKind::Uint8Clamped),
+        BUILTIN_INT16_ARRAY => Some(TypedArrayKind::Int16),
+        BUILTIN_UINT16_ARRAY => Some(TypedArrayKind::Uint16),
+        BUILTIN_INT32_ARRAY => Some(TypedArrayKind::Int32),
+        BUILTIN_UINT32_ARRAY => Some(TypedArrayKind::Uint32),
+        BUILTIN_FLOAT32_ARRAY => Some(TypedArrayKind::Float32),
+        BUILTIN_FLOAT64_ARRAY => Some(TypedArrayKind::Float64),
+        _ => None,
+    }
+}
+
+/// Index of the next entry in a side table of `len` entries
+///
+/// Values carry side-table indices as u32; a table that outgrows that is
+/// reported instead of silently wrapping onto existing entries.
+fn table_index(len: usize) -> InterpreterResult<u32> {
+    u32::try_from(len)
+        .map_err(|_| InterpreterError::InternalError("too many objects allocated".to_string()))
+}
+
+/// Bytes of bytecode, constants and string literals held by a script and
+/// its inner functions
+fn script_bytes(bytecode: &FunctionBytecode) -> usize {
+    let metrics = bytecode.metrics();
+    metrics.bytecode_bytes
+        + metrics.constants * core::mem::size_of::<Value>()
+        + metrics.string_bytes
+}
+
+/// Bytes a recorded performance entry holds
+fn performance_entry_bytes(entry: &PerformanceEntry) -> usize {
+    core::mem::size_of::<PerformanceEntry>() + entry.name.capacity()
+}
+
+/// Bytes held by an object's property list and its keys
+fn object_bytes(obj: &ObjectInstance) -> usize {
+    obj.properties.capacity() * PROPERTY_BYTES
+}
+
+/// Bytes a new property adds to an object's property list; its key is
+/// charged once, when first interned
+const PROPERTY_BYTES: usize = core::mem::size_of::<(Atom, Value)>();
+
+/// Whether a builtin object index names a constructor (typeof "function")
+/// rather than a namespace or prototype object
+fn is_builtin_constructor(idx: u32) -> bool {
+    !matches!(
+        idx,
+        BUILTIN_MATH
+            | BUILTIN_JSON
+            | BUILTIN_CONSOLE
+            | BUILTIN_PERFORMANCE
+            | BUILTIN_GLOBAL_THIS
+            | BUILTIN_ARRAY_PROTOTYPE
+            | BUILTIN_STRING_PROTOTYPE
+            | BUILTIN_OBJECT_PROTOTYPE
+            | BUILTIN_ERROR_PROTOTYPE
+            | BUILTIN_REFLECT
+    )
+}
+
+/// Builder for objects allocated by native functions
+///
+/// Created by [`Interpreter::alloc_object`]; properties keep insertion order.
+pub struct ObjectBuilder<'a> {
+    interp: &'a mut Interpreter,
+    object: ObjectInstance,
+}
+
+impl ObjectBuilder<'_> {
+    /// Add a property, replacing an earlier one with the same key
+    pub fn prop(mut self, key: &str, value: Value) -> Self {
+        let key = self.interp.atom(key);
+        self.object.set(key, value);
+        self
+    }
+
+    /// Add a string property
+    pub fn prop_str(self, key: &str, value: &str) -> Self {
+        let value = self.interp.alloc_string(value);
+        self.prop(key, value)
+    }
 
-/// Native function signature
+    /// Set the constructor reported by `instanceof`
+    pub fn constructor(mut self, constructor: Value) -> Self {
+        self.object.constructor = Some(constructor);
+        self
+    }
+
+    /// Store the object and return its value
+    pub fn finish(self) -> Value {
+        self.interp.push_object(self.object)
+    }
+}
+
+/// Signature of an engine builtin
 ///
-/// Native functions take an interpreter reference, this value, and arguments.
-/// Returns a Result with the value or an error message.
+/// Builtins take the whole interpreter; host functions registered through
+/// a `Context` are [`HostFn`]s and get a [`NativeScope`] instead. Both
+/// follow the same rules for errors, panics and receivers (see [`HostFn`]).
 pub type NativeFn =
-    fn(interp: &mut Interpreter, this: Value, args: &[Value]) -> Result<Value, String>;
+    fn(interp: &mut Interpreter, this: Value, args: &[Value]) -> Result<Value, HostError>;
 
 /// Native function entry in the registry
 #[derive(Clone)]
 pub struct NativeFunction {
     /// The name of the function
-    pub name: &'static str,
+    pub name: Rc<str>,
     /// The native function implementation
-    pub func: NativeFn,
-    /// Number of expected arguments (for arity checking, 0 = variadic)
+    pub(crate) func: NativeImpl,
+    /// Number of declared parameters, reported as the function's `length`
+    ///
+    /// Informational only: calls passing fewer or more arguments are never
+    /// rejected, and missing arguments read as undefined. Natives check
+    /// what they require themselves, with [`Args`].
     pub arity: u8,
+    /// What the function expects as `this`
+    pub receiver: Receiver,
+}
+
+/// What a native function expects as its `this`, enforced before it runs
+/// (see [`HostFn`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Receiver {
+    /// Ignores `this`, like `parseInt` or `Math.abs`; the native is passed
+    /// undefined whether it is called bare or as a method
+    None,
+    /// Must be called on a receiver of the given kind, like
+    /// `String.prototype.indexOf`; other receivers throw a TypeError naming
+    /// the function and the kind
+    Required(ReceiverKind),
+    /// Gets whatever it was called on, undefined when called bare, and
+    /// handles every receiver itself
+    Optional,
+}
+
+/// Kind of receiver a [`Receiver::Required`] native accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiverKind {
+    /// Anything but null and undefined, like the String methods accept
+    Coercible,
+    /// An object, array or function
+    Object,
+    /// An array
+    Array,
+    /// A number
+    Number,
+    /// Something callable
+    Function,
+    /// A Date
+    Date,
+    /// A Promise
+    Promise,
+    /// A RegExp
+    RegExp,
+    /// A typed array
+    TypedArray,
+}
+
+impl ReceiverKind {
+    /// The kind as error messages name it, e.g. "an array"
+    pub fn description(self) -> &'static str {
+        match self {
+            ReceiverKind::Coercible => "a value other than null or undefined",
+            ReceiverKind::Object => "an object",
+            ReceiverKind::Array => "an array",
+            ReceiverKind::Number => "a number",
+            ReceiverKind::Function => "a function",
+            ReceiverKind::Date => "a Date",
+            ReceiverKind::Promise => "a Promise",
+            ReceiverKind::RegExp => "a RegExp",
+            ReceiverKind::TypedArray => "a typed array",
+        }
+    }
 }
 
 /// Object instance storing properties and constructor reference
@@ -92,8 +325,13 @@ pub struct NativeFunction {
 pub struct ObjectInstance {
     /// Constructor that created this object (closure index), if any
     pub constructor: Option<Value>,
-    /// Object properties as key-value pairs
-    pub properties: Vec<(String, Value)>,
+    /// Prototype it was given by `Object.create`, if any; otherwise it
+    /// inherits from its constructor's `prototype`
+    pub proto: Option<Value>,
+    /// Object properties as key-value pairs, keyed by interned name
+    pub properties: Vec<(Atom, Value)>,
+    /// Own properties skipped by enumeration (defined with `enumerable: false`)
+    pub non_enumerable: Vec<Atom>,
 }
 
 impl Default for ObjectInstance {
@@ -107,7 +345,9 @@ impl ObjectInstance {
     pub fn new() -> Self {
         ObjectInstance {
             constructor: None,
+            proto: None,
             properties: Vec::new(),
+            non_enumerable: Vec::new(),
         }
     }
 
@@ -115,9 +355,160 @@ impl ObjectInstance {
     pub fn with_constructor(constructor: Value) -> Self {
         ObjectInstance {
             constructor: Some(constructor),
+            proto: None,
             properties: Vec::new(),
+            non_enumerable: Vec::new(),
+        }
+    }
+
+    /// The value of the own property `key`
+    pub fn get(&self, key: Atom) -> Option<Value> {
+        self.properties
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    }
+
+    /// Set the own property `key`, returning whether it was newly added
+    pub fn set(&mut self, key: Atom, value: Value) -> bool {
+        match self.properties.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => {
+                entry.1 = value;
+                false
+            }
+            None => {
+                self.properties.push((key, value));
+                true
+            }
+        }
+    }
+
+    /// Whether `key` is an own property
+    pub fn has_own(&self, key: Atom) -> bool {
+        self.properties.iter().any(|(k, _)| *k == key)
+    }
+
+    /// Whether `key` is an own property visited by enumeration
+    pub fn is_enumerable(&self, key: Atom) -> bool {
+        self.has_own(key) && !self.non_enumerable.contains(&key)
+    }
+
+    /// Own property keys in insertion order, optionally only enumerable ones
+    pub fn own_keys(&self, enumerable_only: bool) -> impl Iterator<Item = Atom> + '_ {
+        self.properties
+            .iter()
+            .map(|(k, _)| *k)
+            .filter(move |k| !enumerable_only || !self.non_enumerable.contains(k))
+    }
+
+    /// Remove an own property, returning whether it existed
+    pub fn remove(&mut self, key: Atom) -> bool {
+        let orig_len = self.properties.len();
+        self.properties.retain(|(k, _)| *k != key);
+        self.non_enumerable.retain(|k| *k != key);
+        self.properties.len() < orig_len
+    }
+}
+
+/// Attributes of an own data property, as a property descriptor reports them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PropertyAttributes {
+    writable: bool,
+    enumerable: bool,
+    configurable: bool,
+}
+
+/// Which properties a lookup takes into account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyLookup {
+    /// Own properties, enumerable or not (`hasOwnProperty`, `getOwnPropertyNames`)
+    Own,
+    /// Own enumerable properties (`Object.keys`)
+    OwnEnumerable,
+    /// Own properties and those inherited from prototypes (`in`, for-in)
+    Chain,
+}
+
+/// The kind of value a property operation is applied to
+///
+/// Property opcodes and the `Object` statics classify their receiver once
+/// and dispatch on the kind, so get, set, has, delete and key enumeration
+/// agree for every kind whichever operation asks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PropertyReceiver {
+    Object(u32),
+    Array(u32),
+    Error(u32),
+    RegExp(u32),
+    TypedArray(u32),
+    ArrayBuffer(u32),
+    Builtin(u32),
+    String,
+    Number,
+    Function,
+    /// undefined, null and booleans
+    Primitive,
+}
+
+impl PropertyReceiver {
+    fn of(value: Value) -> Self {
+        // The tag guarantees the index is there
+        let index = |idx: Option<u32>| idx.unwrap_or_default();
+        match value.classify() {
+            ValueTag::Object => PropertyReceiver::Object(index(value.to_object_idx())),
+            ValueTag::Array => PropertyReceiver::Array(index(value.to_array_idx())),
+            ValueTag::Error => PropertyReceiver::Error(index(value.to_error_object_idx())),
+            ValueTag::RegExp => PropertyReceiver::RegExp(index(value.to_regexp_object_idx())),
+            ValueTag::TypedArray => PropertyReceiver::TypedArray(index(value.to_typed_array_idx())),
+            ValueTag::ArrayBuffer => {
+                PropertyReceiver::ArrayBuffer(index(value.to_array_buffer_idx()))
+            }
+            ValueTag::Builtin => PropertyReceiver::Builtin(index(value.to_builtin_object_idx())),
+            ValueTag::String => PropertyReceiver::String,
+            ValueTag::Int => PropertyReceiver::Number,
+            ValueTag::Closure | ValueTag::Bytecode | ValueTag::ShortFunc | ValueTag::NativeFunc => {
+                PropertyReceiver::Function
+            }
+            ValueTag::Undefined
+            | ValueTag::Null
+            | ValueTag::Bool
+            | ValueTag::ForInIterator
+            | ValueTag::ForOfIterator
+            | ValueTag::Exception
+            | ValueTag::Uninitialized => PropertyReceiver::Primitive,
         }
     }
+
+    /// Whether the receiver is an object rather than a primitive value
+    fn is_object(self) -> bool {
+        !matches!(
+            self,
+            PropertyReceiver::String | PropertyReceiver::Number | PropertyReceiver::Primitive
+        )
+    }
+}
+
+/// The array index named by `key`, if it is one in canonical form
+fn array_index(key: &str) -> Option<usize> {
+    key.parse::<u32>()
+        .ok()
+        .filter(|i| i.to_string() == key)
+        .map(|i| i as usize)
+}
+
+/// Whether `key` is the canonical string form of a number, such as "-1" or
+/// "1.5" but not "01"
+///
+/// Typed arrays treat every such key as an element index, so ones that are
+/// not valid indices read as undefined and ignore writes instead of
+/// becoming named properties.
+fn numeric_key(key: &str) -> bool {
+    match key {
+        "-0" | "NaN" | "Infinity" | "-Infinity" => true,
+        _ => key
+            .parse::<f64>()
+            .is_ok_and(|n| n.is_finite() && n.to_string() == key),
+    }
 }
 
 /// For-in iterator state
@@ -131,8 +522,11 @@ pub struct ForInIterator {
 
 impl ForInIterator {
     /// Create a new for-in iterator from an object
-    pub fn from_object(obj: &ObjectInstance) -> Self {
-        let keys = obj.properties.iter().map(|(k, _)| k.clone()).collect();
+    pub fn from_object(obj: &ObjectInstance, atoms: &AtomTable) -> Self {
+        let keys = obj
+            .own_keys(true)
+            .map(|k| atoms.name(k).to_string())
+            .collect();
         ForInIterator { keys, index: 0 }
     }
 
@@ -238,7 +632,8 @@ pub struct CallFrame {
     pub pc: usize,
     /// Frame pointer (index into stack where locals start)
     pub frame_ptr: usize,
-    /// Number of arguments
+    /// Number of arguments the caller passed, which may exceed the declared
+    /// parameters (see [`CallFrame::extra_arg_count`])
     pub arg_count: u16,
     /// Return address (pc to return to, or usize::MAX for top-level)
     pub return_pc: usize,
@@ -254,6 +649,39 @@ pub struct CallFrame {
     pub is_constructor: bool,
 }
 
+/// How a call is made, for [`Interpreter::push_call_frame`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CallFlags {
+    /// Called with `new`
+    construct: bool,
+}
+
+impl CallFlags {
+    /// An ordinary call
+    const CALL: CallFlags = CallFlags { construct: false };
+    /// A `new` expression
+    const CONSTRUCT: CallFlags = CallFlags { construct: true };
+}
+
+/// Which conversion ToPrimitive prefers for an object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PrimitiveHint {
+    /// `+` and `==`: as Number, except that dates convert as String
+    Default,
+    /// Arithmetic and relational comparison: `valueOf` before `toString`
+    Number,
+    /// ToString: `toString` before `valueOf`
+    String,
+}
+
+/// What starting a call did
+enum CallOutcome {
+    /// A bytecode frame was pushed for the run loop to execute
+    Pushed,
+    /// The callee ran to completion with this result
+    Returned(Value),
+}
+
 impl CallFrame {
     /// Create a new call frame
     pub fn new(
@@ -344,6 +772,50 @@ impl CallFrame {
             is_constructor: true,
         }
     }
+
+    /// Consume the operand of the instruction being executed
+    ///
+    /// Fetch leaves pc just past the opcode byte; this decodes the operand
+    /// per `op`'s format and moves pc to the next instruction.
+    #[inline(always)]
+    pub fn take_operand(&mut self, op: OpCode) -> i32 {
+        // Safety: bytecode pointer is valid for frame lifetime
+        let bytecode = unsafe { &*self.bytecode };
+        let (operand, next_pc) = opcode::read_operand_at(&bytecode.bytecode, self.pc, op);
+        self.pc = next_pc;
+        operand
+    }
+
+    /// Number of passed arguments beyond the declared parameters
+    ///
+    /// These sit on the stack just below `frame_ptr`, so parameters and
+    /// locals keep their `frame_ptr + index` addressing.
+    #[inline]
+    pub fn extra_arg_count(&self) -> usize {
+        // Safety: bytecode pointer is valid for frame lifetime
+        let declared = unsafe { (*self.bytecode).arg_count } as usize;
+        (self.arg_count as usize).saturating_sub(declared)
+    }
+
+    /// Stack index of the first value pushed for this frame
+    #[inline]
+    pub fn base(&self) -> usize {
+        self.frame_ptr - self.extra_arg_count()
+    }
+
+    /// Stack index holding passed argument `index`, or None if the caller
+    /// passed fewer arguments
+    pub fn arg_slot(&self, index: usize) -> Option<usize> {
+        if index >= self.arg_count as usize {
+            return None;
+        }
+        let declared = self.arg_count as usize - self.extra_arg_count();
+        if index < declared {
+            Some(self.frame_ptr + index)
+        } else {
+            Some(self.base() + index - declared)
+        }
+    }
 }
 
 /// Interpreter error
@@ -361,25 +833,61 @@ pub enum InterpreterError {
     TypeError(String),
     /// Reference error
     ReferenceError(String),
+    /// Range error
+    RangeError(String),
+    /// Syntax error found at run time, such as an invalid RegExp pattern
+    SyntaxError(String),
     /// Internal error
     InternalError(String),
+    /// Execution passed its deadline
+    Interrupted,
+    /// A value thrown by script code and not caught by the handlers of
+    /// the interpreter loop it was thrown in
+    Exception(Value),
+}
+
+/// Message of [`InterpreterError::Interrupted`], recognised again when the
+/// error passes through a native function
+pub(crate) const INTERRUPTED_MESSAGE: &str = "interrupted: deadline exceeded";
+
+/// Message of [`InterpreterError::Exception`]; a native returning it after
+/// a failed [`Interpreter::call_value`] rethrows the original value
+pub(crate) const EXCEPTION_MESSAGE: &str = "exception thrown by callback";
+
+impl InterpreterError {
+    /// Name and message of the error object a script sees for this error,
+    /// or None for failures scripts cannot catch
+    pub fn js_error_parts(&self) -> Option<(&'static str, &str)> {
+        match self {
+            Self::DivisionByZero => Some(("RangeError", "division by zero")),
+            Self::TypeError(msg) => Some(("TypeError", msg)),
+            Self::RangeError(msg) => Some(("RangeError", msg)),
+            Self::ReferenceError(msg) => Some(("ReferenceError", msg)),
+            Self::SyntaxError(msg) => Some(("SyntaxError", msg)),
+            _ => None,
+        }
+    }
 }
 
-impl std::fmt::Display for InterpreterError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::StackUnderflow => write!(f, "stack underflow"),
             Self::StackOverflow => write!(f, "stack overflow"),
             Self::InvalidOpcode(op) => write!(f, "invalid opcode: {}", op),
-            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::DivisionByZero => write!(f, "RangeError: division by zero"),
             Self::TypeError(msg) => write!(f, "TypeError: {}", msg),
             Self::ReferenceError(msg) => write!(f, "ReferenceError: {}", msg),
+            Self::RangeError(msg) => write!(f, "RangeError: {}", msg),
+            Self::SyntaxError(msg) => write!(f, "SyntaxError: {}", msg),
             Self::InternalError(msg) => write!(f, "InternalError: {}", msg),
+            Self::Interrupted => write!(f, "{}", INTERRUPTED_MESSAGE),
+            Self::Exception(_) => write!(f, "{}", EXCEPTION_MESSAGE),
         }
     }
 }
 
-impl std::error::Error for InterpreterError {}
+impl core::error::Error for InterpreterError {}
 
 /// Result type for interpreter operations
 pub type InterpreterResult<T> = Result<T, InterpreterError>;
@@ -395,6 +903,20 @@ pub struct ExceptionHandler {
     pub stack_depth: usize,
 }
 
+/// Console stream a line of output is written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleLevel {
+    /// console.log and the print statement
+    Log,
+    /// console.error
+    Error,
+    /// console.warn
+    Warn,
+}
+
+/// Callback receiving console output instead of stdout/stderr
+pub type ConsoleSink = Box<dyn FnMut(ConsoleLevel, &str)>;
+
 /// Interpreter state
 pub struct Interpreter {
     /// Value stack
@@ -403,6 +925,8 @@ pub struct Interpreter {
     call_stack: Vec<CallFrame>,
     /// Maximum call recursion depth
     max_recursion: usize,
+    /// Deepest call stack since creation or the last `reset_watermarks`
+    call_depth_high_water: usize,
     /// Runtime strings (created during execution, e.g., from concatenation)
     /// Indices start from 0x8000 to distinguish from compile-time strings
     runtime_strings: Vec<String>,
@@ -417,12 +941,36 @@ pub struct Interpreter {
     /// Objects created during execution
     /// Values on the stack can reference objects by index
     objects: Vec<ObjectInstance>,
+    /// Named (non-index) properties stored on arrays, keyed by array index
+    /// Allocated on the first named write so plain arrays pay nothing
+    array_props: HashMap<u32, ObjectInstance>,
+    /// Deleted elements of arrays, keyed by array index: they read as
+    /// undefined but are not own properties, so `in` and for-in skip them
+    array_holes: HashMap<u32, BTreeSet<usize>>,
+    /// Arrays stored sparsely, keyed by array index (see
+    /// [`Interpreter::SPARSE_ARRAY_GAP`])
+    sparse_arrays: HashMap<u32, SparseArray>,
+    /// Named properties added to errors, regexps, typed arrays and array
+    /// buffers, which have no property storage of their own
+    exotic_props: HashMap<PropertyReceiver, ObjectInstance>,
+    /// Objects assigned to the `prototype` of script functions, keyed by
+    /// the function
+    function_prototypes: HashMap<Value, Value>,
+    /// Time values of Date objects, keyed by object index (None = Invalid Date)
+    date_values: HashMap<u32, Option<i64>>,
+    /// States of promise objects, keyed by object index
+    promises: HashMap<u32, PromiseState>,
+    /// Promise reactions ready to run, oldest first
+    promise_jobs: JobQueue,
+    /// Outcomes reported by resolver handles, applied when jobs next run
+    settlements: SettlementQueue,
     /// For-in iterators created during execution
     for_in_iterators: Vec<ForInIterator>,
     /// For-of iterators created during execution
     for_of_iterators: Vec<ForOfIterator>,
-    /// Native function registry
-    native_functions: Vec<NativeFunction>,
+    /// Native function registry; `None` marks a slot freed by
+    /// [`Interpreter::unregister_native`], whose index is never reused
+    native_functions: Vec<Option<NativeFunction>>,
     /// Error objects created during execution
     /// Stores (error_type, message) pairs
     error_objects: Vec<ErrorObject>,
@@ -442,10 +990,186 @@ pub struct Interpreter {
     timers: Vec<Timer>,
     /// Next timer ID
     next_timer_id: u32,
-    /// GC stats
-    gc_count: u32,
+    /// Bytes scripts may hold across the tables counted by
+    /// [`Interpreter::memory_used`]
+    memory_limit: usize,
+    /// Running count of heap bytes, charged as allocations are made and
+    /// credited as they are reclaimed; resynchronized with the tables
+    /// after a collection or before an allocation is refused
+    heap_charged: usize,
+    /// Exception of the last failed [`Interpreter::call_value`], rethrown
+    /// when the native that made the call returns its error
+    native_exception: Option<Value>,
+    /// Value thrown by the last script or timer callback that no handler
+    /// caught
+    uncaught_exception: Option<Value>,
+    /// Set when an allocation went over the limit; before the next
+    /// instruction runs a collection tries to make room, and a RangeError
+    /// is raised if it cannot
+    out_of_memory: bool,
+    /// Set when a string could not be stored because every runtime string
+    /// slot was taken; no collection can undo that
+    string_table_full: bool,
+    /// Bytes of typed array and ArrayBuffer storage allocated so far
+    binary_memory_used: usize,
+    /// Time source for timers and deadlines
+    clock: Rc<dyn Clock>,
+    /// Clock time (ms) after which running code is interrupted
+    deadline: Option<u64>,
+    /// Backward jumps and calls left before the deadline is next checked
+    deadline_countdown: u32,
+    /// Garbage collection statistics
+    gc_stats: GcStats,
+    /// Hook invoked at the start and end of each collection
+    gc_callback: Option<GcCallback>,
+    /// Array indices whose storage has been reclaimed
+    freed_arrays: HashSet<u32>,
+    /// Closure slots reclaimed by the collector, reused lowest first
+    freed_closures: BTreeSet<u32>,
+    /// Object slots reclaimed by the collector, reused lowest first
+    freed_objects: BTreeSet<u32>,
+    /// Runtime string slots reclaimed by the collector, reused lowest first
+    freed_strings: BTreeSet<u16>,
+    /// Allocation counts per array slot, for weak handles
+    array_generations: SlotGenerations,
+    /// Allocation counts per closure slot, for weak handles
+    closure_generations: SlotGenerations,
+    /// Allocation counts per object slot, for weak handles
+    object_generations: SlotGenerations,
+    /// Allocation counts per runtime string slot, for weak handles
+    string_generations: SlotGenerations,
+    /// Arrays returned by natives that have not escaped the statement
+    /// that created them (see `alloc_temp_array`)
+    temp_arrays: Vec<u32>,
+    /// Global bindings published by top-level scripts
+    globals: Vec<(String, Value)>,
+    /// Globals declared with `var` or `function` by a top-level script,
+    /// which `delete` cannot remove
+    declared_globals: HashSet<String>,
+    /// Interned string literals (content -> runtime string index)
+    interned_strings: HashMap<String, u16>,
+    /// Interned property keys of every object
+    atoms: AtomTable,
+    /// Top-level scripts kept alive for closures that outlive their execution
+    /// (boxed so frame pointers stay valid when the Vec reallocates); None
+    /// once a script that defines no functions has finished running
+    scripts: Vec<Option<Box<FunctionBytecode>>>,
+    /// Bytes of bytecode, constants and string literals held by `scripts`
+    script_bytes: usize,
+    /// Optional console output hook (None writes to stdout/stderr)
+    console_sink: Option<ConsoleSink>,
+    /// Clock microseconds that performance.now counts from (interpreter
+    /// creation or the last clock change)
+    time_origin: u64,
+    /// Xorshift state behind Math.random, seeded from the clock on first use
+    random_state: u64,
+    /// Marks and measures recorded by performance.mark/measure, charged to
+    /// the memory budget until performance.clearMarks/clearMeasures drops them
+    performance_entries: Vec<PerformanceEntry>,
+    /// Start times (performance.now microseconds) of console.time labels
+    console_timers: HashMap<String, i64>,
+    /// Host values attached to userdata objects, keyed by object index
+    userdata: HashMap<u32, UserDataSlot>,
+    /// Object indices whose userdata slot has been reclaimed
+    finalized_userdata: HashSet<u32>,
+    /// Classes registered by the host
+    host_classes: Vec<HostClass>,
+    /// Native function indices that stand for host class constructors and methods
+    host_natives: HashMap<u32, HostNative>,
+    /// Index of the first native registered by the host rather than the builtins
+    first_host_native: u32,
+    /// Lazy and deprecated entries of host modules, keyed by module object index
+    modules: HashMap<u32, PendingModule>,
+    /// Values the host keeps alive with [`PersistentValue`] handles
+    host_roots: SharedRoots,
+    /// Log of nondeterministic inputs being recorded or replayed, if any
+    replay: Option<ReplayMode>,
+    /// Resource counters, while profiling is enabled
+    profile: Option<Box<Profile>>,
+    /// Executions per opcode and calls per native function
+    #[cfg(feature = "opcode-stats")]
+    opcode_stats: Box<OpcodeStats>,
+    /// How deeply scripts compiled by the interpreter may nest
+    max_nesting_depth: usize,
+    /// Whether scripts compiled by the interpreter may use `print`
+    print_enabled: bool,
+    /// Limits on the work compiling one script may take
+    compile_budget: CompileBudget,
+    /// Whether builtin objects refuse writes and `__proto__` and
+    /// `constructor` are blocked (see [`Interpreter::set_hardened`])
+    hardened: bool,
+    /// Longest array scripts may grow (see
+    /// [`Interpreter::set_max_array_length`])
+    max_array_length: usize,
+    /// Longest string scripts may build, in bytes (see
+    /// [`Interpreter::set_max_string_length`])
+    max_string_length: usize,
+    /// Instruction budget of the sliced run in progress, if any
+    slice: Option<Slice>,
+    /// Whether profiling or a sliced run needs to see every instruction
+    instruction_hooks: bool,
+}
+
+/// Type-erased userdata finalizer
+type ErasedFinalizer = Box<dyn FnOnce(Box<dyn Any>)>;
+
+/// Host value owned by a userdata object
+struct UserDataSlot {
+    /// The wrapped value
+    value: Box<dyn Any>,
+    /// Called with the value once the object becomes unreachable
+    finalizer: Option<ErasedFinalizer>,
+}
+
+/// A performance.mark or performance.measure entry
+#[derive(Debug, Clone)]
+pub struct PerformanceEntry {
+    /// Entry name
+    pub name: String,
+    /// "mark" or "measure"
+    pub entry_type: &'static str,
+    /// Start time in microseconds since the time origin
+    pub start_time: i64,
+    /// Duration in microseconds (0 for marks)
+    pub duration: i64,
+}
+
+/// How many times each slot of a reusable table has been handed out
+///
+/// A weak handle records its slot's count; once the slot is reclaimed and
+/// given to something else the counts no longer match.
+#[derive(Debug, Default)]
+struct SlotGenerations(Vec<u32>);
+
+impl SlotGenerations {
+    /// Record that `slot` now holds a new allocation
+    fn bump(&mut self, slot: usize) {
+        if slot >= self.0.len() {
+            self.0.resize(slot + 1, 0);
+        }
+        self.0[slot] = self.0[slot].wrapping_add(1);
+    }
+
+    fn get(&self, slot: usize) -> u32 {
+        self.0.get(slot).copied().unwrap_or(0)
+    }
+}
+
+/// Elements of an array whose length runs far past the elements it holds
+///
+/// Indices below `len` missing from `elements` are holes. The array's
+/// dense storage stays empty while it is stored this way.
+#[derive(Debug, Default)]
+struct SparseArray {
+    /// Length of the array
+    len: usize,
+    /// Elements that are not holes, by index
+    elements: BTreeMap<usize, Value>,
 }
 
+/// Bytes charged for each element of a sparse array
+const SPARSE_ENTRY_BYTES: usize = core::mem::size_of::<(usize, Value)>();
+
 /// Error object storage
 #[derive(Debug, Clone)]
 pub struct ErrorObject {
@@ -453,13 +1177,15 @@ pub struct ErrorObject {
     pub name: String,
     /// Error message
     pub message: String,
+    /// The `cause` option it was constructed with, if any
+    pub cause: Option<Value>,
 }
 
 /// RegExp object storage
 #[derive(Clone)]
 pub struct RegExpObject {
     /// The compiled regex pattern
-    pub regex: regex::Regex,
+    pub regex: Regex,
     /// Original pattern string
     pub pattern: String,
     /// Flags string (e.g., "gi")
@@ -472,8 +1198,8 @@ pub struct RegExpObject {
     pub multiline: bool,
 }
 
-impl std::fmt::Debug for RegExpObject {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for RegExpObject {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("RegExpObject")
             .field("pattern", &self.pattern)
             .field("flags", &self.flags)
@@ -505,6 +1231,21 @@ impl TypedArrayKind {
             TypedArrayKind::Float64 => 8,
         }
     }
+
+    /// Constructor name, e.g. "Uint8Array"
+    pub fn name(&self) -> &'static str {
+        match self {
+            TypedArrayKind::Int8 => "Int8Array",
+            TypedArrayKind::Uint8 => "Uint8Array",
+            TypedArrayKind::Uint8Clamped => "Uint8ClampedArray",
+            TypedArrayKind::Int16 => "Int16Array",
+            TypedArrayKind::Uint16 => "Uint16Array",
+            TypedArrayKind::Int32 => "Int32Array",
+            TypedArrayKind::Uint32 => "Uint32Array",
+            TypedArrayKind::Float32 => "Float32Array",
+            TypedArrayKind::Float64 => "Float64Array",
+        }
+    }
 }
 
 /// TypedArray object - stores typed array data
@@ -703,8 +1444,130 @@ pub struct Timer {
     pub cancelled: bool,
 }
 
-/// Statistics about interpreter memory usage
+/// Garbage collection statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// Number of collections run
+    pub collections: u32,
+    /// Total time spent collecting, in microseconds
+    pub total_pause_us: u64,
+    /// Duration of the last collection in microseconds
+    pub last_pause_us: u64,
+    /// Estimated heap bytes before the last collection
+    pub bytes_before: usize,
+    /// Estimated heap bytes after the last collection
+    pub bytes_after: usize,
+    /// Objects, arrays and closures found reachable by the last collection
+    pub live_objects: usize,
+}
+
+/// Point in a collection at which the GC callback is invoked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GcPhase {
+    /// Before marking begins
+    Start,
+    /// After unreachable storage has been reclaimed
+    End,
+}
+
+/// Callback notified at the start and end of each collection
+pub type GcCallback = Box<dyn FnMut(GcPhase)>;
+
+/// Resources used by the code run since profiling was enabled or last reset
+///
+/// See [`Interpreter::set_profiling`].
 #[derive(Debug, Clone, Default)]
+pub struct ExecutionReport {
+    /// Bytecode instructions executed
+    pub instructions: u64,
+    /// Most values on the value stack at once
+    pub peak_stack_depth: usize,
+    /// Most call frames active at once
+    pub peak_call_depth: usize,
+    /// Native function calls made, by scripts or by other natives
+    pub native_calls: u64,
+    /// Calls per native function name, most called first
+    pub natives: Vec<(String, u64)>,
+    /// Entries allocated into each side table
+    pub allocations: InterpreterStats,
+}
+
+/// Depths of the interpreter's stacks where top-level code started
+#[derive(Debug, Clone, Copy)]
+struct TopLevelBase {
+    stack_depth: usize,
+    frame_depth: usize,
+    handler_depth: usize,
+    temp_depth: usize,
+}
+
+impl TopLevelBase {
+    /// Nested call target for code starting here: run from a native, it
+    /// must stop when its own frame returns rather than carry on into the
+    /// frames already running
+    fn target(&self) -> Option<usize> {
+        (self.frame_depth > 0).then_some(self.frame_depth)
+    }
+}
+
+/// A top-level script or timer callback running in bounded slices
+///
+/// Started by [`Interpreter::start_sliced`] or
+/// [`Interpreter::start_next_timer_sliced`] and driven by
+/// [`Interpreter::run_slice`].
+#[derive(Debug)]
+pub struct SlicedRun {
+    base: TopLevelBase,
+    /// Outcome of a run that finished while starting
+    result: Option<InterpreterResult<Value>>,
+}
+
+/// Instruction budget of a sliced run
+#[derive(Debug, Clone, Copy)]
+struct Slice {
+    /// Instructions left before the run pauses
+    remaining: u64,
+    /// Nested call target of the run's own loop; loops run by natives
+    /// calling back into scripts cannot pause
+    target: Option<usize>,
+    /// Set when the run paused because the budget ran out
+    yielded: bool,
+}
+
+/// Counters behind an [`ExecutionReport`]
+#[derive(Debug, Default)]
+struct Profile {
+    instructions: u64,
+    peak_stack_depth: usize,
+    peak_call_depth: usize,
+    /// Calls per native function index
+    natives: HashMap<u32, u64>,
+    allocations: InterpreterStats,
+}
+
+/// Counters behind [`Interpreter::opcode_stats`] and
+/// [`Interpreter::native_call_stats`]
+#[cfg(feature = "opcode-stats")]
+#[derive(Debug)]
+struct OpcodeStats {
+    /// Executions per opcode byte
+    opcodes: [u64; 256],
+    /// Calls per native function index
+    natives: HashMap<u32, u64>,
+}
+
+#[cfg(feature = "opcode-stats")]
+impl Default for OpcodeStats {
+    fn default() -> Self {
+        Self {
+            opcodes: [0; 256],
+            natives: HashMap::new(),
+        }
+    }
+}
+
+/// Statistics about interpreter memory usage
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct InterpreterStats {
     /// Number of runtime strings
     pub runtime_strings: usize,
@@ -722,6 +1585,10 @@ pub struct InterpreterStats {
     pub typed_arrays: usize,
     /// Number of array buffers
     pub array_buffers: usize,
+    /// Most values ever on the value stack
+    pub stack_high_water: usize,
+    /// Deepest the call stack has been
+    pub call_depth_high_water: usize,
 }
 
 impl Interpreter {
@@ -729,6 +1596,18 @@ impl Interpreter {
     const DEFAULT_STACK_SIZE: usize = 1024;
     /// Default max recursion
     const DEFAULT_MAX_RECURSION: usize = 512;
+    /// Backward jumps and calls between deadline checks
+    const DEADLINE_CHECK_INTERVAL: u32 = 1024;
+    /// Largest single typed array or ArrayBuffer allocation in bytes
+    const MAX_BINARY_ALLOCATION: usize = 1 << 28;
+    /// Holes past which a longer array is stored sparsely
+    ///
+    /// Growing an array longer than this so that fewer than a quarter of
+    /// its elements are present, or by more than this many holes at once
+    /// while fewer than half are, switches it to a map from index to
+    /// element. It goes back to a vector once half its elements are present
+    /// or its length drops to this.
+    const SPARSE_ARRAY_GAP: usize = 1024;
 
     /// Create a new interpreter
     pub fn new() -> Self {
@@ -736,11 +1615,21 @@ impl Interpreter {
             stack: Stack::new(Self::DEFAULT_STACK_SIZE),
             call_stack: Vec::with_capacity(64),
             max_recursion: Self::DEFAULT_MAX_RECURSION,
+            call_depth_high_water: 0,
             runtime_strings: Vec::new(),
             closures: Vec::new(),
             exception_handlers: Vec::new(),
             arrays: Vec::new(),
             objects: Vec::new(),
+            array_props: HashMap::new(),
+            array_holes: HashMap::new(),
+            sparse_arrays: HashMap::new(),
+            exotic_props: HashMap::new(),
+            function_prototypes: HashMap::new(),
+            date_values: HashMap::new(),
+            promises: HashMap::new(),
+            promise_jobs: JobQueue::new(),
+            settlements: SettlementQueue::default(),
             for_in_iterators: Vec::new(),
             for_of_iterators: Vec::new(),
             native_functions: Vec::new(),
@@ -752,9 +1641,61 @@ impl Interpreter {
             nested_call_target_depth: None,
             timers: Vec::new(),
             next_timer_id: 1,
-            gc_count: 0,
+            memory_limit: usize::MAX,
+            heap_charged: 0,
+            out_of_memory: false,
+            string_table_full: false,
+            native_exception: None,
+            uncaught_exception: None,
+            binary_memory_used: 0,
+            clock: default_clock(),
+            deadline: None,
+            deadline_countdown: Self::DEADLINE_CHECK_INTERVAL,
+            gc_stats: GcStats::default(),
+            gc_callback: None,
+            freed_arrays: HashSet::new(),
+            freed_closures: BTreeSet::new(),
+            freed_objects: BTreeSet::new(),
+            freed_strings: BTreeSet::new(),
+            array_generations: SlotGenerations::default(),
+            closure_generations: SlotGenerations::default(),
+            object_generations: SlotGenerations::default(),
+            string_generations: SlotGenerations::default(),
+            temp_arrays: Vec::new(),
+            globals: Vec::new(),
+            declared_globals: HashSet::new(),
+            interned_strings: HashMap::new(),
+            atoms: AtomTable::new(),
+            scripts: Vec::new(),
+            script_bytes: 0,
+            console_sink: None,
+            time_origin: 0,
+            random_state: 0,
+            performance_entries: Vec::new(),
+            console_timers: HashMap::new(),
+            userdata: HashMap::new(),
+            finalized_userdata: HashSet::new(),
+            host_classes: Vec::new(),
+            host_natives: HashMap::new(),
+            first_host_native: 0,
+            modules: HashMap::new(),
+            host_roots: SharedRoots::default(),
+            replay: None,
+            profile: None,
+            #[cfg(feature = "opcode-stats")]
+            opcode_stats: Box::default(),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            print_enabled: true,
+            compile_budget: CompileBudget::default(),
+            hardened: false,
+            max_array_length: Self::MAX_ARRAY_LENGTH,
+            max_string_length: Self::DEFAULT_MAX_STRING_LENGTH,
+            slice: None,
+            instruction_hooks: false,
         };
+        interp.time_origin = interp.clock.now_us();
         interp.register_builtins();
+        interp.first_host_native = interp.native_functions.len() as u32;
         interp
     }
 
@@ -764,11 +1705,21 @@ impl Interpreter {
             stack: Stack::new(stack_size),
             call_stack: Vec::with_capacity(64),
             max_recursion,
+            call_depth_high_water: 0,
             runtime_strings: Vec::new(),
             closures: Vec::new(),
             exception_handlers: Vec::new(),
             arrays: Vec::new(),
             objects: Vec::new(),
+            array_props: HashMap::new(),
+            array_holes: HashMap::new(),
+            sparse_arrays: HashMap::new(),
+            exotic_props: HashMap::new(),
+            function_prototypes: HashMap::new(),
+            date_values: HashMap::new(),
+            promises: HashMap::new(),
+            promise_jobs: JobQueue::new(),
+            settlements: SettlementQueue::default(),
             for_in_iterators: Vec::new(),
             for_of_iterators: Vec::new(),
             native_functions: Vec::new(),
@@ -780,23 +1731,269 @@ impl Interpreter {
             nested_call_target_depth: None,
             timers: Vec::new(),
             next_timer_id: 1,
-            gc_count: 0,
+            memory_limit: usize::MAX,
+            heap_charged: 0,
+            out_of_memory: false,
+            string_table_full: false,
+            native_exception: None,
+            uncaught_exception: None,
+            binary_memory_used: 0,
+            clock: default_clock(),
+            deadline: None,
+            deadline_countdown: Self::DEADLINE_CHECK_INTERVAL,
+            gc_stats: GcStats::default(),
+            gc_callback: None,
+            freed_arrays: HashSet::new(),
+            freed_closures: BTreeSet::new(),
+            freed_objects: BTreeSet::new(),
+            freed_strings: BTreeSet::new(),
+            array_generations: SlotGenerations::default(),
+            closure_generations: SlotGenerations::default(),
+            object_generations: SlotGenerations::default(),
+            string_generations: SlotGenerations::default(),
+            temp_arrays: Vec::new(),
+            globals: Vec::new(),
+            declared_globals: HashSet::new(),
+            interned_strings: HashMap::new(),
+            atoms: AtomTable::new(),
+            scripts: Vec::new(),
+            script_bytes: 0,
+            console_sink: None,
+            time_origin: 0,
+            random_state: 0,
+            performance_entries: Vec::new(),
+            console_timers: HashMap::new(),
+            userdata: HashMap::new(),
+            finalized_userdata: HashSet::new(),
+            host_classes: Vec::new(),
+            host_natives: HashMap::new(),
+            first_host_native: 0,
+            modules: HashMap::new(),
+            host_roots: SharedRoots::default(),
+            replay: None,
+            profile: None,
+            #[cfg(feature = "opcode-stats")]
+            opcode_stats: Box::default(),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            print_enabled: true,
+            compile_budget: CompileBudget::default(),
+            hardened: false,
+            max_array_length: Self::MAX_ARRAY_LENGTH,
+            max_string_length: Self::DEFAULT_MAX_STRING_LENGTH,
+            slice: None,
+            instruction_hooks: false,
         };
+        interp.time_origin = interp.clock.now_us();
         interp.register_builtins();
+        interp.first_host_native = interp.native_functions.len() as u32;
         interp
     }
 
     /// Get memory statistics from the interpreter
     pub fn get_stats(&self) -> InterpreterStats {
         InterpreterStats {
-            runtime_strings: self.runtime_strings.len(),
+            runtime_strings: self.runtime_strings.len() - self.freed_strings.len(),
             arrays: self.arrays.len(),
-            objects: self.objects.len(),
+            objects: self.objects.len() - self.freed_objects.len(),
             closures: self.closures.len(),
             error_objects: self.error_objects.len(),
             regex_objects: self.regex_objects.len(),
             typed_arrays: self.typed_arrays.len(),
             array_buffers: self.array_buffers.len(),
+            stack_high_water: self.stack.high_water(),
+            call_depth_high_water: self.call_depth_high_water,
+        }
+    }
+
+    /// Restart the stack high-water marks in [`InterpreterStats`] from the
+    /// current depths
+    ///
+    /// Nothing else resets them, so they hold the maxima across every
+    /// eval, callback and timer run since the interpreter was created.
+    pub fn reset_watermarks(&mut self) {
+        self.stack.reset_high_water();
+        self.call_depth_high_water = self.call_stack.len();
+    }
+
+    /// The value stack, for hooks watching how close it is to capacity
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    /// Push a call frame, recording the deepest call stack seen
+    #[inline]
+    fn enter_frame(&mut self, frame: CallFrame) {
+        self.call_stack.push(frame);
+        self.call_depth_high_water = self.call_depth_high_water.max(self.call_stack.len());
+    }
+
+    /// Start or stop collecting an [`ExecutionReport`]
+    ///
+    /// While disabled the interpreter only checks that profiling is off
+    /// on each instruction, native call and allocation. Enabling starts
+    /// from zero; disabling discards the counters.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        if !enabled {
+            self.profile = None;
+        } else if self.profile.is_none() {
+            self.profile = Some(Box::default());
+            self.reset_execution_report();
+        }
+        self.update_instruction_hooks();
+    }
+
+    /// Whether an [`ExecutionReport`] is being collected
+    pub fn is_profiling(&self) -> bool {
+        self.profile.is_some()
+    }
+
+    /// Zero the profiling counters; peak depths restart from the current
+    /// ones
+    pub fn reset_execution_report(&mut self) {
+        let (stack_depth, call_depth) = (self.stack.len(), self.call_stack.len());
+        if let Some(profile) = self.profile.as_deref_mut() {
+            *profile = Profile {
+                peak_stack_depth: stack_depth,
+                peak_call_depth: call_depth,
+                ..Profile::default()
+            };
+        }
+    }
+
+    /// Resources used since profiling was enabled or last reset; all zero
+    /// while profiling is disabled
+    pub fn execution_report(&self) -> ExecutionReport {
+        let Some(profile) = self.profile.as_deref() else {
+            return ExecutionReport::default();
+        };
+        let natives = self.native_counts_by_name(&profile.natives);
+        ExecutionReport {
+            instructions: profile.instructions,
+            peak_stack_depth: profile.peak_stack_depth,
+            peak_call_depth: profile.peak_call_depth,
+            native_calls: profile.natives.values().sum(),
+            natives,
+            allocations: profile.allocations.clone(),
+        }
+    }
+
+    /// Per-native call counts keyed by name, most called first
+    fn native_counts_by_name(&self, counts: &HashMap<u32, u64>) -> Vec<(String, u64)> {
+        let mut natives: Vec<(String, u64)> = counts
+            .iter()
+            .map(|(&idx, &count)| {
+                let name = self
+                    .native_functions
+                    .get(idx as usize)
+                    .and_then(|nf| nf.as_ref())
+                    .map_or("<unregistered>", |nf| &nf.name);
+                (name.to_string(), count)
+            })
+            .collect();
+        natives.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then_with(|| a_name.cmp(b_name)));
+        natives
+    }
+
+    /// Executions of every opcode since the interpreter was created or the
+    /// counters were last reset, most executed first
+    ///
+    /// Opcodes that never ran are listed with a count of zero, after the
+    /// rest, in opcode order.
+    #[cfg(feature = "opcode-stats")]
+    pub fn opcode_stats(&self) -> Vec<(OpCode, u64)> {
+        let mut stats: Vec<(OpCode, u64)> = (0..OpCode::COUNT as u8)
+            .filter_map(|byte| {
+                Some((
+                    OpCode::from_u8(byte)?,
+                    self.opcode_stats.opcodes[byte as usize],
+                ))
+            })
+            .collect();
+        // Stable, so ties stay in opcode order
+        stats.sort_by(|(_, a), (_, b)| b.cmp(a));
+        stats
+    }
+
+    /// Calls per native function name since the counters were last reset,
+    /// most called first
+    #[cfg(feature = "opcode-stats")]
+    pub fn native_call_stats(&self) -> Vec<(String, u64)> {
+        self.native_counts_by_name(&self.opcode_stats.natives)
+    }
+
+    /// Zero the opcode and native call counters
+    #[cfg(feature = "opcode-stats")]
+    pub fn reset_opcode_stats(&mut self) {
+        *self.opcode_stats = OpcodeStats::default();
+    }
+
+    /// The opcode and native call counters as a table for reading
+    ///
+    /// Lists every opcode that ran and the ten most called natives, each
+    /// with its share of the total. Two calls of a function summing
+    /// `for (var i = 0; i < n; i = i + 1) { s = s + i; }` report:
+    ///
+    /// ```text
+    /// opcodes: 639 executed
+    ///   GetLoc3                    92   14.4%
+    ///   Goto                       90   14.1%
+    ///   Drop                       60    9.4%
+    ///   Dup                        60    9.4%
+    ///   Add                        60    9.4%
+    ///   ...
+    /// natives: 1 calls
+    ///   Math.max                    1  100.0%
+    /// ```
+    ///
+    /// Opcodes at the top are where the dispatch loop spends its time. Here
+    /// every `Dup` is matched by a `Drop`: assignments used as
+    /// statements keep a copy of the value only to discard it, so fusing
+    /// the pair would remove a fifth of the dispatches. A high `GetField`
+    /// or `GetGlobal` share points at lookups worth caching instead, and a
+    /// hot native at a builtin worth a fast path.
+    #[cfg(feature = "opcode-stats")]
+    pub fn opcode_stats_report(&self) -> String {
+        use core::fmt::Write;
+
+        /// A heading with the total, then one line per row; shares are of
+        /// the total, which may include rows not listed
+        fn section(out: &mut String, heading: &str, total: u64, rows: &[(String, u64)]) {
+            let _ = writeln!(out, "{}", heading);
+            for (name, count) in rows {
+                let share = *count as f64 * 100.0 / total as f64;
+                let _ = writeln!(out, "  {:<20} {:>8} {:>6.1}%", name, count, share);
+            }
+        }
+
+        let opcodes: Vec<(String, u64)> = self
+            .opcode_stats()
+            .into_iter()
+            .take_while(|&(_, count)| count > 0)
+            .map(|(op, count)| (format!("{:?}", op), count))
+            .collect();
+        let executed: u64 = opcodes.iter().map(|(_, count)| count).sum();
+        let natives = self.native_call_stats();
+        let calls: u64 = natives.iter().map(|(_, count)| count).sum();
+        let mut out = String::new();
+        section(
+            &mut out,
+            &format!("opcodes: {} executed", executed),
+            executed,
+            &opcodes,
+        );
+        section(
+            &mut out,
+            &format!("natives: {} calls", calls),
+            calls,
+            &natives[..natives.len().min(10)],
+        );
+        out
+    }
+
+    /// Count an entry allocated into the side table `table` picks
+    fn count_allocation(&mut self, table: fn(&mut InterpreterStats) -> &mut usize) {
+        if let Some(profile) = self.profile.as_deref_mut() {
+            *table(&mut profile.allocations) += 1;
         }
     }
 
@@ -806,46 +2003,207 @@ impl Interpreter {
     /// Runtime string index offset (indices >= this are runtime strings)
     const RUNTIME_STRING_OFFSET: u16 = 0x8000;
 
-    /// Get string content from a string value
+    /// Get string content from a string value, resolving compile-time
+    /// strings against `bytecode`
     fn get_string_content<'a>(
         &'a self,
         val: Value,
         bytecode: &'a FunctionBytecode,
-    ) -> Option<&'a str> {
-        if !val.is_string() {
-            return None;
+    ) -> Option<JsStr<'a>> {
+        if let Some(s) = val.to_inline_string() {
+            return Some(JsStr::Inline(s));
         }
         let idx = val.to_string_idx()?;
 
         // Check if it's a built-in string
         if let Some(s) = crate::value::get_builtin_string(idx) {
-            return Some(s);
+            return Some(JsStr::Table(s));
         }
 
         // Check if it's a runtime string
         if idx >= Self::RUNTIME_STRING_OFFSET {
             let runtime_idx = (idx - Self::RUNTIME_STRING_OFFSET) as usize;
-            return self.runtime_strings.get(runtime_idx).map(|s| s.as_str());
+            return self
+                .runtime_strings
+                .get(runtime_idx)
+                .map(|s| JsStr::Table(s.as_str()));
         }
 
         // Otherwise it's a compile-time string
         bytecode
             .string_constants
             .get(idx as usize)
-            .map(|s| s.as_str())
+            .map(|s| JsStr::Table(s.as_str()))
     }
 
-    /// Create a runtime string and return its Value
+    /// Create a string value, storing short strings inline
+    ///
+    /// Every string value built at runtime comes from here. Slots the
+    /// collector reclaimed are reused lowest first. Once the runtime string
+    /// table reaches the reserved builtin string indices the result is the
+    /// empty string and a RangeError is raised before the next instruction.
     fn create_runtime_string(&mut self, s: String) -> Value {
-        let idx = self.runtime_strings.len();
-        self.runtime_strings.push(s);
-        Value::string(Self::RUNTIME_STRING_OFFSET + idx as u16)
+        if let Some(val) = Value::inline_string(&s) {
+            return val;
+        }
+        if s.is_empty() {
+            return Value::string(crate::value::STR_EMPTY);
+        }
+        let slot = match self.freed_strings.first() {
+            Some(&slot) => slot as usize,
+            None => self.runtime_strings.len(),
+        };
+        let Some(idx) = u16::try_from(slot)
+            .ok()
+            .and_then(|idx| Self::RUNTIME_STRING_OFFSET.checked_add(idx))
+            .filter(|&idx| !crate::value::is_builtin_string(idx))
+        else {
+            self.string_table_full = true;
+            self.out_of_memory = true;
+            return Value::string(crate::value::STR_EMPTY);
+        };
+        self.charge_memory(s.capacity());
+        if slot < self.runtime_strings.len() {
+            self.freed_strings.remove(&(slot as u16));
+            self.runtime_strings[slot] = s;
+        } else {
+            self.runtime_strings.push(s);
+        }
+        self.string_generations.bump(slot);
+        self.count_allocation(|t| &mut t.runtime_strings);
+        Value::string(idx)
+    }
+
+    /// A string value for `s`, reusing its interned copy if there is one
+    fn reuse_or_create_string(&mut self, s: String) -> Value {
+        match self.interned_strings.get(&s) {
+            Some(&idx) => Value::string(idx),
+            None => self.create_runtime_string(s),
+        }
+    }
+
+    /// Contents of a string value, or None if `value` is not a string
+    ///
+    /// This is the one accessor for string contents: it handles strings
+    /// stored inline in the value as well as table-backed ones.
+    pub fn get_string(&self, value: Value) -> Option<JsStr<'_>> {
+        if let Some(s) = value.to_inline_string() {
+            return Some(JsStr::Inline(s));
+        }
+        self.get_string_by_idx(value.to_string_idx()?)
+            .map(JsStr::Table)
+    }
+
+    /// Elements of an array value, or None if `value` is not an array or
+    /// is stored sparsely
+    ///
+    /// [`Interpreter::array_len`] and [`Interpreter::array_element_at`]
+    /// read arrays however they are stored.
+    pub fn get_array_elements(&self, value: Value) -> Option<&[Value]> {
+        let idx = value.to_array_idx()?;
+        if self.sparse_arrays.contains_key(&idx) {
+            return None;
+        }
+        self.get_array(idx).map(Vec::as_slice)
+    }
+
+    /// Number of own properties of a plain object, or None if `obj` is not
+    /// one
+    ///
+    /// With [`Interpreter::object_property_at`], [`Interpreter::array_len`],
+    /// [`Interpreter::array_element_at`] and [`Interpreter::get_string`]
+    /// this is how natives and embedders read script values; unlike the
+    /// interpreter's tables it stays put when storage changes.
+    pub fn object_property_count(&self, obj: Value) -> Option<usize> {
+        Some(self.get_object(obj.to_object_idx()?)?.properties.len())
+    }
+
+    /// Own property `index` of a plain object in insertion order, as a key
+    /// and value
+    ///
+    /// Non-enumerable properties are included; check them with
+    /// [`Interpreter::has_property`] and [`PropertyLookup::OwnEnumerable`].
+    pub fn object_property_at(&self, obj: Value, index: usize) -> Option<(&str, Value)> {
+        let (key, val) = self
+            .get_object(obj.to_object_idx()?)?
+            .properties
+            .get(index)?;
+        Some((self.atoms.name(*key), *val))
+    }
+
+    /// Number of elements of an array, or None if `arr` is not an array
+    pub fn array_len(&self, arr: Value) -> Option<usize> {
+        let idx = arr.to_array_idx()?;
+        self.get_array(idx)?;
+        Some(self.array_length(idx))
     }
 
-    /// Get a string by its index (works for both compile-time and runtime strings)
+    /// Element `index` of an array, or None if `arr` is not an array or is
+    /// shorter; holes read as undefined
+    pub fn array_element_at(&self, arr: Value, index: usize) -> Option<Value> {
+        let idx = arr.to_array_idx()?;
+        (index < self.array_len(arr)?).then(|| self.array_element(idx, index))
+    }
+
+    /// Number of elements of a typed array, or None if `value` is not one
+    pub fn typed_array_len(&self, value: Value) -> Option<usize> {
+        let idx = value.to_typed_array_idx()?;
+        Some(self.typed_arrays.get(idx as usize)?.length)
+    }
+
+    /// Element `index` of a typed array as a number, or None if `value` is
+    /// not a typed array or is shorter
+    pub fn typed_array_element_at(&self, value: Value, index: usize) -> Option<Value> {
+        let idx = value.to_typed_array_idx()?;
+        self.typed_arrays
+            .get(idx as usize)?
+            .get(index)
+            .map(Value::int)
+    }
+
+    /// Create or overwrite own property `key` of a plain object
+    ///
+    /// Returns false, changing nothing, if `obj` is not a plain object.
+    pub fn object_set(&mut self, obj: Value, key: &str, val: Value) -> bool {
+        let Some(idx) = obj
+            .to_object_idx()
+            .filter(|&idx| self.get_object(idx).is_some())
+        else {
+            return false;
+        };
+        self.promote(val);
+        let key = self.atom(key);
+        self.object_set_property(idx, key, val);
+        true
+    }
+
+    /// Append `val` to an array, returning its new length, or None if `arr`
+    /// is not an array or is already as long as
+    /// [`Interpreter::max_array_length`] allows
+    pub fn array_push(&mut self, arr: Value, val: Value) -> Option<usize> {
+        let idx = arr.to_array_idx()?;
+        self.check_array_length(self.array_length(idx).saturating_add(1))
+            .ok()?;
+        self.promote(val);
+        if self.sparse_arrays.contains_key(&idx) {
+            let len = self.array_length(idx);
+            self.set_array_element(idx, len, val).ok()?;
+            return Some(len + 1);
+        }
+        let elements = self.arrays.get_mut(idx as usize)?;
+        let capacity = elements.capacity();
+        elements.push(val);
+        let (len, grown) = (elements.len(), elements.capacity() - capacity);
+        self.charge_memory(grown * core::mem::size_of::<Value>());
+        Some(len)
+    }
+
+    /// Get a string by its index (works for built-in, compile-time and runtime strings)
     /// For compile-time strings, uses current_string_constants if set.
     pub fn get_string_by_idx(&self, str_idx: u16) -> Option<&str> {
-        if str_idx >= Self::RUNTIME_STRING_OFFSET {
+        if let Some(s) = crate::value::get_builtin_string(str_idx) {
+            Some(s)
+        } else if str_idx >= Self::RUNTIME_STRING_OFFSET {
             let runtime_idx = (str_idx - Self::RUNTIME_STRING_OFFSET) as usize;
             self.runtime_strings.get(runtime_idx).map(|s| s.as_str())
         } else {
@@ -860,72 +2218,845 @@ impl Interpreter {
         }
     }
 
-    /// Create a closure and return a Value that references it
-    fn create_closure(&mut self, bytecode: *const FunctionBytecode, var_refs: Vec<Value>) -> Value {
-        let idx = self.closures.len();
-        self.closures.push(ClosureData::new(bytecode, var_refs));
-        // Use high bit to mark as closure index
-        Value::closure_idx(idx as u32)
+    /// The atom for the property key `key`, interning it if it is new
+    pub fn atom(&mut self, key: &str) -> Atom {
+        let (atom, new) = self.atoms.intern(key);
+        if new {
+            self.charge_memory(atom_bytes(key));
+        }
+        atom
     }
 
-    /// Get a closure by index
-    fn get_closure(&self, idx: u32) -> Option<&ClosureData> {
-        self.closures.get(idx as usize)
+    /// The property key an atom stands for
+    pub fn atom_name(&self, atom: Atom) -> &str {
+        self.atoms.name(atom)
     }
 
-    /// Create an array and return a Value that references it
-    fn create_array(&mut self, elements: Vec<Value>) -> Value {
-        let idx = self.arrays.len();
-        self.arrays.push(elements);
-        Value::array_idx(idx as u32)
+    /// The own property `key` of a property store; keys never interned
+    /// are owned by nothing
+    fn named_get(&self, props: &ObjectInstance, key: &str) -> Option<Value> {
+        props.get(self.atoms.lookup(key)?)
     }
 
-    /// Get an array by index
-    fn get_array(&self, idx: u32) -> Option<&Vec<Value>> {
-        self.arrays.get(idx as usize)
+    /// Intern a string literal as a runtime string, reusing an existing entry
+    fn intern_string(&mut self, s: &str) -> Value {
+        if let Some(val) = Value::inline_string(s) {
+            return val;
+        }
+        if let Some(&idx) = self.interned_strings.get(s) {
+            return Value::string(idx);
+        }
+        let val = self.create_runtime_string(s.to_string());
+        if let Some(idx) = val.to_string_idx()
+            && idx >= Self::RUNTIME_STRING_OFFSET
+        {
+            self.interned_strings.insert(s.to_string(), idx);
+        }
+        val
     }
 
-    /// Get an array by index without bounds checking
+    /// Rewrite the string literals in a script's constant pool as interned
+    /// runtime strings, and resolve its string constants to atoms
     ///
-    /// # Safety
-    /// Caller must ensure idx < self.arrays.len()
-    #[inline]
-    unsafe fn get_array_unchecked(&self, idx: u32) -> &Vec<Value> {
-        debug_assert!((idx as usize) < self.arrays.len());
-        unsafe { self.arrays.get_unchecked(idx as usize) }
+    /// Compile-time string indices are only meaningful while their own bytecode is
+    /// executing, so any literal stored in a global, array or object would otherwise
+    /// resolve to the wrong text once the script returns.
+    fn intern_string_constants(&mut self, bytecode: &mut FunctionBytecode) {
+        for i in 0..bytecode.constants.len() {
+            let val = bytecode.constants[i];
+            if let Some(str_idx) = val.to_string_idx()
+                && str_idx < Self::RUNTIME_STRING_OFFSET
+                && let Some(s) = bytecode.string_constants.get(str_idx as usize)
+            {
+                let s = s.clone();
+                bytecode.constants[i] = self.intern_string(&s);
+            }
+        }
+        bytecode.atoms = bytecode
+            .string_constants
+            .iter()
+            .map(|s| self.atom(s))
+            .collect();
+        for inner in &mut bytecode.inner_functions {
+            self.intern_string_constants(inner);
+        }
     }
 
-    /// Get a mutable array by index
-    fn get_array_mut(&mut self, idx: u32) -> Option<&mut Vec<Value>> {
-        self.arrays.get_mut(idx as usize)
+    /// The global name stored at constant `name_idx` of `bytecode`
+    fn global_name(&self, bytecode: &FunctionBytecode, name_idx: u16) -> InterpreterResult<String> {
+        bytecode
+            .constants
+            .get(name_idx as usize)
+            .and_then(|v| self.get_string_content(*v, bytecode))
+            .map(|name| name.to_string())
+            .ok_or_else(|| {
+                InterpreterError::InternalError(format!(
+                    "invalid global name constant: {}",
+                    name_idx
+                ))
+            })
     }
 
-    /// Get a mutable array by index without bounds checking
+    /// The builtin a global name refers to when no script or host binding
+    /// shadows it
+    fn builtin_global(&self, name: &str) -> Option<Value> {
+        match name {
+            "undefined" => Some(Value::undefined()),
+            "NaN" => Some(Value::int(0)), // TODO: proper NaN when floats are added
+            "Infinity" => Some(Value::int(Value::INT_MAX)), // TODO: proper infinity when floats are added
+            "Math" => Some(Value::builtin_object(BUILTIN_MATH)),
+            "JSON" => Some(Value::builtin_object(BUILTIN_JSON)),
+            "Reflect" => Some(Value::builtin_object(BUILTIN_REFLECT)),
+            "Number" => Some(Value::builtin_object(BUILTIN_NUMBER)),
+            "Boolean" => Some(Value::builtin_object(BUILTIN_BOOLEAN)),
+            "String" => Some(Value::builtin_object(BUILTIN_STRING)),
+            "Object" => Some(Value::builtin_object(BUILTIN_OBJECT)),
+            "Array" => Some(Value::builtin_object(BUILTIN_ARRAY)),
+            "console" => Some(Value::builtin_object(BUILTIN_CONSOLE)),
+            "performance" => Some(Value::builtin_object(BUILTIN_PERFORMANCE)),
+            "Date" => Some(Value::builtin_object(BUILTIN_DATE)),
+            "Error" => Some(Value::builtin_object(BUILTIN_ERROR)),
+            "TypeError" => Some(Value::builtin_object(BUILTIN_TYPE_ERROR)),
+            "ReferenceError" => Some(Value::builtin_object(BUILTIN_REFERENCE_ERROR)),
+            "SyntaxError" => Some(Value::builtin_object(BUILTIN_SYNTAX_ERROR)),
+            "RangeError" => Some(Value::builtin_object(BUILTIN_RANGE_ERROR)),
+            "EvalError" => Some(Value::builtin_object(BUILTIN_EVAL_ERROR)),
+            "URIError" => Some(Value::builtin_object(BUILTIN_URI_ERROR)),
+            "InternalError" => Some(Value::builtin_object(BUILTIN_INTERNAL_ERROR)),
+            "RegExp" => Some(Value::builtin_object(BUILTIN_REGEXP)),
+            "globalThis" => Some(Value::builtin_object(BUILTIN_GLOBAL_THIS)),
+            // TypedArray and ArrayBuffer constructors
+            "ArrayBuffer" => Some(Value::builtin_object(BUILTIN_ARRAY_BUFFER)),
+            "Int8Array" => Some(Value::builtin_object(BUILTIN_INT8_ARRAY)),
+            "Uint8Array" => Some(Value::builtin_object(BUILTIN_UINT8_ARRAY)),
+            "Uint8ClampedArray" => Some(Value::builtin_object(BUILTIN_UINT8_CLAMPED_ARRAY)),
+            "Int16Array" => Some(Value::builtin_object(BUILTIN_INT16_ARRAY)),
+            "Uint16Array" => Some(Value::builtin_object(BUILTIN_UINT16_ARRAY)),
+            "Int32Array" => Some(Value::builtin_object(BUILTIN_INT32_ARRAY)),
+            "Uint32Array" => Some(Value::builtin_object(BUILTIN_UINT32_ARRAY)),
+            "Float32Array" => Some(Value::builtin_object(BUILTIN_FLOAT32_ARRAY)),
+            "Float64Array" => Some(Value::builtin_object(BUILTIN_FLOAT64_ARRAY)),
+            _ => self.get_native_func(name),
+        }
+    }
+
+    /// A global read as a property of globalThis: script and host bindings
+    /// first, then builtins and global natives
     ///
-    /// # Safety
-    /// Caller must ensure idx < self.arrays.len()
-    #[inline]
-    unsafe fn get_array_mut_unchecked(&mut self, idx: u32) -> &mut Vec<Value> {
-        debug_assert!((idx as usize) < self.arrays.len());
-        unsafe { self.arrays.get_unchecked_mut(idx as usize) }
+    /// Natives such as `Math.abs` are registered under dotted names; they
+    /// are methods of their objects, not globals.
+    fn global_this_property(&self, name: &str) -> Option<Value> {
+        self.global_binding(name)
+            .or_else(|| (!name.contains('.')).then(|| self.builtin_global(name))?)
     }
 
-    /// Create a new object and return its value
-    fn create_object(&mut self) -> Value {
-        let idx = self.objects.len();
-        self.objects.push(ObjectInstance::new());
-        Value::object_idx(idx as u32)
+    /// A script or host binding as globalThis sees it
+    ///
+    /// Scripts publish their top-level bindings when they finish; until
+    /// then those live in the script's frame, and a running script's
+    /// binding is the current one.
+    fn global_binding(&self, name: &str) -> Option<Value> {
+        self.running_script_bindings()
+            .find(|(binding, _)| *binding == name)
+            .map(|(_, val)| val)
+            .or_else(|| self.get_global(name))
+    }
+
+    /// Initialized top-level bindings of the scripts on the call stack,
+    /// innermost script first
+    fn running_script_bindings(&self) -> impl Iterator<Item = (&str, Value)> {
+        self.call_stack.iter().rev().flat_map(move |frame| {
+            // Safety: bytecode pointer is valid for frame lifetime
+            let bytecode = unsafe { &*frame.bytecode };
+            bytecode
+                .global_slots
+                .iter()
+                .filter_map(move |(slot, name)| {
+                    let val = self.stack.get_local_at(frame.frame_ptr, *slot as usize)?;
+                    (!val.is_uninitialized()).then_some((name.as_str(), val))
+                })
+        })
     }
 
-    /// Create a new object with a constructor reference and return its value
-    fn create_object_with_constructor(&mut self, constructor: Value) -> Value {
-        let idx = self.objects.len();
-        self.objects
-            .push(ObjectInstance::with_constructor(constructor));
-        Value::object_idx(idx as u32)
+    /// Look up a global binding published by a top-level script
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.globals
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| *v)
     }
 
-    /// Get an object by index
+    /// Write a global binding as `globalThis[name] = val` does
+    ///
+    /// A binding of a running script lives in its frame until the script
+    /// finishes, so the write goes there; any other creates or updates the
+    /// published binding.
+    fn set_global_binding(&mut self, name: &str, val: Value) {
+        let slot = self.call_stack.iter().rev().find_map(|frame| {
+            // Safety: bytecode pointer is valid for frame lifetime
+            let bytecode = unsafe { &*frame.bytecode };
+            let (slot, _) = bytecode.global_slots.iter().find(|(slot, binding)| {
+                binding == name
+                    && self
+                        .stack
+                        .get_local_at(frame.frame_ptr, *slot as usize)
+                        .is_some_and(|v| !v.is_uninitialized())
+            })?;
+            Some((frame.frame_ptr, *slot as usize))
+        });
+        match slot {
+            Some((frame_ptr, slot)) => self.stack.set_local_at(frame_ptr, slot, val),
+            None => self.set_global(name, val),
+        }
+    }
+
+    /// Create or update a global binding
+    pub fn set_global(&mut self, name: &str, val: Value) {
+        if let Some(entry) = self.globals.iter_mut().find(|(k, _)| k == name) {
+            entry.1 = val;
+        } else {
+            self.globals.push((name.to_string(), val));
+        }
+    }
+
+    /// Delete a global binding as `delete name` does, returning whether it
+    /// is gone
+    ///
+    /// Bindings created by assignment or [`set_global`](Self::set_global)
+    /// are removed. Those a top-level `var` or `function` declared, and
+    /// builtins, cannot be; deleting them changes nothing and is false.
+    /// Names bound to nothing are trivially gone.
+    pub fn delete_global(&mut self, name: &str) -> bool {
+        if self.declared_globals.contains(name) {
+            return false;
+        }
+        if let Some(pos) = self.globals.iter().position(|(k, _)| k == name) {
+            self.globals.remove(pos);
+            return true;
+        }
+        self.builtin_global(name).is_none()
+    }
+
+    /// Redirect console output to a callback, or restore stdout/stderr with None
+    pub fn set_console_sink(&mut self, sink: Option<ConsoleSink>) {
+        self.console_sink = sink;
+    }
+
+    /// Write a line of console output to the sink or the standard streams
+    fn write_console(&mut self, level: ConsoleLevel, line: &str) {
+        match &mut self.console_sink {
+            Some(sink) => sink(level, line),
+            #[cfg(feature = "std")]
+            None if level == ConsoleLevel::Log => println!("{}", line),
+            #[cfg(feature = "std")]
+            None => eprintln!("{}", line),
+            // Without std there is nowhere to print; output needs a sink
+            #[cfg(not(feature = "std"))]
+            None => {}
+        }
+    }
+
+    /// Format a value the way console.log prints it
+    pub fn format_value(&self, val: Value) -> String {
+        format_value(self, val)
+    }
+
+    /// Take the value thrown by the last script or timer callback that
+    /// failed with an uncaught exception
+    pub fn take_uncaught_exception(&mut self) -> Option<Value> {
+        self.uncaught_exception.take()
+    }
+
+    /// Name and message of a thrown value: an error object's own, those
+    /// of an object that looks like an error, or an empty name and the
+    /// value as `print` shows it
+    pub fn describe_exception(&self, exception: Value) -> (String, String) {
+        match exception
+            .to_error_object_idx()
+            .and_then(|idx| self.error_objects.get(idx as usize))
+        {
+            Some(err) => (err.name.clone(), err.message.clone()),
+            None => self
+                .error_like_parts(exception)
+                .unwrap_or_else(|| (String::new(), format_value(self, exception))),
+        }
+    }
+
+    /// Name and message of an object that looks like an error: one whose
+    /// `name` and `message`, own or inherited, are both strings
+    fn error_like_parts(&self, val: Value) -> Option<(String, String)> {
+        Some((
+            self.object_text_field(val, "name")?,
+            self.object_text_field(val, "message")?,
+        ))
+    }
+
+    /// The string an object holds or inherits at `key`, without running
+    /// any script code
+    fn object_text_field(&self, val: Value, key: &str) -> Option<String> {
+        let field = self.object_get_property(val.to_object_idx()?, key);
+        if field.is_undefined() {
+            return self.error_prototype_field(val, key).map(str::to_string);
+        }
+        self.get_string(field).map(|s| s.to_string())
+    }
+
+    /// Build the value a JSON document describes without running a script
+    ///
+    /// Errors carry the line and column where parsing stopped.
+    pub fn parse_json(&mut self, text: &str, options: JsonOptions) -> Result<Value, JsonError> {
+        JsonParser::new(text, options).parse_document(self)
+    }
+
+    /// Serialize a value the way `JSON.stringify` does, indenting nested
+    /// lines by two spaces when `pretty` is set
+    ///
+    /// Values JSON leaves out, such as undefined or a function, produce an
+    /// error rather than an empty string.
+    pub fn to_json(&mut self, value: Value, pretty: bool) -> Result<String, JsonError> {
+        let indent = if pretty { "  " } else { "" };
+        let json = JsonWriter::new(indent.to_string())
+            .write(self, value)
+            .map_err(|err| JsonError {
+                message: err.to_string(),
+                pos: None,
+            })?;
+        json.ok_or_else(|| JsonError {
+            message: "value has no JSON representation".to_string(),
+            pos: None,
+        })
+    }
+
+    /// Create a closure and return a Value that references it
+    ///
+    /// Takes the lowest slot the collector has freed before growing the
+    /// table, so a loop that drops its closures between collections keeps
+    /// reusing the same slots in the same order.
+    fn create_closure(
+        &mut self,
+        bytecode: *const FunctionBytecode,
+        var_refs: Vec<Value>,
+    ) -> InterpreterResult<Value> {
+        self.charge_memory(var_refs.capacity() * core::mem::size_of::<Value>());
+        let closure = ClosureData::new(bytecode, var_refs);
+        let idx = match self.freed_closures.pop_first() {
+            Some(idx) => {
+                self.closures[idx as usize] = closure;
+                idx
+            }
+            None => {
+                let idx = table_index(self.closures.len())?;
+                self.closures.push(closure);
+                idx
+            }
+        };
+        self.closure_generations.bump(idx as usize);
+        self.count_allocation(|t| &mut t.closures);
+        Ok(Value::closure_idx(idx))
+    }
+
+    /// Get a closure by index
+    fn get_closure(&self, idx: u32) -> Option<&ClosureData> {
+        self.closures.get(idx as usize)
+    }
+
+    /// Allocate an array holding `elements`
+    ///
+    /// Scripts cannot grow an array past [`Interpreter::max_array_length`],
+    /// at most [`Interpreter::MAX_ARRAY_LENGTH`]; hosts should keep to the
+    /// same limit.
+    pub fn alloc_array(&mut self, elements: Vec<Value>) -> Value {
+        self.charge_memory(elements.capacity() * core::mem::size_of::<Value>());
+        let idx = self.arrays.len();
+        self.arrays.push(elements);
+        self.array_generations.bump(idx);
+        self.count_allocation(|t| &mut t.arrays);
+        Value::array_idx(idx as u32)
+    }
+
+    /// Truncate array `idx` or pad it with holes to `len` elements, as
+    /// writing its `length` does
+    ///
+    /// Growth that does not fit in the memory budget fails with a
+    /// RangeError and leaves the array unchanged; growth that would leave
+    /// it mostly holes stores it sparsely instead.
+    fn resize_array(&mut self, idx: u32, len: usize) -> InterpreterResult<()> {
+        self.check_array_length(len)?;
+        if let Some(sparse) = self.sparse_arrays.get_mut(&idx) {
+            sparse.elements.split_off(&len);
+            sparse.len = len;
+            self.settle_sparse_array(idx);
+            return Ok(());
+        }
+        let Some(old_len) = self.get_array(idx).map(Vec::len) else {
+            return Ok(());
+        };
+        if len > old_len && self.prefers_sparse(idx, old_len, len) {
+            self.make_array_sparse(idx, len);
+            return Ok(());
+        }
+        self.resize_dense_array(idx, len)?;
+        if len > old_len {
+            self.array_holes
+                .entry(idx)
+                .or_default()
+                .extend(old_len..len);
+        }
+        Ok(())
+    }
+
+    /// Truncate the vector of array `idx` or pad it with undefined to
+    /// `len` elements, charging the memory budget for growth
+    fn resize_dense_array(&mut self, idx: u32, len: usize) -> InterpreterResult<()> {
+        let Some(old_len) = self.get_array(idx).map(Vec::len) else {
+            return Ok(());
+        };
+        self.check_array_length(len)?;
+        if len > old_len {
+            self.reserve_array(idx, len - old_len)?;
+        }
+        self.arrays[idx as usize].resize(len, Value::undefined());
+        self.remap_array_holes(idx, |i| (i < len).then_some(i));
+        Ok(())
+    }
+
+    /// Make room for `additional` more elements in array `idx` and return
+    /// its length once they are added
+    ///
+    /// Capacity grows by doubling, so the budget is checked for what the
+    /// growth actually allocates. Growth that does not fit, or that would
+    /// make the array too long, fails with a RangeError and
+    /// leaves the array unchanged.
+    fn reserve_array(&mut self, idx: u32, additional: usize) -> InterpreterResult<usize> {
+        let value_size = core::mem::size_of::<Value>();
+        let Some((len, capacity)) = self.get_array(idx).map(|arr| (arr.len(), arr.capacity()))
+        else {
+            return Err(InterpreterError::InternalError("invalid array".to_string()));
+        };
+        let needed = len.saturating_add(additional);
+        self.check_array_length(needed)?;
+        if needed > capacity {
+            let grown = needed.max(capacity * 2) - capacity;
+            self.check_memory(grown * value_size)?;
+            let arr = &mut self.arrays[idx as usize];
+            arr.reserve(additional);
+            let grown = arr.capacity() - capacity;
+            self.charge_memory(grown * value_size);
+        }
+        Ok(needed)
+    }
+
+    /// Allocate an array that is reclaimed at the end of the current
+    /// statement unless it escapes
+    ///
+    /// For fresh native results whose elements are primitives or values
+    /// already held elsewhere. The array is promoted to an ordinary one as
+    /// soon as it is stored anywhere other than the value stack.
+    fn alloc_temp_array(&mut self, elements: Vec<Value>) -> Value {
+        let val = self.alloc_array(elements);
+        if let Some(idx) = val.to_array_idx() {
+            self.temp_arrays.push(idx);
+        }
+        val
+    }
+
+    /// Allocate a new array derived from existing values
+    ///
+    /// Natives that build a dense result from other arrays' elements
+    /// (filter, flat) go through here; those that keep holes where their
+    /// source had them (slice, map, concat) use
+    /// [`Interpreter::alloc_array_entries`]. Iterator handles belong to the
+    /// value stack only and are stored as undefined; any temporary array
+    /// among the elements is promoted now that another array references it.
+    fn alloc_derived_array(&mut self, elements: impl IntoIterator<Item = Value>) -> Value {
+        let elements: Vec<Value> = elements
+            .into_iter()
+            .map(|v| {
+                if v.is_iterator() || v.is_for_of_iterator() {
+                    Value::undefined()
+                } else {
+                    v
+                }
+            })
+            .collect();
+        self.promote_all(&elements);
+        self.alloc_temp_array(elements)
+    }
+
+    /// Keep a temporary array alive past the current statement
+    ///
+    /// Must be called wherever a value leaves the value stack for
+    /// longer-lived storage: properties, elements, globals, captured
+    /// variables, native arguments and results handed back to Rust.
+    #[inline]
+    fn promote(&mut self, val: Value) {
+        if !self.temp_arrays.is_empty()
+            && let Some(idx) = val.to_array_idx()
+        {
+            self.temp_arrays.retain(|&t| t != idx);
+        }
+    }
+
+    /// [`Interpreter::promote`] every value in a slice
+    #[inline]
+    fn promote_all(&mut self, vals: &[Value]) {
+        if !self.temp_arrays.is_empty() {
+            for &val in vals {
+                self.promote(val);
+            }
+        }
+    }
+
+    /// Reclaim temporary arrays no longer referenced from the value stack
+    ///
+    /// Skipped inside callbacks invoked from natives, which may still hold
+    /// values on the Rust stack. Trailing arrays are dropped from the table
+    /// so their indices are reused; the rest only lose their storage.
+    fn release_temp_arrays(&mut self) {
+        if self.temp_arrays.is_empty() || self.nested_call_target_depth.is_some() {
+            return;
+        }
+        let stack = self.stack.values();
+        let mut dead: Vec<u32> = Vec::new();
+        self.temp_arrays.retain(|&idx| {
+            let live = stack.iter().any(|v| v.to_array_idx() == Some(idx));
+            if !live {
+                dead.push(idx);
+            }
+            live
+        });
+        dead.sort_unstable();
+        for &idx in dead.iter().rev() {
+            let storage = self.arrays[idx as usize].capacity() * core::mem::size_of::<Value>();
+            self.heap_charged = self.heap_charged.saturating_sub(storage);
+            self.array_props.remove(&idx);
+            self.array_holes.remove(&idx);
+            self.sparse_arrays.remove(&idx);
+            if idx as usize + 1 == self.arrays.len() {
+                self.arrays.pop();
+                self.freed_arrays.remove(&idx);
+            } else {
+                self.arrays[idx as usize] = Vec::new();
+                self.freed_arrays.insert(idx);
+            }
+        }
+    }
+
+    /// Start building a plain object
+    pub fn alloc_object(&mut self) -> ObjectBuilder<'_> {
+        ObjectBuilder {
+            interp: self,
+            object: ObjectInstance::new(),
+        }
+    }
+
+    /// Allocate a string, reusing an existing runtime string with the same contents
+    pub fn alloc_string(&mut self, s: &str) -> Value {
+        self.intern_string(s)
+    }
+
+    /// Allocate an error object such as `alloc_error("TypeError", "bad input")`
+    pub fn alloc_error(&mut self, name: &str, message: &str) -> Value {
+        self.charge_memory(name.len() + message.len());
+        let idx = self.error_objects.len() as u32;
+        self.error_objects.push(ErrorObject {
+            name: name.to_string(),
+            message: message.to_string(),
+            cause: None,
+        });
+        self.count_allocation(|t| &mut t.error_objects);
+        Value::error_object(idx)
+    }
+
+    /// Get an array by index
+    fn get_array(&self, idx: u32) -> Option<&Vec<Value>> {
+        self.arrays.get(idx as usize)
+    }
+
+    /// Get an array by index without bounds checking
+    ///
+    /// # Safety
+    /// Caller must ensure idx < self.arrays.len()
+    #[inline]
+    unsafe fn get_array_unchecked(&self, idx: u32) -> &Vec<Value> {
+        debug_assert!((idx as usize) < self.arrays.len());
+        unsafe { self.arrays.get_unchecked(idx as usize) }
+    }
+
+    /// Get a mutable array by index
+    fn get_array_mut(&mut self, idx: u32) -> Option<&mut Vec<Value>> {
+        self.arrays.get_mut(idx as usize)
+    }
+
+    /// Get a mutable array by index without bounds checking
+    ///
+    /// # Safety
+    /// Caller must ensure idx < self.arrays.len()
+    #[inline]
+    unsafe fn get_array_mut_unchecked(&mut self, idx: u32) -> &mut Vec<Value> {
+        debug_assert!((idx as usize) < self.arrays.len());
+        unsafe { self.arrays.get_unchecked_mut(idx as usize) }
+    }
+
+    /// Whether element `i` of array `idx` is a hole: deleted, or skipped
+    /// over by growth, and not written since
+    fn is_array_hole(&self, idx: u32, i: usize) -> bool {
+        if let Some(sparse) = self.sparse_arrays.get(&idx) {
+            return i < sparse.len && !sparse.elements.contains_key(&i);
+        }
+        self.array_holes
+            .get(&idx)
+            .is_some_and(|holes| holes.contains(&i))
+    }
+
+    /// Move the holes of array `idx` to where `f` puts their elements,
+    /// dropping those it maps to None
+    ///
+    /// Array methods that move elements call this so the holes move with
+    /// them; writing an element passes a map that drops just that index.
+    fn remap_array_holes(&mut self, idx: u32, f: impl Fn(usize) -> Option<usize>) {
+        if let Some(holes) = self.array_holes.get_mut(&idx) {
+            *holes = holes.iter().filter_map(|&i| f(i)).collect();
+            if holes.is_empty() {
+                self.array_holes.remove(&idx);
+            }
+        }
+    }
+
+    /// Fill hole `i` of array `idx`, if it is one, now that it is written
+    fn fill_array_hole(&mut self, idx: u32, i: usize) {
+        if let Some(holes) = self.array_holes.get_mut(&idx)
+            && holes.remove(&i)
+            && holes.is_empty()
+        {
+            self.array_holes.remove(&idx);
+        }
+    }
+
+    /// Length of array `idx`, however it is stored
+    fn array_length(&self, idx: u32) -> usize {
+        match self.sparse_arrays.get(&idx) {
+            Some(sparse) => sparse.len,
+            None => self.get_array(idx).map_or(0, Vec::len),
+        }
+    }
+
+    /// Element `i` of array `idx`: undefined for holes and past the end
+    fn array_element(&self, idx: u32, i: usize) -> Value {
+        match self.sparse_arrays.get(&idx) {
+            Some(sparse) => sparse.elements.get(&i).copied(),
+            None => self.get_array(idx).and_then(|arr| arr.get(i)).copied(),
+        }
+        .unwrap_or_default()
+    }
+
+    /// Index and value of every element of array `idx` that is not a hole,
+    /// in index order
+    fn array_entries(&self, idx: u32) -> Vec<(usize, Value)> {
+        if let Some(sparse) = self.sparse_arrays.get(&idx) {
+            return sparse.elements.iter().map(|(&i, &v)| (i, v)).collect();
+        }
+        let holes = self.array_holes.get(&idx);
+        self.get_array(idx)
+            .map(|arr| {
+                arr.iter()
+                    .enumerate()
+                    .filter(|(i, _)| holes.is_none_or(|holes| !holes.contains(i)))
+                    .map(|(i, &v)| (i, v))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Every element of array `idx` in a vector, holes read as undefined
+    ///
+    /// For operations that need the elements side by side. Expanding a
+    /// sparse array this way must fit in the memory budget.
+    fn array_values(&mut self, idx: u32) -> InterpreterResult<Vec<Value>> {
+        let Some(len) = self.sparse_arrays.get(&idx).map(|sparse| sparse.len) else {
+            return Ok(self.get_array(idx).cloned().unwrap_or_default());
+        };
+        self.check_memory(len.saturating_mul(core::mem::size_of::<Value>()))?;
+        Ok((0..len).map(|i| self.array_element(idx, i)).collect())
+    }
+
+    /// Write element `i` of array `idx`, growing the array past it if needed
+    ///
+    /// Filling a hole makes it an element again. Growth that would leave
+    /// the array mostly holes stores it sparsely rather than padding it.
+    fn set_array_element(&mut self, idx: u32, i: usize, val: Value) -> InterpreterResult<()> {
+        if let Some(len) = self.sparse_arrays.get(&idx).map(|sparse| sparse.len) {
+            self.check_array_length(len.max(i.saturating_add(1)))?;
+            let sparse = self.sparse_arrays.entry(idx).or_default();
+            sparse.len = len.max(i + 1);
+            if sparse.elements.insert(i, val).is_none() {
+                self.charge_memory(SPARSE_ENTRY_BYTES);
+            }
+            self.settle_sparse_array(idx);
+            return Ok(());
+        }
+        let Some(len) = self.get_array(idx).map(Vec::len) else {
+            return Ok(());
+        };
+        if i >= len {
+            let new_len = i.saturating_add(1);
+            self.check_array_length(new_len)?;
+            if self.prefers_sparse(idx, len, new_len) {
+                self.make_array_sparse(idx, len);
+                return self.set_array_element(idx, i, val);
+            }
+            self.resize_dense_array(idx, new_len)?;
+            if i > len {
+                self.array_holes.entry(idx).or_default().extend(len..i);
+            }
+        }
+        self.arrays[idx as usize][i] = val;
+        self.fill_array_hole(idx, i);
+        Ok(())
+    }
+
+    /// Whether array `idx`, now `len` long, is better stored sparsely once
+    /// it grows to `new_len`
+    fn prefers_sparse(&self, idx: u32, len: usize, new_len: usize) -> bool {
+        let holes = self.array_holes.get(&idx).map_or(0, BTreeSet::len);
+        let present = len - holes.min(len);
+        new_len > Self::SPARSE_ARRAY_GAP
+            && (present.saturating_mul(4) < new_len
+                || (new_len - len > Self::SPARSE_ARRAY_GAP && present.saturating_mul(2) < new_len))
+    }
+
+    /// Move the elements of array `idx` into sparse storage `len` long
+    fn make_array_sparse(&mut self, idx: u32, len: usize) {
+        let elements: BTreeMap<usize, Value> = self.array_entries(idx).into_iter().collect();
+        self.charge_memory(elements.len() * SPARSE_ENTRY_BYTES);
+        self.arrays[idx as usize] = Vec::new();
+        self.array_holes.remove(&idx);
+        self.sparse_arrays
+            .insert(idx, SparseArray { len, elements });
+    }
+
+    /// Store array `idx` in a vector again if it is sparse, with its
+    /// missing elements as holes
+    ///
+    /// Methods that rearrange elements in place call this first; the
+    /// vector must fit in the memory budget.
+    fn make_array_dense(&mut self, idx: u32) -> InterpreterResult<()> {
+        let Some(len) = self.sparse_arrays.get(&idx).map(|sparse| sparse.len) else {
+            return Ok(());
+        };
+        let value_size = core::mem::size_of::<Value>();
+        self.check_memory(len.saturating_mul(value_size))?;
+        let sparse = self.sparse_arrays.remove(&idx).unwrap_or_default();
+        let mut elements = vec![Value::undefined(); len];
+        for (&i, &val) in &sparse.elements {
+            elements[i] = val;
+        }
+        let holes: BTreeSet<usize> = (0..len)
+            .filter(|i| !sparse.elements.contains_key(i))
+            .collect();
+        if !holes.is_empty() {
+            self.array_holes.insert(idx, holes);
+        }
+        self.charge_memory(elements.capacity() * value_size);
+        self.arrays[idx as usize] = elements;
+        Ok(())
+    }
+
+    /// Go back to a vector for sparse array `idx` once it is short or at
+    /// least half full, if the vector fits in the memory budget
+    fn settle_sparse_array(&mut self, idx: u32) {
+        let Some(sparse) = self.sparse_arrays.get(&idx) else {
+            return;
+        };
+        let (len, present) = (sparse.len, sparse.elements.len());
+        if (len <= Self::SPARSE_ARRAY_GAP || present.saturating_mul(2) >= len)
+            && self.memory_available(len * core::mem::size_of::<Value>())
+        {
+            let _ = self.make_array_dense(idx);
+        }
+    }
+
+    /// Allocate an array `len` long holding `entries`, in index order, with
+    /// holes at the indices they leave out
+    ///
+    /// Like [`Interpreter::alloc_derived_array`], for results that keep the
+    /// holes of the array they were made from; a result that is mostly
+    /// holes is stored sparsely.
+    fn alloc_array_entries(
+        &mut self,
+        len: usize,
+        entries: Vec<(usize, Value)>,
+    ) -> InterpreterResult<Value> {
+        self.check_array_length(len)?;
+        let entries: Vec<(usize, Value)> = entries
+            .into_iter()
+            .map(|(i, v)| {
+                if v.is_iterator() || v.is_for_of_iterator() {
+                    (i, Value::undefined())
+                } else {
+                    (i, v)
+                }
+            })
+            .collect();
+        for &(_, val) in &entries {
+            self.promote(val);
+        }
+        if len > Self::SPARSE_ARRAY_GAP && entries.len().saturating_mul(4) < len {
+            let arr = self.alloc_temp_array(Vec::new());
+            if let Some(idx) = arr.to_array_idx() {
+                self.charge_memory(entries.len() * SPARSE_ENTRY_BYTES);
+                let elements = entries.into_iter().collect();
+                self.sparse_arrays
+                    .insert(idx, SparseArray { len, elements });
+            }
+            return Ok(arr);
+        }
+        self.check_memory(len.saturating_mul(core::mem::size_of::<Value>()))?;
+        let mut elements = vec![Value::undefined(); len];
+        let mut holes = BTreeSet::new();
+        let mut next = 0;
+        for (i, val) in entries {
+            holes.extend(next..i);
+            elements[i] = val;
+            next = i + 1;
+        }
+        holes.extend(next..len);
+        let arr = self.alloc_temp_array(elements);
+        if let Some(idx) = arr.to_array_idx()
+            && !holes.is_empty()
+        {
+            self.array_holes.insert(idx, holes);
+        }
+        Ok(arr)
+    }
+
+    /// Store an object and return its value
+    ///
+    /// Slots the collector reclaimed are reused lowest first.
+    fn push_object(&mut self, object: ObjectInstance) -> Value {
+        self.charge_memory(object_bytes(&object));
+        let idx = match self.freed_objects.pop_first() {
+            Some(idx) => {
+                self.objects[idx as usize] = object;
+                self.finalized_userdata.remove(&idx);
+                idx
+            }
+            None => {
+                self.objects.push(object);
+                (self.objects.len() - 1) as u32
+            }
+        };
+        self.object_generations.bump(idx as usize);
+        self.count_allocation(|t| &mut t.objects);
+        Value::object_idx(idx)
+    }
+
+    /// Create a new object with a constructor reference and return its value
+    fn create_object_with_constructor(&mut self, constructor: Value) -> Value {
+        self.push_object(ObjectInstance::with_constructor(constructor))
+    }
+
+    /// Get an object by index
     fn get_object(&self, idx: u32) -> Option<&ObjectInstance> {
         self.objects.get(idx as usize)
     }
@@ -937,40 +3068,1585 @@ impl Interpreter {
 
     /// Get a property from an object
     fn object_get_property(&self, obj_idx: u32, key: &str) -> Value {
-        if let Some(obj) = self.get_object(obj_idx) {
-            for (k, v) in obj.properties.iter() {
-                if k == key {
-                    return *v;
+        self.get_object(obj_idx)
+            .and_then(|obj| self.named_get(obj, key))
+            .or_else(|| self.object_inherited_property(obj_idx, key))
+            .unwrap_or_default()
+    }
+
+    /// Look up a property an object inherits from its class or Object.prototype
+    fn object_inherited_property(&self, obj_idx: u32, key: &str) -> Option<Value> {
+        if let Some(class) = self.host_class_of(obj_idx)
+            && let Some(&(_, idx)) = self.host_classes[class]
+                .methods
+                .iter()
+                .find(|(name, _)| *name == key)
+        {
+            return Some(Value::native_func(idx));
+        }
+        if self.date_values.contains_key(&obj_idx)
+            && let Some(method) = self.get_native_func(&format!("Date.prototype.{}", key))
+        {
+            return Some(method);
+        }
+        if self.promises.contains_key(&obj_idx)
+            && let Some(method) = self.get_native_func(&format!("Promise.prototype.{}", key))
+        {
+            return Some(method);
+        }
+        for proto in self.prototype_chain(Value::object_idx(obj_idx)) {
+            let found = match PropertyReceiver::of(proto) {
+                PropertyReceiver::Object(idx) => {
+                    self.get_object(idx).and_then(|o| self.named_get(o, key))
                 }
+                PropertyReceiver::Builtin(idx) => {
+                    Some(self.get_builtin_property(idx, key)).filter(|v| !v.is_undefined())
+                }
+                _ => None,
+            };
+            if found.is_some() {
+                return found;
             }
         }
         // Fallback to Object.prototype methods
         match key {
-            "hasOwnProperty" => self
-                .get_native_func("Object.prototype.hasOwnProperty")
-                .unwrap_or_default(),
-            "toString" => self
-                .get_native_func("Object.prototype.toString")
-                .unwrap_or_default(),
-            _ => Value::undefined(),
+            "toString" => self.get_native_func("Object.prototype.toString"),
+            _ => self.object_prototype_method(key),
         }
     }
 
-    /// Set a property on an object
-    fn object_set_property(&mut self, obj_idx: u32, key: String, value: Value) {
-        if let Some(obj) = self.get_object_mut(obj_idx) {
-            // Check if property already exists
-            for (k, v) in obj.properties.iter_mut() {
-                if k == &key {
-                    *v = value;
-                    return;
-                }
+    /// The prototype an object inherits from: the one `Object.create` gave
+    /// it, or its constructor's `prototype`
+    fn prototype_of(&self, val: Value) -> Option<Value> {
+        let obj = self.get_object(val.to_object_idx()?)?;
+        obj.proto
+            .or_else(|| self.constructor_prototype(obj.constructor?))
+    }
+
+    /// The prototypes `val` inherits from, nearest first
+    ///
+    /// Chains longer than [`MAX_PROTOTYPE_DEPTH`] are cut short, which
+    /// also ends cyclic ones.
+    fn prototype_chain(&self, val: Value) -> Vec<Value> {
+        let mut chain = Vec::new();
+        let mut current = val;
+        while chain.len() < MAX_PROTOTYPE_DEPTH
+            && let Some(proto) = self.prototype_of(current)
+        {
+            chain.push(proto);
+            current = proto;
+        }
+        chain
+    }
+
+    /// The `prototype` of a constructor: `Error.prototype` for `Error`, and
+    /// whatever was assigned for script functions
+    fn constructor_prototype(&self, ctor: Value) -> Option<Value> {
+        match ctor.to_builtin_object_idx() {
+            Some(BUILTIN_ERROR) => Some(Value::builtin_object(BUILTIN_ERROR_PROTOTYPE)),
+            Some(_) => None,
+            None => self.function_prototypes.get(&ctor).copied(),
+        }
+    }
+
+    /// The `name` or `message` an object inherits from `Error.prototype`,
+    /// if it inherits from it
+    fn error_prototype_field(&self, val: Value, key: &str) -> Option<&'static str> {
+        let text = match key {
+            "name" => "Error",
+            "message" => "",
+            _ => return None,
+        };
+        self.prototype_chain(val)
+            .contains(&Value::builtin_object(BUILTIN_ERROR_PROTOTYPE))
+            .then_some(text)
+    }
+
+    /// An Object.prototype method every object inherits, whatever its kind
+    fn object_prototype_method(&self, key: &str) -> Option<Value> {
+        match key {
+            "hasOwnProperty" | "propertyIsEnumerable" => {
+                self.get_native_func(&format!("Object.prototype.{}", key))
             }
-            // Add new property
-            obj.properties.push((key, value));
+            _ => None,
         }
     }
 
+    /// Check whether `obj` has the property `key` under the given lookup
+    ///
+    /// Strings answer for their wrapper object: they own their indices and
+    /// length. Numbers, booleans, null and undefined own nothing.
+    pub fn has_property(&self, obj: Value, key: &str, lookup: PropertyLookup) -> bool {
+        let receiver = PropertyReceiver::of(obj);
+        let hidden_ok = lookup != PropertyLookup::OwnEnumerable;
+        let own_builtin = match receiver {
+            PropertyReceiver::Array(idx) => match array_index(key) {
+                Some(i) => {
+                    return i < self.array_length(idx) && !self.is_array_hole(idx, i);
+                }
+                None => hidden_ok && key == "length",
+            },
+            PropertyReceiver::TypedArray(idx) => array_index(key).is_some_and(|i| {
+                i < self
+                    .typed_arrays
+                    .get(idx as usize)
+                    .map_or(0, |ta| ta.length)
+            }),
+            PropertyReceiver::String => match array_index(key) {
+                Some(i) => return i < self.get_string(obj).map_or(0, |s| s.encode_utf16().count()),
+                None => hidden_ok && key == "length",
+            },
+            PropertyReceiver::Error(idx) => {
+                hidden_ok
+                    && match key {
+                        "message" | "stack" => true,
+                        "cause" => self
+                            .error_objects
+                            .get(idx as usize)
+                            .is_some_and(|err| err.cause.is_some()),
+                        _ => false,
+                    }
+            }
+            PropertyReceiver::Function => {
+                hidden_ok
+                    && (matches!(key, "name" | "length")
+                        || key == "prototype" && self.function_prototypes.contains_key(&obj))
+            }
+            // Bindings are enumerable properties of globalThis; builtins,
+            // even when they are undefined, are not
+            PropertyReceiver::Builtin(BUILTIN_GLOBAL_THIS) => {
+                self.global_binding(key).is_some()
+                    || (hidden_ok && self.global_this_property(key).is_some())
+            }
+            PropertyReceiver::Builtin(idx) => {
+                hidden_ok && !self.get_builtin_property(idx, key).is_undefined()
+            }
+            // Lazy and deprecated module entries are not enumerable until read
+            PropertyReceiver::Object(idx) => {
+                hidden_ok
+                    && self
+                        .modules
+                        .get(&idx)
+                        .is_some_and(|module| module.resolves(key))
+            }
+            _ => false,
+        };
+        let own_named = self
+            .named_props(receiver)
+            .zip(self.atoms.lookup(key))
+            .is_some_and(|(o, key)| match lookup {
+                PropertyLookup::OwnEnumerable => o.is_enumerable(key),
+                PropertyLookup::Own | PropertyLookup::Chain => o.has_own(key),
+            });
+        own_builtin
+            || own_named
+            || (lookup == PropertyLookup::Chain && self.has_inherited_property(obj, receiver, key))
+    }
+
+    /// Whether `obj` inherits `key` from its prototype
+    fn has_inherited_property(&self, obj: Value, receiver: PropertyReceiver, key: &str) -> bool {
+        let inherited = match receiver {
+            PropertyReceiver::Object(idx) => {
+                self.object_inherited_property(idx, key).is_some()
+                    || self.host_class_of(idx).is_some_and(|class| {
+                        self.host_classes[class]
+                            .getters
+                            .iter()
+                            .any(|(name, _)| *name == key)
+                    })
+            }
+            PropertyReceiver::Array(_) => !self.get_array_property(obj, key).is_undefined(),
+            PropertyReceiver::Error(_) => matches!(key, "name" | "toString"),
+            PropertyReceiver::RegExp(idx) => {
+                key == "source" || !self.get_regexp_property(idx, key).is_undefined()
+            }
+            PropertyReceiver::TypedArray(idx) => {
+                !self.get_typed_array_property(idx, key).is_undefined()
+            }
+            PropertyReceiver::ArrayBuffer(idx) => {
+                !self.get_array_buffer_property(idx, key).is_undefined()
+            }
+            PropertyReceiver::String => !self.get_string_property(obj, key).is_undefined(),
+            PropertyReceiver::Number => !self.get_number_property(obj, key).is_undefined(),
+            PropertyReceiver::Function => !self.get_function_property(key).is_undefined(),
+            PropertyReceiver::Builtin(_) | PropertyReceiver::Primitive => false,
+        };
+        inherited || (receiver.is_object() && self.object_prototype_method(key).is_some())
+    }
+
+    /// Attributes of the own property `key` of `obj`, or None if it has no
+    /// such property
+    ///
+    /// Properties scripts create are writable and configurable; only their
+    /// enumerability can change, through `Object.defineProperty`. The
+    /// properties the engine provides report what assignment and `delete`
+    /// do to them.
+    fn own_property_attributes(&self, obj: Value, key: &str) -> Option<PropertyAttributes> {
+        if !self.has_property(obj, key, PropertyLookup::Own) {
+            return None;
+        }
+        let enumerable = self.has_property(obj, key, PropertyLookup::OwnEnumerable);
+        let receiver = PropertyReceiver::of(obj);
+        let named = self
+            .named_props(receiver)
+            .zip(self.atoms.lookup(key))
+            .is_some_and(|(o, key)| o.has_own(key));
+        let (writable, configurable) = match receiver {
+            _ if named => (true, true),
+            PropertyReceiver::Array(_) if key == "length" => (true, false),
+            PropertyReceiver::String => (false, false),
+            PropertyReceiver::Function if key == "prototype" => (true, false),
+            PropertyReceiver::Function => (false, true),
+            PropertyReceiver::Builtin(BUILTIN_GLOBAL_THIS) => {
+                (true, !self.declared_globals.contains(key))
+            }
+            PropertyReceiver::Builtin(_) => (!self.hardened, false),
+            _ => (true, true),
+        };
+        Some(PropertyAttributes {
+            writable,
+            enumerable,
+            configurable,
+        })
+    }
+
+    /// Property names of `obj` under the given lookup, in enumeration order
+    ///
+    /// Integer keys come first in ascending order, then the other keys in
+    /// insertion order; deleted array elements are not listed. `Chain` lists
+    /// what for-in visits: inherited builtin properties are never
+    /// enumerable, so it matches `OwnEnumerable`. Builtin objects such as
+    /// `Math` list nothing, except globalThis, which lists the global
+    /// bindings scripts and the host created.
+    pub fn property_keys(&self, obj: Value, lookup: PropertyLookup) -> Vec<String> {
+        let enumerable_only = lookup != PropertyLookup::Own;
+        let receiver = PropertyReceiver::of(obj);
+        let indices = |len: usize| (0..len).map(|i| i.to_string());
+        let mut keys: Vec<String> = match receiver {
+            PropertyReceiver::Array(idx) => self
+                .array_entries(idx)
+                .into_iter()
+                .map(|(i, _)| i.to_string())
+                .collect(),
+            PropertyReceiver::TypedArray(idx) => indices(
+                self.typed_arrays
+                    .get(idx as usize)
+                    .map_or(0, |ta| ta.length),
+            )
+            .collect(),
+            PropertyReceiver::String => {
+                indices(self.get_string(obj).map_or(0, |s| s.encode_utf16().count())).collect()
+            }
+            PropertyReceiver::Builtin(BUILTIN_GLOBAL_THIS) => {
+                let mut names: Vec<String> =
+                    self.globals.iter().map(|(name, _)| name.clone()).collect();
+                for (name, _) in self.running_script_bindings() {
+                    if !names.iter().any(|n| n == name) {
+                        names.push(name.to_string());
+                    }
+                }
+                names
+            }
+            _ => Vec::new(),
+        };
+        if !enumerable_only {
+            match receiver {
+                PropertyReceiver::Array(_) | PropertyReceiver::String => {
+                    keys.push("length".to_string())
+                }
+                PropertyReceiver::Error(_) => {
+                    keys.extend(["stack", "message"].map(String::from));
+                }
+                PropertyReceiver::Function => {
+                    keys.extend(["length", "name"].map(String::from));
+                }
+                _ => {}
+            }
+        }
+        if let Some(named) = self.named_props(receiver) {
+            // Integer keys come first, in ascending order
+            let (mut numeric, named): (Vec<_>, Vec<_>) = named
+                .own_keys(enumerable_only)
+                .map(|k| self.atoms.name(k))
+                .partition(|k| array_index(k).is_some());
+            numeric.sort_by_key(|k| array_index(k));
+            keys.extend(numeric.into_iter().chain(named).map(str::to_string));
+        }
+        keys
+    }
+
+    /// Storage for the named properties of `receiver`, if it has any yet
+    fn named_props(&self, receiver: PropertyReceiver) -> Option<&ObjectInstance> {
+        match receiver {
+            PropertyReceiver::Object(idx) => self.get_object(idx),
+            PropertyReceiver::Array(idx) => self.array_props.get(&idx),
+            PropertyReceiver::Error(_)
+            | PropertyReceiver::RegExp(_)
+            | PropertyReceiver::TypedArray(_)
+            | PropertyReceiver::ArrayBuffer(_) => self.exotic_props.get(&receiver),
+            _ => None,
+        }
+    }
+
+    /// Get the property `key` of `obj`, as `obj.key` and `obj[key]` do
+    ///
+    /// Own named properties shadow what the value's kind defines itself.
+    fn get_property(&mut self, obj: Value, key: &str) -> InterpreterResult<Value> {
+        if self.hardened {
+            self.check_hardened_access(obj, key, false)?;
+            if key == "constructor" {
+                return Ok(Value::undefined());
+            }
+        }
+        let receiver = PropertyReceiver::of(obj);
+        let shadowing = match receiver {
+            PropertyReceiver::Error(_)
+            | PropertyReceiver::RegExp(_)
+            | PropertyReceiver::TypedArray(_)
+            | PropertyReceiver::ArrayBuffer(_) => self
+                .exotic_props
+                .get(&receiver)
+                .and_then(|o| self.named_get(o, key)),
+            _ => None,
+        };
+        if let Some(val) = shadowing {
+            return Ok(val);
+        }
+        let val = match receiver {
+            PropertyReceiver::Object(idx) => return self.get_object_field(idx, key),
+            PropertyReceiver::Array(idx) => match array_index(key) {
+                Some(i) => self.array_element(idx, i),
+                None => self.get_array_property(obj, key),
+            },
+            PropertyReceiver::TypedArray(idx) => match array_index(key) {
+                Some(i) => self
+                    .typed_arrays
+                    .get(idx as usize)
+                    .and_then(|ta| ta.get(i))
+                    .map(Value::int)
+                    .unwrap_or_default(),
+                None => self.get_typed_array_property(idx, key),
+            },
+            PropertyReceiver::Error(idx) => self.get_error_property(idx, key),
+            PropertyReceiver::RegExp(idx) => self.get_regexp_property(idx, key),
+            PropertyReceiver::ArrayBuffer(idx) => self.get_array_buffer_property(idx, key),
+            PropertyReceiver::Builtin(BUILTIN_ERROR_PROTOTYPE)
+                if matches!(key, "name" | "message") =>
+            {
+                self.alloc_string(if key == "name" { "Error" } else { "" })
+            }
+            PropertyReceiver::Builtin(idx) => self.get_builtin_property(idx, key),
+            PropertyReceiver::String => match array_index(key) {
+                Some(i) => {
+                    let unit = self.get_string(obj).and_then(|s| s.encode_utf16().nth(i));
+                    match unit {
+                        Some(unit) => self.alloc_string(&code_unit_string(unit)),
+                        None => Value::undefined(),
+                    }
+                }
+                None => self.get_string_property(obj, key),
+            },
+            PropertyReceiver::Number => self.get_number_property(obj, key),
+            PropertyReceiver::Function => match key {
+                "name" => {
+                    let name = self.function_metadata(obj).unwrap_or_default().0;
+                    self.alloc_string(&name)
+                }
+                "length" => {
+                    let length = self.function_metadata(obj).map_or(0, |(_, len)| len);
+                    Value::int_saturating(length as i64)
+                }
+                "prototype" => self
+                    .function_prototypes
+                    .get(&obj)
+                    .copied()
+                    .unwrap_or_default(),
+                _ => self.get_function_property(key),
+            },
+            PropertyReceiver::Primitive => Value::undefined(),
+        };
+        if val.is_undefined()
+            && receiver.is_object()
+            && let Some(method) = self.object_prototype_method(key)
+        {
+            return Ok(method);
+        }
+        Ok(val)
+    }
+
+    /// Copy the own enumerable properties of `source` onto `target` in
+    /// enumeration order, except those named in `excluded`
+    ///
+    /// Shared by `Object.assign`, object spread and object rest. Arrays and
+    /// strings contribute their indices; null, undefined and other
+    /// primitives contribute nothing.
+    fn copy_data_properties(
+        &mut self,
+        target: Value,
+        source: Value,
+        excluded: &[String],
+    ) -> InterpreterResult<()> {
+        for key in self.property_keys(source, PropertyLookup::OwnEnumerable) {
+            if excluded.contains(&key) {
+                continue;
+            }
+            let val = self.get_property(source, &key)?;
+            self.promote(val);
+            self.set_property(target, &key, val)?;
+        }
+        Ok(())
+    }
+
+    /// Set the property `key` of `obj`, as `obj.key = val` and `obj[key] = val` do
+    ///
+    /// Writes to primitives, builtin objects and read-only accessors such
+    /// as a typed array's `length` are ignored, except that a hardened
+    /// interpreter rejects writes to builtin objects and to `__proto__`.
+    /// Writes to globalThis create or update the global binding.
+    fn set_property(&mut self, obj: Value, key: &str, val: Value) -> InterpreterResult<()> {
+        if self.hardened {
+            self.check_hardened_access(obj, key, true)?;
+        }
+        let receiver = PropertyReceiver::of(obj);
+        match receiver {
+            PropertyReceiver::Object(idx) => {
+                let key = self.atom(key);
+                self.object_set_property(idx, key, val);
+            }
+            PropertyReceiver::Array(idx) => {
+                if let Some(i) = array_index(key) {
+                    self.set_array_element(idx, i, val)?;
+                } else if key == "length" {
+                    // Writing length truncates or pads the array
+                    if let Some(new_len) = val.to_i32().filter(|n| *n >= 0) {
+                        self.resize_array(idx, new_len as usize)?;
+                    }
+                } else {
+                    self.array_set_named(idx, key, val);
+                }
+            }
+            PropertyReceiver::TypedArray(idx) => {
+                if let Some(i) = array_index(key) {
+                    // Writes past the end are dropped rather than growing it
+                    if let Some(ta) = self.typed_arrays.get_mut(idx as usize) {
+                        ta.set(i, val.to_i32().unwrap_or(0));
+                    }
+                } else if !numeric_key(key)
+                    && !matches!(key, "length" | "byteLength" | "BYTES_PER_ELEMENT")
+                {
+                    self.exotic_set(receiver, key, val);
+                }
+            }
+            PropertyReceiver::Error(idx) if matches!(key, "name" | "message") => {
+                let text = self
+                    .get_string(val)
+                    .map(|s| s.to_string())
+                    .or_else(|| self.value_to_string(&val));
+                if let Some(text) = text
+                    && let Some(err) = self.error_objects.get_mut(idx as usize)
+                {
+                    if key == "name" {
+                        err.name = text;
+                    } else {
+                        err.message = text;
+                    }
+                }
+            }
+            PropertyReceiver::RegExp(_)
+                if matches!(key, "global" | "ignoreCase" | "multiline" | "source") => {}
+            PropertyReceiver::ArrayBuffer(_) if key == "byteLength" => {}
+            PropertyReceiver::Error(_)
+            | PropertyReceiver::RegExp(_)
+            | PropertyReceiver::ArrayBuffer(_) => self.exotic_set(receiver, key, val),
+            PropertyReceiver::Function if key == "prototype" => {
+                self.function_prototypes.insert(obj, val);
+            }
+            PropertyReceiver::Builtin(BUILTIN_GLOBAL_THIS) => self.set_global_binding(key, val),
+            PropertyReceiver::Builtin(_)
+            | PropertyReceiver::String
+            | PropertyReceiver::Number
+            | PropertyReceiver::Function
+            | PropertyReceiver::Primitive => {}
+        }
+        Ok(())
+    }
+
+    /// Set a named property on a value without property storage of its own
+    fn exotic_set(&mut self, receiver: PropertyReceiver, key: &str, val: Value) {
+        let key = self.atom(key);
+        if self.exotic_props.entry(receiver).or_default().set(key, val) {
+            self.charge_memory(PROPERTY_BYTES);
+        }
+    }
+
+    /// Delete the property `key` of `obj`, as `delete obj[key]` does
+    ///
+    /// Returns false only for properties that cannot be deleted: array and
+    /// string lengths, string and typed array indices, and builtin object
+    /// members. Deleting an array index leaves undefined in its place.
+    fn delete_property(&mut self, obj: Value, key: &str) -> bool {
+        let receiver = PropertyReceiver::of(obj);
+        match receiver {
+            PropertyReceiver::Object(idx) => {
+                if let Some(key) = self.atoms.lookup(key)
+                    && let Some(obj) = self.get_object_mut(idx)
+                {
+                    obj.remove(key);
+                }
+                true
+            }
+            PropertyReceiver::Array(idx) => {
+                if let Some(i) = array_index(key) {
+                    if let Some(sparse) = self.sparse_arrays.get_mut(&idx) {
+                        sparse.elements.remove(&i);
+                    } else if let Some(slot) = self.get_array_mut(idx).and_then(|a| a.get_mut(i)) {
+                        *slot = Value::undefined();
+                        self.array_holes.entry(idx).or_default().insert(i);
+                    }
+                    true
+                } else if key == "length" {
+                    false
+                } else {
+                    self.array_delete_named(idx, key);
+                    true
+                }
+            }
+            PropertyReceiver::Error(idx) if key == "message" => {
+                // The inherited Error.prototype.message is empty
+                if let Some(err) = self.error_objects.get_mut(idx as usize) {
+                    err.message.clear();
+                }
+                true
+            }
+            PropertyReceiver::TypedArray(_)
+            | PropertyReceiver::String
+            | PropertyReceiver::Builtin(_)
+                if self.has_property(obj, key, PropertyLookup::Own)
+                    && self
+                        .named_props(receiver)
+                        .is_none_or(|o| self.named_get(o, key).is_none()) =>
+            {
+                false
+            }
+            PropertyReceiver::Error(_)
+            | PropertyReceiver::RegExp(_)
+            | PropertyReceiver::TypedArray(_)
+            | PropertyReceiver::ArrayBuffer(_) => {
+                if let Some(key) = self.atoms.lookup(key)
+                    && let Some(props) = self.exotic_props.get_mut(&receiver)
+                {
+                    props.remove(key);
+                }
+                true
+            }
+            PropertyReceiver::Builtin(_)
+            | PropertyReceiver::String
+            | PropertyReceiver::Number
+            | PropertyReceiver::Function
+            | PropertyReceiver::Primitive => true,
+        }
+    }
+
+    /// The property key a computed member expression names: strings name
+    /// themselves, numbers, booleans, null and undefined their string form
+    ///
+    /// Objects and functions name nothing, which reads as undefined.
+    fn property_key(&self, key: Value, bytecode: &FunctionBytecode) -> Option<String> {
+        if key.is_string() {
+            self.get_string_content(key, bytecode)
+                .map(|s| s.to_string())
+        } else if let Some(n) = key.to_i32() {
+            Some(n.to_string())
+        } else if let Some(b) = key.to_bool() {
+            Some(b.to_string())
+        } else if key.is_null() {
+            Some("null".to_string())
+        } else if key.is_undefined() {
+            Some("undefined".to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Get `obj[key]`, indexing arrays and typed arrays directly
+    fn get_element(
+        &mut self,
+        obj: Value,
+        key: Value,
+        bytecode: &FunctionBytecode,
+    ) -> InterpreterResult<Value> {
+        if let Some(i) = key.to_i32().and_then(|i| usize::try_from(i).ok()) {
+            match PropertyReceiver::of(obj) {
+                PropertyReceiver::Array(idx) => return Ok(self.array_element(idx, i)),
+                PropertyReceiver::TypedArray(idx) => {
+                    return Ok(self
+                        .typed_arrays
+                        .get(idx as usize)
+                        .and_then(|ta| ta.get(i))
+                        .map(Value::int)
+                        .unwrap_or_default());
+                }
+                _ => {}
+            }
+        }
+        match self.property_key(key, bytecode) {
+            Some(key) => self.get_property(obj, &key),
+            None => Ok(Value::undefined()),
+        }
+    }
+
+    /// Set `obj[key] = val`
+    fn set_element(
+        &mut self,
+        obj: Value,
+        key: Value,
+        val: Value,
+        bytecode: &FunctionBytecode,
+    ) -> InterpreterResult<()> {
+        match self.property_key(key, bytecode) {
+            Some(key) => self.set_property(obj, &key, val),
+            None => Ok(()),
+        }
+    }
+
+    /// The atom for string constant `str_idx` of `bytecode`, as the field
+    /// opcodes name properties
+    ///
+    /// Loaded scripts have theirs resolved up front; other bytecode interns
+    /// the name on the spot.
+    fn field_atom(
+        &mut self,
+        bytecode: &FunctionBytecode,
+        str_idx: usize,
+    ) -> InterpreterResult<Atom> {
+        if let Some(&atom) = bytecode.atoms.get(str_idx) {
+            return Ok(atom);
+        }
+        let name = bytecode.string_constants.get(str_idx).ok_or_else(|| {
+            InterpreterError::InternalError(format!("invalid string index: {}", str_idx))
+        })?;
+        Ok(self.atom(name))
+    }
+
+    /// Get `obj.key` for the field opcodes
+    ///
+    /// Own properties of plain objects are read by atom; everything else,
+    /// and every read while hardened, goes through
+    /// [`get_property`](Self::get_property) by name.
+    fn get_field(&mut self, obj: Value, key: Atom) -> InterpreterResult<Value> {
+        if !self.hardened
+            && let Some(val) = obj
+                .to_object_idx()
+                .and_then(|idx| self.get_object(idx))
+                .and_then(|o| o.get(key))
+        {
+            return Ok(val);
+        }
+        let name = self.atoms.shared_name(key);
+        self.get_property(obj, &name)
+    }
+
+    /// Get a property of a plain object, running host class getters and
+    /// resolving pending module entries
+    fn get_object_field(&mut self, obj_idx: u32, key: &str) -> InterpreterResult<Value> {
+        if !self.modules.is_empty()
+            && self
+                .get_object(obj_idx)
+                .is_none_or(|obj| self.named_get(obj, key).is_none())
+            && let Some(val) = self.module_property(obj_idx, key)?
+        {
+            return Ok(val);
+        }
+        if let Some(class) = self.host_class_of(obj_idx)
+            && let Some(getter) = self.host_classes[class]
+                .getters
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, getter)| Rc::clone(getter))
+            && self
+                .get_object(obj_idx)
+                .is_none_or(|obj| self.named_get(obj, key).is_none())
+        {
+            return self
+                .with_host_value(class, obj_idx, |interp, value| Ok(getter(interp, &*value)));
+        }
+        let val = self.object_get_property(obj_idx, key);
+        if val.is_undefined()
+            && let Some(text) = self.error_prototype_field(Value::object_idx(obj_idx), key)
+        {
+            return Ok(self.alloc_string(text));
+        }
+        Ok(val)
+    }
+
+    /// Set a property on an object
+    fn object_set_property(&mut self, obj_idx: u32, key: Atom, value: Value) {
+        if self
+            .get_object_mut(obj_idx)
+            .is_some_and(|obj| obj.set(key, value))
+        {
+            self.charge_memory(PROPERTY_BYTES);
+        }
+    }
+
+    /// Create an object wrapping a host value
+    ///
+    /// The optional finalizer receives the value back when the object is
+    /// found unreachable by [`Interpreter::collect_garbage`].
+    pub fn create_userdata<T: 'static>(
+        &mut self,
+        value: T,
+        finalizer: Option<Box<dyn FnOnce(T)>>,
+    ) -> Value {
+        let obj = self.alloc_object().finish();
+        let finalizer = finalizer.map(|f| -> ErasedFinalizer {
+            Box::new(move |value: Box<dyn Any>| {
+                if let Ok(value) = value.downcast::<T>() {
+                    f(*value);
+                }
+            })
+        });
+        if let Some(idx) = obj.to_object_idx() {
+            self.userdata.insert(
+                idx,
+                UserDataSlot {
+                    value: Box::new(value),
+                    finalizer,
+                },
+            );
+        }
+        obj
+    }
+
+    /// Register a class scripts can instantiate with `new`
+    ///
+    /// Returns the class value; publish it with `set_global` to make it
+    /// visible to scripts. Instances are userdata objects whose methods and
+    /// getters come from `def`, and `instanceof` the class value holds for them.
+    pub fn register_class<T: 'static>(&mut self, name: &'static str, def: ClassDef<T>) -> Value {
+        let class = self.host_classes.len();
+        let class_idx = self.register_native(name, native_host_class, 0);
+        self.host_natives
+            .insert(class_idx, HostNative::Constructor(class));
+
+        let ClassDef {
+            constructor,
+            methods,
+            getters,
+            finalizer,
+        } = def;
+        let constructor: HostConstructor =
+            Rc::new(move |interp: &mut Interpreter, args: &[Value]| {
+                constructor(NativeScope::of_mut(interp), args)
+                    .map(|value| Box::new(value) as Box<dyn Any>)
+            });
+        let methods = methods
+            .into_iter()
+            .map(|(method_name, method)| {
+                let idx = self.register_native(method_name, native_host_class, 0);
+                let erased = Rc::new(
+                    move |interp: &mut Interpreter, value: &mut dyn Any, args: &[Value]| match value
+                        .downcast_mut::<T>()
+                    {
+                        Some(value) => method(NativeScope::of_mut(interp), value, args),
+                        None => Err(HostError::Type(format!(
+                            "{} is not a {}",
+                            method_name, name
+                        ))),
+                    },
+                );
+                self.host_natives
+                    .insert(idx, HostNative::Method(class, erased));
+                (method_name, idx)
+            })
+            .collect();
+        let getters = getters
+            .into_iter()
+            .map(|(getter_name, getter)| {
+                let erased = Rc::new(move |interp: &mut Interpreter, value: &dyn Any| {
+                    value
+                        .downcast_ref::<T>()
+                        .map_or_else(Value::undefined, |value| {
+                            getter(NativeScope::of_mut(interp), value)
+                        })
+                });
+                (getter_name, erased as _)
+            })
+            .collect();
+        let finalizer = finalizer.map(|finalizer| {
+            Rc::new(move |value: Box<dyn Any>| {
+                if let Ok(value) = value.downcast::<T>() {
+                    finalizer(*value);
+                }
+            }) as _
+        });
+
+        self.host_classes.push(HostClass {
+            name,
+            constructor,
+            methods,
+            getters,
+            finalizer,
+        });
+        Value::native_func(class_idx)
+    }
+
+    /// Register a module of native functions
+    ///
+    /// Returns the module object; publish it with `set_global` to make it
+    /// visible to scripts. Eager functions are registered now, as natives
+    /// named `module.function`; lazy ones when a script first reads them.
+    pub fn register_module(&mut self, def: ModuleBuilder) -> Value {
+        let ModuleBuilder {
+            name,
+            functions,
+            lazy,
+            deprecated,
+        } = def;
+        let mut module = self.alloc_object();
+        for (func_name, func, arity) in functions {
+            let idx = module.interp.push_native(
+                format!("{}.{}", name, func_name),
+                func,
+                arity,
+                Receiver::Optional,
+            );
+            module = module.prop(func_name, Value::native_func(idx));
+        }
+        let module = module.finish();
+        if let Some(idx) = module.to_object_idx()
+            && !(lazy.is_empty() && deprecated.is_empty())
+        {
+            let deprecated = deprecated
+                .into_iter()
+                .map(|(old, new)| (old, new, false))
+                .collect();
+            self.modules.insert(
+                idx,
+                PendingModule {
+                    name,
+                    lazy,
+                    deprecated,
+                },
+            );
+        }
+        module
+    }
+
+    /// Read `key` of a module object through its pending entries, or None
+    /// if the module has no pending entry of that name
+    ///
+    /// A lazy function is registered and stored on the module, so later
+    /// reads find it as an ordinary property. A deprecated name warns the
+    /// first time and then reads its replacement.
+    fn module_property(&mut self, obj_idx: u32, key: &str) -> InterpreterResult<Option<Value>> {
+        let Some(module) = self.modules.get_mut(&obj_idx) else {
+            return Ok(None);
+        };
+        let module_name = module.name;
+        if let Some(pos) = module.lazy.iter().position(|(name, _)| *name == key) {
+            let (func_name, init) = module.lazy.swap_remove(pos);
+            let idx = self.push_native(
+                format!("{}.{}", module_name, func_name),
+                init(),
+                0,
+                Receiver::Optional,
+            );
+            let func = Value::native_func(idx);
+            let atom = self.atom(func_name);
+            self.object_set_property(obj_idx, atom, func);
+            return Ok(Some(func));
+        }
+        let Some((_, replacement, warned)) =
+            module.deprecated.iter_mut().find(|(name, ..)| *name == key)
+        else {
+            return Ok(None);
+        };
+        let replacement = *replacement;
+        if !core::mem::replace(warned, true) {
+            let warning = format!(
+                "DeprecationWarning: {0}.{1} is deprecated, use {0}.{2} instead",
+                module_name, key, replacement
+            );
+            self.write_console(ConsoleLevel::Warn, &warning);
+        }
+        self.get_property(Value::object_idx(obj_idx), replacement)
+            .map(Some)
+    }
+
+    /// The host class an object was constructed from, if any
+    fn host_class_of(&self, obj_idx: u32) -> Option<usize> {
+        if self.host_classes.is_empty() {
+            return None;
+        }
+        let ctor = self.get_object(obj_idx)?.constructor?;
+        match self.host_natives.get(&ctor.to_native_func_idx()?)? {
+            HostNative::Constructor(class) => Some(*class),
+            HostNative::Method(..) => None,
+        }
+    }
+
+    /// Run `new` on a host class: build the host value and wrap it
+    fn construct_host_object(
+        &mut self,
+        class: usize,
+        class_val: Value,
+        args: &[Value],
+    ) -> InterpreterResult<Value> {
+        let constructor = Rc::clone(&self.host_classes[class].constructor);
+        let value = self.catch_native_panic(|interp| {
+            constructor(interp, args).map_err(|msg| interp.native_error(msg))
+        })?;
+        let finalizer = self.host_classes[class]
+            .finalizer
+            .clone()
+            .map(|finalizer| -> ErasedFinalizer { Box::new(move |value| finalizer(value)) });
+        let obj = self.create_object_with_constructor(class_val);
+        if let Some(idx) = obj.to_object_idx() {
+            self.userdata.insert(idx, UserDataSlot { value, finalizer });
+        }
+        Ok(obj)
+    }
+
+    /// Call a host class constructor or method registered as a native
+    fn call_host_native(
+        &mut self,
+        host: HostNative,
+        this: Value,
+        args: &[Value],
+    ) -> InterpreterResult<Value> {
+        match host {
+            HostNative::Constructor(class) => Err(InterpreterError::TypeError(format!(
+                "Class constructor {} cannot be invoked without 'new'",
+                self.host_classes[class].name
+            ))),
+            HostNative::Method(class, method) => {
+                let obj_idx = this
+                    .to_object_idx()
+                    .filter(|&idx| self.host_class_of(idx) == Some(class))
+                    .ok_or_else(|| {
+                        InterpreterError::TypeError(format!(
+                            "{} method called on incompatible receiver",
+                            self.host_classes[class].name
+                        ))
+                    })?;
+                self.with_host_value(class, obj_idx, |interp, value| method(interp, value, args))
+            }
+        }
+    }
+
+    /// Lend the host value of a class instance to `f`
+    ///
+    /// The value is taken out of its slot for the duration of the call so
+    /// `f` can re-enter the interpreter; a nested access to the same
+    /// instance meanwhile fails with a TypeError.
+    fn with_host_value(
+        &mut self,
+        class: usize,
+        obj_idx: u32,
+        f: impl FnOnce(&mut Self, &mut dyn Any) -> Result<Value, HostError>,
+    ) -> InterpreterResult<Value> {
+        let Some(mut slot) = self.userdata.remove(&obj_idx) else {
+            return Err(InterpreterError::TypeError(format!(
+                "{} object is already in use or has been finalized",
+                self.host_classes[class].name
+            )));
+        };
+        // Catching a panic here rather than in the caller puts the value
+        // back in its slot
+        let result = self.catch_native_panic(|interp| {
+            f(interp, slot.value.as_mut()).map_err(|msg| interp.native_error(msg))
+        });
+        self.userdata.insert(obj_idx, slot);
+        result
+    }
+
+    /// Borrow the host value of a userdata object, if it is live and of type `T`
+    pub fn get_userdata<T: 'static>(&self, val: Value) -> Option<&T> {
+        self.userdata
+            .get(&val.to_object_idx()?)?
+            .value
+            .downcast_ref::<T>()
+    }
+
+    /// Whether a value still refers to something that has not been reclaimed
+    ///
+    /// Objects, userdata slots, array storage, closures and runtime strings
+    /// are reclaimed; every other value stays alive for the lifetime of the
+    /// interpreter. A reclaimed object's, array's, closure's or string's
+    /// slot may be reused, after which the stale value names the new one;
+    /// compare [`Interpreter::slot_generation`] to tell them apart.
+    pub fn is_live(&self, val: Value) -> bool {
+        if let Some(idx) = val.to_array_idx() {
+            return (idx as usize) < self.arrays.len() && !self.freed_arrays.contains(&idx);
+        }
+        if let Some(idx) = val.to_closure_idx() {
+            return (idx as usize) < self.closures.len() && !self.freed_closures.contains(&idx);
+        }
+        if let Some(idx) = val.to_string_idx()
+            && let Some(slot) = idx.checked_sub(Self::RUNTIME_STRING_OFFSET)
+        {
+            return (slot as usize) < self.runtime_strings.len()
+                && !self.freed_strings.contains(&slot);
+        }
+        val.to_object_idx().is_none_or(|idx| {
+            (idx as usize) < self.objects.len()
+                && !self.freed_objects.contains(&idx)
+                && !self.finalized_userdata.contains(&idx)
+        })
+    }
+
+    /// How many times the slot `val` names has been allocated
+    ///
+    /// Changes whenever a reclaimed object, array, closure or runtime
+    /// string slot is reused; zero for values that do not live in such a
+    /// slot.
+    pub(crate) fn slot_generation(&self, val: Value) -> u32 {
+        if let Some(idx) = val.to_object_idx() {
+            return self.object_generations.get(idx as usize);
+        }
+        if let Some(idx) = val.to_array_idx() {
+            return self.array_generations.get(idx as usize);
+        }
+        if let Some(idx) = val.to_closure_idx() {
+            return self.closure_generations.get(idx as usize);
+        }
+        match val
+            .to_string_idx()
+            .and_then(|idx| idx.checked_sub(Self::RUNTIME_STRING_OFFSET))
+        {
+            Some(slot) => self.string_generations.get(slot as usize),
+            None => 0,
+        }
+    }
+
+    /// Root `value` until the returned handle is dropped
+    ///
+    /// The collector keeps what it names, and everything reachable from
+    /// it, even when no script references it any more.
+    pub fn persist(&mut self, value: Value) -> PersistentValue {
+        self.promote(value);
+        PersistentValue::new(value, &self.host_roots)
+    }
+
+    /// Statistics gathered by [`Interpreter::collect_garbage`]
+    pub fn gc_stats(&self) -> GcStats {
+        self.gc_stats
+    }
+
+    /// Install a callback invoked at the start and end of each collection
+    pub fn set_gc_callback(&mut self, callback: GcCallback) {
+        self.gc_callback = Some(callback);
+    }
+
+    /// Estimated bytes held by arrays, objects, property keys, closures,
+    /// runtime strings, error objects and binary storage
+    fn heap_bytes(&self) -> usize {
+        let value_size = core::mem::size_of::<Value>();
+        let arrays: usize = self
+            .arrays
+            .iter()
+            .map(|a| a.capacity() * value_size)
+            .sum::<usize>()
+            + self
+                .sparse_arrays
+                .values()
+                .map(|sparse| sparse.elements.len() * SPARSE_ENTRY_BYTES)
+                .sum::<usize>();
+        let array_props: usize = self
+            .array_props
+            .values()
+            .chain(self.exotic_props.values())
+            .map(object_bytes)
+            .sum();
+        let objects: usize = self.objects.iter().map(object_bytes).sum();
+        let closures: usize = self
+            .closures
+            .iter()
+            .map(|c| c.var_refs.capacity() * value_size)
+            .sum();
+        let strings: usize = self.runtime_strings.iter().map(|s| s.capacity()).sum();
+        let errors: usize = self
+            .error_objects
+            .iter()
+            .map(|e| e.name.capacity() + e.message.capacity())
+            .sum();
+        let performance_entries: usize = self
+            .performance_entries
+            .iter()
+            .map(performance_entry_bytes)
+            .sum();
+        arrays
+            + array_props
+            + objects
+            + self.atoms.bytes()
+            + closures
+            + strings
+            + errors
+            + performance_entries
+            + self.binary_memory_used
+            + self.script_bytes
+    }
+
+    /// Reclaim unreachable objects, userdata slots, array storage, closures
+    /// and runtime strings
+    ///
+    /// Reachability is traced from the value stack, call frames, globals
+    /// and pending timers through arrays, objects, closures and iterators.
+    /// Promises and host modules are never reclaimed.
+    /// Values held only by the host are not roots. Each userdata finalizer
+    /// runs exactly once. Returns the number of userdata slots reclaimed.
+    pub fn collect_garbage(&mut self) -> usize {
+        if let Some(callback) = self.gc_callback.as_mut() {
+            callback(GcPhase::Start);
+        }
+        let started = self.clock.now_us();
+        let bytes_before = self.heap_bytes();
+
+        let mut marked_objects = vec![false; self.objects.len()];
+        let mut marked_arrays = vec![false; self.arrays.len()];
+        let mut marked_closures = vec![false; self.closures.len()];
+        let mut marked_iterators = vec![false; self.for_of_iterators.len()];
+        let mut marked_strings = vec![false; self.runtime_strings.len()];
+        // Interned strings back the literals of loaded scripts, which live
+        // as long as the interpreter
+        for &idx in self.interned_strings.values() {
+            if let Some(flag) = marked_strings.get_mut((idx - Self::RUNTIME_STRING_OFFSET) as usize)
+            {
+                *flag = true;
+            }
+        }
+
+        let mut worklist: Vec<Value> = self.stack.values().to_vec();
+        for frame in &self.call_stack {
+            worklist.push(frame.this_val);
+            worklist.push(frame.this_func);
+            if let Some(idx) = frame.closure_idx {
+                worklist.push(Value::closure_idx(idx as u32));
+            }
+        }
+        worklist.extend(self.globals.iter().map(|(_, v)| *v));
+        worklist.extend(self.host_roots.borrow().values());
+        worklist.extend(self.native_exception);
+        worklist.extend(self.uncaught_exception);
+        // Errors, regexps and typed arrays are never reclaimed, so whatever
+        // their named properties and error causes hold stays reachable
+        worklist.extend(
+            self.exotic_props
+                .values()
+                .flat_map(|props| props.properties.iter().map(|(_, v)| *v)),
+        );
+        worklist.extend(self.error_objects.iter().filter_map(|err| err.cause));
+        // Functions other than closures are never reclaimed either
+        worklist.extend(
+            self.function_prototypes
+                .iter()
+                .filter(|(func, _)| !func.is_closure())
+                .map(|(_, proto)| *proto),
+        );
+        worklist.extend(
+            self.timers
+                .iter()
+                .filter(|t| !t.cancelled)
+                .map(|t| t.callback),
+        );
+        for state in self.promises.values() {
+            match state {
+                PromiseState::Pending(reactions) => worklist.extend(
+                    reactions
+                        .iter()
+                        .flat_map(|r| [r.on_fulfilled, r.on_rejected, r.derived]),
+                ),
+                PromiseState::Fulfilled(val) | PromiseState::Rejected(val) => worklist.push(*val),
+            }
+        }
+        worklist.extend(
+            self.promise_jobs
+                .iter()
+                .flat_map(|job| [job.handler, job.argument, job.derived]),
+        );
+        // Resolver handles and module tables name their objects by index
+        worklist.extend(
+            self.promises
+                .keys()
+                .chain(self.modules.keys())
+                .map(|&idx| Value::object_idx(idx)),
+        );
+
+        fn mark(flags: &mut [bool], idx: u32) -> bool {
+            match flags.get_mut(idx as usize) {
+                Some(flag) if !*flag => {
+                    *flag = true;
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        while let Some(val) = worklist.pop() {
+            if let Some(idx) = val.to_object_idx() {
+                if mark(&mut marked_objects, idx) {
+                    let obj = &self.objects[idx as usize];
+                    worklist.extend(obj.properties.iter().map(|(_, v)| *v));
+                    worklist.extend(obj.constructor);
+                    worklist.extend(obj.proto);
+                }
+            } else if let Some(idx) = val.to_array_idx() {
+                if mark(&mut marked_arrays, idx) {
+                    worklist.extend_from_slice(&self.arrays[idx as usize]);
+                    if let Some(sparse) = self.sparse_arrays.get(&idx) {
+                        worklist.extend(sparse.elements.values());
+                    }
+                    if let Some(props) = self.array_props.get(&idx) {
+                        worklist.extend(props.properties.iter().map(|(_, v)| *v));
+                    }
+                }
+            } else if let Some(idx) = val.to_closure_idx() {
+                if mark(&mut marked_closures, idx) {
+                    worklist.extend_from_slice(&self.closures[idx as usize].var_refs);
+                    worklist.extend(self.function_prototypes.get(&val));
+                }
+            } else if let Some(idx) = val.to_for_of_iterator_idx() {
+                if mark(&mut marked_iterators, idx) {
+                    worklist.extend_from_slice(&self.for_of_iterators[idx as usize].values);
+                }
+            } else if let Some(idx) = val.to_string_idx()
+                && idx >= Self::RUNTIME_STRING_OFFSET
+            {
+                mark(
+                    &mut marked_strings,
+                    (idx - Self::RUNTIME_STRING_OFFSET) as u32,
+                );
+            }
+        }
+
+        let dead: Vec<u32> = self
+            .userdata
+            .keys()
+            .copied()
+            .filter(|idx| !marked_objects.get(*idx as usize).copied().unwrap_or(false))
+            .collect();
+        for idx in &dead {
+            // Remove the slot before finalizing so a finalizer can never run twice
+            if let Some(slot) = self.userdata.remove(idx) {
+                self.finalized_userdata.insert(*idx);
+                if let Some(finalizer) = slot.finalizer {
+                    finalizer(slot.value);
+                }
+            }
+        }
+
+        for (idx, _) in marked_arrays.iter().enumerate().filter(|(_, m)| !**m) {
+            let idx = idx as u32;
+            if self.freed_arrays.insert(idx) {
+                self.arrays[idx as usize] = Vec::new();
+                self.array_props.remove(&idx);
+                self.array_holes.remove(&idx);
+                self.sparse_arrays.remove(&idx);
+            }
+        }
+        // A temporary array reclaimed here must not be released again once
+        // its slot holds another array
+        self.temp_arrays
+            .retain(|&idx| marked_arrays.get(idx as usize).copied().unwrap_or(false));
+
+        // Unreachable runtime strings give up their text and their slots go
+        // back to `create_runtime_string`; trailing ones are popped off
+        for (idx, _) in marked_strings.iter().enumerate().filter(|(_, m)| !**m) {
+            if self.freed_strings.insert(idx as u16) {
+                self.runtime_strings[idx] = String::new();
+            }
+        }
+        while let Some(last) = self.runtime_strings.len().checked_sub(1)
+            && self.freed_strings.remove(&(last as u16))
+        {
+            self.runtime_strings.pop();
+        }
+
+        // Unreachable objects drop their properties and their slots go back
+        // to `push_object`; trailing ones are popped off the table
+        for (idx, _) in marked_objects.iter().enumerate().filter(|(_, m)| !**m) {
+            let idx = idx as u32;
+            if self.freed_objects.insert(idx) {
+                self.objects[idx as usize] = ObjectInstance::new();
+                self.date_values.remove(&idx);
+            }
+        }
+        while let Some(last) = self.objects.len().checked_sub(1)
+            && self.freed_objects.remove(&(last as u32))
+        {
+            self.objects.pop();
+        }
+
+        // Unreachable closures drop their captures and their slots go back
+        // to `create_closure`; trailing ones are popped off the table
+        for (idx, _) in marked_closures.iter().enumerate().filter(|(_, m)| !**m) {
+            let idx = idx as u32;
+            if self.freed_closures.insert(idx) {
+                self.closures[idx as usize].var_refs = Vec::new();
+                self.function_prototypes.remove(&Value::closure_idx(idx));
+            }
+        }
+        while let Some(last) = self.closures.len().checked_sub(1)
+            && self.freed_closures.remove(&(last as u32))
+        {
+            self.closures.pop();
+        }
+
+        let pause_us = self.clock.now_us().saturating_sub(started);
+        let bytes_after = self.heap_bytes();
+        self.heap_charged = bytes_after;
+        let stats = &mut self.gc_stats;
+        stats.collections += 1;
+        stats.last_pause_us = pause_us;
+        stats.total_pause_us += pause_us;
+        stats.bytes_before = bytes_before;
+        stats.bytes_after = bytes_after;
+        stats.live_objects = [&marked_objects, &marked_arrays, &marked_closures]
+            .iter()
+            .map(|flags| flags.iter().filter(|m| **m).count())
+            .sum();
+
+        if let Some(callback) = self.gc_callback.as_mut() {
+            callback(GcPhase::End);
+        }
+        dead.len()
+    }
+
+    /// Microseconds elapsed on the clock since this interpreter was created
+    fn performance_now(&mut self) -> Result<i64, HostError> {
+        let elapsed =
+            self.read_clock(|interp| interp.clock.now_us().saturating_sub(interp.time_origin))?;
+        Ok(elapsed as i64)
+    }
+
+    /// Create a Date object holding the given time value
+    fn create_date(&mut self, time: Option<i64>) -> Value {
+        let date = self.create_object_with_constructor(Value::builtin_object(BUILTIN_DATE));
+        if let Some(idx) = date.to_object_idx() {
+            self.date_values.insert(idx, time);
+        }
+        date
+    }
+
+    /// Time value of a Date object: None if `val` is not a Date,
+    /// Some(None) for an Invalid Date
+    pub(crate) fn date_value(&self, val: Value) -> Option<Option<i64>> {
+        self.date_values.get(&val.to_object_idx()?).copied()
+    }
+
+    /// Time values of two Dates, or None unless both operands are Dates
+    ///
+    /// Subtraction and the relational operators use them directly: times
+    /// after 2038 do not fit an int, so going through `valueOf` would throw
+    /// where the difference or ordering is exact. Subtraction still counts
+    /// whole seconds, as `a.getTime() - b.getTime()` would; ordering uses
+    /// the full millisecond time.
+    fn date_operands(&self, a: Value, b: Value) -> Option<(Option<i64>, Option<i64>)> {
+        Some((self.date_value(a)?, self.date_value(b)?))
+    }
+
+    /// Apply a relational operator to two Dates by time value, or None
+    /// unless both operands are Dates
+    ///
+    /// An Invalid Date is NaN and compares false with everything.
+    fn compare_dates(
+        &self,
+        a: Value,
+        b: Value,
+        holds: fn(core::cmp::Ordering) -> bool,
+    ) -> Option<Value> {
+        let (a, b) = self.date_operands(a, b)?;
+        let ordering = a.zip(b).map(|(a, b)| a.cmp(&b));
+        Some(Value::bool(ordering.is_some_and(holds)))
+    }
+
+    /// Create a pending promise and the handle the host settles it with
+    ///
+    /// A native can return the promise at once and settle it once the work
+    /// behind it finishes; see [`ResolverHandle`].
+    pub fn create_pending_promise(&mut self) -> (Value, ResolverHandle) {
+        let promise = self.create_promise();
+        let handle = ResolverHandle::new(promise, &self.settlements);
+        (promise, handle)
+    }
+
+    /// Create a pending promise object
+    fn create_promise(&mut self) -> Value {
+        let promise = self.push_object(ObjectInstance::new());
+        if let Some(idx) = promise.to_object_idx() {
+            self.promises.insert(idx, PromiseState::Pending(Vec::new()));
+        }
+        promise
+    }
+
+    /// Object index of a promise, or None if `val` is not one
+    fn promise_idx(&self, val: Value) -> Option<u32> {
+        val.to_object_idx()
+            .filter(|idx| self.promises.contains_key(idx))
+    }
+
+    /// Settle a pending promise and queue the reactions waiting for it
+    ///
+    /// Fulfilling with another promise makes this one follow it instead.
+    /// Promises that already settled are left alone.
+    fn settle_promise(&mut self, promise: Value, value: Value, rejected: bool) {
+        let Some(idx) = self.promise_idx(promise) else {
+            return;
+        };
+        if !matches!(self.promises.get(&idx), Some(PromiseState::Pending(_))) {
+            return;
+        }
+        self.promote(value);
+        if !rejected && let Some(followed) = self.promise_idx(value) {
+            if followed == idx {
+                let error = self.alloc_error("TypeError", "Chaining cycle detected for promise");
+                return self.settle_promise(promise, error, true);
+            }
+            let forward = PromiseReaction {
+                on_fulfilled: Value::undefined(),
+                on_rejected: Value::undefined(),
+                derived: promise,
+            };
+            return self.add_promise_reaction(followed, forward);
+        }
+        let state = if rejected {
+            PromiseState::Rejected(value)
+        } else {
+            PromiseState::Fulfilled(value)
+        };
+        if let Some(PromiseState::Pending(reactions)) = self.promises.insert(idx, state) {
+            for reaction in reactions {
+                self.queue_promise_job(reaction, value, rejected);
+            }
+        }
+    }
+
+    /// Run `reaction` once promise `idx` settles, or queue it now if it has
+    fn add_promise_reaction(&mut self, idx: u32, reaction: PromiseReaction) {
+        match self.promises.get_mut(&idx) {
+            Some(PromiseState::Pending(reactions)) => reactions.push(reaction),
+            Some(&mut PromiseState::Fulfilled(value)) => {
+                self.queue_promise_job(reaction, value, false)
+            }
+            Some(&mut PromiseState::Rejected(reason)) => {
+                self.queue_promise_job(reaction, reason, true)
+            }
+            None => {}
+        }
+    }
+
+    /// Queue the handler of `reaction` that matches how its promise settled
+    fn queue_promise_job(&mut self, reaction: PromiseReaction, argument: Value, rejected: bool) {
+        let handler = if rejected {
+            reaction.on_rejected
+        } else {
+            reaction.on_fulfilled
+        };
+        self.promise_jobs.push_back(PromiseJob {
+            handler,
+            argument,
+            rejected,
+            derived: reaction.derived,
+        });
+    }
+
+    /// Settle the promises resolver handles reported on, then run promise
+    /// reactions until none are left
+    ///
+    /// Reactions queued along the way run too. A handler that throws
+    /// rejects the promise its `then` returned instead of failing the run;
+    /// only errors no script can catch, such as a timeout, are returned.
+    /// Returns the number of reactions run.
+    pub fn run_pending_jobs(&mut self) -> InterpreterResult<usize> {
+        for settlement in self.settlements.take() {
+            self.settle_promise(settlement.promise, settlement.value, settlement.rejected);
+        }
+
+        let mut ran = 0;
+        while let Some(job) = self.promise_jobs.pop_front() {
+            ran += 1;
+            if !self.is_callable(job.handler) {
+                self.settle_promise(job.derived, job.argument, job.rejected);
+                continue;
+            }
+            // A throw from the handler is the derived promise's rejection,
+            // not an uncaught exception
+            let earlier = self.uncaught_exception.take();
+            let result = self.run_top_level(|interp| {
+                interp.call_value(job.handler, Value::undefined(), &[job.argument])
+            });
+            let thrown = core::mem::replace(&mut self.uncaught_exception, earlier);
+            match (result, thrown) {
+                (Ok(value), _) => self.settle_promise(job.derived, value, false),
+                (Err(_), Some(exception)) => self.settle_promise(job.derived, exception, true),
+                (Err(e), None) => return Err(e),
+            }
+        }
+        Ok(ran)
+    }
+
+    /// Get a named property stored on an array
+    fn array_get_named(&self, arr_idx: u32, key: &str) -> Option<Value> {
+        self.named_get(self.array_props.get(&arr_idx)?, key)
+    }
+
+    /// Set a named property on an array, allocating its property map if needed
+    fn array_set_named(&mut self, arr_idx: u32, key: &str, value: Value) {
+        let key = self.atom(key);
+        if self.array_props.entry(arr_idx).or_default().set(key, value) {
+            self.charge_memory(PROPERTY_BYTES);
+        }
+    }
+
+    /// Remove a named property from an array, returning whether it existed
+    fn array_delete_named(&mut self, arr_idx: u32, key: &str) -> bool {
+        match (self.atoms.lookup(key), self.array_props.get_mut(&arr_idx)) {
+            (Some(key), Some(obj)) => obj.remove(key),
+            _ => false,
+        }
+    }
+
+    /// Names of the named properties on an array, in insertion order
+    fn array_named_keys(&self, arr_idx: u32) -> Vec<String> {
+        self.array_props
+            .get(&arr_idx)
+            .map(|obj| {
+                obj.own_keys(false)
+                    .map(|k| self.atoms.name(k).to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Check whether a value can be called as a function
+    pub(crate) fn is_callable(&self, val: Value) -> bool {
+        val.is_closure()
+            || val.to_func_ptr().is_some()
+            || val.is_native_func()
+            || val
+                .to_builtin_object_idx()
+                .is_some_and(is_builtin_constructor)
+            || self.get_bound_function(val).is_some()
+    }
+
+    /// Unpack a function created by bind() into (target, this, bound args)
+    fn get_bound_function(&self, func: Value) -> Option<(Value, Value, Vec<Value>)> {
+        let obj = self.get_object(func.to_object_idx()?)?;
+        let prop = |name: &str| self.named_get(obj, name);
+        prop("__is_bound__")?;
+        let bound_args = prop("__bound_args__")
+            .and_then(|v| v.to_array_idx())
+            .and_then(|idx| self.get_array(idx))
+            .cloned()
+            .unwrap_or_default();
+        Some((
+            prop("__bound_func__")?,
+            prop("__bound_this__").unwrap_or_default(),
+            bound_args,
+        ))
+    }
+
     /// Get a mutable closure by index
     fn get_closure_mut(&mut self, idx: u32) -> Option<&mut ClosureData> {
         self.closures.get_mut(idx as usize)
@@ -984,16 +4660,84 @@ impl Interpreter {
         func: Value,
         this_val: Value,
         args: &[Value],
+    ) -> InterpreterResult<Value> {
+        self.promote(this_val);
+        self.promote_all(args);
+        self.run_nested(|interp| interp.call_value_inner(func, this_val, args))
+    }
+
+    /// Compile and run `source` as a script while other code is running,
+    /// such as from a native function scripts call
+    ///
+    /// The script shares this interpreter's globals, and its top-level
+    /// declarations become globals when it finishes. The frames of the
+    /// running code are left alone, so natives may nest evaluations up to
+    /// the recursion limit. Like every loaded script its bytecode lives as
+    /// long as the interpreter, so closures it creates stay callable after
+    /// it returns.
+    ///
+    /// A compile error becomes a SyntaxError exception. An exception the
+    /// script does not catch comes back as [`InterpreterError::Exception`];
+    /// a native returning it as its error rethrows the original value to
+    /// its caller, as after [`Interpreter::call_value`].
+    pub fn eval_nested(&mut self, source: &str) -> InterpreterResult<Value> {
+        let compiled = match self.compiler(source).compile() {
+            Ok(compiled) => compiled,
+            Err(err) => {
+                let message = match err {
+                    CompileError::SyntaxError(msg) => msg,
+                    err => err.to_string(),
+                };
+                let exception = self.alloc_error("SyntaxError", &message);
+                self.native_exception = Some(exception);
+                return Err(InterpreterError::Exception(exception));
+            }
+        };
+        let script = self.load_script(FunctionBytecode::from_compiled(compiled));
+        let bytecode = self.script_bytecode(script)?;
+        // SAFETY: boxed scripts are never moved, and only dropped below
+        let result = self.run_nested(|interp| {
+            interp.call_function(unsafe { &*bytecode }, Value::undefined(), &[])
+        });
+        self.unload_script_without_functions(script);
+        result
+    }
+
+    /// Run `f`, which calls into script code, returning as soon as control
+    /// is back at the current call depth
+    ///
+    /// Whatever a failed call leaves on the stacks is dropped, and an
+    /// exception is kept for the native that made the call to rethrow.
+    fn run_nested(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> InterpreterResult<Value>,
     ) -> InterpreterResult<Value> {
         // Save current call stack depth to return when we're back to this level
         let saved_target = self.nested_call_target_depth;
         self.nested_call_target_depth = Some(self.call_stack.len());
 
-        let result = self.call_value_inner(func, this_val, args);
+        let stack_depth = self.stack.len();
+        let frame_depth = self.call_stack.len();
+        let result = f(self);
 
         // Restore the previous target depth
         self.nested_call_target_depth = saved_target;
 
+        match result {
+            // The caller may store the result where statement ends cannot see it
+            Ok(val) => self.promote(val),
+            Err(ref err) => {
+                // Drop what the failed call left behind so the native that
+                // made it can carry on or return the error
+                self.stack
+                    .drop_n(self.stack.len().saturating_sub(stack_depth));
+                self.call_stack.truncate(frame_depth);
+                self.prune_exception_handlers();
+                if let InterpreterError::Exception(exception) = *err {
+                    self.native_exception = Some(exception);
+                }
+            }
+        }
         result
     }
 
@@ -1004,64 +4748,904 @@ impl Interpreter {
         this_val: Value,
         args: &[Value],
     ) -> InterpreterResult<Value> {
-        // Handle closures
-        if let Some(closure_idx) = func.to_closure_idx() {
-            let closure = self.get_closure(closure_idx).ok_or_else(|| {
-                InterpreterError::InternalError(format!("invalid closure index: {}", closure_idx))
-            })?;
-            let bytecode = unsafe { &*closure.bytecode };
+        match self.push_call_frame(func, this_val, args, CallFlags::CALL)? {
+            CallOutcome::Pushed => self.run(),
+            CallOutcome::Returned(result) => Ok(result),
+        }
+    }
 
-            // Check recursion limit
-            if self.call_stack.len() >= self.max_recursion {
-                return Err(InterpreterError::InternalError(
-                    "maximum call stack size exceeded".to_string(),
-                ));
+    /// Drop exception handlers installed by frames that are no longer on the
+    /// call stack (e.g. after returning from inside a try block)
+    fn prune_exception_handlers(&mut self) {
+        let depth = self.call_stack.len();
+        while self
+            .exception_handlers
+            .last()
+            .is_some_and(|h| h.frame_depth > depth)
+        {
+            self.exception_handlers.pop();
+        }
+    }
+
+    /// Transfer control to the nearest exception handler
+    ///
+    /// Unwinds the call and value stacks to the handler's depth, pushes the
+    /// exception for the catch block and jumps to it. Returns false if no
+    /// handler is active.
+    fn unwind_to_handler(&mut self, exception: Value) -> bool {
+        let Some(handler) = self.exception_handlers.pop() else {
+            return false;
+        };
+
+        // Unwind call stack to the handler's frame
+        self.call_stack.truncate(handler.frame_depth);
+
+        // Restore stack to handler's depth
+        while self.stack.len() > handler.stack_depth {
+            self.stack.pop();
+        }
+
+        // Push the exception value for the catch block
+        self.stack.push(exception);
+
+        // Jump to catch block
+        match self.call_stack.last_mut() {
+            Some(frame) => {
+                frame.pc = handler.catch_pc;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Raise an interpreter error as a catchable JS exception
+    ///
+    /// Type, range and reference errors become error objects thrown to the
+    /// nearest handler, provided it was installed at or above `base_depth`
+    /// (i.e. by the running invocation of the interpreter loop). Otherwise,
+    /// or for internal failures, the error is returned unchanged so it
+    /// propagates to the caller.
+    fn throw_error(&mut self, err: InterpreterError, base_depth: usize) -> InterpreterResult<()> {
+        let (name, message) = match &err {
+            InterpreterError::Exception(exception) => {
+                let exception = *exception;
+                let caught_here = self
+                    .exception_handlers
+                    .last()
+                    .is_some_and(|h| h.frame_depth >= base_depth);
+                return if caught_here && self.unwind_to_handler(exception) {
+                    Ok(())
+                } else {
+                    Err(err)
+                };
+            }
+            _ => match err.js_error_parts() {
+                Some(parts) => parts,
+                None => return Err(err),
+            },
+        };
+        if self
+            .exception_handlers
+            .last()
+            .is_none_or(|h| h.frame_depth < base_depth)
+        {
+            return Err(err);
+        }
+
+        let exception = self.alloc_error(name, message);
+        // The handler gets to run even if the error object itself did not fit
+        self.out_of_memory = false;
+        if self.unwind_to_handler(exception) {
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
+    /// Execute bytecode and return the result
+    ///
+    /// # Safety
+    /// The bytecode pointer must be valid for the duration of execution.
+    pub fn execute(&mut self, bytecode: &FunctionBytecode) -> InterpreterResult<Value> {
+        self.run_top_level(|interp| interp.call_function(bytecode, Value::undefined(), &[]))
+    }
+
+    /// Run top-level code such as a script or a timer callback
+    fn run_top_level(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> InterpreterResult<Value>,
+    ) -> InterpreterResult<Value> {
+        let base = self.top_level_base();
+        // Start from the budget rather than a flag left by earlier code, so
+        // a script whose bytecode did not fit fails before it runs
+        self.out_of_memory = !self.fits_in_budget(0);
+        self.string_table_full = false;
+
+        let saved_target = self.nested_call_target_depth;
+        self.nested_call_target_depth = base.target();
+        let result = f(self);
+        self.nested_call_target_depth = saved_target;
+        self.finish_top_level(result, &base)
+    }
+
+    /// Depths of the stacks top-level code starts from
+    fn top_level_base(&self) -> TopLevelBase {
+        TopLevelBase {
+            stack_depth: self.stack.len(),
+            frame_depth: self.call_stack.len(),
+            handler_depth: self.exception_handlers.len(),
+            temp_depth: self.temp_arrays.len(),
+        }
+    }
+
+    /// Report the outcome of top-level code that started from `base`
+    fn finish_top_level(
+        &mut self,
+        mut result: InterpreterResult<Value>,
+        base: &TopLevelBase,
+    ) -> InterpreterResult<Value> {
+        let TopLevelBase {
+            stack_depth,
+            frame_depth,
+            handler_depth,
+            temp_depth,
+        } = *base;
+
+        // Whatever is still temporary now belongs to the host
+        self.temp_arrays.truncate(temp_depth);
+
+        if let Err(InterpreterError::Exception(exception)) = result {
+            self.uncaught_exception = Some(exception);
+            let text = match exception.to_error_object_idx() {
+                Some(idx) => self.error_chain_text(idx),
+                None => match self.error_like_parts(exception) {
+                    Some((name, message)) if message.is_empty() => name,
+                    Some((name, message)) => format!("{}: {}", name, message),
+                    None => format_value(self, exception),
+                },
+            };
+            result = Err(InterpreterError::InternalError(format!(
+                "Uncaught exception: {}",
+                text
+            )));
+        }
+
+        // An uncaught error leaves frames behind; drop them so the
+        // interpreter can run further scripts
+        if result.is_err() {
+            self.stack
+                .drop_n(self.stack.len().saturating_sub(stack_depth));
+            self.call_stack.truncate(frame_depth);
+            self.exception_handlers.truncate(handler_depth);
+        }
+        result
+    }
+
+    /// Prepare `script` to run in slices with [`Interpreter::run_slice`]
+    ///
+    /// Nothing runs yet. The script's frames live on the interpreter's
+    /// stacks until it finishes, so other code may run on top of them
+    /// between slices and the collector sees their values.
+    pub fn start_sliced(&mut self, script: usize) -> InterpreterResult<SlicedRun> {
+        let ptr = self.script_bytecode(script)?;
+        // SAFETY: boxed scripts are never moved, and loaded ones never dropped
+        let bytecode = unsafe { &*ptr };
+        if self.call_stack.len() >= self.max_recursion {
+            return Err(InterpreterError::InternalError(
+                "maximum call stack size exceeded".to_string(),
+            ));
+        }
+        let base = self.top_level_base();
+        let (frame_ptr, argc) = self.push_frame_values(bytecode, &[]);
+        self.enter_frame(CallFrame::new(
+            ptr,
+            frame_ptr,
+            argc,
+            Value::undefined(),
+            Value::undefined(),
+        ));
+        Ok(SlicedRun { base, result: None })
+    }
+
+    /// Remove the earliest timer due at `now` and prepare its callback to
+    /// run in slices, as [`Interpreter::run_next_timer`] would run it
+    ///
+    /// Returns None if no timer is due.
+    pub fn start_next_timer_sliced(&mut self, now: u64) -> Option<InterpreterResult<SlicedRun>> {
+        let timer = self.take_due_timer(now)?;
+        let base = self.top_level_base();
+        let saved_target = self.nested_call_target_depth;
+        self.nested_call_target_depth = base.target();
+        let outcome =
+            self.push_call_frame(timer.callback, Value::undefined(), &[], CallFlags::CALL);
+        self.nested_call_target_depth = saved_target;
+        let result = match outcome {
+            Ok(CallOutcome::Pushed) => None,
+            // A native callback has already run
+            Ok(CallOutcome::Returned(value)) => Some(Ok(value)),
+            Err(err) => Some(Err(err)),
+        };
+        Some(Ok(SlicedRun { base, result }))
+    }
+
+    /// Run `run` for at most `budget` instructions, taking what it uses
+    /// from the budget
+    ///
+    /// Returns the completion value once the run finishes, or None if the
+    /// budget ran out first; call again to carry on. A run fails as
+    /// [`Interpreter::execute`] would and leaves the stacks as it found
+    /// them.
+    ///
+    /// The run can only pause in its own code: a native calling back into
+    /// script code, such as `Array.prototype.map`, finishes the callbacks
+    /// first, so a slice may overrun its budget by what they execute.
+    pub fn run_slice(
+        &mut self,
+        run: &mut SlicedRun,
+        budget: &mut u64,
+    ) -> InterpreterResult<Option<Value>> {
+        if let Some(result) = run.result.take() {
+            return self.finish_top_level(result, &run.base).map(Some);
+        }
+        if run.base.frame_depth >= self.call_stack.len() {
+            return Err(InterpreterError::InternalError(
+                "sliced run has already finished".to_string(),
+            ));
+        }
+        self.out_of_memory = !self.fits_in_budget(0);
+        self.string_table_full = false;
+
+        let target = run.base.target();
+        let saved_slice = self.slice.replace(Slice {
+            remaining: *budget,
+            target,
+            yielded: false,
+        });
+        self.update_instruction_hooks();
+        let saved_target = self.nested_call_target_depth;
+        self.nested_call_target_depth = target;
+        let result = self.run_from(run.base.frame_depth + 1);
+        self.nested_call_target_depth = saved_target;
+        let slice = core::mem::replace(&mut self.slice, saved_slice);
+        self.update_instruction_hooks();
+
+        if let Some(slice) = slice {
+            *budget = slice.remaining;
+            if slice.yielded {
+                return Ok(None);
+            }
+        }
+        self.finish_top_level(result, &run.base).map(Some)
+    }
+
+    /// Count the instruction about to run for profiling and against the
+    /// sliced run's budget, returning whether the run pauses instead
+    ///
+    /// Kept out of line so the run loop pays only for testing
+    /// `instruction_hooks` when neither is in use.
+    #[cold]
+    #[inline(never)]
+    fn run_instruction_hooks(&mut self) -> bool {
+        let target = self.nested_call_target_depth;
+        if let Some(slice) = self.slice.as_mut() {
+            if slice.remaining == 0 && target == slice.target {
+                slice.yielded = true;
+                return true;
+            }
+            slice.remaining = slice.remaining.saturating_sub(1);
+        }
+        if let Some(profile) = self.profile.as_deref_mut() {
+            profile.instructions += 1;
+            profile.peak_stack_depth = profile.peak_stack_depth.max(self.stack.len());
+            profile.peak_call_depth = profile.peak_call_depth.max(self.call_stack.len());
+        }
+        false
+    }
+
+    /// Recompute whether the run loop calls `run_instruction_hooks`
+    fn update_instruction_hooks(&mut self) {
+        self.instruction_hooks = self.profile.is_some() || self.slice.is_some();
+    }
+
+    /// Drop what is left of an unfinished sliced run
+    pub fn abandon_slice(&mut self, run: SlicedRun) {
+        let base = run.base;
+        if self.call_stack.len() > base.frame_depth {
+            self.stack
+                .drop_n(self.stack.len().saturating_sub(base.stack_depth));
+            self.call_stack.truncate(base.frame_depth);
+            self.exception_handlers.truncate(base.handler_depth);
+        }
+        self.temp_arrays.truncate(base.temp_depth);
+    }
+
+    /// Limit the bytes scripts may hold
+    ///
+    /// The budget covers everything [`Interpreter::memory_used`] counts.
+    /// Large allocations (string concatenation, `repeat`, padding, growing
+    /// an array by index, `length`, `push` or `unshift`, `concat`, typed
+    /// arrays) are checked before they are made; smaller ones are counted
+    /// as they happen. Going over the limit first collects garbage between
+    /// instructions, so scripts that keep replacing strings stay within it;
+    /// if that does not make room it throws a catchable
+    /// `RangeError: out of memory`.
+    pub fn set_memory_limit(&mut self, bytes: usize) {
+        self.memory_limit = bytes;
+        self.heap_charged = self.heap_bytes();
+    }
+
+    /// The limit set by [`Interpreter::set_memory_limit`]
+    pub fn memory_limit(&self) -> usize {
+        self.memory_limit
+    }
+
+    /// Bytes currently held by scripts
+    ///
+    /// Counts array element storage and named array properties, object
+    /// properties and their keys, closure captures, runtime strings, error
+    /// names and messages, typed array and ArrayBuffer storage, and the
+    /// values on the value stack, and the bytecode of loaded scripts. The
+    /// interpreter's fixed tables are not counted. Objects stay counted until a
+    /// collection reclaims them.
+    pub fn memory_used(&self) -> usize {
+        self.heap_bytes() + self.stack_bytes()
+    }
+
+    /// Bytes of the values currently on the value stack
+    pub(crate) fn stack_bytes(&self) -> usize {
+        self.stack.len() * core::mem::size_of::<Value>()
+    }
+
+    /// Whether `bytes` more fit in the memory budget
+    ///
+    /// Checks the running heap count plus the value stack, which is cheap.
+    /// Only frees nothing credits, such as a deleted property, make the
+    /// count high, so it is recomputed from the tables once before an
+    /// allocation is refused.
+    fn memory_available(&mut self, bytes: usize) -> bool {
+        if self.fits_in_budget(bytes) {
+            return true;
+        }
+        self.heap_charged = self.heap_bytes();
+        self.fits_in_budget(bytes)
+    }
+
+    /// Whether `bytes` more fit by the running count
+    fn fits_in_budget(&self, bytes: usize) -> bool {
+        self.heap_charged
+            .saturating_add(self.stack_bytes())
+            .saturating_add(bytes)
+            <= self.memory_limit
+    }
+
+    /// Fail with a RangeError unless `bytes` more fit in the memory budget
+    fn check_memory(&mut self, bytes: usize) -> InterpreterResult<()> {
+        if self.memory_available(bytes) {
+            Ok(())
+        } else {
+            Err(InterpreterError::RangeError(OUT_OF_MEMORY.to_string()))
+        }
+    }
+
+    /// Like [`Interpreter::check_memory`], but collects garbage before
+    /// refusing
+    ///
+    /// Only for instructions that hold no values outside the value stack
+    /// and the other roots. Inside a callback a native is running this is
+    /// [`Interpreter::check_memory`].
+    fn check_memory_or_collect(&mut self, bytes: usize) -> InterpreterResult<()> {
+        if self.memory_available(bytes) {
+            return Ok(());
+        }
+        if self.nested_call_target_depth.is_none() {
+            self.collect_garbage();
+        }
+        self.check_memory(bytes)
+    }
+
+    /// Count an allocation of `bytes` that has already been made
+    ///
+    /// Allocations that cannot fail on the spot push usage over the limit
+    /// by at most their own size; the error is raised before the next
+    /// instruction.
+    fn charge_memory(&mut self, bytes: usize) {
+        self.heap_charged = self.heap_charged.saturating_add(bytes);
+        if !self.fits_in_budget(0) {
+            self.out_of_memory = true;
+        }
+    }
+
+    /// Try to get back within the memory budget after an allocation went
+    /// over it
+    ///
+    /// Runs between instructions, where every value a script holds is on
+    /// the value stack or reachable from a root, so unreachable runtime
+    /// strings, arrays and closures can be collected. Inside a callback a
+    /// native is running, whose values the collector cannot see, the count
+    /// is only recomputed.
+    fn recover_memory(&mut self) -> bool {
+        if self.nested_call_target_depth.is_none() {
+            self.collect_garbage();
+        } else {
+            self.heap_charged = self.heap_bytes();
+        }
+        self.fits_in_budget(0)
+    }
+
+    /// Account for a typed array or ArrayBuffer allocation of
+    /// `count * elem_size` bytes
+    ///
+    /// Fails with a RangeError if the size overflows or exceeds either the
+    /// per-allocation cap or the remaining memory budget.
+    fn reserve_binary(&mut self, count: usize, elem_size: usize) -> InterpreterResult<usize> {
+        match count.checked_mul(elem_size) {
+            Some(bytes) if bytes <= Self::MAX_BINARY_ALLOCATION && self.memory_available(bytes) => {
+                self.binary_memory_used += bytes;
+                self.heap_charged += bytes;
+                Ok(bytes)
+            }
+            _ => Err(InterpreterError::RangeError(format!(
+                "array buffer allocation failed: {} elements of {} bytes exceed the memory limit",
+                count, elem_size
+            ))),
+        }
+    }
+
+    /// Replace the clock used by timers and deadlines
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.time_origin = clock.now_us();
+        self.clock = clock;
+    }
+
+    /// The clock used by timers and deadlines
+    pub fn clock(&self) -> Rc<dyn Clock> {
+        Rc::clone(&self.clock)
+    }
+
+    /// Interrupt running code once the clock reaches `deadline` (ms)
+    pub fn set_deadline(&mut self, deadline: Option<u64>) {
+        self.deadline = deadline;
+        self.deadline_countdown = Self::DEADLINE_CHECK_INTERVAL;
+    }
+
+    /// Count a backward jump or call and fail once the deadline has passed
+    ///
+    /// The clock is only read every `DEADLINE_CHECK_INTERVAL` events.
+    fn check_deadline(&mut self) -> InterpreterResult<()> {
+        self.deadline_countdown -= 1;
+        if self.deadline_countdown > 0 {
+            return Ok(());
+        }
+        self.deadline_countdown = Self::DEADLINE_CHECK_INTERVAL;
+        match self.deadline {
+            Some(deadline) if self.clock.now_ms() >= deadline => Err(InterpreterError::Interrupted),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fire time (ms) of the earliest pending timer
+    pub fn next_timer_due(&self) -> Option<u64> {
+        self.timers
+            .iter()
+            .filter(|t| !t.cancelled)
+            .map(|t| t.fire_at)
+            .min()
+    }
+
+    /// Number of timers waiting to fire
+    pub fn pending_timers(&self) -> usize {
+        self.timers.iter().filter(|t| !t.cancelled).count()
+    }
+
+    /// Remove the earliest timer due at `now` and run its callback
+    ///
+    /// Timers due at the same time fire in the order they were scheduled.
+    /// Returns None if no timer is due.
+    pub fn run_next_timer(&mut self, now: u64) -> Option<InterpreterResult<Value>> {
+        let timer = self.take_due_timer(now)?;
+        Some(
+            self.run_top_level(|interp| interp.call_value(timer.callback, Value::undefined(), &[])),
+        )
+    }
+
+    /// Remove and return the earliest timer due at `now`
+    fn take_due_timer(&mut self, now: u64) -> Option<Timer> {
+        self.timers.retain(|t| !t.cancelled);
+        let pos = self
+            .timers
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.fire_at <= now)
+            .min_by_key(|(_, t)| (t.fire_at, t.id))
+            .map(|(i, _)| i)?;
+        Some(self.timers.remove(pos))
+    }
+
+    /// Execute a top-level script, taking ownership of its bytecode
+    ///
+    /// A script that defines functions is kept alive for the lifetime of the
+    /// interpreter so closures and globals created by it stay valid after it
+    /// returns; one that defines none is dropped once it finishes. Its
+    /// string literals are interned so they resolve correctly from later
+    /// scripts.
+    pub fn execute_script(&mut self, bytecode: FunctionBytecode) -> InterpreterResult<Value> {
+        let script = self.load_script(bytecode);
+        let result = self.run_script(script);
+        self.unload_script_without_functions(script);
+        result
+    }
+
+    /// Keep a top-level script alive for the interpreter's lifetime without
+    /// running it, returning the index to pass to [`run_script`](Self::run_script)
+    ///
+    /// The bytecode counts against the memory limit; a script that does not
+    /// fit raises a RangeError when it starts running.
+    pub fn load_script(&mut self, mut bytecode: FunctionBytecode) -> usize {
+        self.intern_string_constants(&mut bytecode);
+        let bytes = script_bytes(&bytecode);
+        self.script_bytes += bytes;
+        self.charge_memory(bytes);
+        self.scripts.push(Some(Box::new(bytecode)));
+        self.scripts.len() - 1
+    }
+
+    /// Drop a script [`execute_script`](Self::execute_script) or a nested
+    /// evaluation loaded once it has run, unless it defines functions
+    ///
+    /// Function values point into the bytecode of their script, so only a
+    /// script without inner functions can have nothing left referring to
+    /// it. Trailing dropped slots are popped off the table.
+    fn unload_script_without_functions(&mut self, script: usize) {
+        let Some(slot) = self.scripts.get_mut(script) else {
+            return;
+        };
+        if slot.as_ref().is_none_or(|b| !b.inner_functions.is_empty()) {
+            return;
+        }
+        if let Some(bytecode) = slot.take() {
+            let bytes = script_bytes(&bytecode);
+            self.script_bytes -= bytes;
+            self.heap_charged = self.heap_charged.saturating_sub(bytes);
+            if self.current_string_constants == Some(&bytecode.string_constants as *const _) {
+                self.current_string_constants = None;
+            }
+        }
+        while self.scripts.last().is_some_and(Option::is_none) {
+            self.scripts.pop();
+        }
+    }
+
+    /// Run a script loaded with [`load_script`](Self::load_script); a
+    /// script may run any number of times
+    pub fn run_script(&mut self, script: usize) -> InterpreterResult<Value> {
+        let ptr = self.script_bytecode(script)?;
+        // SAFETY: boxed scripts are never moved, and only dropped after running
+        self.execute(unsafe { &*ptr })
+    }
+
+    /// Address of a loaded script's bytecode
+    ///
+    /// Scripts are boxed and only dropped once they have run and nothing
+    /// can refer to them, so the address stays valid however many scripts
+    /// are loaded after it, including while it runs.
+    fn script_bytecode(&self, script: usize) -> InterpreterResult<*const FunctionBytecode> {
+        match self.scripts.get(script).and_then(Option::as_ref) {
+            Some(bytecode) => Ok(&**bytecode),
+            None => Err(InterpreterError::InternalError(format!(
+                "invalid script index: {}",
+                script
+            ))),
+        }
+    }
+
+    /// Compiler for scripts the interpreter compiles itself, with the
+    /// settings of the context that owns it
+    pub(crate) fn compiler<'s>(&self, source: &'s str) -> Compiler<'s> {
+        let compiler = Compiler::new(source)
+            .with_max_nesting_depth(self.max_nesting_depth)
+            .with_print(self.print_enabled)
+            .with_max_tokens(self.compile_budget.max_tokens)
+            .with_max_bytecode(self.compile_budget.max_bytecode);
+        match self.compile_budget.timeout {
+            Some(timeout) => compiler.with_timeout(Rc::clone(&self.clock), timeout),
+            None => compiler,
+        }
+    }
+
+    /// Set the limits on the work compiling one script may take
+    pub(crate) fn set_compile_budget(&mut self, budget: CompileBudget) {
+        self.compile_budget = budget;
+    }
+
+    /// Set how deeply compiled scripts may nest
+    pub(crate) fn set_max_nesting_depth(&mut self, limit: usize) {
+        self.max_nesting_depth = limit;
+    }
+
+    /// Set whether compiled scripts may use the `print` statement
+    pub(crate) fn set_print_enabled(&mut self, enabled: bool) {
+        self.print_enabled = enabled;
+    }
+
+    /// Protect the builtins from prototype pollution by untrusted scripts
+    ///
+    /// While hardened, writes to builtin objects such as `Array.prototype`,
+    /// `Object.prototype` or `Math` throw a TypeError instead of being
+    /// ignored, `constructor` reads as undefined on every value, and any
+    /// read, write or definition of `__proto__` throws a TypeError. Scripts
+    /// that only call builtin methods are unaffected. `globalThis` is the
+    /// global environment rather than a builtin and stays writable.
+    pub fn set_hardened(&mut self, enabled: bool) {
+        self.hardened = enabled;
+    }
+
+    /// Whether [`Interpreter::set_hardened`] is in effect
+    pub fn is_hardened(&self) -> bool {
+        self.hardened
+    }
+
+    /// Longest array the engine supports, 2^30 - 1 elements
+    ///
+    /// Every index below it fits an int value, unlike the 2^32 - 1 the
+    /// specification allows. It is the default and the highest possible
+    /// [`Interpreter::set_max_array_length`].
+    pub const MAX_ARRAY_LENGTH: usize = crate::runtime::MAX_ARRAY_LENGTH as usize;
+
+    /// Limit how long scripts may grow an array
+    ///
+    /// Index writes, `length` writes, `Array(length)` and methods that
+    /// would make an array longer than `len` throw `RangeError: Invalid
+    /// array length` and leave it unchanged, as growth past the memory
+    /// budget throws `RangeError: out of memory`. The limit cannot be
+    /// raised past [`Interpreter::MAX_ARRAY_LENGTH`].
+    pub fn set_max_array_length(&mut self, len: usize) {
+        self.max_array_length = len.min(Self::MAX_ARRAY_LENGTH);
+    }
+
+    /// The limit set by [`Interpreter::set_max_array_length`]
+    pub fn max_array_length(&self) -> usize {
+        self.max_array_length
+    }
+
+    /// Fail with a RangeError if `len` is too long for an array
+    fn check_array_length(&self, len: usize) -> InterpreterResult<()> {
+        if len > self.max_array_length {
+            return Err(InterpreterError::RangeError(
+                INVALID_ARRAY_LENGTH.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Default for [`Interpreter::set_max_string_length`]
+    pub const DEFAULT_MAX_STRING_LENGTH: usize = 64 * 1024 * 1024;
+
+    /// Limit how long a string scripts may build
+    ///
+    /// Lengths are counted in UTF-8 bytes, which are never fewer than the
+    /// UTF-16 code units scripts see. `+`, `concat`, `repeat`, `padStart`,
+    /// `padEnd`, `join` and `JSON.stringify` check the length of their
+    /// result before building it and throw `RangeError: Invalid string
+    /// length` past the limit, so one call cannot allocate far beyond the
+    /// memory budget before it is noticed. Defaults to 64MB.
+    pub fn set_max_string_length(&mut self, len: usize) {
+        self.max_string_length = len;
+    }
+
+    /// The limit set by [`Interpreter::set_max_string_length`]
+    pub fn max_string_length(&self) -> usize {
+        self.max_string_length
+    }
+
+    /// Make room for a string of `len` bytes
+    ///
+    /// Fails with a RangeError if the string would be longer than the
+    /// string length limit or would not fit in the memory budget.
+    fn reserve_string(&mut self, len: usize) -> InterpreterResult<()> {
+        self.check_string_length(len)?;
+        self.check_memory(len)
+    }
+
+    /// Fail with a RangeError if `len` bytes is too long for a string
+    fn check_string_length(&self, len: usize) -> InterpreterResult<()> {
+        if len > self.max_string_length {
+            return Err(InterpreterError::RangeError(
+                INVALID_STRING_LENGTH.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check an access to `obj[key]` against the hardening rules
+    ///
+    /// Callers test [`Interpreter::is_hardened`] first so unhardened
+    /// interpreters skip the key comparisons.
+    fn check_hardened_access(&self, obj: Value, key: &str, write: bool) -> InterpreterResult<()> {
+        if key == "__proto__" {
+            return Err(InterpreterError::TypeError(
+                "'__proto__' is not accessible in a hardened context".to_string(),
+            ));
+        }
+        if write
+            && let PropertyReceiver::Builtin(idx) = PropertyReceiver::of(obj)
+            && idx != BUILTIN_GLOBAL_THIS
+        {
+            return Err(InterpreterError::TypeError(format!(
+                "Cannot assign to property '{}' of a frozen builtin object",
+                key
+            )));
+        }
+        Ok(())
+    }
+
+    /// Push a callee's arguments and locals, returning its frame pointer
+    /// and the argument count to record in its frame
+    ///
+    /// Arguments beyond the declared parameters go first, so they outlive
+    /// the call without moving anything the bytecode addresses; the
+    /// declared ones follow, padded with undefined, then the other locals.
+    fn push_frame_values(&mut self, bytecode: &FunctionBytecode, args: &[Value]) -> (usize, u16) {
+        let args = &args[..args.len().min(u16::MAX as usize)];
+        let declared = bytecode.arg_count as usize;
+        if let Some(extra) = args.get(declared..) {
+            for &arg in extra {
+                self.stack.push(arg);
             }
+        }
+
+        let frame_ptr = self.stack.len();
+        for i in 0..declared {
+            let arg = args.get(i).copied().unwrap_or_default();
+            self.stack.push(arg);
+        }
+
+        // Allocate space for locals (beyond arguments)
+        let extra_locals = bytecode.local_count.saturating_sub(bytecode.arg_count);
+        for _ in 0..extra_locals {
+            self.stack.push(Value::undefined());
+        }
+        (frame_ptr, args.len() as u16)
+    }
+
+    /// Pop the `argc` arguments of a call off the stack, first argument
+    /// first
+    fn pop_call_args(&mut self, argc: usize) -> InterpreterResult<Vec<Value>> {
+        let start = self
+            .stack
+            .len()
+            .checked_sub(argc)
+            .ok_or(InterpreterError::StackUnderflow)?;
+        let args = self.stack.values()[start..].to_vec();
+        self.stack.truncate(start);
+        self.promote_all(&args);
+        Ok(args)
+    }
 
-            let frame_ptr = self.stack.len();
+    /// Start a call to `callee` with the given receiver and arguments
+    ///
+    /// Every call goes through here, from the call opcodes and from natives
+    /// via [`Interpreter::call_value`]. Bound functions are unwrapped to
+    /// their target first. Natives and builtins run to completion and
+    /// their result is returned; bytecode functions get a frame pushed for
+    /// the run loop to execute.
+    ///
+    /// With [`CallFlags::CONSTRUCT`] the receiver is replaced by a new
+    /// object linked to the constructor, while builtin and host class
+    /// constructors build their own instances.
+    fn push_call_frame(
+        &mut self,
+        callee: Value,
+        this_val: Value,
+        args: &[Value],
+        flags: CallFlags,
+    ) -> InterpreterResult<CallOutcome> {
+        let not_callable = || {
+            let what = if flags.construct {
+                "constructor"
+            } else {
+                "function"
+            };
+            InterpreterError::TypeError(format!("not a {}", what))
+        };
 
-            // Push arguments (pad with undefined if needed)
-            for i in 0..bytecode.arg_count as usize {
-                let arg = args.get(i).copied().unwrap_or_default();
-                self.stack.push(arg);
-            }
+        let (mut callee, mut this_val, mut args) = (callee, this_val, Cow::Borrowed(args));
+        while let Some((target, bound_this, mut bound_args)) = self.get_bound_function(callee) {
+            bound_args.extend_from_slice(&args);
+            (callee, this_val, args) = (target, bound_this, Cow::Owned(bound_args));
+        }
 
-            // Allocate space for locals (beyond arguments)
-            let extra_locals = bytecode.local_count.saturating_sub(bytecode.arg_count);
-            for _ in 0..extra_locals {
-                self.stack.push(Value::undefined());
+        if let Some(native_idx) = callee.to_native_func_idx() {
+            if !flags.construct {
+                return self
+                    .call_native_func(native_idx, this_val, &args)
+                    .map(CallOutcome::Returned);
             }
+            // Host classes build a userdata object instead of running bytecode
+            return match self.host_natives.get(&native_idx) {
+                Some(&HostNative::Constructor(class)) => self
+                    .construct_host_object(class, callee, &args)
+                    .map(CallOutcome::Returned),
+                _ => Err(not_callable()),
+            };
+        }
+        if let Some(builtin_idx) = callee.to_builtin_object_idx() {
+            let result = if flags.construct {
+                self.construct_builtin(builtin_idx, &args)?
+                    .ok_or_else(not_callable)?
+            } else {
+                self.call_builtin_as_function(builtin_idx, this_val, &args)?
+            };
+            return Ok(CallOutcome::Returned(result));
+        }
+
+        let (bytecode, closure_idx): (*const FunctionBytecode, Option<usize>) =
+            if let Some(idx) = callee.to_closure_idx() {
+                let closure = self.get_closure(idx).ok_or_else(|| {
+                    InterpreterError::InternalError(format!("invalid closure index: {}", idx))
+                })?;
+                (closure.bytecode, Some(idx as usize))
+            } else if let Some(ptr) = callee.to_func_ptr() {
+                // Pointer-based function (from FClosure without captures or ThisFunc)
+                (ptr, None)
+            } else if let Some(idx) = callee.to_func_idx() {
+                // Index-based function (legacy, shouldn't happen anymore)
+                let inner = self.call_stack.last().and_then(|frame| {
+                    // Safety: bytecode pointer is valid for frame lifetime
+                    unsafe { &*frame.bytecode }
+                        .inner_functions
+                        .get(idx as usize)
+                });
+                let inner = inner.ok_or_else(|| {
+                    InterpreterError::InternalError(format!("invalid function index: {}", idx))
+                })?;
+                (inner as *const _, None)
+            } else {
+                return Err(not_callable());
+            };
+        // Safety: closures and function pointers keep their bytecode alive
+        // for as long as the interpreter
+        let bytecode = unsafe { &*bytecode };
 
-            // Create frame with closure
-            let frame = CallFrame::new_closure(
-                bytecode as *const _,
-                frame_ptr,
-                args.len().min(u16::MAX as usize) as u16,
-                this_val,
-                func,
-                closure_idx as usize,
-            );
-            self.call_stack.push(frame);
-
-            // Run the interpreter loop
-            return self.run();
+        // Check recursion limit
+        if self.call_stack.len() >= self.max_recursion {
+            return Err(InterpreterError::InternalError(
+                "maximum call stack size exceeded".to_string(),
+            ));
+        }
+        if self.deadline.is_some() {
+            self.check_deadline()?;
         }
 
-        // Handle function pointers
-        if let Some(ptr) = func.to_func_ptr() {
-            let bytecode = unsafe { &*ptr };
-            return self.call_function(bytecode, this_val, args);
+        if flags.construct {
+            // The constructor reference is kept for instanceof
+            this_val = self.create_object_with_constructor(callee);
         }
+        let (frame_ptr, argc) = self.push_frame_values(bytecode, &args);
+        self.enter_frame(CallFrame {
+            closure_idx,
+            is_constructor: flags.construct,
+            ..CallFrame::new(bytecode, frame_ptr, argc, this_val, callee)
+        });
+        Ok(CallOutcome::Pushed)
+    }
 
-        Err(InterpreterError::TypeError("not a function".to_string()))
+    /// Every argument passed to the innermost script frame, for tests that
+    /// inspect the frame layout from a native
+    #[cfg(test)]
+    pub(crate) fn current_frame_args(&self) -> Vec<Value> {
+        let Some(frame) = self.call_stack.last() else {
+            return Vec::new();
+        };
+        (0..frame.arg_count as usize)
+            .filter_map(|i| frame.arg_slot(i))
+            .map(|slot| self.stack.values()[slot])
+            .collect()
     }
 
-    /// Execute bytecode and return the result
-    ///
-    /// # Safety
-    /// The bytecode pointer must be valid for the duration of execution.
-    pub fn execute(&mut self, bytecode: &FunctionBytecode) -> InterpreterResult<Value> {
-        self.call_function(bytecode, Value::undefined(), &[])
+    /// Current value stack depth, for tests checking frames clean up
+    #[cfg(test)]
+    pub(crate) fn stack_len(&self) -> usize {
+        self.stack.len()
     }
 
     /// Call a function with the given `this` value and arguments
@@ -1078,36 +5662,58 @@ impl Interpreter {
             ));
         }
 
-        let frame_ptr = self.stack.len();
-
-        // Push arguments (pad with undefined if needed)
-        for i in 0..bytecode.arg_count as usize {
-            let arg = args.get(i).copied().unwrap_or_default();
-            self.stack.push(arg);
-        }
-
-        // Allocate space for locals (beyond arguments)
-        let extra_locals = bytecode.local_count.saturating_sub(bytecode.arg_count);
-        for _ in 0..extra_locals {
-            self.stack.push(Value::undefined());
-        }
+        let (frame_ptr, argc) = self.push_frame_values(bytecode, args);
 
         let frame = CallFrame::new(
             bytecode as *const _,
             frame_ptr,
-            args.len().min(u16::MAX as usize) as u16,
+            argc,
             this_val,
             Value::undefined(), // Top-level call has no function value
         );
-        self.call_stack.push(frame);
+        self.enter_frame(frame);
 
         // Run the interpreter loop
         self.run()
     }
 
-    /// Main interpreter loop
+    /// Run the interpreter loop for the frame just pushed
+    ///
+    /// Errors raised while executing are thrown to script-level handlers
+    /// where possible, and execution resumes at the catch block.
     fn run(&mut self) -> InterpreterResult<Value> {
+        self.run_from(self.call_stack.len())
+    }
+
+    /// Run the interpreter loop, throwing errors to handlers of frames at
+    /// `base_depth` or deeper
+    fn run_from(&mut self, base_depth: usize) -> InterpreterResult<Value> {
+        loop {
+            match self.run_loop() {
+                Err(err) => self.throw_error(err, base_depth)?,
+                result => return result,
+            }
+        }
+    }
+
+    /// Main interpreter loop
+    fn run_loop(&mut self) -> InterpreterResult<Value> {
         loop {
+            if self.out_of_memory {
+                self.out_of_memory = false;
+                // A string lost to a full string table stays lost
+                if core::mem::take(&mut self.string_table_full) {
+                    return Err(InterpreterError::RangeError(STRING_TABLE_FULL.to_string()));
+                }
+                if !self.recover_memory() {
+                    return Err(InterpreterError::RangeError(OUT_OF_MEMORY.to_string()));
+                }
+            }
+
+            if self.instruction_hooks && self.run_instruction_hooks() {
+                return Ok(Value::undefined());
+            }
+
             // Get current frame
             let frame = self.call_stack.last_mut().ok_or_else(|| {
                 InterpreterError::InternalError("no active call frame".to_string())
@@ -1126,10 +5732,16 @@ impl Interpreter {
                 return Ok(Value::undefined());
             }
 
-            // Fetch opcode
+            // Fetch opcode; handlers with an operand consume it through
+            // CallFrame::take_operand, which moves pc to the next instruction
             let opcode_byte = bc[frame.pc];
             frame.pc += 1;
 
+            #[cfg(feature = "opcode-stats")]
+            {
+                self.opcode_stats.opcodes[opcode_byte as usize] += 1;
+            }
+
             // Decode and execute
             match opcode_byte {
                 // Invalid
@@ -1168,30 +5780,21 @@ impl Interpreter {
 
                 // Push 8-bit signed integer
                 op if op == OpCode::PushI8 as u8 => {
-                    let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let val = bytecode.bytecode[frame.pc] as i8 as i32;
-                    frame.pc += 1;
-                    self.stack.push(Value::int(val));
+                    self.stack
+                        .push(Value::int(frame.take_operand(OpCode::PushI8)));
                 }
 
                 // Push 16-bit signed integer
                 op if op == OpCode::PushI16 as u8 => {
-                    let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let val = i16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as i32;
-                    frame.pc += 2;
-                    self.stack.push(Value::int(val));
+                    self.stack
+                        .push(Value::int(frame.take_operand(OpCode::PushI16)));
                 }
 
                 // Push constant from pool
                 op if op == OpCode::PushConst as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
                     let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let idx = frame.take_operand(OpCode::PushConst) as usize;
                     let val = bytecode.constants.get(idx).copied().unwrap_or_default();
                     self.stack.push(val);
                 }
@@ -1200,8 +5803,7 @@ impl Interpreter {
                 op if op == OpCode::PushConst8 as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
                     let bytecode = unsafe { &*frame.bytecode };
-                    let idx = bytecode.bytecode[frame.pc] as usize;
-                    frame.pc += 1;
+                    let idx = frame.take_operand(OpCode::PushConst8) as usize;
                     let val = bytecode.constants.get(idx).copied().unwrap_or_default();
                     self.stack.push(val);
                 }
@@ -1249,10 +5851,7 @@ impl Interpreter {
                 // Get local variable (16-bit index)
                 op if op == OpCode::GetLoc as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let idx = frame.take_operand(OpCode::GetLoc) as usize;
                     let frame_ptr = frame.frame_ptr;
                     let val = self.stack.get_local_at(frame_ptr, idx).unwrap_or_default();
                     self.stack.push(val);
@@ -1262,10 +5861,7 @@ impl Interpreter {
                 op if op == OpCode::PutLoc as u8 => {
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let idx = frame.take_operand(OpCode::PutLoc) as usize;
                     let frame_ptr = frame.frame_ptr;
                     self.stack.set_local_at(frame_ptr, idx, val);
                 }
@@ -1325,9 +5921,7 @@ impl Interpreter {
                 // Get local (8-bit index)
                 op if op == OpCode::GetLoc8 as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let idx = bytecode.bytecode[frame.pc] as usize;
-                    frame.pc += 1;
+                    let idx = frame.take_operand(OpCode::GetLoc8) as usize;
                     let frame_ptr = frame.frame_ptr;
                     let val = self.stack.get_local_at(frame_ptr, idx).unwrap_or_default();
                     self.stack.push(val);
@@ -1337,9 +5931,7 @@ impl Interpreter {
                 op if op == OpCode::PutLoc8 as u8 => {
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let idx = bytecode.bytecode[frame.pc] as usize;
-                    frame.pc += 1;
+                    let idx = frame.take_operand(OpCode::PutLoc8) as usize;
                     let frame_ptr = frame.frame_ptr;
                     self.stack.set_local_at(frame_ptr, idx, val);
                 }
@@ -1347,10 +5939,7 @@ impl Interpreter {
                 // Get argument (16-bit index)
                 op if op == OpCode::GetArg as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let idx = frame.take_operand(OpCode::GetArg) as usize;
                     let frame_ptr = frame.frame_ptr;
                     // Arguments are at the start of the frame
                     let val = self.stack.get_local_at(frame_ptr, idx).unwrap_or_default();
@@ -1361,10 +5950,7 @@ impl Interpreter {
                 op if op == OpCode::PutArg as u8 => {
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let idx = frame.take_operand(OpCode::PutArg) as usize;
                     let frame_ptr = frame.frame_ptr;
                     self.stack.set_local_at(frame_ptr, idx, val);
                 }
@@ -1427,6 +6013,17 @@ impl Interpreter {
                     self.stack.push(frame.this_val);
                 }
 
+                // Push new.target: the constructor for `new` calls, else undefined
+                op if op == OpCode::NewTarget as u8 => {
+                    let frame = self.call_stack.last().unwrap();
+                    let target = if frame.is_constructor {
+                        frame.this_func
+                    } else {
+                        Value::undefined()
+                    };
+                    self.stack.push(target);
+                }
+
                 // Push current function (for self-reference/recursion)
                 op if op == OpCode::ThisFunc as u8 => {
                     let frame = self.call_stack.last().unwrap();
@@ -1437,10 +6034,7 @@ impl Interpreter {
                 // Get captured variable (16-bit index)
                 op if op == OpCode::GetVarRef as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let idx = frame.take_operand(OpCode::GetVarRef) as usize;
 
                     // Get the closure for this frame
                     let closure_idx = frame.closure_idx;
@@ -1457,11 +6051,9 @@ impl Interpreter {
                 // Set captured variable (16-bit index)
                 op if op == OpCode::PutVarRef as u8 => {
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    self.promote(val);
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let idx = frame.take_operand(OpCode::PutVarRef) as usize;
 
                     // Set the captured variable in the closure
                     if let Some(closure_idx) = frame.closure_idx
@@ -1474,63 +6066,47 @@ impl Interpreter {
                 // Arithmetic: Negate
                 op if op == OpCode::Neg as u8 => {
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    let val = self.coerce_to_primitive(val, PrimitiveHint::Number)?;
                     let result = self.op_neg(val)?;
                     self.stack.push(result);
                 }
 
+                // Plus - unary plus converts its operand to a number
+                op if op == OpCode::Plus as u8 => {
+                    let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    let val = self.coerce_to_primitive(val, PrimitiveHint::Number)?;
+                    let result = self.to_number(val);
+                    self.stack.push(result);
+                }
+
                 // Arithmetic: Add (also handles string concatenation)
                 op if op == OpCode::Add as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    let (a, b) = self.coerce_operands(a, b, PrimitiveHint::Default)?;
 
                     // String concatenation: if either operand is a string, convert both to strings and concat
                     if a.is_string() || b.is_string() {
                         let frame = self.call_stack.last().unwrap();
                         let bytecode = unsafe { &*frame.bytecode };
 
-                        let str_a = if a.is_string() {
-                            self.get_string_content(a, bytecode)
-                                .unwrap_or_default()
-                                .to_string()
-                        } else if let Some(n) = a.to_i32() {
-                            n.to_string()
-                        } else if a.is_bool() {
-                            if a.to_bool().unwrap_or(false) {
-                                "true"
-                            } else {
-                                "false"
-                            }
-                            .to_string()
-                        } else if a.is_null() {
-                            "null".to_string()
-                        } else if a.is_undefined() {
-                            "undefined".to_string()
-                        } else {
-                            "[object]".to_string()
-                        };
-
-                        let str_b = if b.is_string() {
-                            self.get_string_content(b, bytecode)
-                                .unwrap_or_default()
-                                .to_string()
-                        } else if let Some(n) = b.to_i32() {
-                            n.to_string()
-                        } else if b.is_bool() {
-                            if b.to_bool().unwrap_or(false) {
-                                "true"
-                            } else {
-                                "false"
-                            }
-                            .to_string()
-                        } else if b.is_null() {
-                            "null".to_string()
-                        } else if b.is_undefined() {
-                            "undefined".to_string()
-                        } else {
-                            "[object]".to_string()
+                        // Build the result at its final length in one allocation,
+                        // once that length is known to be allowed
+                        let joined = {
+                            let piece_a = self.concat_operand(a, bytecode);
+                            let piece_b = self.concat_operand(b, bytecode);
+                            let len = piece_a.len().saturating_add(piece_b.len());
+                            self.check_string_length(len)?;
+                            let mut joined = String::with_capacity(len);
+                            joined.push_str(&piece_a);
+                            joined.push_str(&piece_b);
+                            joined
                         };
 
-                        let result = self.create_runtime_string(str_a + &str_b);
+                        // The operands are copied out, so nothing the
+                        // collector cannot see is left to keep alive
+                        self.check_memory_or_collect(joined.len())?;
+                        let result = self.create_runtime_string(joined);
                         self.stack.push(result);
                     } else {
                         let result = self.op_add(a, b)?;
@@ -1542,7 +6118,17 @@ impl Interpreter {
                 op if op == OpCode::Sub as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let result = self.op_sub(a, b)?;
+                    let result = match self.date_operands(a, b) {
+                        // Invalid Date gives NaN, stored as 0
+                        Some((Some(a), Some(b))) => {
+                            int_result(date::to_epoch_seconds(a) - date::to_epoch_seconds(b))?
+                        }
+                        Some(_) => Value::int(0),
+                        None => {
+                            let (a, b) = self.coerce_operands(a, b, PrimitiveHint::Number)?;
+                            self.op_sub(a, b)?
+                        }
+                    };
                     self.stack.push(result);
                 }
 
@@ -1550,6 +6136,7 @@ impl Interpreter {
                 op if op == OpCode::Mul as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    let (a, b) = self.coerce_operands(a, b, PrimitiveHint::Number)?;
                     let result = self.op_mul(a, b)?;
                     self.stack.push(result);
                 }
@@ -1558,6 +6145,7 @@ impl Interpreter {
                 op if op == OpCode::Div as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    let (a, b) = self.coerce_operands(a, b, PrimitiveHint::Number)?;
                     let result = self.op_div(a, b)?;
                     self.stack.push(result);
                 }
@@ -1566,6 +6154,7 @@ impl Interpreter {
                 op if op == OpCode::Mod as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    let (a, b) = self.coerce_operands(a, b, PrimitiveHint::Number)?;
                     let result = self.op_mod(a, b)?;
                     self.stack.push(result);
                 }
@@ -1574,7 +6163,13 @@ impl Interpreter {
                 op if op == OpCode::Lt as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let result = self.op_lt(a, b)?;
+                    let result = match self.compare_dates(a, b, core::cmp::Ordering::is_lt) {
+                        Some(result) => result,
+                        None => {
+                            let (a, b) = self.coerce_operands(a, b, PrimitiveHint::Number)?;
+                            self.op_lt(a, b)?
+                        }
+                    };
                     self.stack.push(result);
                 }
 
@@ -1582,7 +6177,13 @@ impl Interpreter {
                 op if op == OpCode::Lte as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let result = self.op_lte(a, b)?;
+                    let result = match self.compare_dates(a, b, core::cmp::Ordering::is_le) {
+                        Some(result) => result,
+                        None => {
+                            let (a, b) = self.coerce_operands(a, b, PrimitiveHint::Number)?;
+                            self.op_lte(a, b)?
+                        }
+                    };
                     self.stack.push(result);
                 }
 
@@ -1590,7 +6191,13 @@ impl Interpreter {
                 op if op == OpCode::Gt as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let result = self.op_gt(a, b)?;
+                    let result = match self.compare_dates(a, b, core::cmp::Ordering::is_gt) {
+                        Some(result) => result,
+                        None => {
+                            let (a, b) = self.coerce_operands(a, b, PrimitiveHint::Number)?;
+                            self.op_gt(a, b)?
+                        }
+                    };
                     self.stack.push(result);
                 }
 
@@ -1598,7 +6205,13 @@ impl Interpreter {
                 op if op == OpCode::Gte as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let result = self.op_gte(a, b)?;
+                    let result = match self.compare_dates(a, b, core::cmp::Ordering::is_ge) {
+                        Some(result) => result,
+                        None => {
+                            let (a, b) = self.coerce_operands(a, b, PrimitiveHint::Number)?;
+                            self.op_gte(a, b)?
+                        }
+                    };
                     self.stack.push(result);
                 }
 
@@ -1622,7 +6235,7 @@ impl Interpreter {
                 op if op == OpCode::StrictEq as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let result = Value::bool(a == b);
+                    let result = Value::bool(self.strict_equals(a, b));
                     self.stack.push(result);
                 }
 
@@ -1630,14 +6243,14 @@ impl Interpreter {
                 op if op == OpCode::StrictNeq as u8 => {
                     let b = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let a = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let result = Value::bool(a != b);
+                    let result = Value::bool(!self.strict_equals(a, b));
                     self.stack.push(result);
                 }
 
                 // Logical NOT
                 op if op == OpCode::LNot as u8 => {
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let result = Value::bool(!Self::value_to_bool(val));
+                    let result = Value::bool(!self.to_boolean(val));
                     self.stack.push(result);
                 }
 
@@ -1713,35 +6326,22 @@ impl Interpreter {
                 // Control flow: Goto
                 op if op == OpCode::Goto as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let offset = i32::from_le_bytes([
-                        bc[frame.pc],
-                        bc[frame.pc + 1],
-                        bc[frame.pc + 2],
-                        bc[frame.pc + 3],
-                    ]);
-                    frame.pc += 4;
-                    // offset is relative to the end of this instruction (after the 4-byte offset)
+                    let offset = frame.take_operand(OpCode::Goto);
+                    // offset is relative to the end of this instruction
                     frame.pc = (frame.pc as i32 + offset) as usize;
+                    if offset < 0 && self.deadline.is_some() {
+                        self.check_deadline()?;
+                    }
                 }
 
                 // Control flow: If false
                 op if op == OpCode::IfFalse as u8 => {
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let is_truthy = Self::value_to_bool(val);
+                    let is_truthy = self.to_boolean(val);
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let offset = i32::from_le_bytes([
-                        bc[frame.pc],
-                        bc[frame.pc + 1],
-                        bc[frame.pc + 2],
-                        bc[frame.pc + 3],
-                    ]);
-                    frame.pc += 4;
+                    let offset = frame.take_operand(OpCode::IfFalse);
                     if !is_truthy {
-                        // offset is relative to the end of this instruction (after the 4-byte offset)
+                        // offset is relative to the end of this instruction
                         frame.pc = (frame.pc as i32 + offset) as usize;
                     }
                 }
@@ -1749,19 +6349,11 @@ impl Interpreter {
                 // Control flow: If true
                 op if op == OpCode::IfTrue as u8 => {
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let is_truthy = Self::value_to_bool(val);
+                    let is_truthy = self.to_boolean(val);
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let offset = i32::from_le_bytes([
-                        bc[frame.pc],
-                        bc[frame.pc + 1],
-                        bc[frame.pc + 2],
-                        bc[frame.pc + 3],
-                    ]);
-                    frame.pc += 4;
+                    let offset = frame.take_operand(OpCode::IfTrue);
                     if is_truthy {
-                        // offset is relative to the end of this instruction (after the 4-byte offset)
+                        // offset is relative to the end of this instruction
                         frame.pc = (frame.pc as i32 + offset) as usize;
                     }
                 }
@@ -1774,10 +6366,10 @@ impl Interpreter {
                     let frame = self.call_stack.pop().ok_or_else(|| {
                         InterpreterError::InternalError("no call frame to return from".to_string())
                     })?;
+                    self.prune_exception_handlers();
 
-                    // Clean up locals from the stack
-                    let local_count = unsafe { (*frame.bytecode).local_count } as usize;
-                    self.stack.drop_n(local_count);
+                    // Clean up arguments and locals from the stack
+                    self.stack.truncate(frame.base());
 
                     // For constructor calls: if result is not an object, return 'this' instead
                     let final_result = if frame.is_constructor && !result.is_object() {
@@ -1810,10 +6402,10 @@ impl Interpreter {
                     let frame = self.call_stack.pop().ok_or_else(|| {
                         InterpreterError::InternalError("no call frame to return from".to_string())
                     })?;
+                    self.prune_exception_handlers();
 
-                    // Clean up locals from the stack
-                    let local_count = unsafe { (*frame.bytecode).local_count } as usize;
-                    self.stack.drop_n(local_count);
+                    // Clean up arguments and locals from the stack
+                    self.stack.truncate(frame.base());
 
                     // For constructor calls: if result is not an object, return 'this' instead
                     let final_result = if frame.is_constructor && !result.is_object() {
@@ -1840,14 +6432,12 @@ impl Interpreter {
 
                 // Function closure creation (16-bit function index)
                 op if op == OpCode::FClosure as u8 => {
-                    let frame = self.call_stack.last().unwrap();
+                    let frame = self.call_stack.last_mut().unwrap();
                     let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let pc = frame.pc;
                     let frame_ptr = frame.frame_ptr;
                     let closure_idx_current = frame.closure_idx;
 
-                    let func_idx = u16::from_le_bytes([bc[pc], bc[pc + 1]]) as usize;
+                    let func_idx = frame.take_operand(OpCode::FClosure) as usize;
 
                     // Get the inner function bytecode
                     let inner_func = bytecode.inner_functions.get(func_idx).ok_or_else(|| {
@@ -1877,14 +6467,15 @@ impl Interpreter {
                         };
                         var_refs.push(val);
                     }
+                    self.promote_all(&var_refs);
 
-                    // Update PC after we're done reading
-                    let frame = self.call_stack.last_mut().unwrap();
-                    frame.pc += 2;
-
-                    // Create closure or simple function reference based on whether there are captures
+                    // A function without captures is its bytecode pointer:
+                    // every evaluation of the same literal yields the same
+                    // value and allocates nothing. Unlike the specification,
+                    // `===` therefore holds between two evaluations of one
+                    // literal; distinct literals stay distinct.
                     let func_val = if !var_refs.is_empty() {
-                        self.create_closure(inner_func as *const _, var_refs)
+                        self.create_closure(inner_func as *const _, var_refs)?
                     } else {
                         Value::func_ptr(inner_func as *const _)
                     };
@@ -1895,527 +6486,66 @@ impl Interpreter {
                 // Function call (16-bit argc)
                 op if op == OpCode::Call as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let argc = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
-
-                    // Collect arguments (they were pushed in order)
-                    let mut args = Vec::with_capacity(argc);
-                    for _ in 0..argc {
-                        args.push(self.stack.pop().ok_or(InterpreterError::StackUnderflow)?);
-                    }
-                    args.reverse(); // Arguments were pushed left-to-right
+                    let argc = frame.take_operand(OpCode::Call) as usize;
+                    let args = self.pop_call_args(argc)?;
 
                     // Pop the function value
                     let func_val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
 
-                    // Check if this is a native function call
-                    if let Some(native_idx) = func_val.to_native_func_idx() {
-                        let result =
-                            self.call_native_func(native_idx, Value::undefined(), &args)?;
-                        self.stack.push(result);
-                        continue;
-                    }
-
-                    // Check if this is a builtin object called as a function
-                    if let Some(builtin_idx) = func_val.to_builtin_object_idx() {
-                        let result = self.call_builtin_as_function(builtin_idx, &args)?;
+                    let outcome =
+                        self.push_call_frame(func_val, Value::undefined(), &args, CallFlags::CALL)?;
+                    if let CallOutcome::Returned(result) = outcome {
                         self.stack.push(result);
-                        continue;
-                    }
-
-                    // Determine if this is a closure or a regular function
-                    let (callee_bytecode, callee_closure_idx): (&FunctionBytecode, Option<usize>) =
-                        if let Some(closure_idx) = func_val.to_closure_idx() {
-                            // Closure call - get bytecode from closure
-                            let closure = self.get_closure(closure_idx).ok_or_else(|| {
-                                InterpreterError::InternalError(format!(
-                                    "invalid closure index: {}",
-                                    closure_idx
-                                ))
-                            })?;
-                            (unsafe { &*closure.bytecode }, Some(closure_idx as usize))
-                        } else if let Some(ptr) = func_val.to_func_ptr() {
-                            // Pointer-based function (from FClosure without captures or ThisFunc)
-                            (unsafe { &*ptr }, None)
-                        } else if let Some(idx) = func_val.to_func_idx() {
-                            // Index-based function (legacy, shouldn't happen anymore)
-                            let bc =
-                                bytecode.inner_functions.get(idx as usize).ok_or_else(|| {
-                                    InterpreterError::InternalError(format!(
-                                        "invalid function index: {}",
-                                        idx
-                                    ))
-                                })?;
-                            (bc, None)
-                        } else {
-                            return Err(InterpreterError::TypeError("not a function".to_string()));
-                        };
-
-                    // Check recursion limit
-                    if self.call_stack.len() >= self.max_recursion {
-                        return Err(InterpreterError::InternalError(
-                            "maximum call stack size exceeded".to_string(),
-                        ));
                     }
-
-                    let callee_frame_ptr = self.stack.len();
-
-                    // Push arguments (pad with undefined if needed)
-                    for i in 0..callee_bytecode.arg_count as usize {
-                        let arg = args.get(i).copied().unwrap_or_default();
-                        self.stack.push(arg);
-                    }
-
-                    // Allocate space for locals (beyond arguments)
-                    let extra_locals = callee_bytecode
-                        .local_count
-                        .saturating_sub(callee_bytecode.arg_count);
-                    for _ in 0..extra_locals {
-                        self.stack.push(Value::undefined());
-                    }
-
-                    // Create frame - with closure_idx if this is a closure call
-                    let callee_frame = if let Some(closure_idx) = callee_closure_idx {
-                        CallFrame::new_closure(
-                            callee_bytecode as *const _,
-                            callee_frame_ptr,
-                            args.len().min(u16::MAX as usize) as u16,
-                            Value::undefined(), // this value
-                            func_val,           // the function value for self-reference
-                            closure_idx,
-                        )
-                    } else {
-                        CallFrame::new(
-                            callee_bytecode as *const _,
-                            callee_frame_ptr,
-                            args.len().min(u16::MAX as usize) as u16,
-                            Value::undefined(), // this value
-                            func_val,           // the function value for self-reference
-                        )
-                    };
-                    self.call_stack.push(callee_frame);
-
-                    // Continue execution in the new frame (run loop will pick it up)
                 }
 
                 // CallConstructor - new operator: func args -> new_object
                 op if op == OpCode::CallConstructor as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let argc = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
-
-                    // Collect arguments (they were pushed in order)
-                    let mut args = Vec::with_capacity(argc);
-                    for _ in 0..argc {
-                        args.push(self.stack.pop().ok_or(InterpreterError::StackUnderflow)?);
-                    }
-                    args.reverse(); // Arguments were pushed left-to-right
+                    let argc = frame.take_operand(OpCode::CallConstructor) as usize;
+                    let args = self.pop_call_args(argc)?;
 
                     // Pop the constructor function value
                     let func_val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
 
-                    // Check if this is a builtin Error constructor
-                    if let Some(builtin_idx) = func_val.to_builtin_object_idx() {
-                        let error_name = match builtin_idx {
-                            BUILTIN_ERROR => Some("Error"),
-                            BUILTIN_TYPE_ERROR => Some("TypeError"),
-                            BUILTIN_REFERENCE_ERROR => Some("ReferenceError"),
-                            BUILTIN_SYNTAX_ERROR => Some("SyntaxError"),
-                            BUILTIN_RANGE_ERROR => Some("RangeError"),
-                            BUILTIN_EVAL_ERROR => Some("EvalError"),
-                            BUILTIN_URI_ERROR => Some("URIError"),
-                            BUILTIN_INTERNAL_ERROR => Some("InternalError"),
-                            _ => None,
-                        };
-
-                        if let Some(error_name) = error_name {
-                            // Create an error object
-
-                            // Get message from first argument (if present)
-                            let message = if let Some(msg_val) = args.first() {
-                                if let Some(str_idx) = msg_val.to_string_idx() {
-                                    self.get_string_by_idx(str_idx)
-                                        .map(|s| s.to_string())
-                                        .unwrap_or_default()
-                                } else if let Some(n) = msg_val.to_i32() {
-                                    n.to_string()
-                                } else {
-                                    String::new()
-                                }
-                            } else {
-                                String::new()
-                            };
-
-                            // Create and store the error object
-                            let error_idx = self.error_objects.len() as u32;
-                            self.error_objects.push(ErrorObject {
-                                name: error_name.to_string(),
-                                message,
-                            });
-
-                            // Push the error object value
-                            self.stack.push(Value::error_object(error_idx));
-                            continue;
-                        }
-
-                        // Check if this is the RegExp constructor
-                        if builtin_idx == BUILTIN_REGEXP {
-                            // Get pattern from first argument
-                            let pattern = if let Some(pattern_val) = args.first() {
-                                if let Some(str_idx) = pattern_val.to_string_idx() {
-                                    self.get_string_by_idx(str_idx)
-                                        .map(|s| s.to_string())
-                                        .unwrap_or_default()
-                                } else {
-                                    String::new()
-                                }
-                            } else {
-                                String::new()
-                            };
-
-                            // Get flags from second argument (if present)
-                            let flags = if let Some(flags_val) = args.get(1) {
-                                if let Some(str_idx) = flags_val.to_string_idx() {
-                                    self.get_string_by_idx(str_idx)
-                                        .map(|s| s.to_string())
-                                        .unwrap_or_default()
-                                } else {
-                                    String::new()
-                                }
-                            } else {
-                                String::new()
-                            };
-
-                            // Parse flags
-                            let global = flags.contains('g');
-                            let ignore_case = flags.contains('i');
-                            let multiline = flags.contains('m');
-
-                            // Build regex pattern with flags
-                            let mut regex_pattern = String::new();
-                            if ignore_case || multiline {
-                                regex_pattern.push_str("(?");
-                                if ignore_case {
-                                    regex_pattern.push('i');
-                                }
-                                if multiline {
-                                    regex_pattern.push('m');
-                                }
-                                regex_pattern.push(')');
-                            }
-                            regex_pattern.push_str(&pattern);
-
-                            // Compile the regex
-                            match regex::Regex::new(&regex_pattern) {
-                                Ok(regex) => {
-                                    let regex_idx = self.regex_objects.len() as u32;
-                                    self.regex_objects.push(RegExpObject {
-                                        regex,
-                                        pattern,
-                                        flags,
-                                        global,
-                                        ignore_case,
-                                        multiline,
-                                    });
-                                    self.stack.push(Value::regexp_object(regex_idx));
-                                }
-                                Err(e) => {
-                                    // Invalid regex - return a SyntaxError
-                                    return Err(InterpreterError::InternalError(format!(
-                                        "Invalid regular expression: {}",
-                                        e
-                                    )));
-                                }
-                            }
-                            continue;
-                        }
-
-                        // Check if this is a TypedArray constructor
-                        let typed_kind = match builtin_idx {
-                            BUILTIN_INT8_ARRAY => Some(TypedArrayKind::Int8),
-                            BUILTIN_UINT8_ARRAY => Some(TypedArrayKind::Uint8),
-                            BUILTIN_UINT8_CLAMPED_ARRAY => Some(TypedArrayKind::Uint8Clamped),
-                            BUILTIN_INT16_ARRAY => Some(TypedArrayKind::Int16),
-                            BUILTIN_UINT16_ARRAY => Some(TypedArrayKind::Uint16),
-                            BUILTIN_INT32_ARRAY => Some(TypedArrayKind::Int32),
-                            BUILTIN_UINT32_ARRAY => Some(TypedArrayKind::Uint32),
-                            BUILTIN_FLOAT32_ARRAY => Some(TypedArrayKind::Float32),
-                            BUILTIN_FLOAT64_ARRAY => Some(TypedArrayKind::Float64),
-                            _ => None,
-                        };
-
-                        if let Some(kind) = typed_kind {
-                            // Get length from first argument
-                            let length = if let Some(len_val) = args.first() {
-                                if let Some(n) = len_val.to_i32() {
-                                    n.max(0) as usize
-                                } else if len_val.is_array() {
-                                    // Creating from an array
-                                    if let Some(arr_idx) = len_val.to_array_idx() {
-                                        self.arrays
-                                            .get(arr_idx as usize)
-                                            .map(|a| a.len())
-                                            .unwrap_or(0)
-                                    } else {
-                                        0
-                                    }
-                                } else {
-                                    0
-                                }
-                            } else {
-                                0
-                            };
-
-                            // Create the typed array
-                            let mut typed_arr = TypedArrayObject::new(kind, length);
-
-                            // If created from an array, copy values
-                            if let Some(src_val) = args.first()
-                                && let Some(arr_idx) = src_val.to_array_idx()
-                                && let Some(arr) = self.arrays.get(arr_idx as usize)
-                            {
-                                for (i, v) in arr.iter().enumerate() {
-                                    if i >= length {
-                                        break;
-                                    }
-                                    if let Some(n) = v.to_i32() {
-                                        typed_arr.set(i, n);
-                                    }
-                                }
-                            }
-
-                            let typed_idx = self.typed_arrays.len() as u32;
-                            self.typed_arrays.push(typed_arr);
-                            self.stack.push(Value::typed_array_object(typed_idx));
-                            continue;
-                        }
-
-                        // Check if this is an ArrayBuffer constructor
-                        if builtin_idx == BUILTIN_ARRAY_BUFFER {
-                            let byte_length = args
-                                .first()
-                                .and_then(|v| v.to_i32())
-                                .map(|n| n.max(0) as usize)
-                                .unwrap_or(0);
-
-                            let ab = ArrayBufferObject::new(byte_length);
-                            let ab_idx = self.array_buffers.len() as u32;
-                            self.array_buffers.push(ab);
-                            self.stack.push(Value::array_buffer_object(ab_idx));
-                            continue;
-                        }
-                    }
-
-                    // Create a new object for 'this', storing the constructor reference for instanceof
-                    let new_obj = self.create_object_with_constructor(func_val);
-
-                    // Determine if this is a closure or a regular function
-                    let (callee_bytecode, callee_closure_idx): (&FunctionBytecode, Option<usize>) =
-                        if let Some(closure_idx) = func_val.to_closure_idx() {
-                            let closure = self.get_closure(closure_idx).ok_or_else(|| {
-                                InterpreterError::InternalError(format!(
-                                    "invalid closure index: {}",
-                                    closure_idx
-                                ))
-                            })?;
-                            (unsafe { &*closure.bytecode }, Some(closure_idx as usize))
-                        } else if let Some(ptr) = func_val.to_func_ptr() {
-                            (unsafe { &*ptr }, None)
-                        } else if let Some(idx) = func_val.to_func_idx() {
-                            let bc =
-                                bytecode.inner_functions.get(idx as usize).ok_or_else(|| {
-                                    InterpreterError::InternalError(format!(
-                                        "invalid function index: {}",
-                                        idx
-                                    ))
-                                })?;
-                            (bc, None)
-                        } else {
-                            return Err(InterpreterError::TypeError(
-                                "not a constructor".to_string(),
-                            ));
-                        };
-
-                    // Check recursion limit
-                    if self.call_stack.len() >= self.max_recursion {
-                        return Err(InterpreterError::InternalError(
-                            "maximum call stack size exceeded".to_string(),
-                        ));
-                    }
-
-                    let callee_frame_ptr = self.stack.len();
-
-                    // Push arguments (pad with undefined if needed)
-                    for i in 0..callee_bytecode.arg_count as usize {
-                        let arg = args.get(i).copied().unwrap_or_default();
-                        self.stack.push(arg);
-                    }
-
-                    // Allocate space for locals (beyond arguments)
-                    let extra_locals = callee_bytecode
-                        .local_count
-                        .saturating_sub(callee_bytecode.arg_count);
-                    for _ in 0..extra_locals {
-                        self.stack.push(Value::undefined());
+                    // When a constructor frame returns, a non-object result is
+                    // replaced by the new object
+                    let outcome = self.push_call_frame(
+                        func_val,
+                        Value::undefined(),
+                        &args,
+                        CallFlags::CONSTRUCT,
+                    )?;
+                    if let CallOutcome::Returned(result) = outcome {
+                        self.stack.push(result);
                     }
-
-                    // Create frame with new object as 'this' - marked as constructor call
-                    let callee_frame = if let Some(closure_idx) = callee_closure_idx {
-                        CallFrame::new_closure_constructor(
-                            callee_bytecode as *const _,
-                            callee_frame_ptr,
-                            args.len().min(u16::MAX as usize) as u16,
-                            new_obj, // 'this' is the new object
-                            func_val,
-                            closure_idx,
-                        )
-                    } else {
-                        CallFrame::new_constructor(
-                            callee_bytecode as *const _,
-                            callee_frame_ptr,
-                            args.len().min(u16::MAX as usize) as u16,
-                            new_obj, // 'this' is the new object
-                            func_val,
-                        )
-                    };
-                    self.call_stack.push(callee_frame);
-
-                    // Continue execution in the new frame
-                    // When the constructor returns, do_return handles returning 'this'
-                    // if the return value isn't an object
                 }
 
                 // CallMethod - method call: obj method args... -> ret
                 // Stack before: [obj, method, arg0, arg1, ...]
                 op if op == OpCode::CallMethod as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let argc = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
-
-                    // Collect arguments (they were pushed in order)
-                    let mut args = Vec::with_capacity(argc);
-                    for _ in 0..argc {
-                        args.push(self.stack.pop().ok_or(InterpreterError::StackUnderflow)?);
-                    }
-                    args.reverse(); // Arguments were pushed left-to-right
+                    let argc = frame.take_operand(OpCode::CallMethod) as usize;
+                    let args = self.pop_call_args(argc)?;
 
                     // Pop the method value
                     let method_val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
 
                     // Pop the object (this value)
                     let this_val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    self.promote(this_val);
 
-                    // Check if this is a native function call
-                    if let Some(native_idx) = method_val.to_native_func_idx() {
-                        let result = self.call_native_func(native_idx, this_val, &args)?;
+                    let outcome =
+                        self.push_call_frame(method_val, this_val, &args, CallFlags::CALL)?;
+                    if let CallOutcome::Returned(result) = outcome {
                         self.stack.push(result);
-                        continue;
-                    }
-
-                    // Determine if this is a closure or a regular function
-                    let (callee_bytecode, callee_closure_idx): (&FunctionBytecode, Option<usize>) =
-                        if let Some(closure_idx) = method_val.to_closure_idx() {
-                            let closure = self.get_closure(closure_idx).ok_or_else(|| {
-                                InterpreterError::InternalError(format!(
-                                    "invalid closure index: {}",
-                                    closure_idx
-                                ))
-                            })?;
-                            (unsafe { &*closure.bytecode }, Some(closure_idx as usize))
-                        } else if let Some(ptr) = method_val.to_func_ptr() {
-                            (unsafe { &*ptr }, None)
-                        } else if let Some(idx) = method_val.to_func_idx() {
-                            let bc =
-                                bytecode.inner_functions.get(idx as usize).ok_or_else(|| {
-                                    InterpreterError::InternalError(format!(
-                                        "invalid function index: {}",
-                                        idx
-                                    ))
-                                })?;
-                            (bc, None)
-                        } else {
-                            return Err(InterpreterError::TypeError("not a function".to_string()));
-                        };
-
-                    // Check recursion limit
-                    if self.call_stack.len() >= self.max_recursion {
-                        return Err(InterpreterError::InternalError(
-                            "maximum call stack size exceeded".to_string(),
-                        ));
-                    }
-
-                    let callee_frame_ptr = self.stack.len();
-
-                    // Push arguments (pad with undefined if needed)
-                    for i in 0..callee_bytecode.arg_count as usize {
-                        let arg = args.get(i).copied().unwrap_or_default();
-                        self.stack.push(arg);
-                    }
-
-                    // Allocate space for locals (beyond arguments)
-                    let extra_locals = callee_bytecode
-                        .local_count
-                        .saturating_sub(callee_bytecode.arg_count);
-                    for _ in 0..extra_locals {
-                        self.stack.push(Value::undefined());
                     }
-
-                    // Create frame with the object as 'this'
-                    let callee_frame = if let Some(closure_idx) = callee_closure_idx {
-                        CallFrame::new_closure(
-                            callee_bytecode as *const _,
-                            callee_frame_ptr,
-                            args.len().min(u16::MAX as usize) as u16,
-                            this_val, // Pass the object as 'this'
-                            method_val,
-                            closure_idx,
-                        )
-                    } else {
-                        CallFrame::new(
-                            callee_bytecode as *const _,
-                            callee_frame_ptr,
-                            args.len().min(u16::MAX as usize) as u16,
-                            this_val, // Pass the object as 'this'
-                            method_val,
-                        )
-                    };
-                    self.call_stack.push(callee_frame);
                 }
 
                 // TypeOf operator
                 op if op == OpCode::TypeOf as u8 => {
-                    use crate::value::{
-                        STR_BOOLEAN, STR_FUNCTION, STR_NUMBER, STR_OBJECT, STR_STRING,
-                        STR_UNDEFINED,
-                    };
-
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
-                    let type_idx = if val.is_undefined() {
-                        STR_UNDEFINED
-                    } else if val.is_null() {
-                        STR_OBJECT // typeof null === "object" (JavaScript quirk)
-                    } else if val.is_bool() {
-                        STR_BOOLEAN
-                    } else if val.is_int() {
-                        STR_NUMBER
-                    } else if val.is_string() {
-                        STR_STRING
-                    } else if val.is_func()
-                        || val.to_func_ptr().is_some()
-                        || val.is_closure()
-                        || val.is_native_func()
-                    {
-                        STR_FUNCTION
-                    } else {
-                        // Objects, arrays, and all other pointers/objects
-                        STR_OBJECT
-                    };
+                    let type_idx = self.type_of(val);
                     self.stack.push(Value::string(type_idx));
                 }
 
@@ -2424,133 +6554,147 @@ impl Interpreter {
                     // Do nothing
                 }
 
+                op if op == OpCode::EndStatement as u8 => {
+                    self.release_temp_arrays();
+                }
+
                 // Print (built-in print statement)
                 op if op == OpCode::Print as u8 => {
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let frame = self.call_stack.last().unwrap();
                     let bytecode = unsafe { &*frame.bytecode };
 
+                    // Integers print straight from a stack buffer
+                    if let Some(n) = val.to_i32() {
+                        self.write_console(ConsoleLevel::Log, IntStr::new(n).as_str());
+                        continue;
+                    }
+
                     // Convert value to string representation
-                    let output = if val.is_string() {
-                        self.get_string_content(val, bytecode)
+                    let output = match val.classify() {
+                        ValueTag::String => self
+                            .get_string_content(val, bytecode)
                             .unwrap_or_default()
-                            .to_string()
-                    } else if let Some(n) = val.to_i32() {
-                        n.to_string()
-                    } else if val.is_bool() {
-                        if val.to_bool().unwrap_or(false) {
-                            "true"
-                        } else {
-                            "false"
+                            .to_string(),
+                        ValueTag::Bool => val.to_bool().unwrap_or(false).to_string(),
+                        ValueTag::Int => val.to_i32().unwrap_or(0).to_string(),
+                        ValueTag::Null => "null".to_string(),
+                        ValueTag::Undefined | ValueTag::Exception | ValueTag::Uninitialized => {
+                            "undefined".to_string()
                         }
-                        .to_string()
-                    } else if val.is_null() {
-                        "null".to_string()
-                    } else if val.is_undefined() {
-                        "undefined".to_string()
-                    } else if val.is_func() || val.to_func_ptr().is_some() {
-                        "[function]".to_string()
-                    } else {
-                        "[object]".to_string()
+                        ValueTag::Closure
+                        | ValueTag::Bytecode
+                        | ValueTag::ShortFunc
+                        | ValueTag::NativeFunc => "[function]".to_string(),
+                        ValueTag::Object
+                        | ValueTag::Array
+                        | ValueTag::Builtin
+                        | ValueTag::Error
+                        | ValueTag::RegExp
+                        | ValueTag::TypedArray
+                        | ValueTag::ArrayBuffer
+                        | ValueTag::ForInIterator
+                        | ValueTag::ForOfIterator => "[object]".to_string(),
                     };
 
-                    println!("{}", output);
+                    self.write_console(ConsoleLevel::Log, &output);
                 }
 
                 // GetGlobal - look up global variable by name
                 op if op == OpCode::GetGlobal as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
                     let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let name_idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]);
-                    frame.pc += 2;
+                    let name_idx = frame.take_operand(OpCode::GetGlobal) as u16;
 
                     // Get the name from constant pool
                     let name = bytecode
                         .constants
                         .get(name_idx as usize)
-                        .and_then(|v| {
-                            if v.is_string() {
-                                let str_idx = v.to_string_idx()?;
-                                bytecode
-                                    .string_constants
-                                    .get(str_idx as usize)
-                                    .map(|s| s.as_str())
-                            } else {
-                                None
-                            }
-                        })
+                        .and_then(|v| self.get_string_content(*v, bytecode))
                         .ok_or_else(|| {
                             InterpreterError::InternalError(format!(
                                 "invalid global name constant: {}",
                                 name_idx
                             ))
                         })?;
+                    let name: &str = &name;
+
+                    // Look up the global by name
+                    // User globals shadow builtins, then special global values and builtin objects
+                    let val = self.get_global(name).or_else(|| self.builtin_global(name));
+
+                    if let Some(v) = val {
+                        self.stack.push(v);
+                    } else {
+                        return Err(InterpreterError::ReferenceError(format!(
+                            "{} is not defined",
+                            name
+                        )));
+                    }
+                }
+
+                // PutGlobal - create or update a global binding by name
+                op if op == OpCode::PutGlobal as u8 => {
+                    let frame = self.call_stack.last_mut().unwrap();
+                    let bytecode = unsafe { &*frame.bytecode };
+                    let name_idx = frame.take_operand(OpCode::PutGlobal) as u16;
+
+                    let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    self.promote(val);
+                    let name = self.global_name(bytecode, name_idx)?;
+
+                    self.set_global(&name, val);
+                }
+
+                // DefineGlobal - publish a declared top-level binding
+                op if op == OpCode::DefineGlobal as u8 => {
+                    let frame = self.call_stack.last_mut().unwrap();
+                    let bytecode = unsafe { &*frame.bytecode };
+                    let name_idx = frame.take_operand(OpCode::DefineGlobal) as u16;
+
+                    let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    self.promote(val);
+                    let name = self.global_name(bytecode, name_idx)?;
 
-                    // Look up the global by name
-                    // First check for special global values and builtin objects
-                    let val = match name {
-                        "undefined" => Some(Value::undefined()),
-                        "NaN" => Some(Value::int(0)), // TODO: proper NaN when floats are added
-                        "Infinity" => Some(Value::int(i32::MAX)), // TODO: proper infinity when floats are added
-                        "Math" => Some(Value::builtin_object(BUILTIN_MATH)),
-                        "JSON" => Some(Value::builtin_object(BUILTIN_JSON)),
-                        "Number" => Some(Value::builtin_object(BUILTIN_NUMBER)),
-                        "Boolean" => Some(Value::builtin_object(BUILTIN_BOOLEAN)),
-                        "String" => Some(Value::builtin_object(BUILTIN_STRING)),
-                        "Object" => Some(Value::builtin_object(BUILTIN_OBJECT)),
-                        "Array" => Some(Value::builtin_object(BUILTIN_ARRAY)),
-                        "console" => Some(Value::builtin_object(BUILTIN_CONSOLE)),
-                        "performance" => Some(Value::builtin_object(BUILTIN_PERFORMANCE)),
-                        "Date" => Some(Value::builtin_object(BUILTIN_DATE)),
-                        "Error" => Some(Value::builtin_object(BUILTIN_ERROR)),
-                        "TypeError" => Some(Value::builtin_object(BUILTIN_TYPE_ERROR)),
-                        "ReferenceError" => Some(Value::builtin_object(BUILTIN_REFERENCE_ERROR)),
-                        "SyntaxError" => Some(Value::builtin_object(BUILTIN_SYNTAX_ERROR)),
-                        "RangeError" => Some(Value::builtin_object(BUILTIN_RANGE_ERROR)),
-                        "EvalError" => Some(Value::builtin_object(BUILTIN_EVAL_ERROR)),
-                        "URIError" => Some(Value::builtin_object(BUILTIN_URI_ERROR)),
-                        "InternalError" => Some(Value::builtin_object(BUILTIN_INTERNAL_ERROR)),
-                        "RegExp" => Some(Value::builtin_object(BUILTIN_REGEXP)),
-                        "globalThis" => Some(Value::builtin_object(BUILTIN_GLOBAL_THIS)),
-                        // TypedArray and ArrayBuffer constructors
-                        "ArrayBuffer" => Some(Value::builtin_object(BUILTIN_ARRAY_BUFFER)),
-                        "Int8Array" => Some(Value::builtin_object(BUILTIN_INT8_ARRAY)),
-                        "Uint8Array" => Some(Value::builtin_object(BUILTIN_UINT8_ARRAY)),
-                        "Uint8ClampedArray" => {
-                            Some(Value::builtin_object(BUILTIN_UINT8_CLAMPED_ARRAY))
-                        }
-                        "Int16Array" => Some(Value::builtin_object(BUILTIN_INT16_ARRAY)),
-                        "Uint16Array" => Some(Value::builtin_object(BUILTIN_UINT16_ARRAY)),
-                        "Int32Array" => Some(Value::builtin_object(BUILTIN_INT32_ARRAY)),
-                        "Uint32Array" => Some(Value::builtin_object(BUILTIN_UINT32_ARRAY)),
-                        "Float32Array" => Some(Value::builtin_object(BUILTIN_FLOAT32_ARRAY)),
-                        "Float64Array" => Some(Value::builtin_object(BUILTIN_FLOAT64_ARRAY)),
-                        _ => self.get_native_func(name),
-                    };
+                    self.set_global(&name, val);
+                    self.declared_globals.insert(name);
+                }
 
-                    if let Some(v) = val {
-                        self.stack.push(v);
-                    } else {
-                        return Err(InterpreterError::ReferenceError(format!(
-                            "{} is not defined",
-                            name
-                        )));
+                // DeleteGlobal - delete a global binding by name
+                op if op == OpCode::DeleteGlobal as u8 => {
+                    let frame = self.call_stack.last_mut().unwrap();
+                    let bytecode = unsafe { &*frame.bytecode };
+                    let name_idx = frame.take_operand(OpCode::DeleteGlobal) as u16;
+
+                    let name = self.global_name(bytecode, name_idx)?;
+                    let deleted = self.delete_global(&name);
+                    self.stack.push(Value::bool(deleted));
+                }
+
+                // CopyDataProperties - object spread and rest:
+                // target source keys... -> target
+                op if op == OpCode::CopyDataProperties as u8 => {
+                    let frame = self.call_stack.last_mut().unwrap();
+                    let key_count = frame.take_operand(OpCode::CopyDataProperties) as usize;
+
+                    let mut excluded = Vec::with_capacity(key_count);
+                    for _ in 0..key_count {
+                        let key = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                        excluded.push(
+                            self.get_string(key)
+                                .map(|s| s.to_string())
+                                .unwrap_or_default(),
+                        );
                     }
+                    let source = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    let target = self.stack.peek().ok_or(InterpreterError::StackUnderflow)?;
+                    self.copy_data_properties(target, source, &excluded)?;
                 }
 
                 // Catch - set up exception handler
                 op if op == OpCode::Catch as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let offset = i32::from_le_bytes([
-                        bc[frame.pc],
-                        bc[frame.pc + 1],
-                        bc[frame.pc + 2],
-                        bc[frame.pc + 3],
-                    ]);
-                    frame.pc += 4;
+                    let offset = frame.take_operand(OpCode::Catch);
 
                     // Calculate catch PC (relative to end of instruction)
                     let catch_pc = (frame.pc as i32 + offset) as usize;
@@ -2572,49 +6716,16 @@ impl Interpreter {
                 // Throw - throw exception
                 op if op == OpCode::Throw as u8 => {
                     let exception = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    self.promote(exception);
 
-                    // Find the nearest exception handler
-                    if let Some(handler) = self.exception_handlers.pop() {
-                        // Unwind call stack to the handler's frame
-                        while self.call_stack.len() > handler.frame_depth {
-                            self.call_stack.pop();
-                        }
-
-                        // Restore stack to handler's depth
-                        while self.stack.len() > handler.stack_depth {
-                            self.stack.pop();
-                        }
-
-                        // Push the exception value for the catch block
-                        self.stack.push(exception);
-
-                        // Jump to catch block
-                        if let Some(frame) = self.call_stack.last_mut() {
-                            frame.pc = handler.catch_pc;
-                        } else {
-                            // No more frames - unhandled exception
-                            return Err(InterpreterError::InternalError(format!(
-                                "Uncaught exception: {:?}",
-                                exception
-                            )));
-                        }
-                    } else {
-                        // No handler - unhandled exception
-                        return Err(InterpreterError::InternalError(format!(
-                            "Uncaught exception: {:?}",
-                            exception
-                        )));
-                    }
+                    // Caught by this loop's handlers or propagated to the caller
+                    return Err(InterpreterError::Exception(exception));
                 }
 
                 // ArrayFrom - create array from stack elements
                 op if op == OpCode::ArrayFrom as u8 => {
-                    let frame = self.call_stack.last_mut().unwrap();
-                    let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
                     // Read number of elements (16-bit)
-                    let count = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let count = frame.take_operand(OpCode::ArrayFrom) as usize;
 
                     // Pop elements from stack (they were pushed in order)
                     let mut elements = Vec::with_capacity(count);
@@ -2622,9 +6733,10 @@ impl Interpreter {
                         elements.push(self.stack.pop().ok_or(InterpreterError::StackUnderflow)?);
                     }
                     elements.reverse(); // Elements were pushed left-to-right
+                    self.promote_all(&elements);
 
                     // Create array and push reference
-                    let arr_val = self.create_array(elements);
+                    let arr_val = self.alloc_array(elements);
                     self.stack.push(arr_val);
                 }
 
@@ -2645,45 +6757,17 @@ impl Interpreter {
                                 // SAFETY: We just checked index < len
                                 unsafe { *array.get_unchecked(index) }
                             } else {
-                                Value::undefined()
+                                // Past the end, or stored sparsely
+                                self.array_element(arr_idx, index)
                             };
                             self.stack.push(val);
                             continue;
                         }
                     }
 
-                    // Check if it's a typed array
-                    if let Some(typed_idx) = arr.to_typed_array_idx() {
-                        let index = idx.to_i32().ok_or_else(|| {
-                            InterpreterError::TypeError(
-                                "typed array index must be a number".to_string(),
-                            )
-                        })? as usize;
-
-                        let val = self
-                            .typed_arrays
-                            .get(typed_idx as usize)
-                            .and_then(|ta| ta.get(index))
-                            .map(Value::int)
-                            .unwrap_or_default();
-                        self.stack.push(val);
-                        continue;
-                    }
-
-                    // Slow path for non-array or non-integer index
-                    let arr_idx = arr.to_array_idx().ok_or_else(|| {
-                        InterpreterError::TypeError("cannot read property of non-array".to_string())
-                    })?;
-
-                    let array = self.get_array(arr_idx).ok_or_else(|| {
-                        InterpreterError::InternalError("invalid array index".to_string())
-                    })?;
-
-                    let index = idx.to_i32().ok_or_else(|| {
-                        InterpreterError::TypeError("array index must be a number".to_string())
-                    })? as usize;
-
-                    let val = array.get(index).copied().unwrap_or_default();
+                    let frame = self.call_stack.last().unwrap();
+                    let bytecode = unsafe { &*frame.bytecode };
+                    let val = self.get_element(arr, idx, bytecode)?;
                     self.stack.push(val);
                 }
 
@@ -2692,39 +6776,9 @@ impl Interpreter {
                     let idx = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let arr = self.stack.peek().ok_or(InterpreterError::StackUnderflow)?;
 
-                    // Check if it's a typed array
-                    if let Some(typed_idx) = arr.to_typed_array_idx() {
-                        let index = idx.to_i32().ok_or_else(|| {
-                            InterpreterError::TypeError(
-                                "typed array index must be a number".to_string(),
-                            )
-                        })? as usize;
-
-                        let val = self
-                            .typed_arrays
-                            .get(typed_idx as usize)
-                            .and_then(|ta| ta.get(index))
-                            .map(Value::int)
-                            .unwrap_or_default();
-                        self.stack.push(val);
-                        continue;
-                    }
-
-                    // Get the array
-                    let arr_idx = arr.to_array_idx().ok_or_else(|| {
-                        InterpreterError::TypeError("cannot read property of non-array".to_string())
-                    })?;
-
-                    let array = self.get_array(arr_idx).ok_or_else(|| {
-                        InterpreterError::InternalError("invalid array index".to_string())
-                    })?;
-
-                    // Get the element
-                    let index = idx.to_i32().ok_or_else(|| {
-                        InterpreterError::TypeError("array index must be a number".to_string())
-                    })? as usize;
-
-                    let val = array.get(index).copied().unwrap_or_default();
+                    let frame = self.call_stack.last().unwrap();
+                    let bytecode = unsafe { &*frame.bytecode };
+                    let val = self.get_element(arr, idx, bytecode)?;
                     self.stack.push(val);
                 }
 
@@ -2732,6 +6786,7 @@ impl Interpreter {
                 op if op == OpCode::PutArrayEl as u8 => {
                     // SAFETY: Stack operations are valid for well-formed bytecode
                     let (val, idx, arr) = unsafe { self.stack.pop3_unchecked() };
+                    self.promote(val);
 
                     // Fast path: regular array with integer index within bounds
                     if arr.is_array() && idx.is_int() {
@@ -2744,113 +6799,88 @@ impl Interpreter {
                             if index < array.len() {
                                 // SAFETY: We just checked index < len
                                 unsafe { *array.get_unchecked_mut(index) = val };
+                                if !self.array_holes.is_empty() {
+                                    self.fill_array_hole(arr_idx, index);
+                                }
                             } else {
                                 // Extend array if index is out of bounds
-                                array.resize(index + 1, Value::undefined());
-                                array[index] = val;
+                                self.set_array_element(arr_idx, index, val)?;
                             }
                             self.stack.push(val);
                             continue;
                         }
                     }
 
-                    // Check if it's a typed array
-                    if let Some(typed_idx) = arr.to_typed_array_idx() {
-                        let index = idx.to_i32().ok_or_else(|| {
-                            InterpreterError::TypeError(
-                                "typed array index must be a number".to_string(),
-                            )
-                        })? as usize;
-
-                        let int_val = val.to_i32().unwrap_or(0);
-                        if let Some(ta) = self.typed_arrays.get_mut(typed_idx as usize) {
-                            ta.set(index, int_val);
-                        }
-                        self.stack.push(val);
-                        continue;
-                    }
+                    let frame = self.call_stack.last().unwrap();
+                    let bytecode = unsafe { &*frame.bytecode };
+                    self.set_element(arr, idx, val, bytecode)?;
 
-                    // Slow path for non-array or non-integer index
-                    let arr_idx = arr.to_array_idx().ok_or_else(|| {
-                        InterpreterError::TypeError("cannot set property of non-array".to_string())
-                    })?;
+                    // Push the assigned value back (assignment is an expression)
+                    self.stack.push(val);
+                }
 
-                    let index = idx.to_i32().ok_or_else(|| {
-                        InterpreterError::TypeError("array index must be a number".to_string())
-                    })? as usize;
+                // Object - create an empty plain object; the compiler only
+                // emits class 0
+                op if op == OpCode::Object as u8 => {
+                    let frame = self.call_stack.last_mut().unwrap();
+                    frame.take_operand(OpCode::Object);
+                    let obj = self.alloc_object().finish();
+                    self.stack.push(obj);
+                }
 
-                    let array = self.get_array_mut(arr_idx).ok_or_else(|| {
-                        InterpreterError::InternalError("invalid array index".to_string())
-                    })?;
+                // DefineField - define an own property of an object
+                // literal: obj val -> obj
+                op if op == OpCode::DefineField as u8 => {
+                    let frame = self.call_stack.last_mut().unwrap();
+                    let bytecode = unsafe { &*frame.bytecode };
+                    let str_idx = frame.take_operand(OpCode::DefineField) as usize;
+
+                    let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    let obj = self.stack.peek().ok_or(InterpreterError::StackUnderflow)?;
+                    self.promote(val);
 
-                    // Extend array if index is out of bounds
-                    if index >= array.len() {
-                        array.resize(index + 1, Value::undefined());
+                    let key = self.field_atom(bytecode, str_idx)?;
+                    if self.hardened {
+                        let name = self.atoms.shared_name(key);
+                        self.check_hardened_access(obj, &name, true)?;
+                    }
+                    if let Some(obj_idx) = obj.to_object_idx() {
+                        self.object_set_property(obj_idx, key, val);
                     }
-                    array[index] = val;
+                }
 
-                    // Push the assigned value back (assignment is an expression)
-                    self.stack.push(val);
+                // DefineArrayEl - define an own property under a computed
+                // key, converted with ToString: obj key val -> obj
+                op if op == OpCode::DefineArrayEl as u8 => {
+                    let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    let key = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    let obj = self.stack.peek().ok_or(InterpreterError::StackUnderflow)?;
+                    self.promote(val);
+
+                    let key = match self.string_content(key, &mut HashSet::new()) {
+                        Ok(key) => key,
+                        Err(err) => return Err(self.native_error(err)),
+                    };
+                    if self.hardened {
+                        self.check_hardened_access(obj, &key, true)?;
+                    }
+                    if let Some(obj_idx) = obj.to_object_idx() {
+                        let key = self.atom(&key);
+                        self.object_set_property(obj_idx, key, val);
+                    }
                 }
 
                 // GetField - get object property: obj -> value
                 op if op == OpCode::GetField as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
                     let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let str_idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let str_idx = frame.take_operand(OpCode::GetField) as usize;
 
                     let obj = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
 
-                    // Get property name from string constants
-                    let prop_name = bytecode.string_constants.get(str_idx).ok_or_else(|| {
-                        InterpreterError::InternalError(format!(
-                            "invalid string index: {}",
-                            str_idx
-                        ))
-                    })?;
-
-                    // Check if this is a builtin object (Math, JSON, etc.)
-                    if let Some(builtin_idx) = obj.to_builtin_object_idx() {
-                        let val = self.get_builtin_property(builtin_idx, prop_name);
-                        self.stack.push(val);
-                    } else if let Some(typed_idx) = obj.to_typed_array_idx() {
-                        // TypedArray property access
-                        let val = self.get_typed_array_property(typed_idx, prop_name);
-                        self.stack.push(val);
-                    } else if let Some(ab_idx) = obj.to_array_buffer_idx() {
-                        // ArrayBuffer property access
-                        let val = self.get_array_buffer_property(ab_idx, prop_name);
-                        self.stack.push(val);
-                    } else if obj.is_array() {
-                        // Array property access - check for Array.prototype methods
-                        let val = self.get_array_property(obj, prop_name);
-                        self.stack.push(val);
-                    } else if let Some(err_idx) = obj.to_error_object_idx() {
-                        // Error object property access
-                        let val = self.get_error_property(err_idx, prop_name);
-                        self.stack.push(val);
-                    } else if let Some(regex_idx) = obj.to_regexp_object_idx() {
-                        // RegExp object property access
-                        let val = self.get_regexp_property(regex_idx, prop_name);
-                        self.stack.push(val);
-                    } else if let Some(obj_idx) = obj.to_object_idx() {
-                        // Get property from regular object
-                        let val = self.object_get_property(obj_idx, prop_name);
-                        self.stack.push(val);
-                    } else if obj.is_string() {
-                        // String property access - check for String.prototype methods
-                        let val = self.get_string_property(obj, prop_name);
-                        self.stack.push(val);
-                    } else if obj.to_i32().is_some() {
-                        // Number property access - check for Number.prototype methods
-                        let val = self.get_number_property(obj, prop_name);
-                        self.stack.push(val);
-                    } else {
-                        // For non-objects, return undefined
-                        self.stack.push(Value::undefined());
-                    }
+                    let key = self.field_atom(bytecode, str_idx)?;
+                    let val = self.get_field(obj, key)?;
+                    self.stack.push(val);
                 }
 
                 // GetField2 - get object property but keep object: obj -> obj value
@@ -2858,45 +6888,13 @@ impl Interpreter {
                 op if op == OpCode::GetField2 as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
                     let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let str_idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let str_idx = frame.take_operand(OpCode::GetField2) as usize;
 
                     // Peek at the object (don't pop - we need to keep it for 'this')
                     let obj = self.stack.peek().ok_or(InterpreterError::StackUnderflow)?;
 
-                    // Get property name from string constants
-                    let prop_name = bytecode.string_constants.get(str_idx).ok_or_else(|| {
-                        InterpreterError::InternalError(format!(
-                            "invalid string index: {}",
-                            str_idx
-                        ))
-                    })?;
-
-                    // Get the property value (same logic as GetField)
-                    let val = if let Some(builtin_idx) = obj.to_builtin_object_idx() {
-                        self.get_builtin_property(builtin_idx, prop_name)
-                    } else if let Some(typed_idx) = obj.to_typed_array_idx() {
-                        self.get_typed_array_property(typed_idx, prop_name)
-                    } else if let Some(ab_idx) = obj.to_array_buffer_idx() {
-                        self.get_array_buffer_property(ab_idx, prop_name)
-                    } else if obj.is_array() {
-                        self.get_array_property(obj, prop_name)
-                    } else if let Some(regex_idx) = obj.to_regexp_object_idx() {
-                        self.get_regexp_property(regex_idx, prop_name)
-                    } else if let Some(obj_idx) = obj.to_object_idx() {
-                        self.object_get_property(obj_idx, prop_name)
-                    } else if obj.is_string() {
-                        self.get_string_property(obj, prop_name)
-                    } else if obj.to_i32().is_some() {
-                        // Number.prototype methods
-                        self.get_number_property(obj, prop_name)
-                    } else if obj.is_closure() || obj.to_func_ptr().is_some() {
-                        // Function.prototype methods (call, apply, bind)
-                        self.get_function_property(prop_name)
-                    } else {
-                        Value::undefined()
-                    };
+                    let key = self.field_atom(bytecode, str_idx)?;
+                    let val = self.get_field(obj, key)?;
 
                     // Push the property value (object is still on stack below it)
                     self.stack.push(val);
@@ -2906,29 +6904,23 @@ impl Interpreter {
                 op if op == OpCode::PutField as u8 => {
                     let frame = self.call_stack.last_mut().unwrap();
                     let bytecode = unsafe { &*frame.bytecode };
-                    let bc = &bytecode.bytecode;
-                    let str_idx = u16::from_le_bytes([bc[frame.pc], bc[frame.pc + 1]]) as usize;
-                    frame.pc += 2;
+                    let str_idx = frame.take_operand(OpCode::PutField) as usize;
 
                     let val = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let obj = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
+                    self.promote(val);
 
-                    // Get property name from string constants
-                    let prop_name = bytecode
-                        .string_constants
-                        .get(str_idx)
-                        .ok_or_else(|| {
-                            InterpreterError::InternalError(format!(
-                                "invalid string index: {}",
-                                str_idx
-                            ))
-                        })?
-                        .clone();
-
-                    // Set property on object
-                    if let Some(obj_idx) = obj.to_object_idx() {
-                        self.object_set_property(obj_idx, prop_name, val);
+                    let key = self.field_atom(bytecode, str_idx)?;
+                    match obj.to_object_idx() {
+                        Some(obj_idx) if !self.hardened => {
+                            self.object_set_property(obj_idx, key, val)
+                        }
+                        _ => {
+                            let name = self.atoms.shared_name(key);
+                            self.set_property(obj, &name, val)?;
+                        }
                     }
+
                     // Push the assigned value back (assignment is an expression)
                     self.stack.push(val);
                 }
@@ -2941,65 +6933,15 @@ impl Interpreter {
                     let obj = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let prop = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
 
-                    let result = if let Some(obj_idx) = obj.to_object_idx() {
-                        // Check if property exists in object
-                        // Convert prop to string, checking bytecode string constants
-                        let prop_name = if prop.is_string() {
-                            if let Some(str_idx) = prop.to_string_idx() {
-                                // Check built-in strings first
-                                use crate::value::{
-                                    STR_BOOLEAN, STR_EMPTY, STR_FUNCTION, STR_NUMBER, STR_OBJECT,
-                                    STR_STRING, STR_UNDEFINED,
-                                };
-                                match str_idx {
-                                    STR_UNDEFINED => Some("undefined".to_string()),
-                                    STR_OBJECT => Some("object".to_string()),
-                                    STR_BOOLEAN => Some("boolean".to_string()),
-                                    STR_NUMBER => Some("number".to_string()),
-                                    STR_FUNCTION => Some("function".to_string()),
-                                    STR_STRING => Some("string".to_string()),
-                                    STR_EMPTY => Some(String::new()),
-                                    _ => {
-                                        if str_idx >= 0x8000 {
-                                            self.runtime_strings
-                                                .get((str_idx - 0x8000) as usize)
-                                                .cloned()
-                                        } else {
-                                            // Compile-time string constant
-                                            bytecode.string_constants.get(str_idx as usize).cloned()
-                                        }
-                                    }
-                                }
-                            } else {
-                                None
-                            }
-                        } else {
-                            prop.to_i32().map(|n| n.to_string())
-                        };
-
-                        if let Some(name) = prop_name {
-                            let obj_props = self.get_object(obj_idx);
-                            let exists = obj_props
-                                .map(|props| props.properties.iter().any(|(k, _)| k == &name))
-                                .unwrap_or(false);
-                            Value::bool(exists)
-                        } else {
-                            Value::bool(false)
-                        }
-                    } else if let Some(arr_idx) = obj.to_array_idx() {
-                        // Check if index exists in array
-                        if let Some(idx) = prop.to_i32() {
-                            let arr = self.get_array(arr_idx);
-                            let exists = arr
-                                .map(|a| idx >= 0 && (idx as usize) < a.len())
-                                .unwrap_or(false);
-                            Value::bool(exists)
-                        } else {
-                            Value::bool(false)
-                        }
-                    } else {
-                        Value::bool(false)
-                    };
+                    if !PropertyReceiver::of(obj).is_object() {
+                        return Err(InterpreterError::TypeError(
+                            "cannot use 'in' operator on a non-object".to_string(),
+                        ));
+                    }
+                    let result =
+                        Value::bool(self.property_key(prop, bytecode).is_some_and(|name| {
+                            self.has_property(obj, &name, PropertyLookup::Chain)
+                        }));
                     self.stack.push(result);
                 }
 
@@ -3011,71 +6953,11 @@ impl Interpreter {
                     let prop = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let obj = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
 
-                    let result = if let Some(obj_idx) = obj.to_object_idx() {
-                        // Convert prop to string, checking bytecode string constants
-                        let prop_name = if prop.is_string() {
-                            if let Some(str_idx) = prop.to_string_idx() {
-                                use crate::value::{
-                                    STR_BOOLEAN, STR_EMPTY, STR_FUNCTION, STR_NUMBER, STR_OBJECT,
-                                    STR_STRING, STR_UNDEFINED,
-                                };
-                                match str_idx {
-                                    STR_UNDEFINED => Some("undefined".to_string()),
-                                    STR_OBJECT => Some("object".to_string()),
-                                    STR_BOOLEAN => Some("boolean".to_string()),
-                                    STR_NUMBER => Some("number".to_string()),
-                                    STR_FUNCTION => Some("function".to_string()),
-                                    STR_STRING => Some("string".to_string()),
-                                    STR_EMPTY => Some(String::new()),
-                                    _ => {
-                                        if str_idx >= 0x8000 {
-                                            self.runtime_strings
-                                                .get((str_idx - 0x8000) as usize)
-                                                .cloned()
-                                        } else {
-                                            bytecode.string_constants.get(str_idx as usize).cloned()
-                                        }
-                                    }
-                                }
-                            } else {
-                                None
-                            }
-                        } else {
-                            prop.to_i32().map(|n| n.to_string())
-                        };
-
-                        // Delete property from object
-                        if let Some(name) = prop_name {
-                            if let Some(obj_props) = self.get_object_mut(obj_idx) {
-                                let orig_len = obj_props.properties.len();
-                                obj_props.properties.retain(|(k, _)| k != &name);
-                                Value::bool(obj_props.properties.len() < orig_len)
-                            } else {
-                                Value::bool(false)
-                            }
-                        } else {
-                            Value::bool(false)
-                        }
-                    } else if let Some(arr_idx) = obj.to_array_idx() {
-                        // For arrays, set element to undefined (don't actually remove)
-                        if let Some(idx) = prop.to_i32() {
-                            if let Some(arr) = self.get_array_mut(arr_idx) {
-                                if idx >= 0 && (idx as usize) < arr.len() {
-                                    arr[idx as usize] = Value::undefined();
-                                    Value::bool(true)
-                                } else {
-                                    Value::bool(true) // Deleting non-existent index returns true
-                                }
-                            } else {
-                                Value::bool(false)
-                            }
-                        } else {
-                            Value::bool(false)
-                        }
-                    } else {
-                        Value::bool(true) // delete on non-object returns true
+                    let result = match self.property_key(prop, bytecode) {
+                        Some(name) => self.delete_property(obj, &name),
+                        None => true,
                     };
-                    self.stack.push(result);
+                    self.stack.push(Value::bool(result));
                 }
 
                 // InstanceOf operator: obj ctor -> bool
@@ -3083,32 +6965,7 @@ impl Interpreter {
                     let ctor = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
                     let obj = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
 
-                    let result = if let Some(obj_idx) = obj.to_object_idx() {
-                        // Get the constructor stored when the object was created
-                        if let Some(obj_instance) = self.get_object(obj_idx) {
-                            if let Some(stored_ctor) = obj_instance.constructor {
-                                // Compare if the stored constructor matches the right operand
-                                // For closures, compare the closure indices
-                                if let (Some(stored_idx), Some(ctor_idx)) =
-                                    (stored_ctor.to_closure_idx(), ctor.to_closure_idx())
-                                {
-                                    // Same closure instance
-                                    Value::bool(stored_idx == ctor_idx)
-                                } else {
-                                    // For non-closure functions, compare raw values
-                                    Value::bool(stored_ctor.0 == ctor.0)
-                                }
-                            } else {
-                                // Object was not created with new
-                                Value::bool(false)
-                            }
-                        } else {
-                            Value::bool(false)
-                        }
-                    } else {
-                        // Left operand is not an object
-                        Value::bool(false)
-                    };
+                    let result = Value::bool(self.instance_of(obj, ctor));
                     self.stack.push(result);
                 }
 
@@ -3116,37 +6973,16 @@ impl Interpreter {
                 op if op == OpCode::ForInStart as u8 => {
                     let obj = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
 
-                    // Create iterator based on value type
-                    let iter = if let Some(obj_idx) = obj.to_object_idx() {
-                        if let Some(obj_instance) = self.get_object(obj_idx) {
-                            ForInIterator::from_object(obj_instance)
-                        } else {
-                            ForInIterator {
-                                keys: Vec::new(),
-                                index: 0,
-                            }
-                        }
-                    } else if let Some(arr_idx) = obj.to_array_idx() {
-                        if let Some(arr) = self.get_array(arr_idx) {
-                            ForInIterator::from_array(arr)
-                        } else {
-                            ForInIterator {
-                                keys: Vec::new(),
-                                index: 0,
-                            }
-                        }
-                    } else {
-                        // For non-objects/arrays, create empty iterator
-                        ForInIterator {
-                            keys: Vec::new(),
-                            index: 0,
-                        }
+                    // Non-objects/arrays produce an empty iterator
+                    let iter = ForInIterator {
+                        keys: self.property_keys(obj, PropertyLookup::Chain),
+                        index: 0,
                     };
 
                     // Store iterator and push reference
-                    let iter_idx = self.for_in_iterators.len();
+                    let iter_idx = table_index(self.for_in_iterators.len())?;
                     self.for_in_iterators.push(iter);
-                    self.stack.push(Value::iterator_idx(iter_idx as u32));
+                    self.stack.push(Value::iterator_idx(iter_idx));
                 }
 
                 // ForInNext - Get next for-in key: iter -> key done
@@ -3181,37 +7017,12 @@ impl Interpreter {
                 op if op == OpCode::ForOfStart as u8 => {
                     let obj = self.stack.pop().ok_or(InterpreterError::StackUnderflow)?;
 
-                    // Create iterator based on value type
-                    let iter = if let Some(arr_idx) = obj.to_array_idx() {
-                        if let Some(arr) = self.get_array(arr_idx) {
-                            ForOfIterator::from_array(arr)
-                        } else {
-                            ForOfIterator {
-                                values: Vec::new(),
-                                index: 0,
-                            }
-                        }
-                    } else if let Some(obj_idx) = obj.to_object_idx() {
-                        if let Some(obj_instance) = self.get_object(obj_idx) {
-                            ForOfIterator::from_object(obj_instance)
-                        } else {
-                            ForOfIterator {
-                                values: Vec::new(),
-                                index: 0,
-                            }
-                        }
-                    } else {
-                        // For non-objects/arrays, create empty iterator
-                        ForOfIterator {
-                            values: Vec::new(),
-                            index: 0,
-                        }
-                    };
+                    let iter = self.for_of_iterator(obj)?;
 
                     // Store iterator and push reference
-                    let iter_idx = self.for_of_iterators.len();
+                    let iter_idx = table_index(self.for_of_iterators.len())?;
                     self.for_of_iterators.push(iter);
-                    self.stack.push(Value::for_of_iterator_idx(iter_idx as u32));
+                    self.stack.push(Value::for_of_iterator_idx(iter_idx));
                 }
 
                 // ForOfNext - Get next for-of value: iter -> value done
@@ -3249,23 +7060,46 @@ impl Interpreter {
         }
     }
 
-    // Helper: Convert value to boolean (static method to avoid borrow issues)
-    fn value_to_bool(val: Value) -> bool {
-        if val.is_bool() {
-            val.to_bool().unwrap_or(false)
-        } else if val.is_int() {
-            val.to_i32().map(|n| n != 0).unwrap_or(false)
-        } else if val.is_null() || val.is_undefined() {
-            false
-        } else {
-            // Objects are truthy
-            true
+    /// Text of one operand of a string `+`, borrowed where possible
+    ///
+    /// Objects and arrays have gone through ToPrimitive already; the other
+    /// kinds convert as ToString does.
+    fn concat_operand<'a>(&'a self, val: Value, bytecode: &'a FunctionBytecode) -> ConcatPiece<'a> {
+        match val.classify() {
+            ValueTag::String => {
+                ConcatPiece::Str(self.get_string_content(val, bytecode).unwrap_or_default())
+            }
+            ValueTag::Int => ConcatPiece::Int(IntStr::new(val.to_i32().unwrap_or(0))),
+            ValueTag::Bool => ConcatPiece::Str(JsStr::Table(if val.to_bool().unwrap_or(false) {
+                "true"
+            } else {
+                "false"
+            })),
+            ValueTag::Null => ConcatPiece::Str(JsStr::Table("null")),
+            ValueTag::Undefined => ConcatPiece::Str(JsStr::Table("undefined")),
+            ValueTag::Object
+            | ValueTag::Array
+            | ValueTag::Closure
+            | ValueTag::Bytecode
+            | ValueTag::ShortFunc
+            | ValueTag::NativeFunc
+            | ValueTag::Builtin
+            | ValueTag::Error
+            | ValueTag::RegExp
+            | ValueTag::TypedArray
+            | ValueTag::ArrayBuffer
+            | ValueTag::ForInIterator
+            | ValueTag::ForOfIterator
+            | ValueTag::Exception
+            | ValueTag::Uninitialized => ConcatPiece::Owned(self.plain_string_content(val)),
         }
     }
 
     /// Convert a value to a string for property access
     fn value_to_string(&self, val: &Value) -> Option<String> {
-        if val.is_string() {
+        if let Some(s) = val.to_inline_string() {
+            Some(s.as_str().to_string())
+        } else if val.is_string() {
             // Get string from string constants or runtime strings
             let str_idx = val.to_string_idx()?;
             // Check if it's a built-in string
@@ -3303,13 +7137,7 @@ impl Interpreter {
 
     fn op_neg(&self, val: Value) -> InterpreterResult<Value> {
         if let Some(n) = val.to_i32() {
-            if n == i32::MIN {
-                // Overflow: would need f64
-                return Err(InterpreterError::InternalError(
-                    "integer overflow".to_string(),
-                ));
-            }
-            Ok(Value::int(-n))
+            int_result(-i64::from(n))
         } else {
             Err(InterpreterError::TypeError(
                 "cannot negate non-number".to_string(),
@@ -3319,7 +7147,7 @@ impl Interpreter {
 
     fn op_add(&self, a: Value, b: Value) -> InterpreterResult<Value> {
         match (a.to_i32(), b.to_i32()) {
-            (Some(va), Some(vb)) => Ok(Value::int(va.wrapping_add(vb))),
+            (Some(va), Some(vb)) => int_result(i64::from(va) + i64::from(vb)),
             _ => Err(InterpreterError::TypeError(
                 "cannot add non-numbers".to_string(),
             )),
@@ -3328,7 +7156,7 @@ impl Interpreter {
 
     fn op_sub(&self, a: Value, b: Value) -> InterpreterResult<Value> {
         match (a.to_i32(), b.to_i32()) {
-            (Some(va), Some(vb)) => Ok(Value::int(va.wrapping_sub(vb))),
+            (Some(va), Some(vb)) => int_result(i64::from(va) - i64::from(vb)),
             _ => Err(InterpreterError::TypeError(
                 "cannot subtract non-numbers".to_string(),
             )),
@@ -3337,7 +7165,7 @@ impl Interpreter {
 
     fn op_mul(&self, a: Value, b: Value) -> InterpreterResult<Value> {
         match (a.to_i32(), b.to_i32()) {
-            (Some(va), Some(vb)) => Ok(Value::int(va.wrapping_mul(vb))),
+            (Some(va), Some(vb)) => int_result(i64::from(va) * i64::from(vb)),
             _ => Err(InterpreterError::TypeError(
                 "cannot multiply non-numbers".to_string(),
             )),
@@ -3349,12 +7177,8 @@ impl Interpreter {
             (Some(va), Some(vb)) => {
                 if vb == 0 {
                     Err(InterpreterError::DivisionByZero)
-                } else if let Some(result) = va.checked_div(vb) {
-                    Ok(Value::int(result))
                 } else {
-                    Err(InterpreterError::InternalError(
-                        "integer overflow".to_string(),
-                    ))
+                    int_result(i64::from(va) / i64::from(vb))
                 }
             }
             _ => Err(InterpreterError::TypeError(
@@ -3368,12 +7192,8 @@ impl Interpreter {
             (Some(va), Some(vb)) => {
                 if vb == 0 {
                     Err(InterpreterError::DivisionByZero)
-                } else if let Some(result) = va.checked_rem(vb) {
-                    Ok(Value::int(result))
                 } else {
-                    Err(InterpreterError::InternalError(
-                        "integer overflow".to_string(),
-                    ))
+                    int_result(i64::from(va) % i64::from(vb))
                 }
             }
             _ => Err(InterpreterError::TypeError(
@@ -3422,87 +7242,110 @@ impl Interpreter {
 
     fn op_eq(&self, a: Value, b: Value) -> InterpreterResult<Value> {
         // Simple equality for now (strict equality)
-        Ok(Value::bool(a == b))
+        Ok(Value::bool(self.strict_equals(a, b)))
     }
 
     fn op_neq(&self, a: Value, b: Value) -> InterpreterResult<Value> {
-        Ok(Value::bool(a != b))
+        Ok(Value::bool(!self.strict_equals(a, b)))
+    }
+
+    /// Strict equality (`===`)
+    ///
+    /// Identical values are equal; strings are compared by content, since the
+    /// same text can live in the constant pool and in runtime strings.
+    pub(crate) fn strict_equals(&self, a: Value, b: Value) -> bool {
+        if a == b {
+            return true;
+        }
+        match (self.get_string(a), self.get_string(b)) {
+            (Some(sa), Some(sb)) => sa == sb,
+            _ => false,
+        }
+    }
+
+    /// SameValueZero, used by `includes`
+    ///
+    /// Differs from strict equality only in treating NaN as equal to itself.
+    /// Numbers are integers here, so there is no NaN to special-case yet.
+    /// Strings are equal when their contents are, wherever they are stored.
+    pub fn same_value_zero(&self, a: Value, b: Value) -> bool {
+        self.strict_equals(a, b)
     }
 
     // Bitwise operations
 
-    fn op_bitwise_not(&self, val: Value) -> InterpreterResult<Value> {
+    /// ToInt32 for the bitwise operators
+    ///
+    /// Booleans, null, undefined and strings go through ToNumber, with NaN
+    /// (unparsable strings, undefined) becoming 0. Objects are first turned
+    /// into primitives: a Date gives its epoch seconds truncated modulo 2^32,
+    /// other objects go through ToPrimitive with the Number hint.
+    fn coerce_to_int32(&mut self, val: Value) -> InterpreterResult<i32> {
         if let Some(n) = val.to_i32() {
-            Ok(Value::int(!n))
-        } else {
-            Err(InterpreterError::TypeError(
-                "cannot apply bitwise NOT to non-number".to_string(),
-            ))
+            return Ok(n);
+        }
+        if !(val.is_object() || val.is_array()) {
+            return Ok(self.to_number(val).to_i32().unwrap_or(0));
         }
+        if let Some(time) = self.date_value(val) {
+            return Ok(time.map_or(0, |t| date::to_epoch_seconds(t) as i32));
+        }
+        let primitive = self.coerce_to_primitive(val, PrimitiveHint::Number)?;
+        self.coerce_to_int32(primitive)
     }
 
-    fn op_bitwise_and(&self, a: Value, b: Value) -> InterpreterResult<Value> {
-        match (a.to_i32(), b.to_i32()) {
-            (Some(va), Some(vb)) => Ok(Value::int(va & vb)),
-            _ => Err(InterpreterError::TypeError(
-                "cannot apply bitwise AND to non-numbers".to_string(),
-            )),
-        }
+    /// Shift count of a shift operator: ToInt32 masked to 0..=31
+    fn shift_count(&mut self, val: Value) -> InterpreterResult<u32> {
+        Ok((self.coerce_to_int32(val)? & 0x1f) as u32)
     }
 
-    fn op_bitwise_or(&self, a: Value, b: Value) -> InterpreterResult<Value> {
-        match (a.to_i32(), b.to_i32()) {
-            (Some(va), Some(vb)) => Ok(Value::int(va | vb)),
-            _ => Err(InterpreterError::TypeError(
-                "cannot apply bitwise OR to non-numbers".to_string(),
-            )),
-        }
+    fn op_bitwise_not(&mut self, val: Value) -> InterpreterResult<Value> {
+        int_result(i64::from(!self.coerce_to_int32(val)?))
     }
 
-    fn op_bitwise_xor(&self, a: Value, b: Value) -> InterpreterResult<Value> {
-        match (a.to_i32(), b.to_i32()) {
-            (Some(va), Some(vb)) => Ok(Value::int(va ^ vb)),
-            _ => Err(InterpreterError::TypeError(
-                "cannot apply bitwise XOR to non-numbers".to_string(),
-            )),
-        }
+    fn op_bitwise_and(&mut self, a: Value, b: Value) -> InterpreterResult<Value> {
+        int_result(i64::from(
+            self.coerce_to_int32(a)? & self.coerce_to_int32(b)?,
+        ))
     }
 
-    fn op_shl(&self, a: Value, b: Value) -> InterpreterResult<Value> {
-        match (a.to_i32(), b.to_i32()) {
-            (Some(va), Some(vb)) => {
-                let shift = (vb & 0x1f) as u32;
-                Ok(Value::int(va << shift))
-            }
-            _ => Err(InterpreterError::TypeError(
-                "cannot apply left shift to non-numbers".to_string(),
-            )),
-        }
+    fn op_bitwise_or(&mut self, a: Value, b: Value) -> InterpreterResult<Value> {
+        int_result(i64::from(
+            self.coerce_to_int32(a)? | self.coerce_to_int32(b)?,
+        ))
     }
 
-    fn op_sar(&self, a: Value, b: Value) -> InterpreterResult<Value> {
-        match (a.to_i32(), b.to_i32()) {
-            (Some(va), Some(vb)) => {
-                let shift = (vb & 0x1f) as u32;
-                Ok(Value::int(va >> shift))
-            }
-            _ => Err(InterpreterError::TypeError(
-                "cannot apply arithmetic right shift to non-numbers".to_string(),
-            )),
-        }
+    fn op_bitwise_xor(&mut self, a: Value, b: Value) -> InterpreterResult<Value> {
+        int_result(i64::from(
+            self.coerce_to_int32(a)? ^ self.coerce_to_int32(b)?,
+        ))
     }
 
-    fn op_shr(&self, a: Value, b: Value) -> InterpreterResult<Value> {
-        match (a.to_i32(), b.to_i32()) {
-            (Some(va), Some(vb)) => {
-                let shift = (vb & 0x1f) as u32;
-                let result = (va as u32) >> shift;
-                Ok(Value::int(result as i32))
-            }
-            _ => Err(InterpreterError::TypeError(
-                "cannot apply logical right shift to non-numbers".to_string(),
-            )),
-        }
+    fn op_shl(&mut self, a: Value, b: Value) -> InterpreterResult<Value> {
+        let va = self.coerce_to_int32(a)?;
+        int_result(i64::from(va << self.shift_count(b)?))
+    }
+
+    fn op_sar(&mut self, a: Value, b: Value) -> InterpreterResult<Value> {
+        let va = self.coerce_to_int32(a)?;
+        int_result(i64::from(va >> self.shift_count(b)?))
+    }
+
+    /// Logical right shift (`>>>`)
+    ///
+    /// The result is unsigned, so a negative operand shifted by 0 (for
+    /// example `-1 >>> 0`, which is 4294967295) can exceed the largest
+    /// integer value. Without floating point numbers such results are
+    /// reported as a RangeError instead of wrapping to a negative number.
+    fn op_shr(&mut self, a: Value, b: Value) -> InterpreterResult<Value> {
+        let va = self.coerce_to_int32(a)?;
+        let result = (va as u32) >> self.shift_count(b)?;
+        Value::try_int(i64::from(result)).ok_or_else(|| {
+            InterpreterError::RangeError(format!(
+                "unsigned right shift result {} is too large for an integer",
+                result
+            ))
+        })
     }
 
     // =========================================================================
@@ -3510,17 +7353,84 @@ impl Interpreter {
     // =========================================================================
 
     /// Register a native function and return its index
-    pub fn register_native(&mut self, name: &'static str, func: NativeFn, arity: u8) -> u32 {
+    ///
+    /// `arity` is the function's `length`; it is not enforced (see
+    /// [`NativeFunction::arity`]).
+    ///
+    /// The function gets whatever `this` it is called on
+    /// ([`Receiver::Optional`]); use
+    /// [`Interpreter::register_native_with_receiver`] to have the receiver
+    /// checked before it runs.
+    pub fn register_native(&mut self, name: impl Into<Rc<str>>, func: NativeFn, arity: u8) -> u32 {
+        self.register_native_with_receiver(name, func, arity, Receiver::Optional)
+    }
+
+    /// Register a native function with the given receiver policy, returning
+    /// its index
+    pub fn register_native_with_receiver(
+        &mut self,
+        name: impl Into<Rc<str>>,
+        func: NativeFn,
+        arity: u8,
+        receiver: Receiver,
+    ) -> u32 {
+        self.push_native(name, NativeImpl::Builtin(func), arity, receiver)
+    }
+
+    /// Register a host function with the given receiver policy, returning
+    /// its index
+    pub(crate) fn register_host_native(
+        &mut self,
+        name: impl Into<Rc<str>>,
+        func: HostFn,
+        arity: u8,
+        receiver: Receiver,
+    ) -> u32 {
+        self.push_native(name, NativeImpl::Host(func), arity, receiver)
+    }
+
+    fn push_native(
+        &mut self,
+        name: impl Into<Rc<str>>,
+        func: NativeImpl,
+        arity: u8,
+        receiver: Receiver,
+    ) -> u32 {
         let idx = self.native_functions.len() as u32;
-        self.native_functions
-            .push(NativeFunction { name, func, arity });
+        self.native_functions.push(Some(NativeFunction {
+            name: name.into(),
+            func,
+            arity,
+            receiver,
+        }));
         idx
     }
 
+    /// Unregister the native function at `idx`, returning whether it was registered
+    ///
+    /// The slot is kept as a tombstone: the name stops resolving, and values
+    /// still referring to the function throw a TypeError when called.
+    pub fn unregister_native(&mut self, idx: u32) -> bool {
+        self.native_functions
+            .get_mut(idx as usize)
+            .and_then(Option::take)
+            .is_some()
+    }
+
+    /// Remove a builtin native so its name no longer resolves, as if it
+    /// had never been registered
+    ///
+    /// Returns false if no native has that name.
+    pub fn remove_builtin_native(&mut self, name: &str) -> bool {
+        self.get_native_func(name)
+            .and_then(|func| func.to_native_func_idx())
+            .is_some_and(|idx| self.unregister_native(idx))
+    }
+
     /// Get a native function value by name
     pub fn get_native_func(&self, name: &str) -> Option<Value> {
         for (idx, nf) in self.native_functions.iter().enumerate() {
-            if nf.name == name {
+            if nf.as_ref().is_some_and(|nf| &*nf.name == name) {
                 return Some(Value::native_func(idx as u32));
             }
         }
@@ -3529,15 +7439,21 @@ impl Interpreter {
 
     /// Get a property from an array (Array.prototype methods or length)
     fn get_array_property(&self, arr: Value, prop_name: &str) -> Value {
+        // Own named properties shadow Array.prototype methods
+        if prop_name != "length"
+            && let Some(val) = arr
+                .to_array_idx()
+                .and_then(|arr_idx| self.array_get_named(arr_idx, prop_name))
+        {
+            return val;
+        }
         match prop_name {
             "length" => {
                 // Return the array length
-                if let Some(arr_idx) = arr.to_array_idx()
-                    && let Some(arr_data) = self.arrays.get(arr_idx as usize)
-                {
-                    return Value::int(arr_data.len() as i32);
+                match arr.to_array_idx() {
+                    Some(arr_idx) => Value::int_saturating(self.array_length(arr_idx) as i64),
+                    None => Value::undefined(),
                 }
-                Value::undefined()
             }
             "push" => self
                 .get_native_func("Array.prototype.push")
@@ -3611,7 +7527,8 @@ impl Interpreter {
             "reduceRight" => self
                 .get_native_func("Array.prototype.reduceRight")
                 .unwrap_or_default(),
-            _ => Value::undefined(),
+            // Array.prototype inherits from Object.prototype
+            _ => self.object_prototype_method(prop_name).unwrap_or_default(),
         }
     }
 
@@ -3620,10 +7537,8 @@ impl Interpreter {
         match prop_name {
             "length" => {
                 // Get string length
-                if let Some(str_idx) = str_val.to_string_idx()
-                    && let Some(s) = self.get_string_by_idx(str_idx)
-                {
-                    return Value::int(s.len() as i32);
+                if let Some(s) = self.get_string(str_val) {
+                    return Value::int_saturating(s.encode_utf16().count() as i64);
                 }
                 Value::int(0)
             }
@@ -3691,6 +7606,9 @@ impl Interpreter {
             "codePointAt" => self
                 .get_native_func("String.prototype.codePointAt")
                 .unwrap_or_default(),
+            "at" => self
+                .get_native_func("String.prototype.at")
+                .unwrap_or_default(),
             "trimStart" => self
                 .get_native_func("String.prototype.trimStart")
                 .unwrap_or_default(),
@@ -3727,19 +7645,15 @@ impl Interpreter {
     fn get_error_property(&mut self, err_idx: u32, prop_name: &str) -> Value {
         if let Some(err) = self.error_objects.get(err_idx as usize).cloned() {
             match prop_name {
-                "name" => {
-                    // Return the error name as a runtime string
-                    self.create_runtime_string(err.name)
-                }
-                "message" => {
-                    // Return the error message as a runtime string
-                    self.create_runtime_string(err.message)
-                }
+                "name" => self.reuse_or_create_string(err.name),
+                "message" => self.reuse_or_create_string(err.message),
                 "stack" => {
-                    // Return a simple stack trace (just error type and message for now)
-                    let stack = format!("{}:{}", err.name, err.message);
+                    // No frames are recorded yet, so the stack is the error
+                    // and its causes
+                    let stack = self.error_chain_text(err_idx);
                     self.create_runtime_string(stack)
                 }
+                "cause" => err.cause.unwrap_or_default(),
                 "toString" => self
                     .get_native_func("Error.prototype.toString")
                     .unwrap_or_default(),
@@ -3769,6 +7683,37 @@ impl Interpreter {
         }
     }
 
+    /// The `name` and `length` a callable reports: its declared name and
+    /// parameter count
+    ///
+    /// Natives report the last segment of their registered name, and bound
+    /// functions what bind() recorded for them. Anonymous functions have an
+    /// empty name.
+    fn function_metadata(&self, func: Value) -> Option<(String, usize)> {
+        let bytecode = if let Some(idx) = func.to_closure_idx() {
+            Some(unsafe { &*self.get_closure(idx)?.bytecode })
+        } else {
+            func.to_func_ptr().map(|ptr| unsafe { &*ptr })
+        };
+        if let Some(bytecode) = bytecode {
+            let name = bytecode.name.clone().unwrap_or_default();
+            return Some((name, bytecode.arg_count as usize));
+        }
+        if let Some(idx) = func.to_native_func_idx() {
+            let nf = self.native_functions.get(idx as usize)?.as_ref()?;
+            let name = nf.name.rsplit('.').next().unwrap_or_default();
+            return Some((name.to_string(), nf.arity as usize));
+        }
+        self.get_bound_function(func)?;
+        let obj = self.get_object(func.to_object_idx()?)?;
+        let prop = |key: &str| self.named_get(obj, key);
+        let name = prop("name")
+            .and_then(|v| self.get_string(v).map(|s| s.to_string()))
+            .unwrap_or_default();
+        let length = prop("length").and_then(|v| v.to_i32()).unwrap_or(0);
+        Some((name, length.max(0) as usize))
+    }
+
     /// Get a property from a RegExp object
     fn get_regexp_property(&self, regex_idx: u32, prop_name: &str) -> Value {
         if let Some(re) = self.regex_objects.get(regex_idx as usize) {
@@ -3798,9 +7743,9 @@ impl Interpreter {
     fn get_typed_array_property(&self, typed_idx: u32, prop_name: &str) -> Value {
         if let Some(ta) = self.typed_arrays.get(typed_idx as usize) {
             match prop_name {
-                "length" => Value::int(ta.length as i32),
-                "byteLength" => Value::int(ta.data.len() as i32),
-                "BYTES_PER_ELEMENT" => Value::int(ta.kind.byte_size() as i32),
+                "length" => Value::int_saturating(ta.length as i64),
+                "byteLength" => Value::int_saturating(ta.data.len() as i64),
+                "BYTES_PER_ELEMENT" => Value::int_saturating(ta.kind.byte_size() as i64),
                 "subarray" => self
                     .get_native_func("TypedArray.prototype.subarray")
                     .unwrap_or_default(),
@@ -3815,7 +7760,7 @@ impl Interpreter {
     fn get_array_buffer_property(&self, ab_idx: u32, prop_name: &str) -> Value {
         if let Some(ab) = self.array_buffers.get(ab_idx as usize) {
             match prop_name {
-                "byteLength" => Value::int(ab.byte_length() as i32),
+                "byteLength" => Value::int_saturating(ab.byte_length() as i64),
                 _ => Value::undefined(),
             }
         } else {
@@ -3875,6 +7820,12 @@ impl Interpreter {
                     _ => Value::undefined(),
                 }
             }
+            BUILTIN_REFLECT => match prop_name {
+                "has" | "get" | "set" | "ownKeys" => self
+                    .get_native_func(&format!("Reflect.{}", prop_name))
+                    .unwrap_or_default(),
+                _ => Value::undefined(),
+            },
             BUILTIN_NUMBER => {
                 // Number object properties
                 match prop_name {
@@ -3882,11 +7833,9 @@ impl Interpreter {
                     "isNaN" => self.get_native_func("Number.isNaN").unwrap_or_default(),
                     "isFinite" => self.get_native_func("Number.isFinite").unwrap_or_default(),
                     "parseInt" => self.get_native_func("parseInt").unwrap_or_default(),
-                    // Use 31-bit safe values (our Value::int only supports 31-bit signed integers)
-                    "MAX_VALUE" => Value::int((1 << 30) - 1), // 1073741823
-                    "MIN_VALUE" => Value::int(-(1 << 30)),    // -1073741824
-                    "MAX_SAFE_INTEGER" => Value::int((1 << 30) - 1),
-                    "MIN_SAFE_INTEGER" => Value::int(-(1 << 30)),
+                    // Integers are exact only within the range a value holds
+                    "MAX_VALUE" | "MAX_SAFE_INTEGER" => Value::int(Value::INT_MAX),
+                    "MIN_VALUE" | "MIN_SAFE_INTEGER" => Value::int(Value::INT_MIN),
                     _ => Value::undefined(),
                 }
             }
@@ -3900,6 +7849,9 @@ impl Interpreter {
                     "log" => self.get_native_func("console.log").unwrap_or_default(),
                     "error" => self.get_native_func("console.error").unwrap_or_default(),
                     "warn" => self.get_native_func("console.warn").unwrap_or_default(),
+                    "time" => self.get_native_func("console.time").unwrap_or_default(),
+                    "timeEnd" => self.get_native_func("console.timeEnd").unwrap_or_default(),
+                    "table" => self.get_native_func("console.table").unwrap_or_default(),
                     _ => Value::undefined(),
                 }
             }
@@ -3907,6 +7859,19 @@ impl Interpreter {
                 // performance object properties
                 match prop_name {
                     "now" => self.get_native_func("performance.now").unwrap_or_default(),
+                    "mark" => self.get_native_func("performance.mark").unwrap_or_default(),
+                    "measure" => self
+                        .get_native_func("performance.measure")
+                        .unwrap_or_default(),
+                    "getEntriesByName" => self
+                        .get_native_func("performance.getEntriesByName")
+                        .unwrap_or_default(),
+                    "clearMarks" => self
+                        .get_native_func("performance.clearMarks")
+                        .unwrap_or_default(),
+                    "clearMeasures" => self
+                        .get_native_func("performance.clearMeasures")
+                        .unwrap_or_default(),
                     _ => Value::undefined(),
                 }
             }
@@ -3914,6 +7879,7 @@ impl Interpreter {
                 // Date object properties
                 match prop_name {
                     "now" => self.get_native_func("Date.now").unwrap_or_default(),
+                    "parse" => self.get_native_func("Date.parse").unwrap_or_default(),
                     _ => Value::undefined(),
                 }
             }
@@ -3921,6 +7887,9 @@ impl Interpreter {
                 // Object static methods
                 match prop_name {
                     "keys" => self.get_native_func("Object.keys").unwrap_or_default(),
+                    "getOwnPropertyNames" => self
+                        .get_native_func("Object.getOwnPropertyNames")
+                        .unwrap_or_default(),
                     "values" => self.get_native_func("Object.values").unwrap_or_default(),
                     "entries" => self.get_native_func("Object.entries").unwrap_or_default(),
                     "getPrototypeOf" => self
@@ -3930,9 +7899,17 @@ impl Interpreter {
                         .get_native_func("Object.setPrototypeOf")
                         .unwrap_or_default(),
                     "create" => self.get_native_func("Object.create").unwrap_or_default(),
+                    "assign" => self.get_native_func("Object.assign").unwrap_or_default(),
                     "defineProperty" => self
                         .get_native_func("Object.defineProperty")
                         .unwrap_or_default(),
+                    "getOwnPropertyDescriptor" => self
+                        .get_native_func("Object.getOwnPropertyDescriptor")
+                        .unwrap_or_default(),
+                    "getOwnPropertyDescriptors" => self
+                        .get_native_func("Object.getOwnPropertyDescriptors")
+                        .unwrap_or_default(),
+                    "prototype" => Value::builtin_object(BUILTIN_OBJECT_PROTOTYPE),
                     _ => Value::undefined(),
                 }
             }
@@ -3940,9 +7917,32 @@ impl Interpreter {
                 // Array static methods
                 match prop_name {
                     "isArray" => self.get_native_func("Array.isArray").unwrap_or_default(),
+                    "prototype" => Value::builtin_object(BUILTIN_ARRAY_PROTOTYPE),
                     _ => Value::undefined(),
                 }
             }
+            BUILTIN_ARRAY_PROTOTYPE => self
+                .get_native_func(&format!("Array.prototype.{}", prop_name))
+                .unwrap_or_default(),
+            BUILTIN_STRING_PROTOTYPE => self
+                .get_native_func(&format!("String.prototype.{}", prop_name))
+                .unwrap_or_default(),
+            BUILTIN_OBJECT_PROTOTYPE => self
+                .get_native_func(&format!("Object.prototype.{}", prop_name))
+                .unwrap_or_default(),
+            BUILTIN_ERROR_PROTOTYPE => match prop_name {
+                "constructor" => Value::builtin_object(BUILTIN_ERROR),
+                "toString" => self
+                    .get_native_func("Error.prototype.toString")
+                    .unwrap_or_default(),
+                _ => Value::undefined(),
+            },
+            idx if error_constructor_name(idx).is_some() => match prop_name {
+                "prototype" if idx == BUILTIN_ERROR => {
+                    Value::builtin_object(BUILTIN_ERROR_PROTOTYPE)
+                }
+                _ => self.get_function_property(prop_name),
+            },
             BUILTIN_STRING => {
                 // String static methods
                 match prop_name {
@@ -3952,49 +7952,11 @@ impl Interpreter {
                     "fromCodePoint" => self
                         .get_native_func("String.fromCodePoint")
                         .unwrap_or_default(),
+                    "prototype" => Value::builtin_object(BUILTIN_STRING_PROTOTYPE),
                     _ => Value::undefined(),
                 }
             }
-            BUILTIN_GLOBAL_THIS => {
-                // globalThis provides access to global builtins
-                match prop_name {
-                    "undefined" => Value::undefined(),
-                    "NaN" => Value::int(0),
-                    "Infinity" => Value::int(i32::MAX),
-                    "Math" => Value::builtin_object(BUILTIN_MATH),
-                    "JSON" => Value::builtin_object(BUILTIN_JSON),
-                    "Number" => Value::builtin_object(BUILTIN_NUMBER),
-                    "Boolean" => Value::builtin_object(BUILTIN_BOOLEAN),
-                    "String" => Value::builtin_object(BUILTIN_STRING),
-                    "Object" => Value::builtin_object(BUILTIN_OBJECT),
-                    "Array" => Value::builtin_object(BUILTIN_ARRAY),
-                    "console" => Value::builtin_object(BUILTIN_CONSOLE),
-                    "performance" => Value::builtin_object(BUILTIN_PERFORMANCE),
-                    "Date" => Value::builtin_object(BUILTIN_DATE),
-                    "Error" => Value::builtin_object(BUILTIN_ERROR),
-                    "RegExp" => Value::builtin_object(BUILTIN_REGEXP),
-                    "globalThis" => Value::builtin_object(BUILTIN_GLOBAL_THIS),
-                    "ArrayBuffer" => Value::builtin_object(BUILTIN_ARRAY_BUFFER),
-                    "Int8Array" => Value::builtin_object(BUILTIN_INT8_ARRAY),
-                    "Uint8Array" => Value::builtin_object(BUILTIN_UINT8_ARRAY),
-                    "Uint8ClampedArray" => Value::builtin_object(BUILTIN_UINT8_CLAMPED_ARRAY),
-                    "Int16Array" => Value::builtin_object(BUILTIN_INT16_ARRAY),
-                    "Uint16Array" => Value::builtin_object(BUILTIN_UINT16_ARRAY),
-                    "Int32Array" => Value::builtin_object(BUILTIN_INT32_ARRAY),
-                    "Uint32Array" => Value::builtin_object(BUILTIN_UINT32_ARRAY),
-                    "Float32Array" => Value::builtin_object(BUILTIN_FLOAT32_ARRAY),
-                    "Float64Array" => Value::builtin_object(BUILTIN_FLOAT64_ARRAY),
-                    "parseInt" => self.get_native_func("parseInt").unwrap_or_default(),
-                    "parseFloat" => self.get_native_func("parseFloat").unwrap_or_default(),
-                    "isNaN" => self.get_native_func("isNaN").unwrap_or_default(),
-                    "isFinite" => self.get_native_func("isFinite").unwrap_or_default(),
-                    "gc" => self.get_native_func("gc").unwrap_or_default(),
-                    "load" => self.get_native_func("load").unwrap_or_default(),
-                    "setTimeout" => self.get_native_func("setTimeout").unwrap_or_default(),
-                    "clearTimeout" => self.get_native_func("clearTimeout").unwrap_or_default(),
-                    _ => Value::undefined(),
-                }
-            }
+            BUILTIN_GLOBAL_THIS => self.global_this_property(prop_name).unwrap_or_default(),
             _ => Value::undefined(),
         }
     }
@@ -4012,17 +7974,351 @@ impl Interpreter {
             .ok_or_else(|| {
                 InterpreterError::InternalError(format!("invalid native function index: {}", idx))
             })?
-            .clone();
+            .clone()
+            .ok_or_else(|| InterpreterError::TypeError("function was unregistered".to_string()))?;
+
+        if let Some(profile) = self.profile.as_deref_mut() {
+            *profile.natives.entry(idx).or_default() += 1;
+        }
+        #[cfg(feature = "opcode-stats")]
+        {
+            *self.opcode_stats.natives.entry(idx).or_default() += 1;
+        }
+
+        let this = match func.receiver {
+            Receiver::None => Value::undefined(),
+            Receiver::Optional => this,
+            Receiver::Required(kind) if self.receiver_matches(kind, this) => this,
+            Receiver::Required(kind) => {
+                return Err(InterpreterError::TypeError(format!(
+                    "{} called on {}, expected {}",
+                    func.name,
+                    receiver_description(this),
+                    kind.description()
+                )));
+            }
+        };
+
+        if !self.host_natives.is_empty()
+            && let Some(host) = self.host_natives.get(&idx).cloned()
+        {
+            return self.call_host_native(host, this, args);
+        }
+
+        self.catch_native_panic(|interp| {
+            if interp.replay.is_some() && idx >= interp.first_host_native {
+                return interp
+                    .call_logged_native(&func, this, args)
+                    .map_err(|msg| interp.native_error(msg));
+            }
+            func.func
+                .call(interp, this, args)
+                .map_err(|msg| interp.native_error(msg))
+        })
+    }
+
+    /// Whether `this` is a receiver of the given kind
+    fn receiver_matches(&self, kind: ReceiverKind, this: Value) -> bool {
+        match kind {
+            ReceiverKind::Coercible => !(this.is_undefined() || this.is_null()),
+            ReceiverKind::Object => {
+                !(this.is_undefined()
+                    || this.is_null()
+                    || this.is_bool()
+                    || this.is_int()
+                    || this.is_string())
+            }
+            ReceiverKind::Array => this.to_array_idx().is_some(),
+            ReceiverKind::Number => this.is_int(),
+            ReceiverKind::Function => self.is_callable(this),
+            ReceiverKind::Date => self.date_value(this).is_some(),
+            ReceiverKind::Promise => self.promise_idx(this).is_some(),
+            ReceiverKind::RegExp => this.to_regexp_object_idx().is_some(),
+            ReceiverKind::TypedArray => this.to_typed_array_idx().is_some(),
+        }
+    }
+
+    /// Run native code, turning a panic into a catchable `InternalError`
+    ///
+    /// The panic may have unwound out of script code the native was running
+    /// through [`Interpreter::call_value`], so the value stack, call stack,
+    /// exception handlers and nested call target are put back as they were
+    /// when the native was entered. The interpreter is then as usable as
+    /// after a native that returned an error. The panic hook still runs, so
+    /// the panic is reported on stderr as usual.
+    #[cfg(feature = "std")]
+    fn catch_native_panic<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> InterpreterResult<T>,
+    ) -> InterpreterResult<T> {
+        let stack_depth = self.stack.len();
+        let frame_depth = self.call_stack.len();
+        let handler_depth = self.exception_handlers.len();
+        let saved_target = self.nested_call_target_depth;
+        // Natives only reach the interpreter through `&mut self`, and
+        // everything a half-finished call can leave behind is reset below
+        let payload = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(self))) {
+            Ok(result) => return result,
+            Err(payload) => payload,
+        };
+
+        self.stack
+            .drop_n(self.stack.len().saturating_sub(stack_depth));
+        self.call_stack.truncate(frame_depth);
+        self.exception_handlers.truncate(handler_depth);
+        self.nested_call_target_depth = saved_target;
+        self.native_exception = None;
+
+        let reason = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown cause");
+        let exception = self.alloc_error(
+            "InternalError",
+            &format!("native function panicked: {}", reason),
+        );
+        Err(InterpreterError::Exception(exception))
+    }
+
+    /// Run native code; without `std` a panic cannot be caught
+    #[cfg(not(feature = "std"))]
+    fn catch_native_panic<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> InterpreterResult<T>,
+    ) -> InterpreterResult<T> {
+        f(self)
+    }
+
+    /// Convert an error returned by a native function
+    ///
+    /// A native passing on the failure of a callback it invoked through
+    /// [`Interpreter::call_value`] rethrows the callback's exception value.
+    fn native_error(&mut self, err: HostError) -> InterpreterError {
+        let kept = self.native_exception.take();
+        match err {
+            HostError::Type(msg) => match kept {
+                Some(exception) if msg == EXCEPTION_MESSAGE => {
+                    InterpreterError::Exception(exception)
+                }
+                _ => InterpreterError::TypeError(msg),
+            },
+            HostError::Range(msg) => InterpreterError::RangeError(msg),
+            HostError::Reference(msg) => InterpreterError::ReferenceError(msg),
+            HostError::Syntax(msg) => InterpreterError::SyntaxError(msg),
+            HostError::Interpreter(err) => err,
+        }
+    }
+
+    /// Start logging host native results and clock and random reads,
+    /// discarding any log being recorded or replayed
+    pub fn start_recording(&mut self) {
+        self.replay = Some(ReplayMode::Recording(ReplayLog::default()));
+    }
+
+    /// Answer host native calls and clock and random reads from `log`
+    /// instead of running them
+    ///
+    /// Once the script's inputs stop matching the log, the next one fails
+    /// with an uncatchable error naming the entry index and both inputs.
+    pub fn start_replay(&mut self, log: ReplayLog) {
+        self.replay = Some(ReplayMode::Replaying { log, next: 0 });
+    }
+
+    /// Stop recording or replaying and return the log
+    pub fn take_replay_log(&mut self) -> Option<ReplayLog> {
+        match self.replay.take()? {
+            ReplayMode::Recording(log) | ReplayMode::Replaying { log, .. } => Some(log),
+        }
+    }
+
+    /// Record or replay a call to a host native
+    fn call_logged_native(
+        &mut self,
+        func: &NativeFunction,
+        this: Value,
+        args: &[Value],
+    ) -> Result<Value, HostError> {
+        let logged_args: Vec<LoggedValue> = args.iter().map(|&a| self.log_value(a, 0)).collect();
+        let args_hash = replay::hash_args(&logged_args);
+        if matches!(self.replay, Some(ReplayMode::Recording(_))) {
+            let result = func.func.call(self, this, args);
+            let logged = match &result {
+                Ok(value) => Ok(self.log_value(*value, 0)),
+                Err(err) => Err(err.to_string()),
+            };
+            self.record(ReplayEntry::NativeCall {
+                name: func.name.to_string(),
+                args_hash,
+                result: logged,
+            });
+            return result;
+        }
+
+        let found = format!("call to '{}'", func.name);
+        let entry = self.replay_next(
+            &found,
+            |entry| matches!(entry, ReplayEntry::NativeCall { name, .. } if **name == *func.name),
+        )?;
+        match entry {
+            ReplayEntry::NativeCall {
+                args_hash: logged_hash,
+                ..
+            } if logged_hash != args_hash => Err(InterpreterError::InternalError(format!(
+                "replay diverged: {} with different arguments",
+                found
+            ))
+            .into()),
+            ReplayEntry::NativeCall {
+                result: Ok(value), ..
+            } => Ok(self.unlog_value(&value)?),
+            // Errors are logged as text, with their type as a prefix
+            ReplayEntry::NativeCall {
+                result: Err(message),
+                ..
+            } => Err(HostError::parse_prefixed(message)),
+            _ => unreachable!("replay_next only returns matching entries"),
+        }
+    }
+
+    /// Read the clock on behalf of a script, recording or replaying the
+    /// reading
+    fn read_clock(&mut self, read: fn(&Self) -> u64) -> InterpreterResult<u64> {
+        if !matches!(self.replay, Some(ReplayMode::Replaying { .. })) {
+            let time = read(self);
+            self.record(ReplayEntry::Time(time));
+            return Ok(time);
+        }
+        match self.replay_next("time read", |e| matches!(e, ReplayEntry::Time(_)))? {
+            ReplayEntry::Time(time) => Ok(time),
+            _ => unreachable!("replay_next only returns matching entries"),
+        }
+    }
+
+    /// Append an entry to the log being recorded, if any
+    fn record(&mut self, entry: ReplayEntry) {
+        if let Some(ReplayMode::Recording(log)) = &mut self.replay {
+            log.push(entry);
+        }
+    }
+
+    /// Take the next replayed entry, failing unless `matches` accepts it
+    ///
+    /// `found` describes the input the script asked for.
+    fn replay_next(
+        &mut self,
+        found: &str,
+        matches: impl Fn(&ReplayEntry) -> bool,
+    ) -> InterpreterResult<ReplayEntry> {
+        let Some(ReplayMode::Replaying { log, next }) = &mut self.replay else {
+            return Err(InterpreterError::InternalError("not replaying".to_string()));
+        };
+        let index = *next;
+        match log.entries().get(index) {
+            Some(entry) if matches(entry) => {
+                *next += 1;
+                Ok(entry.clone())
+            }
+            expected => Err(InterpreterError::InternalError(format!(
+                "replay diverged at entry {}: log has {}, script made {}",
+                index,
+                expected.map_or_else(|| "no more entries".to_string(), ReplayEntry::describe),
+                found
+            ))),
+        }
+    }
+
+    /// Detach a value from the interpreter for the replay log
+    fn log_value(&self, val: Value, depth: usize) -> LoggedValue {
+        // Deep or cyclic structures are cut off rather than followed forever
+        const MAX_DEPTH: usize = 32;
+        if val.is_undefined() {
+            LoggedValue::Undefined
+        } else if val.is_null() {
+            LoggedValue::Null
+        } else if let Some(b) = val.to_bool() {
+            LoggedValue::Bool(b)
+        } else if let Some(n) = val.to_i32() {
+            LoggedValue::Int(n)
+        } else if let Some(s) = self.get_string(val) {
+            LoggedValue::String(s.to_string())
+        } else if depth >= MAX_DEPTH {
+            LoggedValue::Unsupported
+        } else if let Some(len) = self.array_len(val) {
+            LoggedValue::Array(
+                (0..len)
+                    .filter_map(|i| self.array_element_at(val, i))
+                    .map(|v| self.log_value(v, depth + 1))
+                    .collect(),
+            )
+        } else if let Some(obj) = val
+            .to_object_idx()
+            .filter(|_| self.date_value(val).is_none() && !self.is_callable(val))
+            .and_then(|idx| self.get_object(idx))
+        {
+            LoggedValue::Object(
+                obj.properties
+                    .iter()
+                    .filter(|(k, _)| obj.is_enumerable(*k))
+                    .map(|(k, v)| {
+                        let key = self.atoms.name(*k).to_string();
+                        (key, self.log_value(*v, depth + 1))
+                    })
+                    .collect(),
+            )
+        } else {
+            LoggedValue::Unsupported
+        }
+    }
 
-        (func.func)(self, this, args).map_err(InterpreterError::TypeError)
+    /// Rebuild a value recorded by [`log_value`](Self::log_value)
+    fn unlog_value(&mut self, logged: &LoggedValue) -> InterpreterResult<Value> {
+        Ok(match logged {
+            LoggedValue::Undefined => Value::undefined(),
+            LoggedValue::Null => Value::null(),
+            LoggedValue::Bool(b) => Value::bool(*b),
+            LoggedValue::Int(n) => Value::int(*n),
+            LoggedValue::String(s) => self.alloc_string(s),
+            LoggedValue::Array(items) => {
+                let elements = items
+                    .iter()
+                    .map(|item| self.unlog_value(item))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.alloc_array(elements)
+            }
+            LoggedValue::Object(props) => {
+                let mut values = Vec::with_capacity(props.len());
+                for (key, item) in props {
+                    values.push((key, self.unlog_value(item)?));
+                }
+                values
+                    .into_iter()
+                    .fold(self.alloc_object(), |b, (key, value)| b.prop(key, value))
+                    .finish()
+            }
+            LoggedValue::Unsupported => {
+                return Err(InterpreterError::InternalError(
+                    "replay log holds a value that cannot be rebuilt".to_string(),
+                ));
+            }
+        })
     }
 
     /// Call a builtin object as a function (e.g., Boolean(value), Number(value))
     fn call_builtin_as_function(
         &mut self,
         builtin_idx: u32,
+        this_val: Value,
         args: &[Value],
     ) -> InterpreterResult<Value> {
+        // Error(...) without new builds the same error as new Error(...),
+        // unless called on an object as `Error.call(this, ...)` is
+        if let Some(error_name) = error_constructor_name(builtin_idx) {
+            if this_val.to_object_idx().is_some() {
+                return self.init_error_fields(this_val, error_name, args);
+            }
+            return self.construct_error(error_name, args);
+        }
         match builtin_idx {
             BUILTIN_BOOLEAN => {
                 // Boolean(value) - coerces value to boolean
@@ -4032,13 +8328,16 @@ impl Interpreter {
             BUILTIN_NUMBER => {
                 // Number(value) - coerces value to number
                 let arg = args.first().copied().unwrap_or_default();
+                let arg = self.coerce_to_primitive(arg, PrimitiveHint::Number)?;
                 Ok(self.to_number(arg))
             }
             BUILTIN_STRING => {
                 // String(value) - coerces value to string
                 let arg = args.first().copied().unwrap_or_default();
-                Ok(self.stringify_value(arg))
+                self.stringify_value(arg)
+                    .map_err(|err| self.native_error(err))
             }
+            BUILTIN_ARRAY => self.construct_array(args),
             _ => Err(InterpreterError::TypeError(format!(
                 "Builtin {} is not callable as a function",
                 builtin_idx
@@ -4046,286 +8345,1370 @@ impl Interpreter {
         }
     }
 
-    /// Convert a value to boolean
-    fn to_boolean(&self, val: Value) -> bool {
-        if val.is_undefined() || val.is_null() {
-            false
-        } else if let Some(b) = val.to_bool() {
-            b
-        } else if let Some(n) = val.to_i32() {
-            n != 0
-        } else if let Some(str_idx) = val.to_string_idx() {
-            // Empty string is falsy
-            if let Some(s) = self.get_string_by_idx(str_idx) {
-                !s.is_empty()
-            } else {
-                true
+    /// Create an error as `new Error(message, options)` does
+    ///
+    /// An options object with a `cause` property makes its value the
+    /// error's cause, whatever that value is.
+    fn construct_error(&mut self, name: &str, args: &[Value]) -> InterpreterResult<Value> {
+        let message = match args.first() {
+            Some(msg_val) if msg_val.is_string() => self
+                .get_string(*msg_val)
+                .map(|s| s.to_string())
+                .unwrap_or_default(),
+            Some(msg_val) => msg_val.to_i32().map(|n| n.to_string()).unwrap_or_default(),
+            None => String::new(),
+        };
+        let cause = match args.get(1) {
+            Some(&options)
+                if options.is_object()
+                    && self.has_property(options, "cause", PropertyLookup::Chain) =>
+            {
+                Some(self.get_property(options, "cause")?)
             }
+            _ => None,
+        };
+
+        let error = self.alloc_error(name, &message);
+        if let Some(err) = error
+            .to_error_object_idx()
+            .and_then(|idx| self.error_objects.get_mut(idx as usize))
+        {
+            err.cause = cause;
+        }
+        Ok(error)
+    }
+
+    /// Give an object the fields an error constructor would, for
+    /// constructor functions that call `Error.call(this, message)`
+    ///
+    /// The message and any `cause` option become own properties, as does
+    /// `name` unless the object already has or inherits one. `stack` is
+    /// the name and message at the time of the call. Returns the object.
+    fn init_error_fields(
+        &mut self,
+        this_val: Value,
+        name: &str,
+        args: &[Value],
+    ) -> InterpreterResult<Value> {
+        if let Some(&message) = args.first().filter(|msg| !msg.is_undefined()) {
+            let message = self
+                .stringify_value(message)
+                .map_err(|err| self.native_error(err))?;
+            self.set_property(this_val, "message", message)?;
+        }
+        if let Some(&options) = args.get(1)
+            && options.is_object()
+            && self.has_property(options, "cause", PropertyLookup::Chain)
+        {
+            let cause = self.get_property(options, "cause")?;
+            self.set_property(this_val, "cause", cause)?;
+        }
+        if self.get_property(this_val, "name")?.is_undefined() {
+            let name = self.alloc_string(name);
+            self.set_property(this_val, "name", name)?;
+        }
+        let name = self
+            .object_text_field(this_val, "name")
+            .unwrap_or_else(|| name.to_string());
+        let message = self
+            .object_text_field(this_val, "message")
+            .unwrap_or_default();
+        let stack = if message.is_empty() {
+            name
         } else {
-            // Objects, arrays, closures are truthy
-            true
+            format!("{}: {}", name, message)
+        };
+        let stack = self.alloc_string(&stack);
+        self.set_property(this_val, "stack", stack)?;
+        Ok(this_val)
+    }
+
+    /// Create an array as `Array(...)` and `new Array(...)` do
+    ///
+    /// A single int argument is the length of an array of holes; any other
+    /// arguments become the elements.
+    fn construct_array(&mut self, args: &[Value]) -> InterpreterResult<Value> {
+        match args {
+            [len] if len.is_int() => {
+                let len = len
+                    .to_i32()
+                    .and_then(|n| usize::try_from(n).ok())
+                    .ok_or_else(|| {
+                        InterpreterError::RangeError(INVALID_ARRAY_LENGTH.to_string())
+                    })?;
+                let arr = self.alloc_temp_array(Vec::new());
+                if let Some(idx) = arr.to_array_idx() {
+                    self.resize_array(idx, len)?;
+                }
+                Ok(arr)
+            }
+            _ => Ok(self.alloc_derived_array(args.iter().copied())),
         }
     }
 
-    /// Convert a value to number
-    fn to_number(&self, val: Value) -> Value {
-        if let Some(n) = val.to_i32() {
-            Value::int(n)
-        } else if let Some(b) = val.to_bool() {
-            Value::int(if b { 1 } else { 0 })
-        } else if val.is_undefined() || val.is_null() {
-            Value::int(0) // Should be NaN for undefined, 0 for null
-        } else if let Some(str_idx) = val.to_string_idx() {
-            // Try to parse string as number
-            if let Some(s) = self.get_string_by_idx(str_idx) {
-                s.trim()
-                    .parse::<i32>()
-                    .map(Value::int)
-                    .unwrap_or(Value::int(0))
+    /// Build an instance of a builtin constructor for `new`, or None if the
+    /// builtin cannot be constructed
+    fn construct_builtin(
+        &mut self,
+        builtin_idx: u32,
+        args: &[Value],
+    ) -> InterpreterResult<Option<Value>> {
+        if let Some(error_name) = error_constructor_name(builtin_idx) {
+            let error = self.construct_error(error_name, args)?;
+            return Ok(Some(error));
+        }
+        if builtin_idx == BUILTIN_ARRAY {
+            return self.construct_array(args).map(Some);
+        }
+
+        // Check if this is the RegExp constructor
+        if builtin_idx == BUILTIN_REGEXP {
+            // Get pattern from first argument
+            let pattern = if let Some(pattern_val) = args.first() {
+                if pattern_val.is_string() {
+                    self.get_string(*pattern_val)
+                        .map(|s| s.to_string())
+                        .unwrap_or_default()
+                } else {
+                    String::new()
+                }
             } else {
-                Value::int(0)
+                String::new()
+            };
+
+            // Get flags from second argument (if present)
+            let flags = if let Some(flags_val) = args.get(1) {
+                if flags_val.is_string() {
+                    self.get_string(*flags_val)
+                        .map(|s| s.to_string())
+                        .unwrap_or_default()
+                } else {
+                    String::new()
+                }
+            } else {
+                String::new()
+            };
+
+            // Parse flags
+            let global = flags.contains('g');
+            let ignore_case = flags.contains('i');
+            let multiline = flags.contains('m');
+
+            let regex = compile_regexp(
+                &pattern,
+                RegexFlags {
+                    ignore_case,
+                    multiline,
+                },
+            )
+            .map_err(InterpreterError::SyntaxError)?;
+            let regex_idx = table_index(self.regex_objects.len())?;
+            self.regex_objects.push(RegExpObject {
+                regex,
+                pattern,
+                flags,
+                global,
+                ignore_case,
+                multiline,
+            });
+            self.count_allocation(|t| &mut t.regex_objects);
+            return Ok(Some(Value::regexp_object(regex_idx)));
+        }
+
+        // Check if this is a TypedArray constructor
+        if let Some(kind) = typed_array_kind(builtin_idx) {
+            // Get length from first argument
+            let length = if let Some(len_val) = args.first() {
+                if let Some(n) = len_val.to_i32() {
+                    usize::try_from(n).map_err(|_| {
+                        InterpreterError::RangeError(format!("invalid typed array length: {}", n))
+                    })?
+                } else if len_val.is_array() {
+                    // Creating from an array
+                    len_val
+                        .to_array_idx()
+                        .map_or(0, |arr_idx| self.array_length(arr_idx))
+                } else {
+                    0
+                }
+            } else {
+                0
+            };
+
+            // Create the typed array
+            let typed_idx = table_index(self.typed_arrays.len())?;
+            self.reserve_binary(length, kind.byte_size())?;
+            let mut typed_arr = TypedArrayObject::new(kind, length);
+
+            // If created from an array, copy values
+            if let Some(src_val) = args.first()
+                && let Some(arr_idx) = src_val.to_array_idx()
+            {
+                for (i, v) in self.array_entries(arr_idx) {
+                    if i >= length {
+                        break;
+                    }
+                    if let Some(n) = v.to_i32() {
+                        typed_arr.set(i, n);
+                    }
+                }
+            }
+
+            self.typed_arrays.push(typed_arr);
+            self.count_allocation(|t| &mut t.typed_arrays);
+            return Ok(Some(Value::typed_array_object(typed_idx)));
+        }
+
+        // Check if this is the Date constructor
+        if builtin_idx == BUILTIN_DATE {
+            let time = match args.first() {
+                None => Some(self.read_clock(|interp| interp.clock.now_ms())? as i64),
+                Some(arg) => {
+                    if let Some(t) = self.date_value(*arg) {
+                        t
+                    } else if arg.is_string() {
+                        self.get_string(*arg).and_then(|s| date::parse_iso8601(&s))
+                    } else {
+                        arg.to_i32()
+                            .and_then(|seconds| date::from_epoch_seconds(seconds.into()))
+                    }
+                }
+            };
+            let date = self.create_date(time);
+            return Ok(Some(date));
+        }
+
+        // Check if this is an ArrayBuffer constructor
+        if builtin_idx == BUILTIN_ARRAY_BUFFER {
+            let byte_length = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+            let byte_length = usize::try_from(byte_length).map_err(|_| {
+                InterpreterError::RangeError(format!(
+                    "invalid array buffer length: {}",
+                    byte_length
+                ))
+            })?;
+
+            let ab_idx = table_index(self.array_buffers.len())?;
+            self.reserve_binary(byte_length, 1)?;
+            let ab = ArrayBufferObject::new(byte_length);
+            self.array_buffers.push(ab);
+            self.count_allocation(|t| &mut t.array_buffers);
+            return Ok(Some(Value::array_buffer_object(ab_idx)));
+        }
+
+        Ok(None)
+    }
+
+    /// Describe an error and the chain of causes behind it, one
+    /// "caused by" line per cause
+    ///
+    /// Causes that are not errors end the chain and are shown as they
+    /// print. Chains longer than [`MAX_CAUSE_DEPTH`] are cut short, which
+    /// also ends cyclic ones.
+    fn error_chain_text(&self, err_idx: u32) -> String {
+        let describe = |err: &ErrorObject| {
+            if err.message.is_empty() {
+                err.name.clone()
+            } else {
+                format!("{}: {}", err.name, err.message)
+            }
+        };
+        let Some(mut err) = self.error_objects.get(err_idx as usize) else {
+            return "Error".to_string();
+        };
+        let mut text = describe(err);
+        for _ in 0..MAX_CAUSE_DEPTH {
+            let Some(cause) = err.cause else {
+                return text;
+            };
+            text.push_str("\n  caused by: ");
+            match cause
+                .to_error_object_idx()
+                .and_then(|idx| self.error_objects.get(idx as usize))
+            {
+                Some(inner) => {
+                    text.push_str(&describe(inner));
+                    err = inner;
+                }
+                None => {
+                    text.push_str(&format_value(self, cause));
+                    return text;
+                }
+            }
+        }
+        if err.cause.is_some() {
+            text.push_str("\n  caused by: ...");
+        }
+        text
+    }
+
+    /// Builtin string index of the `typeof` result for a value
+    fn type_of(&self, val: Value) -> u16 {
+        use crate::value::{
+            STR_BOOLEAN, STR_FUNCTION, STR_NUMBER, STR_OBJECT, STR_STRING, STR_UNDEFINED,
+        };
+
+        match val.classify() {
+            ValueTag::Undefined | ValueTag::Exception | ValueTag::Uninitialized => STR_UNDEFINED,
+            // typeof null === "object" (JavaScript quirk)
+            ValueTag::Null => STR_OBJECT,
+            ValueTag::Bool => STR_BOOLEAN,
+            ValueTag::Int => STR_NUMBER,
+            ValueTag::String => STR_STRING,
+            ValueTag::Closure | ValueTag::Bytecode | ValueTag::ShortFunc | ValueTag::NativeFunc => {
+                STR_FUNCTION
+            }
+            ValueTag::Builtin
+                if val
+                    .to_builtin_object_idx()
+                    .is_some_and(is_builtin_constructor) =>
+            {
+                STR_FUNCTION
+            }
+            // Bound functions are objects
+            ValueTag::Object if self.is_callable(val) => STR_FUNCTION,
+            ValueTag::Object
+            | ValueTag::Builtin
+            | ValueTag::Array
+            | ValueTag::Error
+            | ValueTag::RegExp
+            | ValueTag::TypedArray
+            | ValueTag::ArrayBuffer
+            | ValueTag::ForInIterator
+            | ValueTag::ForOfIterator => STR_OBJECT,
+        }
+    }
+
+    /// Convert a value to boolean (ToBoolean)
+    ///
+    /// The only truthiness test: conditions, `!`, `&&`, `||` and `Boolean()`
+    /// all go through it. false, 0, "", null and undefined are falsy;
+    /// everything else, empty arrays and objects included, is truthy.
+    fn to_boolean(&self, val: Value) -> bool {
+        match val.classify() {
+            ValueTag::Undefined
+            | ValueTag::Null
+            | ValueTag::Exception
+            | ValueTag::Uninitialized => false,
+            ValueTag::Bool => val.to_bool().unwrap_or(false),
+            ValueTag::Int => val.to_i32().is_some_and(|n| n != 0),
+            // Empty string is falsy
+            ValueTag::String => self.get_string(val).is_none_or(|s| !s.is_empty()),
+            ValueTag::Object
+            | ValueTag::Array
+            | ValueTag::Closure
+            | ValueTag::Bytecode
+            | ValueTag::ShortFunc
+            | ValueTag::NativeFunc
+            | ValueTag::Builtin
+            | ValueTag::Error
+            | ValueTag::RegExp
+            | ValueTag::TypedArray
+            | ValueTag::ArrayBuffer
+            | ValueTag::ForInIterator
+            | ValueTag::ForOfIterator => true,
+        }
+    }
+
+    /// The `[object ...]` brand of a value, as `Object.prototype.toString`
+    /// reports it
+    fn brand(&self, val: Value) -> &'static str {
+        match val.classify() {
+            ValueTag::Undefined | ValueTag::Exception | ValueTag::Uninitialized => "Undefined",
+            ValueTag::Null => "Null",
+            ValueTag::Bool => "Boolean",
+            ValueTag::Int => "Number",
+            ValueTag::String => "String",
+            ValueTag::Array => "Array",
+            ValueTag::Error => "Error",
+            ValueTag::RegExp => "RegExp",
+            ValueTag::TypedArray => val
+                .to_typed_array_idx()
+                .and_then(|idx| self.typed_arrays.get(idx as usize))
+                .map_or("Object", |ta| ta.kind.name()),
+            ValueTag::ArrayBuffer => "ArrayBuffer",
+            ValueTag::Closure | ValueTag::Bytecode | ValueTag::ShortFunc | ValueTag::NativeFunc => {
+                "Function"
+            }
+            ValueTag::Builtin => match val.to_builtin_object_idx() {
+                Some(BUILTIN_MATH) => "Math",
+                Some(BUILTIN_JSON) => "JSON",
+                Some(BUILTIN_REFLECT) => "Reflect",
+                Some(idx) if is_builtin_constructor(idx) => "Function",
+                _ => "Object",
+            },
+            ValueTag::Object if self.date_value(val).is_some() => "Date",
+            ValueTag::Object if self.promise_idx(val).is_some() => "Promise",
+            ValueTag::Object if self.is_callable(val) => "Function",
+            ValueTag::Object | ValueTag::ForInIterator | ValueTag::ForOfIterator => "Object",
+        }
+    }
+
+    /// Values a for-of loop over `obj` visits
+    ///
+    /// Arrays and typed arrays give their elements, strings their
+    /// characters and plain objects their property values; anything else
+    /// is not iterable.
+    fn for_of_iterator(&mut self, obj: Value) -> InterpreterResult<ForOfIterator> {
+        let values = match obj.classify() {
+            ValueTag::Array => match obj.to_array_idx() {
+                Some(idx) => self.array_values(idx)?,
+                None => Vec::new(),
+            },
+            ValueTag::Object => obj
+                .to_object_idx()
+                .and_then(|idx| self.get_object(idx))
+                .map(|o| o.properties.iter().map(|(_, v)| *v).collect())
+                .unwrap_or_default(),
+            ValueTag::TypedArray => self.typed_array_values(obj),
+            ValueTag::String => {
+                let text = self.get_string(obj).unwrap_or_default().to_string();
+                let mut buf = [0; 4];
+                text.chars()
+                    .map(|c| self.alloc_string(c.encode_utf8(&mut buf)))
+                    .collect()
+            }
+            ValueTag::Undefined
+            | ValueTag::Null
+            | ValueTag::Bool
+            | ValueTag::Int
+            | ValueTag::Closure
+            | ValueTag::Bytecode
+            | ValueTag::ShortFunc
+            | ValueTag::NativeFunc
+            | ValueTag::Builtin
+            | ValueTag::Error
+            | ValueTag::RegExp
+            | ValueTag::ArrayBuffer
+            | ValueTag::ForInIterator
+            | ValueTag::ForOfIterator
+            | ValueTag::Exception
+            | ValueTag::Uninitialized => {
+                return Err(InterpreterError::TypeError(format!(
+                    "{} is not iterable",
+                    format_value(self, obj)
+                )));
+            }
+        };
+        Ok(ForOfIterator { values, index: 0 })
+    }
+
+    /// Whether `obj instanceof ctor` holds
+    ///
+    /// Objects made by `new` match the constructor they were made with and
+    /// any whose `prototype` they inherit from; builtin kinds match their
+    /// builtin constructor, errors any error
+    /// constructor of their type or `Error`. Every object is an instance of
+    /// `Object`, and primitives of nothing.
+    fn instance_of(&self, obj: Value, ctor: Value) -> bool {
+        let ctor_builtin = ctor.to_builtin_object_idx();
+        let own_kind = match obj.classify() {
+            ValueTag::Undefined
+            | ValueTag::Null
+            | ValueTag::Bool
+            | ValueTag::Int
+            | ValueTag::String
+            | ValueTag::ForInIterator
+            | ValueTag::ForOfIterator
+            | ValueTag::Exception
+            | ValueTag::Uninitialized => return false,
+            ValueTag::Object => {
+                let stored = obj
+                    .to_object_idx()
+                    .and_then(|idx| self.get_object(idx))
+                    .and_then(|o| o.constructor);
+                stored == Some(ctor)
+                    || (ctor_builtin == Some(BUILTIN_DATE) && self.date_value(obj).is_some())
+                    || self
+                        .constructor_prototype(ctor)
+                        .is_some_and(|proto| self.prototype_chain(obj).contains(&proto))
+            }
+            ValueTag::Array => ctor_builtin == Some(BUILTIN_ARRAY),
+            ValueTag::Error => {
+                let name = obj
+                    .to_error_object_idx()
+                    .and_then(|idx| self.error_objects.get(idx as usize))
+                    .map(|err| err.name.as_str());
+                ctor_builtin
+                    .and_then(error_constructor_name)
+                    .is_some_and(|ctor_name| ctor_name == "Error" || name == Some(ctor_name))
             }
+            ValueTag::RegExp => ctor_builtin == Some(BUILTIN_REGEXP),
+            ValueTag::TypedArray => {
+                let kind = obj
+                    .to_typed_array_idx()
+                    .and_then(|idx| self.typed_arrays.get(idx as usize))
+                    .map(|ta| ta.kind);
+                kind.is_some() && ctor_builtin.and_then(typed_array_kind) == kind
+            }
+            ValueTag::ArrayBuffer => ctor_builtin == Some(BUILTIN_ARRAY_BUFFER),
+            ValueTag::Closure
+            | ValueTag::Bytecode
+            | ValueTag::ShortFunc
+            | ValueTag::NativeFunc
+            | ValueTag::Builtin => false,
+        };
+        own_kind || ctor_builtin == Some(BUILTIN_OBJECT)
+    }
+
+    /// ToPrimitive: convert an object or array to a primitive value
+    ///
+    /// Tries `valueOf` and then `toString`, or the other way round for the
+    /// String hint, and returns the first result that is not an object.
+    /// Fails with a TypeError if neither gives one. Other values are
+    /// returned unchanged, functions included.
+    fn coerce_to_primitive(&mut self, val: Value, hint: PrimitiveHint) -> InterpreterResult<Value> {
+        if !(val.is_object() || val.is_array()) {
+            return Ok(val);
+        }
+        let string_first = match hint {
+            PrimitiveHint::String => true,
+            PrimitiveHint::Number => false,
+            PrimitiveHint::Default => self.date_value(val).is_some(),
+        };
+        let methods = if string_first {
+            ["toString", "valueOf"]
         } else {
-            Value::int(0) // Should be NaN for objects
+            ["valueOf", "toString"]
+        };
+        for method in methods {
+            let func = self.get_property(val, method)?;
+            if self.is_callable(func) {
+                let result = self.call_value(func, val, &[])?;
+                if !(result.is_object() || result.is_array()) {
+                    return Ok(result);
+                }
+            }
         }
+        Err(InterpreterError::TypeError(
+            "Cannot convert object to primitive value".to_string(),
+        ))
     }
 
-    /// Convert a value to string
-    fn stringify_value(&mut self, val: Value) -> Value {
-        let s = if val.is_undefined() {
-            "undefined".to_string()
-        } else if val.is_null() {
-            "null".to_string()
-        } else if let Some(b) = val.to_bool() {
-            b.to_string()
-        } else if let Some(n) = val.to_i32() {
-            n.to_string()
-        } else if val.to_string_idx().is_some() {
+    /// Convert both operands of a binary operator with ToPrimitive
+    fn coerce_operands(
+        &mut self,
+        a: Value,
+        b: Value,
+        hint: PrimitiveHint,
+    ) -> InterpreterResult<(Value, Value)> {
+        Ok((
+            self.coerce_to_primitive(a, hint)?,
+            self.coerce_to_primitive(b, hint)?,
+        ))
+    }
+
+    /// Convert a value to number
+    ///
+    /// Objects are expected to have gone through ToPrimitive already. NaN
+    /// does not exist yet, so values that would convert to it give 0.
+    fn to_number(&self, val: Value) -> Value {
+        match val.classify() {
+            ValueTag::Int => val,
+            ValueTag::Bool => Value::int(i32::from(val.to_bool().unwrap_or(false))),
+            ValueTag::String => Value::int(
+                self.get_string(val)
+                    .and_then(|s| parse_number_string(&s))
+                    .unwrap_or(0),
+            ),
+            ValueTag::Undefined
+            | ValueTag::Null
+            | ValueTag::Object
+            | ValueTag::Array
+            | ValueTag::Closure
+            | ValueTag::Bytecode
+            | ValueTag::ShortFunc
+            | ValueTag::NativeFunc
+            | ValueTag::Builtin
+            | ValueTag::Error
+            | ValueTag::RegExp
+            | ValueTag::TypedArray
+            | ValueTag::ArrayBuffer
+            | ValueTag::ForInIterator
+            | ValueTag::ForOfIterator
+            | ValueTag::Exception
+            | ValueTag::Uninitialized => Value::int(0),
+        }
+    }
+
+    /// Convert a value to a string value (ToString)
+    fn stringify_value(&mut self, val: Value) -> Result<Value, HostError> {
+        if val.is_string() {
             // Already a string - return as-is
-            return val;
-        } else if val.is_array() {
-            "[object Array]".to_string()
-        } else if val.is_object() {
-            "[object Object]".to_string()
-        } else if val.is_closure() {
-            "[object Function]".to_string()
-        } else {
-            "".to_string()
+            return Ok(val);
+        }
+        let s = self.string_content(val, &mut HashSet::new())?;
+        Ok(self.create_runtime_string(s))
+    }
+
+    /// String contents of a value under ToString
+    ///
+    /// Arrays join their elements with ","; objects with a callable
+    /// `toString` use its result. `joining` holds the arrays currently being
+    /// joined, so reaching one of them again renders the cycle as empty.
+    fn string_content(
+        &mut self,
+        val: Value,
+        joining: &mut HashSet<u32>,
+    ) -> Result<String, HostError> {
+        let s = match val.classify() {
+            ValueTag::Array => {
+                let arr_idx = val.to_array_idx().unwrap_or_default();
+                if !joining.insert(arr_idx) {
+                    return Ok(String::new());
+                }
+                let len = self.array_length(arr_idx);
+                let entries = self.array_entries(arr_idx);
+                let joined = self.join_values(len, &entries, ",", joining);
+                joining.remove(&arr_idx);
+                joined?
+            }
+            ValueTag::Object => match self.date_value(val) {
+                Some(time) => time.map_or_else(|| "Invalid Date".to_string(), date::to_date_string),
+                None => {
+                    let primitive = self.coerce_to_primitive(val, PrimitiveHint::String)?;
+                    self.string_content(primitive, joining)?
+                }
+            },
+            ValueTag::TypedArray => {
+                let elements = self.typed_array_values(val);
+                let entries: Vec<_> = elements.into_iter().enumerate().collect();
+                self.join_values(entries.len(), &entries, ",", joining)?
+            }
+            ValueTag::Undefined
+            | ValueTag::Null
+            | ValueTag::Bool
+            | ValueTag::Int
+            | ValueTag::String
+            | ValueTag::Closure
+            | ValueTag::Bytecode
+            | ValueTag::ShortFunc
+            | ValueTag::NativeFunc
+            | ValueTag::Builtin
+            | ValueTag::Error
+            | ValueTag::RegExp
+            | ValueTag::ArrayBuffer
+            | ValueTag::ForInIterator
+            | ValueTag::ForOfIterator
+            | ValueTag::Exception
+            | ValueTag::Uninitialized => self.plain_string_content(val),
         };
-        self.create_runtime_string(s)
+        Ok(s)
+    }
+
+    /// String contents under ToString of a value that converts without
+    /// running script code
+    ///
+    /// Errors read as `Error.prototype.toString` gives them, regexps as
+    /// their literal and other objects as their `[object ...]` brand.
+    /// Arrays, typed arrays and plain objects, which need
+    /// [`string_content`](Self::string_content), give their brand.
+    fn plain_string_content(&self, val: Value) -> String {
+        match val.classify() {
+            ValueTag::Undefined | ValueTag::Exception | ValueTag::Uninitialized => {
+                "undefined".to_string()
+            }
+            ValueTag::Null => "null".to_string(),
+            ValueTag::Bool => val.to_bool().unwrap_or(false).to_string(),
+            ValueTag::Int => IntStr::new(val.to_i32().unwrap_or(0)).as_str().to_string(),
+            ValueTag::String => self.get_string(val).unwrap_or_default().to_string(),
+            ValueTag::Error => match val
+                .to_error_object_idx()
+                .and_then(|idx| self.error_objects.get(idx as usize))
+            {
+                Some(err) if err.message.is_empty() => err.name.clone(),
+                Some(err) => format!("{}: {}", err.name, err.message),
+                None => "Error".to_string(),
+            },
+            ValueTag::RegExp => val
+                .to_regexp_object_idx()
+                .and_then(|idx| self.regex_objects.get(idx as usize))
+                .map_or_else(String::new, |re| format!("/{}/{}", re.pattern, re.flags)),
+            ValueTag::Object
+            | ValueTag::Array
+            | ValueTag::TypedArray
+            | ValueTag::Closure
+            | ValueTag::Bytecode
+            | ValueTag::ShortFunc
+            | ValueTag::NativeFunc
+            | ValueTag::Builtin
+            | ValueTag::ArrayBuffer
+            | ValueTag::ForInIterator
+            | ValueTag::ForOfIterator => format!("[object {}]", self.brand(val)),
+        }
+    }
+
+    /// Elements of a typed array as values, or none for other values
+    fn typed_array_values(&self, val: Value) -> Vec<Value> {
+        val.to_typed_array_idx()
+            .and_then(|idx| self.typed_arrays.get(idx as usize))
+            .map(|ta| {
+                (0..ta.length)
+                    .filter_map(|i| ta.get(i))
+                    .map(Value::int)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Join values with a separator, rendering null and undefined as empty
+    ///
+    /// `entries` are the present `(index, value)` pairs of a `len`-long
+    /// list in index order; the indices between them are holes, which join
+    /// as empty too.
+    fn join_values(
+        &mut self,
+        len: usize,
+        entries: &[(usize, Value)],
+        separator: &str,
+        joining: &mut HashSet<u32>,
+    ) -> Result<String, HostError> {
+        let mut out = String::new();
+        // Index 0 has no separator before it; every later index adds one,
+        // holes included
+        let mut next = 1;
+        for &(i, element) in entries {
+            let piece = if let Some(n) = element.to_i32() {
+                IntStr::new(n).as_str().to_string()
+            } else if let Some(s) = self.get_string(element) {
+                s.to_string()
+            } else if !element.is_undefined() && !element.is_null() {
+                self.string_content(element, joining)?
+            } else {
+                String::new()
+            };
+            // The result is checked as it grows, before each piece is added
+            let seps = (i + 1).saturating_sub(next);
+            let sep_len = separator.len().saturating_mul(seps);
+            self.reserve_string(
+                out.len()
+                    .saturating_add(sep_len)
+                    .saturating_add(piece.len()),
+            )?;
+            out.push_str(&separator.repeat(seps));
+            out.push_str(&piece);
+            next = next.max(i + 1);
+        }
+        let seps = len.saturating_sub(next);
+        self.reserve_string(
+            out.len()
+                .saturating_add(separator.len().saturating_mul(seps)),
+        )?;
+        out.push_str(&separator.repeat(seps));
+        Ok(out)
     }
 
     /// Register built-in native functions
     fn register_builtins(&mut self) {
-        // Array methods
-        self.register_native("Array.prototype.push", native_array_push, 0);
-        self.register_native("Array.prototype.pop", native_array_pop, 0);
-        self.register_native("Array.prototype.length", native_array_length, 0);
-        self.register_native("Array.prototype.shift", native_array_shift, 0);
-        self.register_native("Array.prototype.unshift", native_array_unshift, 0);
-        self.register_native("Array.prototype.indexOf", native_array_index_of, 1);
-        self.register_native("Array.prototype.lastIndexOf", native_array_last_index_of, 1);
-        self.register_native("Array.prototype.join", native_array_join, 0);
-        self.register_native("Array.prototype.reverse", native_array_reverse, 0);
-        self.register_native("Array.prototype.slice", native_array_slice, 0);
-        self.register_native("Array.prototype.map", native_array_map, 1);
-        self.register_native("Array.prototype.filter", native_array_filter, 1);
-        self.register_native("Array.prototype.forEach", native_array_foreach, 1);
-        self.register_native("Array.prototype.reduce", native_array_reduce, 1);
-        self.register_native("Array.prototype.find", native_array_find, 1);
-        self.register_native("Array.prototype.findIndex", native_array_find_index, 1);
-        self.register_native("Array.prototype.some", native_array_some, 1);
-        self.register_native("Array.prototype.every", native_array_every, 1);
-        self.register_native("Array.prototype.includes", native_array_includes, 1);
-        self.register_native("Array.prototype.concat", native_array_concat, 0);
-        self.register_native("Array.prototype.sort", native_array_sort, 0);
-        self.register_native("Array.prototype.flat", native_array_flat, 0);
-        self.register_native("Array.prototype.fill", native_array_fill, 1);
-
-        // TypedArray.prototype methods
-        self.register_native(
-            "TypedArray.prototype.subarray",
-            native_typed_array_subarray,
-            2,
-        );
-
-        // Global functions
-        self.register_native("parseInt", native_parse_int, 1);
-        self.register_native("parseFloat", native_parse_float, 1);
-        self.register_native("isNaN", native_is_nan, 1);
-        self.register_native("isFinite", native_is_finite, 1);
-
-        // Math functions
-        self.register_native("Math.abs", native_math_abs, 1);
-        self.register_native("Math.floor", native_math_floor, 1);
-        self.register_native("Math.ceil", native_math_ceil, 1);
-        self.register_native("Math.round", native_math_round, 1);
-        self.register_native("Math.sqrt", native_math_sqrt, 1);
-        self.register_native("Math.pow", native_math_pow, 2);
-        self.register_native("Math.max", native_math_max, 0);
-        self.register_native("Math.min", native_math_min, 0);
-        // mquickjs-specific Math functions
-        self.register_native("Math.imul", native_math_imul, 2);
-        self.register_native("Math.clz32", native_math_clz32, 1);
-        self.register_native("Math.fround", native_math_fround, 1);
-        self.register_native("Math.trunc", native_math_trunc, 1);
-        self.register_native("Math.log2", native_math_log2, 1);
-        self.register_native("Math.log10", native_math_log10, 1);
-        self.register_native("Math.sign", native_math_sign, 1);
-        self.register_native("Math.sin", native_math_sin, 1);
-        self.register_native("Math.cos", native_math_cos, 1);
-        self.register_native("Math.tan", native_math_tan, 1);
-        self.register_native("Math.exp", native_math_exp, 1);
-        self.register_native("Math.log", native_math_log, 1);
-        self.register_native("Math.random", native_math_random, 0);
-        self.register_native("Math.atan2", native_math_atan2, 2);
-        self.register_native("Math.asin", native_math_asin, 1);
-        self.register_native("Math.acos", native_math_acos, 1);
-        self.register_native("Math.atan", native_math_atan, 1);
-
-        // String methods
-        self.register_native("String.prototype.charAt", native_string_char_at, 1);
-        self.register_native("String.prototype.charCodeAt", native_string_char_code_at, 1);
-        self.register_native("String.prototype.indexOf", native_string_index_of, 1);
-        self.register_native(
-            "String.prototype.lastIndexOf",
-            native_string_last_index_of,
-            1,
-        );
-        self.register_native("String.fromCharCode", native_string_from_char_code, 0);
-        self.register_native("String.fromCodePoint", native_string_from_code_point, 0);
-        self.register_native("String.prototype.slice", native_string_slice, 0);
-        self.register_native("String.prototype.substring", native_string_substring, 0);
-        self.register_native(
-            "String.prototype.toUpperCase",
-            native_string_to_upper_case,
-            0,
-        );
-        self.register_native(
-            "String.prototype.toLowerCase",
-            native_string_to_lower_case,
-            0,
-        );
-        self.register_native("String.prototype.trim", native_string_trim, 0);
-        self.register_native("String.prototype.split", native_string_split, 0);
-        self.register_native("String.prototype.concat", native_string_concat, 0);
-        self.register_native("String.prototype.repeat", native_string_repeat, 1);
-        self.register_native("String.prototype.startsWith", native_string_starts_with, 1);
-        self.register_native("String.prototype.endsWith", native_string_ends_with, 1);
-        self.register_native("String.prototype.padStart", native_string_pad_start, 1);
-        self.register_native("String.prototype.padEnd", native_string_pad_end, 1);
-        self.register_native("String.prototype.replace", native_string_replace, 2);
-        self.register_native("String.prototype.includes", native_string_includes, 1);
-        self.register_native("String.prototype.match", native_string_match, 1);
-        self.register_native("String.prototype.search", native_string_search, 1);
-        // mquickjs-specific String methods
-        self.register_native(
-            "String.prototype.codePointAt",
-            native_string_code_point_at,
-            1,
-        );
-        self.register_native("String.prototype.trimStart", native_string_trim_start, 0);
-        self.register_native("String.prototype.trimEnd", native_string_trim_end, 0);
-        self.register_native("String.prototype.replaceAll", native_string_replace_all, 2);
-
-        // Number static methods
-        self.register_native("Number.isInteger", native_number_is_integer, 1);
-        self.register_native("Number.isNaN", native_number_is_nan, 1);
-        self.register_native("Number.isFinite", native_number_is_finite, 1);
-
-        // Number.prototype methods
-        self.register_native("Number.prototype.toString", native_number_to_string, 0);
-        self.register_native("Number.prototype.toFixed", native_number_to_fixed, 0);
-        self.register_native(
-            "Number.prototype.toExponential",
-            native_number_to_exponential,
-            0,
-        );
-        self.register_native(
-            "Number.prototype.toPrecision",
-            native_number_to_precision,
-            0,
-        );
+        // Receiver policies (see `Receiver`)
+        const NONE: Receiver = Receiver::None;
+        const ANY: Receiver = Receiver::Optional;
+        const COERCIBLE: Receiver = Receiver::Required(ReceiverKind::Coercible);
+        const OBJECT: Receiver = Receiver::Required(ReceiverKind::Object);
+        const ARRAY: Receiver = Receiver::Required(ReceiverKind::Array);
+        const NUMBER: Receiver = Receiver::Required(ReceiverKind::Number);
+        const FUNCTION: Receiver = Receiver::Required(ReceiverKind::Function);
+        const DATE: Receiver = Receiver::Required(ReceiverKind::Date);
+        const PROMISE: Receiver = Receiver::Required(ReceiverKind::Promise);
+        const REGEXP: Receiver = Receiver::Required(ReceiverKind::RegExp);
+        const TYPED_ARRAY: Receiver = Receiver::Required(ReceiverKind::TypedArray);
+
+        let builtins: &[(&str, NativeFn, u8, Receiver)] = &[
+            // Array methods
+            ("Array.prototype.push", native_array_push, 0, OBJECT),
+            ("Array.prototype.pop", native_array_pop, 0, ARRAY),
+            ("Array.prototype.length", native_array_length, 0, ARRAY),
+            ("Array.prototype.shift", native_array_shift, 0, ARRAY),
+            ("Array.prototype.unshift", native_array_unshift, 0, ARRAY),
+            (
+                "Array.prototype.indexOf",
+                native_array_index_of,
+                1,
+                COERCIBLE,
+            ),
+            (
+                "Array.prototype.lastIndexOf",
+                native_array_last_index_of,
+                1,
+                COERCIBLE,
+            ),
+            ("Array.prototype.join", native_array_join, 0, COERCIBLE),
+            ("Array.prototype.reverse", native_array_reverse, 0, ARRAY),
+            ("Array.prototype.slice", native_array_slice, 0, COERCIBLE),
+            ("Array.prototype.map", native_array_map, 1, COERCIBLE),
+            ("Array.prototype.filter", native_array_filter, 1, COERCIBLE),
+            (
+                "Array.prototype.forEach",
+                native_array_foreach,
+                1,
+                COERCIBLE,
+            ),
+            ("Array.prototype.reduce", native_array_reduce, 1, COERCIBLE),
+            ("Array.prototype.find", native_array_find, 1, COERCIBLE),
+            (
+                "Array.prototype.findIndex",
+                native_array_find_index,
+                1,
+                COERCIBLE,
+            ),
+            ("Array.prototype.some", native_array_some, 1, COERCIBLE),
+            ("Array.prototype.every", native_array_every, 1, COERCIBLE),
+            (
+                "Array.prototype.includes",
+                native_array_includes,
+                1,
+                COERCIBLE,
+            ),
+            ("Array.prototype.concat", native_array_concat, 0, ARRAY),
+            ("Array.prototype.sort", native_array_sort, 0, ARRAY),
+            ("Array.prototype.flat", native_array_flat, 0, COERCIBLE),
+            ("Array.prototype.fill", native_array_fill, 1, ARRAY),
+            // TypedArray.prototype methods
+            (
+                "TypedArray.prototype.subarray",
+                native_typed_array_subarray,
+                2,
+                TYPED_ARRAY,
+            ),
+            // Global functions
+            ("parseInt", native_parse_int, 1, NONE),
+            ("parseFloat", native_parse_float, 1, NONE),
+            ("isNaN", native_is_nan, 1, NONE),
+            ("isFinite", native_is_finite, 1, NONE),
+            // Math functions
+            ("Math.abs", native_math_abs, 1, NONE),
+            ("Math.floor", native_math_floor, 1, NONE),
+            ("Math.ceil", native_math_ceil, 1, NONE),
+            ("Math.round", native_math_round, 1, NONE),
+            ("Math.sqrt", native_math_sqrt, 1, NONE),
+            ("Math.pow", native_math_pow, 2, NONE),
+            ("Math.max", native_math_max, 0, NONE),
+            ("Math.min", native_math_min, 0, NONE),
+            // mquickjs-specific Math functions
+            ("Math.imul", native_math_imul, 2, NONE),
+            ("Math.clz32", native_math_clz32, 1, NONE),
+            ("Math.fround", native_math_fround, 1, NONE),
+            ("Math.trunc", native_math_trunc, 1, NONE),
+            ("Math.log2", native_math_log2, 1, NONE),
+            ("Math.log10", native_math_log10, 1, NONE),
+            ("Math.sign", native_math_sign, 1, NONE),
+            ("Math.sin", native_math_sin, 1, NONE),
+            ("Math.cos", native_math_cos, 1, NONE),
+            ("Math.tan", native_math_tan, 1, NONE),
+            ("Math.exp", native_math_exp, 1, NONE),
+            ("Math.log", native_math_log, 1, NONE),
+            ("Math.random", native_math_random, 0, NONE),
+            ("Math.atan2", native_math_atan2, 2, NONE),
+            ("Math.asin", native_math_asin, 1, NONE),
+            ("Math.acos", native_math_acos, 1, NONE),
+            ("Math.atan", native_math_atan, 1, NONE),
+            // String methods
+            (
+                "String.prototype.charAt",
+                native_string_char_at,
+                1,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.charCodeAt",
+                native_string_char_code_at,
+                1,
+                COERCIBLE,
+            ),
+            ("String.prototype.at", native_string_at, 1, COERCIBLE),
+            (
+                "String.prototype.indexOf",
+                native_string_index_of,
+                1,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.lastIndexOf",
+                native_string_last_index_of,
+                1,
+                COERCIBLE,
+            ),
+            ("String.fromCharCode", native_string_from_char_code, 0, NONE),
+            (
+                "String.fromCodePoint",
+                native_string_from_code_point,
+                0,
+                NONE,
+            ),
+            ("String.prototype.slice", native_string_slice, 0, COERCIBLE),
+            (
+                "String.prototype.substring",
+                native_string_substring,
+                0,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.toUpperCase",
+                native_string_to_upper_case,
+                0,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.toLowerCase",
+                native_string_to_lower_case,
+                0,
+                COERCIBLE,
+            ),
+            ("String.prototype.trim", native_string_trim, 0, COERCIBLE),
+            ("String.prototype.split", native_string_split, 0, COERCIBLE),
+            (
+                "String.prototype.concat",
+                native_string_concat,
+                0,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.repeat",
+                native_string_repeat,
+                1,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.startsWith",
+                native_string_starts_with,
+                1,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.endsWith",
+                native_string_ends_with,
+                1,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.padStart",
+                native_string_pad_start,
+                1,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.padEnd",
+                native_string_pad_end,
+                1,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.replace",
+                native_string_replace,
+                2,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.includes",
+                native_string_includes,
+                1,
+                COERCIBLE,
+            ),
+            ("String.prototype.match", native_string_match, 1, COERCIBLE),
+            (
+                "String.prototype.search",
+                native_string_search,
+                1,
+                COERCIBLE,
+            ),
+            // mquickjs-specific String methods
+            (
+                "String.prototype.codePointAt",
+                native_string_code_point_at,
+                1,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.trimStart",
+                native_string_trim_start,
+                0,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.trimEnd",
+                native_string_trim_end,
+                0,
+                COERCIBLE,
+            ),
+            (
+                "String.prototype.replaceAll",
+                native_string_replace_all,
+                2,
+                COERCIBLE,
+            ),
+            // Number static methods
+            ("Number.isInteger", native_number_is_integer, 1, NONE),
+            ("Number.isNaN", native_number_is_nan, 1, NONE),
+            ("Number.isFinite", native_number_is_finite, 1, NONE),
+            // Number.prototype methods
+            (
+                "Number.prototype.toString",
+                native_number_to_string,
+                0,
+                NUMBER,
+            ),
+            (
+                "Number.prototype.toFixed",
+                native_number_to_fixed,
+                0,
+                NUMBER,
+            ),
+            (
+                "Number.prototype.toExponential",
+                native_number_to_exponential,
+                0,
+                NUMBER,
+            ),
+            (
+                "Number.prototype.toPrecision",
+                native_number_to_precision,
+                0,
+                NUMBER,
+            ),
+            // console methods
+            ("console.log", native_console_log, 0, NONE),
+            ("console.error", native_console_error, 0, NONE),
+            ("console.warn", native_console_warn, 0, NONE),
+            ("console.time", native_console_time, 0, NONE),
+            ("console.timeEnd", native_console_time_end, 0, NONE),
+            ("console.table", native_console_table, 1, NONE),
+            // JSON methods
+            ("JSON.stringify", native_json_stringify, 1, NONE),
+            ("JSON.parse", native_json_parse, 1, NONE),
+            // Reflect methods
+            ("Reflect.has", native_reflect_has, 2, NONE),
+            ("Reflect.get", native_reflect_get, 2, NONE),
+            ("Reflect.set", native_reflect_set, 3, NONE),
+            ("Reflect.ownKeys", native_reflect_own_keys, 1, NONE),
+            // Date methods
+            ("Date.now", native_date_now, 0, NONE),
+            ("Date.parse", native_date_parse, 1, NONE),
+            ("Date.prototype.getTime", native_date_get_time, 0, DATE),
+            ("Date.prototype.valueOf", native_date_get_time, 0, DATE),
+            (
+                "Date.prototype.getFullYear",
+                native_date_get_full_year,
+                0,
+                DATE,
+            ),
+            ("Date.prototype.getMonth", native_date_get_month, 0, DATE),
+            ("Date.prototype.getDate", native_date_get_date, 0, DATE),
+            ("Date.prototype.getDay", native_date_get_day, 0, DATE),
+            ("Date.prototype.getHours", native_date_get_hours, 0, DATE),
+            (
+                "Date.prototype.getMinutes",
+                native_date_get_minutes,
+                0,
+                DATE,
+            ),
+            (
+                "Date.prototype.getSeconds",
+                native_date_get_seconds,
+                0,
+                DATE,
+            ),
+            (
+                "Date.prototype.getMilliseconds",
+                native_date_get_milliseconds,
+                0,
+                DATE,
+            ),
+            (
+                "Date.prototype.toISOString",
+                native_date_to_iso_string,
+                0,
+                DATE,
+            ),
+            ("Date.prototype.toJSON", native_date_to_json, 1, DATE),
+            ("Date.prototype.toString", native_date_to_string, 0, DATE),
+            ("performance.now", native_performance_now, 0, NONE),
+            ("performance.mark", native_performance_mark, 1, NONE),
+            ("performance.measure", native_performance_measure, 1, NONE),
+            (
+                "performance.getEntriesByName",
+                native_performance_get_entries_by_name,
+                1,
+                NONE,
+            ),
+            (
+                "performance.clearMarks",
+                native_performance_clear_marks,
+                0,
+                NONE,
+            ),
+            (
+                "performance.clearMeasures",
+                native_performance_clear_measures,
+                0,
+                NONE,
+            ),
+            // Promise methods
+            ("Promise.prototype.then", native_promise_then, 2, PROMISE),
+            ("Promise.prototype.catch", native_promise_catch, 1, PROMISE),
+            // RegExp methods
+            ("RegExp.prototype.test", native_regexp_test, 1, REGEXP),
+            ("RegExp.prototype.exec", native_regexp_exec, 1, REGEXP),
+            // Object static methods
+            ("Object.keys", native_object_keys, 1, NONE),
+            (
+                "Object.getOwnPropertyNames",
+                native_object_get_own_property_names,
+                1,
+                NONE,
+            ),
+            ("Object.values", native_object_values, 1, NONE),
+            ("Object.entries", native_object_entries, 1, NONE),
+            (
+                "Object.getPrototypeOf",
+                native_object_get_prototype_of,
+                1,
+                NONE,
+            ),
+            (
+                "Object.setPrototypeOf",
+                native_object_set_prototype_of,
+                2,
+                NONE,
+            ),
+            ("Object.create", native_object_create, 1, NONE),
+            ("Object.assign", native_object_assign, 2, NONE),
+            (
+                "Object.defineProperty",
+                native_object_define_property,
+                3,
+                NONE,
+            ),
+            (
+                "Object.getOwnPropertyDescriptor",
+                native_object_get_own_property_descriptor,
+                2,
+                NONE,
+            ),
+            (
+                "Object.getOwnPropertyDescriptors",
+                native_object_get_own_property_descriptors,
+                1,
+                NONE,
+            ),
+            // Object.prototype methods
+            (
+                "Object.prototype.hasOwnProperty",
+                native_object_has_own_property,
+                1,
+                ANY,
+            ),
+            (
+                "Object.prototype.propertyIsEnumerable",
+                native_object_property_is_enumerable,
+                1,
+                ANY,
+            ),
+            ("Object.prototype.toString", native_object_to_string, 0, ANY),
+            // Array static methods
+            ("Array.isArray", native_array_is_array, 1, NONE),
+            // Function.prototype methods
+            ("Function.prototype.call", native_function_call, 0, FUNCTION),
+            (
+                "Function.prototype.apply",
+                native_function_apply,
+                0,
+                FUNCTION,
+            ),
+            ("Function.prototype.bind", native_function_bind, 0, FUNCTION),
+            (
+                "Function.prototype.toString",
+                native_function_to_string,
+                0,
+                NONE,
+            ),
+            // Error.prototype methods
+            ("Error.prototype.toString", native_error_to_string, 0, ANY),
+            // Array.prototype.toString and reduceRight
+            ("Array.prototype.toString", native_array_to_string, 0, ANY),
+            (
+                "Array.prototype.reduceRight",
+                native_array_reduce_right,
+                2,
+                COERCIBLE,
+            ),
+            // Global utility functions
+            ("gc", native_gc, 0, NONE),
+            ("setTimeout", native_set_timeout, 2, NONE),
+            ("clearTimeout", native_clear_timeout, 1, NONE),
+        ];
+        for &(name, func, arity, receiver) in builtins {
+            self.register_native_with_receiver(name, func, arity, receiver);
+        }
+        #[cfg(feature = "std")]
+        self.register_native_with_receiver("load", native_load, 1, NONE);
 
-        // console methods
-        self.register_native("console.log", native_console_log, 0);
-        self.register_native("console.error", native_console_error, 0);
-        self.register_native("console.warn", native_console_warn, 0);
-
-        // JSON methods
-        self.register_native("JSON.stringify", native_json_stringify, 1);
-        self.register_native("JSON.parse", native_json_parse, 1);
-
-        // Date methods
-        self.register_native("Date.now", native_date_now, 0);
-        self.register_native("performance.now", native_performance_now, 0);
-
-        // RegExp methods
-        self.register_native("RegExp.prototype.test", native_regexp_test, 1);
-        self.register_native("RegExp.prototype.exec", native_regexp_exec, 1);
-
-        // Object static methods
-        self.register_native("Object.keys", native_object_keys, 1);
-        self.register_native("Object.values", native_object_values, 1);
-        self.register_native("Object.entries", native_object_entries, 1);
-        self.register_native("Object.getPrototypeOf", native_object_get_prototype_of, 1);
-        self.register_native("Object.setPrototypeOf", native_object_set_prototype_of, 2);
-        self.register_native("Object.create", native_object_create, 1);
-        self.register_native("Object.defineProperty", native_object_define_property, 3);
-        // Object.prototype methods
-        self.register_native(
-            "Object.prototype.hasOwnProperty",
-            native_object_has_own_property,
-            1,
-        );
-        self.register_native("Object.prototype.toString", native_object_to_string, 0);
+        // Interned strings are never reclaimed
+        self.intern_string("RangeError");
+        self.intern_string(STRING_TABLE_FULL);
+    }
+}
 
-        // Array static methods
-        self.register_native("Array.isArray", native_array_is_array, 1);
+// =============================================================================
+// Native function implementations
+// =============================================================================
 
-        // Function.prototype methods
-        self.register_native("Function.prototype.call", native_function_call, 0);
-        self.register_native("Function.prototype.apply", native_function_apply, 0);
-        self.register_native("Function.prototype.bind", native_function_bind, 0);
-        self.register_native("Function.prototype.toString", native_function_to_string, 0);
+/// Short description of a receiver's type for error messages
+pub(crate) fn receiver_description(this: Value) -> &'static str {
+    if this.is_undefined() {
+        "undefined"
+    } else if this.is_null() {
+        "null"
+    } else if this.is_bool() {
+        "boolean"
+    } else if this.is_int() {
+        "number"
+    } else if this.is_string() {
+        "string"
+    } else if this.to_array_idx().is_some() {
+        "array"
+    } else if this.is_func()
+        || this.to_func_ptr().is_some()
+        || this.is_closure()
+        || this.is_native_func()
+    {
+        "function"
+    } else {
+        "object"
+    }
+}
 
-        // Error.prototype methods
-        self.register_native("Error.prototype.toString", native_error_to_string, 0);
+/// TypeError for a prototype method called on a receiver it cannot handle,
+/// e.g. "Array.prototype.push called on undefined"
+fn incompatible_receiver(method: &str, this: Value) -> HostError {
+    HostError::Type(format!(
+        "{} called on {}",
+        method,
+        receiver_description(this)
+    ))
+}
 
-        // Array.prototype.toString and reduceRight
-        self.register_native("Array.prototype.toString", native_array_to_string, 0);
-        self.register_native("Array.prototype.reduceRight", native_array_reduce_right, 2);
+/// Read the length of an array-like object (missing or invalid length is 0)
+///
+/// The length is script-controlled, so one past the array length limit is
+/// a RangeError rather than a loop bound.
+fn array_like_length(interp: &Interpreter, obj_idx: u32) -> Result<usize, HostError> {
+    let len = interp
+        .object_get_property(obj_idx, "length")
+        .to_i32()
+        .unwrap_or(0)
+        .max(0) as usize;
+    interp.check_array_length(len)?;
+    Ok(len)
+}
 
-        // Global utility functions
-        self.register_native("gc", native_gc, 0);
-        self.register_native("load", native_load, 1);
-        self.register_native("setTimeout", native_set_timeout, 2);
-        self.register_native("clearTimeout", native_clear_timeout, 1);
+/// Collect the indexed elements of an array-like object
+///
+/// Array-likes are plain objects with a numeric `length`; missing indices
+/// read as undefined. The collected elements are checked against the
+/// memory budget before they are read.
+fn array_like_elements(interp: &mut Interpreter, obj_idx: u32) -> Result<Vec<Value>, HostError> {
+    let len = array_like_length(interp, obj_idx)?;
+    interp.check_memory(len.saturating_mul(core::mem::size_of::<Value>()))?;
+    Ok((0..len)
+        .map(|i| interp.object_get_property(obj_idx, &i.to_string()))
+        .collect())
+}
+
+/// Receiver coercion for the String and Array prototype methods
+///
+/// | receiver              | String methods | Array read methods | Array mutators |
+/// |-----------------------|----------------|--------------------|----------------|
+/// | string                | as is          | its characters     | TypeError      |
+/// | number, boolean       | ToString       | TypeError          | TypeError      |
+/// | array                 | ToString       | as is              | as is          |
+/// | object with `length`  | ToString       | indexed properties | push only      |
+/// | null, undefined       | TypeError      | TypeError          | TypeError      |
+///
+/// Strings are immutable here, so methods that write to the receiver keep
+/// rejecting them rather than failing partway through. The String methods
+/// are registered with a coercible receiver, so null and undefined are
+/// rejected before they run and this only reads the receiver's content.
+fn string_receiver(interp: &mut Interpreter, this: Value) -> Result<String, HostError> {
+    interp.string_content(this, &mut HashSet::new())
+}
+
+/// Elements of an array, string or array-like object receiver, for the
+/// Array methods that only read their receiver (see
+/// [`string_receiver`] for the full matrix)
+fn require_arraylike_receiver(
+    interp: &mut Interpreter,
+    this: Value,
+    method: &str,
+) -> Result<Vec<Value>, HostError> {
+    if let Some(arr_idx) = this.to_array_idx() {
+        return interp.array_values(arr_idx).map_err(HostError::from);
+    }
+    if this.is_string() {
+        let s = interp.get_string(this).unwrap_or_default().to_string();
+        let mut buf = [0u8; 4];
+        return Ok(s
+            .chars()
+            .map(|ch| interp.alloc_string(ch.encode_utf8(&mut buf)))
+            .collect());
     }
+    let obj_idx = this
+        .to_object_idx()
+        .ok_or_else(|| incompatible_receiver(&format!("Array.prototype.{}", method), this))?;
+    array_like_elements(interp, obj_idx)
 }
 
-// =============================================================================
-// Native function implementations
-// =============================================================================
+/// Length and elements of an array read method's receiver, for the
+/// methods that skip holes
+///
+/// Arrays give only the elements that are not holes, however they are
+/// stored; strings and array-likes give every index, as
+/// [`require_arraylike_receiver`] does.
+fn require_array_entries(
+    interp: &mut Interpreter,
+    this: Value,
+    method: &str,
+) -> Result<(usize, Vec<(usize, Value)>), HostError> {
+    if let Some(arr_idx) = this.to_array_idx() {
+        return Ok((interp.array_length(arr_idx), interp.array_entries(arr_idx)));
+    }
+    let elements = require_arraylike_receiver(interp, this, method)?;
+    Ok((elements.len(), elements.into_iter().enumerate().collect()))
+}
 
 /// Array.prototype.push - add elements to end of array
 fn native_array_push(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
+    // Array-like object: write indexed properties and bump length
+    if let Some(obj_idx) = this.to_object_idx() {
+        let mut len = array_like_length(interp, obj_idx)?;
+        interp.check_array_length(len.saturating_add(args.len()))?;
+        for arg in args {
+            let key = interp.atom(&len.to_string());
+            interp.object_set_property(obj_idx, key, *arg);
+            len += 1;
+        }
+        let key = interp.atom("length");
+        interp.object_set_property(obj_idx, key, Value::int_saturating(len as i64));
+        return Ok(Value::int_saturating(len as i64));
+    }
+
     let arr_idx = this
         .to_array_idx()
-        .ok_or_else(|| "push called on non-array".to_string())?;
+        .ok_or_else(|| incompatible_receiver("Array.prototype.push", this))?;
 
-    if let Some(arr) = interp.arrays.get_mut(arr_idx as usize) {
-        for arg in args {
-            arr.push(*arg);
+    if interp.sparse_arrays.contains_key(&arr_idx) {
+        let len = interp.array_length(arr_idx);
+        for (i, &arg) in args.iter().enumerate() {
+            interp.set_array_element(arr_idx, len.saturating_add(i), arg)?;
         }
-        Ok(Value::int(arr.len() as i32))
-    } else {
-        Err("invalid array".to_string())
+        return Ok(Value::int_saturating(interp.array_length(arr_idx) as i64));
     }
+    let len = interp.reserve_array(arr_idx, args.len())?;
+    interp.arrays[arr_idx as usize].extend_from_slice(args);
+    Ok(Value::int(len as i32))
 }
 
 /// Array.prototype.pop - remove and return last element
@@ -4333,15 +9716,27 @@ fn native_array_pop(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let arr_idx = this
         .to_array_idx()
-        .ok_or_else(|| "pop called on non-array".to_string())?;
+        .ok_or_else(|| incompatible_receiver("Array.prototype.pop", this))?;
 
+    if interp.sparse_arrays.contains_key(&arr_idx) {
+        let len = interp.array_length(arr_idx);
+        let Some(last) = len.checked_sub(1) else {
+            return Ok(Value::undefined());
+        };
+        let popped = interp.array_element(arr_idx, last);
+        interp.resize_array(arr_idx, last)?;
+        return Ok(popped);
+    }
     if let Some(arr) = interp.arrays.get_mut(arr_idx as usize) {
-        Ok(arr.pop().unwrap_or_default())
+        let popped = arr.pop().unwrap_or_default();
+        let len = arr.len();
+        interp.remap_array_holes(arr_idx, |i| (i < len).then_some(i));
+        Ok(popped)
     } else {
-        Err("invalid array".to_string())
+        Err(HostError::Type("invalid array".to_string()))
     }
 }
 
@@ -4350,16 +9745,12 @@ fn native_array_length(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let arr_idx = this
         .to_array_idx()
-        .ok_or_else(|| "length called on non-array".to_string())?;
+        .ok_or_else(|| incompatible_receiver("Array.prototype.length", this))?;
 
-    if let Some(arr) = interp.arrays.get(arr_idx as usize) {
-        Ok(Value::int(arr.len() as i32))
-    } else {
-        Err("invalid array".to_string())
-    }
+    Ok(Value::int_saturating(interp.array_length(arr_idx) as i64))
 }
 
 /// Array.prototype.shift - remove and return first element
@@ -4367,19 +9758,22 @@ fn native_array_shift(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let arr_idx = this
         .to_array_idx()
-        .ok_or_else(|| "shift called on non-array".to_string())?;
+        .ok_or_else(|| incompatible_receiver("Array.prototype.shift", this))?;
+    interp.make_array_dense(arr_idx)?;
 
     if let Some(arr) = interp.arrays.get_mut(arr_idx as usize) {
         if arr.is_empty() {
             Ok(Value::undefined())
         } else {
-            Ok(arr.remove(0))
+            let shifted = arr.remove(0);
+            interp.remap_array_holes(arr_idx, |i| i.checked_sub(1));
+            Ok(shifted)
         }
     } else {
-        Err("invalid array".to_string())
+        Err(HostError::Type("invalid array".to_string()))
     }
 }
 
@@ -4388,71 +9782,72 @@ fn native_array_unshift(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let arr_idx = this
         .to_array_idx()
-        .ok_or_else(|| "unshift called on non-array".to_string())?;
+        .ok_or_else(|| incompatible_receiver("Array.prototype.unshift", this))?;
+    interp.check_array_length(interp.array_length(arr_idx).saturating_add(args.len()))?;
+    interp.make_array_dense(arr_idx)?;
 
-    if let Some(arr) = interp.arrays.get_mut(arr_idx as usize) {
-        // Insert arguments at beginning in order
-        for (i, arg) in args.iter().enumerate() {
-            arr.insert(i, *arg);
-        }
-        Ok(Value::int(arr.len() as i32))
-    } else {
-        Err("invalid array".to_string())
-    }
+    let len = interp.reserve_array(arr_idx, args.len())?;
+    // One move of the existing elements, however many are added
+    interp.arrays[arr_idx as usize].splice(0..0, args.iter().copied());
+    interp.remap_array_holes(arr_idx, |i| Some(i + args.len()));
+    Ok(Value::int(len as i32))
+}
+
+/// Start position for a forward search from an optional fromIndex argument
+///
+/// Negative values count back from the end; the result is clamped to 0..=len.
+fn forward_search_start(from: Option<&Value>, len: usize) -> usize {
+    let from = from.and_then(|v| v.to_i32()).unwrap_or(0) as i64;
+    let start = if from < 0 { len as i64 + from } else { from };
+    start.clamp(0, len as i64) as usize
 }
 
-/// Array.prototype.indexOf - find index of element
+/// Array.prototype.indexOf - find index of element using strict equality
 fn native_array_index_of(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "indexOf called on non-array".to_string())?;
-
+) -> Result<Value, HostError> {
+    let (len, entries) = require_array_entries(interp, this, "indexOf")?;
     let search_val = args.first().copied().unwrap_or_default();
+    let start = forward_search_start(args.get(1), len);
 
-    if let Some(arr) = interp.arrays.get(arr_idx as usize) {
-        for (i, val) in arr.iter().enumerate() {
-            // Simple equality check (comparing raw values)
-            if val.0 == search_val.0 {
-                return Ok(Value::int(i as i32));
-            }
-        }
-        Ok(Value::int(-1)) // Not found
-    } else {
-        Err("invalid array".to_string())
-    }
+    let found = entries
+        .iter()
+        .find(|&&(i, val)| i >= start && interp.strict_equals(val, search_val));
+    Ok(Value::int_saturating(found.map_or(-1, |&(i, _)| i as i64)))
 }
 
 /// Array.prototype.lastIndexOf - find last occurrence of element
+///
+/// The optional fromIndex is where the backwards search starts; negative
+/// values count back from the end.
 fn native_array_last_index_of(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "lastIndexOf called on non-array".to_string())?;
-
-    let search_val = args.first().copied().unwrap_or_default();
-
-    if let Some(arr) = interp.arrays.get(arr_idx as usize) {
-        // Search from end to beginning
-        for (i, val) in arr.iter().enumerate().rev() {
-            // Simple equality check (comparing raw values)
-            if val.0 == search_val.0 {
-                return Ok(Value::int(i as i32));
-            }
-        }
-        Ok(Value::int(-1)) // Not found
-    } else {
-        Err("invalid array".to_string())
+) -> Result<Value, HostError> {
+    let (len, entries) = require_array_entries(interp, this, "lastIndexOf")?;
+    let args = Args::new("Array.prototype.lastIndexOf", args);
+    let search_val = args.get(0);
+    let len = len as i64;
+
+    let from = match args.opt_int(1, i32::MAX)? {
+        n if n < 0 => len + n as i64,
+        n => (n as i64).min(len - 1),
+    };
+    if from < 0 {
+        return Ok(Value::int(-1));
     }
+
+    let found = entries
+        .iter()
+        .rev()
+        .find(|&&(i, val)| i <= from as usize && interp.strict_equals(val, search_val));
+    Ok(Value::int_saturating(found.map_or(-1, |&(i, _)| i as i64)))
 }
 
 /// Array.prototype.join - join elements with separator
@@ -4460,49 +9855,21 @@ fn native_array_join(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "join called on non-array".to_string())?;
-
-    // Get separator (default is ",")
-    let separator = if let Some(sep_val) = args.first() {
-        if let Some(str_idx) = sep_val.to_string_idx() {
-            // We'd need access to string table here, for now use ","
-            let _ = str_idx;
-            ","
-        } else {
-            ","
-        }
-    } else {
-        ","
-    };
-
-    if let Some(arr) = interp.arrays.get(arr_idx as usize) {
-        let parts: Vec<String> = arr
-            .iter()
-            .map(|v| {
-                if let Some(n) = v.to_i32() {
-                    n.to_string()
-                } else if v.is_undefined() || v.is_null() {
-                    String::new()
-                } else if let Some(b) = v.to_bool() {
-                    b.to_string()
-                } else {
-                    String::new()
-                }
-            })
-            .collect();
+) -> Result<Value, HostError> {
+    let (len, entries) = require_array_entries(interp, this, "join")?;
 
-        let result = parts.join(separator);
+    // Separator defaults to "," and is otherwise converted with ToString
+    let separator = match args.first() {
+        Some(sep) if !sep.is_undefined() => interp.string_content(*sep, &mut HashSet::new())?,
+        _ => ",".to_string(),
+    };
 
-        // Store result string and return string value
-        let str_idx = interp.runtime_strings.len() as u16;
-        interp.runtime_strings.push(result);
-        Ok(Value::string(str_idx))
-    } else {
-        Err("invalid array".to_string())
+    let mut joining = HashSet::new();
+    if let Some(arr_idx) = this.to_array_idx() {
+        joining.insert(arr_idx);
     }
+    let result = interp.join_values(len, &entries, &separator, &mut joining)?;
+    Ok(interp.alloc_string(&result))
 }
 
 /// Array.prototype.reverse - reverse array in place
@@ -4510,16 +9877,19 @@ fn native_array_reverse(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let arr_idx = this
         .to_array_idx()
-        .ok_or_else(|| "reverse called on non-array".to_string())?;
+        .ok_or_else(|| incompatible_receiver("Array.prototype.reverse", this))?;
+    interp.make_array_dense(arr_idx)?;
 
     if let Some(arr) = interp.arrays.get_mut(arr_idx as usize) {
         arr.reverse();
+        let last = arr.len().saturating_sub(1);
+        interp.remap_array_holes(arr_idx, |i| Some(last - i));
         Ok(this) // Return the array itself
     } else {
-        Err("invalid array".to_string())
+        Err(HostError::Type("invalid array".to_string()))
     }
 }
 
@@ -4528,42 +9898,36 @@ fn native_array_slice(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "slice called on non-array".to_string())?;
+) -> Result<Value, HostError> {
+    let (len, entries) = require_array_entries(interp, this, "slice")?;
+    let args = Args::new("Array.prototype.slice", args);
 
-    if let Some(arr) = interp.arrays.get(arr_idx as usize) {
-        let len = arr.len() as i32;
+    let len = len.min(i32::MAX as usize) as i32;
 
-        // Get start index (default 0)
-        let mut start = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
-        if start < 0 {
-            start = (len + start).max(0);
-        }
-        let start = start.min(len) as usize;
-
-        // Get end index (default length)
-        let mut end = args.get(1).and_then(|v| v.to_i32()).unwrap_or(len);
-        if end < 0 {
-            end = (len + end).max(0);
-        }
-        let end = end.min(len) as usize;
-
-        // Create new array with slice
-        let slice: Vec<Value> = if start < end {
-            arr[start..end].to_vec()
-        } else {
-            Vec::new()
-        };
+    // Get start index (default 0)
+    let mut start = args.opt_int(0, 0)?;
+    if start < 0 {
+        start = (len + start).max(0);
+    }
+    let start = start.min(len) as usize;
 
-        // Store the new array
-        let new_idx = interp.arrays.len() as u32;
-        interp.arrays.push(slice);
-        Ok(Value::array_idx(new_idx))
-    } else {
-        Err("invalid array".to_string())
+    // Get end index (default length)
+    let mut end = args.opt_int(1, len)?;
+    if end < 0 {
+        end = (len + end).max(0);
     }
+    let end = end.min(len) as usize;
+
+    // Copy the requested range, holes included; an empty range gives an
+    // empty array
+    let slice = entries
+        .into_iter()
+        .filter(|&(i, _)| (start..end).contains(&i))
+        .map(|(i, v)| (i - start, v))
+        .collect();
+    interp
+        .alloc_array_entries(end.saturating_sub(start), slice)
+        .map_err(HostError::from)
 }
 
 /// Array.prototype.map - create new array with callback applied to each element
@@ -4571,40 +9935,23 @@ fn native_array_map(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "map called on non-array".to_string())?;
-
-    let callback = args
-        .first()
-        .copied()
-        .ok_or_else(|| "map requires a callback function".to_string())?;
-
-    if !callback.is_closure() && callback.to_func_ptr().is_none() {
-        return Err("map callback must be a function".to_string());
-    }
+) -> Result<Value, HostError> {
+    let (len, entries) = require_array_entries(interp, this, "map")?;
 
-    // Clone the array to avoid borrow issues
-    let arr_clone = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
+    let callback = Args::new("Array.prototype.map", args).callable(0, NativeScope::of(interp))?;
 
-    let mut result = Vec::with_capacity(arr_clone.len());
+    // Holes stay holes in the result
+    let mut result = Vec::with_capacity(entries.len());
 
-    for (i, element) in arr_clone.iter().enumerate() {
-        let call_args = vec![*element, Value::int(i as i32), this];
-        let mapped = interp
-            .call_value(callback, Value::undefined(), &call_args)
-            .map_err(|e| e.to_string())?;
-        result.push(mapped);
+    for (i, element) in entries {
+        let call_args = vec![element, Value::int_saturating(i as i64), this];
+        let mapped = interp.call_value(callback, Value::undefined(), &call_args)?;
+        result.push((i, mapped));
     }
 
-    let new_idx = interp.arrays.len() as u32;
-    interp.arrays.push(result);
-    Ok(Value::array_idx(new_idx))
+    interp
+        .alloc_array_entries(len, result)
+        .map_err(HostError::from)
 }
 
 /// Array.prototype.filter - create new array with elements that pass the test
@@ -4612,44 +9959,25 @@ fn native_array_filter(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "filter called on non-array".to_string())?;
+) -> Result<Value, HostError> {
+    let (_, entries) = require_array_entries(interp, this, "filter")?;
 
-    let callback = args
-        .first()
-        .copied()
-        .ok_or_else(|| "filter requires a callback function".to_string())?;
-
-    if !callback.is_closure() && callback.to_func_ptr().is_none() {
-        return Err("filter callback must be a function".to_string());
-    }
-
-    // Clone the array to avoid borrow issues
-    let arr_clone = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
+    let callback =
+        Args::new("Array.prototype.filter", args).callable(0, NativeScope::of(interp))?;
 
     let mut result = Vec::new();
 
-    for (i, element) in arr_clone.iter().enumerate() {
-        let call_args = vec![*element, Value::int(i as i32), this];
-        let keep = interp
-            .call_value(callback, Value::undefined(), &call_args)
-            .map_err(|e| e.to_string())?;
+    for (i, element) in entries {
+        let call_args = vec![element, Value::int_saturating(i as i64), this];
+        let keep = interp.call_value(callback, Value::undefined(), &call_args)?;
 
         // Convert to boolean
-        if Interpreter::value_to_bool(keep) {
-            result.push(*element);
+        if interp.to_boolean(keep) {
+            result.push(element);
         }
     }
 
-    let new_idx = interp.arrays.len() as u32;
-    interp.arrays.push(result);
-    Ok(Value::array_idx(new_idx))
+    Ok(interp.alloc_derived_array(result))
 }
 
 /// Array.prototype.forEach - call callback for each element
@@ -4657,32 +9985,15 @@ fn native_array_foreach(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "forEach called on non-array".to_string())?;
-
-    let callback = args
-        .first()
-        .copied()
-        .ok_or_else(|| "forEach requires a callback function".to_string())?;
+) -> Result<Value, HostError> {
+    let (_, entries) = require_array_entries(interp, this, "forEach")?;
 
-    if !callback.is_closure() && callback.to_func_ptr().is_none() {
-        return Err("forEach callback must be a function".to_string());
-    }
-
-    // Clone the array to avoid borrow issues
-    let arr_clone = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
+    let callback =
+        Args::new("Array.prototype.forEach", args).callable(0, NativeScope::of(interp))?;
 
-    for (i, element) in arr_clone.iter().enumerate() {
-        let call_args = vec![*element, Value::int(i as i32), this];
-        interp
-            .call_value(callback, Value::undefined(), &call_args)
-            .map_err(|e| e.to_string())?;
+    for (i, element) in entries {
+        let call_args = vec![element, Value::int_saturating(i as i64), this];
+        interp.call_value(callback, Value::undefined(), &call_args)?;
     }
 
     Ok(Value::undefined())
@@ -4693,43 +10004,29 @@ fn native_array_reduce(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "reduce called on non-array".to_string())?;
-
-    let callback = args
-        .first()
-        .copied()
-        .ok_or_else(|| "reduce requires a callback function".to_string())?;
-
-    if !callback.is_closure() && callback.to_func_ptr().is_none() {
-        return Err("reduce callback must be a function".to_string());
-    }
-
-    // Clone the array to avoid borrow issues
-    let arr_clone = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
-
-    if arr_clone.is_empty() && args.len() < 2 {
-        return Err("reduce of empty array with no initial value".to_string());
-    }
-
-    // Get initial value or first element
-    let (mut accumulator, start_idx) = if args.len() >= 2 {
-        (args[1], 0)
-    } else {
-        (arr_clone[0], 1)
+) -> Result<Value, HostError> {
+    let (_, entries) = require_array_entries(interp, this, "reduce")?;
+
+    let callback =
+        Args::new("Array.prototype.reduce", args).callable(0, NativeScope::of(interp))?;
+
+    // Get initial value or first element that is not a hole
+    let mut entries = entries.into_iter();
+    let mut accumulator = match args.get(1) {
+        Some(&initial) => initial,
+        None => match entries.next() {
+            Some((_, first)) => first,
+            None => {
+                return Err(HostError::Type(
+                    "reduce of empty array with no initial value".to_string(),
+                ));
+            }
+        },
     };
 
-    for (i, element) in arr_clone.iter().enumerate().skip(start_idx) {
-        let call_args = vec![accumulator, *element, Value::int(i as i32), this];
-        accumulator = interp
-            .call_value(callback, Value::undefined(), &call_args)
-            .map_err(|e| e.to_string())?;
+    for (i, element) in entries {
+        let call_args = vec![accumulator, element, Value::int_saturating(i as i64), this];
+        accumulator = interp.call_value(callback, Value::undefined(), &call_args)?;
     }
 
     Ok(accumulator)
@@ -4740,34 +10037,16 @@ fn native_array_find(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "find called on non-array".to_string())?;
-
-    let callback = args
-        .first()
-        .copied()
-        .ok_or_else(|| "find requires a callback function".to_string())?;
-
-    if !callback.is_closure() && callback.to_func_ptr().is_none() {
-        return Err("find callback must be a function".to_string());
-    }
+) -> Result<Value, HostError> {
+    let arr_clone = require_arraylike_receiver(interp, this, "find")?;
 
-    // Clone the array to avoid borrow issues
-    let arr_clone = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
+    let callback = Args::new("Array.prototype.find", args).callable(0, NativeScope::of(interp))?;
 
     for (i, element) in arr_clone.iter().enumerate() {
-        let call_args = vec![*element, Value::int(i as i32), this];
-        let result = interp
-            .call_value(callback, Value::undefined(), &call_args)
-            .map_err(|e| e.to_string())?;
+        let call_args = vec![*element, Value::int_saturating(i as i64), this];
+        let result = interp.call_value(callback, Value::undefined(), &call_args)?;
 
-        if Interpreter::value_to_bool(result) {
+        if interp.to_boolean(result) {
             return Ok(*element);
         }
     }
@@ -4780,35 +10059,18 @@ fn native_array_find_index(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "findIndex called on non-array".to_string())?;
-
-    let callback = args
-        .first()
-        .copied()
-        .ok_or_else(|| "findIndex requires a callback function".to_string())?;
-
-    if !callback.is_closure() && callback.to_func_ptr().is_none() {
-        return Err("findIndex callback must be a function".to_string());
-    }
+) -> Result<Value, HostError> {
+    let arr_clone = require_arraylike_receiver(interp, this, "findIndex")?;
 
-    // Clone the array to avoid borrow issues
-    let arr_clone = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
+    let callback =
+        Args::new("Array.prototype.findIndex", args).callable(0, NativeScope::of(interp))?;
 
     for (i, element) in arr_clone.iter().enumerate() {
-        let call_args = vec![*element, Value::int(i as i32), this];
-        let result = interp
-            .call_value(callback, Value::undefined(), &call_args)
-            .map_err(|e| e.to_string())?;
+        let call_args = vec![*element, Value::int_saturating(i as i64), this];
+        let result = interp.call_value(callback, Value::undefined(), &call_args)?;
 
-        if Interpreter::value_to_bool(result) {
-            return Ok(Value::int(i as i32));
+        if interp.to_boolean(result) {
+            return Ok(Value::int_saturating(i as i64));
         }
     }
 
@@ -4820,34 +10082,16 @@ fn native_array_some(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "some called on non-array".to_string())?;
-
-    let callback = args
-        .first()
-        .copied()
-        .ok_or_else(|| "some requires a callback function".to_string())?;
-
-    if !callback.is_closure() && callback.to_func_ptr().is_none() {
-        return Err("some callback must be a function".to_string());
-    }
+) -> Result<Value, HostError> {
+    let (_, entries) = require_array_entries(interp, this, "some")?;
 
-    // Clone the array to avoid borrow issues
-    let arr_clone = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
+    let callback = Args::new("Array.prototype.some", args).callable(0, NativeScope::of(interp))?;
 
-    for (i, element) in arr_clone.iter().enumerate() {
-        let call_args = vec![*element, Value::int(i as i32), this];
-        let result = interp
-            .call_value(callback, Value::undefined(), &call_args)
-            .map_err(|e| e.to_string())?;
+    for (i, element) in entries {
+        let call_args = vec![element, Value::int_saturating(i as i64), this];
+        let result = interp.call_value(callback, Value::undefined(), &call_args)?;
 
-        if Interpreter::value_to_bool(result) {
+        if interp.to_boolean(result) {
             return Ok(Value::bool(true));
         }
     }
@@ -4858,36 +10102,18 @@ fn native_array_some(
 /// Array.prototype.every - check if all elements satisfy the test
 fn native_array_every(
     interp: &mut Interpreter,
-    this: Value,
-    args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "every called on non-array".to_string())?;
-
-    let callback = args
-        .first()
-        .copied()
-        .ok_or_else(|| "every requires a callback function".to_string())?;
-
-    if !callback.is_closure() && callback.to_func_ptr().is_none() {
-        return Err("every callback must be a function".to_string());
-    }
+    this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let (_, entries) = require_array_entries(interp, this, "every")?;
 
-    // Clone the array to avoid borrow issues
-    let arr_clone = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
+    let callback = Args::new("Array.prototype.every", args).callable(0, NativeScope::of(interp))?;
 
-    for (i, element) in arr_clone.iter().enumerate() {
-        let call_args = vec![*element, Value::int(i as i32), this];
-        let result = interp
-            .call_value(callback, Value::undefined(), &call_args)
-            .map_err(|e| e.to_string())?;
+    for (i, element) in entries {
+        let call_args = vec![element, Value::int_saturating(i as i64), this];
+        let result = interp.call_value(callback, Value::undefined(), &call_args)?;
 
-        if !Interpreter::value_to_bool(result) {
+        if !interp.to_boolean(result) {
             return Ok(Value::bool(false));
         }
     }
@@ -4895,29 +10121,21 @@ fn native_array_every(
     Ok(Value::bool(true))
 }
 
-/// Array.prototype.includes - check if array includes a value
+/// Array.prototype.includes - check if array includes a value (SameValueZero)
 fn native_array_includes(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "includes called on non-array".to_string())?;
-
+) -> Result<Value, HostError> {
+    let arr = require_arraylike_receiver(interp, this, "includes")?;
     let search_val = args.first().copied().unwrap_or_default();
+    let start = forward_search_start(args.get(1), arr.len());
 
-    if let Some(arr) = interp.arrays.get(arr_idx as usize) {
-        for element in arr.iter() {
-            // Simple equality check
-            if element.raw() == search_val.raw() {
-                return Ok(Value::bool(true));
-            }
-        }
-        Ok(Value::bool(false))
-    } else {
-        Err("invalid array".to_string())
-    }
+    Ok(Value::bool(
+        arr[start..]
+            .iter()
+            .any(|&val| interp.same_value_zero(val, search_val)),
+    ))
 }
 
 /// Array.prototype.concat - concatenate arrays
@@ -4925,105 +10143,165 @@ fn native_array_concat(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let arr_idx = this
         .to_array_idx()
-        .ok_or_else(|| "concat called on non-array".to_string())?;
+        .ok_or_else(|| incompatible_receiver("Array.prototype.concat", this))?;
 
-    // Clone the original array
-    let original = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
-
-    let mut result = original;
+    // Check the result fits before copying anything
+    let array_len = |interp: &Interpreter, idx: u32| interp.array_length(idx);
+    let len = args.iter().fold(array_len(interp, arr_idx), |len, arg| {
+        len.saturating_add(arg.to_array_idx().map_or(1, |idx| array_len(interp, idx)))
+    });
+    interp.check_array_length(len)?;
 
-    // Concatenate each argument
-    for arg in args {
-        if let Some(other_idx) = arg.to_array_idx() {
-            // Argument is an array - append all elements
-            if let Some(other_arr) = interp.arrays.get(other_idx as usize) {
-                result.extend(other_arr.iter().cloned());
-            }
+    // Holes of the arrays stay holes in the result
+    let mut result = Vec::new();
+    let mut offset = 0;
+    for part in core::iter::once(this).chain(args.iter().copied()) {
+        if let Some(idx) = part.to_array_idx() {
+            // An array - append its elements
+            result.extend(
+                interp
+                    .array_entries(idx)
+                    .into_iter()
+                    .map(|(i, v)| (offset + i, v)),
+            );
+            offset += array_len(interp, idx);
         } else {
-            // Argument is a single value - append it
-            result.push(*arg);
+            // A single value - append it
+            result.push((offset, part));
+            offset += 1;
         }
     }
 
-    let new_arr_idx = interp.arrays.len() as u32;
-    interp.arrays.push(result);
-    Ok(Value::array_idx(new_arr_idx))
+    interp
+        .alloc_array_entries(len, result)
+        .map_err(HostError::from)
 }
 
 /// Array.prototype.sort - sort array in place
+///
+/// Undefined elements move to the end without reaching the comparator,
+/// followed by the holes, which stay holes. Without a comparator the rest
+/// are ordered by their ToString forms, so `[10, 9, 1]` sorts to
+/// `[1, 10, 9]`. Both orders are stable.
 fn native_array_sort(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let arr_idx = this
         .to_array_idx()
-        .ok_or_else(|| "sort called on non-array".to_string())?;
+        .ok_or_else(|| incompatible_receiver("Array.prototype.sort", this))?;
+    interp.make_array_dense(arr_idx)?;
 
-    // Get optional compare function
-    let compare_fn = args.first().copied();
+    let args = Args::new("Array.prototype.sort", args);
+    let compare_fn = if args.is_absent(0) {
+        None
+    } else {
+        Some(args.callable(0, NativeScope::of(interp))?)
+    };
 
-    if let Some(arr) = interp.arrays.get_mut(arr_idx as usize) {
-        if compare_fn.is_some()
-            && (compare_fn.unwrap().is_closure() || compare_fn.unwrap().to_func_ptr().is_some())
-        {
-            // Custom comparator - need to call the function for each comparison
-            // For now, just do default sort without custom comparator support
-            // TODO: Implement custom comparator
-            arr.sort_by(|a, b| {
-                // Default: convert to strings and compare
-                let a_val = a.to_i32().unwrap_or(0);
-                let b_val = b.to_i32().unwrap_or(0);
-                a_val.cmp(&b_val)
-            });
-        } else {
-            // Default sort - numeric comparison for integers
-            arr.sort_by(|a, b| {
-                let a_val = a.to_i32().unwrap_or(0);
-                let b_val = b.to_i32().unwrap_or(0);
-                a_val.cmp(&b_val)
-            });
+    let len = interp.array_length(arr_idx);
+    let (defined, undefined): (Vec<Value>, Vec<Value>) = interp
+        .array_entries(arr_idx)
+        .into_iter()
+        .map(|(_, v)| v)
+        .partition(|v| !v.is_undefined());
+
+    let mut sorted = match compare_fn {
+        Some(f) => merge_sort_by(defined, &mut |a, b| {
+            let result = interp.call_value(f, Value::undefined(), &[a, b])?;
+            // NaN converts to 0, which keeps the pair in order
+            let n = interp.to_number(result).to_i32().unwrap_or(0);
+            Ok(n.cmp(&0))
+        })?,
+        None => {
+            let mut keyed = Vec::with_capacity(defined.len());
+            for v in defined {
+                keyed.push((interp.string_content(v, &mut HashSet::new())?, v));
+            }
+            keyed.sort_by(|a, b| a.0.cmp(&b.0));
+            keyed.into_iter().map(|(_, v)| v).collect()
         }
+    };
+    sorted.extend(undefined);
+
+    let present = sorted.len();
+    if let Some(arr) = interp.arrays.get_mut(arr_idx as usize) {
+        arr.clear();
+        arr.extend_from_slice(&sorted);
+        arr.resize(len, Value::undefined());
+    }
+    interp.array_holes.remove(&arr_idx);
+    if present < len {
+        interp.array_holes.insert(arr_idx, (present..len).collect());
     }
 
     // Return the array itself (sort is in-place)
     Ok(this)
 }
 
+/// Stable merge sort with a comparator that can fail
+///
+/// The standard library sorts cannot stop early when a script comparator
+/// throws, so the error is returned as soon as it happens instead.
+fn merge_sort_by(
+    values: Vec<Value>,
+    compare: &mut impl FnMut(Value, Value) -> Result<core::cmp::Ordering, HostError>,
+) -> Result<Vec<Value>, HostError> {
+    if values.len() <= 1 {
+        return Ok(values);
+    }
+    let mut right = values;
+    let left = right.drain(..right.len() / 2).collect();
+    let left = merge_sort_by(left, compare)?;
+    let right = merge_sort_by(right, compare)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        // Take from the left on ties to keep equal elements in order
+        if compare(left[i], right[j])? == core::cmp::Ordering::Greater {
+            merged.push(right[j]);
+            j += 1;
+        } else {
+            merged.push(left[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&left[i..]);
+    merged.extend_from_slice(&right[j..]);
+    Ok(merged)
+}
+
 /// Array.prototype.flat - flatten nested arrays
 fn native_array_flat(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "flat called on non-array".to_string())?;
+) -> Result<Value, HostError> {
+    let (_, original) = require_array_entries(interp, this, "flat")?;
+    let original: Vec<Value> = original.into_iter().map(|(_, v)| v).collect();
 
     // Get depth (default 1)
-    let depth = args.first().and_then(|v| v.to_i32()).unwrap_or(1).max(0) as usize;
-
-    let original = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
+    let depth = Args::new("Array.prototype.flat", args)
+        .opt_int(0, 1)?
+        .max(0) as usize;
 
     fn flatten_recursive(interp: &Interpreter, arr: &[Value], depth: usize) -> Vec<Value> {
         let mut result = Vec::new();
         for elem in arr {
             if depth > 0
                 && let Some(nested_idx) = elem.to_array_idx()
-                && let Some(nested) = interp.arrays.get(nested_idx as usize)
             {
-                result.extend(flatten_recursive(interp, nested, depth - 1));
+                let nested: Vec<Value> = interp
+                    .array_entries(nested_idx)
+                    .into_iter()
+                    .map(|(_, v)| v)
+                    .collect();
+                result.extend(flatten_recursive(interp, &nested, depth - 1));
                 continue;
             }
             result.push(*elem);
@@ -5033,9 +10311,7 @@ fn native_array_flat(
 
     let flattened = flatten_recursive(interp, &original, depth);
 
-    let new_arr_idx = interp.arrays.len() as u32;
-    interp.arrays.push(flattened);
-    Ok(Value::array_idx(new_arr_idx))
+    Ok(interp.alloc_derived_array(flattened))
 }
 
 /// Array.prototype.fill - fill array with a value
@@ -5043,10 +10319,11 @@ fn native_array_fill(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let arr_idx = this
         .to_array_idx()
-        .ok_or_else(|| "fill called on non-array".to_string())?;
+        .ok_or_else(|| incompatible_receiver("Array.prototype.fill", this))?;
+    interp.make_array_dense(arr_idx)?;
 
     let fill_value = args.first().copied().unwrap_or_default();
 
@@ -5086,6 +10363,7 @@ fn native_array_fill(
             arr[i] = fill_value;
         }
     }
+    interp.remap_array_holes(arr_idx, |i| (!(start..end).contains(&i)).then_some(i));
 
     // Return the array itself (fill is in-place)
     Ok(this)
@@ -5096,7 +10374,7 @@ fn native_parse_int(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let val = args.first().copied().unwrap_or_default();
 
     if let Some(n) = val.to_i32() {
@@ -5108,7 +10386,11 @@ fn native_parse_int(
 }
 
 /// isNaN - check if value is NaN
-fn native_is_nan(_interp: &mut Interpreter, _this: Value, args: &[Value]) -> Result<Value, String> {
+fn native_is_nan(
+    _interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
     let val = args.first().copied().unwrap_or_default();
 
     // We don't have real NaN support yet, so just check if it's a number
@@ -5125,7 +10407,7 @@ fn native_parse_float(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let val = args.first().copied().unwrap_or_default();
 
     // If it's already a number, return it
@@ -5134,9 +10416,7 @@ fn native_parse_float(
     }
 
     // Try to parse as string
-    if let Some(str_idx) = val.to_string_idx()
-        && let Some(s) = interp.get_string_by_idx(str_idx)
-    {
+    if let Some(s) = interp.get_string(val) {
         // Parse leading numeric portion, treating decimal point
         let s = s.trim();
         let mut result = 0i32;
@@ -5180,7 +10460,7 @@ fn native_is_finite(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let val = args.first().copied().unwrap_or_default();
 
     // Since we only have 31-bit integers, all our numbers are finite
@@ -5200,30 +10480,88 @@ fn native_number_to_string(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let radix = args.first().and_then(|v| v.to_i32()).unwrap_or(10);
+) -> Result<Value, HostError> {
+    let radix = match args.first() {
+        Some(v) if !v.is_undefined() => v.to_i32().unwrap_or(0),
+        _ => 10,
+    };
+    if !(2..=36).contains(&radix) {
+        return Err(HostError::Range(
+            "toString() radix must be between 2 and 36".to_string(),
+        ));
+    }
 
     if let Some(n) = this.to_i32() {
-        let s = match radix {
-            2 => format!("{:b}", n),
-            8 => format!("{:o}", n),
-            16 => format!("{:x}", n),
-            _ => n.to_string(),
-        };
-        Ok(interp.create_runtime_string(s))
+        Ok(interp.alloc_string(&format_radix(n, radix as u32)))
     } else {
-        Err("toString called on non-number".to_string())
+        Err(incompatible_receiver("Number.prototype.toString", this))
+    }
+}
+
+/// Format an integer in the given radix (2-36) with lowercase digits and a leading '-'
+fn format_radix(n: i32, radix: u32) -> String {
+    let mut magnitude = (n as i64).unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        let digit = (magnitude % radix as u64) as u32;
+        digits.push(char::from_digit(digit, radix).unwrap_or('0'));
+        magnitude /= radix as u64;
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if n < 0 {
+        digits.push('-');
+    }
+    digits.iter().rev().collect()
+}
+
+/// Parse a string with the ToNumber grammar for integers
+///
+/// Accepts surrounding whitespace, an optional sign on decimal input, and
+/// 0x/0o/0b prefixes. An empty string is 0. Returns None where JS would
+/// produce NaN (trailing garbage, out-of-range values).
+fn parse_number_string(s: &str) -> Option<i32> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Some(0);
+    }
+    let prefixed = |p: &str, radix| {
+        let (lower, upper) = (p, p.to_uppercase());
+        s.strip_prefix(lower)
+            .or_else(|| s.strip_prefix(upper.as_str()))
+            .map(|digits| {
+                // from_str_radix accepts a sign, the JS grammar does not
+                if digits.starts_with(['+', '-']) {
+                    None
+                } else {
+                    i32::from_str_radix(digits, radix).ok()
+                }
+            })
+    };
+    if let Some(result) = prefixed("0x", 16)
+        .or_else(|| prefixed("0o", 8))
+        .or_else(|| prefixed("0b", 2))
+    {
+        return result;
     }
+    s.parse::<i32>().ok()
 }
 
 /// Number.prototype.toFixed - format number with fixed decimal places
-/// Since we only have integers, this just pads with zeros
+/// Since we only have integers, this just pads with zeros (no rounding needed)
 fn native_number_to_fixed(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let digits = args.first().and_then(|v| v.to_i32()).unwrap_or(0) as usize;
+) -> Result<Value, HostError> {
+    let digits = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+    if !(0..=100).contains(&digits) {
+        return Err(HostError::Range(
+            "toFixed() digits argument must be between 0 and 100".to_string(),
+        ));
+    }
+    let digits = digits as usize;
 
     if let Some(n) = this.to_i32() {
         let s = if digits > 0 {
@@ -5231,9 +10569,9 @@ fn native_number_to_fixed(
         } else {
             n.to_string()
         };
-        Ok(interp.create_runtime_string(s))
+        Ok(interp.alloc_string(&s))
     } else {
-        Err("toFixed called on non-number".to_string())
+        Err(incompatible_receiver("Number.prototype.toFixed", this))
     }
 }
 
@@ -5242,21 +10580,24 @@ fn native_number_to_exponential(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     if let Some(n) = this.to_i32() {
         // Simple exponential format for integers
         if n == 0 {
-            Ok(interp.create_runtime_string("0e+0".to_string()))
+            Ok(interp.alloc_string("0e+0"))
         } else {
             let abs_n = n.abs();
             let exp = (abs_n as f64).log10().floor() as i32;
             let sign = if n < 0 { "-" } else { "" };
             let mantissa = abs_n / 10_i32.pow(exp as u32);
             let s = format!("{}{}e+{}", sign, mantissa, exp);
-            Ok(interp.create_runtime_string(s))
+            Ok(interp.alloc_string(&s))
         }
     } else {
-        Err("toExponential called on non-number".to_string())
+        Err(incompatible_receiver(
+            "Number.prototype.toExponential",
+            this,
+        ))
     }
 }
 
@@ -5265,14 +10606,14 @@ fn native_number_to_precision(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let _precision = args.first().and_then(|v| v.to_i32()).unwrap_or(1) as usize;
 
     if let Some(n) = this.to_i32() {
         // For integers, just return the string representation
-        Ok(interp.create_runtime_string(n.to_string()))
+        Ok(interp.alloc_string(&n.to_string()))
     } else {
-        Err("toPrecision called on non-number".to_string())
+        Err(incompatible_receiver("Number.prototype.toPrecision", this))
     }
 }
 
@@ -5285,10 +10626,10 @@ fn native_typed_array_subarray(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let typed_idx = this
         .to_typed_array_idx()
-        .ok_or_else(|| "subarray called on non-TypedArray".to_string())?;
+        .ok_or_else(|| incompatible_receiver("TypedArray.prototype.subarray", this))?;
 
     let start = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
     let end = args.get(1).and_then(|v| v.to_i32());
@@ -5299,8 +10640,10 @@ fn native_typed_array_subarray(
         .ok_or_else(|| "invalid TypedArray index".to_string())?;
 
     let new_ta = ta.subarray(start, end);
-    let new_idx = interp.typed_arrays.len() as u32;
+    let new_idx = table_index(interp.typed_arrays.len())?;
+    interp.reserve_binary(new_ta.length, new_ta.kind.byte_size())?;
     interp.typed_arrays.push(new_ta);
+    interp.count_allocation(|t| &mut t.typed_arrays);
 
     Ok(Value::typed_array_object(new_idx))
 }
@@ -5310,105 +10653,172 @@ fn native_math_abs(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let val = args.first().copied().unwrap_or_default();
+) -> Result<Value, HostError> {
+    let n = Args::new("Math.abs", args).int(0)?;
+    Ok(Value::int(n.abs()))
+}
 
-    if let Some(n) = val.to_i32() {
-        Ok(Value::int(n.abs()))
+/// Numeric argument `index` of a Math function under ToNumber
+///
+/// A missing argument is NaN. Numbers are integers for now, so this and
+/// [`math_result`] are the only places the rounding functions need to
+/// change once fractional values exist.
+fn math_arg(interp: &Interpreter, args: &[Value], index: usize) -> f64 {
+    args.get(index)
+        .and_then(|&v| interp.to_number(v).to_i32())
+        .map_or(f64::NAN, f64::from)
+}
+
+/// The integer value of an arithmetic result, or the RangeError thrown
+/// when it falls outside [`Value::INT_MIN`]..=[`Value::INT_MAX`]
+fn int_result(n: i64) -> InterpreterResult<Value> {
+    Value::try_int(n).ok_or_else(|| InterpreterError::RangeError("integer overflow".to_string()))
+}
+
+/// Store the result of a Math function
+///
+/// Integer-only for now: fractions truncate, NaN and -0 become 0, and
+/// +/-Infinity saturate to `Value::INT_MAX`/`Value::INT_MIN`.
+fn math_result(x: f64) -> Value {
+    Value::int_saturating(x as i64)
+}
+
+/// Rounding functions of the Math object
+#[derive(Debug, Clone, Copy)]
+enum Rounding {
+    Floor,
+    Ceil,
+    Round,
+    Trunc,
+}
+
+impl Rounding {
+    /// Round `x` with JavaScript semantics
+    ///
+    /// Math.round sends half-way cases toward +Infinity (2.5 -> 3,
+    /// -2.5 -> -2) rather than to even, and every mode keeps -0 and NaN.
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            Rounding::Floor => x.floor(),
+            Rounding::Ceil => x.ceil(),
+            Rounding::Trunc => x.trunc(),
+            Rounding::Round => {
+                let floor = x.floor();
+                let rounded = if x - floor >= 0.5 { floor + 1.0 } else { floor };
+                if rounded == 0.0 {
+                    // -0.5 <= x <= -0 rounds to -0
+                    0.0f64.copysign(x)
+                } else {
+                    rounded
+                }
+            }
+        }
+    }
+}
+
+/// Math.sign semantics: 1, -1, or the argument itself for +/-0 and NaN
+fn number_sign(x: f64) -> f64 {
+    if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        -1.0
     } else {
-        Err("Math.abs requires a number".to_string())
+        x
     }
 }
 
-/// Math.floor - floor value
+/// Shared body of Math.floor/ceil/round/trunc
+fn math_rounding(interp: &Interpreter, args: &[Value], mode: Rounding) -> Result<Value, HostError> {
+    Ok(math_result(mode.apply(math_arg(interp, args, 0))))
+}
+
+/// Math.floor - round toward -Infinity
 fn native_math_floor(
-    _interp: &mut Interpreter,
+    interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let val = args.first().copied().unwrap_or_default();
-
-    if let Some(n) = val.to_i32() {
-        Ok(Value::int(n)) // Already an integer
-    } else {
-        Err("Math.floor requires a number".to_string())
-    }
+) -> Result<Value, HostError> {
+    math_rounding(interp, args, Rounding::Floor)
 }
 
-/// Math.ceil - ceiling value
+/// Math.ceil - round toward +Infinity
 fn native_math_ceil(
-    _interp: &mut Interpreter,
+    interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let val = args.first().copied().unwrap_or_default();
-
-    if let Some(n) = val.to_i32() {
-        Ok(Value::int(n)) // Already an integer
-    } else {
-        Err("Math.ceil requires a number".to_string())
-    }
+) -> Result<Value, HostError> {
+    math_rounding(interp, args, Rounding::Ceil)
 }
 
-/// Math.max - maximum of values
-fn native_math_max(
-    _interp: &mut Interpreter,
+/// Math.round - round to nearest, half-way cases toward +Infinity
+fn native_math_round(
+    interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    if args.is_empty() {
-        return Ok(Value::int(i32::MIN)); // -Infinity for no args
-    }
+) -> Result<Value, HostError> {
+    math_rounding(interp, args, Rounding::Round)
+}
 
-    let mut max = i32::MIN;
-    for arg in args {
-        if let Some(n) = arg.to_i32() {
-            if n > max {
-                max = n;
-            }
-        } else {
-            return Err("Math.max requires numbers".to_string());
-        }
-    }
-    Ok(Value::int(max))
+/// Math.trunc - round toward zero
+fn native_math_trunc(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    math_rounding(interp, args, Rounding::Trunc)
 }
 
-/// Math.min - minimum of values
-fn native_math_min(
-    _interp: &mut Interpreter,
+/// Math.sign - returns the sign of a number
+fn native_math_sign(
+    interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    if args.is_empty() {
-        return Ok(Value::int(i32::MAX)); // Infinity for no args
-    }
+) -> Result<Value, HostError> {
+    Ok(math_result(number_sign(math_arg(interp, args, 0))))
+}
 
-    let mut min = i32::MAX;
-    for arg in args {
-        if let Some(n) = arg.to_i32() {
-            if n < min {
-                min = n;
-            }
-        } else {
-            return Err("Math.min requires numbers".to_string());
-        }
-    }
-    Ok(Value::int(min))
+/// Math.max - largest argument after ToNumber
+///
+/// With no arguments the result is -Infinity, stored as `Value::INT_MIN` until
+/// infinities exist. A NaN argument makes the result NaN (stored as 0).
+fn native_math_max(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    math_extremum(interp, args, f64::NEG_INFINITY, |n, best| n > best)
 }
 
-/// Math.round - round to nearest integer
-fn native_math_round(
-    _interp: &mut Interpreter,
+/// Math.min - smallest argument after ToNumber
+///
+/// With no arguments the result is +Infinity, stored as `Value::INT_MAX` until
+/// infinities exist. A NaN argument makes the result NaN (stored as 0).
+fn native_math_min(
+    interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let val = args.first().copied().unwrap_or_default();
+) -> Result<Value, HostError> {
+    math_extremum(interp, args, f64::INFINITY, |n, best| n < best)
+}
 
-    if let Some(n) = val.to_i32() {
-        Ok(Value::int(n)) // Already an integer
-    } else {
-        Err("Math.round requires a number".to_string())
+/// Shared body of Math.max/min: fold the arguments with `better`
+fn math_extremum(
+    interp: &Interpreter,
+    args: &[Value],
+    empty: f64,
+    better: fn(f64, f64) -> bool,
+) -> Result<Value, HostError> {
+    let mut best = empty;
+    for index in 0..args.len() {
+        let n = math_arg(interp, args, index);
+        if n.is_nan() {
+            return Ok(math_result(f64::NAN));
+        }
+        if better(n, best) {
+            best = n;
+        }
     }
+    Ok(math_result(best))
 }
 
 /// Math.sqrt - square root (integer approximation for now)
@@ -5416,18 +10826,13 @@ fn native_math_sqrt(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let val = args.first().copied().unwrap_or_default();
-
-    if let Some(n) = val.to_i32() {
-        if n < 0 {
-            Ok(Value::int(0)) // NaN for negative (return 0 for now)
-        } else {
-            // Integer square root
-            Ok(Value::int((n as f64).sqrt() as i32))
-        }
+) -> Result<Value, HostError> {
+    let n = Args::new("Math.sqrt", args).int(0)?;
+    if n < 0 {
+        Ok(Value::int(0)) // NaN for negative (return 0 for now)
     } else {
-        Err("Math.sqrt requires a number".to_string())
+        // Integer square root
+        Ok(Value::int((n as f64).sqrt() as i32))
     }
 }
 
@@ -5436,23 +10841,16 @@ fn native_math_pow(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let base = args.first().copied().unwrap_or_default();
-    let exp = args.get(1).copied().unwrap_or_default();
-
-    if let (Some(b), Some(e)) = (base.to_i32(), exp.to_i32()) {
-        if e < 0 {
-            Ok(Value::int(0)) // Integer division for negative exponents
-        } else if e == 0 {
-            Ok(Value::int(1))
-        } else {
-            let result = (b as i64).pow(e as u32);
-            Ok(Value::int(
-                result.min(i32::MAX as i64).max(i32::MIN as i64) as i32
-            ))
-        }
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.pow", args);
+    let b = args.int(0)?;
+    let e = args.int(1)?;
+    if e < 0 {
+        Ok(Value::int(0)) // Integer division for negative exponents
+    } else if e == 0 {
+        Ok(Value::int(1))
     } else {
-        Err("Math.pow requires numbers".to_string())
+        Ok(Value::int_saturating((b as i64).saturating_pow(e as u32)))
     }
 }
 
@@ -5461,9 +10859,10 @@ fn native_math_imul(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let a = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
-    let b = args.get(1).and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.imul", args);
+    let a = args.opt_int(0, 0)?;
+    let b = args.opt_int(1, 0)?;
     // Perform 32-bit multiplication with wrapping
     let result = (a as i64 * b as i64) as i32;
     Ok(Value::int(result))
@@ -5474,8 +10873,9 @@ fn native_math_clz32(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.clz32", args);
+    let n = args.opt_int(0, 0)?;
     let result = (n as u32).leading_zeros() as i32;
     Ok(Value::int(result))
 }
@@ -5485,30 +10885,21 @@ fn native_math_fround(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.fround", args);
+    let n = args.opt_int(0, 0)?;
     // For integer-only engine, just return the value
     Ok(Value::int(n))
 }
 
-/// Math.trunc - truncate to integer (remove fractional part)
-fn native_math_trunc(
-    _interp: &mut Interpreter,
-    _this: Value,
-    args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
-    // For integer-only engine, value is already truncated
-    Ok(Value::int(n))
-}
-
 /// Math.log2 - base-2 logarithm (integer approximation)
 fn native_math_log2(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.log2", args);
+    let n = args.opt_int(0, 0)?;
     if n <= 0 {
         // Return a special value for non-positive
         Ok(Value::int(-1))
@@ -5524,8 +10915,9 @@ fn native_math_log10(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.log10", args);
+    let n = args.opt_int(0, 0)?;
     if n <= 0 {
         Ok(Value::int(-1))
     } else {
@@ -5540,29 +10932,14 @@ fn native_math_log10(
     }
 }
 
-/// Math.sign - returns the sign of a number
-fn native_math_sign(
-    _interp: &mut Interpreter,
-    _this: Value,
-    args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
-    Ok(Value::int(if n > 0 {
-        1
-    } else if n < 0 {
-        -1
-    } else {
-        0
-    }))
-}
-
 /// Math.sin - returns sine of a number (approximation for integers)
 fn native_math_sin(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.sin", args);
+    let n = args.opt_int(0, 0)?;
     // Simple approximation: sin is periodic and bounded [-1, 1]
     // For integers, return 0 for multiples of ~3 (pi), else approximate
     let n = n % 360; // Treat as degrees roughly
@@ -5582,8 +10959,9 @@ fn native_math_cos(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.cos", args);
+    let n = args.opt_int(0, 0)?;
     let n = n % 360;
     if n == 0 {
         Ok(Value::int(1))
@@ -5601,8 +10979,9 @@ fn native_math_tan(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.tan", args);
+    let n = args.opt_int(0, 0)?;
     let n = n % 180;
     if n == 0 {
         Ok(Value::int(0))
@@ -5620,8 +10999,9 @@ fn native_math_exp(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.exp", args);
+    let n = args.opt_int(0, 0)?;
     if n < 0 {
         Ok(Value::int(0)) // e^-x < 1
     } else if n == 0 {
@@ -5641,8 +11021,9 @@ fn native_math_log(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let n = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.log", args);
+    let n = args.opt_int(0, 0)?;
     if n <= 0 {
         Ok(Value::int(-1)) // NaN or -Infinity
     } else if n == 1 {
@@ -5661,21 +11042,31 @@ fn native_math_log(
 
 /// Math.random - returns a pseudo-random number
 fn native_math_random(
-    _interp: &mut Interpreter,
+    interp: &mut Interpreter,
     _this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
-    use std::time::{SystemTime, UNIX_EPOCH};
+) -> Result<Value, HostError> {
+    if let Some(ReplayMode::Replaying { .. }) = interp.replay {
+        return match interp.replay_next("Math.random", |e| matches!(e, ReplayEntry::Random(_)))? {
+            ReplayEntry::Random(n) => Ok(Value::int(n)),
+            _ => unreachable!("replay_next only returns matching entries"),
+        };
+    }
 
-    // Simple pseudo-random using time
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_nanos())
-        .unwrap_or(0);
+    // Xorshift64, seeded from the clock (the state must never be zero)
+    let mut x = interp.random_state;
+    if x == 0 {
+        x = interp.clock.now_us() | 1;
+    }
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    interp.random_state = x;
 
     // Return a value between 0 and 1000 (representing 0.000 to 0.999)
     // Since we don't have floats, caller can divide by 1000
-    let random = (now % 1000) as i32;
+    let random = (x % 1000) as i32;
+    interp.record(ReplayEntry::Random(random));
     Ok(Value::int(random))
 }
 
@@ -5684,9 +11075,10 @@ fn native_math_atan2(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let y = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
-    let x = args.get(1).and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.atan2", args);
+    let y = args.opt_int(0, 0)?;
+    let x = args.opt_int(1, 0)?;
 
     // Simplified atan2 returning approximate degrees
     if x == 0 {
@@ -5720,8 +11112,9 @@ fn native_math_asin(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let x = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.asin", args);
+    let x = args.opt_int(0, 0)?;
     // asin only defined for -1 to 1, but with integers we approximate
     if x <= -1 {
         Ok(Value::int(-90))
@@ -5738,8 +11131,9 @@ fn native_math_acos(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let x = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.acos", args);
+    let x = args.opt_int(0, 0)?;
     // acos only defined for -1 to 1, but with integers we approximate
     if x <= -1 {
         Ok(Value::int(180))
@@ -5756,8 +11150,9 @@ fn native_math_atan(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let x = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+) -> Result<Value, HostError> {
+    let args = Args::new("Math.atan", args);
+    let x = args.opt_int(0, 0)?;
     // Simplified approximation
     if x == 0 {
         Ok(Value::int(0))
@@ -5780,61 +11175,73 @@ fn native_math_atan(
 // String.prototype methods
 // =============================================================================
 
-/// String.prototype.charAt - get character at index
+/// UTF-16 code units of a string receiver, for the index accessors
+fn this_code_units(interp: &mut Interpreter, this: Value) -> Result<Vec<u16>, HostError> {
+    let s = string_receiver(interp, this)?;
+    Ok(s.encode_utf16().collect())
+}
+
+/// Index argument of the string accessors (ToInteger; missing means 0)
+fn string_index_arg(interp: &Interpreter, args: &[Value]) -> i64 {
+    args.first()
+        .and_then(|&v| interp.to_number(v).to_i32())
+        .unwrap_or(0) as i64
+}
+
+/// Code unit at `index`, or None when the index is negative or past the end
+fn code_unit_at(units: &[u16], index: i64) -> Option<u16> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| units.get(i).copied())
+}
+
+/// Render a single code unit; a lone surrogate becomes U+FFFD
+fn code_unit_string(unit: u16) -> String {
+    unicode::from_code_units(&[unit])
+}
+
+/// String.prototype.charAt - get the code unit at index as a string
+///
+/// Indices count UTF-16 code units; negative or past-the-end indices give "".
 fn native_string_char_at(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "charAt called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
-
-    let index = args.first().and_then(|v| v.to_i32()).unwrap_or(0) as usize;
-
-    if index < s.len() {
-        // Get the character at index (for ASCII strings)
-        let ch = s
-            .chars()
-            .nth(index)
-            .map(|c| c.to_string())
-            .unwrap_or_default();
-        let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-        interp.runtime_strings.push(ch);
-        Ok(Value::string(new_idx))
-    } else {
-        // Return empty string for out of bounds
-        let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-        interp.runtime_strings.push(String::new());
-        Ok(Value::string(new_idx))
-    }
+) -> Result<Value, HostError> {
+    let units = this_code_units(interp, this)?;
+    let index = string_index_arg(interp, args);
+    let ch = code_unit_at(&units, index).map(code_unit_string);
+    Ok(interp.alloc_string(&ch.unwrap_or_default()))
 }
 
-/// String.prototype.charCodeAt - get character code at index
+/// String.prototype.charCodeAt - get the UTF-16 code unit at index
+///
+/// Out-of-range indices give NaN, which is 0 until floats exist (the same
+/// value the global `NaN` evaluates to).
 fn native_string_char_code_at(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "charCodeAt called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
-
-    let index = args.first().and_then(|v| v.to_i32()).unwrap_or(0) as usize;
+) -> Result<Value, HostError> {
+    let units = this_code_units(interp, this)?;
+    let index = string_index_arg(interp, args);
+    Ok(Value::int(code_unit_at(&units, index).map_or(0, i32::from)))
+}
 
-    if let Some(ch) = s.chars().nth(index) {
-        Ok(Value::int(ch as i32))
-    } else {
-        // Return NaN for out of bounds - using 0 for now since we don't have proper NaN
-        Ok(Value::int(0))
+/// String.prototype.at - get the code unit at index, counting back from the end when negative
+fn native_string_at(
+    interp: &mut Interpreter,
+    this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let units = this_code_units(interp, this)?;
+    let mut index = string_index_arg(interp, args);
+    if index < 0 {
+        index += units.len() as i64;
+    }
+    match code_unit_at(&units, index) {
+        Some(unit) => Ok(interp.alloc_string(&code_unit_string(unit))),
+        None => Ok(Value::undefined()),
     }
 }
 
@@ -5843,55 +11250,34 @@ fn native_string_last_index_of(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "lastIndexOf called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
-
-    // Get search string
-    let search = if let Some(search_val) = args.first() {
-        if let Some(search_idx) = search_val.to_string_idx() {
-            interp
-                .get_string_by_idx(search_idx)
-                .unwrap_or_default()
-                .to_string()
-        } else if let Some(n) = search_val.to_i32() {
-            n.to_string()
-        } else {
-            return Ok(Value::int(-1));
-        }
-    } else {
-        return Ok(Value::int(-1));
-    };
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
+    let search =
+        Args::new("String.prototype.lastIndexOf", args).string(0, NativeScope::of(interp))?;
 
-    // Find the last occurrence
+    // Find the last occurrence, reported in code units
     match s.rfind(&search) {
-        Some(idx) => Ok(Value::int(idx as i32)),
+        Some(idx) => Ok(Value::int_saturating(
+            unicode::utf8_to_utf16_index(&s, idx) as i64
+        )),
         None => Ok(Value::int(-1)),
     }
 }
 
-/// String.fromCharCode - create string from character codes
+/// String.fromCharCode - create string from UTF-16 code units
+///
+/// Each argument is taken modulo 2^16. Surrogates that pair up form one
+/// character; unpaired ones become U+FFFD.
 fn native_string_from_char_code(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let mut result = String::new();
-    for arg in args {
-        if let Some(code) = arg.to_i32()
-            && let Some(ch) = char::from_u32(code as u32)
-        {
-            result.push(ch);
-        }
-    }
-    let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_idx))
+) -> Result<Value, HostError> {
+    let units: Vec<u16> = args
+        .iter()
+        .map(|&arg| interp.to_number(arg).to_i32().unwrap_or(0) as u16)
+        .collect();
+    Ok(interp.alloc_string(&unicode::from_code_units(&units)))
 }
 
 /// String.fromCodePoint - create string from code points
@@ -5899,23 +11285,19 @@ fn native_string_from_code_point(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let mut result = String::new();
     for arg in args {
         if let Some(code) = arg.to_i32() {
-            if code < 0 {
-                return Err("Invalid code point".to_string());
-            }
-            if let Some(ch) = char::from_u32(code as u32) {
-                result.push(ch);
-            } else {
-                return Err("Invalid code point".to_string());
-            }
+            let code = u32::try_from(code)
+                .ok()
+                .filter(|&code| code <= 0x10FFFF)
+                .ok_or_else(|| HostError::Range(format!("Invalid code point {}", code)))?;
+            // Lone surrogates are valid code points but not chars
+            result.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
         }
     }
-    let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_idx))
+    Ok(interp.alloc_string(&result))
 }
 
 /// String.prototype.indexOf - find substring
@@ -5923,34 +11305,15 @@ fn native_string_index_of(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "indexOf called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
-
-    // Get search string
-    let search = if let Some(search_val) = args.first() {
-        if let Some(search_idx) = search_val.to_string_idx() {
-            interp
-                .get_string_by_idx(search_idx)
-                .unwrap_or_default()
-                .to_string()
-        } else if let Some(n) = search_val.to_i32() {
-            n.to_string()
-        } else {
-            return Ok(Value::int(-1));
-        }
-    } else {
-        return Ok(Value::int(-1));
-    };
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
+    let search = Args::new("String.prototype.indexOf", args).string(0, NativeScope::of(interp))?;
 
-    // Find the substring
+    // Find the substring, reported in code units
     match s.find(&search) {
-        Some(pos) => Ok(Value::int(pos as i32)),
+        Some(pos) => Ok(Value::int_saturating(
+            unicode::utf8_to_utf16_index(&s, pos) as i64
+        )),
         None => Ok(Value::int(-1)),
     }
 }
@@ -5960,41 +11323,27 @@ fn native_string_slice(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "slice called on non-string".to_string())?;
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
+    let args = Args::new("String.prototype.slice", args);
 
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
-
-    let len = s.len() as i32;
+    let len = unicode::utf16_len(&s).min(i32::MAX as usize) as i32;
 
     // Get start index
-    let mut start = args.first().and_then(|v| v.to_i32()).unwrap_or(0);
+    let mut start = args.opt_int(0, 0)?;
     if start < 0 {
         start = (len + start).max(0);
     }
     let start = start.min(len) as usize;
 
     // Get end index
-    let mut end = args.get(1).and_then(|v| v.to_i32()).unwrap_or(len);
+    let mut end = args.opt_int(1, len)?;
     if end < 0 {
         end = (len + end).max(0);
     }
     let end = end.min(len) as usize;
 
-    // Extract slice
-    let result = if start < end {
-        s[start..end].to_string()
-    } else {
-        String::new()
-    };
-
-    let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_idx))
+    Ok(interp.alloc_string(&unicode::slice_utf16(&s, start, end)))
 }
 
 /// String.prototype.substring - extract portion of string (similar to slice but different negative handling)
@@ -6002,32 +11351,17 @@ fn native_string_substring(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "substring called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
+    let args = Args::new("String.prototype.substring", args);
 
-    let len = s.len() as i32;
+    let len = unicode::utf16_len(&s).min(i32::MAX as usize) as i32;
 
     // Get start index (negative becomes 0)
-    let start = args
-        .first()
-        .and_then(|v| v.to_i32())
-        .unwrap_or(0)
-        .max(0)
-        .min(len) as usize;
+    let start = args.opt_int(0, 0)?.max(0).min(len) as usize;
 
     // Get end index (negative becomes 0)
-    let end = args
-        .get(1)
-        .and_then(|v| v.to_i32())
-        .unwrap_or(len)
-        .max(0)
-        .min(len) as usize;
+    let end = args.opt_int(1, len)?.max(0).min(len) as usize;
 
     // Swap if start > end
     let (start, end) = if start > end {
@@ -6036,11 +11370,7 @@ fn native_string_substring(
         (start, end)
     };
 
-    let result = s[start..end].to_string();
-
-    let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_idx))
+    Ok(interp.alloc_string(&unicode::slice_utf16(&s, start, end)))
 }
 
 /// String.prototype.toUpperCase - convert to uppercase
@@ -6048,20 +11378,12 @@ fn native_string_to_upper_case(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "toUpperCase called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
 
     let result = s.to_uppercase();
 
-    let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_idx))
+    Ok(interp.alloc_string(&result))
 }
 
 /// String.prototype.toLowerCase - convert to lowercase
@@ -6069,20 +11391,12 @@ fn native_string_to_lower_case(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "toLowerCase called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
 
     let result = s.to_lowercase();
 
-    let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_idx))
+    Ok(interp.alloc_string(&result))
 }
 
 /// String.prototype.trim - remove whitespace from both ends
@@ -6090,20 +11404,12 @@ fn native_string_trim(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "trim called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
 
     let result = s.trim().to_string();
 
-    let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_idx))
+    Ok(interp.alloc_string(&result))
 }
 
 /// String.prototype.split - split string into array
@@ -6111,46 +11417,32 @@ fn native_string_split(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "split called on non-string".to_string())?;
-
-    // Clone the string to avoid borrow issues
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?
-        .to_string();
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
+    let args = Args::new("String.prototype.split", args);
 
     // Get separator
-    let separator = if let Some(sep_val) = args.first() {
-        if let Some(sep_idx) = sep_val.to_string_idx() {
-            interp.get_string_by_idx(sep_idx).unwrap_or(",").to_string()
-        } else {
-            ",".to_string()
-        }
-    } else {
+    if args.is_absent(0) {
         // No separator - return array with whole string
-        let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-        interp.runtime_strings.push(s);
+        let whole = interp.alloc_string(&s);
+        return Ok(interp.alloc_temp_array(vec![whole]));
+    }
+    let separator = args.string(0, NativeScope::of(interp))?;
 
-        let arr_idx = interp.arrays.len() as u32;
-        interp.arrays.push(vec![Value::string(new_str_idx)]);
-        return Ok(Value::array_idx(arr_idx));
+    // Split and create array of strings; an empty separator splits into
+    // UTF-16 code units, without the empty ends `str::split` would give
+    let string_parts: Vec<String> = if separator.is_empty() {
+        s.encode_utf16().map(code_unit_string).collect()
+    } else {
+        s.split(&separator).map(|p| p.to_string()).collect()
     };
-
-    // Split and create array of strings
-    let string_parts: Vec<String> = s.split(&separator).map(|p| p.to_string()).collect();
     let mut parts: Vec<Value> = Vec::with_capacity(string_parts.len());
     for part in string_parts {
-        let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-        interp.runtime_strings.push(part);
-        parts.push(Value::string(new_str_idx));
+        let part = interp.alloc_string(&part);
+        parts.push(part);
     }
 
-    let arr_idx = interp.arrays.len() as u32;
-    interp.arrays.push(parts);
-    Ok(Value::array_idx(arr_idx))
+    Ok(interp.alloc_temp_array(parts))
 }
 
 /// String.prototype.concat - concatenate strings
@@ -6158,40 +11450,29 @@ fn native_string_concat(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "concat called on non-string".to_string())?;
+) -> Result<Value, HostError> {
+    let mut result = string_receiver(interp, this)?;
 
-    let mut result = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?
-        .to_string();
-
-    // Concatenate all arguments
+    // Concatenate all arguments, checking the length before each one
     for arg in args {
-        if let Some(arg_idx) = arg.to_string_idx() {
-            if let Some(arg_str) = interp.get_string_by_idx(arg_idx) {
-                result.push_str(arg_str);
-            }
+        let piece = if arg.is_string() {
+            interp.get_string(*arg).unwrap_or_default().to_string()
         } else if let Some(n) = arg.to_i32() {
-            result.push_str(&n.to_string());
+            IntStr::new(n).as_str().to_string()
         } else if arg.is_undefined() {
-            result.push_str("undefined");
+            "undefined".to_string()
         } else if arg.is_null() {
-            result.push_str("null");
+            "null".to_string()
         } else if arg.is_bool() {
-            result.push_str(if arg.to_bool().unwrap_or(false) {
-                "true"
-            } else {
-                "false"
-            });
-        }
+            arg.to_bool().unwrap_or(false).to_string()
+        } else {
+            continue;
+        };
+        interp.reserve_string(result.len().saturating_add(piece.len()))?;
+        result.push_str(&piece);
     }
 
-    let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_str_idx))
+    Ok(interp.alloc_string(&result))
 }
 
 /// String.prototype.repeat - repeat string n times
@@ -6199,23 +11480,17 @@ fn native_string_repeat(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "repeat called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?
-        .to_string();
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
 
-    let count = args.first().and_then(|v| v.to_i32()).unwrap_or(0).max(0) as usize;
+    let count = Args::new("String.prototype.repeat", args)
+        .opt_int(0, 0)?
+        .max(0) as usize;
+    interp.reserve_string(s.len().saturating_mul(count))?;
 
     let result = s.repeat(count);
 
-    let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_str_idx))
+    Ok(interp.alloc_string(&result))
 }
 
 /// String.prototype.startsWith - check if string starts with search string
@@ -6223,36 +11498,16 @@ fn native_string_starts_with(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "startsWith called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
-
-    let search = if let Some(search_val) = args.first() {
-        if let Some(search_idx) = search_val.to_string_idx() {
-            interp
-                .get_string_by_idx(search_idx)
-                .unwrap_or_default()
-                .to_string()
-        } else {
-            return Ok(Value::bool(false));
-        }
-    } else {
-        return Ok(Value::bool(false));
-    };
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
+    let args = Args::new("String.prototype.startsWith", args);
+    let search = args.string(0, NativeScope::of(interp))?;
 
-    // Optional position argument
-    let position = args.get(1).and_then(|v| v.to_i32()).unwrap_or(0).max(0) as usize;
+    // Optional position argument, in code units
+    let position = args.opt_int(1, 0)?.max(0) as usize;
+    let rest = unicode::slice_utf16(&s, position, usize::MAX);
 
-    if position >= s.len() {
-        return Ok(Value::bool(search.is_empty()));
-    }
-
-    Ok(Value::bool(s[position..].starts_with(&search)))
+    Ok(Value::bool(rest.starts_with(&search)))
 }
 
 /// String.prototype.endsWith - check if string ends with search string
@@ -6260,42 +11515,20 @@ fn native_string_ends_with(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "endsWith called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
-
-    let search = if let Some(search_val) = args.first() {
-        if let Some(search_idx) = search_val.to_string_idx() {
-            interp
-                .get_string_by_idx(search_idx)
-                .unwrap_or_default()
-                .to_string()
-        } else {
-            return Ok(Value::bool(false));
-        }
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
+    let args = Args::new("String.prototype.endsWith", args);
+    let search = args.string(0, NativeScope::of(interp))?;
+
+    // Optional end position argument, in code units
+    let end = if args.is_absent(1) {
+        usize::MAX
     } else {
-        return Ok(Value::bool(false));
+        args.int(1)?.max(0) as usize
     };
+    let head = unicode::slice_utf16(&s, 0, end);
 
-    // Optional end position argument
-    let end_position = args
-        .get(1)
-        .and_then(|v| v.to_i32())
-        .map(|v| v.max(0) as usize)
-        .unwrap_or(s.len());
-
-    let end = end_position.min(s.len());
-
-    if search.len() > end {
-        return Ok(Value::bool(false));
-    }
-
-    Ok(Value::bool(s[..end].ends_with(&search)))
+    Ok(Value::bool(head.ends_with(&search)))
 }
 
 /// String.prototype.padStart - pad string from start to target length
@@ -6303,51 +11536,8 @@ fn native_string_pad_start(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "padStart called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?
-        .to_string();
-
-    let target_length = args.first().and_then(|v| v.to_i32()).unwrap_or(0).max(0) as usize;
-
-    if s.len() >= target_length {
-        let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-        interp.runtime_strings.push(s);
-        return Ok(Value::string(new_str_idx));
-    }
-
-    let pad_string = if let Some(pad_val) = args.get(1) {
-        if let Some(pad_idx) = pad_val.to_string_idx() {
-            interp.get_string_by_idx(pad_idx).unwrap_or(" ").to_string()
-        } else {
-            " ".to_string()
-        }
-    } else {
-        " ".to_string()
-    };
-
-    if pad_string.is_empty() {
-        let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-        interp.runtime_strings.push(s);
-        return Ok(Value::string(new_str_idx));
-    }
-
-    let pad_needed = target_length - s.len();
-    let full_pads = pad_needed / pad_string.len();
-    let partial_pad = pad_needed % pad_string.len();
-
-    let mut result = pad_string.repeat(full_pads);
-    result.push_str(&pad_string[..partial_pad]);
-    result.push_str(&s);
-
-    let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_str_idx))
+) -> Result<Value, HostError> {
+    string_pad(interp, this, args, true)
 }
 
 /// String.prototype.padEnd - pad string from end to target length
@@ -6355,51 +11545,48 @@ fn native_string_pad_end(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "padEnd called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?
-        .to_string();
+) -> Result<Value, HostError> {
+    string_pad(interp, this, args, false)
+}
 
-    let target_length = args.first().and_then(|v| v.to_i32()).unwrap_or(0).max(0) as usize;
+/// Shared body of padStart/padEnd
+///
+/// Lengths count UTF-16 code units. The pad string (default " ") is
+/// repeated and cut off mid-pattern so the result is exactly the target
+/// length; a string already that long, or an empty pad, is returned as is.
+fn string_pad(
+    interp: &mut Interpreter,
+    this: Value,
+    args: &[Value],
+    at_start: bool,
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
 
-    if s.len() >= target_length {
-        let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-        interp.runtime_strings.push(s);
-        return Ok(Value::string(new_str_idx));
-    }
+    let target_length = args
+        .first()
+        .and_then(|&v| interp.to_number(v).to_i32())
+        .unwrap_or(0)
+        .max(0) as usize;
+    let pad_needed = target_length.saturating_sub(s.encode_utf16().count());
 
-    let pad_string = if let Some(pad_val) = args.get(1) {
-        if let Some(pad_idx) = pad_val.to_string_idx() {
-            interp.get_string_by_idx(pad_idx).unwrap_or(" ").to_string()
-        } else {
-            " ".to_string()
+    let pad_string = match args.get(1) {
+        Some(&v) if !v.is_undefined() => {
+            let pad = interp.stringify_value(v)?;
+            interp.get_string(pad).unwrap_or_default().to_string()
         }
-    } else {
-        " ".to_string()
+        _ => " ".to_string(),
     };
 
-    if pad_string.is_empty() {
-        let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-        interp.runtime_strings.push(s);
-        return Ok(Value::string(new_str_idx));
+    if pad_needed == 0 || pad_string.is_empty() {
+        return Ok(this);
     }
+    interp.reserve_string(s.len().saturating_add(pad_needed))?;
 
-    let pad_needed = target_length - s.len();
-    let full_pads = pad_needed / pad_string.len();
-    let partial_pad = pad_needed % pad_string.len();
-
-    let mut result = s;
-    result.push_str(&pad_string.repeat(full_pads));
-    result.push_str(&pad_string[..partial_pad]);
+    let filler: Vec<u16> = pad_string.encode_utf16().cycle().take(pad_needed).collect();
+    let filler = unicode::from_code_units(&filler);
+    let result = if at_start { filler + &s } else { s + &filler };
 
-    let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_str_idx))
+    Ok(interp.alloc_string(&result))
 }
 
 /// String.prototype.replace - replace first occurrence of search with replacement
@@ -6407,48 +11594,16 @@ fn native_string_replace(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "replace called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?
-        .to_string();
-
-    let search = if let Some(search_val) = args.first() {
-        if let Some(search_idx) = search_val.to_string_idx() {
-            interp
-                .get_string_by_idx(search_idx)
-                .unwrap_or_default()
-                .to_string()
-        } else {
-            "".to_string()
-        }
-    } else {
-        "".to_string()
-    };
-
-    let replacement = if let Some(replace_val) = args.get(1) {
-        if let Some(replace_idx) = replace_val.to_string_idx() {
-            interp
-                .get_string_by_idx(replace_idx)
-                .unwrap_or_default()
-                .to_string()
-        } else {
-            "".to_string()
-        }
-    } else {
-        "".to_string()
-    };
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
+    let args = Args::new("String.prototype.replace", args);
+    let search = args.string(0, NativeScope::of(interp))?;
+    let replacement = args.string(1, NativeScope::of(interp))?;
 
     // Replace first occurrence only
     let result = s.replacen(&search, &replacement, 1);
 
-    let new_str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_str_idx))
+    Ok(interp.alloc_string(&result))
 }
 
 /// String.prototype.includes - check if string contains search string
@@ -6456,207 +11611,158 @@ fn native_string_includes(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "includes called on non-string".to_string())?;
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
+    let args = Args::new("String.prototype.includes", args);
+    let search = args.string(0, NativeScope::of(interp))?;
 
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
+    // Optional position argument, in code units
+    let position = args.opt_int(1, 0)?.max(0) as usize;
+    let rest = unicode::slice_utf16(&s, position, usize::MAX);
 
-    let search = if let Some(search_val) = args.first() {
-        if let Some(search_idx) = search_val.to_string_idx() {
-            interp
-                .get_string_by_idx(search_idx)
-                .unwrap_or_default()
-                .to_string()
-        } else {
-            return Ok(Value::bool(false));
-        }
+    Ok(Value::bool(rest.contains(&search)))
+}
+
+/// Compile a RegExp pattern with the engine this build uses
+///
+/// The error is the message of the SyntaxError to throw, naming the
+/// pattern and what is wrong with it or unsupported in it.
+fn compile_regexp(pattern: &str, flags: RegexFlags) -> Result<Regex, String> {
+    regexp::compile(pattern, flags)
+        .map_err(|msg| format!("Invalid regular expression: /{}/: {}", pattern, msg))
+}
+
+/// Regex used by String.prototype.match/search for an argument
+///
+/// RegExp objects are used as-is; anything else is converted to a string
+/// and compiled as a pattern (so "a.b" matches "axb"), and undefined
+/// matches the empty string. Returns the regex and its global flag.
+fn coerce_regexp(interp: &mut Interpreter, arg: Value) -> Result<(Regex, bool), HostError> {
+    if let Some(regex_idx) = arg.to_regexp_object_idx() {
+        let re = interp
+            .regex_objects
+            .get(regex_idx as usize)
+            .ok_or_else(|| "invalid RegExp object".to_string())?;
+        return Ok((re.regex.clone(), re.global));
+    }
+
+    let pattern = if arg.is_undefined() {
+        String::new()
     } else {
-        return Ok(Value::bool(true)); // includes() with no args returns true
+        let str_val = interp.stringify_value(arg)?;
+        interp.get_string(str_val).unwrap_or_default().to_string()
     };
+    let regex = compile_regexp(&pattern, RegexFlags::default()).map_err(HostError::Syntax)?;
+    Ok((regex, false))
+}
 
-    // Optional position argument
-    let position = args.get(1).and_then(|v| v.to_i32()).unwrap_or(0).max(0) as usize;
+/// Build the exec-style result for the first match of `regex` in `s`
+///
+/// The array holds the match and its capture groups (undefined for groups
+/// that did not participate) and carries `index` and `input` properties.
+/// Returns null when there is no match.
+fn regexp_exec_result(interp: &mut Interpreter, regex: &Regex, s: &str) -> Value {
+    let Some(caps) = regex.captures_at(s, 0) else {
+        return Value::null();
+    };
+    let index = caps
+        .first()
+        .cloned()
+        .flatten()
+        .map_or(0, |m| unicode::utf8_to_utf16_index(s, m.start));
+    let elements: Vec<Value> = caps
+        .into_iter()
+        .map(|group| match group {
+            Some(span) => interp.alloc_string(&s[span]),
+            None => Value::undefined(),
+        })
+        .collect();
 
-    if position >= s.len() {
-        return Ok(Value::bool(search.is_empty()));
+    let arr = interp.alloc_temp_array(elements);
+    if let Some(arr_idx) = arr.to_array_idx() {
+        let input = interp.alloc_string(s);
+        interp.array_set_named(arr_idx, "index", Value::int_saturating(index as i64));
+        interp.array_set_named(arr_idx, "input", input);
     }
-
-    Ok(Value::bool(s[position..].contains(&search)))
+    arr
 }
 
 /// String.prototype.match - match string against a RegExp
+///
+/// Without the g flag this returns the exec-style result; with it, an array
+/// of every matched substring. Both return null when nothing matches.
 fn native_string_match(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "match called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?
-        .to_string();
-
-    // Get the RegExp argument
-    let regex_arg = args.first().copied().unwrap_or_default();
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
 
-    // Check if it's a RegExp object
-    if let Some(regex_idx) = regex_arg.to_regexp_object_idx() {
-        let re = interp
-            .regex_objects
-            .get(regex_idx as usize)
-            .ok_or_else(|| "invalid RegExp object".to_string())?
-            .clone();
-
-        if re.global {
-            // Global match - return array of all matches
-            let matches: Vec<String> = re
-                .regex
-                .find_iter(&s)
-                .map(|m| m.as_str().to_string())
-                .collect();
-
-            if matches.is_empty() {
-                return Ok(Value::null());
-            }
-
-            // Create array of matched strings
-            let mut result_arr: Vec<Value> = Vec::with_capacity(matches.len());
-            for matched in matches {
-                let str_idx =
-                    interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-                interp.runtime_strings.push(matched);
-                result_arr.push(Value::string(str_idx));
-            }
+    let (regex, global) = coerce_regexp(interp, args.first().copied().unwrap_or_default())?;
+    if !global {
+        return Ok(regexp_exec_result(interp, &regex, &s));
+    }
 
-            let arr_idx = interp.arrays.len() as u32;
-            interp.arrays.push(result_arr);
-            Ok(Value::array_idx(arr_idx))
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start <= s.len()
+        && let Some(span) = regex.find_at(&s, start)
+    {
+        // Step past an empty match so the search moves on
+        start = if span.is_empty() {
+            span.end + s[span.end..].chars().next().map_or(1, char::len_utf8)
         } else {
-            // Non-global match - return first match with groups (like exec)
-            if let Some(m) = re.regex.find(&s) {
-                let matched = m.as_str().to_string();
-                let str_idx =
-                    interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-                interp.runtime_strings.push(matched);
-
-                let arr_idx = interp.arrays.len() as u32;
-                interp.arrays.push(vec![Value::string(str_idx)]);
-                Ok(Value::array_idx(arr_idx))
-            } else {
-                Ok(Value::null())
-            }
-        }
-    } else if let Some(pattern_idx) = regex_arg.to_string_idx() {
-        // String argument - convert to RegExp
-        let pattern = interp
-            .get_string_by_idx(pattern_idx)
-            .ok_or_else(|| "invalid pattern string".to_string())?
-            .to_string();
-
-        match regex::Regex::new(&pattern) {
-            Ok(re) => {
-                if let Some(m) = re.find(&s) {
-                    let matched = m.as_str().to_string();
-                    let str_idx =
-                        interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-                    interp.runtime_strings.push(matched);
-
-                    let arr_idx = interp.arrays.len() as u32;
-                    interp.arrays.push(vec![Value::string(str_idx)]);
-                    Ok(Value::array_idx(arr_idx))
-                } else {
-                    Ok(Value::null())
-                }
-            }
-            Err(_) => Ok(Value::null()),
-        }
-    } else {
+            span.end
+        };
+        matches.push(interp.alloc_string(&s[span]));
+    }
+    if matches.is_empty() {
         Ok(Value::null())
+    } else {
+        Ok(interp.alloc_temp_array(matches))
     }
 }
 
-/// String.prototype.search - search for a match and return index
+/// String.prototype.search - index of the first match, or -1
+///
+/// Indices count UTF-16 code units, like String.prototype.indexOf.
 fn native_string_search(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "search called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?
-        .to_string();
-
-    // Get the RegExp argument
-    let regex_arg = args.first().copied().unwrap_or_default();
-
-    // Check if it's a RegExp object
-    if let Some(regex_idx) = regex_arg.to_regexp_object_idx() {
-        let re = interp
-            .regex_objects
-            .get(regex_idx as usize)
-            .ok_or_else(|| "invalid RegExp object".to_string())?
-            .clone();
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
 
-        if let Some(m) = re.regex.find(&s) {
-            Ok(Value::int(m.start() as i32))
-        } else {
-            Ok(Value::int(-1))
-        }
-    } else if let Some(pattern_idx) = regex_arg.to_string_idx() {
-        // String argument - convert to RegExp
-        let pattern = interp
-            .get_string_by_idx(pattern_idx)
-            .ok_or_else(|| "invalid pattern string".to_string())?
-            .to_string();
-
-        match regex::Regex::new(&pattern) {
-            Ok(re) => {
-                if let Some(m) = re.find(&s) {
-                    Ok(Value::int(m.start() as i32))
-                } else {
-                    Ok(Value::int(-1))
-                }
-            }
-            Err(_) => Ok(Value::int(-1)),
-        }
-    } else {
-        Ok(Value::int(-1))
-    }
+    let (regex, _) = coerce_regexp(interp, args.first().copied().unwrap_or_default())?;
+    Ok(Value::int_saturating(
+        regex
+            .find_at(&s, 0)
+            .map_or(-1, |m| unicode::utf8_to_utf16_index(&s, m.start) as i64),
+    ))
 }
 
 /// String.prototype.codePointAt - get Unicode code point at position
+///
+/// At a high surrogate followed by a low surrogate this returns the combined
+/// code point; otherwise it returns the code unit itself. Negative or
+/// past-the-end indices give undefined.
 fn native_string_code_point_at(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "codePointAt called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
-
-    let index = args.first().and_then(|v| v.to_i32()).unwrap_or(0) as usize;
-
-    // Get code point at index
-    if let Some(ch) = s.chars().nth(index) {
-        Ok(Value::int(ch as i32))
-    } else {
-        Ok(Value::undefined())
-    }
+) -> Result<Value, HostError> {
+    let units = this_code_units(interp, this)?;
+    let index = string_index_arg(interp, args);
+    let Some(first) = code_unit_at(&units, index) else {
+        return Ok(Value::undefined());
+    };
+    let code_point = match code_unit_at(&units, index + 1) {
+        Some(second) if (0xD800..0xDC00).contains(&first) && (0xDC00..0xE000).contains(&second) => {
+            0x10000 + ((first as i32 - 0xD800) << 10) + (second as i32 - 0xDC00)
+        }
+        _ => first as i32,
+    };
+    Ok(Value::int(code_point))
 }
 
 /// String.prototype.trimStart - remove leading whitespace
@@ -6664,19 +11770,11 @@ fn native_string_trim_start(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "trimStart called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
 
     let trimmed = s.trim_start().to_string();
-    let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(trimmed);
-    Ok(Value::string(new_idx))
+    Ok(interp.alloc_string(&trimmed))
 }
 
 /// String.prototype.trimEnd - remove trailing whitespace
@@ -6684,19 +11782,11 @@ fn native_string_trim_end(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "trimEnd called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?;
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
 
     let trimmed = s.trim_end().to_string();
-    let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(trimmed);
-    Ok(Value::string(new_idx))
+    Ok(interp.alloc_string(&trimmed))
 }
 
 /// String.prototype.replaceAll - replace all occurrences
@@ -6704,32 +11794,14 @@ fn native_string_replace_all(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let str_idx = this
-        .to_string_idx()
-        .ok_or_else(|| "replaceAll called on non-string".to_string())?;
-
-    let s = interp
-        .get_string_by_idx(str_idx)
-        .ok_or_else(|| "invalid string".to_string())?
-        .to_string();
-
-    let search = args
-        .first()
-        .and_then(|v| v.to_string_idx())
-        .and_then(|idx| interp.get_string_by_idx(idx).map(|s| s.to_string()))
-        .unwrap_or_default();
-
-    let replacement = args
-        .get(1)
-        .and_then(|v| v.to_string_idx())
-        .and_then(|idx| interp.get_string_by_idx(idx).map(|s| s.to_string()))
-        .unwrap_or_default();
+) -> Result<Value, HostError> {
+    let s = string_receiver(interp, this)?;
+    let args = Args::new("String.prototype.replaceAll", args);
+    let search = args.string(0, NativeScope::of(interp))?;
+    let replacement = args.string(1, NativeScope::of(interp))?;
 
     let result = s.replace(&search, &replacement);
-    let new_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-    interp.runtime_strings.push(result);
-    Ok(Value::string(new_idx))
+    Ok(interp.alloc_string(&result))
 }
 
 // =============================================================================
@@ -6741,7 +11813,7 @@ fn native_number_is_integer(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let val = args.first().copied().unwrap_or_default();
 
     // In our implementation, all numbers are integers (32-bit signed)
@@ -6757,7 +11829,7 @@ fn native_number_is_nan(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let val = args.first().copied().unwrap_or_default();
 
     // NaN is represented as a special value in our implementation
@@ -6772,7 +11844,7 @@ fn native_number_is_finite(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let val = args.first().copied().unwrap_or_default();
 
     // All our integers are finite (we don't have Infinity representation yet)
@@ -6792,9 +11864,9 @@ fn native_console_log(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let output = format_console_args(interp, args);
-    println!("{}", output);
+    interp.write_console(ConsoleLevel::Log, &output);
     Ok(Value::undefined())
 }
 
@@ -6803,9 +11875,9 @@ fn native_console_error(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let output = format_console_args(interp, args);
-    eprintln!("{}", output);
+    interp.write_console(ConsoleLevel::Error, &output);
     Ok(Value::undefined())
 }
 
@@ -6814,12 +11886,189 @@ fn native_console_warn(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let output = format_console_args(interp, args);
-    eprintln!("{}", output);
+    interp.write_console(ConsoleLevel::Warn, &output);
+    Ok(Value::undefined())
+}
+
+/// Label argument of console.time/timeEnd ("default" when missing)
+fn console_label(interp: &mut Interpreter, args: &[Value]) -> Result<String, HostError> {
+    match args.first() {
+        Some(&v) if !v.is_undefined() => interp.string_content(v, &mut HashSet::new()),
+        _ => Ok("default".to_string()),
+    }
+}
+
+/// console.time - start a timer under a label
+fn native_console_time(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let label = console_label(interp, args)?;
+    if interp.console_timers.contains_key(&label) {
+        let warning = format!("Timer '{}' already exists", label);
+        interp.write_console(ConsoleLevel::Warn, &warning);
+    } else {
+        let start = interp.performance_now()?;
+        interp.console_timers.insert(label, start);
+    }
+    Ok(Value::undefined())
+}
+
+/// console.timeEnd - print the time since console.time and stop the timer
+///
+/// Prints "label: 12.3ms" using the monotonic performance clock.
+fn native_console_time_end(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let label = console_label(interp, args)?;
+    match interp.console_timers.remove(&label) {
+        Some(start) => {
+            let micros = (interp.performance_now()? - start).max(0);
+            let line = format!("{}: {}.{}ms", label, micros / 1000, micros % 1000 / 100);
+            interp.write_console(ConsoleLevel::Log, &line);
+        }
+        None => {
+            let warning = format!("No such label '{}' for console.timeEnd()", label);
+            interp.write_console(ConsoleLevel::Warn, &warning);
+        }
+    }
     Ok(Value::undefined())
 }
 
+/// Maximum number of rows console.table prints
+const CONSOLE_TABLE_MAX_ROWS: usize = 50;
+
+/// Maximum width of a console.table cell; longer values end in "..."
+const CONSOLE_TABLE_MAX_WIDTH: usize = 24;
+
+/// console.table - print an array of rows as an ASCII table
+///
+/// Columns are the union of the rows' keys in first-seen order; primitive
+/// rows go in a "Values" column. Anything other than an array is logged
+/// as with console.log.
+fn native_console_table(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let data = args.first().copied().unwrap_or_default();
+    let Some(len) = interp.array_len(data) else {
+        let output = format_console_args(interp, args);
+        interp.write_console(ConsoleLevel::Log, &output);
+        return Ok(Value::undefined());
+    };
+    let rows: Vec<Value> = (0..len.min(CONSOLE_TABLE_MAX_ROWS))
+        .filter_map(|i| interp.array_element_at(data, i))
+        .collect();
+    let omitted = len.saturating_sub(CONSOLE_TABLE_MAX_ROWS);
+
+    // Collect each row's cells keyed by column name
+    const VALUES: &str = "Values";
+    let mut columns: Vec<String> = Vec::new();
+    let mut has_values = false;
+    let mut cells: Vec<Vec<(String, Value)>> = Vec::new();
+    for &row in rows.iter().take(CONSOLE_TABLE_MAX_ROWS) {
+        let entries: Vec<(String, Value)> = if let Some(obj_idx) = row.to_object_idx() {
+            interp
+                .objects
+                .get(obj_idx as usize)
+                .map(|obj| {
+                    obj.properties
+                        .iter()
+                        .map(|&(k, v)| (interp.atom_name(k).to_string(), v))
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else if let Some(arr_idx) = row.to_array_idx() {
+            interp
+                .array_entries(arr_idx)
+                .into_iter()
+                .map(|(i, v)| (i.to_string(), v))
+                .collect()
+        } else {
+            has_values = true;
+            cells.push(vec![(VALUES.to_string(), row)]);
+            continue;
+        };
+        for (key, _) in &entries {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+        cells.push(entries);
+    }
+    if has_values {
+        columns.push(VALUES.to_string());
+    }
+
+    // Stringify every cell, then lay out the grid
+    let mut header = vec!["(index)".to_string()];
+    header.extend(columns.iter().cloned());
+    let mut grid = vec![header];
+    for (index, entries) in cells.iter().enumerate() {
+        let mut line = vec![index.to_string()];
+        for column in &columns {
+            let text = match entries.iter().find(|(key, _)| key == column) {
+                Some(&(_, v)) => interp.string_content(v, &mut HashSet::new())?,
+                None => String::new(),
+            };
+            line.push(truncate_cell(&text));
+        }
+        grid.push(line);
+    }
+
+    let widths: Vec<usize> = (0..grid[0].len())
+        .map(|col| {
+            grid.iter()
+                .map(|line| line[col].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let border = widths
+        .iter()
+        .map(|&w| "-".repeat(w + 2))
+        .collect::<Vec<_>>()
+        .join("+");
+    let border = format!("+{}+", border);
+
+    let mut lines = vec![border.clone()];
+    for (i, line) in grid.iter().enumerate() {
+        let padded: Vec<String> = line
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &w)| format!(" {:<w$} ", cell, w = w))
+            .collect();
+        lines.push(format!("|{}|", padded.join("|")));
+        if i == 0 {
+            lines.push(border.clone());
+        }
+    }
+    lines.push(border);
+    if omitted > 0 {
+        lines.push(format!("... {} more rows", omitted));
+    }
+
+    interp.write_console(ConsoleLevel::Log, &lines.join("\n"));
+    Ok(Value::undefined())
+}
+
+/// Cut a console.table cell down to CONSOLE_TABLE_MAX_WIDTH characters
+fn truncate_cell(text: &str) -> String {
+    let text = text.replace('\n', " ");
+    if text.chars().count() <= CONSOLE_TABLE_MAX_WIDTH {
+        return text;
+    }
+    let mut cut: String = text.chars().take(CONSOLE_TABLE_MAX_WIDTH - 3).collect();
+    cut.push_str("...");
+    cut
+}
+
 /// Format arguments for console output
 fn format_console_args(interp: &Interpreter, args: &[Value]) -> String {
     args.iter()
@@ -6830,52 +12079,95 @@ fn format_console_args(interp: &Interpreter, args: &[Value]) -> String {
 
 /// Format a single value for output
 fn format_value(interp: &Interpreter, val: Value) -> String {
-    if let Some(n) = val.to_i32() {
-        n.to_string()
-    } else if let Some(b) = val.to_bool() {
-        b.to_string()
-    } else if val.is_null() {
-        "null".to_string()
-    } else if val.is_undefined() {
-        "undefined".to_string()
-    } else if let Some(str_idx) = val.to_string_idx() {
-        if let Some(s) = interp.get_string_by_idx(str_idx) {
-            s.to_string()
-        } else {
+    format_value_inner(interp, val, &mut HashSet::new())
+}
+
+/// Text of a `+` operand: a string's contents or a formatted integer
+enum ConcatPiece<'a> {
+    Str(JsStr<'a>),
+    Int(IntStr),
+    Owned(String),
+}
+
+impl core::ops::Deref for ConcatPiece<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            ConcatPiece::Str(s) => s,
+            ConcatPiece::Int(n) => n.as_str(),
+            ConcatPiece::Owned(s) => s,
+        }
+    }
+}
+
+/// Format a value, rendering arrays already being formatted (listed in
+/// `formatting`) as `[Circular]`
+fn format_value_inner(interp: &Interpreter, val: Value, formatting: &mut HashSet<u32>) -> String {
+    match val.classify() {
+        ValueTag::String if interp.get_string(val).is_none() => {
             // Compile-time string - can't look up without bytecode
             "<string>".to_string()
         }
-    } else if val.is_array() {
-        if let Some(arr_idx) = val.to_array_idx() {
-            if let Some(arr) = interp.arrays.get(arr_idx as usize) {
-                let items: Vec<String> = arr.iter().map(|v| format_value(interp, *v)).collect();
+        ValueTag::Array => {
+            let arr_idx = val.to_array_idx().unwrap_or_default();
+            if !formatting.insert(arr_idx) {
+                "[Circular]".to_string()
+            } else if let Some(len) = interp.array_len(val) {
+                // Runs of holes collapse to one item, so sparse arrays stay short
+                let mut items: Vec<String> = Vec::new();
+                let mut next = 0;
+                let empty = |n: usize| match n {
+                    1 => "<1 empty item>".to_string(),
+                    n => format!("<{n} empty items>"),
+                };
+                for (i, v) in interp.array_entries(arr_idx) {
+                    if i > next {
+                        items.push(empty(i - next));
+                    }
+                    items.push(format_value_inner(interp, v, formatting));
+                    next = i + 1;
+                }
+                if len > next {
+                    items.push(empty(len - next));
+                }
+                formatting.remove(&arr_idx);
                 format!("[{}]", items.join(", "))
             } else {
                 "[Array]".to_string()
             }
-        } else {
-            "[Array]".to_string()
-        }
-    } else if val.is_error_object() {
-        if let Some(err_idx) = val.to_error_object_idx() {
-            if let Some(err) = interp.error_objects.get(err_idx as usize) {
-                if err.message.is_empty() {
-                    err.name.clone()
-                } else {
-                    format!("{}: {}", err.name, err.message)
-                }
-            } else {
-                "Error".to_string()
-            }
-        } else {
-            "Error".to_string()
         }
-    } else if val.is_object() {
-        "[object Object]".to_string()
-    } else if val.is_closure() {
-        "[Function]".to_string()
-    } else {
-        format!("{:?}", val)
+        ValueTag::TypedArray => {
+            let items: Vec<String> = interp
+                .typed_array_values(val)
+                .iter()
+                .map(|v| IntStr::new(v.to_i32().unwrap_or(0)).as_str().to_string())
+                .collect();
+            format!("{} [{}]", interp.brand(val), items.join(", "))
+        }
+        ValueTag::Object => match interp.date_value(val) {
+            Some(time) => time.map_or_else(|| "Invalid Date".to_string(), date::to_iso_string),
+            None if interp.is_callable(val) => "[Function]".to_string(),
+            None => "[object Object]".to_string(),
+        },
+        ValueTag::Closure | ValueTag::Bytecode | ValueTag::ShortFunc | ValueTag::NativeFunc => {
+            "[Function]".to_string()
+        }
+        ValueTag::Builtin if interp.brand(val) == "Function" => "[Function]".to_string(),
+        ValueTag::Exception => "[exception]".to_string(),
+        ValueTag::Uninitialized => "[uninitialized]".to_string(),
+        // Loop iterators never reach scripts, but render harmlessly if one does
+        ValueTag::Undefined
+        | ValueTag::Null
+        | ValueTag::Bool
+        | ValueTag::Int
+        | ValueTag::String
+        | ValueTag::Builtin
+        | ValueTag::Error
+        | ValueTag::RegExp
+        | ValueTag::ArrayBuffer
+        | ValueTag::ForInIterator
+        | ValueTag::ForOfIterator => interp.plain_string_content(val),
     }
 }
 
@@ -6884,87 +12176,147 @@ fn format_value(interp: &Interpreter, val: Value) -> String {
 // ===========================================
 
 /// JSON.stringify - convert a value to a JSON string
+///
+/// The third argument indents nested lines by that many spaces (at most
+/// 10) or by the string itself; the replacer argument is ignored.
 fn native_json_stringify(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    if args.is_empty() {
-        return Ok(Value::undefined());
+) -> Result<Value, HostError> {
+    let val = args.first().copied().unwrap_or_default();
+    let space = args.get(2).copied().unwrap_or_default();
+    let indent = if let Some(n) = space.to_i32() {
+        " ".repeat(n.clamp(0, 10) as usize)
+    } else {
+        interp
+            .get_string(space)
+            .map(|s| s.chars().take(10).collect())
+            .unwrap_or_default()
+    };
+    match JsonWriter::new(indent).write(interp, val)? {
+        Some(json) => {
+            interp.reserve_string(json.len())?;
+            Ok(interp.alloc_string(&json))
+        }
+        None => Ok(Value::undefined()),
     }
-    let val = args[0];
-    let json_str = json_stringify_value(interp, val);
-    Ok(interp.create_runtime_string(json_str))
 }
 
-/// Helper function to stringify a value to JSON format
-fn json_stringify_value(interp: &Interpreter, val: Value) -> String {
-    if let Some(n) = val.to_i32() {
-        n.to_string()
-    } else if let Some(b) = val.to_bool() {
-        b.to_string()
-    } else if val.is_null() {
-        "null".to_string()
-    } else if val.is_undefined() {
-        // undefined values are excluded in JSON.stringify
-        "undefined".to_string()
-    } else if let Some(str_idx) = val.to_string_idx() {
-        if let Some(s) = interp.get_string_by_idx(str_idx) {
-            // Escape the string for JSON
-            format!("\"{}\"", escape_json_string(s))
-        } else {
-            "\"\"".to_string()
+/// Serializer shared by `JSON.stringify` and [`Interpreter::to_json`]
+struct JsonWriter {
+    /// Indentation per nesting level; empty for compact output
+    indent: String,
+    /// Arrays and objects being serialized, to detect cycles
+    open: Vec<Value>,
+}
+
+impl JsonWriter {
+    fn new(indent: String) -> Self {
+        Self {
+            indent,
+            open: Vec::new(),
         }
-    } else if val.is_array() {
-        if let Some(arr_idx) = val.to_array_idx() {
-            if let Some(arr) = interp.arrays.get(arr_idx as usize) {
-                let items: Vec<String> = arr
-                    .iter()
-                    .map(|v| {
-                        let s = json_stringify_value(interp, *v);
-                        // Replace undefined with null in arrays
-                        if s == "undefined" {
-                            "null".to_string()
-                        } else {
-                            s
-                        }
-                    })
-                    .collect();
-                format!("[{}]", items.join(","))
-            } else {
-                "[]".to_string()
+    }
+
+    /// Serialize `val`, or None for values JSON leaves out (undefined and
+    /// functions)
+    ///
+    /// Objects with a callable `toJSON` property are replaced by its result
+    /// before serialization, as with Date objects.
+    fn write(&mut self, interp: &mut Interpreter, val: Value) -> Result<Option<String>, HostError> {
+        let mut val = val;
+        if let Some(obj_idx) = val.to_object_idx() {
+            let to_json = interp.object_get_property(obj_idx, "toJSON");
+            if interp.is_callable(to_json) {
+                val = interp.call_value(to_json, val, &[])?;
             }
-        } else {
-            "[]".to_string()
         }
-    } else if val.is_object() {
-        if let Some(obj_idx) = val.to_object_idx() {
-            if let Some(obj) = interp.objects.get(obj_idx as usize) {
-                let items: Vec<String> = obj
-                    .properties
-                    .iter()
-                    .filter_map(|(k, v)| {
-                        let val_str = json_stringify_value(interp, *v);
-                        // Skip undefined values in objects
-                        if val_str == "undefined" {
-                            None
-                        } else {
-                            Some(format!("\"{}\":{}", escape_json_string(k), val_str))
-                        }
-                    })
-                    .collect();
-                format!("{{{}}}", items.join(","))
-            } else {
-                "{}".to_string()
+        let s = if let Some(n) = val.to_i32() {
+            IntStr::new(n).as_str().to_string()
+        } else if let Some(b) = val.to_bool() {
+            b.to_string()
+        } else if val.is_null() {
+            "null".to_string()
+        } else if val.is_undefined() || interp.is_callable(val) {
+            return Ok(None);
+        } else if val.is_string() {
+            let s = interp.get_string(val).unwrap_or_default();
+            format!("\"{}\"", escape_json_string(&s))
+        } else if val.is_array() || val.is_object() {
+            if self.open.contains(&val) {
+                return Err(HostError::Type(
+                    "Converting circular structure to JSON".to_string(),
+                ));
+            }
+            self.open.push(val);
+            let result = self.write_container(interp, val);
+            self.open.pop();
+            result?
+        } else {
+            "null".to_string()
+        };
+        Ok(Some(s))
+    }
+
+    /// Serialize the elements of an array or the enumerable properties of
+    /// an object
+    fn write_container(
+        &mut self,
+        interp: &mut Interpreter,
+        val: Value,
+    ) -> Result<String, HostError> {
+        let mut items = Vec::new();
+        // Lengths are read up front and entries as they are reached, so a
+        // toJSON that changes the container is seen part way
+        let (open, close) = if let Some(len) = interp.array_len(val) {
+            for i in 0..len {
+                let v = interp.array_element_at(val, i).unwrap_or_default();
+                // Left-out values become null inside arrays
+                items.push(self.write(interp, v)?.unwrap_or_else(|| "null".to_string()));
             }
+            ('[', ']')
         } else {
-            "{}".to_string()
+            let colon = if self.indent.is_empty() { ":" } else { ": " };
+            for i in 0..interp.object_property_count(val).unwrap_or(0) {
+                let Some((k, v)) = interp.object_property_at(val, i) else {
+                    break;
+                };
+                if !interp.has_property(val, k, PropertyLookup::OwnEnumerable) {
+                    continue;
+                }
+                let k = escape_json_string(k);
+                // Left-out values drop the whole property
+                if let Some(json) = self.write(interp, v)? {
+                    items.push(format!("\"{}\"{}{}", k, colon, json));
+                }
+            }
+            ('{', '}')
+        };
+        if items.is_empty() {
+            return Ok(format!("{}{}", open, close));
+        }
+        // Containers on the open stack, this one included, set the depth
+        let depth = self.open.len();
+        let per_item = 2 + self.indent.len().saturating_mul(depth);
+        let len = items.iter().fold(per_item, |len, item| {
+            len.saturating_add(item.len() + per_item)
+        });
+        interp.reserve_string(len)?;
+        if self.indent.is_empty() {
+            return Ok(format!("{}{}{}", open, items.join(","), close));
         }
-    } else if val.is_closure() {
-        // Functions are excluded in JSON.stringify
-        "undefined".to_string()
-    } else {
-        "null".to_string()
+        let inner = self.indent.repeat(depth);
+        let outer = self.indent.repeat(depth - 1);
+        let separator = format!(",\n{}", inner);
+        Ok(format!(
+            "{}\n{}{}\n{}{}",
+            open,
+            inner,
+            items.join(&separator),
+            outer,
+            close
+        ))
     }
 }
 
@@ -6990,44 +12342,138 @@ fn native_json_parse(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     if args.is_empty() {
-        return Err("JSON.parse requires a string argument".to_string());
+        return Err(HostError::Type(
+            "JSON.parse requires a string argument".to_string(),
+        ));
     }
     let val = args[0];
 
     // Get the string to parse
-    let json_str = if let Some(str_idx) = val.to_string_idx() {
-        if let Some(s) = interp.get_string_by_idx(str_idx) {
+    let json_str = if val.is_string() {
+        if let Some(s) = interp.get_string(val) {
             s.to_string()
         } else {
-            return Err("Invalid string argument".to_string());
+            return Err(HostError::Type("Invalid string argument".to_string()));
         }
     } else if let Some(n) = val.to_i32() {
         // Numbers can be parsed as JSON
         return Ok(Value::int(n));
     } else {
-        return Err("JSON.parse requires a string argument".to_string());
+        return Err(HostError::Type(
+            "JSON.parse requires a string argument".to_string(),
+        ));
     };
 
-    // Parse the JSON string
-    let mut parser = JsonParser::new(&json_str);
-    parser.parse_value(interp)
+    interp
+        .parse_json(&json_str, JsonOptions::default())
+        .map_err(|e| HostError::Type(e.message))
+}
+
+/// Extensions to strict JSON accepted by [`Interpreter::parse_json`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonOptions {
+    /// Skip `// line` and `/* block */` comments wherever whitespace may appear
+    pub allow_comments: bool,
+    /// Accept a comma after the last element of an array or object
+    pub allow_trailing_commas: bool,
+}
+
+/// Error from parsing or serializing JSON
+#[derive(Debug, Clone)]
+pub struct JsonError {
+    /// What went wrong
+    pub message: String,
+    /// Where parsing stopped, with 1-based line and column; None for
+    /// serialization errors
+    pub pos: Option<SourcePos>,
+}
+
+impl core::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.pos {
+            Some(pos) => write!(
+                f,
+                "{} at line {} column {}",
+                self.message, pos.line, pos.column
+            ),
+            None => f.write_str(&self.message),
+        }
+    }
 }
 
+impl core::error::Error for JsonError {}
+
 /// Simple JSON parser
 struct JsonParser<'a> {
     input: &'a str,
     pos: usize,
+    options: JsonOptions,
+    /// Arrays and objects currently open
+    depth: usize,
 }
 
 impl<'a> JsonParser<'a> {
-    fn new(input: &'a str) -> Self {
-        Self { input, pos: 0 }
+    fn new(input: &'a str, options: JsonOptions) -> Self {
+        Self {
+            input,
+            pos: 0,
+            options,
+            depth: 0,
+        }
+    }
+
+    /// Parse a whole document: one value with nothing but whitespace (and
+    /// comments, if allowed) after it
+    ///
+    /// On failure the error points at the character parsing stopped on.
+    fn parse_document(&mut self, interp: &mut Interpreter) -> Result<Value, JsonError> {
+        let result = self.parse_value(interp).and_then(|value| {
+            self.skip_whitespace()?;
+            if self.pos < self.input.len() {
+                return Err(format!(
+                    "Unexpected character '{}' after JSON value",
+                    self.peek_char()
+                ));
+            }
+            Ok(value)
+        });
+        result.map_err(|message| JsonError {
+            message,
+            pos: Some(self.source_pos()),
+        })
+    }
+
+    /// Line and column of the current position
+    fn source_pos(&self) -> SourcePos {
+        let before = &self.input[..self.pos];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        SourcePos {
+            offset: self.pos,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    /// Parse a nested array or object one level deeper, failing past the
+    /// compiler's nesting limit instead of exhausting the native stack
+    fn nested(
+        &mut self,
+        interp: &mut Interpreter,
+        parse: fn(&mut Self, &mut Interpreter) -> Result<Value, String>,
+    ) -> Result<Value, String> {
+        if self.depth >= DEFAULT_MAX_NESTING_DEPTH {
+            return Err("JSON nested too deeply".to_string());
+        }
+        self.depth += 1;
+        let result = parse(self, interp);
+        self.depth -= 1;
+        result
     }
 
     fn parse_value(&mut self, interp: &mut Interpreter) -> Result<Value, String> {
-        self.skip_whitespace();
+        self.skip_whitespace()?;
 
         if self.pos >= self.input.len() {
             return Err("Unexpected end of JSON input".to_string());
@@ -7036,8 +12482,8 @@ impl<'a> JsonParser<'a> {
         let c = self.peek_char();
         match c {
             '"' => self.parse_string(interp),
-            '[' => self.parse_array(interp),
-            '{' => self.parse_object(interp),
+            '[' => self.nested(interp, Self::parse_array),
+            '{' => self.nested(interp, Self::parse_object),
             't' | 'f' => self.parse_boolean(),
             'n' => self.parse_null(),
             '-' | '0'..='9' => self.parse_number(),
@@ -7057,20 +12503,73 @@ impl<'a> JsonParser<'a> {
         c
     }
 
-    fn skip_whitespace(&mut self) {
+    /// Consume the separator `expected`, leaving the position on whatever
+    /// character was found instead
+    fn expect_char(&mut self, expected: char, context: &str) -> Result<(), String> {
+        let c = self.peek_char();
+        if c != expected {
+            return Err(format!(
+                "Expected '{}' {}, found '{}'",
+                expected, context, c
+            ));
+        }
+        self.next_char();
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), String> {
         while self.pos < self.input.len() {
             match self.peek_char() {
                 ' ' | '\t' | '\n' | '\r' => {
                     self.next_char();
                 }
+                '/' if self.options.allow_comments => self.skip_comment()?,
                 _ => break,
             }
         }
+        Ok(())
+    }
+
+    /// Skip a `//` or `/* */` comment starting at the current position
+    fn skip_comment(&mut self) -> Result<(), String> {
+        let rest = &self.input[self.pos..];
+        if rest.starts_with("//") {
+            self.pos += rest.find('\n').unwrap_or(rest.len());
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            match body.find("*/") {
+                Some(end) => self.pos += end + 4,
+                None => return Err("Unterminated comment in JSON".to_string()),
+            }
+        } else {
+            return Err("Unexpected character '/' in JSON".to_string());
+        }
+        Ok(())
+    }
+
+    /// After a comma: whether a trailing comma closes the container here
+    fn at_trailing_close(&mut self, close: char) -> Result<bool, String> {
+        self.skip_whitespace()?;
+        if self.options.allow_trailing_commas && self.peek_char() == close {
+            self.next_char();
+            return Ok(true);
+        }
+        Ok(false)
     }
 
     fn parse_string(&mut self, interp: &mut Interpreter) -> Result<Value, String> {
+        let s = self.parse_string_content()?;
+        Ok(interp.alloc_string(&s))
+    }
+
+    /// Parse a quoted string, used for both values and object keys
+    ///
+    /// `\u` escapes are collected as UTF-16 code units, so an escaped
+    /// surrogate pair becomes one character and an unpaired surrogate
+    /// U+FFFD.
+    fn parse_string_content(&mut self) -> Result<String, String> {
         self.next_char(); // consume opening quote
         let mut result = String::new();
+        let mut escaped_units = Vec::new();
 
         loop {
             if self.pos >= self.input.len() {
@@ -7078,6 +12577,26 @@ impl<'a> JsonParser<'a> {
             }
 
             let c = self.next_char();
+            if c == '\\' && self.peek_char() == 'u' {
+                self.next_char();
+                // Parse unicode escape \uXXXX
+                let hex: String = (0..4)
+                    .filter_map(|_| {
+                        let c = self.next_char();
+                        if c.is_ascii_hexdigit() { Some(c) } else { None }
+                    })
+                    .collect();
+                if hex.len() == 4
+                    && let Ok(unit) = u16::from_str_radix(&hex, 16)
+                {
+                    escaped_units.push(unit);
+                }
+                continue;
+            }
+            if !escaped_units.is_empty() {
+                result.push_str(&unicode::from_code_units(&escaped_units));
+                escaped_units.clear();
+            }
             match c {
                 '"' => break,
                 '\\' => {
@@ -7091,21 +12610,6 @@ impl<'a> JsonParser<'a> {
                         't' => result.push('\t'),
                         'b' => result.push('\x08'),
                         'f' => result.push('\x0C'),
-                        'u' => {
-                            // Parse unicode escape \uXXXX
-                            let hex: String = (0..4)
-                                .filter_map(|_| {
-                                    let c = self.next_char();
-                                    if c.is_ascii_hexdigit() { Some(c) } else { None }
-                                })
-                                .collect();
-                            if hex.len() == 4
-                                && let Ok(code) = u32::from_str_radix(&hex, 16)
-                                && let Some(c) = char::from_u32(code)
-                            {
-                                result.push(c);
-                            }
-                        }
                         _ => result.push(escaped),
                     }
                 }
@@ -7113,9 +12617,8 @@ impl<'a> JsonParser<'a> {
             }
         }
 
-        Ok(interp.create_runtime_string(result))
+        Ok(result)
     }
-
     fn parse_number(&mut self) -> Result<Value, String> {
         let start = self.pos;
 
@@ -7156,7 +12659,7 @@ impl<'a> JsonParser<'a> {
             Ok(Value::int(n))
         } else if let Ok(f) = num_str.parse::<f64>() {
             // Truncate to integer
-            Ok(Value::int(f as i32))
+            Ok(Value::int_saturating(f as i64))
         } else {
             Err(format!("Invalid number in JSON: {}", num_str))
         }
@@ -7185,167 +12688,553 @@ impl<'a> JsonParser<'a> {
 
     fn parse_array(&mut self, interp: &mut Interpreter) -> Result<Value, String> {
         self.next_char(); // consume '['
-        self.skip_whitespace();
+        self.skip_whitespace()?;
 
         let mut items: Vec<Value> = Vec::new();
 
         // Empty array
         if self.peek_char() == ']' {
             self.next_char();
-            let arr_idx = interp.arrays.len() as u32;
-            interp.arrays.push(items);
-            return Ok(Value::array_idx(arr_idx));
+            return Ok(interp.alloc_array(items));
         }
 
         loop {
             let value = self.parse_value(interp)?;
             items.push(value);
 
-            self.skip_whitespace();
-            let c = self.next_char();
-
-            match c {
-                ',' => {
-                    self.skip_whitespace();
-                }
-                ']' => break,
-                _ => return Err(format!("Expected ',' or ']' in array, found '{}'", c)),
+            self.skip_whitespace()?;
+            if self.peek_char() == ']' {
+                self.next_char();
+                break;
+            }
+            self.expect_char(',', "or ']' in array")?;
+            if self.at_trailing_close(']')? {
+                break;
             }
         }
 
-        let arr_idx = interp.arrays.len() as u32;
-        interp.arrays.push(items);
-        Ok(Value::array_idx(arr_idx))
+        Ok(interp.alloc_array(items))
     }
 
     fn parse_object(&mut self, interp: &mut Interpreter) -> Result<Value, String> {
         self.next_char(); // consume '{'
-        self.skip_whitespace();
+        self.skip_whitespace()?;
 
         let mut props: Vec<(String, Value)> = Vec::new();
 
         // Empty object
         if self.peek_char() == '}' {
             self.next_char();
-            let obj_idx = interp.objects.len() as u32;
-            let obj = ObjectInstance {
-                constructor: None,
-                properties: props,
-            };
-            interp.objects.push(obj);
-            return Ok(Value::object_idx(obj_idx));
+            return Ok(interp.alloc_object().finish());
         }
 
         loop {
-            self.skip_whitespace();
+            self.skip_whitespace()?;
 
             // Parse key (must be a string)
             if self.peek_char() != '"' {
                 return Err("Expected string key in object".to_string());
             }
+            let key = self.parse_string_content()?;
 
-            // Parse the key string directly
-            self.next_char(); // consume opening quote
-            let mut key = String::new();
-            loop {
-                if self.pos >= self.input.len() {
-                    return Err("Unterminated string key in JSON".to_string());
-                }
-                let c = self.next_char();
-                match c {
-                    '"' => break,
-                    '\\' => {
-                        let escaped = self.next_char();
-                        match escaped {
-                            '"' => key.push('"'),
-                            '\\' => key.push('\\'),
-                            'n' => key.push('\n'),
-                            _ => key.push(escaped),
-                        }
-                    }
-                    _ => key.push(c),
-                }
-            }
+            self.skip_whitespace()?;
+            self.expect_char(':', "after key in object")?;
 
-            self.skip_whitespace();
+            // Parse value
+            let value = self.parse_value(interp)?;
+            props.push((key, value));
 
-            // Expect colon
-            if self.next_char() != ':' {
-                return Err("Expected ':' after key in object".to_string());
+            self.skip_whitespace()?;
+            if self.peek_char() == '}' {
+                self.next_char();
+                break;
             }
+            self.expect_char(',', "or '}' in object")?;
+            if self.at_trailing_close('}')? {
+                break;
+            }
+        }
+
+        // Later duplicate keys overwrite earlier ones
+        let builder = props
+            .into_iter()
+            .fold(interp.alloc_object(), |b, (key, value)| b.prop(&key, value));
+        Ok(builder.finish())
+    }
+}
 
-            self.skip_whitespace();
+// ===========================================
+// Reflect Functions
+// ===========================================
 
-            // Parse value
-            let value = self.parse_value(interp)?;
-            props.push((key, value));
+/// Target and property key of a Reflect call, which only accepts objects
+/// as its target
+fn reflect_target(
+    interp: &mut Interpreter,
+    args: &[Value],
+    method: &str,
+) -> Result<(Value, String), HostError> {
+    let target = args.first().copied().unwrap_or_default();
+    if !PropertyReceiver::of(target).is_object() {
+        return Err(HostError::Type(format!(
+            "Reflect.{} called on non-object",
+            method
+        )));
+    }
+    let key = args.get(1).copied().unwrap_or_default();
+    let key = interp.string_content(key, &mut HashSet::new())?;
+    Ok((target, key))
+}
+
+/// Reflect.has - whether the target has the property, as `key in target`
+fn native_reflect_has(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let (target, key) = reflect_target(interp, args, "has")?;
+    Ok(Value::bool(interp.has_property(
+        target,
+        &key,
+        PropertyLookup::Chain,
+    )))
+}
+
+/// Reflect.get - read the property, as `target[key]`
+fn native_reflect_get(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let (target, key) = reflect_target(interp, args, "get")?;
+    interp.get_property(target, &key).map_err(HostError::from)
+}
+
+/// Reflect.set - write the property, as `target[key] = value`
+///
+/// Returns false, changing nothing, where the assignment would be ignored:
+/// on builtin objects other than globalThis, on functions and on read-only
+/// properties.
+fn native_reflect_set(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let (target, key) = reflect_target(interp, args, "set")?;
+    let val = args.get(2).copied().unwrap_or_default();
+    let writable = match PropertyReceiver::of(target) {
+        PropertyReceiver::Builtin(idx) if idx != BUILTIN_GLOBAL_THIS => false,
+        PropertyReceiver::Function => false,
+        _ => interp
+            .own_property_attributes(target, &key)
+            .is_none_or(|attributes| attributes.writable),
+    };
+    if writable {
+        interp.promote(val);
+        interp.set_property(target, &key, val)?;
+    }
+    Ok(Value::bool(writable))
+}
+
+/// Reflect.ownKeys - every own key, enumerable or not, as
+/// `Object.getOwnPropertyNames`
+fn native_reflect_own_keys(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let target = args.first().copied().unwrap_or_default();
+    if !PropertyReceiver::of(target).is_object() {
+        return Err(HostError::Type(
+            "Reflect.ownKeys called on non-object".to_string(),
+        ));
+    }
+    native_object_get_own_property_names(interp, Value::undefined(), args)
+}
+
+// ===========================================
+// Date Functions
+// ===========================================
+
+/// Date.now - current time in epoch seconds
+fn native_date_now(
+    interp: &mut Interpreter,
+    _this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    let millis = interp.read_clock(|interp| interp.clock.now_ms())? as i64;
+    time_to_value(Some(millis))
+}
+
+/// Convert a time value to a number of epoch seconds (see [`date`])
+///
+/// Invalid Date gives NaN, which is 0 until floats exist. Valid times outside
+/// the integer range raise a RangeError rather than silently wrapping.
+fn time_to_value(time: Option<i64>) -> Result<Value, HostError> {
+    match time {
+        None => Ok(Value::int(0)),
+        Some(t) => {
+            let seconds = date::to_epoch_seconds(t);
+            Value::try_int(seconds).ok_or_else(|| {
+                HostError::Range(format!("time value {} does not fit in an integer", seconds))
+            })
+        }
+    }
+}
+
+/// Time value of a Date receiver
+fn this_date(interp: &Interpreter, this: Value) -> Result<Option<i64>, HostError> {
+    interp
+        .date_value(this)
+        .ok_or_else(|| HostError::Type("this is not a Date object".to_string()))
+}
+
+/// Date.parse - parse an ISO-8601 date string into epoch seconds
+fn native_date_parse(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let time = args
+        .first()
+        .and_then(|&v| interp.get_string(v))
+        .and_then(|s| date::parse_iso8601(&s));
+    time_to_value(time)
+}
+
+/// Date.prototype.getTime / valueOf - epoch seconds
+fn native_date_get_time(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    time_to_value(this_date(interp, this)?)
+}
+
+/// Read one UTC field of a Date receiver (NaN, i.e. 0, for Invalid Date)
+fn date_field(
+    interp: &Interpreter,
+    this: Value,
+    field: fn(&date::DateFields) -> i64,
+) -> Result<Value, HostError> {
+    let value = this_date(interp, this)?.map_or(0, |t| field(&date::fields(t)));
+    Ok(Value::int_saturating(value))
+}
+
+/// Date.prototype.getFullYear
+fn native_date_get_full_year(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    date_field(interp, this, |f| f.year)
+}
+
+/// Date.prototype.getMonth (0-based)
+fn native_date_get_month(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    date_field(interp, this, |f| f.month as i64)
+}
+
+/// Date.prototype.getDate - day of the month
+fn native_date_get_date(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    date_field(interp, this, |f| f.day as i64)
+}
+
+/// Date.prototype.getDay - day of the week, 0 = Sunday
+fn native_date_get_day(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    date_field(interp, this, |f| f.weekday as i64)
+}
+
+/// Date.prototype.getHours
+fn native_date_get_hours(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    date_field(interp, this, |f| f.hours as i64)
+}
+
+/// Date.prototype.getMinutes
+fn native_date_get_minutes(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    date_field(interp, this, |f| f.minutes as i64)
+}
+
+/// Date.prototype.getSeconds
+fn native_date_get_seconds(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    date_field(interp, this, |f| f.seconds as i64)
+}
+
+/// Date.prototype.getMilliseconds
+fn native_date_get_milliseconds(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    date_field(interp, this, |f| f.millis as i64)
+}
+
+/// Date.prototype.toISOString - "YYYY-MM-DDTHH:MM:SS.sssZ"
+fn native_date_to_iso_string(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    let time = this_date(interp, this)?
+        .ok_or_else(|| HostError::Range("Invalid time value".to_string()))?;
+    Ok(interp.alloc_string(&date::to_iso_string(time)))
+}
+
+/// Date.prototype.toJSON - ISO string, or null for Invalid Date
+fn native_date_to_json(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    match this_date(interp, this)? {
+        Some(time) => Ok(interp.alloc_string(&date::to_iso_string(time))),
+        None => Ok(Value::null()),
+    }
+}
+
+/// Date.prototype.toString - "Sat Jun 01 2024 12:30:00 GMT+0000", always UTC
+fn native_date_to_string(
+    interp: &mut Interpreter,
+    this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    let s =
+        this_date(interp, this)?.map_or_else(|| "Invalid Date".to_string(), date::to_date_string);
+    Ok(interp.alloc_string(&s))
+}
+
+/// performance.now - monotonic time since interpreter creation
+///
+/// Returns integer microseconds until floats exist, saturating at the
+/// integer maximum (about 35 minutes) so the clock never runs backwards.
+fn native_performance_now(
+    interp: &mut Interpreter,
+    _this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    Ok(micros_to_value(interp.performance_now()?))
+}
 
-            self.skip_whitespace();
-            let c = self.next_char();
+/// Convert a microsecond count to a saturated integer value
+fn micros_to_value(micros: i64) -> Value {
+    Value::int_saturating(micros)
+}
 
-            match c {
-                ',' => {
-                    self.skip_whitespace();
-                }
-                '}' => break,
-                _ => return Err(format!("Expected ',' or '}}' in object, found '{}'", c)),
-            }
-        }
+/// Materialize a performance entry as a JS object
+fn performance_entry_object(interp: &mut Interpreter, entry: &PerformanceEntry) -> Value {
+    interp
+        .alloc_object()
+        .prop_str("name", &entry.name)
+        .prop_str("entryType", entry.entry_type)
+        .prop("startTime", micros_to_value(entry.start_time))
+        .prop("duration", micros_to_value(entry.duration))
+        .finish()
+}
 
-        let obj_idx = interp.objects.len() as u32;
-        let obj = ObjectInstance {
-            constructor: None,
-            properties: props,
-        };
-        interp.objects.push(obj);
-        Ok(Value::object_idx(obj_idx))
-    }
+/// Start time of the most recent mark with the given name
+fn performance_mark_time(interp: &Interpreter, name: &str) -> Result<i64, HostError> {
+    interp
+        .performance_entries
+        .iter()
+        .rev()
+        .find(|e| e.entry_type == "mark" && e.name == name)
+        .map(|e| e.start_time)
+        .ok_or_else(|| HostError::Type(format!("performance mark '{}' does not exist", name)))
 }
 
-// ===========================================
-// Date Functions
-// ===========================================
+/// performance.mark(name) - record a named timestamp
+fn native_performance_mark(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let name = format_value(interp, args.first().copied().unwrap_or_default());
+    let entry = PerformanceEntry {
+        name,
+        entry_type: "mark",
+        start_time: interp.performance_now()?,
+        duration: 0,
+    };
+    record_performance_entry(interp, entry)
+}
 
-/// Date.now - returns current timestamp in milliseconds
-/// Note: Due to 31-bit integer limitation, we return milliseconds modulo 2^30
-/// This allows for relative timing within ~12 day windows
-fn native_date_now(
-    _interp: &mut Interpreter,
+/// performance.measure(name, startMark, endMark) - record the time between marks
+///
+/// A missing start mark measures from interpreter creation and a missing end
+/// mark measures up to now.
+fn native_performance_measure(
+    interp: &mut Interpreter,
     _this: Value,
-    _args: &[Value],
-) -> Result<Value, String> {
-    use std::time::{SystemTime, UNIX_EPOCH};
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let name = format_value(interp, args.first().copied().unwrap_or_default());
+    let mark_arg = |i: usize| args.get(i).filter(|v| !v.is_undefined()).copied();
+
+    let start = match mark_arg(1) {
+        Some(v) => performance_mark_time(interp, &format_value(interp, v))?,
+        None => 0,
+    };
+    let end = match mark_arg(2) {
+        Some(v) => performance_mark_time(interp, &format_value(interp, v))?,
+        None => interp.performance_now()?,
+    };
+
+    let entry = PerformanceEntry {
+        name,
+        entry_type: "measure",
+        start_time: start,
+        duration: end - start,
+    };
+    record_performance_entry(interp, entry)
+}
 
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| format!("Time error: {}", e))?;
+/// Store a new mark or measure, charged to the memory budget, and return
+/// it as a JS object
+fn record_performance_entry(
+    interp: &mut Interpreter,
+    entry: PerformanceEntry,
+) -> Result<Value, HostError> {
+    let bytes = performance_entry_bytes(&entry);
+    interp.check_memory(bytes)?;
+    let obj = performance_entry_object(interp, &entry);
+    interp.performance_entries.push(entry);
+    interp.charge_memory(bytes);
+    Ok(obj)
+}
 
-    // Return milliseconds modulo 2^30 (about 12.4 days worth)
-    // This fits in 31-bit signed range and allows relative timing
-    let millis = now.as_millis() as i64;
-    let max_val = 1 << 30; // 2^30 = 1073741824
+/// Drop the recorded entries of one type, only those named by the first
+/// argument if it is given
+fn clear_performance_entries(interp: &mut Interpreter, args: &[Value], entry_type: &str) {
+    let name = args
+        .first()
+        .filter(|v| !v.is_undefined())
+        .map(|v| format_value(interp, *v));
+    let mut freed = 0;
+    interp.performance_entries.retain(|e| {
+        let clear = e.entry_type == entry_type && name.as_ref().is_none_or(|n| *n == e.name);
+        if clear {
+            freed += performance_entry_bytes(e);
+        }
+        !clear
+    });
+    interp.heap_charged = interp.heap_charged.saturating_sub(freed);
+}
 
-    Ok(Value::int((millis % max_val) as i32))
+/// performance.clearMarks(name) - forget the marks with the given name, or
+/// all marks
+fn native_performance_clear_marks(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    clear_performance_entries(interp, args, "mark");
+    Ok(Value::undefined())
 }
 
-/// performance.now - high-resolution time in milliseconds
-fn native_performance_now(
-    _interp: &mut Interpreter,
+/// performance.clearMeasures(name) - forget the measures with the given
+/// name, or all measures
+fn native_performance_clear_measures(
+    interp: &mut Interpreter,
     _this: Value,
-    _args: &[Value],
-) -> Result<Value, String> {
-    use std::time::{SystemTime, UNIX_EPOCH};
+    args: &[Value],
+) -> Result<Value, HostError> {
+    clear_performance_entries(interp, args, "measure");
+    Ok(Value::undefined())
+}
 
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| format!("Time error: {}", e))?;
+/// performance.getEntriesByName(name, type) - recorded entries in insertion order
+fn native_performance_get_entries_by_name(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let name = format_value(interp, args.first().copied().unwrap_or_default());
+    let entry_type = args
+        .get(1)
+        .filter(|v| !v.is_undefined())
+        .map(|v| format_value(interp, *v));
+
+    let entries: Vec<PerformanceEntry> = interp
+        .performance_entries
+        .iter()
+        .filter(|e| e.name == name && entry_type.as_ref().is_none_or(|t| t == e.entry_type))
+        .cloned()
+        .collect();
+    let items = entries
+        .iter()
+        .map(|e| performance_entry_object(interp, e))
+        .collect();
+    Ok(interp.alloc_array(items))
+}
 
-    // Return milliseconds modulo 2^30 (about 12.4 days worth)
-    // This fits in 31-bit signed range and allows relative timing
-    let millis = now.as_millis() as i64;
-    let max_val = 1 << 30; // 2^30 = 1073741824
+// ===========================================
+// Promise Methods
+// ===========================================
 
-    Ok(Value::int((millis % max_val) as i32))
+/// Promise.prototype.then - run a handler once the promise settles
+///
+/// Returns a new promise settled with what the handler returns, or
+/// rejected with what it throws. A missing handler passes the outcome on.
+fn native_promise_then(
+    interp: &mut Interpreter,
+    this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let idx = interp
+        .promise_idx(this)
+        .ok_or_else(|| incompatible_receiver("Promise.prototype.then", this))?;
+    let handler = |i: usize| {
+        args.get(i)
+            .copied()
+            .filter(|&h| interp.is_callable(h))
+            .unwrap_or_default()
+    };
+    let (on_fulfilled, on_rejected) = (handler(0), handler(1));
+    let derived = interp.create_promise();
+    interp.add_promise_reaction(
+        idx,
+        PromiseReaction {
+            on_fulfilled,
+            on_rejected,
+            derived,
+        },
+    );
+    Ok(derived)
+}
+
+/// Promise.prototype.catch - same as then(undefined, onRejected)
+fn native_promise_catch(
+    interp: &mut Interpreter,
+    this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let on_rejected = args.first().copied().unwrap_or_default();
+    native_promise_then(interp, this, &[Value::undefined(), on_rejected])
+        .map_err(|_| incompatible_receiver("Promise.prototype.catch", this))
 }
 
 // ===========================================
@@ -7357,10 +13246,10 @@ fn native_regexp_test(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let regex_idx = this
         .to_regexp_object_idx()
-        .ok_or_else(|| "test called on non-RegExp".to_string())?;
+        .ok_or_else(|| incompatible_receiver("RegExp.prototype.test", this))?;
 
     let re = interp
         .regex_objects
@@ -7370,9 +13259,9 @@ fn native_regexp_test(
 
     // Get string to test
     let test_str = if let Some(str_val) = args.first() {
-        if let Some(str_idx) = str_val.to_string_idx() {
+        if str_val.is_string() {
             interp
-                .get_string_by_idx(str_idx)
+                .get_string(*str_val)
                 .ok_or_else(|| "invalid string".to_string())?
                 .to_string()
         } else if let Some(n) = str_val.to_i32() {
@@ -7384,7 +13273,7 @@ fn native_regexp_test(
         "undefined".to_string()
     };
 
-    Ok(Value::bool(re.regex.is_match(&test_str)))
+    Ok(Value::bool(re.regex.test(&test_str)))
 }
 
 /// RegExp.prototype.exec - executes the regex and returns match result
@@ -7392,10 +13281,10 @@ fn native_regexp_exec(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let regex_idx = this
         .to_regexp_object_idx()
-        .ok_or_else(|| "exec called on non-RegExp".to_string())?;
+        .ok_or_else(|| incompatible_receiver("RegExp.prototype.exec", this))?;
 
     let re = interp
         .regex_objects
@@ -7405,9 +13294,9 @@ fn native_regexp_exec(
 
     // Get string to match
     let match_str = if let Some(str_val) = args.first() {
-        if let Some(str_idx) = str_val.to_string_idx() {
+        if str_val.is_string() {
             interp
-                .get_string_by_idx(str_idx)
+                .get_string(*str_val)
                 .ok_or_else(|| "invalid string".to_string())?
                 .to_string()
         } else if let Some(n) = str_val.to_i32() {
@@ -7419,81 +13308,42 @@ fn native_regexp_exec(
         "undefined".to_string()
     };
 
-    // Find the match
-    if let Some(m) = re.regex.find(&match_str) {
-        // Create result array with matched string
-        let matched = m.as_str().to_string();
-        let str_idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
-        interp.runtime_strings.push(matched);
-
-        let arr_idx = interp.arrays.len() as u32;
-        interp.arrays.push(vec![Value::string(str_idx)]);
-
-        // Create result object with index and input properties
-        let _result_obj_idx = interp.objects.len() as u32;
-        interp.objects.push(crate::vm::interpreter::ObjectInstance {
-            constructor: None,
-            properties: vec![("index".to_string(), Value::int(m.start() as i32))],
-        });
-
-        // For now, just return the array (input property would require more work)
-        Ok(Value::array_idx(arr_idx))
-    } else {
-        Ok(Value::null())
-    }
+    Ok(regexp_exec_result(interp, &re.regex, &match_str))
 }
 
 // ===========================================
 // Object Static Methods
 // ===========================================
 
-/// Object.keys - returns array of object's own property names
+/// Object.keys - returns array of object's own enumerable property names
 fn native_object_keys(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let obj = args.first().copied().unwrap_or_default();
+    let keys: Vec<Value> = interp
+        .property_keys(obj, PropertyLookup::OwnEnumerable)
+        .into_iter()
+        .map(|k| interp.alloc_string(&k))
+        .collect();
+    Ok(interp.alloc_temp_array(keys))
+}
 
-    if let Some(obj_idx) = obj.to_object_idx() {
-        // Clone keys first to avoid borrow issues
-        let key_strings: Vec<String> = interp
-            .objects
-            .get(obj_idx as usize)
-            .map(|obj| obj.properties.iter().map(|(k, _)| k.clone()).collect())
-            .unwrap_or_default();
-
-        // Now create string values
-        let keys: Vec<Value> = key_strings
-            .into_iter()
-            .map(|k| interp.create_runtime_string(k))
-            .collect();
-
-        let arr_idx = interp.arrays.len() as u32;
-        interp.arrays.push(keys);
-        return Ok(Value::array_idx(arr_idx));
-    } else if let Some(arr_idx) = obj.to_array_idx() {
-        // For arrays, get length first
-        let len = interp
-            .arrays
-            .get(arr_idx as usize)
-            .map(|a| a.len())
-            .unwrap_or(0);
-
-        // Create index strings
-        let keys: Vec<Value> = (0..len)
-            .map(|i| interp.create_runtime_string(i.to_string()))
-            .collect();
-
-        let new_arr_idx = interp.arrays.len() as u32;
-        interp.arrays.push(keys);
-        return Ok(Value::array_idx(new_arr_idx));
-    }
-
-    // Return empty array for non-objects
-    let arr_idx = interp.arrays.len() as u32;
-    interp.arrays.push(Vec::new());
-    Ok(Value::array_idx(arr_idx))
+/// Object.getOwnPropertyNames - returns array of all own property names,
+/// including non-enumerable ones
+fn native_object_get_own_property_names(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let obj = args.first().copied().unwrap_or_default();
+    let keys: Vec<Value> = interp
+        .property_keys(obj, PropertyLookup::Own)
+        .into_iter()
+        .map(|k| interp.alloc_string(&k))
+        .collect();
+    Ok(interp.alloc_temp_array(keys))
 }
 
 /// Object.values - returns array of object's own property values
@@ -7501,7 +13351,7 @@ fn native_object_values(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let obj = args.first().copied().unwrap_or_default();
 
     if let Some(obj_idx) = obj.to_object_idx() {
@@ -7509,12 +13359,16 @@ fn native_object_values(
         let values: Vec<Value> = interp
             .objects
             .get(obj_idx as usize)
-            .map(|obj| obj.properties.iter().map(|(_, v)| *v).collect())
+            .map(|obj| {
+                obj.properties
+                    .iter()
+                    .filter(|(k, _)| obj.is_enumerable(*k))
+                    .map(|(_, v)| *v)
+                    .collect()
+            })
             .unwrap_or_default();
 
-        let arr_idx = interp.arrays.len() as u32;
-        interp.arrays.push(values);
-        return Ok(Value::array_idx(arr_idx));
+        return Ok(interp.alloc_temp_array(values));
     } else if let Some(arr_idx) = obj.to_array_idx() {
         // For arrays, return a copy of values
         let arr_copy = interp
@@ -7522,15 +13376,11 @@ fn native_object_values(
             .get(arr_idx as usize)
             .cloned()
             .unwrap_or_default();
-        let new_arr_idx = interp.arrays.len() as u32;
-        interp.arrays.push(arr_copy);
-        return Ok(Value::array_idx(new_arr_idx));
+        return Ok(interp.alloc_derived_array(arr_copy));
     }
 
     // Return empty array for non-objects
-    let arr_idx = interp.arrays.len() as u32;
-    interp.arrays.push(Vec::new());
-    Ok(Value::array_idx(arr_idx))
+    Ok(interp.alloc_temp_array(Vec::new()))
 }
 
 /// Object.entries - returns array of [key, value] pairs
@@ -7538,37 +13388,38 @@ fn native_object_entries(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let obj = args.first().copied().unwrap_or_default();
 
     if let Some(obj_idx) = obj.to_object_idx() {
         // Clone properties to avoid borrow issues
-        let props: Vec<(String, Value)> = interp
+        let props: Vec<(Atom, Value)> = interp
             .objects
             .get(obj_idx as usize)
-            .map(|obj| obj.properties.clone())
+            .map(|obj| {
+                obj.properties
+                    .iter()
+                    .filter(|(k, _)| obj.is_enumerable(*k))
+                    .copied()
+                    .collect()
+            })
             .unwrap_or_default();
 
         // Create array of [key, value] pairs
         let mut entries: Vec<Value> = Vec::new();
 
         for (k, v) in props {
-            let key_val = interp.create_runtime_string(k);
+            let key = interp.atoms.shared_name(k);
+            let key_val = interp.alloc_string(&key);
             // Create inner array [key, value]
-            let pair_idx = interp.arrays.len() as u32;
-            interp.arrays.push(vec![key_val, v]);
-            entries.push(Value::array_idx(pair_idx));
+            entries.push(interp.alloc_array(vec![key_val, v]));
         }
 
-        let arr_idx = interp.arrays.len() as u32;
-        interp.arrays.push(entries);
-        return Ok(Value::array_idx(arr_idx));
+        return Ok(interp.alloc_temp_array(entries));
     }
 
     // Return empty array for non-objects
-    let arr_idx = interp.arrays.len() as u32;
-    interp.arrays.push(Vec::new());
-    Ok(Value::array_idx(arr_idx))
+    Ok(interp.alloc_temp_array(Vec::new()))
 }
 
 /// Object.prototype.hasOwnProperty - check if object has own property
@@ -7576,59 +13427,112 @@ fn native_object_has_own_property(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     // Get the property name to check
     let prop_name = args
         .first()
-        .and_then(|v| v.to_string_idx())
-        .and_then(|idx| interp.get_string_by_idx(idx).map(|s| s.to_string()));
+        .and_then(|&v| interp.get_string(v).map(|s| s.to_string()));
 
     let prop_name = match prop_name {
         Some(s) => s,
         None => return Ok(Value::bool(false)),
     };
 
-    // Check if 'this' is an object and has the property
-    if let Some(obj_idx) = this.to_object_idx() {
-        if let Some(obj) = interp.get_object(obj_idx) {
-            for (k, _) in obj.properties.iter() {
-                if k == &prop_name {
-                    return Ok(Value::bool(true));
-                }
-            }
-        }
-        return Ok(Value::bool(false));
+    Ok(Value::bool(interp.has_property(
+        this,
+        &prop_name,
+        PropertyLookup::Own,
+    )))
+}
+
+/// Object.prototype.propertyIsEnumerable - whether the key is an own
+/// property that enumeration visits
+fn native_object_property_is_enumerable(
+    interp: &mut Interpreter,
+    this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let key = args.first().copied().unwrap_or_default();
+    let key = interp.string_content(key, &mut HashSet::new())?;
+    Ok(Value::bool(interp.has_property(
+        this,
+        &key,
+        PropertyLookup::OwnEnumerable,
+    )))
+}
+
+/// Object.getOwnPropertyDescriptor - describe an own property as a fresh
+/// `{ value, writable, enumerable, configurable }` object, or undefined
+fn native_object_get_own_property_descriptor(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let obj = args.first().copied().unwrap_or_default();
+    if obj.is_null() || obj.is_undefined() {
+        return Err(HostError::Type(
+            "Cannot convert undefined or null to object".to_string(),
+        ));
     }
+    let key = args.get(1).copied().unwrap_or_default();
+    let key = interp.string_content(key, &mut HashSet::new())?;
+    property_descriptor(interp, obj, &key)
+}
 
-    // Check if 'this' is an array
-    if let Some(arr_idx) = this.to_array_idx() {
-        if let Some(arr) = interp.arrays.get(arr_idx as usize) {
-            // Check numeric indices
-            if let Ok(idx) = prop_name.parse::<usize>() {
-                return Ok(Value::bool(idx < arr.len()));
-            }
-            // Arrays also have 'length'
-            if prop_name == "length" {
-                return Ok(Value::bool(true));
-            }
-        }
-        return Ok(Value::bool(false));
+/// Object.getOwnPropertyDescriptors - an object mapping every own key to
+/// its descriptor
+fn native_object_get_own_property_descriptors(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let obj = args.first().copied().unwrap_or_default();
+    if obj.is_null() || obj.is_undefined() {
+        return Err(HostError::Type(
+            "Cannot convert undefined or null to object".to_string(),
+        ));
+    }
+    let result = interp.alloc_object().finish();
+    for key in interp.property_keys(obj, PropertyLookup::Own) {
+        let descriptor = property_descriptor(interp, obj, &key)?;
+        interp.object_set(result, &key, descriptor);
     }
+    Ok(result)
+}
 
-    Ok(Value::bool(false))
+/// Descriptor object for the own property `key` of `obj`, or undefined
+fn property_descriptor(
+    interp: &mut Interpreter,
+    obj: Value,
+    key: &str,
+) -> Result<Value, HostError> {
+    let Some(attributes) = interp.own_property_attributes(obj, key) else {
+        return Ok(Value::undefined());
+    };
+    let value = interp.get_property(obj, key)?;
+    Ok(interp
+        .alloc_object()
+        .prop("value", value)
+        .prop("writable", Value::bool(attributes.writable))
+        .prop("enumerable", Value::bool(attributes.enumerable))
+        .prop("configurable", Value::bool(attributes.configurable))
+        .finish())
 }
 
 /// Object.getPrototypeOf - get the prototype of an object
 fn native_object_get_prototype_of(
-    _interp: &mut Interpreter,
+    interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let obj = args.first().copied().unwrap_or_default();
 
-    // For our simple implementation, most objects don't have explicit prototypes
-    // Arrays inherit from Array.prototype, objects from Object.prototype
-    if obj.is_array() {
+    // Objects linked by Object.create or a constructor's prototype report
+    // it; otherwise arrays inherit from Array.prototype, objects from
+    // Object.prototype
+    if let Some(proto) = interp.prototype_of(obj) {
+        Ok(proto)
+    } else if obj.is_array() {
         // Return Array.prototype (represented as builtin)
         Ok(Value::builtin_object(BUILTIN_ARRAY))
     } else if obj.to_object_idx().is_some() {
@@ -7650,7 +13554,7 @@ fn native_object_set_prototype_of(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     // In our simple implementation, we don't support changing prototypes
     // Just return the object as-is (like a no-op)
     let obj = args.first().copied().unwrap_or_default();
@@ -7662,18 +13566,40 @@ fn native_object_create(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let _proto = args.first().copied().unwrap_or(Value::null());
-
-    // Create a new empty object
-    // In our simple implementation, we don't actually link the prototype
-    let obj_idx = interp.objects.len() as u32;
-    interp.objects.push(ObjectInstance {
-        constructor: None,
-        properties: Vec::new(),
-    });
+) -> Result<Value, HostError> {
+    let proto = args.first().copied().unwrap_or(Value::null());
+    let obj = interp.alloc_object().finish();
+    // Properties are looked up on object and builtin prototypes; any other
+    // object still counts for instanceof
+    if PropertyReceiver::of(proto).is_object()
+        && let Some(created) = obj
+            .to_object_idx()
+            .and_then(|idx| interp.get_object_mut(idx))
+    {
+        created.proto = Some(proto);
+    }
+    Ok(obj)
+}
 
-    Ok(Value::object_idx(obj_idx))
+/// Object.assign - copy the own enumerable properties of each source onto
+/// the target, later sources overriding earlier ones, and return the target
+///
+/// Null and undefined sources are skipped.
+fn native_object_assign(
+    interp: &mut Interpreter,
+    _this: Value,
+    args: &[Value],
+) -> Result<Value, HostError> {
+    let target = args.first().copied().unwrap_or_default();
+    if target.is_null() || target.is_undefined() {
+        return Err(HostError::Type(
+            "Object.assign called on null or undefined".to_string(),
+        ));
+    }
+    for &source in args.iter().skip(1) {
+        interp.copy_data_properties(target, source, &[])?;
+    }
+    Ok(target)
 }
 
 /// Object.defineProperty - define a property on an object
@@ -7681,14 +13607,14 @@ fn native_object_define_property(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let obj = args.first().copied().unwrap_or_default();
     let prop = args.get(1).copied().unwrap_or_default();
     let descriptor = args.get(2).copied().unwrap_or_default();
 
     // Get property name
-    let prop_name = if let Some(str_idx) = prop.to_string_idx() {
-        interp.get_string_by_idx(str_idx).map(|s| s.to_string())
+    let prop_name = if prop.is_string() {
+        interp.get_string(prop).map(|s| s.to_string())
     } else {
         prop.to_i32().map(|n| n.to_string())
     };
@@ -7698,26 +13624,42 @@ fn native_object_define_property(
         None => return Ok(obj),
     };
 
-    // Get value from descriptor
-    let value = if let Some(desc_idx) = descriptor.to_object_idx() {
-        // Look for 'value' property in descriptor
-        if let Some(desc_obj) = interp.objects.get(desc_idx as usize) {
-            desc_obj
-                .properties
-                .iter()
-                .find(|(k, _)| k == "value")
-                .map(|(_, v)| *v)
-                .unwrap_or_default()
-        } else {
-            Value::undefined()
-        }
-    } else {
-        Value::undefined()
+    if interp.hardened {
+        interp.check_hardened_access(obj, &prop_name, true)?;
+    }
+    let Some(obj_idx) = obj.to_object_idx() else {
+        return Ok(obj);
     };
-
-    // Set the property on the object
-    if let Some(obj_idx) = obj.to_object_idx() {
-        interp.object_set_property(obj_idx, prop_name, value);
+    let field = |interp: &Interpreter, key: &str| {
+        descriptor
+            .to_object_idx()
+            .and_then(|desc_idx| interp.get_object(desc_idx))
+            .and_then(|desc| interp.named_get(desc, key))
+    };
+    let value = field(interp, "value");
+    let enumerable = field(interp, "enumerable").map(|v| interp.to_boolean(v));
+
+    // Attributes missing from the descriptor keep their current setting on an
+    // existing property and default to false on a new one
+    let prop_name = interp.atom(&prop_name);
+    let exists = interp
+        .get_object(obj_idx)
+        .is_some_and(|o| o.has_own(prop_name));
+    if value.is_some() || !exists {
+        interp.object_set_property(obj_idx, prop_name, value.unwrap_or_default());
+    }
+    let enumerable = enumerable.unwrap_or(
+        exists && {
+            interp
+                .get_object(obj_idx)
+                .is_some_and(|o| o.is_enumerable(prop_name))
+        },
+    );
+    if let Some(o) = interp.get_object_mut(obj_idx) {
+        o.non_enumerable.retain(|k| *k != prop_name);
+        if !enumerable {
+            o.non_enumerable.push(prop_name);
+        }
     }
 
     Ok(obj)
@@ -7732,7 +13674,7 @@ fn native_array_is_array(
     _interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let val = args.first().copied().unwrap_or_default();
     Ok(Value::bool(val.is_array()))
 }
@@ -7747,10 +13689,10 @@ fn native_function_call(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     // 'this' is the function to call
-    if !this.is_closure() && this.to_func_ptr().is_none() {
-        return Err("call() called on non-function".to_string());
+    if !interp.is_callable(this) {
+        return Err(incompatible_receiver("Function.prototype.call", this));
     }
 
     // First argument is the new 'this' value
@@ -7761,7 +13703,7 @@ fn native_function_call(
 
     interp
         .call_value(this, new_this, &call_args)
-        .map_err(|e| e.to_string())
+        .map_err(HostError::from)
 }
 
 /// Function.prototype.apply - call function with specified this value and arguments array
@@ -7770,10 +13712,10 @@ fn native_function_apply(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     // 'this' is the function to call
-    if !this.is_closure() && this.to_func_ptr().is_none() {
-        return Err("apply() called on non-function".to_string());
+    if !interp.is_callable(this) {
+        return Err(incompatible_receiver("Function.prototype.apply", this));
     }
 
     // First argument is the new 'this' value
@@ -7782,11 +13724,13 @@ fn native_function_apply(
     // Second argument should be an array of arguments
     let call_args: Vec<Value> = if let Some(arr_val) = args.get(1) {
         if let Some(arr_idx) = arr_val.to_array_idx() {
-            interp.get_array(arr_idx).cloned().unwrap_or_default()
+            interp.array_values(arr_idx)?
         } else if arr_val.is_undefined() || arr_val.is_null() {
             Vec::new()
         } else {
-            return Err("second argument to apply() must be an array".to_string());
+            return Err(HostError::Type(
+                "second argument to apply() must be an array".to_string(),
+            ));
         }
     } else {
         Vec::new()
@@ -7794,7 +13738,7 @@ fn native_function_apply(
 
     interp
         .call_value(this, new_this, &call_args)
-        .map_err(|e| e.to_string())
+        .map_err(HostError::from)
 }
 
 /// Function.prototype.bind - create a new function with bound this value
@@ -7804,10 +13748,10 @@ fn native_function_bind(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     // 'this' is the function to bind
-    if !this.is_closure() && this.to_func_ptr().is_none() {
-        return Err("bind() called on non-function".to_string());
+    if !interp.is_callable(this) {
+        return Err(incompatible_receiver("Function.prototype.bind", this));
     }
 
     // Create a bound function object
@@ -7815,27 +13759,26 @@ fn native_function_bind(
     let bound_this = args.first().copied().unwrap_or_default();
     let bound_args: Vec<Value> = args.iter().skip(1).copied().collect();
 
-    // Create an object to store the bound function info
-    let obj_idx = interp.objects.len() as u32;
-    let mut obj = ObjectInstance::new();
-    obj.properties.push(("__bound_func__".to_string(), this));
-    obj.properties
-        .push(("__bound_this__".to_string(), bound_this));
+    // Bound functions are named after their target and expect only the
+    // arguments bind() did not supply
+    let (target_name, target_length) = interp.function_metadata(this).unwrap_or_default();
+    let name = interp.alloc_string(&format!("bound {}", target_name));
+    let length = Value::int_saturating(target_length.saturating_sub(bound_args.len()) as i64);
 
     // Store bound args in an array
-    let arr_idx = interp.arrays.len() as u32;
-    interp.arrays.push(bound_args);
-    obj.properties
-        .push(("__bound_args__".to_string(), Value::array_idx(arr_idx)));
-
-    // Mark as bound function
-    obj.properties
-        .push(("__is_bound__".to_string(), Value::bool(true)));
+    let bound_args = interp.alloc_array(bound_args);
 
-    interp.objects.push(obj);
-
-    // Return as object (will be callable via special handling)
-    Ok(Value::object_idx(obj_idx))
+    // Create an object to store the bound function info, marked as bound
+    // (callable via special handling)
+    Ok(interp
+        .alloc_object()
+        .prop("__bound_func__", this)
+        .prop("__bound_this__", bound_this)
+        .prop("__bound_args__", bound_args)
+        .prop("__is_bound__", Value::bool(true))
+        .prop("name", name)
+        .prop("length", length)
+        .finish())
 }
 
 /// Error.prototype.toString - returns "ErrorName: message"
@@ -7843,7 +13786,7 @@ fn native_error_to_string(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     if let Some(err_idx) = this.to_error_object_idx()
         && let Some(err) = interp.error_objects.get(err_idx as usize).cloned()
     {
@@ -7852,10 +13795,27 @@ fn native_error_to_string(
         } else {
             format!("{}: {}", err.name, err.message)
         };
-        return Ok(interp.create_runtime_string(result));
+        return Ok(interp.alloc_string(&result));
+    }
+    if !PropertyReceiver::of(this).is_object() {
+        return Ok(interp.alloc_string("Error"));
     }
-    // Fallback
-    Ok(interp.create_runtime_string("Error".to_string()))
+    // Any other object formats its own name and message the same way
+    let mut field = |key: &str, default: &str| -> Result<String, HostError> {
+        let val = interp.get_property(this, key)?;
+        if val.is_undefined() {
+            return Ok(default.to_string());
+        }
+        interp.string_content(val, &mut HashSet::new())
+    };
+    let name = field("name", "Error")?;
+    let message = field("message", "")?;
+    let result = match (name.is_empty(), message.is_empty()) {
+        (_, true) => name,
+        (true, false) => message,
+        (false, false) => format!("{}: {}", name, message),
+    };
+    Ok(interp.alloc_string(&result))
 }
 
 /// Function.prototype.toString - returns function source representation
@@ -7863,10 +13823,10 @@ fn native_function_to_string(
     interp: &mut Interpreter,
     _this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     // In a real implementation, this would return the function source
     // For our simple implementation, return a generic representation
-    Ok(interp.create_runtime_string("function () { [native code] }".to_string()))
+    Ok(interp.alloc_string("function () { [native code] }"))
 }
 
 /// Array.prototype.toString - same as join()
@@ -7874,15 +13834,14 @@ fn native_array_to_string(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
-    if let Some(arr_idx) = this.to_array_idx()
-        && let Some(arr) = interp.arrays.get(arr_idx as usize).cloned()
-    {
+) -> Result<Value, HostError> {
+    if let Some(arr_idx) = this.to_array_idx() {
+        let arr = interp.array_values(arr_idx)?;
         let parts: Vec<String> = arr.iter().map(|v| format_value(interp, *v)).collect();
         let result = parts.join(",");
-        return Ok(interp.create_runtime_string(result));
+        return Ok(interp.alloc_string(&result));
     }
-    Ok(interp.create_runtime_string(String::new()))
+    Ok(interp.alloc_string(""))
 }
 
 /// Array.prototype.reduceRight - reduce array from right to left
@@ -7890,98 +13849,94 @@ fn native_array_reduce_right(
     interp: &mut Interpreter,
     this: Value,
     args: &[Value],
-) -> Result<Value, String> {
-    let arr_idx = this
-        .to_array_idx()
-        .ok_or_else(|| "reduceRight called on non-array".to_string())?;
-
-    let callback = args
-        .first()
-        .copied()
-        .ok_or_else(|| "reduceRight requires a callback function".to_string())?;
-
-    if !callback.is_closure() && callback.to_func_ptr().is_none() {
-        return Err("reduceRight callback must be a function".to_string());
-    }
-
-    // Clone the array to avoid borrow issues
-    let arr_clone = interp
-        .arrays
-        .get(arr_idx as usize)
-        .ok_or_else(|| "invalid array".to_string())?
-        .clone();
-
-    if arr_clone.is_empty() && args.len() < 2 {
-        return Err("reduceRight of empty array with no initial value".to_string());
-    }
-
-    // Get initial value or last element
-    let len = arr_clone.len();
-    let (mut accumulator, end_idx) = if args.len() >= 2 {
-        (args[1], len)
-    } else {
-        (arr_clone[len - 1], len - 1)
+) -> Result<Value, HostError> {
+    let (_, entries) = require_array_entries(interp, this, "reduceRight")?;
+
+    let callback =
+        Args::new("Array.prototype.reduceRight", args).callable(0, NativeScope::of(interp))?;
+
+    // Get initial value or last element that is not a hole
+    let mut entries = entries.into_iter().rev();
+    let mut accumulator = match args.get(1) {
+        Some(&initial) => initial,
+        None => match entries.next() {
+            Some((_, last)) => last,
+            None => {
+                return Err(HostError::Type(
+                    "reduceRight of empty array with no initial value".to_string(),
+                ));
+            }
+        },
     };
 
     // Iterate from right to left
-    for i in (0..end_idx).rev() {
-        let element = arr_clone[i];
-        let call_args = vec![accumulator, element, Value::int(i as i32), this];
-        accumulator = interp
-            .call_value(callback, Value::undefined(), &call_args)
-            .map_err(|e| e.to_string())?;
+    for (i, element) in entries {
+        let call_args = vec![accumulator, element, Value::int_saturating(i as i64), this];
+        accumulator = interp.call_value(callback, Value::undefined(), &call_args)?;
     }
 
     Ok(accumulator)
 }
 
-/// Object.prototype.toString - returns "[object Type]" string representation
+/// Object.prototype.toString - returns the "[object Type]" brand of the receiver
 fn native_object_to_string(
     interp: &mut Interpreter,
     this: Value,
     _args: &[Value],
-) -> Result<Value, String> {
-    let type_str = if this.is_null() {
-        "[object Null]"
-    } else if this.is_undefined() {
-        "[object Undefined]"
-    } else if this.is_array() {
-        "[object Array]"
-    } else if this.to_object_idx().is_some() {
-        "[object Object]"
-    } else if this.is_error_object() {
-        "[object Error]"
-    } else if this.is_regexp_object() {
-        "[object RegExp]"
-    } else if this.to_string_idx().is_some() || this.is_string() {
-        "[object String]"
-    } else if this.to_i32().is_some() {
-        "[object Number]"
-    } else if this.to_bool().is_some() {
-        "[object Boolean]"
-    } else if this.is_closure() || this.to_native_func_idx().is_some() {
-        "[object Function]"
-    } else {
-        "[object Object]"
-    };
+) -> Result<Value, HostError> {
+    let tag = interp.brand(this);
+    Ok(interp.alloc_string(&format!("[object {}]", tag)))
+}
 
-    Ok(interp.create_runtime_string(type_str.to_string()))
+/// Placeholder entry for host class constructors and methods
+///
+/// `call_native_func` dispatches these to the class closures, so this only
+/// runs if that lookup is bypassed.
+fn native_host_class(
+    _interp: &mut Interpreter,
+    _this: Value,
+    _args: &[Value],
+) -> Result<Value, HostError> {
+    Err(HostError::Type(
+        "host class function called without its class".to_string(),
+    ))
 }
 
-/// gc() - trigger garbage collection (placeholder)
-fn native_gc(interp: &mut Interpreter, _this: Value, _args: &[Value]) -> Result<Value, String> {
-    // In a full implementation, this would trigger GC
-    // For now, just increment the count and return undefined
-    interp.gc_count += 1;
-    Ok(Value::undefined())
+/// gc() - collect garbage and return `{bytes_freed, collections, pause_us, live_objects}`
+///
+/// Skipped inside callbacks invoked from natives, whose in-flight
+/// arguments live on the Rust stack where the collector cannot see them;
+/// the result then reports nothing freed.
+fn native_gc(interp: &mut Interpreter, _this: Value, _args: &[Value]) -> Result<Value, HostError> {
+    let collected = interp.nested_call_target_depth.is_none();
+    if collected {
+        interp.collect_garbage();
+    }
+    let stats = interp.gc_stats;
+    let (bytes_freed, pause_us) = if collected {
+        (
+            stats.bytes_before.saturating_sub(stats.bytes_after),
+            stats.last_pause_us,
+        )
+    } else {
+        (0, 0)
+    };
+    let clamp = |n: u64| Value::int_saturating(n.min(i64::MAX as u64) as i64);
+    Ok(interp
+        .alloc_object()
+        .prop("bytes_freed", clamp(bytes_freed as u64))
+        .prop("collections", clamp(stats.collections as u64))
+        .prop("pause_us", clamp(pause_us))
+        .prop("live_objects", clamp(stats.live_objects as u64))
+        .finish())
 }
 
 /// load(filename) - load and execute a JavaScript file
-fn native_load(interp: &mut Interpreter, _this: Value, args: &[Value]) -> Result<Value, String> {
+#[cfg(feature = "std")]
+fn native_load(interp: &mut Interpreter, _this: Value, args: &[Value]) -> Result<Value, HostError> {
     let filename = args
         .first()
-        .and_then(|v| v.to_string_idx())
-        .and_then(|idx| interp.get_string_by_idx(idx).map(|s| s.to_string()))
+        .and_then(|&v| interp.get_string(v).map(|s| s.to_string()))
         .ok_or_else(|| "load requires a filename string".to_string())?;
 
     // Read the file
@@ -7989,47 +13944,19 @@ fn native_load(interp: &mut Interpreter, _this: Value, args: &[Value]) -> Result
         .map_err(|e| format!("cannot load '{}': {}", filename, e))?;
 
     // Compile the source
-    use crate::parser::compiler::Compiler;
-    use crate::runtime::CaptureInfo;
-
-    let compiled = Compiler::new(&contents)
+    let compiled = interp
+        .compiler(&contents)
         .compile()
         .map_err(|e| format!("compile error in '{}': {}", filename, e))?;
+    let script = interp.load_script(FunctionBytecode::from_compiled(compiled));
+    let bytecode = interp.script_bytecode(script)?;
 
-    // Convert to FunctionBytecode
-    fn to_bytecode(compiled: crate::parser::compiler::CompiledFunction) -> FunctionBytecode {
-        let inner_functions = compiled.functions.into_iter().map(to_bytecode).collect();
-
-        let captures = compiled
-            .captures
-            .into_iter()
-            .map(|c| CaptureInfo {
-                outer_index: c.outer_index,
-                is_local: c.is_local,
-            })
-            .collect();
-
-        FunctionBytecode {
-            name: None,
-            arg_count: compiled.arg_count as u16,
-            local_count: compiled.local_count as u16,
-            stack_size: 64,
-            has_arguments: false,
-            bytecode: compiled.bytecode,
-            constants: compiled.constants,
-            string_constants: compiled.string_constants,
-            source_file: None,
-            line_numbers: Vec::new(),
-            inner_functions,
-            captures,
-        }
-    }
-
-    let bytecode = to_bytecode(compiled);
-
-    interp
-        .execute(&bytecode)
-        .map_err(|e| format!("runtime error in '{}': {}", filename, e))
+    // Errors the file does not catch reach the script that loaded it
+    // SAFETY: boxed scripts are never moved, and only dropped below
+    let result = interp
+        .run_nested(|interp| interp.call_function(unsafe { &*bytecode }, Value::undefined(), &[]));
+    interp.unload_script_without_functions(script);
+    result.map_err(HostError::from)
 }
 
 /// setTimeout(callback, delay) - schedule callback after delay (returns timer ID)
@@ -8037,7 +13964,7 @@ fn native_set_timeout(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let callback = args
         .first()
         .copied()
@@ -8047,17 +13974,14 @@ fn native_set_timeout(
         && callback.to_func_ptr().is_none()
         && callback.to_native_func_idx().is_none()
     {
-        return Err("setTimeout callback must be a function".to_string());
+        return Err(HostError::Type(
+            "setTimeout callback must be a function".to_string(),
+        ));
     }
 
     let delay = args.get(1).and_then(|v| v.to_i32()).unwrap_or(0) as u64;
 
-    // Get current time
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_millis() as u64)
-        .unwrap_or(0);
+    let now = interp.clock.now_ms();
 
     let timer_id = interp.next_timer_id;
     interp.next_timer_id += 1;
@@ -8069,7 +13993,7 @@ fn native_set_timeout(
         cancelled: false,
     });
 
-    Ok(Value::int(timer_id as i32))
+    Ok(Value::int_saturating(timer_id as i64))
 }
 
 /// clearTimeout(id) - cancel a scheduled timeout
@@ -8077,7 +14001,7 @@ fn native_clear_timeout(
     interp: &mut Interpreter,
     _this: Value,
     args: &[Value],
-) -> Result<Value, String> {
+) -> Result<Value, HostError> {
     let timer_id = args
         .first()
         .and_then(|v| v.to_i32())
@@ -8349,4 +14273,402 @@ mod tests {
         let result = interp.call_function(&fb, Value::undefined(), &[]);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_math_rounding_semantics() {
+        // Half-way cases go toward +Infinity, not to even
+        assert_eq!(Rounding::Round.apply(2.5), 3.0);
+        assert_eq!(Rounding::Round.apply(-2.5), -2.0);
+        assert_eq!(Rounding::Round.apply(0.5), 1.0);
+        assert_eq!(Rounding::Round.apply(-0.5), 0.0);
+        assert!(Rounding::Round.apply(-0.5).is_sign_negative());
+        assert!(Rounding::Round.apply(-0.2).is_sign_negative());
+        assert_eq!(Rounding::Round.apply(1.4999), 1.0);
+        assert_eq!(Rounding::Floor.apply(-1.5), -2.0);
+        assert_eq!(Rounding::Ceil.apply(-1.5), -1.0);
+        assert!(Rounding::Ceil.apply(-0.5).is_sign_negative());
+        assert_eq!(Rounding::Trunc.apply(-1.7), -1.0);
+        assert!(Rounding::Floor.apply(f64::NAN).is_nan());
+
+        // Math.sign(-0) is -0; it only surfaces once -0 is representable
+        assert!(number_sign(-0.0).is_sign_negative());
+        assert!(number_sign(f64::NAN).is_nan());
+        assert_eq!(number_sign(-3.0), -1.0);
+    }
+
+    #[test]
+    fn test_inline_and_table_strings_agree() {
+        let mut interp = Interpreter::new();
+        // Force a string into the runtime table even if it would fit inline
+        let table_string = |interp: &mut Interpreter, s: &str| {
+            let idx = interp.runtime_strings.len() as u16 + Interpreter::RUNTIME_STRING_OFFSET;
+            interp.runtime_strings.push(s.to_string());
+            Value::string(idx)
+        };
+
+        let alphabet = ["a", "b", "0", "7", " ", "é"];
+        let mut seed: u32 = 12345;
+        let mut next = || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as usize
+        };
+        for _ in 0..500 {
+            let mut texts = Vec::new();
+            for _ in 0..2 {
+                let mut s = String::new();
+                while s.len() < 3 {
+                    let c = alphabet[next() % alphabet.len()];
+                    if s.len() + c.len() > 3 || next() % 4 == 0 {
+                        break;
+                    }
+                    s.push_str(c);
+                }
+                texts.push(s);
+            }
+            let (a, b) = (&texts[0], &texts[1]);
+
+            let inline_a = interp.create_runtime_string(a.clone());
+            let inline_b = interp.create_runtime_string(b.clone());
+            let table_a = table_string(&mut interp, a);
+            let table_b = table_string(&mut interp, b);
+            assert_eq!(inline_a.is_inline_string(), !a.is_empty());
+
+            assert_eq!(interp.get_string(inline_a), interp.get_string(table_a));
+            assert!(interp.strict_equals(inline_a, table_a));
+            assert_eq!(interp.strict_equals(inline_a, table_b), a == b);
+            assert_eq!(interp.strict_equals(table_a, inline_b), a == b);
+            assert_eq!(interp.to_boolean(inline_a), interp.to_boolean(table_a));
+            assert_eq!(interp.to_number(inline_a), interp.to_number(table_a));
+
+            let concat_inline = native_string_concat(&mut interp, inline_a, &[inline_b]).unwrap();
+            let concat_table = native_string_concat(&mut interp, table_a, &[table_b]).unwrap();
+            assert!(interp.strict_equals(concat_inline, concat_table));
+            assert_eq!(
+                interp.get_string(concat_inline).as_deref(),
+                Some(format!("{}{}", a, b).as_str())
+            );
+
+            let index_inline = native_string_index_of(&mut interp, inline_a, &[inline_b]).unwrap();
+            let index_table = native_string_index_of(&mut interp, table_a, &[table_b]).unwrap();
+            assert_eq!(index_inline, index_table);
+        }
+    }
+
+    #[test]
+    fn test_alloc_derived_array() {
+        let mut interp = Interpreter::new();
+        let temp = interp.alloc_temp_array(vec![Value::int(1)]);
+        let derived = interp.alloc_derived_array(vec![
+            temp,
+            Value::undefined(),
+            Value::iterator_idx(0),
+            Value::for_of_iterator_idx(0),
+        ]);
+
+        let elements = interp.get_array_elements(derived).unwrap();
+        assert_eq!(elements.len(), 4);
+        assert_eq!(elements[0], temp);
+        assert!(elements[1..].iter().all(|v| v.is_undefined()));
+        // The nested array is referenced from storage now, the result is not
+        let derived_idx = derived.to_array_idx().unwrap();
+        assert_eq!(interp.temp_arrays, vec![derived_idx]);
+    }
+
+    /// One value of every tag through every consumer that matches on tags;
+    /// a new tag fails here until its row documents how it behaves
+    #[test]
+    fn test_every_value_tag_through_every_consumer() {
+        let mut interp = Interpreter::new();
+        let script = interp
+            .eval_nested(
+                "function outer() { var n = 1; function inner() { return n; } return inner; }
+                 return [{ a: 1 }, [1, 2], outer(), Math.max, Math, new TypeError('t'),
+                   new RegExp('a+', 'g'), new Uint8Array(2), new ArrayBuffer(4), 'ab'];",
+            )
+            .unwrap();
+        let made = interp.get_array_elements(script).unwrap().to_vec();
+        let bytecode = make_bytecode(vec![OpCode::Return as u8]);
+
+        // (value, tag, typeof, ToBoolean, ToNumber, ToString, brand, formatted,
+        //  for-of count or not iterable, instanceof Object)
+        let rows = [
+            (
+                Value::undefined(),
+                ValueTag::Undefined,
+                "undefined",
+                false,
+                0,
+                "undefined",
+                "Undefined",
+                "undefined",
+                None,
+                false,
+            ),
+            (
+                Value::null(),
+                ValueTag::Null,
+                "object",
+                false,
+                0,
+                "null",
+                "Null",
+                "null",
+                None,
+                false,
+            ),
+            (
+                Value::bool(true),
+                ValueTag::Bool,
+                "boolean",
+                true,
+                1,
+                "true",
+                "Boolean",
+                "true",
+                None,
+                false,
+            ),
+            (
+                Value::int(7),
+                ValueTag::Int,
+                "number",
+                true,
+                7,
+                "7",
+                "Number",
+                "7",
+                None,
+                false,
+            ),
+            (
+                made[9],
+                ValueTag::String,
+                "string",
+                true,
+                0,
+                "ab",
+                "String",
+                "ab",
+                Some(2),
+                false,
+            ),
+            (
+                made[0],
+                ValueTag::Object,
+                "object",
+                true,
+                0,
+                "[object Object]",
+                "Object",
+                "[object Object]",
+                Some(1),
+                true,
+            ),
+            (
+                made[1],
+                ValueTag::Array,
+                "object",
+                true,
+                0,
+                "1,2",
+                "Array",
+                "[1, 2]",
+                Some(2),
+                true,
+            ),
+            (
+                made[2],
+                ValueTag::Closure,
+                "function",
+                true,
+                0,
+                "[object Function]",
+                "Function",
+                "[Function]",
+                None,
+                true,
+            ),
+            (
+                Value::func_ptr(&bytecode),
+                ValueTag::Bytecode,
+                "function",
+                true,
+                0,
+                "[object Function]",
+                "Function",
+                "[Function]",
+                None,
+                true,
+            ),
+            (
+                Value::func(0),
+                ValueTag::ShortFunc,
+                "function",
+                true,
+                0,
+                "[object Function]",
+                "Function",
+                "[Function]",
+                None,
+                true,
+            ),
+            (
+                made[3],
+                ValueTag::NativeFunc,
+                "function",
+                true,
+                0,
+                "[object Function]",
+                "Function",
+                "[Function]",
+                None,
+                true,
+            ),
+            (
+                made[4],
+                ValueTag::Builtin,
+                "object",
+                true,
+                0,
+                "[object Math]",
+                "Math",
+                "[object Math]",
+                None,
+                true,
+            ),
+            (
+                made[5],
+                ValueTag::Error,
+                "object",
+                true,
+                0,
+                "TypeError: t",
+                "Error",
+                "TypeError: t",
+                None,
+                true,
+            ),
+            (
+                made[6],
+                ValueTag::RegExp,
+                "object",
+                true,
+                0,
+                "/a+/g",
+                "RegExp",
+                "/a+/g",
+                None,
+                true,
+            ),
+            (
+                made[7],
+                ValueTag::TypedArray,
+                "object",
+                true,
+                0,
+                "0,0",
+                "Uint8Array",
+                "Uint8Array [0, 0]",
+                Some(2),
+                true,
+            ),
+            (
+                made[8],
+                ValueTag::ArrayBuffer,
+                "object",
+                true,
+                0,
+                "[object ArrayBuffer]",
+                "ArrayBuffer",
+                "[object ArrayBuffer]",
+                None,
+                true,
+            ),
+            (
+                Value::iterator_idx(0),
+                ValueTag::ForInIterator,
+                "object",
+                true,
+                0,
+                "[object Object]",
+                "Object",
+                "[object Object]",
+                None,
+                false,
+            ),
+            (
+                Value::for_of_iterator_idx(0),
+                ValueTag::ForOfIterator,
+                "object",
+                true,
+                0,
+                "[object Object]",
+                "Object",
+                "[object Object]",
+                None,
+                false,
+            ),
+            (
+                Value::exception(),
+                ValueTag::Exception,
+                "undefined",
+                false,
+                0,
+                "undefined",
+                "Undefined",
+                "[exception]",
+                None,
+                false,
+            ),
+            (
+                Value::uninitialized(),
+                ValueTag::Uninitialized,
+                "undefined",
+                false,
+                0,
+                "undefined",
+                "Undefined",
+                "[uninitialized]",
+                None,
+                false,
+            ),
+        ];
+        for tag in ValueTag::ALL {
+            assert!(rows.iter().any(|row| row.1 == tag), "no row for {:?}", tag);
+        }
+
+        let object_ctor = Value::builtin_object(BUILTIN_OBJECT);
+        for (value, tag, type_of, truthy, number, string, brand, formatted, iterated, instance) in
+            rows
+        {
+            assert_eq!(value.classify(), tag);
+            let type_of_result = interp.get_string(Value::string(interp.type_of(value)));
+            assert_eq!(type_of_result.as_deref(), Some(type_of), "typeof {:?}", tag);
+            assert_eq!(interp.to_boolean(value), truthy, "ToBoolean {:?}", tag);
+            assert_eq!(
+                interp.to_number(value).to_i32(),
+                Some(number),
+                "ToNumber {:?}",
+                tag
+            );
+            let text = interp.string_content(value, &mut HashSet::new()).unwrap();
+            assert_eq!(text, string, "ToString {:?}", tag);
+            assert_eq!(interp.brand(value), brand, "brand {:?}", tag);
+            assert_eq!(format_value(&interp, value), formatted, "format {:?}", tag);
+            let count = interp.for_of_iterator(value).ok().map(|it| it.values.len());
+            assert_eq!(count, iterated, "for-of {:?}", tag);
+            assert_eq!(
+                interp.instance_of(value, object_ctor),
+                instance,
+                "instanceof {:?}",
+                tag
+            );
+            assert_eq!(
+                PropertyReceiver::of(value).is_object(),
+                instance,
+                "property receiver {:?}",
+                tag
+            );
+        }
+    }
 }
@@ -2,12 +2,36 @@
 //!
 //! The VM executes JavaScript bytecode using a stack-based architecture.
 
+pub mod args;
+pub mod atom;
+pub mod class;
+pub mod clock;
+pub mod diff;
 pub mod interpreter;
+pub mod module;
+pub mod native;
 pub mod opcode;
+pub mod promise;
+pub mod replay;
+pub mod roots;
 pub mod stack;
 
+pub use args::Args;
+pub use atom::{Atom, AtomTable};
+pub use class::ClassDef;
+#[cfg(feature = "std")]
+pub use clock::SystemClock;
+pub use clock::{Clock, ManualClock};
+pub use diff::engine_module;
 pub use interpreter::{
-    CallFrame, Interpreter, InterpreterError, InterpreterResult, InterpreterStats,
+    CallFrame, ConsoleLevel, ConsoleSink, ExecutionReport, GcCallback, GcPhase, GcStats,
+    Interpreter, InterpreterError, InterpreterResult, InterpreterStats, JsonError, JsonOptions,
+    NativeFn, ObjectBuilder, PropertyLookup, Receiver, ReceiverKind, SlicedRun,
 };
+pub use module::ModuleBuilder;
+pub use native::{HostError, HostFn, NativeScope};
 pub use opcode::OpCode;
+pub use promise::ResolverHandle;
+pub use replay::{LoggedValue, ReplayEntry, ReplayFormatError, ReplayLog};
+pub use roots::PersistentValue;
 pub use stack::Stack;
@@ -0,0 +1,102 @@
+//! Host modules: namespace objects grouping native functions
+//!
+//! A [`ModuleBuilder`] lists the functions a module exposes. Eager entries
+//! are registered when the module is, lazy ones the first time a script
+//! reads them, so a large API surface costs nothing until it is used.
+//! Deprecated names forward to their replacement after a one-time warning.
+
+use super::interpreter::NativeFn;
+use super::native::{HostFn, NativeImpl};
+use crate::prelude::*;
+
+/// Produces a lazily registered native function
+type LazyInit = Box<dyn FnOnce() -> NativeImpl>;
+
+/// Description of a module registered with
+/// [`Interpreter::register_module`](super::Interpreter::register_module)
+///
+/// ```
+/// use mquickjs::embed::{Context, HostError, ModuleBuilder, NativeScope, Value};
+///
+/// fn read(_: &mut NativeScope, _: Value, _: &[Value]) -> Result<Value, HostError> {
+///     Ok(Value::int(1))
+/// }
+///
+/// let mut ctx = Context::new(64 * 1024);
+/// ctx.register_module(
+///     ModuleBuilder::new("gpio")
+///         .lazy_fn("readPin", || read)
+///         .deprecated("read", "readPin"),
+/// );
+/// let result = ctx.eval("gpio.readPin(4);").unwrap();
+/// assert_eq!(result.to_i32(), Some(1));
+/// ```
+pub struct ModuleBuilder {
+    pub(crate) name: &'static str,
+    pub(crate) functions: Vec<(&'static str, NativeImpl, u8)>,
+    pub(crate) lazy: Vec<(&'static str, LazyInit)>,
+    pub(crate) deprecated: Vec<(&'static str, &'static str)>,
+}
+
+impl ModuleBuilder {
+    /// Start a module published as the global `name`
+    pub fn new(name: &'static str) -> Self {
+        ModuleBuilder {
+            name,
+            functions: Vec::new(),
+            lazy: Vec::new(),
+            deprecated: Vec::new(),
+        }
+    }
+
+    /// Add a function registered together with the module
+    pub fn function(mut self, name: &'static str, func: HostFn, arity: u8) -> Self {
+        self.functions.push((name, NativeImpl::Host(func), arity));
+        self
+    }
+
+    /// Add an engine builtin registered together with the module
+    pub(crate) fn builtin(mut self, name: &'static str, func: NativeFn, arity: u8) -> Self {
+        self.functions
+            .push((name, NativeImpl::Builtin(func), arity));
+        self
+    }
+
+    /// Add a function whose native is only produced and registered the
+    /// first time a script reads it
+    ///
+    /// Until then the name answers `in` but is not a registered native.
+    /// The function is variadic.
+    pub fn lazy_fn(mut self, name: &'static str, init: impl FnOnce() -> HostFn + 'static) -> Self {
+        self.lazy
+            .push((name, Box::new(move || NativeImpl::Host(init()))));
+        self
+    }
+
+    /// Keep `name` working as an alias of the module's `replacement`
+    ///
+    /// The first read of `name` writes a warning to the console sink at
+    /// [`ConsoleLevel::Warn`](super::ConsoleLevel::Warn); every read
+    /// returns what reading `replacement` does.
+    pub fn deprecated(mut self, name: &'static str, replacement: &'static str) -> Self {
+        self.deprecated.push((name, replacement));
+        self
+    }
+}
+
+/// Entries of a registered module that are resolved when first read
+pub(crate) struct PendingModule {
+    pub(crate) name: &'static str,
+    pub(crate) lazy: Vec<(&'static str, LazyInit)>,
+    /// Deprecated names, their replacements, and whether the warning was
+    /// written yet
+    pub(crate) deprecated: Vec<(&'static str, &'static str, bool)>,
+}
+
+impl PendingModule {
+    /// Whether reading `key` goes through the module rather than the object
+    pub(crate) fn resolves(&self, key: &str) -> bool {
+        self.lazy.iter().any(|(name, _)| *name == key)
+            || self.deprecated.iter().any(|(name, ..)| *name == key)
+    }
+}
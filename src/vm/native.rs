@@ -0,0 +1,302 @@
+//! What host native functions see of the interpreter
+//!
+//! Host functions registered through a `Context` receive a [`NativeScope`]
+//! rather than the interpreter itself: it reads and builds values, calls
+//! back into scripts and creates errors, and nothing else, so the
+//! interpreter can change without breaking them.
+
+use crate::prelude::*;
+
+use super::interpreter::{
+    INTERRUPTED_MESSAGE, Interpreter, InterpreterError, NativeFn, ObjectBuilder, PropertyLookup,
+};
+use super::promise::ResolverHandle;
+use crate::value::{JsStr, Value};
+
+/// Error a native function throws to its caller
+///
+/// The variant picks the type of the error object the script catches. A
+/// plain message converts to [`HostError::Type`], so `?` works on the
+/// `String` errors of [`Args`](super::Args).
+///
+/// ```
+/// use mquickjs::embed::{Args, Context, HostError, NativeScope, Value};
+///
+/// fn percent(_: &mut NativeScope, _this: Value, args: &[Value]) -> Result<Value, HostError> {
+///     let n = Args::new("percent", args).int(0)?;
+///     if !(0..=100).contains(&n) {
+///         return Err(HostError::Range(format!("percent: {} is out of range", n)));
+///     }
+///     Ok(Value::int(n))
+/// }
+///
+/// let mut ctx = Context::new(64 * 1024);
+/// ctx.register_function("percent", percent, 1);
+/// let result = ctx.eval("var name; try { percent(150); } catch (e) { name = e.name; } name;").unwrap();
+/// assert_eq!(ctx.get_string(result).as_deref(), Some("RangeError"));
+/// ```
+#[derive(Debug, Clone)]
+pub enum HostError {
+    /// Thrown as a TypeError
+    Type(String),
+    /// Thrown as a RangeError
+    Range(String),
+    /// Thrown as a ReferenceError
+    Reference(String),
+    /// Thrown as a SyntaxError
+    Syntax(String),
+    /// A failure passed on from the interpreter, such as the exception a
+    /// callback threw through [`NativeScope::call`]; it is rethrown as is
+    Interpreter(InterpreterError),
+}
+
+impl HostError {
+    /// Read an error type from a message prefix such as `"RangeError: "`,
+    /// the convention natives used before `HostError` existed
+    #[deprecated(note = "return the HostError variant for the error type instead")]
+    pub fn from_prefixed(message: String) -> Self {
+        Self::parse_prefixed(message)
+    }
+
+    /// [`HostError::from_prefixed`], for the replay log, which stores errors
+    /// as their displayed text
+    pub(crate) fn parse_prefixed(message: String) -> Self {
+        let strip = |prefix: &str| message.strip_prefix(prefix).map(str::to_string);
+        if message == INTERRUPTED_MESSAGE {
+            Self::Interpreter(InterpreterError::Interrupted)
+        } else if let Some(msg) = strip("RangeError: ") {
+            Self::Range(msg)
+        } else if let Some(msg) = strip("ReferenceError: ") {
+            Self::Reference(msg)
+        } else if let Some(msg) = strip("TypeError: ") {
+            Self::Type(msg)
+        } else if let Some(msg) = strip("SyntaxError: ") {
+            Self::Syntax(msg)
+        } else if let Some(msg) = strip("InternalError: ") {
+            Self::Interpreter(InterpreterError::InternalError(msg))
+        } else {
+            // EXCEPTION_MESSAGE stays a message; the interpreter matches it
+            // against the exception it kept
+            Self::Type(message)
+        }
+    }
+}
+
+impl core::fmt::Display for HostError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Type(msg) => write!(f, "TypeError: {}", msg),
+            Self::Range(msg) => write!(f, "RangeError: {}", msg),
+            Self::Reference(msg) => write!(f, "ReferenceError: {}", msg),
+            Self::Syntax(msg) => write!(f, "SyntaxError: {}", msg),
+            Self::Interpreter(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl core::error::Error for HostError {}
+
+impl From<String> for HostError {
+    fn from(message: String) -> Self {
+        Self::Type(message)
+    }
+}
+
+impl From<&str> for HostError {
+    fn from(message: &str) -> Self {
+        Self::Type(message.to_string())
+    }
+}
+
+impl From<InterpreterError> for HostError {
+    fn from(err: InterpreterError) -> Self {
+        Self::Interpreter(err)
+    }
+}
+
+/// Signature of a host native function
+///
+/// Natives take a [`NativeScope`], this value, and arguments. Returns a
+/// Result with the value or the [`HostError`] to throw. [`Args`](super::Args)
+/// reads typed arguments and words the messages for arguments of the wrong
+/// type.
+///
+/// A native that panics throws a catchable `InternalError` whose message
+/// carries the panic message, and leaves the interpreter usable.
+///
+/// The `this` a native sees depends on its [`Receiver`](super::Receiver)
+/// policy, checked before the native runs: a `Receiver::None` native always
+/// gets undefined, however it was called; a `Receiver::Required` one only
+/// runs on a receiver of the right kind, so it can rely on that kind; a
+/// `Receiver::Optional` one gets whatever it was called on, undefined when
+/// called bare.
+pub type HostFn =
+    fn(scope: &mut NativeScope, this: Value, args: &[Value]) -> Result<Value, HostError>;
+
+/// Implementation behind a registered native function
+#[derive(Clone, Copy)]
+pub(crate) enum NativeImpl {
+    /// An engine builtin, given the whole interpreter
+    Builtin(NativeFn),
+    /// A host function, given a [`NativeScope`]
+    Host(HostFn),
+}
+
+impl NativeImpl {
+    pub(crate) fn call(
+        self,
+        interp: &mut Interpreter,
+        this: Value,
+        args: &[Value],
+    ) -> Result<Value, HostError> {
+        match self {
+            NativeImpl::Builtin(func) => func(interp, this, args),
+            NativeImpl::Host(func) => func(NativeScope::of_mut(interp), this, args),
+        }
+    }
+}
+
+/// The interpreter as a host native function sees it
+///
+/// ```
+/// use mquickjs::embed::{Args, Context, HostError, NativeScope, Value};
+///
+/// fn pair(scope: &mut NativeScope, _this: Value, args: &[Value]) -> Result<Value, HostError> {
+///     let args = Args::new("pair", args);
+///     let name = args.string(0, scope)?;
+///     let label = scope.alloc_string(&name.to_uppercase());
+///     Ok(scope.alloc_array(vec![label, Value::int(args.opt_int(1, 0)?)]))
+/// }
+///
+/// let mut ctx = Context::new(64 * 1024);
+/// ctx.register_function("pair", pair, 2);
+/// let result = ctx.eval("pair(\"a\", 2).join();").unwrap();
+/// assert_eq!(ctx.get_string(result).as_deref(), Some("A,2"));
+/// ```
+#[repr(transparent)]
+pub struct NativeScope {
+    interp: Interpreter,
+}
+
+impl NativeScope {
+    /// View an interpreter as a scope
+    pub(crate) fn of(interp: &Interpreter) -> &NativeScope {
+        // SAFETY: NativeScope is a transparent wrapper around Interpreter
+        unsafe { &*(interp as *const Interpreter as *const NativeScope) }
+    }
+
+    /// View an interpreter as a scope natives can change values through
+    pub(crate) fn of_mut(interp: &mut Interpreter) -> &mut NativeScope {
+        // SAFETY: NativeScope is a transparent wrapper around Interpreter
+        unsafe { &mut *(interp as *mut Interpreter as *mut NativeScope) }
+    }
+
+    /// Contents of a string value, or None if `value` is not a string
+    pub fn get_string(&self, value: Value) -> Option<JsStr<'_>> {
+        self.interp.get_string(value)
+    }
+
+    /// Allocate a string
+    pub fn alloc_string(&mut self, s: &str) -> Value {
+        self.interp.alloc_string(s)
+    }
+
+    /// Allocate an array holding `elements`
+    pub fn alloc_array(&mut self, elements: Vec<Value>) -> Value {
+        self.interp.alloc_array(elements)
+    }
+
+    /// Number of elements of an array, or None if `arr` is not an array
+    pub fn array_len(&self, arr: Value) -> Option<usize> {
+        self.interp.array_len(arr)
+    }
+
+    /// Element `index` of an array, or None if `arr` is not an array or is
+    /// shorter; holes read as undefined
+    pub fn array_element_at(&self, arr: Value, index: usize) -> Option<Value> {
+        self.interp.array_element_at(arr, index)
+    }
+
+    /// Append `val` to an array, returning its new length, or None if `arr`
+    /// is not an array or cannot grow
+    pub fn array_push(&mut self, arr: Value, val: Value) -> Option<usize> {
+        self.interp.array_push(arr, val)
+    }
+
+    /// Start building a plain object
+    ///
+    /// ```
+    /// # use mquickjs::embed::{HostError, NativeScope, Value};
+    /// fn stats(scope: &mut NativeScope, _this: Value, args: &[Value]) -> Result<Value, HostError> {
+    ///     let n = args.len() as i32;
+    ///     Ok(scope
+    ///         .alloc_object()
+    ///         .prop("ok", Value::bool(true))
+    ///         .prop("count", Value::int(n))
+    ///         .finish())
+    /// }
+    /// ```
+    pub fn alloc_object(&mut self) -> ObjectBuilder<'_> {
+        self.interp.alloc_object()
+    }
+
+    /// Number of own properties of a plain object, or None if `obj` is not
+    /// one
+    pub fn object_property_count(&self, obj: Value) -> Option<usize> {
+        self.interp.object_property_count(obj)
+    }
+
+    /// Own property `index` of a plain object in insertion order
+    pub fn object_property_at(&self, obj: Value, index: usize) -> Option<(&str, Value)> {
+        self.interp.object_property_at(obj, index)
+    }
+
+    /// Create or overwrite own property `key` of a plain object, returning
+    /// false if `obj` is not one
+    pub fn object_set(&mut self, obj: Value, key: &str, val: Value) -> bool {
+        self.interp.object_set(obj, key, val)
+    }
+
+    /// Check whether `obj` has the property `key` under the given lookup
+    pub fn has_property(&self, obj: Value, key: &str, lookup: PropertyLookup) -> bool {
+        self.interp.has_property(obj, key, lookup)
+    }
+
+    /// Create an object wrapping a host value
+    pub fn create_userdata<T: 'static>(&mut self, value: T) -> Value {
+        self.interp.create_userdata(value, None)
+    }
+
+    /// Borrow the host value of a userdata object, if it is live and of type `T`
+    pub fn get_userdata<T: 'static>(&self, val: Value) -> Option<&T> {
+        self.interp.get_userdata(val)
+    }
+
+    /// Whether a value can be called as a function
+    pub fn is_callable(&self, val: Value) -> bool {
+        self.interp.is_callable(val)
+    }
+
+    /// Call a function value with the given `this` and arguments
+    ///
+    /// An exception the callee throws comes back as an `Err` that, returned
+    /// from the native, rethrows the original value to the native's caller.
+    pub fn call(&mut self, func: Value, this: Value, args: &[Value]) -> Result<Value, HostError> {
+        Ok(self.interp.call_value(func, this, args)?)
+    }
+
+    /// Create a pending promise and the handle the host settles it with
+    pub fn create_pending_promise(&mut self) -> (Value, ResolverHandle) {
+        self.interp.create_pending_promise()
+    }
+
+    /// Allocate an error object such as `alloc_error("TypeError", "bad input")`
+    pub fn alloc_error(&mut self, name: &str, message: &str) -> Value {
+        self.interp.alloc_error(name, message)
+    }
+
+    /// Format a value the way console.log prints it
+    pub fn format_value(&self, val: Value) -> String {
+        self.interp.format_value(val)
+    }
+}
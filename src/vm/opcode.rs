@@ -8,6 +8,8 @@
 //! - Number of values pushed to stack (n_push)
 //! - An operand format
 
+use crate::prelude::*;
+
 /// Opcode operand formats
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -60,6 +62,58 @@ pub enum OpFormat {
     Value,
 }
 
+impl OpFormat {
+    /// Number of operand bytes following the opcode byte
+    pub const fn operand_size(self) -> usize {
+        match self {
+            OpFormat::None
+            | OpFormat::NoneInt
+            | OpFormat::NoneLoc
+            | OpFormat::NoneArg
+            | OpFormat::NoneVarRef
+            | OpFormat::NPopX => 0,
+            OpFormat::U8 | OpFormat::I8 | OpFormat::Loc8 | OpFormat::Const8 | OpFormat::Label8 => 1,
+            OpFormat::U16
+            | OpFormat::I16
+            | OpFormat::Label16
+            | OpFormat::NPop
+            | OpFormat::Loc
+            | OpFormat::Arg
+            | OpFormat::VarRef
+            | OpFormat::Const16 => 2,
+            OpFormat::U32 | OpFormat::I32 | OpFormat::Label | OpFormat::Value => 4,
+        }
+    }
+
+    /// Whether the operand is a jump offset relative to the next instruction
+    pub const fn is_label(self) -> bool {
+        matches!(self, OpFormat::Label8 | OpFormat::Label16 | OpFormat::Label)
+    }
+
+    /// Read this format's operand from the bytes following the opcode
+    ///
+    /// Signed formats are sign-extended, everything else is zero-extended.
+    /// The caller guarantees `bytes` holds at least `operand_size()` bytes.
+    #[inline(always)]
+    pub fn read_operand(self, bytes: &[u8]) -> i32 {
+        match self {
+            OpFormat::U8 | OpFormat::Loc8 | OpFormat::Const8 => bytes[0] as i32,
+            OpFormat::I8 | OpFormat::Label8 => bytes[0] as i8 as i32,
+            OpFormat::I16 | OpFormat::Label16 => i16::from_le_bytes([bytes[0], bytes[1]]) as i32,
+            OpFormat::U16
+            | OpFormat::NPop
+            | OpFormat::Loc
+            | OpFormat::Arg
+            | OpFormat::VarRef
+            | OpFormat::Const16 => u16::from_le_bytes([bytes[0], bytes[1]]) as i32,
+            OpFormat::U32 | OpFormat::I32 | OpFormat::Label | OpFormat::Value => {
+                i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+            }
+            _ => 0,
+        }
+    }
+}
+
 /// JavaScript bytecode opcodes
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -354,11 +408,51 @@ pub enum OpCode {
     Print,
     /// Get global variable by name (16-bit constant index)
     GetGlobal,
+    /// Set global variable by name (16-bit constant index)
+    PutGlobal,
+
+    // Hints
+    /// End of a statement: reclaim temporary arrays that did not escape
+    EndStatement,
+
+    // Object literals
+    /// Define property with a runtime key: obj key val -> obj
+    DefineArrayEl,
+
+    // Globals
+    /// Publish a declared top-level binding, which delete cannot remove
+    /// (16-bit constant index)
+    DefineGlobal,
+    /// Delete a global binding by name, pushing whether it is gone
+    /// (16-bit constant index)
+    DeleteGlobal,
+
+    // Spread and rest
+    /// Copy the own enumerable properties of source into target, skipping
+    /// the excluded keys: target source keys... -> target (16-bit key count)
+    CopyDataProperties,
 }
 
 impl OpCode {
     /// Total number of opcodes
-    pub const COUNT: usize = OpCode::GetGlobal as usize + 1;
+    pub const COUNT: usize = OpCode::CopyDataProperties as usize + 1;
+
+    /// Convert a bytecode byte to an opcode
+    #[inline(always)]
+    pub fn from_u8(byte: u8) -> Option<OpCode> {
+        if (byte as usize) < OpCode::COUNT {
+            // Safety: OpCode is repr(u8) with contiguous discriminants below COUNT
+            Some(unsafe { core::mem::transmute::<u8, OpCode>(byte) })
+        } else {
+            None
+        }
+    }
+
+    /// Metadata for this opcode
+    #[inline(always)]
+    pub fn info(self) -> &'static OpCodeInfo {
+        &OPCODE_INFO[self as usize]
+    }
 }
 
 /// Opcode metadata
@@ -385,6 +479,200 @@ impl OpCodeInfo {
     }
 }
 
+/// A decoded instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    /// The opcode
+    pub opcode: OpCode,
+    /// Operand value, 0 for formats without an operand
+    pub operand: i32,
+    /// Encoded size in bytes, including the opcode byte
+    pub size: usize,
+}
+
+impl Instruction {
+    /// Jump target for label operands, given the instruction's own pc
+    pub fn jump_target(&self, pc: usize) -> Option<usize> {
+        if self.opcode.info().format.is_label() {
+            usize::try_from((pc + self.size) as i64 + self.operand as i64).ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// Read the operand of an `op` instruction whose operand starts at `pc`
+///
+/// Returns the operand and the pc of the next instruction. The interpreter
+/// calls this from each handler with the handler's own opcode, so the table
+/// lookup and format dispatch fold to a fixed-width load.
+#[inline(always)]
+pub fn read_operand_at(bc: &[u8], pc: usize, op: OpCode) -> (i32, usize) {
+    let next = pc + op.info().format.operand_size();
+    (op.info().format.read_operand(&bc[pc..next]), next)
+}
+
+/// Error produced when bytecode cannot be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Byte at `pc` is not an opcode
+    InvalidOpcode { pc: usize, byte: u8 },
+    /// Operand of the opcode at `pc` runs past the end of the bytecode
+    Truncated { pc: usize, opcode: OpCode },
+    /// Jump at `pc` does not land on an instruction boundary
+    BadJump { pc: usize, target: Option<usize> },
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodeError::InvalidOpcode { pc, byte } => {
+                write!(f, "invalid opcode {} at pc {}", byte, pc)
+            }
+            DecodeError::Truncated { pc, opcode } => {
+                write!(f, "truncated {:?} operand at pc {}", opcode, pc)
+            }
+            DecodeError::BadJump { pc, target } => match target {
+                Some(target) => write!(f, "jump at pc {} to invalid target {}", pc, target),
+                None => write!(f, "jump at pc {} out of range", pc),
+            },
+        }
+    }
+}
+
+/// Decode the instruction starting at `pc`
+#[inline(always)]
+pub fn decode(bc: &[u8], pc: usize) -> Result<Instruction, DecodeError> {
+    let byte = bc[pc];
+    let opcode = OpCode::from_u8(byte).ok_or(DecodeError::InvalidOpcode { pc, byte })?;
+    let info = opcode.info();
+    let size = info.size as usize;
+    let operand = match bc.get(pc + 1..pc + size) {
+        Some(bytes) => info.format.read_operand(bytes),
+        None => return Err(DecodeError::Truncated { pc, opcode }),
+    };
+    Ok(Instruction {
+        opcode,
+        operand,
+        size,
+    })
+}
+
+/// Iterate over the instructions of a bytecode buffer with their pcs
+///
+/// Iteration stops after the first decode error.
+pub fn instructions(bc: &[u8]) -> impl Iterator<Item = Result<(usize, Instruction), DecodeError>> {
+    let mut pc = 0;
+    let mut failed = false;
+    core::iter::from_fn(move || {
+        if failed || pc >= bc.len() {
+            return None;
+        }
+        match decode(bc, pc) {
+            Ok(insn) => {
+                let at = pc;
+                pc += insn.size;
+                Some(Ok((at, insn)))
+            }
+            Err(e) => {
+                failed = true;
+                Some(Err(e))
+            }
+        }
+    })
+}
+
+/// Check that bytecode decodes cleanly and every jump lands on an instruction
+///
+/// A jump to the end of the buffer is allowed (it acts as an implicit return).
+pub fn validate(bc: &[u8]) -> Result<(), DecodeError> {
+    let mut starts = vec![false; bc.len() + 1];
+    let mut jumps = Vec::new();
+    for item in instructions(bc) {
+        let (pc, insn) = item?;
+        starts[pc] = true;
+        if insn.opcode.info().format.is_label() {
+            jumps.push((pc, insn.jump_target(pc)));
+        }
+    }
+    starts[bc.len()] = true;
+
+    for (pc, target) in jumps {
+        match target {
+            Some(t) if starts.get(t).copied().unwrap_or(false) => {}
+            _ => return Err(DecodeError::BadJump { pc, target }),
+        }
+    }
+    Ok(())
+}
+
+/// Deepest the value stack can get while running a bytecode buffer
+///
+/// A static walk over every path using the pop/push counts in
+/// [`OPCODE_INFO`]; call opcodes also pop their argument count. Each
+/// instruction is visited with the first depth that reaches it, which the
+/// compiler keeps the same on every path. A `Catch` handler starts one
+/// deeper for the thrown value, while its fall-through path does not.
+pub fn max_stack_depth(bc: &[u8]) -> Result<usize, DecodeError> {
+    let mut seen = vec![false; bc.len()];
+    let mut work = vec![(0usize, 0usize)];
+    let mut max = 0;
+    while let Some((mut pc, mut depth)) = work.pop() {
+        while pc < bc.len() && !seen[pc] {
+            seen[pc] = true;
+            let insn = decode(bc, pc)?;
+            let info = insn.opcode.info();
+            let mut pops = info.n_pop as usize;
+            if info.format == OpFormat::NPop {
+                pops += insn.operand as usize;
+            }
+            depth = depth.saturating_sub(pops) + info.n_push as usize;
+            max = max.max(depth);
+            if let Some(target) = insn.jump_target(pc) {
+                work.push((target, depth));
+            }
+            match insn.opcode {
+                OpCode::Catch => depth -= 1,
+                OpCode::Goto
+                | OpCode::Return
+                | OpCode::ReturnUndef
+                | OpCode::Throw
+                | OpCode::Ret => break,
+                _ => {}
+            }
+            pc += insn.size;
+        }
+    }
+    Ok(max)
+}
+
+/// Render bytecode as one instruction per line
+///
+/// Each line shows the pc, the opcode and its operand; jumps also show
+/// their absolute target. Undecodable bytes end the listing with an error line.
+pub fn disassemble(bc: &[u8]) -> String {
+    let mut out = String::new();
+    for item in instructions(bc) {
+        match item {
+            Ok((pc, insn)) => {
+                out.push_str(&format!("{:5}  {:?}", pc, insn.opcode));
+                let format = insn.opcode.info().format;
+                if format.operand_size() > 0 {
+                    out.push_str(&format!(" {}", insn.operand));
+                }
+                if let Some(target) = insn.jump_target(pc) {
+                    out.push_str(&format!(" -> {}", target));
+                }
+                out.push('\n');
+            }
+            Err(e) => {
+                out.push_str(&format!("error: {}\n", e));
+            }
+        }
+    }
+    out
+}
+
 /// Opcode information table
 pub static OPCODE_INFO: [OpCodeInfo; OpCode::COUNT] = [
     // Invalid
@@ -647,6 +935,19 @@ pub static OPCODE_INFO: [OpCodeInfo; OpCode::COUNT] = [
     OpCodeInfo::new(1, 1, 0, OpFormat::None),
     // GetGlobal - 3 bytes (opcode + 16-bit constant index), pops 0, pushes 1
     OpCodeInfo::new(3, 0, 1, OpFormat::Const16),
+    // PutGlobal - 3 bytes (opcode + 16-bit constant index), pops 1, pushes 0
+    OpCodeInfo::new(3, 1, 0, OpFormat::Const16),
+    // EndStatement
+    OpCodeInfo::new(1, 0, 0, OpFormat::None),
+    // DefineArrayEl
+    OpCodeInfo::new(1, 3, 1, OpFormat::None),
+    // DefineGlobal - 3 bytes (opcode + 16-bit constant index), pops 1, pushes 0
+    OpCodeInfo::new(3, 1, 0, OpFormat::Const16),
+    // DeleteGlobal - 3 bytes (opcode + 16-bit constant index), pops 0, pushes 1
+    OpCodeInfo::new(3, 0, 1, OpFormat::Const16),
+    // CopyDataProperties - 3 bytes (opcode + 16-bit key count), pops 2 plus
+    // the keys, pushes 1
+    OpCodeInfo::new(3, 2, 1, OpFormat::NPop),
 ];
 
 #[cfg(test)]
@@ -658,6 +959,101 @@ mod tests {
         assert_eq!(OPCODE_INFO.len(), OpCode::COUNT);
     }
 
+    #[test]
+    fn test_opcode_table_covers_all_opcodes() {
+        for byte in 0..=u8::MAX {
+            let Some(op) = OpCode::from_u8(byte) else {
+                assert!(byte as usize >= OpCode::COUNT);
+                continue;
+            };
+            assert_eq!(op as u8, byte);
+            let info = op.info();
+            assert_eq!(
+                info.size as usize,
+                1 + info.format.operand_size(),
+                "{:?} size disagrees with its operand format",
+                op
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_operands() {
+        let bc = [
+            OpCode::PushI8 as u8,
+            0xfe,
+            OpCode::PushI16 as u8,
+            0x00,
+            0x80,
+            OpCode::GetLoc as u8,
+            0x34,
+            0x12,
+            OpCode::Goto as u8,
+            0xfb,
+            0xff,
+            0xff,
+            0xff,
+        ];
+        let insns: Vec<_> = instructions(&bc).map(Result::unwrap).collect();
+        assert_eq!(insns[0].1.operand, -2);
+        assert_eq!(insns[1].1.operand, -32768);
+        assert_eq!(insns[2].1.operand, 0x1234);
+        assert_eq!(insns[3], (8, insns[3].1));
+        assert_eq!(insns[3].1.jump_target(8), Some(8));
+        assert!(validate(&bc).is_ok());
+        assert!(disassemble(&bc).contains("Goto -5 -> 8"));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_bytecode() {
+        assert_eq!(
+            validate(&[0xff]),
+            Err(DecodeError::InvalidOpcode { pc: 0, byte: 0xff })
+        );
+        assert_eq!(
+            validate(&[OpCode::GetLoc as u8, 0]),
+            Err(DecodeError::Truncated {
+                pc: 0,
+                opcode: OpCode::GetLoc
+            })
+        );
+        // Jump into the middle of its own operand
+        assert_eq!(
+            validate(&[OpCode::Goto as u8, 0xfe, 0xff, 0xff, 0xff]),
+            Err(DecodeError::BadJump {
+                pc: 0,
+                target: Some(3)
+            })
+        );
+    }
+
+    #[test]
+    fn test_max_stack_depth() {
+        // f(1, 2) + 3, with an IfFalse branch that skips a deeper push
+        let bc = [
+            OpCode::Undefined as u8,
+            OpCode::Push1 as u8,
+            OpCode::Push2 as u8,
+            OpCode::Call as u8,
+            2,
+            0,
+            OpCode::Push3 as u8,
+            OpCode::Add as u8,
+            OpCode::IfFalse as u8,
+            2,
+            0,
+            0,
+            0,
+            OpCode::Push1 as u8,
+            OpCode::Drop as u8,
+            OpCode::ReturnUndef as u8,
+        ];
+        assert!(validate(&bc).is_ok());
+        assert_eq!(max_stack_depth(&bc), Ok(3));
+        assert_eq!(max_stack_depth(&[]), Ok(0));
+        assert!(max_stack_depth(&[0xff]).is_err());
+    }
+
     #[test]
     fn test_opcode_sizes() {
         // Verify some known opcode sizes
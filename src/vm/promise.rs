@@ -0,0 +1,176 @@
+//! Promises settled by the host
+//!
+//! A script cannot create promises yet; they come from the embedder, which
+//! hands one to the script and keeps a [`ResolverHandle`] to settle it
+//! later. The handle is `Send`, so the work behind the promise can finish
+//! on another thread: settling only queues the outcome, and the context
+//! applies it the next time it runs pending jobs.
+
+use crate::prelude::*;
+use alloc::collections::VecDeque;
+use alloc::sync::{Arc, Weak};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::value::Value;
+
+/// State of a promise object
+#[derive(Debug)]
+pub(crate) enum PromiseState {
+    /// Not settled yet, with the reactions waiting for it in the order
+    /// `then` attached them
+    Pending(Vec<PromiseReaction>),
+    /// Resolved with a value
+    Fulfilled(Value),
+    /// Rejected with a reason
+    Rejected(Value),
+}
+
+/// Handlers attached by `then`, and the promise `then` returned
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PromiseReaction {
+    pub(crate) on_fulfilled: Value,
+    pub(crate) on_rejected: Value,
+    pub(crate) derived: Value,
+}
+
+/// A reaction whose promise has settled, waiting to run
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PromiseJob {
+    /// Handler to call, or undefined to pass the outcome straight on
+    pub(crate) handler: Value,
+    /// The fulfilled value or rejection reason
+    pub(crate) argument: Value,
+    /// Whether `argument` is a rejection reason
+    pub(crate) rejected: bool,
+    /// Promise settled with the handler's result
+    pub(crate) derived: Value,
+}
+
+/// Microtask queue of promise jobs, run first in, first out
+pub(crate) type JobQueue = VecDeque<PromiseJob>;
+
+/// An outcome a [`ResolverHandle`] reported, not yet applied
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Settlement {
+    pub(crate) promise: Value,
+    pub(crate) value: Value,
+    pub(crate) rejected: bool,
+}
+
+/// Outcomes reported by resolver handles, shared with the handles
+pub(crate) type SettlementQueue = Arc<Settlements>;
+
+/// Outcomes reported by resolver handles and not yet applied
+///
+/// Handles may report from any thread. With `std` the list sits behind a
+/// `Mutex`; without it behind a spin lock, which is held only long enough
+/// to push one outcome or take them all.
+#[derive(Debug, Default)]
+pub(crate) struct Settlements {
+    #[cfg(feature = "std")]
+    list: std::sync::Mutex<Vec<Settlement>>,
+    #[cfg(not(feature = "std"))]
+    locked: AtomicBool,
+    #[cfg(not(feature = "std"))]
+    list: core::cell::UnsafeCell<Vec<Settlement>>,
+}
+
+// SAFETY: the list is only reached through `with`, which holds the lock
+#[cfg(not(feature = "std"))]
+unsafe impl Sync for Settlements {}
+
+impl Settlements {
+    /// Queue an outcome
+    pub(crate) fn push(&self, settlement: Settlement) {
+        self.with(|list| list.push(settlement));
+    }
+
+    /// Take every queued outcome, oldest first
+    pub(crate) fn take(&self) -> Vec<Settlement> {
+        self.with(core::mem::take)
+    }
+
+    #[cfg(feature = "std")]
+    fn with<R>(&self, f: impl FnOnce(&mut Vec<Settlement>) -> R) -> R {
+        let mut list = self
+            .list
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut list)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn with<R>(&self, f: impl FnOnce(&mut Vec<Settlement>) -> R) -> R {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        // SAFETY: the lock is held until the store below
+        let result = f(unsafe { &mut *self.list.get() });
+        self.locked.store(false, Ordering::Release);
+        result
+    }
+}
+
+/// Settles one promise created by
+/// [`Context::create_pending`](crate::Context::create_pending)
+///
+/// Resolving or rejecting queues the outcome; the promise settles and its
+/// reactions run on the next
+/// [`Context::run_pending_jobs`](crate::Context::run_pending_jobs). Only
+/// the first outcome counts: later calls are ignored, as are calls made
+/// after the context was dropped.
+///
+/// The value must stay alive until then: a primitive, or a value the
+/// script also holds, such as one published with
+/// [`Context::set_global`](crate::Context::set_global).
+#[derive(Debug)]
+pub struct ResolverHandle {
+    promise: Value,
+    queue: Weak<Settlements>,
+    settled: AtomicBool,
+}
+
+impl ResolverHandle {
+    pub(crate) fn new(promise: Value, queue: &SettlementQueue) -> Self {
+        ResolverHandle {
+            promise,
+            queue: Arc::downgrade(queue),
+            settled: AtomicBool::new(false),
+        }
+    }
+
+    /// Fulfil the promise with `value`
+    ///
+    /// Returns false if the promise was already settled through this
+    /// handle or the context is gone.
+    pub fn resolve(&self, value: Value) -> bool {
+        self.settle(value, false)
+    }
+
+    /// Reject the promise with `reason`
+    ///
+    /// Returns false if the promise was already settled through this
+    /// handle or the context is gone.
+    pub fn reject(&self, reason: Value) -> bool {
+        self.settle(reason, true)
+    }
+
+    fn settle(&self, value: Value, rejected: bool) -> bool {
+        let Some(queue) = self.queue.upgrade() else {
+            return false;
+        };
+        if self.settled.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+        queue.push(Settlement {
+            promise: self.promise,
+            value,
+            rejected,
+        });
+        true
+    }
+}
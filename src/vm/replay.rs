@@ -0,0 +1,386 @@
+//! Recording and replaying the nondeterministic inputs of a script
+//!
+//! While recording, the interpreter logs the result of every call to a
+//! host-registered native and every clock or random number read made by a
+//! builtin. Replaying the log substitutes those results instead of calling
+//! the host or reading the clock, so a failure seen on a device can be
+//! reproduced on a workstation with the same script.
+
+use crate::prelude::*;
+use core::fmt;
+
+/// Bytes every encoded log starts with, followed by the format version
+const MAGIC: &[u8; 4] = b"MQRL";
+const VERSION: u8 = 1;
+
+/// A value returned by a host native, detached from the interpreter
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoggedValue {
+    /// `undefined`
+    Undefined,
+    /// `null`
+    Null,
+    /// A boolean
+    Bool(bool),
+    /// A number
+    Int(i32),
+    /// A string's contents
+    String(String),
+    /// An array's elements
+    Array(Vec<LoggedValue>),
+    /// Own enumerable properties in order
+    Object(Vec<(String, LoggedValue)>),
+    /// A value that cannot be rebuilt on replay, such as a function or
+    /// userdata
+    Unsupported,
+}
+
+/// One nondeterministic input observed by a script
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayEntry {
+    /// Call of a host-registered native: its name, a hash of the arguments
+    /// and what it returned or threw
+    NativeCall {
+        name: String,
+        args_hash: u64,
+        result: Result<LoggedValue, String>,
+    },
+    /// Clock reading: milliseconds for Date, microseconds since interpreter
+    /// creation for performance and console.time
+    Time(u64),
+    /// Result of Math.random
+    Random(i32),
+}
+
+impl ReplayEntry {
+    /// Short description used in divergence errors
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            Self::NativeCall { name, .. } => format!("call to '{}'", name),
+            Self::Time(_) => "time read".to_string(),
+            Self::Random(_) => "Math.random".to_string(),
+        }
+    }
+}
+
+/// Ordered log of the inputs a script observed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayLog {
+    entries: Vec<ReplayEntry>,
+}
+
+/// Error decoding a replay log from bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayFormatError {
+    /// Byte offset where decoding failed
+    pub offset: usize,
+}
+
+impl fmt::Display for ReplayFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "malformed replay log at byte {}", self.offset)
+    }
+}
+
+impl core::error::Error for ReplayFormatError {}
+
+impl ReplayLog {
+    /// Entries in the order they were recorded
+    pub fn entries(&self) -> &[ReplayEntry] {
+        &self.entries
+    }
+
+    /// Number of recorded entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing was recorded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, entry: ReplayEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Encode the log in its compact binary format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        write_varint(&mut out, self.entries.len() as u64);
+        for entry in &self.entries {
+            match entry {
+                ReplayEntry::NativeCall {
+                    name,
+                    args_hash,
+                    result,
+                } => {
+                    out.push(0);
+                    write_str(&mut out, name);
+                    out.extend_from_slice(&args_hash.to_le_bytes());
+                    match result {
+                        Ok(value) => {
+                            out.push(0);
+                            write_value(&mut out, value);
+                        }
+                        Err(message) => {
+                            out.push(1);
+                            write_str(&mut out, message);
+                        }
+                    }
+                }
+                ReplayEntry::Time(t) => {
+                    out.push(1);
+                    write_varint(&mut out, *t);
+                }
+                ReplayEntry::Random(n) => {
+                    out.push(2);
+                    write_varint(&mut out, zigzag(*n));
+                }
+            }
+        }
+        out
+    }
+
+    /// Decode a log produced by [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<ReplayLog, ReplayFormatError> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != MAGIC || reader.byte()? != VERSION {
+            return Err(ReplayFormatError { offset: 0 });
+        }
+        let count = reader.varint()?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let entry = match reader.byte()? {
+                0 => {
+                    let name = reader.string()?;
+                    let args_hash = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+                    let result = match reader.byte()? {
+                        0 => Ok(reader.value()?),
+                        1 => Err(reader.string()?),
+                        _ => return Err(reader.error()),
+                    };
+                    ReplayEntry::NativeCall {
+                        name,
+                        args_hash,
+                        result,
+                    }
+                }
+                1 => ReplayEntry::Time(reader.varint()?),
+                2 => {
+                    let n = reader.varint()?;
+                    ReplayEntry::Random(unzigzag(n).ok_or_else(|| reader.error())?)
+                }
+                _ => return Err(reader.error()),
+            };
+            entries.push(entry);
+        }
+        if reader.pos != bytes.len() {
+            return Err(reader.error());
+        }
+        Ok(ReplayLog { entries })
+    }
+}
+
+/// Whether the interpreter is recording a log or replaying one
+pub(crate) enum ReplayMode {
+    Recording(ReplayLog),
+    /// `next` is the index of the entry the next input must match
+    Replaying {
+        log: ReplayLog,
+        next: usize,
+    },
+}
+
+/// Hash of a native call's arguments, stable across runs and platforms
+/// (FNV-1a over their encoding)
+pub(crate) fn hash_args(args: &[LoggedValue]) -> u64 {
+    let mut bytes = Vec::new();
+    for arg in args {
+        write_value(&mut bytes, arg);
+    }
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn zigzag(n: i32) -> u64 {
+    ((n << 1) ^ (n >> 31)) as u32 as u64
+}
+
+fn unzigzag(n: u64) -> Option<i32> {
+    let n = u32::try_from(n).ok()?;
+    Some(((n >> 1) as i32) ^ -((n & 1) as i32))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_varint(out, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_value(out: &mut Vec<u8>, value: &LoggedValue) {
+    match value {
+        LoggedValue::Undefined => out.push(0),
+        LoggedValue::Null => out.push(1),
+        LoggedValue::Bool(b) => out.push(if *b { 3 } else { 2 }),
+        LoggedValue::Int(n) => {
+            out.push(4);
+            write_varint(out, zigzag(*n));
+        }
+        LoggedValue::String(s) => {
+            out.push(5);
+            write_str(out, s);
+        }
+        LoggedValue::Array(items) => {
+            out.push(6);
+            write_varint(out, items.len() as u64);
+            for item in items {
+                write_value(out, item);
+            }
+        }
+        LoggedValue::Object(props) => {
+            out.push(7);
+            write_varint(out, props.len() as u64);
+            for (key, item) in props {
+                write_str(out, key);
+                write_value(out, item);
+            }
+        }
+        LoggedValue::Unsupported => out.push(8),
+    }
+}
+
+/// Cursor over an encoded log
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self) -> ReplayFormatError {
+        ReplayFormatError { offset: self.pos }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ReplayFormatError> {
+        let end = self.pos.checked_add(n).filter(|&e| e <= self.bytes.len());
+        let end = end.ok_or_else(|| self.error())?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8, ReplayFormatError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, ReplayFormatError> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            n |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(self.error())
+    }
+
+    fn length(&mut self) -> Result<usize, ReplayFormatError> {
+        let n = self.varint()?;
+        // Every counted item takes at least one byte
+        usize::try_from(n)
+            .ok()
+            .filter(|&n| n <= self.bytes.len() - self.pos)
+            .ok_or_else(|| self.error())
+    }
+
+    fn string(&mut self) -> Result<String, ReplayFormatError> {
+        let len = self.length()?;
+        let start = self.pos;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| ReplayFormatError { offset: start })
+    }
+
+    fn value(&mut self) -> Result<LoggedValue, ReplayFormatError> {
+        Ok(match self.byte()? {
+            0 => LoggedValue::Undefined,
+            1 => LoggedValue::Null,
+            2 => LoggedValue::Bool(false),
+            3 => LoggedValue::Bool(true),
+            4 => {
+                let n = self.varint()?;
+                LoggedValue::Int(unzigzag(n).ok_or_else(|| self.error())?)
+            }
+            5 => LoggedValue::String(self.string()?),
+            6 => {
+                let len = self.length()?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(self.value()?);
+                }
+                LoggedValue::Array(items)
+            }
+            7 => {
+                let len = self.length()?;
+                let mut props = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key = self.string()?;
+                    props.push((key, self.value()?));
+                }
+                LoggedValue::Object(props)
+            }
+            8 => LoggedValue::Unsupported,
+            _ => return Err(self.error()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_round_trips_through_bytes() {
+        let mut log = ReplayLog::default();
+        log.push(ReplayEntry::Time(1_700_000_000_123));
+        log.push(ReplayEntry::Random(-417));
+        log.push(ReplayEntry::NativeCall {
+            name: "readSensor".to_string(),
+            args_hash: hash_args(&[LoggedValue::Int(3)]),
+            result: Ok(LoggedValue::Object(vec![
+                ("ok".to_string(), LoggedValue::Bool(true)),
+                (
+                    "values".to_string(),
+                    LoggedValue::Array(vec![LoggedValue::Int(i32::MIN), LoggedValue::Null]),
+                ),
+            ])),
+        });
+        log.push(ReplayEntry::NativeCall {
+            name: "fail".to_string(),
+            args_hash: hash_args(&[]),
+            result: Err("RangeError: nope".to_string()),
+        });
+
+        let bytes = log.to_bytes();
+        assert_eq!(ReplayLog::from_bytes(&bytes), Ok(log));
+
+        // Truncation and trailing garbage are both rejected
+        assert!(ReplayLog::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut padded = bytes.clone();
+        padded.push(0);
+        assert!(ReplayLog::from_bytes(&padded).is_err());
+        assert_eq!(
+            ReplayLog::from_bytes(b"nope"),
+            Err(ReplayFormatError { offset: 0 })
+        );
+    }
+}
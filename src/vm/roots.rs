@@ -0,0 +1,71 @@
+//! Values the host keeps alive across collections
+//!
+//! A plain [`Value`] held by the embedder is not a root: the collector may
+//! reclaim what it names once scripts stop referencing it. A
+//! [`PersistentValue`] registers its value in a root table the collector
+//! marks from, and removes it again when dropped.
+
+use alloc::rc::{Rc, Weak};
+use core::cell::RefCell;
+use hashbrown::HashMap;
+
+use crate::value::Value;
+
+/// Values rooted by live [`PersistentValue`] handles, keyed by handle id
+#[derive(Debug, Default)]
+pub(crate) struct RootTable {
+    values: HashMap<u64, Value>,
+    next_id: u64,
+}
+
+impl RootTable {
+    /// Every rooted value, for the collector to mark from
+    pub(crate) fn values(&self) -> impl Iterator<Item = Value> + '_ {
+        self.values.values().copied()
+    }
+}
+
+/// Root table shared between an interpreter and its handles
+pub(crate) type SharedRoots = Rc<RefCell<RootTable>>;
+
+/// A value kept alive, and its storage left in place, until the handle is
+/// dropped
+///
+/// Created by [`Context::persist`](crate::Context::persist). Read it with
+/// [`PersistentValue::value`] and the usual accessors such as
+/// [`Context::get_array`](crate::Context::get_array); they keep working
+/// after later evals and collections. Dropping the handle after the
+/// context is gone does nothing.
+#[derive(Debug)]
+pub struct PersistentValue {
+    id: u64,
+    value: Value,
+    roots: Weak<RefCell<RootTable>>,
+}
+
+impl PersistentValue {
+    pub(crate) fn new(value: Value, roots: &SharedRoots) -> Self {
+        let mut table = roots.borrow_mut();
+        let id = table.next_id;
+        table.next_id += 1;
+        table.values.insert(id, value);
+        PersistentValue {
+            id,
+            value,
+            roots: Rc::downgrade(roots),
+        }
+    }
+
+    /// The rooted value
+    pub fn value(&self) -> Value {
+        self.value
+    }
+}
+
+impl Drop for PersistentValue {
+    fn drop(&mut self) {
+        if let Some(roots) = self.roots.upgrade() {
+            roots.borrow_mut().values.remove(&self.id);
+        }
+    }
+}
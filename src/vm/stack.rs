@@ -2,6 +2,7 @@
 //!
 //! The stack grows downward in memory (toward lower addresses).
 
+use crate::prelude::*;
 use crate::value::Value;
 
 /// Value stack for bytecode execution
@@ -10,6 +11,10 @@ pub struct Stack {
     values: Vec<Value>,
     /// Current frame pointer (index into values)
     frame_ptr: usize,
+    /// Number of values the stack was sized for
+    capacity: usize,
+    /// Most values ever on the stack since creation or the last reset
+    high_water: usize,
 }
 
 impl Stack {
@@ -18,6 +23,8 @@ impl Stack {
         Stack {
             values: Vec::with_capacity(capacity),
             frame_ptr: 0,
+            capacity,
+            high_water: 0,
         }
     }
 
@@ -25,6 +32,7 @@ impl Stack {
     #[inline]
     pub fn push(&mut self, value: Value) {
         self.values.push(value);
+        self.high_water = self.high_water.max(self.values.len());
     }
 
     /// Pop a value from the stack
@@ -94,6 +102,12 @@ impl Stack {
         }
     }
 
+    /// All values currently on the stack, bottom first
+    #[inline]
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
     /// Get the current stack depth
     #[inline]
     pub fn len(&self) -> usize {
@@ -106,12 +120,41 @@ impl Stack {
         self.values.is_empty()
     }
 
+    /// Number of values the stack was sized for
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Values that fit before the stack outgrows its capacity
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.capacity.saturating_sub(self.values.len())
+    }
+
+    /// Most values ever on the stack since creation or the last
+    /// [`reset_high_water`](Self::reset_high_water)
+    #[inline]
+    pub fn high_water(&self) -> usize {
+        self.high_water
+    }
+
+    /// Restart the high-water mark from the current depth
+    pub fn reset_high_water(&mut self) {
+        self.high_water = self.values.len();
+    }
+
     /// Drop n values from the stack
     pub fn drop_n(&mut self, n: usize) {
         let new_len = self.values.len().saturating_sub(n);
         self.values.truncate(new_len);
     }
 
+    /// Shrink the stack to `len` values
+    pub fn truncate(&mut self, len: usize) {
+        self.values.truncate(len);
+    }
+
     /// Duplicate the top value
     pub fn dup(&mut self) -> Option<()> {
         let val = self.peek()?;
@@ -156,6 +199,7 @@ impl Stack {
         for _ in 0..locals {
             self.values.push(Value::undefined());
         }
+        self.high_water = self.high_water.max(self.values.len());
 
         self.frame_ptr = new_frame_ptr;
     }
@@ -254,4 +298,21 @@ mod tests {
         assert_eq!(stack.get_local(1).unwrap().to_i32(), Some(20));
         assert_eq!(stack.get_local(2).unwrap().to_i32(), Some(30));
     }
+
+    #[test]
+    fn test_high_water() {
+        let mut stack = Stack::new(16);
+
+        stack.push_frame(3);
+        stack.push(Value::int(1));
+        stack.drop_n(4);
+        assert_eq!(stack.high_water(), 4);
+        assert_eq!(stack.remaining(), 16);
+
+        stack.push(Value::int(2));
+        assert_eq!(stack.high_water(), 4);
+        assert_eq!(stack.remaining(), 15);
+        stack.reset_high_water();
+        assert_eq!(stack.high_water(), 1);
+    }
 }
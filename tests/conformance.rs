@@ -0,0 +1,206 @@
+//! Conformance tests: run every script in tests/scripts through Context::eval
+//!
+//! Each script ends with a block of expected console output:
+//!
+//! ```text
+//! console.log(1 + 2);
+//! // expect:
+//! // 3
+//! ```
+//!
+//! Directives (anywhere in the file, on their own line):
+//! - `// expect-error: TypeError` - evaluation must fail with an error
+//!   whose message contains the given text
+//! - `// skip: reason` - known failure; the script is still run and reported
+//!   separately, but does not fail the test
+//!
+//! Set `BLESS=1` to rewrite the expect blocks from the actual output.
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use mquickjs::embed::Context;
+
+/// Marker line that starts the expected output block
+const EXPECT_MARKER: &str = "// expect:";
+
+/// A parsed conformance script
+struct Script {
+    /// Source code before the expect block
+    source: String,
+    /// Expected console output lines
+    expected: Vec<String>,
+    /// Expected error text, if the script must fail
+    expect_error: Option<String>,
+    /// Reason the script is a known failure
+    skip: Option<String>,
+}
+
+impl Script {
+    fn parse(text: &str) -> Script {
+        let lines: Vec<&str> = text.lines().collect();
+        let marker = lines.iter().rposition(|l| l.trim() == EXPECT_MARKER);
+
+        let (source_lines, expected) = match marker {
+            Some(pos) => {
+                let expected = lines[pos + 1..]
+                    .iter()
+                    .map(|l| {
+                        let l = l.trim_start();
+                        let l = l.strip_prefix("//").unwrap_or(l);
+                        l.strip_prefix(' ').unwrap_or(l).to_string()
+                    })
+                    .collect();
+                (&lines[..pos], expected)
+            }
+            None => (&lines[..], Vec::new()),
+        };
+
+        let directive = |name: &str| {
+            source_lines.iter().find_map(|l| {
+                l.trim()
+                    .strip_prefix(name)
+                    .map(|rest| rest.trim().to_string())
+            })
+        };
+
+        Script {
+            source: source_lines.join("\n"),
+            expected,
+            expect_error: directive("// expect-error:"),
+            skip: directive("// skip:"),
+        }
+    }
+}
+
+/// Result of running one script
+struct Outcome {
+    /// Console output lines
+    output: Vec<String>,
+    /// Error message, if evaluation failed
+    error: Option<String>,
+}
+
+fn run_script(source: &str) -> Outcome {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let sink = Rc::clone(&output);
+
+    let mut ctx = Context::new(1024 * 1024);
+    ctx.set_console_sink(Some(Box::new(move |_level, line: &str| {
+        sink.borrow_mut()
+            .extend(line.split('\n').map(str::to_string));
+    })));
+    let error = ctx.eval(source).err().map(|e| e.to_string());
+    drop(ctx);
+
+    let output = output.borrow().clone();
+    Outcome { output, error }
+}
+
+/// Compare an outcome against a script's expectations
+fn check(script: &Script, outcome: &Outcome) -> Result<(), String> {
+    match (&script.expect_error, &outcome.error) {
+        (None, Some(err)) => return Err(format!("unexpected error: {}", err)),
+        (Some(want), None) => return Err(format!("expected error containing {:?}", want)),
+        (Some(want), Some(err)) if !err.contains(want.as_str()) => {
+            return Err(format!(
+                "expected error containing {:?}, got: {}",
+                want, err
+            ));
+        }
+        _ => {}
+    }
+
+    if outcome.output != script.expected {
+        let mut msg = String::from("output mismatch:\n");
+        let len = outcome.output.len().max(script.expected.len());
+        for i in 0..len {
+            let want = script.expected.get(i).map(String::as_str);
+            let got = outcome.output.get(i).map(String::as_str);
+            if want != got {
+                msg.push_str(&format!(
+                    "  line {}: expected {:?}, got {:?}\n",
+                    i + 1,
+                    want.unwrap_or("<none>"),
+                    got.unwrap_or("<none>")
+                ));
+            }
+        }
+        return Err(msg);
+    }
+    Ok(())
+}
+
+/// Rewrite a script's expect block with the actual output
+fn bless(path: &Path, script: &Script, outcome: &Outcome) {
+    let mut text = script.source.trim_end().to_string();
+    text.push('\n');
+    text.push_str(EXPECT_MARKER);
+    text.push('\n');
+    for line in &outcome.output {
+        if line.is_empty() {
+            text.push_str("//\n");
+        } else {
+            text.push_str(&format!("// {}\n", line));
+        }
+    }
+    fs::write(path, text).unwrap();
+}
+
+fn script_paths() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "js"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn conformance() {
+    let blessing = std::env::var_os("BLESS").is_some();
+    let mut failures = Vec::new();
+    let mut known_failures = Vec::new();
+    let mut passed = 0;
+
+    for path in script_paths() {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let script = Script::parse(&fs::read_to_string(&path).unwrap());
+        let outcome = run_script(&script.source);
+        let result = check(&script, &outcome);
+
+        match (&script.skip, result) {
+            (Some(reason), Ok(())) => {
+                known_failures.push(format!("{} (now passing, remove skip: {})", name, reason));
+            }
+            (Some(reason), Err(_)) => known_failures.push(format!("{} ({})", name, reason)),
+            (None, Ok(())) => passed += 1,
+            (None, Err(_))
+                if blessing && script.expect_error.is_some() == outcome.error.is_some() =>
+            {
+                bless(&path, &script, &outcome);
+                passed += 1;
+            }
+            (None, Err(msg)) => failures.push(format!("{}: {}", name, msg)),
+        }
+    }
+
+    println!("conformance: {} passed", passed);
+    if !known_failures.is_empty() {
+        println!("known failures ({}):", known_failures.len());
+        for entry in &known_failures {
+            println!("  {}", entry);
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} conformance script(s) failed:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,14 @@
+// Integer arithmetic and operator precedence
+console.log(1 + 2 * 3);
+console.log((1 + 2) * 3);
+console.log(10 - 4 - 3);
+console.log(7 % 3);
+console.log(-7 % 3);
+console.log(2 * -3);
+// expect:
+// 7
+// 9
+// 3
+// 1
+// -1
+// -6
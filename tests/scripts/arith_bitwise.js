@@ -0,0 +1,10 @@
+// Bitwise and shift operators
+console.log(5 & 3, 5 | 3, 5 ^ 3);
+console.log(~5);
+console.log(1 << 4, -16 >> 2);
+console.log(-1 >>> 28);
+// expect:
+// 1 7 6
+// -6
+// 16 -4
+// 15
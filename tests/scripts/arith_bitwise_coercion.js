@@ -0,0 +1,32 @@
+// Bitwise operators convert their operands with ToInt32
+console.log("12" | 0, ("12" | 0) === 12);
+console.log(true << 2, false | 0, null | 0, undefined | 0);
+console.log(~"5", ~~"42", "abc" | 0);
+console.log([7] | 0, "3" ^ 1, "1" << "3");
+var d = new Date(1000);
+console.log(d | 0);
+function Box() {}
+function nine() { return 9; }
+var b = new Box();
+b.valueOf = nine;
+console.log(b | 0, new Box() | 0);
+var x = "6";
+x &= "3";
+console.log(x);
+// -1 >>> 0 is 4294967295, which does not fit an integer value yet
+try {
+    console.log(-1 >>> 0);
+} catch (e) {
+    console.log(e.name);
+}
+console.log(-8 >>> 1);
+// expect:
+// 12 true
+// 4 0 0 0
+// -6 42 0
+// 7 2 8
+// 1000
+// 9 0
+// 2
+// RangeError
+// 2147483644
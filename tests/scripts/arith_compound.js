@@ -0,0 +1,14 @@
+// Compound assignment and increments
+var x = 10;
+x += 5;
+x -= 3;
+x *= 2;
+console.log(x);
+var i = 0;
+++i;
+++i;
+--i;
+console.log(i);
+// expect:
+// 24
+// 1
@@ -0,0 +1,7 @@
+// Division that does not produce an integer
+console.log(6 / 3);
+console.log(7 / 2);
+// skip: no floating point values yet
+// expect:
+// 2
+// 3.5
@@ -0,0 +1,36 @@
+// Integer arithmetic is exact up to the largest integer value and throws
+// a RangeError past it instead of wrapping
+var max = Number.MAX_SAFE_INTEGER;
+var min = Number.MIN_SAFE_INTEGER;
+console.log(max, min, max === 2147483647);
+console.log((1 << 30) + (1 << 29), max - 1 + 1, min + 1 - 1);
+console.log(46340 * 46340, max % 2, min / 2);
+function addOne() { return max + 1; }
+function subOne() { return min - 1; }
+function double() { return max * 2; }
+function negate() { return -min; }
+function divide() { return min / -1; }
+var ops = [addOne, subOne, double, negate, divide];
+var results = [];
+for (var i = 0; i < ops.length; i = i + 1) {
+    try {
+        results.push(ops[i]());
+    } catch (e) {
+        results.push(e.name + " " + e.message);
+    }
+}
+console.log(results.join("; "));
+var n = max;
+try {
+    n = n + 1;
+} catch (e) {
+    console.log("n is still", n);
+}
+console.log(min % -1, 2147483646 + 1);
+// expect:
+// 2147483647 -2147483648 true
+// 1610612736 2147483647 -2147483648
+// 2147395600 1 -1073741824
+// RangeError integer overflow; RangeError integer overflow; RangeError integer overflow; RangeError integer overflow; RangeError integer overflow
+// n is still 2147483647
+// 0 2147483647
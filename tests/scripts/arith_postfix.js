@@ -0,0 +1,8 @@
+// Postfix increment and decrement
+var i = 0;
+i++;
+i++;
+i--;
+console.log(i);
+// expect:
+// 1
@@ -0,0 +1,15 @@
+// Array literals, indexing and length
+var a = [1, 2, 3];
+console.log(a);
+console.log(a.length, a[0], a[2]);
+a[5] = 6;
+console.log(a.length);
+console.log(a[10]);
+var nested = [[1, 2], [3]];
+console.log(nested[0][1]);
+// expect:
+// [1, 2, 3]
+// 3 1 3
+// 6
+// undefined
+// 2
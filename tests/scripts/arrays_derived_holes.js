@@ -0,0 +1,33 @@
+// slice, map and concat keep elements removed by delete as holes, filter
+// and flat skip them, and sort moves them to the end
+var arr = [1, 2, 3, 4];
+delete arr[1];
+function id(x) {
+    return x;
+}
+function keep(x) {
+    return true;
+}
+var sliced = arr.slice(0);
+var mapped = arr.map(id);
+var filtered = arr.filter(keep);
+var joined = arr.concat([5], 6);
+var flat = [arr, [5]].flat();
+console.log(arr.length, sliced.length, mapped.length, filtered.length, joined.length, flat.length);
+console.log(sliced[1] === undefined, mapped[1] === undefined, filtered[1] === 3);
+console.log(joined[1] === undefined, flat[1] === 3, 1 in sliced === false, 1 in mapped === false);
+console.log(1 in joined === false, 4 in joined, joined[5], [1, 2].concat(arr)[3] === undefined, 3 in [1, 2].concat(arr));
+console.log(arr.slice(1, 3), 0 in arr.slice(1, 3));
+console.log(arr.slice(3, 1).length);
+var h = [3, 0, 1, undefined];
+delete h[1];
+h.sort();
+console.log(h.length, h[0], h[1], 2 in h, 3 in h);
+// expect:
+// 4 4 4 3 6 4
+// true true true
+// true true true true
+// true true 6 true false
+// [<1 empty item>, 3] false
+// 0
+// 4 1 3 true false
@@ -0,0 +1,27 @@
+// Joining arrays
+console.log([1, 2, 3].join("-"));
+var words = ["a", "b"];
+console.log(words.join(""));
+console.log(words.join(" and "), words.join());
+console.log([1, [2, [3, 4]], null, undefined, true].join(";"));
+function pointToString() {
+    return "(" + this.x + "," + this.y + ")";
+}
+function Point(x, y) {
+    this.x = x;
+    this.y = y;
+    this.toString = pointToString;
+}
+console.log([new Point(1, 2), "end"].join(" "));
+var self = [1, 2];
+self.push(self);
+console.log(self.join("|"));
+console.log(String([5, [6, 7]]));
+// expect:
+// 1-2-3
+// ab
+// a and b a,b
+// 1;2,3,4;;;true
+// (1,2) end
+// 1|2|
+// 5,6,7
@@ -0,0 +1,20 @@
+// Array mutation methods
+var a = [1, 2];
+a.push(3);
+console.log(a);
+console.log(a.pop());
+a.unshift(0);
+console.log(a);
+console.log(a.shift());
+console.log(a.indexOf(2));
+a.reverse();
+console.log(a);
+console.log([1, 2, 3, 4].slice(1, 3));
+// expect:
+// [1, 2, 3]
+// 3
+// [0, 1, 2]
+// 0
+// 1
+// [2, 1]
+// [2, 3]
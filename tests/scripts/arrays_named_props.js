@@ -0,0 +1,19 @@
+// Named properties on arrays
+var results = [3, 4];
+results.total = 7;
+console.log(results.total, results.length);
+for (var k in results) {
+    console.log(k);
+}
+console.log(Object.keys(results));
+console.log(JSON.stringify(results));
+delete results.total;
+console.log("total" in results);
+// expect:
+// 7 2
+// 0
+// 1
+// total
+// [0, 1, total]
+// [3,4]
+// false
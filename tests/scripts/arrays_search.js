@@ -0,0 +1,12 @@
+// indexOf, lastIndexOf and includes compare strings by content and honour fromIndex
+var letters = ["a", "b", "a", "b"];
+var b = "b" + "";
+console.log(letters.indexOf(b), letters.indexOf(b, 2), letters.indexOf("a", -2));
+console.log(letters.lastIndexOf("a"), letters.lastIndexOf("b", -2), letters.lastIndexOf("b", -5));
+console.log(letters.includes(b), letters.includes("c"), letters.includes("a", 3));
+console.log(b === "b", b !== "b");
+// expect:
+// 1 3 2
+// 2 1 -1
+// true false false
+// true false
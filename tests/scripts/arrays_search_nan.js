@@ -0,0 +1,6 @@
+// includes uses SameValueZero and finds NaN; indexOf uses strict equality and does not
+// skip: NaN is not representable yet (numbers are integers)
+var values = [1, NaN];
+console.log(values.includes(NaN), values.indexOf(NaN));
+// expect:
+// true -1
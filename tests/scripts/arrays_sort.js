@@ -0,0 +1,52 @@
+// Array.prototype.sort: string order by default, undefined last, stable
+function Rec(key, tag) {
+    this.key = key;
+    this.tag = tag;
+}
+
+function byKey(a, b) {
+    return a.key - b.key;
+}
+
+function tags(recs) {
+    var out = [];
+    for (var i = 0; i < recs.length; i = i + 1) {
+        out.push(recs[i].tag);
+    }
+    return out.join("");
+}
+
+var calls = [];
+function logged(a, b) {
+    calls.push(a + ":" + b);
+    return a - b;
+}
+
+function yes() {
+    return "yes";
+}
+
+console.log([10, 9, 1, 100, -5, 25].sort());
+console.log(["pear", "Apple", "fig", "apple", ""].sort());
+console.log([3, undefined, 1, undefined, 2].sort());
+
+var seen = [2, undefined, 1].sort(logged);
+console.log(seen, calls.join(" "));
+
+// Equal keys keep their relative order through both sorts
+var recs = [new Rec(2, "a"), new Rec(1, "b"), new Rec(2, "c"), new Rec(1, "d"), new Rec(3, "e")];
+recs.sort(byKey);
+console.log(tags(recs));
+recs.sort(byKey).reverse().sort(byKey);
+console.log(tags(recs));
+
+// A comparator returning a non-number counts as 0: nothing moves
+console.log([3, 1, 2].sort(yes));
+// expect:
+// [-5, 1, 10, 100, 25, 9]
+// [, Apple, apple, fig, pear]
+// [1, 2, 3, undefined, undefined]
+// [1, 2, undefined] 2:1
+// bdace
+// dbcae
+// [3, 1, 2]
@@ -0,0 +1,5 @@
+// Array.prototype.sort with a comparator
+function desc(a, b) { return b - a; }
+console.log([3, 1, 2].sort(desc));
+// expect:
+// [3, 2, 1]
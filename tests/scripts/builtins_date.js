@@ -0,0 +1,23 @@
+// Date parsing and formatting (UTC only)
+var d = new Date("2024-06-01T12:30:45.678+02:00");
+console.log(d.toISOString());
+console.log(d.toString());
+console.log(d.getFullYear(), d.getMonth(), d.getDate(), d.getDay());
+console.log(d.getHours(), d.getMinutes(), d.getSeconds(), d.getMilliseconds());
+console.log(JSON.stringify(d));
+console.log(new Date(d.toISOString()).toISOString());
+console.log(new Date("2024-02-29").toString());
+console.log(Date.parse("1970-01-01T00:00:10Z"), Date.parse("2024-06-01T12:30:00Z"));
+console.log(new Date(Date.parse("2024-06-01T12:30:00Z")).toISOString());
+console.log(new Date("2024-02-30").toString());
+// expect:
+// 2024-06-01T10:30:45.678Z
+// Sat Jun 01 2024 10:30:45 GMT+0000
+// 2024 5 1 6
+// 10 30 45 678
+// "2024-06-01T10:30:45.678Z"
+// 2024-06-01T10:30:45.678Z
+// Thu Feb 29 2024 00:00:00 GMT+0000
+// 10 1717245000
+// 2024-06-01T12:30:00.000Z
+// Invalid Date
@@ -0,0 +1,28 @@
+// Date subtraction, ordering and equality
+var start = new Date("2024-06-01T12:30:00Z");
+var end = new Date("2024-06-01T12:31:02.500Z");
+var same = new Date("2024-06-01T12:30:00Z");
+console.log(end - start);
+console.log(start < end, end < start, start <= same, start >= same, start > same);
+console.log(start == same, start === same, start != same, start == start);
+var early = new Date(5000);
+var alsoEarly = new Date(5000);
+console.log(early.getTime() === alsoEarly.getTime(), early == alsoEarly, +early, early - 1000);
+function byTime(a, b) { return a - b; }
+function byOrder(a, b) { if (a < b) { return -1; } if (a > b) { return 1; } return 0; }
+function iso(d) { return d.toISOString(); }
+var dates = [end, start, new Date("2024-05-31T00:00:00Z")];
+console.log(dates.slice().sort(byTime).map(iso).join(" "));
+console.log(dates.slice().sort(byOrder).map(iso).join(" "));
+var invalid = new Date("not a date");
+console.log(invalid - start, invalid < start, invalid >= start);
+console.log(JSON.stringify({ at: start, list: [early] }));
+// expect:
+// 62
+// true false true true false
+// false false true true
+// true false 5000 4000
+// 2024-05-31T00:00:00.000Z 2024-06-01T12:30:00.000Z 2024-06-01T12:31:02.500Z
+// 2024-05-31T00:00:00.000Z 2024-06-01T12:30:00.000Z 2024-06-01T12:31:02.500Z
+// 0 false false
+// {"at":"2024-06-01T12:30:00.000Z","list":["1970-01-01T01:23:20.000Z"]}
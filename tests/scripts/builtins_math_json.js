@@ -0,0 +1,11 @@
+// Math and JSON
+console.log(Math.max(1, 5, 3), Math.min(4, 2));
+console.log(Math.abs(-7), Math.floor(7), Math.pow(2, 10));
+console.log(JSON.stringify([1, 2, 3]));
+var parsed = JSON.parse("[4, 5]");
+console.log(parsed[1]);
+// expect:
+// 5 2
+// 7 7 1024
+// [1,2,3]
+// 5
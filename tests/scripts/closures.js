@@ -0,0 +1,18 @@
+// Closures capturing outer variables
+function outer() {
+    var x = 10;
+    function inner(y) {
+        return x + y;
+    }
+    return inner(5);
+}
+console.log(outer());
+function makeAdder(n) {
+    function add(m) { return n + m; }
+    return add;
+}
+var add3 = makeAdder(3);
+console.log(add3(4));
+// expect:
+// 15
+// 7
@@ -0,0 +1,15 @@
+// Closure state shared across calls
+function counter() {
+    var count = 0;
+    function next() {
+        count = count + 1;
+        return count;
+    }
+    return next;
+}
+var c = counter();
+c();
+c();
+console.log(c());
+// expect:
+// 3
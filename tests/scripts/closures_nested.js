@@ -0,0 +1,61 @@
+// Functions nested more than one level deep see outer arguments
+function outer(x) {
+    function middle() {
+        function inner() {
+            return x;
+        }
+        return inner;
+    }
+    return middle;
+}
+console.log(outer(5)()());
+
+function a(x) {
+    function b() {
+        function c() {
+            function d() {
+                return x * 2;
+            }
+            return d;
+        }
+        return c;
+    }
+    return b;
+}
+console.log(a(21)()()());
+
+// The innermost function keeps its own writes across calls
+function makeCounter(start) {
+    function middle() {
+        function next() {
+            start = start + 1;
+            return start;
+        }
+        return next;
+    }
+    return middle();
+}
+var next = makeCounter(10);
+next();
+next();
+console.log(next());
+
+// `this` reaches nested functions through a captured variable
+function Box(v) {
+    this.v = v;
+    var self = this;
+    function reader() {
+        function read() {
+            return self.v;
+        }
+        return read;
+    }
+    this.read = reader();
+    return this;
+}
+console.log(new Box(42).read());
+// expect:
+// 5
+// 42
+// 13
+// 42
@@ -0,0 +1,8 @@
+// Comparison and equality
+console.log(1 < 2, 2 <= 2, 3 > 4, 4 >= 5);
+console.log(1 === 1, 1 !== 2, null === null);
+console.log("a" === "a", "a" === "b");
+// expect:
+// true true false false
+// true true true
+// true false
@@ -0,0 +1,6 @@
+// The empty string is falsy
+if ("") { console.log("truthy"); } else { console.log("falsy"); }
+console.log(!"");
+// expect:
+// falsy
+// true
@@ -0,0 +1,25 @@
+// Arithmetic failures are thrown as catchable error objects
+try {
+    var q = 1 / 0;
+} catch (e) {
+    console.log(e.name + ": " + e.message);
+}
+try {
+    var d = "a" - 1;
+} catch (e) {
+    console.log(e.name + ": " + e.message);
+}
+function remainder(x) {
+    return x % 0;
+}
+try {
+    remainder(5);
+} catch (e) {
+    console.log("caught " + e.name);
+}
+console.log(6 * 7);
+// expect:
+// RangeError: division by zero
+// TypeError: cannot subtract non-numbers
+// caught RangeError
+// 42
@@ -0,0 +1,24 @@
+// try/catch/finally
+try {
+    throw 42;
+} catch (e) {
+    console.log("caught", e);
+}
+try {
+    console.log("no throw");
+} catch (e) {
+    console.log("unreachable");
+} finally {
+    console.log("finally");
+}
+function thrower() { throw "boom"; }
+try {
+    thrower();
+} catch (e) {
+    console.log(e);
+}
+// expect:
+// caught 42
+// no throw
+// finally
+// boom
@@ -0,0 +1,9 @@
+// Errors raised by natives can be caught
+try {
+    null.foo;
+} catch (e) {
+    console.log("caught");
+}
+// skip: property reads on null or undefined do not throw yet
+// expect:
+// caught
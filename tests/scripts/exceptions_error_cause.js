@@ -0,0 +1,33 @@
+// Errors carry the cause they were constructed with
+function parseConfig() {
+    throw new SyntaxError("bad config");
+}
+function init() {
+    try {
+        parseConfig();
+    } catch (e) {
+        throw new Error("init failed", { cause: e });
+    }
+}
+
+try {
+    init();
+} catch (e) {
+    console.log(e.message, e.cause.name, e.cause.message);
+    console.log(e.stack);
+}
+
+var wrapped = new TypeError("write failed", { cause: "disk full" });
+console.log(wrapped.cause, typeof wrapped.cause);
+
+var plain = new Error("plain");
+var called = RangeError("called", { cause: 0 });
+console.log(plain.cause, "cause" in plain, "cause" in called, called.cause);
+console.log(Object.keys(wrapped).length);
+// expect:
+// init failed SyntaxError bad config
+// Error: init failed
+//   caused by: SyntaxError: bad config
+// disk full string
+// undefined false true 0
+// 0
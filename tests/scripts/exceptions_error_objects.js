@@ -0,0 +1,15 @@
+// Error constructors
+try {
+    throw new TypeError("bad type");
+} catch (e) {
+    console.log(e.name);
+    console.log(e.message);
+    console.log(e);
+}
+var err = new Error("plain");
+console.log(err.message);
+// expect:
+// TypeError
+// bad type
+// TypeError: bad type
+// plain
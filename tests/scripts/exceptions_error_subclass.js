@@ -0,0 +1,50 @@
+// Error types defined with constructor functions and Error.call
+function AppError(msg) {
+    Error.call(this, msg);
+    this.name = "AppError";
+    this.message = msg;
+}
+AppError.prototype = Object.create(Error.prototype);
+AppError.prototype.constructor = AppError;
+
+function NotFound(what) {
+    AppError.call(this, what + " not found");
+    this.name = "NotFound";
+}
+NotFound.prototype = Object.create(AppError.prototype);
+
+function lookup(key) {
+    throw new NotFound(key);
+}
+
+try {
+    throw new AppError("boom");
+} catch (e) {
+    console.log(e instanceof AppError, e instanceof Error, e instanceof TypeError);
+    console.log(e.name, e.message, String(e));
+    console.log(e.stack);
+}
+
+try {
+    lookup("user");
+} catch (e) {
+    console.log(e instanceof NotFound, e instanceof AppError, e instanceof Error);
+    console.log(e.toString());
+}
+
+var bare = Object.create(Error.prototype);
+console.log(bare instanceof Error, bare.name, bare.message === "", String(bare));
+
+var target = {};
+Error.call(target, "plain");
+console.log(target.name, target.message);
+throw new AppError("uncaught");
+// expect-error: AppError: uncaught
+// expect:
+// true true false
+// AppError boom AppError: boom
+// Error: boom
+// true true true
+// NotFound: user not found
+// true Error true Error
+// Error plain
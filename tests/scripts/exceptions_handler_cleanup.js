@@ -0,0 +1,27 @@
+// Leaving a try block early must remove its handler
+function first(arr) {
+  for (var i = 0; i < arr.length; ++i) {
+    try { if (arr[i] > 0) { return arr[i]; } } catch (e) { return -1; }
+  }
+  return 0;
+}
+var total = 0;
+try {
+  for (var n = 0; n < 10; ++n) { total += first([0, 0, 3]); }
+  throw "caller";
+} catch (e) {
+  console.log(total, e);
+}
+var seen = [];
+try {
+  while (true) {
+    try { break; } catch (e) { seen.push("inner"); }
+  }
+  throw "after loop";
+} catch (e) {
+  seen.push(e);
+}
+console.log(seen);
+// expect:
+// 30 caller
+// [after loop]
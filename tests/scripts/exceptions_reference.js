@@ -0,0 +1,4 @@
+// Reading an undeclared variable is a ReferenceError
+console.log(missingVariable);
+// expect-error: ReferenceError
+// expect:
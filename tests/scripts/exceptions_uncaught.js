@@ -0,0 +1,7 @@
+// An uncaught TypeError aborts the script
+console.log("before");
+throw new TypeError("not caught");
+console.log("after");
+// expect-error: TypeError
+// expect:
+// before
@@ -0,0 +1,14 @@
+// Function declarations, arguments and recursion
+function add(a, b) { return a + b; }
+function fact(n) {
+    if (n <= 1) { return 1; }
+    return n * fact(n - 1);
+}
+function noReturn() {}
+console.log(add(2, 3));
+console.log(fact(6));
+console.log(noReturn());
+// expect:
+// 5
+// 720
+// undefined
@@ -0,0 +1,59 @@
+// Plain calls, method calls, new and calls made by natives all set up
+// frames the same way, for plain functions and closures alike
+function PlainPrimitive(v) {
+    this.v = v;
+    return 5;
+}
+function PlainObject(v) {
+    this.v = v;
+    return { replaced: v };
+}
+function makeClosures(tag) {
+    function ClosurePrimitive(v) {
+        this.v = tag + v;
+        return "ignored";
+    }
+    function ClosureObject(v) {
+        return { replaced: tag + v };
+    }
+    function method(x) {
+        return tag + this.name + x;
+    }
+    return [ClosurePrimitive, ClosureObject, method];
+}
+var made = makeClosures("c:");
+var ClosurePrimitive = made[0];
+var ClosureObject = made[1];
+
+console.log(new PlainPrimitive(1).v, new PlainObject(2).replaced, new PlainObject(2).v);
+console.log(new ClosurePrimitive(3).v, new ClosureObject(4).replaced);
+console.log(new PlainPrimitive(1) instanceof PlainPrimitive, new ClosurePrimitive(1) instanceof ClosurePrimitive);
+console.log(PlainPrimitive(1), PlainObject(2).replaced);
+
+function plainMethod(x) {
+    return this.name + x;
+}
+var obj = { name: "o", plain: plainMethod, closure: made[2] };
+console.log(obj.plain("!"), obj.closure("?"));
+console.log(plainMethod.call(obj, 1), made[2].call(obj, 2));
+
+var bound = plainMethod.bind({ name: "b" }, "+");
+var other = { name: "other", f: bound };
+console.log(bound(), other.f());
+
+var Bound = PlainPrimitive.bind(null, 7);
+var built = new Bound();
+console.log(built.v, built instanceof PlainPrimitive);
+
+var holder = { toStr: String };
+console.log(holder.toStr(12), holder.toStr(true));
+// expect:
+// 1 2 undefined
+// c:3 c:4
+// true true
+// 5 2
+// o! c:o?
+// o1 c:o2
+// b+ b+
+// 7 true
+// 12 true
@@ -0,0 +1,9 @@
+// Function expressions and arrows
+var double = function (x) { return x * 2; };
+var inc = (x) => x + 1;
+console.log(double(inc(1)));
+console.log(double.name, inc.name);
+// skip: arrow functions are not parsed
+// expect:
+// 4
+// double inc
@@ -0,0 +1,30 @@
+// Function name and length
+function add(a, b) {
+    return a + b;
+}
+function noop() {}
+
+console.log(add.name, add.length, noop.name, noop.length);
+
+var shapes = {
+    area(w, h) {
+        return w * h;
+    },
+    plus: add,
+};
+console.log(shapes.area.name, shapes.area.length, shapes.plus.name);
+
+var inc = add.bind(null, 1);
+var fixed = add.bind(null, 1, 2, 3);
+console.log(inc.name, inc.length, inc(4));
+console.log(fixed.name, fixed.length, fixed());
+
+console.log(Math.max.name, typeof add.name, typeof add.length);
+console.log(add.hasOwnProperty("name"), Object.keys(add).length);
+// expect:
+// add 2 noop 0
+// area 2 add
+// bound add 1 5
+// bound add 0 3
+// max string number
+// true 0
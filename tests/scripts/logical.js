@@ -0,0 +1,10 @@
+// Logical not and truthiness in conditions
+console.log(!true, !0, !1, !null);
+if (0) { console.log("bad"); } else { console.log("zero is falsy"); }
+if (null) { console.log("bad"); } else { console.log("null is falsy"); }
+if ("x") { console.log("string is truthy"); }
+// expect:
+// false true false true
+// zero is falsy
+// null is falsy
+// string is truthy
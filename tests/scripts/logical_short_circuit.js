@@ -0,0 +1,6 @@
+// && and || yield one of their operands
+console.log(true && false, true || false);
+console.log(0 || 5, 3 && 4, null || "x");
+// expect:
+// false true
+// 5 4 x
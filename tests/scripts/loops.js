@@ -0,0 +1,16 @@
+// while, for, break and continue
+var sum = 0;
+for (var i = 1; i <= 10; i = i + 1) {
+    if (i % 2 === 0) { continue; }
+    sum = sum + i;
+}
+console.log(sum);
+var n = 0;
+while (true) {
+    n = n + 1;
+    if (n >= 5) { break; }
+}
+console.log(n);
+// expect:
+// 25
+// 5
@@ -0,0 +1,29 @@
+// Declarations in loop heads: pre-declared targets, shared var, several declarators
+var k;
+var keys = [];
+for (k in {a: 1, b: 2}) { keys.push(k); }
+for (k in {c: 3, d: 4}) { keys.push(k); }
+console.log(keys.join(","), k);
+
+function repeated() {
+    var seen = [];
+    for (var k in {x: 1}) { seen.push(k); }
+    for (var k in {y: 2}) { seen.push(k); }
+    return seen.join(",") + " " + k;
+}
+console.log(repeated());
+
+var arr = [10, 20, 30, 40];
+var total = 0;
+for (var i = 0, n = arr.length; i < n; i++) {
+    total = total + arr[i];
+}
+console.log(total, i, n);
+
+var a = 1, b = a + 1, c;
+console.log(a, b, c);
+// expect:
+// a,b,c,d d
+// x,y y
+// 100 4 4
+// 1 2 undefined
@@ -0,0 +1,9 @@
+// do-while loops
+var i = 0;
+do {
+    i = i + 1;
+} while (i < 3);
+console.log(i);
+// skip: do-while is not parsed
+// expect:
+// 3
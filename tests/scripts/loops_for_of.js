@@ -0,0 +1,14 @@
+// for-in and for-of over arrays
+var total = 0;
+for (var v of [1, 2, 3]) {
+    total = total + v;
+}
+console.log(total);
+var keys = "";
+for (var k in [7, 8]) {
+    keys = keys + k;
+}
+console.log(keys);
+// expect:
+// 6
+// 01
@@ -0,0 +1,48 @@
+// Loops left early hold plain values, never the loop's internal iterator
+function firstOver(list, limit) {
+    var found;
+    for (var v of list) {
+        found = v;
+        if (v > limit) {
+            break;
+        }
+    }
+    var after;
+    return typeof found + " " + found + " " + typeof after;
+}
+console.log(firstOver([1, 5, 9], 3));
+
+function keysUntil(list) {
+    var seen = "";
+    for (var k in list) {
+        seen = seen + k;
+        break;
+    }
+    for (var w of list) {
+        continue;
+    }
+    var later;
+    var other;
+    return seen + " " + typeof later + " " + typeof other;
+}
+console.log(keysUntil([7, 8]));
+
+function inner() {
+    var out = [];
+    for (var a of [1, 2]) {
+        for (var b of [3, 4]) {
+            out.push(a * b);
+            break;
+        }
+    }
+    var x;
+    var y;
+    out.push(x);
+    out.push(y);
+    return out;
+}
+console.log(inner());
+// expect:
+// number 5 undefined
+// 0 undefined undefined
+// [3, 6, undefined, undefined]
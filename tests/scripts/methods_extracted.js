@@ -0,0 +1,59 @@
+// Prototype methods extracted from their receiver: called bare they throw a
+// TypeError naming the receiver; .call with a real receiver works
+var arr = [1, 2];
+var push = arr.push;
+var slice = arr.slice;
+var charAt = "abc".charAt;
+var num = 5;
+var toFixed = num.toFixed;
+var re = new RegExp("b");
+var test = re.test;
+
+function attempt(label, f, receiver) {
+    try {
+        console.log(label, f.call(receiver, 1));
+    } catch (e) {
+        console.log(label, e.name + ": " + e.message);
+    }
+}
+
+attempt("push", push, undefined);
+attempt("push", push, 7);
+attempt("push", push, arr);
+attempt("slice", slice, undefined);
+attempt("slice", slice, "xy");
+attempt("slice", slice, arr);
+attempt("charAt", charAt, undefined);
+attempt("charAt", charAt, 42);
+attempt("charAt", charAt, "xyz");
+attempt("toFixed", toFixed, undefined);
+attempt("toFixed", toFixed, "3");
+attempt("toFixed", toFixed, 3);
+attempt("test", test, undefined);
+attempt("test", test, arr);
+attempt("test", test, new RegExp("1"));
+
+try {
+    push(9);
+} catch (e) {
+    console.log(e.message);
+}
+console.log(arr);
+// expect:
+// push TypeError: Array.prototype.push called on undefined, expected an object
+// push TypeError: Array.prototype.push called on number, expected an object
+// push 3
+// slice TypeError: Array.prototype.slice called on undefined, expected a value other than null or undefined
+// slice [y]
+// slice [2, 1]
+// charAt TypeError: String.prototype.charAt called on undefined, expected a value other than null or undefined
+// charAt 2
+// charAt y
+// toFixed TypeError: Number.prototype.toFixed called on undefined, expected a number
+// toFixed TypeError: Number.prototype.toFixed called on string, expected a number
+// toFixed 3.0
+// test TypeError: RegExp.prototype.test called on undefined, expected a RegExp
+// test TypeError: RegExp.prototype.test called on array, expected a RegExp
+// test true
+// Array.prototype.push called on undefined, expected an object
+// [1, 2, 1]
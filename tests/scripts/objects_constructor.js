@@ -0,0 +1,17 @@
+// Objects built by constructors
+function Point(x, y) {
+    this.x = x;
+    this.y = y;
+    return this;
+}
+var p = new Point(3, 4);
+console.log(p.x, p.y);
+p.x = 10;
+console.log(p.x + p.y);
+console.log(p instanceof Point);
+console.log("x" in p, "z" in p);
+// expect:
+// 3 4
+// 14
+// true
+// true false
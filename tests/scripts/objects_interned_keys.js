@@ -0,0 +1,54 @@
+// Property keys with the same text name the same property wherever the
+// text came from: literals, computed expressions, runtime concatenation,
+// JSON and defineProperty
+var part = "wid";
+var o = { width: 1 };
+o[part + "th"] = 2;
+console.log(o.width, o["width"], Object.keys(o).length);
+
+var p = {};
+p["he" + "ight"] = 3;
+console.log(p.height, "height" in p, p.hasOwnProperty("hei" + "ght"));
+
+var j = JSON.parse('{"depth": 4}');
+j.depth = j["dep" + "th"] + 1;
+console.log(j.depth, Object.keys(j));
+
+var d = {};
+Object.defineProperty(d, "si" + "ze", { value: 6, enumerable: true });
+console.log(d.size, d["size"], Object.keys(d).length);
+
+// Deleting through one spelling removes what another created
+delete o["wi" + "dth"];
+console.log(o.width, "width" in o, Object.keys(o).length);
+
+// Keys no script has used yet are simply absent
+var fresh = "never" + "-used-" + 1;
+console.log(o[fresh], fresh in o, delete o[fresh]);
+
+// Enumeration hands back the original text, in insertion order
+var e = {};
+e["a" + 1] = 1;
+e.b2 = 2;
+e["c" + "3"] = 3;
+var seen = "";
+for (var k in e) {
+    seen = seen + k + ",";
+}
+console.log(seen, Object.keys(e), JSON.stringify(e));
+
+// Named properties of arrays and errors share the same keys
+var arr = [1, 2];
+arr["ta" + "g"] = "t";
+var err = new Error("m");
+err["co" + "de"] = 42;
+console.log(arr.tag, err.code);
+// expect:
+// 2 2 1
+// 3 true true
+// 5 [depth]
+// 6 6 1
+// undefined false 0
+// undefined false true
+// a1,b2,c3, [a1, b2, c3] {"a1":1,"b2":2,"c3":3}
+// t 42
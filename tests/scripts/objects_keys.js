@@ -0,0 +1,20 @@
+// Enumerating object properties
+function Pair() {
+    this.a = 1;
+    this.b = 2;
+    return this;
+}
+var o = new Pair();
+console.log(Object.keys(o));
+console.log(Object.values(o));
+for (var k in o) {
+    console.log(k);
+}
+delete o.a;
+console.log(Object.keys(o));
+// expect:
+// [a, b]
+// [1, 2]
+// a
+// b
+// [b]
@@ -0,0 +1,5 @@
+// Object literal syntax
+var o = { a: 1, b: 2 };
+console.log(o.a + o.b);
+// expect:
+// 3
@@ -0,0 +1,58 @@
+// Object literal keys: identifiers, strings, numbers and computed keys all
+// land on the same strings later lookups use
+function Tag(name) {
+    this.name = name;
+}
+function tagName() {
+    return this.name;
+}
+function explode() {
+    throw new Error("no key");
+}
+
+var prefix = "dyn";
+var t = new Tag("tagged");
+t.toString = tagName;
+
+var o = {
+    plain: 1,
+    "my-key": 2,
+    "": 3,
+    42: "x",
+    [prefix + "amic"]: 4,
+    [7]: "seven",
+    [t]: 5,
+    [null]: 6,
+};
+console.log(o.plain, o["plain"], o["pl" + "ain"]);
+console.log(o["my-key"], o["my" + "-key"], o[""]);
+console.log(o[42], o["42"], o[40 + 2]);
+console.log(o.dynamic, o["dynamic"], o[prefix + "amic"]);
+console.log(o[7], o["7"], o.tagged, o["null"]);
+console.log(Object.keys(o).length);
+
+// Later duplicates overwrite in place
+var d = { x: 1, y: 2, x: 3 };
+console.log(Object.keys(d), d.x);
+
+// Nested literals and an empty one
+var n = { inner: { v: 5 }, list: [1, 2], empty: {} };
+console.log(n.inner.v, n.list[1], Object.keys(n.empty).length);
+
+var u = new Tag("u");
+u.toString = explode;
+try {
+    var bad = { [u]: 1 };
+} catch (e) {
+    console.log("caught", e.message);
+}
+// expect:
+// 1 1 1
+// 2 2 3
+// x x x
+// 4 4 4
+// seven seven 5 6
+// 8
+// [x, y] 3
+// 5 2 0
+// caught no key
@@ -0,0 +1,46 @@
+// Shorthand properties and methods in object literals
+var x = 1;
+var label = "pt";
+
+function make(y) {
+    var z = 3;
+    return {
+        x,
+        y,
+        z,
+        label,
+        sum() {
+            return this.x + this.y + this.z;
+        },
+    };
+}
+
+var p = make(2);
+console.log(p.x, p.y, p.z, p.label, p.sum());
+p.x = 10;
+console.log(p.sum());
+
+var m = {
+    n: 5,
+    fact(n) {
+        if (n <= 1) {
+            return 1;
+        }
+        return n * fact(n - 1);
+    },
+    "quoted name"() {
+        return "q";
+    },
+    [label + "Fn"]() {
+        return this.n;
+    },
+    get: 1,
+    set: 2,
+};
+console.log(m.fact(5), m["quoted name"](), m.ptFn(), m.get, m.set);
+console.log(typeof m.fact, Object.keys(m).length);
+// expect:
+// 1 2 3 pt 6
+// 15
+// 120 q 5 1 2
+// function 6
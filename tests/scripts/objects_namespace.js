@@ -0,0 +1,17 @@
+// Functions stored in nested object literals (namespacing pattern)
+var app = {
+    utils: {
+        unit: "px",
+        clamp: function (v, lo, hi) {
+            if (v < lo) { return lo; }
+            if (v > hi) { return hi; }
+            return v;
+        },
+        describe: function (v) { return this.clamp(v, 0, 3) + this.unit; }
+    }
+};
+console.log(app.utils.clamp(5, 0, 3));
+console.log(app.utils.describe(5));
+// expect:
+// 3
+// 3px
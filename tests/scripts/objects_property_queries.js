@@ -0,0 +1,41 @@
+// Own enumerable, own non-enumerable and inherited properties
+function Thing() {
+    this.visible = 1;
+    return this;
+}
+function Hidden() {
+    this.value = 2;
+    return this;
+}
+var o = new Thing();
+Object.defineProperty(o, "hidden", new Hidden());
+
+var names = ["visible", "hidden", "toString"];
+for (var i = 0; i < names.length; i = i + 1) {
+    var name = names[i];
+    console.log(
+        name,
+        name in o,
+        o.hasOwnProperty(name),
+        Object.keys(o).indexOf(name) >= 0,
+        Object.getOwnPropertyNames(o).indexOf(name) >= 0
+    );
+}
+console.log(o.hidden);
+for (var k in o) {
+    console.log(k);
+}
+
+var arr = [1, 2];
+console.log("push" in arr, arr.hasOwnProperty("length"));
+console.log(Object.keys(arr));
+console.log(Object.getOwnPropertyNames(arr));
+// expect:
+// visible true true true true
+// hidden true true false true
+// toString true false false false
+// 2
+// visible
+// true true
+// [0, 1]
+// [0, 1, length]
@@ -0,0 +1,67 @@
+// get, set, in, delete and key enumeration on every kind of receiver.
+// Captured before property operations were unified, and identical since
+// except for these deliberate fixes:
+// - computed member access works on every receiver instead of throwing
+//   "cannot read property of non-array" for anything but arrays
+// - errors, regexps and typed arrays keep named properties written to them
+// - typed array indices and string indices are own, enumerable properties
+// - `in` throws a TypeError for a primitive right-hand side
+// - functions see Function.prototype methods through plain member reads
+// - functions report their declared parameter count as length
+// - integer keys enumerate before the other keys
+function Plain() {
+    this.a = 1;
+    return this;
+}
+function attempt(label, op, t) {
+    try {
+        return op(t);
+    } catch (e) {
+        return label + " threw " + e.name;
+    }
+}
+function reads(t) {
+    return [t.a, t[0], t.length, typeof t.toString].join("|");
+}
+function writes(t) {
+    t.extra = 7;
+    t[1] = 9;
+    return [t.extra, t[1]].join("|");
+}
+function has(t) {
+    return ["extra" in t, 0 in t].join("|");
+}
+function deletes(t) {
+    return [delete t.extra, t.extra].join("|");
+}
+function keys(t) {
+    var seen = "";
+    for (var k in t) {
+        seen = seen + k + ",";
+    }
+    return Object.keys(t).join(",") + "|" + seen;
+}
+var targets = [new Plain(), [1, 2], new Error("boom"), new RegExp("x+", "g"), new Uint8Array(2), "str", 5, Plain, Math];
+var labels = ["object", "array", "error", "regexp", "typed", "string", "number", "function", "builtin"];
+for (var i = 0; i < targets.length; i = i + 1) {
+    var t = targets[i];
+    var label = labels[i];
+    console.log(
+        label,
+        attempt("get", reads, t),
+        attempt("set", writes, t),
+        attempt("in", has, t),
+        attempt("delete", deletes, t),
+        attempt("keys", keys, t)
+    );
+}
+// expect:
+// object 1|||function 7|9 true|false true| 1,a|1,a,
+// array |1|2|function 7|9 true|true true| 0,1|0,1,
+// error |||function 7|9 true|false true| 1|1,
+// regexp |||undefined 7|9 true|false true| 1|1,
+// typed |0|2|undefined 7|9 true|true true| 0,1|0,1,
+// string |s|3|undefined |t in threw TypeError true| 0,1,2|0,1,2,
+// number |||function | in threw TypeError true| |
+// function ||0|function | false|false true| |
+// builtin |||undefined | false|false true| |
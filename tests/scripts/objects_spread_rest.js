@@ -0,0 +1,39 @@
+// Object spread in literals and object rest in declarations: spreads copy
+// own enumerable properties in order, later keys override earlier ones,
+// and rest collects whatever the pattern did not name
+var defaults = { host: "localhost", port: 80, debug: false };
+var overrides = { port: 8080, debug: true };
+var merged = { ...defaults, ...overrides, extra: 1 };
+console.log(JSON.stringify(merged));
+console.log(JSON.stringify({ port: 1, ...overrides, debug: "last" }));
+
+var { host, port: p, ...rest } = merged;
+console.log(host, p, JSON.stringify(rest));
+function pick() {
+    let { x, ...others } = { x: 1, y: 2, z: 3 };
+    return [x, Object.keys(others).join(",")].join("|");
+}
+console.log(pick());
+
+// The rest object is a fresh copy
+const { ...copy } = defaults;
+copy.host = "changed";
+console.log(defaults.host, copy.host);
+
+// Spreading null or undefined adds nothing; arrays and strings spread indices
+console.log(JSON.stringify({ ...undefined, ...null, a: 1 }));
+console.log(JSON.stringify({ ...[10, 20] }), JSON.stringify({ ..."hi" }));
+
+// Object.assign shares the copy and returns its target
+var target = { a: 1 };
+var same = Object.assign(target, { b: 2 }, null, { a: 3 });
+console.log(same === target, JSON.stringify(target));
+// expect:
+// {"host":"localhost","port":8080,"debug":true,"extra":1}
+// {"port":8080,"debug":"last"}
+// localhost 8080 {"debug":true,"extra":1}
+// 1|y,z
+// localhost changed
+// {"a":1}
+// {"0":10,"1":20} {"0":"h","1":"i"}
+// true {"a":3,"b":2}
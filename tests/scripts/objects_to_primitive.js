@@ -0,0 +1,53 @@
+// Conversion hooks on plain objects: arithmetic and comparison prefer
+// valueOf, string conversion prefers toString, + prefers valueOf, and
+// JSON.stringify serializes what toJSON returns
+function money(cents) {
+    return {
+        cents: cents,
+        valueOf() { return this.cents; },
+        toString() { return "$" + this.cents; }
+    };
+}
+var a = money(150);
+var b = money(275);
+console.log(a + b, b - a, a * 2, -a, Number(b));
+console.log(a < b, b <= a, a > 100, 300 >= b);
+console.log(String(a), [a, b].join(" "), "sum " + a);
+
+// Only one hook is enough
+var counter = { valueOf() { return 3; } };
+console.log(counter + 1, "" + counter, counter | 4);
+var label = { toString() { return "L"; } };
+console.log(label + "!", String(label));
+
+var user = {
+    name: "ann",
+    password: "hunter2",
+    toJSON() { return { name: this.name, password: "***" }; }
+};
+console.log(JSON.stringify({ user: user, count: 1 }));
+
+// Hooks must produce a primitive; falling back to the other is fine
+var fallback = { toString() { return {}; }, valueOf() { return 7; } };
+console.log(String(fallback));
+var bad = { toString() { return {}; } };
+try {
+    console.log("" + bad);
+} catch (e) {
+    console.log(e.name + ": " + e.message);
+}
+try {
+    String(bad);
+} catch (e) {
+    console.log(e.name + ": " + e.message);
+}
+// expect:
+// 425 125 300 -150 275
+// true false true true
+// $150 $150 $275 sum 150
+// 4 3 7
+// L! L
+// {"user":{"name":"ann","password":"***"},"count":1}
+// 7
+// TypeError: Cannot convert object to primitive value
+// TypeError: Cannot convert object to primitive value
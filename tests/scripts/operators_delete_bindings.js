@@ -0,0 +1,56 @@
+// delete on variables and builtin properties: locals, captures and
+// declared globals stay; globals created by assignment go; builtins are
+// never removed and keep working
+var declared = 1;
+function local() {
+    var v = 2;
+    return [delete v, v].join("|");
+}
+function capture() {
+    var c = 3;
+    function inner() {
+        return [delete c, c].join("|");
+    }
+    return inner();
+}
+function assign() {
+    assigned = 4;
+}
+console.log(delete declared, declared, local(), capture());
+
+assign();
+console.log(assigned, delete assigned, delete assigned);
+try {
+    assigned;
+} catch (e) {
+    console.log(e.name);
+}
+console.log(delete neverDefined);
+
+// Builtins are non-configurable: deletes are false and change nothing
+console.log(delete Math.floor, Math.floor(7 / 2));
+console.log(delete Math, Math.max(1, 2));
+console.log(delete parseInt, parseInt(12));
+console.log(delete JSON.stringify, JSON.stringify([1]));
+console.log(Math.floor(9 / 2));
+
+// Only the last member of a chain is deleted
+var nested = { a: { b: { c: 1, d: 2 } }, list: [{ x: 1 }] };
+console.log(delete nested.a.b.c, delete nested["a"].b["d"], delete nested.list[0].x);
+console.log(JSON.stringify(nested), delete Array.prototype.push, [1].push(2));
+
+// Deleting something that is not a reference is true
+console.log(delete (1 + 2), delete ("a" + "b"));
+// expect:
+// false 1 false|2 false|3
+// 4 true true
+// ReferenceError
+// true
+// false 3
+// false 2
+// false 12
+// false [1]
+// 4
+// true true true
+// {"a":{"b":{}},"list":[{}]} false 2
+// true true
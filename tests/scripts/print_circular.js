@@ -0,0 +1,39 @@
+// Self-referencing structures print and convert without recursing forever
+var list = [1, 2];
+list.push(list);
+console.log(list);
+var nested = [list, [list]];
+console.log(nested);
+console.log(String(list));
+
+function Node() {
+    this.name = "root";
+    return this;
+}
+var node = new Node();
+node.self = node;
+console.log(node);
+console.log(node.self.self.name);
+
+try {
+    JSON.stringify(node);
+} catch (e) {
+    console.log(e.name + ": " + e.message);
+}
+try {
+    JSON.stringify(list);
+} catch (e) {
+    console.log(e.name);
+}
+// Shared but acyclic references are not circular
+var shared = [0];
+console.log(JSON.stringify([shared, shared]));
+// expect:
+// [1, 2, [Circular]]
+// [[1, 2, [Circular]], [[1, 2, [Circular]]]]
+// 1,2,
+// [object Object]
+// root
+// TypeError: Converting circular structure to JSON
+// TypeError
+// [[0],[0]]
@@ -0,0 +1,6 @@
+// The print statement shares the console sink
+print(1 + 1);
+print("text");
+// expect:
+// 2
+// text
@@ -0,0 +1,50 @@
+// Generic prototype methods coerce their receiver where the spec does:
+// String methods ToString it, Array read methods take strings and
+// array-likes, and methods that write to the receiver still reject strings
+function Pair(a, b) {
+    this[0] = a;
+    this[1] = b;
+    this.length = 2;
+}
+
+function attempt(label, f, receiver, arg) {
+    try {
+        console.log(label, f.call(receiver, arg));
+    } catch (e) {
+        console.log(label, e.name + ": " + e.message);
+    }
+}
+
+function double(x) {
+    return x + x;
+}
+
+attempt("toUpperCase", String.prototype.toUpperCase, 42);
+attempt("toUpperCase", String.prototype.toUpperCase, true);
+attempt("toUpperCase", String.prototype.toUpperCase, [1, "b"]);
+attempt("toUpperCase", String.prototype.toUpperCase, null);
+attempt("repeat", String.prototype.repeat, 7, 3);
+attempt("split", String.prototype.split, 1234, "3");
+attempt("join", Array.prototype.join, "abc", "-");
+attempt("join", Array.prototype.join, 42, "-");
+attempt("slice", Array.prototype.slice, new Pair("a", "b"), 0);
+attempt("slice", Array.prototype.slice, "hey", 1);
+attempt("map", Array.prototype.map, "ab", double);
+attempt("indexOf", Array.prototype.indexOf, "abc", "c");
+attempt("push", Array.prototype.push, "abc", "d");
+attempt("reverse", Array.prototype.reverse, "abc");
+// expect:
+// toUpperCase 42
+// toUpperCase TRUE
+// toUpperCase 1,B
+// toUpperCase TypeError: String.prototype.toUpperCase called on null, expected a value other than null or undefined
+// repeat 777
+// split [12, 4]
+// join a-b-c
+// join TypeError: Array.prototype.join called on number
+// slice [a, b]
+// slice [e, y]
+// map [aa, bb]
+// indexOf 2
+// push TypeError: Array.prototype.push called on string, expected an object
+// reverse TypeError: Array.prototype.reverse called on string, expected an array
@@ -0,0 +1,16 @@
+// String concatenation
+var a = "foo";
+var b = "bar";
+console.log(a + b);
+console.log(a + 1 + 2);
+console.log("n=" + null + ", b=" + true);
+var s = "";
+for (var i = 0; i < 3; i = i + 1) {
+    s = s + i;
+}
+console.log(s);
+// expect:
+// foobar
+// foo12
+// n=null, b=true
+// 012
@@ -0,0 +1,13 @@
+// String escapes and line continuations
+var joined = 'one \
+two';
+console.log(joined === 'one two', joined.length);
+var codes = '\0\v\b\f\n\r\t';
+var out = [];
+for (var i = 0; i < codes.length; i = i + 1) { out.push(codes.charCodeAt(i)); }
+console.log(out.join(" "));
+console.log('a\'b'.length, "\\".charCodeAt(0));
+// expect:
+// true 7
+// 0 11 8 12 10 13 9
+// 3 92
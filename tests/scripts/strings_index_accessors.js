@@ -0,0 +1,13 @@
+// charAt, charCodeAt, codePointAt and at index UTF-16 code units
+var s = "a" + String.fromCodePoint(128512) + "b";
+console.log(s.charAt(0), s.charAt(3), "[" + s.charAt(-1) + "]", "[" + s.charAt(4) + "]");
+console.log(s.charCodeAt(1), s.charCodeAt(2), s.charCodeAt(-1), s.charCodeAt(4));
+console.log(s.codePointAt(1), s.codePointAt(2), s.codePointAt(-1), s.codePointAt(4));
+console.log(s.at(0), s.at(-1), s.at(-4), s.at(-5), s.at(4));
+console.log("abc".charAt("1"), "abc".at(true));
+// expect:
+// a b [] []
+// 55357 56832 0 0
+// 128512 56832 undefined undefined
+// a b a undefined undefined
+// b b
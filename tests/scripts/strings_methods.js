@@ -0,0 +1,19 @@
+// Common String.prototype methods
+var s = "Hello, World";
+console.log(s.length);
+console.log(s.toUpperCase());
+console.log(s.toLowerCase());
+console.log(s.indexOf("World"));
+console.log(s.slice(7));
+console.log(s.charAt(4));
+console.log("  pad ".trim() + "|");
+console.log("ab".repeat(3));
+// expect:
+// 12
+// HELLO, WORLD
+// hello, world
+// 7
+// World
+// o
+// pad|
+// ababab
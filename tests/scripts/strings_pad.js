@@ -0,0 +1,20 @@
+// padStart/padEnd repeat and truncate the pad string to the exact width
+console.log("[" + "abc".padStart(8) + "]");
+console.log("[" + "abc".padEnd(8) + "]");
+console.log((7).toFixed(2).padStart(8, "0"));
+console.log("42".padStart(8, "ab"));
+console.log("42".padEnd(7, "xyz"));
+console.log("already long".padStart(4, "*"));
+console.log("abc".padEnd(6, ""));
+console.log("é".padStart(5, "-"));
+console.log("é".padStart(5, "-").length);
+// expect:
+// [     abc]
+// [abc     ]
+// 00007.00
+// ababab42
+// 42xyzxy
+// already long
+// abc
+// ----é
+// 5
@@ -0,0 +1,34 @@
+// Strings produced by split, match and Object.keys feed further natives
+function trimPart(p) {
+    return p.trim();
+}
+function shout(p) {
+    return p.toUpperCase() + "!";
+}
+var csv = " alpha ,  beta,gamma  , delta ";
+console.log(csv.split(",").map(trimPart).join(";"));
+console.log(csv.split(",").map(trimPart).map(shout).join(" "));
+
+function Config() {
+    this.host = "example.org";
+    this.path = "/index.html";
+    this.scheme = "https";
+    return this;
+}
+var o = new Config();
+function lookup(k) {
+    return o[k];
+}
+console.log(Object.keys(o).map(lookup).join(" | "));
+console.log(Object.keys(o).map(shout).join(" "));
+
+var runs = "ab1234cd56789ef".match(new RegExp("[0-9]+", "g"));
+console.log(runs.map(trimPart).map(shout).join(","));
+console.log(runs[1].split("").reverse().join("").padStart(8, "-"));
+// expect:
+// alpha;beta;gamma;delta
+// ALPHA! BETA! GAMMA! DELTA!
+// example.org | /index.html | https
+// HOST! PATH! SCHEME!
+// 1234!,56789!
+// ---98765
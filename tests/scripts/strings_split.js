@@ -0,0 +1,13 @@
+// Splitting and searching strings
+var parts = "a,b,c".split(",");
+console.log(parts.length);
+console.log(parts[1]);
+console.log("hello".startsWith("he"), "hello".endsWith("lo"));
+console.log("hello".includes("ell"));
+console.log("5".padStart(3, "0"));
+// expect:
+// 3
+// b
+// true true
+// true
+// 005
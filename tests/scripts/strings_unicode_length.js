@@ -0,0 +1,4 @@
+// String length counts UTF-16 code units
+console.log("héllo".length);
+// expect:
+// 5
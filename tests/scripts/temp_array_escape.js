@@ -0,0 +1,42 @@
+// Arrays returned by natives survive every way of escaping their statement
+function Pair() {
+    this.x = 1;
+    this.y = 2;
+    return this;
+}
+var o = new Pair();
+
+var local = Object.keys(o);
+var holder = new Pair();
+holder.keys = Object.keys(o);
+var list = [Object.keys(o)];
+list[1] = "a,b".split(",");
+function capture() {
+    var inner = Object.values(o);
+    function get() { return inner; }
+    return get;
+}
+var getter = capture();
+function make() { return Object.keys(o); }
+var returned = make();
+function keysOf(v) { return Object.keys(o); }
+var mapped = [1, 2].map(keysOf);
+
+var total = 0;
+for (var i = 0; i < 10; i = i + 1) { total = total + Object.keys(o).length; }
+
+console.log(local);
+console.log(holder.keys);
+console.log(list);
+console.log(getter());
+console.log(returned);
+console.log(mapped);
+console.log(total);
+// expect:
+// [x, y]
+// [x, y]
+// [[x, y], [a, b]]
+// [1, 2]
+// [x, y]
+// [[x, y], [x, y]]
+// 20
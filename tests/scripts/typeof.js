@@ -0,0 +1,9 @@
+// typeof results
+function f() {}
+console.log(typeof 1, typeof "s", typeof true);
+console.log(typeof undefined, typeof null);
+console.log(typeof f, typeof [1]);
+// expect:
+// number string boolean
+// undefined object
+// function object